@@ -0,0 +1,223 @@
+// asyncio support without an async runtime: `await source.next_event()` for
+// a FeedClient or EventQueue, and `await gateway.send(order)` for an
+// OrderGateway. next_event() returns the next FeedEvent:
+//   NextEvent.__await__ pops an event if one is queued; otherwise it creates
+//   a future on the running loop, registers it in the source's Waiters and
+//   yields it to the task (after popping once more, so an event pushed in
+//   between is not missed)
+//   the producer thread, after queueing an event, resolves every registered
+//   future through loop.call_soon_threadsafe, so the loop wakes only when
+//   there is something to take and no thread polls on its behalf
+// A woken task that finds nothing (another consumer won the event) simply
+// waits again. Cancelling the await cancels the future; it is discarded at
+// the next wake.
+// One-shot awaitables (OrderGateway.send) use the same loop.call_soon_threadsafe
+// path: spawn() runs the blocking work on a worker thread, then schedules a
+// Completion on the loop that hands the result to `finish` on the loop thread
+// and settles the future.
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use pyo3::exceptions::PyStopIteration;
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+
+use crate::feed::FeedEvent;
+
+// Something `next_event()` can take events from
+pub(crate) trait EventSource: Send + Sync {
+    // The next queued event, without waiting
+    fn try_next(&self) -> PyResult<Option<FeedEvent>>;
+    fn waiters(&self) -> &Waiters;
+}
+
+// Futures of tasks waiting on a source
+#[derive(Default)]
+pub(crate) struct Waiters {
+    // futures.len(), read by the producer without the lock
+    pending: AtomicUsize,
+    futures: Mutex<Vec<Py<PyAny>>>,
+}
+
+impl Waiters {
+    fn register(&self, future: Py<PyAny>) {
+        let mut futures = self.futures.lock().unwrap_or_else(PoisonError::into_inner);
+        futures.push(future);
+        self.pending.store(futures.len(), Ordering::SeqCst);
+        drop(futures);
+        // Orders the registration before the consumer's second look at the
+        // queue; pairs with wake
+        fence(Ordering::SeqCst);
+    }
+
+    // Called by the producer after queueing an event. Takes the GIL only
+    // when someone is waiting.
+    pub(crate) fn wake(&self) {
+        fence(Ordering::SeqCst);
+        if self.pending.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        Python::with_gil(|py| {
+            let futures = {
+                let mut futures = self.futures.lock().unwrap_or_else(PoisonError::into_inner);
+                self.pending.store(0, Ordering::SeqCst);
+                std::mem::take(&mut *futures)
+            };
+            for future in futures {
+                // A closed loop has nobody left to wake
+                let _ = resolve_soon(py, future.bind(py));
+            }
+        });
+    }
+}
+
+// Resolves a future unless it is already done (e.g. cancelled)
+#[pyfunction]
+fn wake_future(future: &Bound<'_, PyAny>) -> PyResult<()> {
+    if !future.call_method0("done")?.is_truthy()? {
+        future.call_method1("set_result", (future.py().None(),))?;
+    }
+    Ok(())
+}
+
+fn resolve_soon(py: Python<'_>, future: &Bound<'_, PyAny>) -> PyResult<()> {
+    static WAKE: GILOnceCell<Py<PyAny>> = GILOnceCell::new();
+    let wake = WAKE.get_or_try_init(py, || {
+        wrap_pyfunction!(wake_future, py).map(|f| f.into_any().unbind())
+    })?;
+    future
+        .call_method0("get_loop")?
+        .call_method1("call_soon_threadsafe", (wake, future))?;
+    Ok(())
+}
+
+// The caller's running loop; RuntimeError outside a coroutine
+pub(crate) fn running_loop(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    py.import("asyncio")?.call_method0("get_running_loop")
+}
+
+type Finish = Box<dyn FnOnce(Python<'_>) -> PyResult<Py<PyAny>> + Send>;
+
+// Run `work` on a named worker thread and return a future on `event_loop`
+// that `finish(work's result)` settles, on the loop thread. The Completion
+// joins the worker first, so no thread of ours outlives the await (one still
+// leaving the GIL while the interpreter shuts down aborts the process).
+pub(crate) fn spawn<'py, T, W, F>(
+    event_loop: &Bound<'py, PyAny>,
+    name: &str,
+    work: W,
+    finish: F,
+) -> PyResult<Bound<'py, PyAny>>
+where
+    T: Send + 'static,
+    W: FnOnce() -> T + Send + 'static,
+    F: FnOnce(Python<'_>, T) -> PyResult<Py<PyAny>> + Send + 'static,
+{
+    let future = event_loop.call_method0("create_future")?;
+    let (target, event_loop) = (future.clone().unbind(), event_loop.clone().unbind());
+    let worker = Arc::new(Mutex::new(None));
+    let joined = worker.clone();
+    let handle = thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            let result = work();
+            Python::with_gil(|py| {
+                let done = Completion {
+                    future: target,
+                    finish: Mutex::new(Some(Box::new(move |py| finish(py, result)))),
+                    worker: joined,
+                };
+                // A closed loop has nobody left to tell
+                let _ = Py::new(py, done).and_then(|done| {
+                    event_loop
+                        .bind(py)
+                        .call_method1("call_soon_threadsafe", (done,))
+                });
+            });
+        })?;
+    // Stored before the GIL is released, so before the Completion can run
+    *worker.lock().unwrap_or_else(PoisonError::into_inner) = Some(handle);
+    Ok(future)
+}
+
+// Scheduled on the loop by spawn()
+#[pyclass(frozen)]
+struct Completion {
+    future: Py<PyAny>,
+    finish: Mutex<Option<Finish>>,
+    worker: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+#[pymethods]
+impl Completion {
+    // Runs even when the await was cancelled, so `finish` always sees the
+    // outcome; only the future is left alone then
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let finish = self
+            .finish
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let Some(finish) = finish else {
+            return Ok(());
+        };
+        let worker = self
+            .worker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(worker) = worker {
+            // Only the GIL release is left for it to do
+            let _ = py.allow_threads(|| worker.join());
+        }
+        let outcome = finish(py);
+        let future = self.future.bind(py);
+        if future.call_method0("done")?.is_truthy()? {
+            return Ok(());
+        }
+        match outcome {
+            Ok(value) => future.call_method1("set_result", (value,))?,
+            Err(err) => future.call_method1("set_exception", (err.value(py),))?,
+        };
+        Ok(())
+    }
+}
+
+// The awaitable returned by next_event()
+#[pyclass(frozen)]
+pub struct NextEvent {
+    source: Arc<dyn EventSource>,
+}
+
+impl NextEvent {
+    pub(crate) fn new(source: Arc<dyn EventSource>) -> Self {
+        Self { source }
+    }
+}
+
+#[pymethods]
+impl NextEvent {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    // Finishes (StopIteration carrying the event) or yields a future for
+    // the task to wait on
+    fn __next__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        if let Some(event) = self.source.try_next()? {
+            return Err(PyStopIteration::new_err((event,)));
+        }
+        let future = running_loop(py)?.call_method0("create_future")?;
+        self.source.waiters().register(future.clone().unbind());
+        if let Some(event) = self.source.try_next()? {
+            return Err(PyStopIteration::new_err((event,)));
+        }
+        future.setattr("_asyncio_future_blocking", true)?;
+        Ok(future)
+    }
+}
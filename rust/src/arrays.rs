@@ -0,0 +1,421 @@
+// NumPy interop without a numpy crate dependency: input goes through the
+// buffer protocol, output is built with numpy.frombuffer at call time.
+use std::fmt;
+
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyByteArray, PyFloat, PyString, PyType};
+
+use crate::{Levels, PriceCodec};
+
+// A decimal held exactly: units * 10^-exp
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fixed {
+    pub units: i64,
+    pub exp: u32,
+}
+
+impl Fixed {
+    // Plain decimal text ("-12.340", ".5", "1E+2", "1.5e-7"); None for
+    // anything else (NaN, inf) or more significant digits than an i64 holds
+    fn parse(text: &str) -> Option<Self> {
+        let (mantissa, exp10) = match text.find(['e', 'E']) {
+            Some(i) => (&text[..i], text[i + 1..].parse::<i32>().ok()?),
+            None => (text, 0),
+        };
+        let (negative, digits) = match mantissa.as_bytes().first()? {
+            b'-' => (true, &mantissa[1..]),
+            b'+' => (false, &mantissa[1..]),
+            _ => (false, mantissa),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        if int.is_empty() && frac.is_empty()
+            || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let all = format!("{}{}", int, frac);
+        let mut all = all.trim_start_matches('0');
+        let mut exp = frac.len() as i64 - exp10 as i64;
+        while exp > 0 && all.ends_with('0') {
+            all = &all[..all.len() - 1];
+            exp -= 1;
+        }
+        let mut units: i64 = 0;
+        for b in all.bytes() {
+            units = units.checked_mul(10)?.checked_add((b - b'0') as i64)?;
+        }
+        if units == 0 {
+            exp = 0;
+        }
+        while exp < 0 {
+            units = units.checked_mul(10)?;
+            exp += 1;
+        }
+        Some(Self {
+            units: if negative { -units } else { units },
+            exp: u32::try_from(exp).ok()?,
+        })
+    }
+
+    // Nearest f64 (correctly rounded)
+    pub fn to_f64(self) -> f64 {
+        format!("{}e-{}", self.units, self.exp)
+            .parse()
+            .unwrap_or(f64::NAN)
+    }
+
+    // The decimal a float most likely stands for: its 15 significant digits,
+    // which drops binary noise such as 0.1 + 0.2 = 0.30000000000000004
+    pub fn from_f64(x: f64) -> Option<Self> {
+        if !x.is_finite() {
+            return None;
+        }
+        Self::parse(&format!("{:.14e}", x))
+    }
+
+    // Whether self is a whole number of `step`s, in exact arithmetic
+    pub fn is_multiple_of(self, step: Self) -> bool {
+        let exp = self.exp.max(step.exp);
+        let scaled = |f: Self| {
+            10i128
+                .checked_pow(exp - f.exp)
+                .and_then(|scale| (f.units as i128).checked_mul(scale))
+        };
+        match (scaled(self), scaled(step)) {
+            (Some(value), Some(step)) if step != 0 => value % step == 0,
+            _ => false,
+        }
+    }
+}
+
+// Plain decimal text; a precision pads the fraction with zeros up to that
+// many places ("{:.2}" of 1.5 is "1.50") but never cuts digits
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exp = self.exp as usize;
+        let places = f.precision().map_or(exp, |p| p.max(exp));
+        let digits = format!("{:0>width$}", self.units.unsigned_abs(), width = exp + 1);
+        let (int, frac) = digits.split_at(digits.len() - exp);
+        let sign = if self.units < 0 { "-" } else { "" };
+        if places == 0 {
+            write!(f, "{}{}", sign, int)
+        } else {
+            write!(f, "{}{}.{:0<places$}", sign, int, frac)
+        }
+    }
+}
+
+// Order prices and sizes: Decimal and str exactly, float via from_f64
+impl<'py> FromPyObject<'py> for Fixed {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        match number(ob)? {
+            Number::Fixed(f) => Ok(f),
+            Number::Float(x) => Self::from_f64(x)
+                .ok_or_else(|| PyValueError::new_err(format!("not a finite decimal: {}", x))),
+        }
+    }
+}
+
+// A price or size from Python: float, int, decimal.Decimal or a decimal
+// string. Decimals and strings are read as text into Fixed, so "0.3" and
+// Decimal("0.3") never pass through a binary float on their way to a tick
+// key; text that is not a plain decimal ("nan", "inf") is parsed as f64.
+enum Number {
+    Float(f64),
+    Fixed(Fixed),
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match self {
+            Self::Float(x) => *x,
+            Self::Fixed(f) => f.to_f64(),
+        }
+    }
+}
+
+fn number(ob: &Bound<'_, PyAny>) -> PyResult<Number> {
+    static DECIMAL: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    if let Ok(x) = ob.downcast::<PyFloat>() {
+        return Ok(Number::Float(x.value()));
+    }
+    let text = if let Ok(text) = ob.downcast::<PyString>() {
+        text.to_cow()?.trim().to_string()
+    } else if ob.is_instance(DECIMAL.import(ob.py(), "decimal", "Decimal")?)? {
+        ob.str()?.to_cow()?.into_owned()
+    } else {
+        return ob.extract().map(Number::Float);
+    };
+    if let Some(fixed) = Fixed::parse(&text) {
+        return Ok(Number::Fixed(fixed));
+    }
+    text.parse()
+        .map(Number::Float)
+        .map_err(|_| PyValueError::new_err(format!("not a number: '{}'", text)))
+}
+
+// (price, size) levels given either as a sequence of pairs or as a 2-D
+// float64 array of shape (N, 2). Decimal and string prices stay exact until
+// levels() knows the target book's PriceCodec.
+pub struct LevelsInput {
+    levels: Levels,
+    // Index and exact value of every Fixed price
+    exact: Vec<(usize, Fixed)>,
+}
+
+impl LevelsInput {
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    // The levels to apply to a book using `codec`: with tick keys, a Fixed
+    // price is placed on its tick by integer arithmetic, so float rounding
+    // can neither split one price into two levels nor move it a tick
+    pub(crate) fn levels(mut self, codec: PriceCodec) -> Levels {
+        for (i, price) in self.exact {
+            if let Some(key) = codec.fixed_key(price) {
+                self.levels[i].0 = codec.price(key);
+            }
+        }
+        self.levels
+    }
+}
+
+impl From<Levels> for LevelsInput {
+    fn from(levels: Levels) -> Self {
+        Self {
+            levels,
+            exact: Vec::new(),
+        }
+    }
+}
+
+// Sequence of (price, size) pairs with any numbers `number` accepts
+fn mixed_levels(ob: &Bound<'_, PyAny>) -> PyResult<LevelsInput> {
+    let mut out = LevelsInput::from(Vec::new());
+    for level in ob.try_iter()? {
+        let (price, size): (Bound<'_, PyAny>, Bound<'_, PyAny>) = level?.extract()?;
+        let price = number(&price)?;
+        if let Number::Fixed(fixed) = price {
+            out.exact.push((out.levels.len(), fixed));
+        }
+        out.levels.push((price.to_f64(), number(&size)?.to_f64()));
+    }
+    Ok(out)
+}
+
+impl<'py> FromPyObject<'py> for LevelsInput {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(buf) = PyBuffer::<f64>::get(ob) {
+            let shape = buf.shape();
+            if shape.len() != 2 || shape[1] != 2 {
+                return Err(PyValueError::new_err(format!(
+                    "levels array must have shape (N, 2), got {:?}",
+                    shape
+                )));
+            }
+            let flat = buf.to_vec(ob.py())?;
+            return Ok(Self::from(
+                flat.chunks_exact(2)
+                    .map(|c| (c[0], c[1]))
+                    .collect::<Levels>(),
+            ));
+        }
+        mixed_levels(ob).map_err(|e| {
+            if e.is_instance_of::<PyValueError>(ob.py()) {
+                return e;
+            }
+            PyTypeError::new_err("levels must be a list of (price, size) or a float64 (N, 2) array")
+        })
+    }
+}
+
+// Row-major float64 matrix given as a 2-D float64 array or a list of rows
+pub struct MatrixInput {
+    pub rows: usize,
+    pub cols: usize,
+    pub values: Vec<f64>,
+}
+
+impl<'py> FromPyObject<'py> for MatrixInput {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(buf) = PyBuffer::<f64>::get(ob) {
+            let shape = buf.shape();
+            if shape.len() != 2 {
+                return Err(PyValueError::new_err(format!(
+                    "matrix must be 2-D, got shape {:?}",
+                    shape
+                )));
+            }
+            return Ok(Self {
+                rows: shape[0],
+                cols: shape[1],
+                values: buf.to_vec(ob.py())?,
+            });
+        }
+        let rows: Vec<Vec<f64>> = ob.extract().map_err(|_| {
+            PyTypeError::new_err("matrix must be a list of rows or a 2-D float64 array")
+        })?;
+        let cols = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|r| r.len() != cols) {
+            return Err(PyValueError::new_err("matrix rows must have equal length"));
+        }
+        Ok(Self {
+            rows: rows.len(),
+            cols,
+            values: rows.concat(),
+        })
+    }
+}
+
+pub fn levels_to_ndarray<'py>(
+    py: Python<'py>,
+    levels: &[(f64, f64)],
+) -> PyResult<Bound<'py, PyAny>> {
+    let flat: Vec<f64> = levels.iter().flat_map(|&(p, s)| [p, s]).collect();
+    rows_to_ndarray(py, &flat, 2)
+}
+
+// Row-major float64 values as an (N, cols) array
+pub fn rows_to_ndarray<'py>(
+    py: Python<'py>,
+    values: &[f64],
+    cols: usize,
+) -> PyResult<Bound<'py, PyAny>> {
+    let mut raw = Vec::with_capacity(values.len() * 8);
+    for v in values {
+        raw.extend_from_slice(&v.to_ne_bytes());
+    }
+    let numpy = py.import("numpy")?;
+    // bytearray keeps the resulting array writable
+    let arr = numpy.call_method1("frombuffer", (PyByteArray::new(py, &raw), "float64"))?;
+    arr.call_method1("reshape", (values.len() / cols, cols))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(units: i64, exp: u32) -> Fixed {
+        Fixed { units, exp }
+    }
+
+    #[test]
+    fn parses_plain_and_exponent_decimals() {
+        for (text, want) in [
+            ("-12.340", fixed(-1234, 2)),
+            ("+7", fixed(7, 0)),
+            ("007", fixed(7, 0)),
+            (".5", fixed(5, 1)),
+            ("5.", fixed(5, 0)),
+            ("1E+2", fixed(100, 0)),
+            ("1.5e-7", fixed(15, 8)),
+            ("2500e-3", fixed(25, 1)),
+            ("0.000", fixed(0, 0)),
+            ("-0", fixed(0, 0)),
+            ("9223372036854775807", fixed(i64::MAX, 0)),
+            ("0.000000000000000001", fixed(1, 18)),
+        ] {
+            assert_eq!(Fixed::parse(text), Some(want), "{}", text);
+        }
+        for text in [
+            "",
+            ".",
+            "-",
+            "+",
+            "e5",
+            "1e",
+            "1e+",
+            "nan",
+            "inf",
+            "1.2.3",
+            "1,5",
+            "0x1",
+            "- 1",
+            "1_000",
+            // More digits than an i64 holds, before or after scaling
+            "9223372036854775808",
+            "1e19",
+        ] {
+            assert_eq!(Fixed::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn displays_without_cutting_digits() {
+        assert_eq!(fixed(1234, 2).to_string(), "12.34");
+        assert_eq!(format!("{:.4}", fixed(1234, 2)), "12.3400");
+        assert_eq!(format!("{:.1}", fixed(1234, 2)), "12.34");
+        assert_eq!(format!("{:.2}", fixed(100, 0)), "100.00");
+        assert_eq!(fixed(-5, 3).to_string(), "-0.005");
+        assert_eq!(fixed(0, 0).to_string(), "0");
+        assert_eq!(format!("{:.0}", fixed(42, 0)), "42");
+        assert_eq!(fixed(i64::MIN, 0).to_string(), "-9223372036854775808");
+        for text in ["0.1", "-3.25", "65000.5", "0.00000001"] {
+            assert_eq!(Fixed::parse(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn floats_map_to_the_decimal_they_stand_for() {
+        assert_eq!(Fixed::from_f64(0.1 + 0.2), Some(fixed(3, 1)));
+        assert_eq!(Fixed::from_f64(1e-8), Some(fixed(1, 8)));
+        assert_eq!(Fixed::from_f64(123_456.789), Some(fixed(123_456_789, 3)));
+        assert_eq!(Fixed::from_f64(-0.0), Some(fixed(0, 0)));
+        assert_eq!(Fixed::from_f64(f64::NAN), None);
+        assert_eq!(Fixed::from_f64(f64::INFINITY), None);
+        assert_eq!(fixed(3, 1).to_f64(), 0.3);
+        assert_eq!(fixed(-650_005, 1).to_f64(), -65_000.5);
+    }
+
+    #[test]
+    fn multiples_are_checked_exactly() {
+        let tick = fixed(1, 1);
+        assert!(fixed(3, 1).is_multiple_of(tick));
+        assert!(fixed(100, 0).is_multiple_of(fixed(1, 2)));
+        assert!(fixed(-12, 1).is_multiple_of(fixed(3, 1)));
+        assert!(fixed(0, 0).is_multiple_of(tick));
+        assert!(!fixed(35, 2).is_multiple_of(tick));
+        assert!(!fixed(1, 0).is_multiple_of(fixed(3, 1)));
+        assert!(!fixed(1, 0).is_multiple_of(fixed(0, 0)));
+        // Scaling past i128 is reported as off grid rather than panicking
+        assert!(!fixed(i64::MAX, 0).is_multiple_of(fixed(1, 30)));
+    }
+
+    #[test]
+    fn exact_prices_land_on_their_tick() {
+        // tick_size 0.1 and 0.5 as PriceCodec::ticks would build them
+        let tenth = PriceCodec::Ticks {
+            tick_size: 0.1,
+            tick_units: 1.0,
+            scale: 10.0,
+        };
+        let half = PriceCodec::Ticks {
+            tick_size: 0.5,
+            tick_units: 5.0,
+            scale: 10.0,
+        };
+        let input = || LevelsInput {
+            levels: vec![
+                (0.30000000000000004, 1.0),
+                (0.25, 2.0),
+                (-0.25, 3.0),
+                (7.7, 4.0),
+            ],
+            exact: vec![(0, fixed(3, 1)), (1, fixed(25, 2)), (2, fixed(-25, 2))],
+        };
+        assert_eq!(input().len(), 4);
+        let levels = input().levels(tenth);
+        assert_eq!(levels[0], (0.3, 1.0));
+        // Float prices are left alone
+        assert_eq!(levels[3], (7.7, 4.0));
+        // Halves round away from zero, as key() does
+        let levels = input().levels(half);
+        assert_eq!((levels[1].0, levels[2].0), (0.5, -0.5));
+        // Without tick keys the floats are used as given
+        assert_eq!(input().levels(PriceCodec::Float), input().levels);
+    }
+}
@@ -0,0 +1,671 @@
+// Arrow export through the C data interface and the Arrow PyCapsule
+// protocol (__arrow_c_schema__ / __arrow_c_array__ / __arrow_c_stream__),
+// without the arrow-rs dependency tree. A batch is a struct array of flat
+// columns: int64 ("l"), nullable uint64 ("L"), float64 ("g") and large
+// UTF-8 ("U"). Buffers are built once in Rust; exported arrays point into
+// them and hold an Arc on the batch until the consumer releases them, so
+// polars.DataFrame(batch) and pyarrow.record_batch(batch) do not copy.
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::ptr;
+use std::sync::Arc;
+
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyCapsule, PyDict};
+
+use crate::Levels;
+
+// ArrowSchema, ArrowArray and ArrowArrayStream from the C data interface
+#[repr(C)]
+struct ArrowSchema {
+    format: *const c_char,
+    name: *const c_char,
+    metadata: *const c_char,
+    flags: i64,
+    n_children: i64,
+    children: *mut *mut ArrowSchema,
+    dictionary: *mut ArrowSchema,
+    release: Option<unsafe extern "C" fn(*mut ArrowSchema)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+struct ArrowArray {
+    length: i64,
+    null_count: i64,
+    offset: i64,
+    n_buffers: i64,
+    n_children: i64,
+    buffers: *mut *const c_void,
+    children: *mut *mut ArrowArray,
+    dictionary: *mut ArrowArray,
+    release: Option<unsafe extern "C" fn(*mut ArrowArray)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+struct ArrowArrayStream {
+    get_schema: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowSchema) -> c_int>,
+    get_next: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowArray) -> c_int>,
+    get_last_error: Option<unsafe extern "C" fn(*mut ArrowArrayStream) -> *const c_char>,
+    release: Option<unsafe extern "C" fn(*mut ArrowArrayStream)>,
+    private_data: *mut c_void,
+}
+
+const FLAG_NULLABLE: i64 = 2;
+
+enum Data {
+    I64(Vec<i64>),
+    U64(Vec<u64>),
+    F64(Vec<f64>),
+    Utf8 { offsets: Vec<i64>, bytes: Vec<u8> },
+}
+
+pub struct Column {
+    name: CString,
+    data: Data,
+    // LSB-first validity bitmap; None when every value is present
+    validity: Option<Vec<u8>>,
+    nulls: usize,
+    len: usize,
+}
+
+impl Column {
+    fn new(name: &str, data: Data, len: usize) -> Self {
+        Self {
+            name: CString::new(name).unwrap_or_default(),
+            data,
+            validity: None,
+            nulls: 0,
+            len,
+        }
+    }
+
+    pub fn i64(name: &str, values: Vec<i64>) -> Self {
+        let len = values.len();
+        Self::new(name, Data::I64(values), len)
+    }
+
+    pub fn f64(name: &str, values: Vec<f64>) -> Self {
+        let len = values.len();
+        Self::new(name, Data::F64(values), len)
+    }
+
+    pub fn utf8<'a>(name: &str, values: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut offsets = vec![0i64];
+        let mut bytes = Vec::new();
+        for v in values {
+            bytes.extend_from_slice(v.as_bytes());
+            offsets.push(bytes.len() as i64);
+        }
+        let len = offsets.len() - 1;
+        Self::new(name, Data::Utf8 { offsets, bytes }, len)
+    }
+
+    pub fn opt_u64(name: &str, values: impl IntoIterator<Item = Option<u64>>) -> Self {
+        let mut data = Vec::new();
+        let mut validity = Vec::new();
+        let mut nulls = 0;
+        for (i, v) in values.into_iter().enumerate() {
+            if i % 8 == 0 {
+                validity.push(0u8);
+            }
+            match v {
+                Some(v) => {
+                    validity[i / 8] |= 1 << (i % 8);
+                    data.push(v);
+                }
+                None => {
+                    nulls += 1;
+                    data.push(0);
+                }
+            }
+        }
+        let len = data.len();
+        Self {
+            validity: (nulls > 0).then_some(validity),
+            nulls,
+            ..Self::new(name, Data::U64(data), len)
+        }
+    }
+
+    fn format(&self) -> &'static CStr {
+        match self.data {
+            Data::I64(_) => c"l",
+            Data::U64(_) => c"L",
+            Data::F64(_) => c"g",
+            Data::Utf8 { .. } => c"U",
+        }
+    }
+
+    fn is_valid(&self, i: usize) -> bool {
+        self.validity
+            .as_ref()
+            .is_none_or(|bits| bits[i / 8] & (1 << (i % 8)) != 0)
+    }
+
+    fn buffers(&self) -> Vec<*const c_void> {
+        let validity = self
+            .validity
+            .as_ref()
+            .map_or(ptr::null(), |v| v.as_ptr().cast());
+        match &self.data {
+            Data::I64(v) => vec![validity, v.as_ptr().cast()],
+            Data::U64(v) => vec![validity, v.as_ptr().cast()],
+            Data::F64(v) => vec![validity, v.as_ptr().cast()],
+            Data::Utf8 { offsets, bytes } => {
+                vec![validity, offsets.as_ptr().cast(), bytes.as_ptr().cast()]
+            }
+        }
+    }
+
+    fn to_py(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let out: Vec<PyObject> = (0..self.len)
+            .map(|i| {
+                if !self.is_valid(i) {
+                    return Ok(py.None());
+                }
+                Ok(match &self.data {
+                    Data::I64(v) => v[i].into_pyobject(py)?.into_any().unbind(),
+                    Data::U64(v) => v[i].into_pyobject(py)?.into_any().unbind(),
+                    Data::F64(v) => v[i].into_pyobject(py)?.into_any().unbind(),
+                    Data::Utf8 { offsets, bytes } => {
+                        let s = &bytes[offsets[i] as usize..offsets[i + 1] as usize];
+                        String::from_utf8_lossy(s)
+                            .into_pyobject(py)?
+                            .into_any()
+                            .unbind()
+                    }
+                })
+            })
+            .collect::<PyResult<_>>()?;
+        Ok(out.into_pyobject(py)?.into_any().unbind())
+    }
+}
+
+pub struct Batch {
+    columns: Vec<Column>,
+    rows: usize,
+}
+
+impl Batch {
+    // Columns must all have the same length
+    pub fn new(columns: Vec<Column>) -> Self {
+        let rows = columns.first().map_or(0, |c| c.len);
+        debug_assert!(columns.iter().all(|c| c.len == rows));
+        Self { columns, rows }
+    }
+}
+
+// One row per level: side ("bid"/"ask"), level (0 = best), price, size
+pub fn levels_batch(bids: &Levels, asks: &Levels) -> Batch {
+    let sides = bids.iter().map(|_| "bid").chain(asks.iter().map(|_| "ask"));
+    let level = (0..bids.len()).chain(0..asks.len()).map(|i| i as i64);
+    let all = || bids.iter().chain(asks);
+    Batch::new(vec![
+        Column::utf8("side", sides),
+        Column::i64("level", level.collect()),
+        Column::f64("price", all().map(|l| l.0).collect()),
+        Column::f64("size", all().map(|l| l.1).collect()),
+    ])
+}
+
+// ---- C data interface export ----
+
+struct SchemaPrivate {
+    format: CString,
+    name: CString,
+    children: Vec<*mut ArrowSchema>,
+}
+
+unsafe extern "C" fn release_schema(schema: *mut ArrowSchema) {
+    if schema.is_null() || (*schema).release.is_none() {
+        return;
+    }
+    let private = Box::from_raw((*schema).private_data as *mut SchemaPrivate);
+    for child in private.children {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    (*schema).release = None;
+}
+
+fn schema_node(
+    format: &CStr,
+    name: &CStr,
+    flags: i64,
+    children: Vec<*mut ArrowSchema>,
+) -> ArrowSchema {
+    let mut private = Box::new(SchemaPrivate {
+        format: format.to_owned(),
+        name: name.to_owned(),
+        children,
+    });
+    ArrowSchema {
+        format: private.format.as_ptr(),
+        name: private.name.as_ptr(),
+        metadata: ptr::null(),
+        flags,
+        n_children: private.children.len() as i64,
+        children: private.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_schema),
+        private_data: Box::into_raw(private).cast(),
+    }
+}
+
+fn export_schema(batch: &Batch) -> ArrowSchema {
+    let children = batch
+        .columns
+        .iter()
+        .map(|c| {
+            let flags = if c.validity.is_some() {
+                FLAG_NULLABLE
+            } else {
+                0
+            };
+            Box::into_raw(Box::new(schema_node(
+                c.format(),
+                &c.name,
+                flags,
+                Vec::new(),
+            )))
+        })
+        .collect();
+    schema_node(c"+s", c"", 0, children)
+}
+
+struct ArrayPrivate {
+    // Keeps the buffers the exported pointers refer to alive
+    _batch: Arc<Batch>,
+    buffers: Vec<*const c_void>,
+    children: Vec<*mut ArrowArray>,
+}
+
+unsafe extern "C" fn release_array(array: *mut ArrowArray) {
+    if array.is_null() || (*array).release.is_none() {
+        return;
+    }
+    let private = Box::from_raw((*array).private_data as *mut ArrayPrivate);
+    for child in private.children {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    (*array).release = None;
+}
+
+fn released_array() -> ArrowArray {
+    ArrowArray {
+        length: 0,
+        null_count: 0,
+        offset: 0,
+        n_buffers: 0,
+        n_children: 0,
+        buffers: ptr::null_mut(),
+        children: ptr::null_mut(),
+        dictionary: ptr::null_mut(),
+        release: None,
+        private_data: ptr::null_mut(),
+    }
+}
+
+fn array_node(
+    batch: &Arc<Batch>,
+    length: usize,
+    nulls: usize,
+    buffers: Vec<*const c_void>,
+    children: Vec<*mut ArrowArray>,
+) -> ArrowArray {
+    let mut private = Box::new(ArrayPrivate {
+        _batch: batch.clone(),
+        buffers,
+        children,
+    });
+    ArrowArray {
+        length: length as i64,
+        null_count: nulls as i64,
+        offset: 0,
+        n_buffers: private.buffers.len() as i64,
+        n_children: private.children.len() as i64,
+        buffers: private.buffers.as_mut_ptr(),
+        children: private.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_array),
+        private_data: Box::into_raw(private).cast(),
+    }
+}
+
+fn export_array(batch: &Arc<Batch>) -> ArrowArray {
+    let children = batch
+        .columns
+        .iter()
+        .map(|c| {
+            Box::into_raw(Box::new(array_node(
+                batch,
+                c.len,
+                c.nulls,
+                c.buffers(),
+                Vec::new(),
+            )))
+        })
+        .collect();
+    array_node(batch, batch.rows, 0, vec![ptr::null()], children)
+}
+
+// A stream of exactly one batch
+struct StreamPrivate {
+    batch: Arc<Batch>,
+    done: bool,
+}
+
+unsafe extern "C" fn stream_schema(stream: *mut ArrowArrayStream, out: *mut ArrowSchema) -> c_int {
+    let private = &*((*stream).private_data as *const StreamPrivate);
+    out.write(export_schema(&private.batch));
+    0
+}
+
+unsafe extern "C" fn stream_next(stream: *mut ArrowArrayStream, out: *mut ArrowArray) -> c_int {
+    let private = &mut *((*stream).private_data as *mut StreamPrivate);
+    // A released array marks the end of the stream
+    out.write(match private.done {
+        true => released_array(),
+        false => export_array(&private.batch),
+    });
+    private.done = true;
+    0
+}
+
+unsafe extern "C" fn stream_error(_: *mut ArrowArrayStream) -> *const c_char {
+    ptr::null()
+}
+
+unsafe extern "C" fn release_stream(stream: *mut ArrowArrayStream) {
+    if stream.is_null() || (*stream).release.is_none() {
+        return;
+    }
+    drop(Box::from_raw((*stream).private_data as *mut StreamPrivate));
+    (*stream).release = None;
+}
+
+// Capsule payload; the exported structs only hold pointers to data owned
+// through private_data
+#[repr(transparent)]
+struct Exported<T>(T);
+
+unsafe impl<T> Send for Exported<T> {}
+
+// Capsule whose destructor releases the struct unless a consumer moved it
+// out (and so nulled its release callback)
+macro_rules! capsule {
+    ($py:expr, $value:expr, $name:expr) => {
+        PyCapsule::new_with_destructor($py, Exported($value), Some($name.to_owned()), |mut v, _| {
+            if let Some(release) = v.0.release {
+                unsafe { release(&mut v.0) }
+            }
+        })
+    };
+}
+
+#[pyclass(frozen)]
+pub struct ArrowBatch {
+    batch: Arc<Batch>,
+}
+
+impl ArrowBatch {
+    pub fn new(batch: Batch) -> Self {
+        Self {
+            batch: Arc::new(batch),
+        }
+    }
+}
+
+#[pymethods]
+impl ArrowBatch {
+    #[getter]
+    pub fn num_rows(&self) -> usize {
+        self.batch.rows
+    }
+
+    #[getter]
+    pub fn column_names(&self) -> Vec<String> {
+        self.batch
+            .columns
+            .iter()
+            .map(|c| c.name.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    // One column as a Python list (copies; use the Arrow export for bulk)
+    pub fn column(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        self.batch
+            .columns
+            .iter()
+            .find(|c| c.name.to_bytes() == name.as_bytes())
+            .ok_or_else(|| PyKeyError::new_err(format!("no column '{}'", name)))?
+            .to_py(py)
+    }
+
+    pub fn to_pydict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for c in &self.batch.columns {
+            dict.set_item(c.name.to_string_lossy(), c.to_py(py)?)?;
+        }
+        Ok(dict)
+    }
+
+    // polars / pandas are imported at call time, not required by the module
+    pub fn to_polars<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        slf.py().import("polars")?.call_method1("DataFrame", (slf,))
+    }
+
+    pub fn to_pandas<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        slf.py()
+            .import("pyarrow")?
+            .call_method1("record_batch", (slf,))?
+            .call_method0("to_pandas")
+    }
+
+    // Arrow PyCapsule protocol. requested_schema is accepted but not
+    // honoured: the batch's own types are always exported.
+    fn __arrow_c_schema__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyCapsule>> {
+        capsule!(py, export_schema(&self.batch), c"arrow_schema")
+    }
+
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_array__<'py>(
+        &self,
+        py: Python<'py>,
+        requested_schema: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<(Bound<'py, PyCapsule>, Bound<'py, PyCapsule>)> {
+        let _ = requested_schema;
+        Ok((
+            capsule!(py, export_schema(&self.batch), c"arrow_schema")?,
+            capsule!(py, export_array(&self.batch), c"arrow_array")?,
+        ))
+    }
+
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_stream__<'py>(
+        &self,
+        py: Python<'py>,
+        requested_schema: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyCapsule>> {
+        let _ = requested_schema;
+        let private = Box::new(StreamPrivate {
+            batch: self.batch.clone(),
+            done: false,
+        });
+        let stream = ArrowArrayStream {
+            get_schema: Some(stream_schema),
+            get_next: Some(stream_next),
+            get_last_error: Some(stream_error),
+            release: Some(release_stream),
+            private_data: Box::into_raw(private).cast(),
+        };
+        capsule!(py, stream, c"arrow_array_stream")
+    }
+
+    fn __len__(&self) -> usize {
+        self.batch.rows
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ArrowBatch(rows={}, columns={:?})",
+            self.batch.rows,
+            self.column_names()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Arc<Batch> {
+        let ids = (0..10).map(|i| (i % 3 != 1).then_some(i as u64 * 100));
+        Arc::new(Batch::new(vec![
+            Column::i64("ts", (0..10).collect()),
+            Column::utf8(
+                "symbol",
+                ["BTC", "", "ÉTH", "x", "", "BTC", "a", "bb", "ccc", "€"],
+            ),
+            Column::opt_u64("update_id", ids),
+            Column::f64("price", (0..10).map(|i| i as f64 + 0.5).collect()),
+        ]))
+    }
+
+    // Buffer `i` of child `c` as a slice of `n` T's
+    unsafe fn buffer<T>(array: &ArrowArray, c: usize, i: usize, n: usize) -> &[T] {
+        let child = &**array.children.add(c);
+        std::slice::from_raw_parts(*child.buffers.add(i) as *const T, n)
+    }
+
+    #[test]
+    fn schema_describes_a_struct_of_flat_columns() {
+        let mut schema = export_schema(&sample());
+        unsafe {
+            assert_eq!(CStr::from_ptr(schema.format), c"+s");
+            assert_eq!(schema.n_children, 4);
+            let fields: Vec<_> = (0..4)
+                .map(|i| {
+                    let child = &**schema.children.add(i);
+                    (
+                        CStr::from_ptr(child.name).to_str().unwrap(),
+                        CStr::from_ptr(child.format).to_str().unwrap(),
+                        child.flags,
+                    )
+                })
+                .collect();
+            assert_eq!(
+                fields,
+                [
+                    ("ts", "l", 0),
+                    ("symbol", "U", 0),
+                    ("update_id", "L", FLAG_NULLABLE),
+                    ("price", "g", 0)
+                ]
+            );
+            release_schema(&mut schema);
+            assert!(schema.release.is_none());
+            // Releasing twice is a no-op
+            release_schema(&mut schema);
+        }
+    }
+
+    #[test]
+    fn array_buffers_hold_values_offsets_and_validity() {
+        let batch = sample();
+        let mut array = export_array(&batch);
+        assert_eq!(Arc::strong_count(&batch), 6);
+        unsafe {
+            assert_eq!(
+                (array.length, array.n_children, array.n_buffers),
+                (10, 4, 1)
+            );
+            assert_eq!(buffer::<i64>(&array, 0, 1, 10), (0..10).collect::<Vec<_>>());
+
+            let offsets = buffer::<i64>(&array, 1, 1, 11);
+            let bytes = buffer::<u8>(&array, 1, 2, offsets[10] as usize);
+            let symbols: Vec<&str> = offsets
+                .windows(2)
+                .map(|w| std::str::from_utf8(&bytes[w[0] as usize..w[1] as usize]).unwrap())
+                .collect();
+            assert_eq!(
+                symbols,
+                ["BTC", "", "ÉTH", "x", "", "BTC", "a", "bb", "ccc", "€"]
+            );
+
+            // Rows 1, 4 and 7 are null; the bitmap spills into a second byte
+            let ids = &**array.children.add(2);
+            assert_eq!(ids.null_count, 3);
+            assert_eq!(buffer::<u8>(&array, 2, 0, 2), [0b0110_1101, 0b0000_0011]);
+            assert_eq!(buffer::<u64>(&array, 2, 1, 10)[9], 900);
+
+            // No validity buffer when nothing is null
+            let prices = &**array.children.add(3);
+            assert!((*prices.buffers).is_null() && prices.null_count == 0);
+            assert_eq!(buffer::<f64>(&array, 3, 1, 10)[3], 3.5);
+
+            release_array(&mut array);
+        }
+        assert!(array.release.is_none());
+        assert_eq!(Arc::strong_count(&batch), 1);
+    }
+
+    #[test]
+    fn opt_u64_without_nulls_has_no_bitmap() {
+        let col = Column::opt_u64("id", [Some(1), Some(2)]);
+        assert!(col.validity.is_none() && col.nulls == 0);
+        let col = Column::opt_u64("id", [None; 8]);
+        assert_eq!((col.validity, col.nulls), (Some(vec![0]), 8));
+    }
+
+    #[test]
+    fn stream_yields_the_batch_once() {
+        let batch = sample();
+        let mut stream = ArrowArrayStream {
+            get_schema: Some(stream_schema),
+            get_next: Some(stream_next),
+            get_last_error: Some(stream_error),
+            release: Some(release_stream),
+            private_data: Box::into_raw(Box::new(StreamPrivate {
+                batch: batch.clone(),
+                done: false,
+            }))
+            .cast(),
+        };
+        unsafe {
+            let mut array = released_array();
+            assert_eq!(stream_next(&mut stream, &mut array), 0);
+            assert_eq!(array.length, 10);
+            release_array(&mut array);
+            assert_eq!(stream_next(&mut stream, &mut array), 0);
+            assert!(array.release.is_none());
+            assert!(stream_error(&mut stream).is_null());
+            release_stream(&mut stream);
+        }
+        assert_eq!(Arc::strong_count(&batch), 1);
+    }
+
+    #[test]
+    fn empty_levels_make_an_empty_batch() {
+        let batch = Arc::new(levels_batch(&Vec::new(), &Vec::new()));
+        let mut array = export_array(&batch);
+        assert_eq!((array.length, array.n_children), (0, 4));
+        unsafe {
+            // Offsets of an empty string column still hold the leading 0
+            assert_eq!(buffer::<i64>(&array, 0, 1, 1), [0]);
+            release_array(&mut array);
+        }
+        let batch = levels_batch(&vec![(100.0, 1.0), (99.5, 2.0)], &vec![(101.0, 3.0)]);
+        let Data::I64(level) = &batch.columns[1].data else {
+            unreachable!()
+        };
+        assert_eq!((batch.rows, level.as_slice()), (3, &[0, 1, 0][..]));
+    }
+}
@@ -0,0 +1,717 @@
+// Event-driven backtest: recorded market data for any number of symbols is
+// replayed in timestamp order (ties keep insertion order) through one
+// SimExchange per symbol. The strategy is any Python object with optional
+// callbacks, each receiving the backtester as `bt`:
+//   on_book(bt, symbol, ts)                  after a snapshot or delta
+//   on_trade(bt, symbol, ts, price, qty)     after a public trade
+//   on_fill(bt, symbol, fill)                for every SimFill of ours
+//   on_reject(bt, symbol, order_id)          post-only order that would cross
+// Fills are booked into per-symbol Positions before on_fill runs, so the
+// strategy always sees its inventory.
+//
+// Latency: with feed_latency, each market event reaches the strategy after a
+// sampled delay and bt.book(symbol) is the strategy's delayed view of the
+// book. With order_latency, bt.submit_limit / submit_market / cancel reach
+// the matching engine after a sampled delay. Both streams stay FIFO, like a
+// TCP connection. bt.exchange(symbol) is the matching engine itself: orders
+// sent there skip order latency.
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::clock::{Clock, ReplayClock};
+use crate::errors::BookError;
+use crate::fees::FeeModel;
+use crate::journal::JournalReader;
+use crate::latency::LatencyModel;
+use crate::native::{self, NativeConfig};
+use crate::position::Position;
+use crate::sim::{check_qty, SimExchange, SimFill};
+use crate::{L2Book, Levels, Side};
+
+pub(crate) enum EventKind {
+    Snapshot(Levels, Levels),
+    Delta(Levels, Levels),
+    Trade(f64, f64),
+}
+
+pub(crate) struct Event {
+    pub(crate) ts: i64,
+    pub(crate) symbol: String,
+    pub(crate) kind: EventKind,
+}
+
+enum Action {
+    Limit {
+        id: u64,
+        side: Side,
+        price: f64,
+        qty: f64,
+        post_only: bool,
+    },
+    Market {
+        id: u64,
+        side: Side,
+        qty: f64,
+    },
+    Cancel(u64),
+}
+
+enum Item {
+    // Delayed delivery of events[i] to the strategy
+    Notify(usize),
+    Order(String, Action),
+}
+
+struct Scheduled {
+    at: i64,
+    seq: u64,
+    item: Item,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Callbacks {
+    on_book: bool,
+    on_trade: bool,
+    on_fill: bool,
+    on_reject: bool,
+}
+
+#[pyclass(get_all)]
+#[derive(Clone)]
+pub struct BacktestReport {
+    pub events: usize,
+    pub fills: Vec<(String, SimFill)>,
+    pub positions: BTreeMap<String, Position>,
+    pub realized_pnl: f64,
+    // Open inventory marked at each book's final mid
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    pub volume: f64,
+    // realized + unrealized - fees
+    pub total_pnl: f64,
+}
+
+#[pymethods]
+impl BacktestReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "BacktestReport(events={}, fills={}, total_pnl={:?}, fees={:?})",
+            self.events,
+            self.fills.len(),
+            self.total_pnl,
+            self.fees
+        )
+    }
+}
+
+#[pyclass]
+pub struct Backtester {
+    pub(crate) events: Vec<Event>,
+    exchanges: BTreeMap<String, Py<SimExchange>>,
+    // What the strategy sees; the exchange book itself without feed latency
+    views: BTreeMap<String, Py<L2Book>>,
+    positions: BTreeMap<String, Position>,
+    fills: Vec<(String, SimFill)>,
+    rejects: Vec<(String, u64)>,
+    pub(crate) fee_model: Option<FeeModel>,
+    pub(crate) queue_power: f64,
+    pub(crate) position_mode: String,
+    feed_latency: Option<LatencyModel>,
+    order_latency: Option<LatencyModel>,
+    scheduled: BinaryHeap<Reverse<Scheduled>>,
+    next_seq: u64,
+    // Latest delivery time per stream, to keep each one FIFO
+    last_feed_at: i64,
+    last_order_at: i64,
+    now: i64,
+    // Follows `now`, for analytics built with this clock
+    clock: Clock,
+}
+
+impl Backtester {
+    fn set_now(&mut self, ts: i64) {
+        self.now = ts;
+        self.clock.set(ts);
+    }
+
+    fn push(&mut self, symbol: String, ts: i64, kind: EventKind) {
+        self.events.push(Event { ts, symbol, kind });
+    }
+
+    fn schedule(&mut self, at: i64, item: Item) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.scheduled.push(Reverse(Scheduled { at, seq, item }));
+    }
+
+    // Fresh exchanges, books, positions and latency streams for a run
+    fn reset_state(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.exchanges.clear();
+        self.views.clear();
+        self.positions.clear();
+        self.fills.clear();
+        self.rejects.clear();
+        self.scheduled.clear();
+        self.last_feed_at = i64::MIN;
+        self.last_order_at = i64::MIN;
+        for model in [&mut self.feed_latency, &mut self.order_latency]
+            .into_iter()
+            .flatten()
+        {
+            model.reset();
+        }
+        for ev in &self.events {
+            if self.exchanges.contains_key(&ev.symbol) {
+                continue;
+            }
+            let sim = Py::new(
+                py,
+                SimExchange::new(py, None, self.fee_model.clone(), self.queue_power)?,
+            )?;
+            let view = match self.feed_latency {
+                Some(_) => Py::new(py, L2Book::default())?,
+                None => sim.borrow(py).book.clone_ref(py),
+            };
+            self.exchanges.insert(ev.symbol.clone(), sim);
+            self.views.insert(ev.symbol.clone(), view);
+            self.positions
+                .insert(ev.symbol.clone(), Position::new(&self.position_mode, None)?);
+        }
+        Ok(())
+    }
+
+    fn sim(&self, py: Python<'_>, symbol: &str) -> PyResult<Py<SimExchange>> {
+        self.exchanges
+            .get(symbol)
+            .map(|s| s.clone_ref(py))
+            .ok_or_else(|| PyKeyError::new_err(symbol.to_owned()))
+    }
+
+    // Hand an order to the matching engine now, or after the order latency
+    fn route(&mut self, py: Python<'_>, symbol: &str, action: Action) -> PyResult<()> {
+        let sim = self.sim(py, symbol)?;
+        match self.order_latency.as_mut() {
+            Some(model) => {
+                let at = (self.now + model.sample_ms()).max(self.last_order_at);
+                self.last_order_at = at;
+                self.schedule(at, Item::Order(symbol.to_owned(), action));
+            }
+            None => {
+                if let Some(id) = Self::execute(py, &sim, action, self.now) {
+                    self.rejects.push((symbol.to_owned(), id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Runs an order action arriving at `at`. Returns the order id of a
+    // rejected post-only order.
+    fn execute(py: Python<'_>, sim: &Py<SimExchange>, action: Action, at: i64) -> Option<u64> {
+        let mut sim = sim.borrow_mut(py);
+        sim.engine.now = sim.engine.now.max(at);
+        match action {
+            Action::Limit {
+                id,
+                side,
+                price,
+                qty,
+                post_only,
+            } => (!sim.place_limit(py, id, side, price, qty, post_only)).then_some(id),
+            Action::Market { id, side, qty } => {
+                sim.place_market(py, id, side, qty);
+                None
+            }
+            Action::Cancel(id) => {
+                sim.cancel(id);
+                None
+            }
+        }
+    }
+
+    // Book new fills and hand them (and rejects) to the strategy until no
+    // more arrive, since callbacks may trade again
+    fn dispatch(slf: &Bound<'_, Self>, strategy: &Bound<'_, PyAny>, cb: Callbacks) -> PyResult<()> {
+        let py = slf.py();
+        loop {
+            let mut batch = Vec::new();
+            let rejects = {
+                let mut bt = slf.borrow_mut();
+                for (symbol, sim) in &bt.exchanges {
+                    for fill in sim.borrow_mut(py).take_fills() {
+                        batch.push((symbol.clone(), fill));
+                    }
+                }
+                for (symbol, fill) in &batch {
+                    let signed = match fill.side {
+                        "buy" => fill.qty,
+                        _ => -fill.qty,
+                    };
+                    if let Some(pos) = bt.positions.get_mut(symbol) {
+                        pos.apply_fill(fill.price, signed, fill.fee);
+                    }
+                }
+                bt.fills.extend(batch.iter().cloned());
+                std::mem::take(&mut bt.rejects)
+            };
+            if batch.is_empty() && rejects.is_empty() {
+                return Ok(());
+            }
+            if cb.on_fill {
+                for (symbol, fill) in batch {
+                    strategy.call_method1("on_fill", (slf, symbol, fill))?;
+                }
+            }
+            if cb.on_reject {
+                for (symbol, id) in rejects {
+                    strategy.call_method1("on_reject", (slf, symbol, id))?;
+                }
+            }
+        }
+    }
+
+    // Deliver events[i] to the strategy at time `at`
+    fn notify(
+        slf: &Bound<'_, Self>,
+        strategy: &Bound<'_, PyAny>,
+        cb: Callbacks,
+        ev: &Event,
+        at: i64,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        {
+            let mut bt = slf.borrow_mut();
+            bt.set_now(at);
+            // A separate view only exists with feed latency
+            if bt.feed_latency.is_some() {
+                let mut view = bt.views[&ev.symbol].borrow_mut(py);
+                match &ev.kind {
+                    EventKind::Snapshot(b, a) => view.load_snapshot(b.clone(), a.clone(), None)?,
+                    EventKind::Delta(b, a) => view.apply_levels(b.clone(), a.clone())?,
+                    EventKind::Trade(..) => {}
+                }
+            }
+        }
+        match ev.kind {
+            EventKind::Trade(p, q) if cb.on_trade => {
+                strategy.call_method1("on_trade", (slf, ev.symbol.as_str(), ev.ts, p, q))?;
+            }
+            EventKind::Snapshot(..) | EventKind::Delta(..) if cb.on_book => {
+                strategy.call_method1("on_book", (slf, ev.symbol.as_str(), ev.ts))?;
+            }
+            _ => return Ok(()),
+        }
+        Self::dispatch(slf, strategy, cb)
+    }
+
+    // Run scheduled notifications and order arrivals due at or before `until`
+    fn run_due(
+        slf: &Bound<'_, Self>,
+        strategy: &Bound<'_, PyAny>,
+        cb: Callbacks,
+        events: &[Event],
+        until: i64,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        loop {
+            let next = {
+                let mut bt = slf.borrow_mut();
+                match bt.scheduled.peek() {
+                    Some(Reverse(s)) if s.at <= until => bt.scheduled.pop().map(|r| r.0),
+                    _ => None,
+                }
+            };
+            let Some(Scheduled { at, item, .. }) = next else {
+                return Ok(());
+            };
+            match item {
+                Item::Notify(i) => Self::notify(slf, strategy, cb, &events[i], at)?,
+                Item::Order(symbol, action) => {
+                    let sim = {
+                        let mut bt = slf.borrow_mut();
+                        bt.set_now(at);
+                        bt.sim(py, &symbol)?
+                    };
+                    if let Some(id) = Self::execute(py, &sim, action, at) {
+                        slf.borrow_mut().rejects.push((symbol, id));
+                    }
+                    Self::dispatch(slf, strategy, cb)?;
+                }
+            }
+        }
+    }
+
+    fn replay(
+        slf: &Bound<'_, Self>,
+        strategy: &Bound<'_, PyAny>,
+        events: &[Event],
+    ) -> PyResult<()> {
+        let py = slf.py();
+        let cb = Callbacks {
+            on_book: strategy.hasattr("on_book")?,
+            on_trade: strategy.hasattr("on_trade")?,
+            on_fill: strategy.hasattr("on_fill")?,
+            on_reject: strategy.hasattr("on_reject")?,
+        };
+        for (i, ev) in events.iter().enumerate() {
+            Self::run_due(slf, strategy, cb, events, ev.ts)?;
+            let sim = {
+                let mut bt = slf.borrow_mut();
+                bt.set_now(ev.ts);
+                bt.exchanges[&ev.symbol].clone_ref(py)
+            };
+            {
+                let mut sim = sim.borrow_mut(py);
+                match &ev.kind {
+                    EventKind::Snapshot(b, a) => {
+                        sim.apply_snapshot(py, b.clone(), a.clone(), ev.ts)?
+                    }
+                    EventKind::Delta(b, a) => sim.apply_delta(py, b.clone(), a.clone(), ev.ts)?,
+                    EventKind::Trade(p, q) => sim.on_trade(*p, *q, ev.ts),
+                }
+            }
+            Self::dispatch(slf, strategy, cb)?;
+            let delayed = {
+                let mut bt = slf.borrow_mut();
+                match bt.feed_latency.as_mut() {
+                    Some(model) => {
+                        let at = (ev.ts + model.sample_ms()).max(bt.last_feed_at);
+                        bt.last_feed_at = at;
+                        bt.schedule(at, Item::Notify(i));
+                        true
+                    }
+                    None => false,
+                }
+            };
+            if !delayed {
+                Self::notify(slf, strategy, cb, ev, ev.ts)?;
+            }
+        }
+        // Deliver whatever is still in flight after the data ends
+        Self::run_due(slf, strategy, cb, events, i64::MAX)
+    }
+
+    // Loaded events in replay order, for native runs
+    pub(crate) fn sorted_events(&self) -> Vec<&Event> {
+        let mut events: Vec<&Event> = self.events.iter().collect();
+        events.sort_by_key(|e| e.ts);
+        events
+    }
+
+    pub(crate) fn native_config(&self) -> NativeConfig {
+        NativeConfig {
+            fee_model: self.fee_model.clone(),
+            queue_power: self.queue_power,
+            position_mode: self.position_mode.clone(),
+        }
+    }
+
+    fn report(&self, py: Python<'_>, events: usize) -> BacktestReport {
+        let mut report = BacktestReport {
+            events,
+            fills: self.fills.clone(),
+            positions: self.positions.clone(),
+            realized_pnl: 0.0,
+            unrealized_pnl: 0.0,
+            fees: 0.0,
+            volume: 0.0,
+            total_pnl: 0.0,
+        };
+        for (symbol, pos) in &self.positions {
+            let book = self.exchanges[symbol].borrow(py).book.clone_ref(py);
+            // The exchange's own book: marked regardless of any staleness limit
+            let mid = book.borrow(py).raw_mid();
+            let unrealized = mid.map_or(0.0, |m| pos.unrealized_pnl(m));
+            report.realized_pnl += pos.realized_pnl();
+            report.unrealized_pnl += unrealized;
+            report.fees += pos.fees();
+            report.volume += pos.volume();
+        }
+        report.total_pnl = report.realized_pnl + report.unrealized_pnl - report.fees;
+        report
+    }
+}
+
+#[pymethods]
+impl Backtester {
+    // position_mode is passed to each symbol's Position ("average" or "fifo").
+    // clock, if given, must be a replay clock; the backtester drives it
+    #[new]
+    #[pyo3(signature = (fee_model=None, queue_power=1.0, position_mode="average", feed_latency=None, order_latency=None, clock=None))]
+    pub fn new(
+        fee_model: Option<FeeModel>,
+        queue_power: f64,
+        position_mode: &str,
+        feed_latency: Option<LatencyModel>,
+        order_latency: Option<LatencyModel>,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        let clock = clock.unwrap_or_else(|| Clock::replay(0));
+        if !clock.is_replay() {
+            return Err(PyValueError::new_err(
+                "a backtest needs a replay clock, not the wall clock",
+            ));
+        }
+        // Validate both up front rather than on the first run
+        Position::new(position_mode, None)?;
+        crate::queue::QueueTracker::new(queue_power)?;
+        Ok(Self {
+            events: Vec::new(),
+            exchanges: BTreeMap::new(),
+            views: BTreeMap::new(),
+            positions: BTreeMap::new(),
+            fills: Vec::new(),
+            rejects: Vec::new(),
+            fee_model,
+            queue_power,
+            position_mode: position_mode.to_owned(),
+            feed_latency,
+            order_latency,
+            scheduled: BinaryHeap::new(),
+            next_seq: 0,
+            last_feed_at: i64::MIN,
+            last_order_at: i64::MIN,
+            now: 0,
+            clock,
+        })
+    }
+
+    // Levels are checked as they are added, so bad data fails here rather
+    // than partway through run()
+    pub fn add_snapshot(
+        &mut self,
+        symbol: String,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+    ) -> PyResult<()> {
+        BookError::check_levels(&bids, &asks)?;
+        self.push(symbol, ts, EventKind::Snapshot(bids, asks));
+        Ok(())
+    }
+
+    pub fn add_delta(
+        &mut self,
+        symbol: String,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+    ) -> PyResult<()> {
+        BookError::check_levels(&bids, &asks)?;
+        self.push(symbol, ts, EventKind::Delta(bids, asks));
+        Ok(())
+    }
+
+    pub fn add_trade(&mut self, symbol: String, ts: i64, price: f64, qty: f64) {
+        self.push(symbol, ts, EventKind::Trade(price, qty));
+    }
+
+    // Load the reader's remaining records with start_ts <= ts < end_ts,
+    // optionally only `symbols`; seeks to start_ts first and stops before
+    // the first record at or past end_ts, so consecutive windows can be
+    // loaded from one reader. Trade sides are dropped. Returns the number
+    // of events added.
+    #[pyo3(signature = (reader, start_ts=None, end_ts=None, symbols=None))]
+    pub fn add_journal(
+        &mut self,
+        py: Python<'_>,
+        reader: &mut JournalReader,
+        start_ts: Option<i64>,
+        end_ts: Option<i64>,
+        symbols: Option<Vec<String>>,
+    ) -> PyResult<usize> {
+        if let Some(ts) = start_ts {
+            reader.seek(py, ts)?;
+        }
+        let mut added = 0;
+        while let Some(rec) = reader.next_record()? {
+            if end_ts.is_some_and(|end| rec.ts >= end) {
+                reader.unread(rec);
+                break;
+            }
+            if symbols.as_ref().is_some_and(|s| !s.contains(&rec.symbol)) {
+                continue;
+            }
+            BookError::check_levels(&rec.bids, &rec.asks)?;
+            let kind = match rec.kind {
+                "snapshot" => EventKind::Snapshot(rec.bids, rec.asks),
+                "delta" => EventKind::Delta(rec.bids, rec.asks),
+                _ => EventKind::Trade(rec.price.unwrap_or(0.0), rec.qty.unwrap_or(0.0)),
+            };
+            self.push(rec.symbol, rec.ts, kind);
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    // Replays all loaded events from a clean state; the data is kept, so
+    // run() can be called again with another strategy
+    pub fn run(slf: &Bound<'_, Self>, strategy: &Bound<'_, PyAny>) -> PyResult<BacktestReport> {
+        let py = slf.py();
+        let events = {
+            let mut bt = slf.borrow_mut();
+            bt.reset_state(py)?;
+            let mut events = std::mem::take(&mut bt.events);
+            events.sort_by_key(|e| e.ts);
+            events
+        };
+        let result = Self::replay(slf, strategy, &events);
+        let count = events.len();
+        let mut bt = slf.borrow_mut();
+        bt.events = events;
+        result?;
+        Ok(bt.report(py, count))
+    }
+
+    // Replays the data through a built-in strategy template (see native.rs)
+    // instead of Python callbacks, e.g. run_template("avellaneda",
+    // {"gamma": 0.1, "size": 0.01}). Fees, queue_power and position_mode
+    // apply as in run(); latency models do not. The backtester's own state
+    // is left untouched.
+    #[pyo3(signature = (strategy, params=None))]
+    pub fn run_template(
+        &self,
+        py: Python<'_>,
+        strategy: &str,
+        params: Option<HashMap<String, f64>>,
+    ) -> PyResult<BacktestReport> {
+        let params = params.unwrap_or_default();
+        native::template(strategy, &params)?;
+        let config = self.native_config();
+        let result = py.allow_threads(|| {
+            native::replay(&[], &self.sorted_events(), &config, strategy, &params, true)
+        })?;
+        Ok(BacktestReport {
+            events: self.events.len(),
+            fills: result.trades,
+            positions: result.positions,
+            realized_pnl: result.realized_pnl,
+            unrealized_pnl: result.unrealized_pnl,
+            fees: result.fees,
+            volume: result.volume,
+            total_pnl: result.total_pnl,
+        })
+    }
+
+    // Order entry subject to order_latency. Returns the order id at once; a
+    // post-only order that would cross is reported through on_reject.
+    #[pyo3(signature = (symbol, side, price, qty, post_only=false))]
+    pub fn submit_limit(
+        &mut self,
+        py: Python<'_>,
+        symbol: &str,
+        side: &str,
+        price: f64,
+        qty: f64,
+        post_only: bool,
+    ) -> PyResult<u64> {
+        let side = Side::parse(side)?;
+        check_qty(qty)?;
+        let id = self.sim(py, symbol)?.borrow_mut(py).engine.reserve_id();
+        let action = Action::Limit {
+            id,
+            side,
+            price,
+            qty,
+            post_only,
+        };
+        self.route(py, symbol, action)?;
+        Ok(id)
+    }
+
+    pub fn submit_market(
+        &mut self,
+        py: Python<'_>,
+        symbol: &str,
+        side: &str,
+        qty: f64,
+    ) -> PyResult<u64> {
+        let side = Side::parse(side)?;
+        check_qty(qty)?;
+        let id = self.sim(py, symbol)?.borrow_mut(py).engine.reserve_id();
+        self.route(py, symbol, Action::Market { id, side, qty })?;
+        Ok(id)
+    }
+
+    // Fills that happen while the cancel is in flight still count
+    pub fn cancel(&mut self, py: Python<'_>, symbol: &str, order_id: u64) -> PyResult<()> {
+        self.route(py, symbol, Action::Cancel(order_id))
+    }
+
+    pub fn exchange(&self, py: Python<'_>, symbol: &str) -> PyResult<Py<SimExchange>> {
+        self.sim(py, symbol)
+    }
+
+    // The strategy's (possibly delayed) view of the book
+    pub fn book(&self, py: Python<'_>, symbol: &str) -> PyResult<Py<L2Book>> {
+        self.views
+            .get(symbol)
+            .map(|b| b.clone_ref(py))
+            .ok_or_else(|| PyKeyError::new_err(symbol.to_owned()))
+    }
+
+    pub fn position(&self, symbol: &str) -> PyResult<Position> {
+        self.positions
+            .get(symbol)
+            .cloned()
+            .ok_or_else(|| PyKeyError::new_err(symbol.to_owned()))
+    }
+
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .events
+            .iter()
+            .map(|e| e.symbol.clone())
+            .chain(self.exchanges.keys().cloned())
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    // Current simulation time: the event being replayed, or the delivery
+    // time of a delayed notification
+    #[getter]
+    fn now(&self) -> i64 {
+        self.now
+    }
+
+    // Replay clock following the simulated time
+    #[getter]
+    fn clock(&self) -> ReplayClock {
+        ReplayClock::from_clock(self.clock.clone())
+    }
+
+    // Orders and notifications still in flight
+    #[getter]
+    fn pending(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    fn __len__(&self) -> usize {
+        self.events.len()
+    }
+}
@@ -0,0 +1,202 @@
+// Price-band circuit breaker per symbol. Quoting halts when the mid
+//   - moved more than max_move_bps against the lowest or highest mid of the
+//     last window_ms (a flash move in either direction), or
+//   - deviates more than max_deviation_bps from an external reference
+//     price (e.g. the index), unless the reference is older than
+//     reference_max_age_ms,
+// and resumes once cooldown_ms have passed since the last breach: a mid
+// still outside the band keeps re-tripping and so extends the halt.
+// Times come from `ts` or the guard's clock.
+use std::collections::{HashMap, VecDeque};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+
+#[derive(Default)]
+struct Band {
+    // Monotonic (ts, mid) queues over the window: increasing for the min,
+    // decreasing for the max
+    lows: VecDeque<(i64, f64)>,
+    highs: VecDeque<(i64, f64)>,
+    reference: Option<(i64, f64)>,
+    // (last breach ts, reason)
+    tripped: Option<(i64, String)>,
+}
+
+fn bps(a: f64, b: f64) -> f64 {
+    (a / b - 1.0).abs() * 10_000.0
+}
+
+#[pyclass]
+pub struct PriceBandGuard {
+    max_move_bps: Option<f64>,
+    window_ms: i64,
+    max_deviation_bps: Option<f64>,
+    reference_max_age_ms: Option<i64>,
+    cooldown_ms: i64,
+    clock: Option<Clock>,
+    symbols: HashMap<String, Band>,
+}
+
+impl PriceBandGuard {
+    fn breach(&self, band: &Band, mid: f64, ts: i64) -> Option<String> {
+        if let Some(limit) = self.max_move_bps {
+            for &(_, extreme) in [band.lows.front(), band.highs.front()]
+                .into_iter()
+                .flatten()
+            {
+                let moved = bps(mid, extreme);
+                if moved > limit {
+                    return Some(format!(
+                        "mid {} moved {:.1} bps from {} within {} ms (max_move_bps {})",
+                        mid, moved, extreme, self.window_ms, limit
+                    ));
+                }
+            }
+        }
+        if let (Some(limit), Some((ref_ts, reference))) = (self.max_deviation_bps, band.reference) {
+            let fresh = self
+                .reference_max_age_ms
+                .is_none_or(|age| ts - ref_ts <= age);
+            let deviation = bps(mid, reference);
+            if fresh && deviation > limit {
+                return Some(format!(
+                    "mid {} deviates {:.1} bps from reference {} (max_deviation_bps {})",
+                    mid, deviation, reference, limit
+                ));
+            }
+        }
+        None
+    }
+
+    fn halted_at(&self, symbol: &str, ts: i64) -> bool {
+        self.symbols
+            .get(symbol)
+            .and_then(|b| b.tripped.as_ref())
+            .is_some_and(|(at, _)| ts - at < self.cooldown_ms)
+    }
+}
+
+#[pymethods]
+impl PriceBandGuard {
+    // Checks left at None are off
+    #[new]
+    #[pyo3(signature = (max_move_bps=None, window_ms=1_000, max_deviation_bps=None, reference_max_age_ms=None, cooldown_ms=30_000, clock=None))]
+    pub fn new(
+        max_move_bps: Option<f64>,
+        window_ms: i64,
+        max_deviation_bps: Option<f64>,
+        reference_max_age_ms: Option<i64>,
+        cooldown_ms: i64,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if window_ms <= 0 || cooldown_ms < 0 {
+            return Err(PyValueError::new_err(
+                "window_ms must be positive and cooldown_ms non-negative",
+            ));
+        }
+        if max_move_bps.is_some_and(|b| b <= 0.0) || max_deviation_bps.is_some_and(|b| b <= 0.0) {
+            return Err(PyValueError::new_err("band widths must be positive"));
+        }
+        Ok(Self {
+            max_move_bps,
+            window_ms,
+            max_deviation_bps,
+            reference_max_age_ms,
+            cooldown_ms,
+            clock,
+            symbols: HashMap::new(),
+        })
+    }
+
+    // Check a new mid, then add it to the window; returns whether the
+    // symbol is halted
+    #[pyo3(signature = (symbol, mid, ts=None))]
+    pub fn on_mid(&mut self, symbol: &str, mid: f64, ts: Option<i64>) -> PyResult<bool> {
+        let ts = clock::require(ts, &self.clock)?;
+        if mid <= 0.0 {
+            return Err(PyValueError::new_err("mid must be positive"));
+        }
+        let window_ms = self.window_ms;
+        let band = self.symbols.entry(symbol.to_owned()).or_default();
+        for q in [&mut band.lows, &mut band.highs] {
+            while q.front().is_some_and(|(t, _)| ts - t > window_ms) {
+                q.pop_front();
+            }
+        }
+        let band = &self.symbols[symbol];
+        let reason = self.breach(band, mid, ts);
+        let band = self.symbols.get_mut(symbol).expect("inserted above");
+        if let Some(reason) = reason {
+            band.tripped = Some((ts, reason));
+        }
+        while band.lows.back().is_some_and(|(_, m)| *m >= mid) {
+            band.lows.pop_back();
+        }
+        band.lows.push_back((ts, mid));
+        while band.highs.back().is_some_and(|(_, m)| *m <= mid) {
+            band.highs.pop_back();
+        }
+        band.highs.push_back((ts, mid));
+        Ok(self.halted_at(symbol, ts))
+    }
+
+    // External reference (index / mark) price for the deviation check
+    #[pyo3(signature = (symbol, price, ts=None))]
+    pub fn set_reference(&mut self, symbol: &str, price: f64, ts: Option<i64>) -> PyResult<()> {
+        let ts = clock::require(ts, &self.clock)?;
+        if price <= 0.0 {
+            return Err(PyValueError::new_err("reference price must be positive"));
+        }
+        self.symbols.entry(symbol.to_owned()).or_default().reference = Some((ts, price));
+        Ok(())
+    }
+
+    // Whether quoting on the symbol is halted at ts
+    #[pyo3(signature = (symbol, ts=None))]
+    pub fn is_halted(&self, symbol: &str, ts: Option<i64>) -> PyResult<bool> {
+        let ts = clock::require(ts, &self.clock)?;
+        Ok(self.halted_at(symbol, ts))
+    }
+
+    // Reason for the symbol's last breach, kept after the halt ends
+    pub fn reason(&self, symbol: &str) -> Option<String> {
+        self.symbols
+            .get(symbol)
+            .and_then(|b| b.tripped.as_ref())
+            .map(|(_, r)| r.clone())
+    }
+
+    #[pyo3(signature = (ts=None))]
+    pub fn halted_symbols(&self, ts: Option<i64>) -> PyResult<Vec<String>> {
+        let ts = clock::require(ts, &self.clock)?;
+        let mut out: Vec<String> = self
+            .symbols
+            .keys()
+            .filter(|s| self.halted_at(s, ts))
+            .cloned()
+            .collect();
+        out.sort();
+        Ok(out)
+    }
+
+    // Manual halt for cooldown_ms from ts
+    #[pyo3(signature = (symbol, reason, ts=None))]
+    pub fn halt(&mut self, symbol: &str, reason: &str, ts: Option<i64>) -> PyResult<()> {
+        let ts = clock::require(ts, &self.clock)?;
+        self.symbols.entry(symbol.to_owned()).or_default().tripped = Some((ts, reason.to_owned()));
+        Ok(())
+    }
+
+    // Lift the halt on one symbol or all; windows and references are kept
+    #[pyo3(signature = (symbol=None))]
+    pub fn reset(&mut self, symbol: Option<&str>) {
+        for (s, band) in self.symbols.iter_mut() {
+            if symbol.is_none_or(|sym| sym == s) {
+                band.tripped = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+// OHLCV bars from the trade stream. A bar closes once it reaches `size`:
+//   time    ms; bars are aligned to multiples of size and span
+//           [start, start + size). Intervals without trades emit no bar.
+//   tick    trade count
+//   volume  base quantity
+//   dollar  notional (price * qty)
+// Volume and dollar bars close at exactly `size`: a trade that overshoots is
+// split, the remainder opening the next bar.
+// Completed bars are rows of COLUMNS; time bars carry the interval bounds as
+// (start_ts, end_ts), other kinds the first and last trade timestamps.
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{arrays, Side};
+
+const COLUMNS: [&str; 10] = [
+    "start_ts",
+    "end_ts",
+    "open",
+    "high",
+    "low",
+    "close",
+    "volume",
+    "vwap",
+    "trades",
+    "buy_volume",
+];
+
+type BarRow = (i64, i64, f64, f64, f64, f64, f64, f64, u64, f64);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BarKind {
+    Time,
+    Tick,
+    Volume,
+    Dollar,
+}
+
+impl BarKind {
+    fn parse(s: &str) -> PyResult<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "time" => Ok(Self::Time),
+            "tick" => Ok(Self::Tick),
+            "volume" => Ok(Self::Volume),
+            "dollar" => Ok(Self::Dollar),
+            other => Err(PyValueError::new_err(format!(
+                "kind must be 'time', 'tick', 'volume' or 'dollar', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Time => "time",
+            Self::Tick => "tick",
+            Self::Volume => "volume",
+            Self::Dollar => "dollar",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bar {
+    start: i64,
+    end: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    notional: f64,
+    trades: u64,
+    buy_volume: f64,
+}
+
+impl Bar {
+    fn new(start: i64, end: i64, price: f64) -> Self {
+        Self {
+            start,
+            end,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+            notional: 0.0,
+            trades: 0,
+            buy_volume: 0.0,
+        }
+    }
+
+    fn add(&mut self, ts: i64, price: f64, qty: f64, side: Option<Side>) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += qty;
+        self.notional += price * qty;
+        self.trades += 1;
+        if side == Some(Side::Bid) {
+            self.buy_volume += qty;
+        }
+        self.end = self.end.max(ts);
+    }
+
+    fn row(&self) -> BarRow {
+        let vwap = if self.volume > 0.0 {
+            self.notional / self.volume
+        } else {
+            self.close
+        };
+        (
+            self.start,
+            self.end,
+            self.open,
+            self.high,
+            self.low,
+            self.close,
+            self.volume,
+            vwap,
+            self.trades,
+            self.buy_volume,
+        )
+    }
+}
+
+#[pyclass]
+pub struct BarBuilder {
+    kind: BarKind,
+    size: f64,
+    current: Option<Bar>,
+    completed: VecDeque<Bar>,
+}
+
+impl BarBuilder {
+    fn close_current(&mut self) {
+        if let Some(bar) = self.current.take() {
+            self.completed.push_back(bar);
+        }
+    }
+}
+
+#[pymethods]
+impl BarBuilder {
+    #[classattr]
+    const COLUMNS: [&'static str; 10] = COLUMNS;
+
+    #[new]
+    #[pyo3(signature = (kind="time", size=60_000.0))]
+    pub fn new(kind: &str, size: f64) -> PyResult<Self> {
+        let kind = BarKind::parse(kind)?;
+        if !(size > 0.0 && size.is_finite()) {
+            return Err(PyValueError::new_err("size must be positive"));
+        }
+        if kind == BarKind::Time && size.fract() != 0.0 {
+            return Err(PyValueError::new_err("time bar size must be whole ms"));
+        }
+        Ok(Self {
+            kind,
+            size,
+            current: None,
+            completed: VecDeque::new(),
+        })
+    }
+
+    // Add a trade (side optional, "buy"/"sell"); returns how many bars it
+    // completed
+    #[pyo3(signature = (ts, price, qty, side=None))]
+    pub fn add_trade(
+        &mut self,
+        ts: i64,
+        price: f64,
+        qty: f64,
+        side: Option<&str>,
+    ) -> PyResult<usize> {
+        if !(price > 0.0 && qty >= 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "price must be positive and qty non-negative, got {} x {}",
+                price, qty
+            )));
+        }
+        let side = side.map(Side::parse).transpose()?;
+        let before = self.completed.len();
+        if self.kind == BarKind::Time {
+            self.advance(ts);
+            let size = self.size as i64;
+            let bar = self.current.get_or_insert_with(|| {
+                let start = ts - ts.rem_euclid(size);
+                Bar::new(start, start + size, price)
+            });
+            // end_ts stays the interval bound, whatever the trade order
+            let end = bar.end;
+            bar.add(ts, price, qty, side);
+            bar.end = end;
+            return Ok(self.completed.len() - before);
+        }
+        if self.kind == BarKind::Tick {
+            self.current
+                .get_or_insert_with(|| Bar::new(ts, ts, price))
+                .add(ts, price, qty, side);
+            if self.current.is_some_and(|b| b.trades as f64 >= self.size) {
+                self.close_current();
+            }
+            return Ok(self.completed.len() - before);
+        }
+        // Volume / dollar: split the trade at bar boundaries
+        let mut left = qty;
+        loop {
+            let bar = self.current.get_or_insert_with(|| Bar::new(ts, ts, price));
+            let room = match self.kind {
+                BarKind::Volume => self.size - bar.volume,
+                _ => (self.size - bar.notional) / price,
+            };
+            let take = left.min(room);
+            bar.add(ts, price, take, side);
+            left -= take;
+            if take >= room {
+                self.close_current();
+            }
+            // A split remainder is one more print in the next bar
+            if left <= qty * 1e-12 {
+                break;
+            }
+        }
+        Ok(self.completed.len() - before)
+    }
+
+    // Close the open time bar if `ts` is past its interval, so quiet markets
+    // still produce bars; a no-op for other kinds. Returns bars completed.
+    pub fn advance(&mut self, ts: i64) -> usize {
+        let before = self.completed.len();
+        if self.kind == BarKind::Time && self.current.is_some_and(|b| ts >= b.end) {
+            self.close_current();
+        }
+        self.completed.len() - before
+    }
+
+    // Force-close the open bar, e.g. at the end of a session
+    pub fn flush(&mut self) -> usize {
+        let before = self.completed.len();
+        self.close_current();
+        self.completed.len() - before
+    }
+
+    // Drain completed bars as tuples in COLUMNS order
+    pub fn take_bars(&mut self) -> Vec<BarRow> {
+        self.completed.drain(..).map(|b| b.row()).collect()
+    }
+
+    // Drain completed bars as a float64 numpy array of shape (N, 10)
+    pub fn take_array<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let flat: Vec<f64> = self
+            .completed
+            .drain(..)
+            .flat_map(|b| {
+                let r = b.row();
+                [
+                    r.0 as f64, r.1 as f64, r.2, r.3, r.4, r.5, r.6, r.7, r.8 as f64, r.9,
+                ]
+            })
+            .collect();
+        arrays::rows_to_ndarray(py, &flat, COLUMNS.len())
+    }
+
+    // The bar still being built, if any
+    pub fn current(&self) -> Option<BarRow> {
+        self.current.map(|b| b.row())
+    }
+
+    #[getter]
+    fn kind(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    #[getter]
+    fn size(&self) -> f64 {
+        self.size
+    }
+
+    pub fn reset(&mut self) {
+        self.current = None;
+        self.completed.clear();
+    }
+
+    // Completed bars not yet taken
+    fn __len__(&self) -> usize {
+        self.completed.len()
+    }
+}
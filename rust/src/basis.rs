@@ -0,0 +1,185 @@
+// Spot/perp basis and funding for perpetual market making.
+//   basis_bps             (perp - spot) / spot * 1e4
+//   annualized_basis      basis as a fraction of spot, scaled by funding
+//                         periods per year (the premium is what funding
+//                         charges away each interval)
+//   annualized_funding    funding rate per interval * periods per year
+//   fair_value            spot * (1 + rate * tau), tau the fraction of the
+//                         interval left until the next funding (1 if unknown):
+//                         a long perp expects to pay that much before then
+//   premium_bps           (perp - fair_value) / fair_value * 1e4, optionally
+//                         EWMA-smoothed over halflife_ms
+//   skew_bps              -skew_factor * premium_bps clamped to max_skew_bps:
+//                         a rich perp shifts quotes down to lean short
+// Rates are per funding interval as published (0.0001 = 1bp per 8h).
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::ewma::Ewma;
+
+const HOURS_PER_YEAR: f64 = 365.0 * 24.0;
+
+#[pyclass]
+pub struct BasisTracker {
+    interval_ms: f64,
+    skew_factor: f64,
+    max_skew_bps: f64,
+    spot: Option<f64>,
+    perp: Option<f64>,
+    last_ts: Option<i64>,
+    funding_rate: Option<f64>,
+    next_funding_ts: Option<i64>,
+    smoother: Option<Ewma>,
+    premium: Option<f64>,
+}
+
+impl BasisTracker {
+    fn periods_per_year(&self) -> f64 {
+        HOURS_PER_YEAR * 3_600_000.0 / self.interval_ms
+    }
+
+    // Fraction of the funding interval left as of the last update
+    fn tau(&self) -> f64 {
+        match (self.next_funding_ts, self.last_ts) {
+            (Some(next), Some(ts)) => ((next - ts) as f64 / self.interval_ms).clamp(0.0, 1.0),
+            _ => 1.0,
+        }
+    }
+
+    fn raw_premium(&self) -> Option<f64> {
+        let fair = self.fair_value()?;
+        Some((self.perp? - fair) / fair * 10_000.0)
+    }
+}
+
+#[pymethods]
+impl BasisTracker {
+    #[new]
+    #[pyo3(signature = (funding_interval_hours=8.0, skew_factor=0.5, max_skew_bps=10.0, halflife_ms=None))]
+    pub fn new(
+        funding_interval_hours: f64,
+        skew_factor: f64,
+        max_skew_bps: f64,
+        halflife_ms: Option<f64>,
+    ) -> PyResult<Self> {
+        if !(funding_interval_hours > 0.0 && funding_interval_hours.is_finite()) {
+            return Err(PyValueError::new_err(
+                "funding_interval_hours must be positive",
+            ));
+        }
+        if skew_factor.is_nan() || skew_factor < 0.0 || max_skew_bps.is_nan() || max_skew_bps < 0.0
+        {
+            return Err(PyValueError::new_err(
+                "skew_factor and max_skew_bps must be non-negative",
+            ));
+        }
+        Ok(Self {
+            interval_ms: funding_interval_hours * 3_600_000.0,
+            skew_factor,
+            max_skew_bps,
+            spot: None,
+            perp: None,
+            last_ts: None,
+            funding_rate: None,
+            next_funding_ts: None,
+            smoother: halflife_ms
+                .map(|h| Ewma::new(None, None, Some(h), None))
+                .transpose()?,
+            premium: None,
+        })
+    }
+
+    // Record spot and perp mids; returns the suggested skew in bps once a
+    // funding rate is known. ts (ms) is required with halflife_ms.
+    #[pyo3(signature = (spot_mid, perp_mid, ts=None))]
+    pub fn update(
+        &mut self,
+        spot_mid: f64,
+        perp_mid: f64,
+        ts: Option<i64>,
+    ) -> PyResult<Option<f64>> {
+        if !(spot_mid > 0.0 && perp_mid > 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "mids must be positive, got {} / {}",
+                spot_mid, perp_mid
+            )));
+        }
+        (self.spot, self.perp) = (Some(spot_mid), Some(perp_mid));
+        self.last_ts = ts.or(self.last_ts);
+        if let Some(raw) = self.raw_premium() {
+            self.premium = Some(match self.smoother.as_mut() {
+                Some(ewma) => ewma.add(raw, ts)?,
+                None => raw,
+            });
+        }
+        Ok(self.skew_bps())
+    }
+
+    // Funding rate per interval, with the next funding time (ms) if known
+    #[pyo3(signature = (rate, next_funding_ts=None))]
+    pub fn set_funding(&mut self, rate: f64, next_funding_ts: Option<i64>) -> PyResult<()> {
+        if !rate.is_finite() {
+            return Err(PyValueError::new_err(format!(
+                "funding rate must be finite, got {}",
+                rate
+            )));
+        }
+        self.funding_rate = Some(rate);
+        self.next_funding_ts = next_funding_ts;
+        Ok(())
+    }
+
+    #[getter]
+    pub fn basis(&self) -> Option<f64> {
+        Some(self.perp? - self.spot?)
+    }
+
+    #[getter]
+    pub fn basis_bps(&self) -> Option<f64> {
+        Some(self.basis()? / self.spot? * 10_000.0)
+    }
+
+    #[getter]
+    pub fn annualized_basis(&self) -> Option<f64> {
+        Some(self.basis()? / self.spot? * self.periods_per_year())
+    }
+
+    #[getter]
+    pub fn funding_rate(&self) -> Option<f64> {
+        self.funding_rate
+    }
+
+    #[getter]
+    pub fn annualized_funding(&self) -> Option<f64> {
+        Some(self.funding_rate? * self.periods_per_year())
+    }
+
+    #[getter]
+    pub fn fair_value(&self) -> Option<f64> {
+        Some(self.spot? * (1.0 + self.funding_rate? * self.tau()))
+    }
+
+    // Perp premium over fair value in bps (smoothed when halflife_ms is set)
+    #[getter]
+    pub fn premium_bps(&self) -> Option<f64> {
+        self.premium
+    }
+
+    #[getter]
+    pub fn skew_bps(&self) -> Option<f64> {
+        let skew = -self.skew_factor * self.premium?;
+        Some(skew.clamp(-self.max_skew_bps, self.max_skew_bps))
+    }
+
+    pub fn reset(&mut self) {
+        self.spot = None;
+        self.perp = None;
+        self.last_ts = None;
+        self.funding_rate = None;
+        self.next_funding_ts = None;
+        self.premium = None;
+        if let Some(ewma) = self.smoother.as_mut() {
+            ewma.reset();
+        }
+    }
+}
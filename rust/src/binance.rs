@@ -0,0 +1,203 @@
+// Binance depth-diff synchronization:
+// buffer stream diffs, take a REST snapshot (lastUpdateId), drop diffs with
+// u <= lastUpdateId, require the first diff to straddle lastUpdateId + 1 and
+// every following diff to continue the chain (U == prev u + 1, or pu == prev u
+// on futures). Any break drops the sync and waits for a fresh snapshot.
+use std::collections::VecDeque;
+
+use pyo3::prelude::*;
+
+use crate::{L2Book, Levels};
+
+struct DepthDiff {
+    first_update_id: u64,
+    final_update_id: u64,
+    prev_final_update_id: Option<u64>,
+    bids: Levels,
+    asks: Levels,
+}
+
+#[pyclass]
+pub struct BinanceBookSync {
+    book: Py<L2Book>,
+    buffer: VecDeque<DepthDiff>,
+    max_buffer: usize,
+    synced: bool,
+    // No diff applied since the snapshot yet: the next one may straddle it
+    fresh_snapshot: bool,
+    resync_count: u64,
+}
+
+impl BinanceBookSync {
+    fn continues(last: u64, diff: &DepthDiff) -> bool {
+        match diff.prev_final_update_id {
+            Some(pu) => pu == last,
+            None => diff.first_update_id == last + 1,
+        }
+    }
+
+    // First diff on top of a snapshot: U <= lastUpdateId + 1 <= u
+    fn straddles(last: u64, diff: &DepthDiff) -> bool {
+        diff.first_update_id <= last + 1 && diff.final_update_id > last
+    }
+
+    // Through L2Book.delta, so the book's cross_policy and debug_checks
+    // apply as for any other delta. The chain was checked by the caller, so
+    // the diff follows whatever the book holds; false only if the book was
+    // flagged for resync behind our back.
+    fn apply(book: &mut L2Book, diff: DepthDiff) -> PyResult<bool> {
+        let last = book.last_update_id;
+        book.delta(diff.bids, diff.asks, Some(diff.final_update_id), last, None)
+    }
+
+    fn lose_sync(&mut self) {
+        self.synced = false;
+        self.resync_count += 1;
+        self.buffer.clear();
+    }
+}
+
+#[pymethods]
+impl BinanceBookSync {
+    #[new]
+    #[pyo3(signature = (book=None, max_buffer=10_000))]
+    pub fn new(py: Python<'_>, book: Option<Py<L2Book>>, max_buffer: usize) -> PyResult<Self> {
+        let book = match book {
+            Some(b) => b,
+            None => Py::new(py, L2Book::default())?,
+        };
+        Ok(Self {
+            book,
+            buffer: VecDeque::new(),
+            max_buffer,
+            synced: false,
+            fresh_snapshot: false,
+            resync_count: 0,
+        })
+    }
+
+    // Feed one depthUpdate event (U, u, b, a[, pu]). While unsynced the diff is
+    // buffered; once synced it is applied immediately. Returns False when the
+    // diff broke the update chain and a new snapshot is required.
+    #[pyo3(signature = (first_update_id, final_update_id, bids, asks, prev_final_update_id=None))]
+    pub fn buffer_diff(
+        &mut self,
+        py: Python<'_>,
+        first_update_id: u64,
+        final_update_id: u64,
+        bids: Levels,
+        asks: Levels,
+        prev_final_update_id: Option<u64>,
+    ) -> PyResult<bool> {
+        let diff = DepthDiff {
+            first_update_id,
+            final_update_id,
+            prev_final_update_id,
+            bids,
+            asks,
+        };
+        if !self.synced {
+            if self.buffer.len() >= self.max_buffer {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(diff);
+            return Ok(true);
+        }
+        let mut book = self.book.borrow_mut(py);
+        let last = book.last_update_id.unwrap_or(0);
+        if diff.final_update_id <= last {
+            return Ok(true);
+        }
+        let follows = if self.fresh_snapshot {
+            Self::straddles(last, &diff)
+        } else {
+            Self::continues(last, &diff)
+        };
+        if !follows {
+            drop(book);
+            self.lose_sync();
+            return Ok(false);
+        }
+        self.fresh_snapshot = false;
+        if !Self::apply(&mut book, diff)? {
+            drop(book);
+            self.lose_sync();
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    // Load the REST snapshot and replay buffered diffs on top of it.
+    // Returns is_synced; False means the snapshot predates the buffered
+    // stream (or the buffer has a hole) and a newer snapshot is needed.
+    pub fn apply_snapshot(
+        &mut self,
+        py: Python<'_>,
+        last_update_id: u64,
+        bids: Levels,
+        asks: Levels,
+    ) -> PyResult<bool> {
+        let mut book = self.book.borrow_mut(py);
+        book.load_snapshot(bids, asks, Some(last_update_id))?;
+        self.synced = false;
+
+        while self
+            .buffer
+            .front()
+            .is_some_and(|d| d.final_update_id <= last_update_id)
+        {
+            self.buffer.pop_front();
+        }
+        if let Some(first) = self.buffer.front() {
+            if first.first_update_id > last_update_id + 1 {
+                // Snapshot is older than the first buffered diff
+                return Ok(false);
+            }
+        }
+
+        let mut last = last_update_id;
+        let mut first = true;
+        while let Some(diff) = self.buffer.pop_front() {
+            if !first && !Self::continues(last, &diff) {
+                drop(book);
+                self.lose_sync();
+                return Ok(false);
+            }
+            first = false;
+            last = diff.final_update_id;
+            if !Self::apply(&mut book, diff)? {
+                drop(book);
+                self.lose_sync();
+                return Ok(false);
+            }
+        }
+        self.synced = true;
+        self.fresh_snapshot = first;
+        Ok(true)
+    }
+
+    pub fn reset(&mut self) {
+        self.synced = false;
+        self.buffer.clear();
+    }
+
+    #[getter]
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    #[getter]
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    #[getter]
+    pub fn resync_count(&self) -> u64 {
+        self.resync_count
+    }
+
+    #[getter]
+    pub fn book(&self, py: Python<'_>) -> Py<L2Book> {
+        self.book.clone_ref(py)
+    }
+}
@@ -0,0 +1,212 @@
+// Fair value as a weighted blend of price sources: the book's own mid and
+// microprice, an external index, other venues' mids. Each source carries a
+// base weight that decays with the age of its last price,
+//   w = weight * 0.5^(age / halflife_ms)
+// and drops out past max_age_ms; the blend is normalized over what remains.
+// Book-backed sources read their L2Book on refresh(); others are pushed with
+// update(). Per-source halflife / max_age override the blender defaults; an
+// infinite halflife turns decay off for that source.
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::L2Book;
+
+struct Source {
+    name: String,
+    weight: f64,
+    book: Option<(Py<L2Book>, String)>,
+    halflife_ms: Option<f64>,
+    max_age_ms: Option<i64>,
+    // Last (ts, price)
+    last: Option<(i64, f64)>,
+}
+
+#[pyclass]
+pub struct FairValueBlender {
+    sources: Vec<Source>,
+    halflife_ms: Option<f64>,
+    max_age_ms: Option<i64>,
+}
+
+impl FairValueBlender {
+    fn source(&mut self, name: &str) -> PyResult<&mut Source> {
+        self.sources
+            .iter_mut()
+            .find(|s| s.name == name)
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))
+    }
+
+    // (name, effective weight, price) of the sources live at `ts`
+    fn live(&self, ts: i64) -> Vec<(&str, f64, f64)> {
+        self.sources
+            .iter()
+            .filter_map(|s| {
+                let (at, price) = s.last?;
+                let age = (ts - at).max(0);
+                if s.max_age_ms
+                    .or(self.max_age_ms)
+                    .is_some_and(|max| age > max)
+                {
+                    return None;
+                }
+                let w = match s.halflife_ms.or(self.halflife_ms) {
+                    Some(h) => s.weight * 0.5f64.powf(age as f64 / h),
+                    None => s.weight,
+                };
+                (w > 0.0).then_some((s.name.as_str(), w, price))
+            })
+            .collect()
+    }
+}
+
+fn check_positive(name: &str, v: Option<f64>) -> PyResult<()> {
+    match v {
+        Some(v) if v.is_nan() || v <= 0.0 => Err(PyValueError::new_err(format!(
+            "{} must be positive, got {}",
+            name, v
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[pymethods]
+impl FairValueBlender {
+    #[new]
+    #[pyo3(signature = (halflife_ms=Some(1_000.0), max_age_ms=None))]
+    pub fn new(halflife_ms: Option<f64>, max_age_ms: Option<i64>) -> PyResult<Self> {
+        check_positive("halflife_ms", halflife_ms)?;
+        Ok(Self {
+            sources: Vec::new(),
+            halflife_ms,
+            max_age_ms,
+        })
+    }
+
+    // Register (or replace) a source. With `book`, refresh() reads its
+    // `reference` price ("mid", "microprice", ...).
+    #[pyo3(signature = (name, weight, book=None, reference="mid", halflife_ms=None, max_age_ms=None))]
+    pub fn add_source(
+        &mut self,
+        name: &str,
+        weight: f64,
+        book: Option<Bound<'_, L2Book>>,
+        reference: &str,
+        halflife_ms: Option<f64>,
+        max_age_ms: Option<i64>,
+    ) -> PyResult<()> {
+        if !(weight >= 0.0 && weight.is_finite()) {
+            return Err(PyValueError::new_err(format!(
+                "weight must be finite and non-negative, got {}",
+                weight
+            )));
+        }
+        check_positive("halflife_ms", halflife_ms)?;
+        if let Some(book) = &book {
+            book.borrow().reference_price(reference)?;
+        }
+        let new = Source {
+            name: name.to_string(),
+            weight,
+            book: book.map(|b| (b.unbind(), reference.to_string())),
+            halflife_ms,
+            max_age_ms,
+            last: None,
+        };
+        match self.sources.iter_mut().find(|s| s.name == name) {
+            Some(s) => *s = new,
+            None => self.sources.push(new),
+        }
+        Ok(())
+    }
+
+    pub fn remove_source(&mut self, name: &str) -> bool {
+        let before = self.sources.len();
+        self.sources.retain(|s| s.name != name);
+        self.sources.len() < before
+    }
+
+    pub fn set_weight(&mut self, name: &str, weight: f64) -> PyResult<()> {
+        if !(weight >= 0.0 && weight.is_finite()) {
+            return Err(PyValueError::new_err(format!(
+                "weight must be finite and non-negative, got {}",
+                weight
+            )));
+        }
+        self.source(name)?.weight = weight;
+        Ok(())
+    }
+
+    // Push a price for a source, e.g. an index tick
+    pub fn update(&mut self, name: &str, price: f64, ts: i64) -> PyResult<()> {
+        if !(price > 0.0 && price.is_finite()) {
+            return Err(PyValueError::new_err(format!(
+                "price must be positive, got {}",
+                price
+            )));
+        }
+        self.source(name)?.last = Some((ts, price));
+        Ok(())
+    }
+
+    // Read every book-backed source; one-sided books keep their last price
+    pub fn refresh(&mut self, py: Python<'_>, ts: i64) -> PyResult<()> {
+        for s in &mut self.sources {
+            if let Some((book, reference)) = &s.book {
+                if let Some(price) = book.borrow(py).reference_price(reference)? {
+                    s.last = Some((ts, price));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Blend as of `ts`; None when no source is live
+    pub fn fair_value(&self, ts: i64) -> Option<f64> {
+        let (wsum, wpsum) = self
+            .live(ts)
+            .iter()
+            .fold((0.0, 0.0), |(w, wp), (_, sw, p)| (w + sw, wp + sw * p));
+        (wsum > 0.0).then(|| wpsum / wsum)
+    }
+
+    // refresh(ts) then fair_value(ts), once per tick
+    pub fn tick(&mut self, py: Python<'_>, ts: i64) -> PyResult<Option<f64>> {
+        self.refresh(py, ts)?;
+        Ok(self.fair_value(ts))
+    }
+
+    // Normalized weight of each live source at `ts`
+    pub fn weights(&self, ts: i64) -> HashMap<String, f64> {
+        let live = self.live(ts);
+        let total: f64 = live.iter().map(|(_, w, _)| w).sum();
+        live.into_iter()
+            .map(|(name, w, _)| (name.to_string(), w / total))
+            .collect()
+    }
+
+    // Last price of a source, regardless of age
+    pub fn price(&self, name: &str) -> Option<f64> {
+        self.sources
+            .iter()
+            .find(|s| s.name == name)?
+            .last
+            .map(|(_, p)| p)
+    }
+
+    pub fn sources(&self) -> Vec<String> {
+        self.sources.iter().map(|s| s.name.clone()).collect()
+    }
+
+    // Forget all prices; sources stay registered
+    pub fn reset(&mut self) {
+        for s in &mut self.sources {
+            s.last = None;
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.sources.len()
+    }
+}
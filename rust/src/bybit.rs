@@ -0,0 +1,142 @@
+// Bybit V5 public orderbook stream (orderbook.{depth}.{symbol})
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::events::BookUpdate;
+use crate::json::{self, Value};
+use crate::{L2Book, Levels};
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub struct BybitBookMessage {
+    pub topic: String,
+    // "snapshot" or "delta"
+    pub kind: String,
+    pub symbol: String,
+    pub update_id: u64,
+    pub seq: Option<u64>,
+    pub ts: Option<i64>,
+    pub applied: bool,
+}
+
+#[pymethods]
+impl BybitBookMessage {
+    // As a normalized BookUpdate (see normalized.rs). The levels went into
+    // the book and are not kept, so bids and asks are empty; DepthParser
+    // keeps them.
+    pub fn normalize(&self) -> BookUpdate {
+        BookUpdate {
+            exchange: Some("bybit".to_string()),
+            symbol: Some(self.symbol.clone()),
+            // A delta with u == 1 replaced the book, see BookMessage
+            kind: if self.kind == "snapshot" || self.update_id == 1 {
+                "snapshot"
+            } else {
+                "delta"
+            },
+            update_id: Some(self.update_id),
+            ts: self.ts,
+            applied: self.applied,
+            ..Default::default()
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Default)]
+pub struct BybitBookParser {}
+
+// Orderbook message fields, before anything is applied
+pub(crate) struct BookMessage {
+    pub topic: String,
+    pub kind: String,
+    pub symbol: String,
+    pub update_id: u64,
+    pub seq: Option<u64>,
+    pub ts: Option<i64>,
+    pub bids: Levels,
+    pub asks: Levels,
+}
+
+impl BookMessage {
+    // A delta with u == 1 means Bybit restarted the service: it replaces the
+    // book like a snapshot
+    pub fn is_snapshot(&self) -> bool {
+        self.kind == "snapshot" || (self.kind == "delta" && self.update_id == 1)
+    }
+}
+
+pub(crate) fn parse_book_message(root: &Value) -> PyResult<BookMessage> {
+    let topic = root
+        .get("topic")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    if !topic.starts_with("orderbook.") {
+        return Err(PyValueError::new_err(format!(
+            "not an orderbook message: topic '{}'",
+            topic
+        )));
+    }
+    let kind = root
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    if kind != "snapshot" && kind != "delta" {
+        return Err(PyValueError::new_err(format!(
+            "unknown message type '{}'",
+            kind
+        )));
+    }
+    let data = root
+        .get("data")
+        .ok_or_else(|| PyValueError::new_err("missing 'data'"))?;
+    let update_id = data
+        .get("u")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| PyValueError::new_err("missing update id 'u'"))?;
+    Ok(BookMessage {
+        topic,
+        kind,
+        symbol: data
+            .get("s")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        update_id,
+        seq: data.get("seq").and_then(Value::as_u64),
+        ts: root.get("ts").and_then(Value::as_i64),
+        bids: json::levels(data.get("b")).map_err(PyValueError::new_err)?,
+        asks: json::levels(data.get("a")).map_err(PyValueError::new_err)?,
+    })
+}
+
+#[pymethods]
+impl BybitBookParser {
+    #[new]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    // Parse a raw orderbook message (str/bytes) and apply it to `book`
+    pub fn apply(&self, book: &mut L2Book, msg: &Bound<'_, PyAny>) -> PyResult<BybitBookMessage> {
+        let text = json::message_text(msg)?;
+        let msg = parse_book_message(&json::parse_py(&text)?)?;
+        let applied = if msg.is_snapshot() {
+            book.load_snapshot(msg.bids, msg.asks, Some(msg.update_id))?;
+            true
+        } else {
+            book.delta(msg.bids, msg.asks, Some(msg.update_id), None, None)?
+        };
+        Ok(BybitBookMessage {
+            topic: msg.topic,
+            kind: msg.kind,
+            symbol: msg.symbol,
+            update_id: msg.update_id,
+            seq: msg.seq,
+            ts: msg.ts,
+            applied,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+// Exchange book checksums (CRC32 over top-of-book level strings).
+//   OKX:    "bidP:bidS:askP:askS:..." interleaved over 25 levels, signed crc32
+//   Kraken: asks then bids over 10 levels, each price/size with '.' removed
+//           and leading zeros stripped, unsigned crc32
+// Exchanges hash their own decimal strings, so pass the instrument's price
+// and size decimals when the exchange pads with trailing zeros.
+
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                CRC32_POLY ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc = CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+fn fmt_num(v: f64, decimals: Option<usize>) -> String {
+    match decimals {
+        Some(d) => format!("{:.*}", d, v),
+        None => format!("{}", v),
+    }
+}
+
+pub fn okx(
+    bids: &[(f64, f64)],
+    asks: &[(f64, f64)],
+    price_decimals: Option<usize>,
+    size_decimals: Option<usize>,
+) -> i64 {
+    let mut parts = Vec::with_capacity(4 * bids.len().max(asks.len()));
+    for i in 0..bids.len().max(asks.len()) {
+        for side in [bids, asks] {
+            if let Some(&(p, s)) = side.get(i) {
+                parts.push(fmt_num(p, price_decimals));
+                parts.push(fmt_num(s, size_decimals));
+            }
+        }
+    }
+    crc32(parts.join(":").as_bytes()) as i32 as i64
+}
+
+fn kraken_digits(v: f64, decimals: Option<usize>) -> String {
+    let s = fmt_num(v, decimals).replace('.', "");
+    s.trim_start_matches('0').to_string()
+}
+
+pub fn kraken(
+    bids: &[(f64, f64)],
+    asks: &[(f64, f64)],
+    price_decimals: Option<usize>,
+    size_decimals: Option<usize>,
+) -> i64 {
+    let mut buf = String::new();
+    for &(p, s) in asks.iter().chain(bids.iter()) {
+        buf.push_str(&kraken_digits(p, price_decimals));
+        buf.push_str(&kraken_digits(s, size_decimals));
+    }
+    crc32(buf.as_bytes()) as i64
+}
@@ -0,0 +1,269 @@
+// Time sources. A Clock is either the wall clock or a replay clock whose time
+// is shared (Arc) between whatever drives it - the Backtester or a journal
+// replay - and the analytics reading it, so time windows, decay and
+// staleness follow replayed timestamps and backtests are reproducible.
+// Analytics built without a clock keep requiring explicit timestamps.
+//
+// For latency accounting, monotonic_ns() reads the monotonic clock in
+// nanoseconds offset to the Unix epoch once per process: it never jumps with
+// NTP adjustments, yet stays comparable with exchange timestamps. Receive
+// times of feed events and book updates use the same scale, and ClockSkew
+// estimates how far the local clock runs ahead of an exchange's.
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+pub fn wall_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+// (monotonic instant, wall time at that instant in ns)
+fn anchor() -> &'static (Instant, i64) {
+    static ANCHOR: OnceLock<(Instant, i64)> = OnceLock::new();
+    ANCHOR.get_or_init(|| {
+        let wall = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as i64);
+        (Instant::now(), wall)
+    })
+}
+
+// An Instant on the monotonic_ns() scale
+pub fn instant_ns(at: Instant) -> i64 {
+    let (origin, wall) = *anchor();
+    if at >= origin {
+        wall + (at - origin).as_nanos() as i64
+    } else {
+        wall - (origin - at).as_nanos() as i64
+    }
+}
+
+pub fn monotonic_ns() -> i64 {
+    instant_ns(Instant::now())
+}
+
+// Epoch-aligned monotonic nanoseconds; see the module header
+#[pyfunction(name = "monotonic_ns")]
+pub fn py_monotonic_ns() -> i64 {
+    monotonic_ns()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Clock(Option<Arc<AtomicI64>>);
+
+impl Clock {
+    pub fn replay(start_ms: i64) -> Self {
+        Self(Some(Arc::new(AtomicI64::new(start_ms))))
+    }
+
+    pub fn now_ms(&self) -> i64 {
+        self.0
+            .as_ref()
+            .map_or_else(wall_ms, |t| t.load(Ordering::Acquire))
+    }
+
+    // No-op on the wall clock
+    pub fn set(&self, ts: i64) {
+        if let Some(t) = &self.0 {
+            t.store(ts, Ordering::Release);
+        }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.0.is_some()
+    }
+
+    // On the monotonic_ns() scale for the wall clock
+    pub fn now_ns(&self) -> i64 {
+        self.0
+            .as_ref()
+            .map_or_else(monotonic_ns, |t| t.load(Ordering::Acquire) * 1_000_000)
+    }
+}
+
+// An explicit timestamp wins; otherwise the clock's time, if there is one
+pub fn resolve(ts: Option<i64>, clock: &Option<Clock>) -> Option<i64> {
+    ts.or_else(|| clock.as_ref().map(Clock::now_ms))
+}
+
+pub fn require(ts: Option<i64>, clock: &Option<Clock>) -> PyResult<i64> {
+    resolve(ts, clock).ok_or_else(|| PyValueError::new_err("ts is required without a clock"))
+}
+
+impl<'py> FromPyObject<'py> for Clock {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        Ok(ob.downcast::<ReplayClock>()?.get().clock.clone())
+    }
+}
+
+#[pyclass(frozen)]
+pub struct ReplayClock {
+    pub(crate) clock: Clock,
+}
+
+impl ReplayClock {
+    pub fn from_clock(clock: Clock) -> Self {
+        Self { clock }
+    }
+}
+
+#[pymethods]
+impl ReplayClock {
+    #[new]
+    #[pyo3(signature = (start_ms=0))]
+    pub fn new(start_ms: i64) -> Self {
+        Self {
+            clock: Clock::replay(start_ms),
+        }
+    }
+
+    // The system clock, for live code sharing an interface with replay
+    #[staticmethod]
+    pub fn wall() -> Self {
+        Self {
+            clock: Clock::default(),
+        }
+    }
+
+    pub fn now_ms(&self) -> i64 {
+        self.clock.now_ms()
+    }
+
+    // Jump to `ts`; replay time may move backwards (e.g. a rerun)
+    pub fn set(&self, ts: i64) -> PyResult<()> {
+        if !self.clock.is_replay() {
+            return Err(PyValueError::new_err("the wall clock cannot be set"));
+        }
+        self.clock.set(ts);
+        Ok(())
+    }
+
+    // Move forward by `ms`; returns the new time
+    pub fn advance(&self, ms: i64) -> PyResult<i64> {
+        if ms < 0 {
+            return Err(PyValueError::new_err("ms must be non-negative"));
+        }
+        let now = self.clock.now_ms() + ms;
+        self.set(now)?;
+        Ok(now)
+    }
+
+    #[getter]
+    pub fn is_replay(&self) -> bool {
+        self.clock.is_replay()
+    }
+
+    fn __repr__(&self) -> String {
+        if self.clock.is_replay() {
+            format!("ReplayClock(now_ms={})", self.clock.now_ms())
+        } else {
+            "ReplayClock.wall()".to_owned()
+        }
+    }
+}
+
+// Rolling minimum of recv - exchange timestamp over the last `window`
+// messages. The minimum is the skew plus the fastest one-way latency, which
+// cannot be separated without a round trip; it is what maps an exchange
+// timestamp to the earliest local time the message could have arrived, and
+// what each message's delay is measured against.
+#[pyclass]
+pub struct ClockSkew {
+    window: usize,
+    // (seq, delay ns), increasing delays for the rolling min
+    min_queue: VecDeque<(u64, i64)>,
+    seen: u64,
+    last_delay: Option<i64>,
+}
+
+impl ClockSkew {
+    fn delay(exchange_ts_ms: i64, recv_ns: Option<i64>) -> i64 {
+        recv_ns.unwrap_or_else(monotonic_ns) - exchange_ts_ms * 1_000_000
+    }
+}
+
+#[pymethods]
+impl ClockSkew {
+    #[new]
+    #[pyo3(signature = (window=1000))]
+    pub fn new(window: usize) -> PyResult<Self> {
+        if window == 0 {
+            return Err(PyValueError::new_err("window must be positive"));
+        }
+        Ok(Self {
+            window,
+            min_queue: VecDeque::new(),
+            seen: 0,
+            last_delay: None,
+        })
+    }
+
+    // Record a message stamped exchange_ts_ms by the venue and received at
+    // recv_ns (default now); returns its raw delay in ms
+    #[pyo3(signature = (exchange_ts_ms, recv_ns=None))]
+    pub fn observe(&mut self, exchange_ts_ms: i64, recv_ns: Option<i64>) -> f64 {
+        let delay = Self::delay(exchange_ts_ms, recv_ns);
+        let seq = self.seen;
+        self.seen += 1;
+        while self.min_queue.back().is_some_and(|(_, d)| *d >= delay) {
+            self.min_queue.pop_back();
+        }
+        self.min_queue.push_back((seq, delay));
+        while self
+            .min_queue
+            .front()
+            .is_some_and(|(s, _)| *s + self.window as u64 <= seq)
+        {
+            self.min_queue.pop_front();
+        }
+        self.last_delay = Some(delay);
+        delay as f64 / 1e6
+    }
+
+    // Local clock minus exchange clock, fastest latency included; None
+    // before the first message
+    #[getter]
+    pub fn offset_ms(&self) -> Option<f64> {
+        self.min_queue.front().map(|(_, d)| *d as f64 / 1e6)
+    }
+
+    // Delay of the last message beyond the fastest in the window, i.e. its
+    // queueing and network jitter
+    #[getter]
+    pub fn excess_ms(&self) -> Option<f64> {
+        let (_, min) = self.min_queue.front()?;
+        self.last_delay.map(|d| (d - min) as f64 / 1e6)
+    }
+
+    // An exchange timestamp on the monotonic_ns() scale
+    pub fn to_local_ns(&self, exchange_ts_ms: i64) -> Option<i64> {
+        let (_, min) = self.min_queue.front()?;
+        Some(exchange_ts_ms * 1_000_000 + min)
+    }
+
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn reset(&mut self) {
+        self.min_queue.clear();
+        self.seen = 0;
+        self.last_delay = None;
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ClockSkew(window={}, count={}, offset_ms={:?})",
+            self.window,
+            self.seen,
+            self.offset_ms()
+        )
+    }
+}
@@ -0,0 +1,244 @@
+// One ladder over several venues' L2Books, each level attributed to its
+// venue. The books are held by reference and read on every query, so the
+// merged view is always current without copying updates around.
+// Costs are per venue: taker fee in bps plus latency_ms * latency_bps_per_ms,
+// the expected adverse move while an order is in flight. Adjusted prices are
+// what a taker actually gets: bids * (1 - cost), asks * (1 + cost). Levels
+// are sorted best first by the (adjusted) price; equal prices keep venue
+// registration order.
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::{L2Book, Side};
+
+// (price, size, venue)
+type VenueLevel = (f64, f64, String);
+// (average price, filled qty, qty per venue)
+type Sweep = (f64, f64, HashMap<String, f64>);
+
+struct Venue {
+    name: String,
+    book: Py<L2Book>,
+    fee_bps: f64,
+    latency_ms: f64,
+}
+
+#[pyclass]
+pub struct ConsolidatedBook {
+    venues: Vec<Venue>,
+    latency_bps_per_ms: f64,
+}
+
+fn check_cost(fee_bps: f64, latency_ms: f64) -> PyResult<()> {
+    if !fee_bps.is_finite() || latency_ms.is_nan() || latency_ms < 0.0 {
+        return Err(PyValueError::new_err(format!(
+            "fee_bps must be finite and latency_ms non-negative, got {} / {}",
+            fee_bps, latency_ms
+        )));
+    }
+    Ok(())
+}
+
+impl ConsolidatedBook {
+    fn venue(&mut self, name: &str) -> PyResult<&mut Venue> {
+        self.venues
+            .iter_mut()
+            .find(|v| v.name == name)
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))
+    }
+
+    fn cost(&self, v: &Venue) -> f64 {
+        (v.fee_bps + v.latency_ms * self.latency_bps_per_ms) / 10_000.0
+    }
+
+    // Merged levels of one side, best first
+    fn levels(&self, py: Python<'_>, side: Side, depth: usize, adjusted: bool) -> Vec<VenueLevel> {
+        let mut out = Vec::new();
+        for v in &self.venues {
+            let book = v.book.borrow(py);
+            let (levels, sign): (Box<dyn Iterator<Item = (f64, f64)>>, f64) = match side {
+                Side::Bid => (Box::new(book.bid_levels()), -1.0),
+                Side::Ask => (Box::new(book.ask_levels()), 1.0),
+            };
+            let factor = if adjusted {
+                1.0 + sign * self.cost(v)
+            } else {
+                1.0
+            };
+            // No venue contributes more than `depth` levels to the top `depth`
+            out.extend(
+                levels
+                    .take(depth)
+                    .map(|(p, s)| (p * factor, s, v.name.clone())),
+            );
+        }
+        match side {
+            Side::Bid => out.sort_by(|a, b| b.0.total_cmp(&a.0)),
+            Side::Ask => out.sort_by(|a, b| a.0.total_cmp(&b.0)),
+        }
+        out.truncate(depth);
+        out
+    }
+}
+
+#[pymethods]
+impl ConsolidatedBook {
+    #[new]
+    #[pyo3(signature = (latency_bps_per_ms=0.0))]
+    pub fn new(latency_bps_per_ms: f64) -> PyResult<Self> {
+        if latency_bps_per_ms.is_nan() || latency_bps_per_ms < 0.0 {
+            return Err(PyValueError::new_err(
+                "latency_bps_per_ms must be non-negative",
+            ));
+        }
+        Ok(Self {
+            venues: Vec::new(),
+            latency_bps_per_ms,
+        })
+    }
+
+    // Register (or replace) a venue's book
+    #[pyo3(signature = (venue, book, fee_bps=0.0, latency_ms=0.0))]
+    pub fn add_venue(
+        &mut self,
+        venue: &str,
+        book: Py<L2Book>,
+        fee_bps: f64,
+        latency_ms: f64,
+    ) -> PyResult<()> {
+        check_cost(fee_bps, latency_ms)?;
+        let new = Venue {
+            name: venue.to_string(),
+            book,
+            fee_bps,
+            latency_ms,
+        };
+        match self.venues.iter_mut().find(|v| v.name == venue) {
+            Some(v) => *v = new,
+            None => self.venues.push(new),
+        }
+        Ok(())
+    }
+
+    pub fn remove_venue(&mut self, venue: &str) -> bool {
+        let before = self.venues.len();
+        self.venues.retain(|v| v.name != venue);
+        self.venues.len() < before
+    }
+
+    // Update one venue's costs, e.g. after a fee tier change or a new
+    // latency measurement
+    #[pyo3(signature = (venue, fee_bps=None, latency_ms=None))]
+    pub fn set_costs(
+        &mut self,
+        venue: &str,
+        fee_bps: Option<f64>,
+        latency_ms: Option<f64>,
+    ) -> PyResult<()> {
+        let v = self.venue(venue)?;
+        let (fee_bps, latency_ms) = (
+            fee_bps.unwrap_or(v.fee_bps),
+            latency_ms.unwrap_or(v.latency_ms),
+        );
+        check_cost(fee_bps, latency_ms)?;
+        (v.fee_bps, v.latency_ms) = (fee_bps, latency_ms);
+        Ok(())
+    }
+
+    // Total adjustment applied to a venue's prices, in bps
+    pub fn cost_bps(&self, venue: &str) -> PyResult<f64> {
+        self.venues
+            .iter()
+            .find(|v| v.name == venue)
+            .map(|v| self.cost(v) * 10_000.0)
+            .ok_or_else(|| PyKeyError::new_err(venue.to_string()))
+    }
+
+    pub fn venues(&self) -> Vec<String> {
+        self.venues.iter().map(|v| v.name.clone()).collect()
+    }
+
+    pub fn book(&self, py: Python<'_>, venue: &str) -> Option<Py<L2Book>> {
+        self.venues
+            .iter()
+            .find(|v| v.name == venue)
+            .map(|v| v.book.clone_ref(py))
+    }
+
+    // Top-N merged levels as (price, size, venue), bids descending
+    #[pyo3(signature = (depth=usize::MAX, adjusted=false))]
+    pub fn bids(&self, py: Python<'_>, depth: usize, adjusted: bool) -> Vec<VenueLevel> {
+        self.levels(py, Side::Bid, depth, adjusted)
+    }
+
+    #[pyo3(signature = (depth=usize::MAX, adjusted=false))]
+    pub fn asks(&self, py: Python<'_>, depth: usize, adjusted: bool) -> Vec<VenueLevel> {
+        self.levels(py, Side::Ask, depth, adjusted)
+    }
+
+    #[pyo3(signature = (adjusted=false))]
+    pub fn best_bid(&self, py: Python<'_>, adjusted: bool) -> Option<VenueLevel> {
+        self.levels(py, Side::Bid, 1, adjusted).pop()
+    }
+
+    #[pyo3(signature = (adjusted=false))]
+    pub fn best_ask(&self, py: Python<'_>, adjusted: bool) -> Option<VenueLevel> {
+        self.levels(py, Side::Ask, 1, adjusted).pop()
+    }
+
+    #[pyo3(signature = (adjusted=false))]
+    pub fn mid(&self, py: Python<'_>, adjusted: bool) -> Option<f64> {
+        let (bid, ask) = (self.best_bid(py, adjusted)?, self.best_ask(py, adjusted)?);
+        Some((bid.0 + ask.0) / 2.0)
+    }
+
+    // Best bid >= best ask across venues. With adjusted=True this means
+    // buying on one venue and selling on another is profitable after costs.
+    #[pyo3(signature = (adjusted=true))]
+    pub fn is_crossed(&self, py: Python<'_>, adjusted: bool) -> bool {
+        match (self.best_bid(py, adjusted), self.best_ask(py, adjusted)) {
+            (Some(b), Some(a)) => b.0 >= a.0,
+            _ => false,
+        }
+    }
+
+    // Route a market order of `qty` across venues, best (adjusted) price
+    // first: (average price, filled qty, {venue: qty}), None if nothing fills
+    #[pyo3(signature = (side, qty, adjusted=true))]
+    pub fn sweep(
+        &self,
+        py: Python<'_>,
+        side: &str,
+        qty: f64,
+        adjusted: bool,
+    ) -> PyResult<Option<Sweep>> {
+        let levels = match Side::parse(side)? {
+            Side::Bid => self.levels(py, Side::Ask, usize::MAX, adjusted),
+            Side::Ask => self.levels(py, Side::Bid, usize::MAX, adjusted),
+        };
+        let mut remaining = qty;
+        let mut notional = 0.0;
+        let mut allocation = HashMap::new();
+        for (p, s, venue) in levels {
+            if remaining <= 0.0 {
+                break;
+            }
+            let take = s.min(remaining);
+            notional += take * p;
+            remaining -= take;
+            *allocation.entry(venue).or_insert(0.0) += take;
+        }
+        let filled = qty - remaining.max(0.0);
+        Ok((filled > 0.0).then(|| (notional / filled, filled, allocation)))
+    }
+
+    fn __len__(&self) -> usize {
+        self.venues.len()
+    }
+
+    fn __contains__(&self, venue: &str) -> bool {
+        self.venues.iter().any(|v| v.name == venue)
+    }
+}
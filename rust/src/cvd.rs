@@ -0,0 +1,130 @@
+// Cumulative volume delta: signed aggressor volume (buys +, sells -).
+// cvd() runs since the last (manual or periodic) reset; windowed queries use
+// a trade history retained for max_window_ms.
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::Side;
+
+#[pyclass]
+pub struct CvdTracker {
+    // Running signed volume since construction, never reset
+    total: f64,
+    // total at the start of the current reset period
+    period_base: f64,
+    // (ts, signed volume, total after this trade)
+    history: VecDeque<(i64, f64, f64)>,
+    max_window_ms: i64,
+    reset_interval_ms: Option<i64>,
+    period: Option<i64>,
+    last_ts: Option<i64>,
+}
+
+impl CvdTracker {
+    // total as of `ts` (after every trade at or before it)
+    fn total_at(&self, ts: i64) -> f64 {
+        let idx = self.history.partition_point(|(t, _, _)| *t <= ts);
+        match idx {
+            0 => self
+                .history
+                .front()
+                .map_or(self.total, |(_, v, cum)| cum - v),
+            i => self.history[i - 1].2,
+        }
+    }
+
+    fn roll_period(&mut self, ts: i64) {
+        if let Some(interval) = self.reset_interval_ms {
+            let period = ts.div_euclid(interval);
+            if self.period.is_some_and(|p| p != period) {
+                self.period_base = self.total;
+            }
+            self.period = Some(period);
+        }
+    }
+
+    fn window_points(&self, ms: i64) -> impl Iterator<Item = &(i64, f64, f64)> {
+        let cutoff = self.last_ts.unwrap_or(0) - ms;
+        self.history.iter().filter(move |(t, _, _)| *t > cutoff)
+    }
+}
+
+#[pymethods]
+impl CvdTracker {
+    // reset_interval_ms resets cvd() on wall-clock period boundaries
+    // (e.g. 86_400_000 for a daily CVD); max_window_ms bounds cvd_window()
+    #[new]
+    #[pyo3(signature = (max_window_ms=3_600_000, reset_interval_ms=None))]
+    pub fn new(max_window_ms: i64, reset_interval_ms: Option<i64>) -> PyResult<Self> {
+        if max_window_ms <= 0 || reset_interval_ms.is_some_and(|r| r <= 0) {
+            return Err(PyValueError::new_err(
+                "window and reset interval must be positive",
+            ));
+        }
+        Ok(Self {
+            total: 0.0,
+            period_base: 0.0,
+            history: VecDeque::new(),
+            max_window_ms,
+            reset_interval_ms,
+            period: None,
+            last_ts: None,
+        })
+    }
+
+    // side is the aggressor: "buy" adds, "sell" subtracts
+    pub fn add_trade(&mut self, ts: i64, size: f64, side: &str) -> PyResult<()> {
+        let signed = match Side::parse(side)? {
+            Side::Bid => size,
+            Side::Ask => -size,
+        };
+        self.roll_period(ts);
+        self.total += signed;
+        self.history.push_back((ts, signed, self.total));
+        let last = self.last_ts.map_or(ts, |t| t.max(ts));
+        self.last_ts = Some(last);
+        while self
+            .history
+            .front()
+            .is_some_and(|(t, _, _)| *t <= last - self.max_window_ms)
+        {
+            self.history.pop_front();
+        }
+        Ok(())
+    }
+
+    pub fn cvd(&self) -> f64 {
+        self.total - self.period_base
+    }
+
+    // Signed volume over the last `ms` (capped at max_window_ms)
+    pub fn cvd_window(&self, ms: i64) -> f64 {
+        let Some(last) = self.last_ts else {
+            return 0.0;
+        };
+        self.total - self.total_at(last - ms.min(self.max_window_ms))
+    }
+
+    // Least-squares slope of cumulative delta over the last `ms`, per second
+    pub fn slope(&self, ms: i64) -> Option<f64> {
+        let pts: Vec<(f64, f64)> = self
+            .window_points(ms.min(self.max_window_ms))
+            .map(|(t, _, cum)| (*t as f64 / 1000.0, *cum))
+            .collect();
+        if pts.len() < 2 {
+            return None;
+        }
+        let n = pts.len() as f64;
+        let mx = pts.iter().map(|p| p.0).sum::<f64>() / n;
+        let my = pts.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxx: f64 = pts.iter().map(|p| (p.0 - mx).powi(2)).sum();
+        let sxy: f64 = pts.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+        (sxx > 0.0).then(|| sxy / sxx)
+    }
+
+    pub fn reset(&mut self) {
+        self.period_base = self.total;
+    }
+}
@@ -0,0 +1,321 @@
+// Depth messages from several exchanges, parsed in Rust (json.rs) into one
+// DepthMessage with the level vectors ready to use, and optionally applied
+// to an L2Book with the venue's sequencing rules:
+//   bybit    orderbook.* topics: snapshot, or delta with update id u (a delta
+//            with u == 1 resets the book like a snapshot)
+//   binance  depthUpdate events U..u (pu on futures), bare or inside a
+//            combined-stream {"stream", "data"} wrapper; partial depth
+//            {"lastUpdateId", "bids", "asks"} is a snapshot. A diff applies
+//            when it continues the book (pu == last, or U == last + 1) or
+//            straddles the snapshot id (U <= last + 1 <= u).
+//   okx      books* / bbo-tbt channels: action "snapshot" or "update" with
+//            seqId and prevSeqId; pushes without an action (books5, bbo-tbt)
+//            are full snapshots. checksum is passed through for
+//            L2Book.verify_checksum(msg.checksum, "okx").
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::bybit;
+use crate::events::BookUpdate;
+use crate::json::{self, Value};
+use crate::{L2Book, Levels};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Venue {
+    Bybit,
+    Binance,
+    Okx,
+}
+
+impl Venue {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bybit" => Ok(Self::Bybit),
+            "binance" => Ok(Self::Binance),
+            "okx" => Ok(Self::Okx),
+            other => Err(PyValueError::new_err(format!(
+                "exchange must be 'bybit', 'binance' or 'okx', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bybit => "bybit",
+            Self::Binance => "binance",
+            Self::Okx => "okx",
+        }
+    }
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct DepthMessage {
+    pub exchange: &'static str,
+    // "snapshot" or "delta"
+    pub kind: &'static str,
+    pub symbol: String,
+    // Bybit u, Binance u / lastUpdateId, OKX seqId
+    pub update_id: Option<u64>,
+    // Binance U
+    pub first_update_id: Option<u64>,
+    // Binance futures pu, OKX prevSeqId (None when -1)
+    pub prev_update_id: Option<u64>,
+    pub ts: Option<i64>,
+    // OKX CRC32 of the top 25 levels after this message
+    pub checksum: Option<i64>,
+    pub bids: Levels,
+    pub asks: Levels,
+    // Set by DepthParser.apply
+    pub applied: bool,
+}
+
+#[pymethods]
+impl DepthMessage {
+    #[getter]
+    pub fn is_snapshot(&self) -> bool {
+        self.kind == "snapshot"
+    }
+
+    // As a normalized BookUpdate (see normalized.rs)
+    pub fn normalize(&self) -> BookUpdate {
+        BookUpdate {
+            exchange: Some(self.exchange.to_string()),
+            symbol: Some(self.symbol.clone()),
+            kind: self.kind,
+            update_id: self.update_id,
+            ts: self.ts,
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            applied: self.applied,
+            ..Default::default()
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DepthMessage(exchange={:?}, kind={:?}, symbol={:?}, update_id={:?}, bids={}, asks={}, applied={})",
+            self.exchange,
+            self.kind,
+            self.symbol,
+            self.update_id,
+            self.bids.len(),
+            self.asks.len(),
+            if self.applied { "True" } else { "False" }
+        )
+    }
+}
+
+fn missing(what: &str) -> PyErr {
+    PyValueError::new_err(format!("missing '{}'", what))
+}
+
+fn levels(v: Option<&Value<'_>>) -> PyResult<Levels> {
+    json::levels(v).map_err(PyValueError::new_err)
+}
+
+fn parse_bybit(root: &Value<'_>) -> PyResult<DepthMessage> {
+    let msg = bybit::parse_book_message(root)?;
+    Ok(DepthMessage {
+        kind: if msg.is_snapshot() {
+            "snapshot"
+        } else {
+            "delta"
+        },
+        symbol: msg.symbol,
+        update_id: Some(msg.update_id),
+        ts: msg.ts,
+        bids: msg.bids,
+        asks: msg.asks,
+        ..Default::default()
+    })
+}
+
+fn parse_binance(root: &Value<'_>) -> PyResult<DepthMessage> {
+    let stream = root.get("stream").and_then(Value::as_str);
+    let data = match stream {
+        Some(_) => root.get("data").ok_or_else(|| missing("data"))?,
+        None => root,
+    };
+    if let Some(last) = data.get("lastUpdateId") {
+        // Partial depth streams carry no symbol; take it from the stream name
+        let symbol = stream
+            .and_then(|s| s.split('@').next())
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        return Ok(DepthMessage {
+            kind: "snapshot",
+            symbol,
+            update_id: Some(last.as_u64().ok_or_else(|| missing("lastUpdateId"))?),
+            bids: levels(data.get("bids"))?,
+            asks: levels(data.get("asks"))?,
+            ..Default::default()
+        });
+    }
+    let event = data.get("e").and_then(Value::as_str).unwrap_or_default();
+    if event != "depthUpdate" {
+        return Err(PyValueError::new_err(format!(
+            "not a depth message: event '{}'",
+            event
+        )));
+    }
+    Ok(DepthMessage {
+        kind: "delta",
+        symbol: data
+            .get("s")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        update_id: Some(
+            data.get("u")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| missing("u"))?,
+        ),
+        first_update_id: Some(
+            data.get("U")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| missing("U"))?,
+        ),
+        prev_update_id: data.get("pu").and_then(Value::as_u64),
+        ts: data.get("E").and_then(Value::as_i64),
+        bids: levels(data.get("b"))?,
+        asks: levels(data.get("a"))?,
+        ..Default::default()
+    })
+}
+
+fn parse_okx(root: &Value<'_>) -> PyResult<DepthMessage> {
+    let arg = root.get("arg").ok_or_else(|| missing("arg"))?;
+    let channel = arg
+        .get("channel")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if !channel.starts_with("books") && channel != "bbo-tbt" {
+        return Err(PyValueError::new_err(format!(
+            "not a depth message: channel '{}'",
+            channel
+        )));
+    }
+    let data = root
+        .get("data")
+        .and_then(Value::as_array)
+        .and_then(|d| d.first())
+        .ok_or_else(|| missing("data"))?;
+    let action = root.get("action").and_then(Value::as_str);
+    Ok(DepthMessage {
+        kind: if action == Some("update") {
+            "delta"
+        } else {
+            "snapshot"
+        },
+        symbol: arg
+            .get("instId")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        update_id: data.get("seqId").and_then(Value::as_u64),
+        prev_update_id: data
+            .get("prevSeqId")
+            .and_then(Value::as_i64)
+            .and_then(|p| u64::try_from(p).ok()),
+        ts: data.get("ts").and_then(Value::as_i64),
+        checksum: data.get("checksum").and_then(Value::as_i64),
+        bids: levels(data.get("bids"))?,
+        asks: levels(data.get("asks"))?,
+        ..Default::default()
+    })
+}
+
+#[pyclass]
+pub struct DepthParser {
+    venue: Venue,
+}
+
+impl DepthParser {
+    fn decode(&self, msg: &Bound<'_, PyAny>) -> PyResult<DepthMessage> {
+        let text = json::message_text(msg)?;
+        let root = json::parse_py(&text)?;
+        let parsed = match self.venue {
+            Venue::Bybit => parse_bybit(&root),
+            Venue::Binance => parse_binance(&root),
+            Venue::Okx => parse_okx(&root),
+        }?;
+        Ok(DepthMessage {
+            exchange: self.venue.name(),
+            ..parsed
+        })
+    }
+
+    // Previous id a delta must follow, in L2Book.delta terms
+    fn expected_prev(&self, book: &L2Book, msg: &DepthMessage) -> Option<u64> {
+        match (
+            self.venue,
+            book.last_update_id,
+            msg.first_update_id,
+            msg.update_id,
+        ) {
+            (Venue::Binance, Some(last), Some(first), Some(u)) if first <= last + 1 && last < u => {
+                Some(last)
+            }
+            (Venue::Binance, _, Some(first), _) => {
+                msg.prev_update_id.or(Some(first.saturating_sub(1)))
+            }
+            _ => msg.prev_update_id,
+        }
+    }
+}
+
+#[pymethods]
+impl DepthParser {
+    #[new]
+    #[pyo3(signature = (exchange="bybit"))]
+    pub fn new(exchange: &str) -> PyResult<Self> {
+        Ok(Self {
+            venue: Venue::parse(exchange)?,
+        })
+    }
+
+    #[getter]
+    pub fn exchange(&self) -> &'static str {
+        self.venue.name()
+    }
+
+    // Parse a raw message (str/bytes) without touching any book. Messages
+    // that are not depth data (acks, other channels) raise ValueError.
+    pub fn parse(&self, msg: &Bound<'_, PyAny>) -> PyResult<DepthMessage> {
+        self.decode(msg)
+    }
+
+    // Parse and apply to `book`: snapshots replace it, deltas go through the
+    // book's gap detection (needs_resync / SequenceGapError) and are
+    // reported with applied=False when stale or out of sequence
+    pub fn apply(&self, book: &mut L2Book, msg: &Bound<'_, PyAny>) -> PyResult<DepthMessage> {
+        let mut parsed = self.decode(msg)?;
+        let (bids, asks) = (parsed.bids.clone(), parsed.asks.clone());
+        parsed.applied = if parsed.is_snapshot() {
+            book.load_snapshot(bids, asks, parsed.update_id)?;
+            true
+        } else {
+            let prev = self.expected_prev(book, &parsed);
+            book.delta(bids, asks, parsed.update_id, prev, None)?
+        };
+        Ok(parsed)
+    }
+}
+
+// One-off form of DepthParser(exchange).parse(msg), or .apply(book, msg)
+// when a book is given
+#[pyfunction]
+#[pyo3(signature = (msg, exchange="bybit", book=None))]
+pub fn parse_depth_message(
+    msg: &Bound<'_, PyAny>,
+    exchange: &str,
+    book: Option<PyRefMut<'_, L2Book>>,
+) -> PyResult<DepthMessage> {
+    let parser = DepthParser::new(exchange)?;
+    match book {
+        Some(mut book) => parser.apply(&mut book, msg),
+        None => parser.decode(msg),
+    }
+}
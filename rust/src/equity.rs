@@ -0,0 +1,220 @@
+// Equity curve with drawdown and risk-adjusted return statistics. Each
+// sample is a mark-to-market equity at a time; the tracker keeps the curve
+// (for export) and running peak, drawdown and time under water (time since
+// the last peak while below it, 0 at a new peak). Returns between
+// consecutive samples are simple returns, or PnL over `capital` when the
+// curve is a PnL series starting near zero. Sharpe and Sortino are computed
+// over the samples in a trailing window and annualized with the mean sample
+// interval: sqrt(year / interval) * mean / std (downside deviation, the
+// root mean square of negative returns, for Sortino).
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::arrays;
+use crate::clock::{self, Clock};
+
+const YEAR_MS: f64 = 365.0 * 86_400_000.0;
+
+#[pyclass]
+pub struct EquityTracker {
+    window_ms: i64,
+    capital: Option<f64>,
+    clock: Option<Clock>,
+    // (ts, equity, drawdown)
+    samples: Vec<(i64, f64, f64)>,
+    peak: f64,
+    peak_ts: i64,
+    max_drawdown: f64,
+    max_drawdown_pct: f64,
+    max_under_water_ms: i64,
+}
+
+impl EquityTracker {
+    // Drawdown as a fraction of the peak, or of capital when given
+    fn pct(&self, drawdown: f64) -> f64 {
+        let base = self.capital.unwrap_or(self.peak);
+        if base > 0.0 {
+            drawdown / base
+        } else {
+            0.0
+        }
+    }
+
+    fn returns(&self, window_ms: Option<i64>) -> PyResult<(Vec<f64>, f64)> {
+        let window_ms = window_ms.unwrap_or(self.window_ms);
+        if window_ms <= 0 {
+            return Err(PyValueError::new_err("window_ms must be positive"));
+        }
+        let Some(&(last, _, _)) = self.samples.last() else {
+            return Ok((Vec::new(), 0.0));
+        };
+        let start = self.samples.partition_point(|s| s.0 < last - window_ms);
+        let window = &self.samples[start..];
+        let mut out = Vec::with_capacity(window.len());
+        for pair in window.windows(2) {
+            let ((_, a, _), (_, b, _)) = (pair[0], pair[1]);
+            let base = self.capital.unwrap_or(a);
+            if base <= 0.0 {
+                return Err(PyValueError::new_err(
+                    "returns need positive equity; pass capital for a PnL curve",
+                ));
+            }
+            out.push((b - a) / base);
+        }
+        let span = (window[window.len() - 1].0 - window[0].0) as f64;
+        let interval = if out.is_empty() {
+            0.0
+        } else {
+            span / out.len() as f64
+        };
+        Ok((out, interval))
+    }
+
+    // sqrt(year / interval) * mean / deviation, where deviation comes from
+    // the returns; None below two returns, a zero interval or deviation
+    fn ratio(
+        &self,
+        window_ms: Option<i64>,
+        deviation: fn(&[f64], f64) -> f64,
+    ) -> PyResult<Option<f64>> {
+        let (returns, interval) = self.returns(window_ms)?;
+        if returns.len() < 2 || interval <= 0.0 {
+            return Ok(None);
+        }
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let dev = deviation(&returns, mean);
+        Ok((dev > 0.0).then(|| mean / dev * (YEAR_MS / interval).sqrt()))
+    }
+}
+
+#[pymethods]
+impl EquityTracker {
+    // window_ms is the default Sharpe/Sortino window (a day)
+    #[new]
+    #[pyo3(signature = (window_ms=86_400_000, capital=None, clock=None))]
+    pub fn new(window_ms: i64, capital: Option<f64>, clock: Option<Clock>) -> PyResult<Self> {
+        if window_ms <= 0 || capital.is_some_and(|c| c <= 0.0) {
+            return Err(PyValueError::new_err(
+                "window_ms and capital must be positive",
+            ));
+        }
+        Ok(Self {
+            window_ms,
+            capital,
+            clock,
+            samples: Vec::new(),
+            peak: f64::NEG_INFINITY,
+            peak_ts: 0,
+            max_drawdown: 0.0,
+            max_drawdown_pct: 0.0,
+            max_under_water_ms: 0,
+        })
+    }
+
+    // Add a sample; ts defaults to the clock's time and may not go back.
+    // Returns the current drawdown.
+    #[pyo3(signature = (equity, ts=None))]
+    pub fn record(&mut self, equity: f64, ts: Option<i64>) -> PyResult<f64> {
+        let ts = clock::require(ts, &self.clock)?;
+        if self.samples.last().is_some_and(|s| ts < s.0) {
+            return Err(PyValueError::new_err("samples must be in time order"));
+        }
+        if equity >= self.peak {
+            self.peak = equity;
+            self.peak_ts = ts;
+        }
+        let drawdown = self.peak - equity;
+        self.max_drawdown = self.max_drawdown.max(drawdown);
+        self.max_drawdown_pct = self.max_drawdown_pct.max(self.pct(drawdown));
+        self.samples.push((ts, equity, drawdown));
+        self.max_under_water_ms = self.max_under_water_ms.max(self.time_under_water_ms());
+        Ok(drawdown)
+    }
+
+    #[getter]
+    pub fn equity(&self) -> Option<f64> {
+        self.samples.last().map(|s| s.1)
+    }
+
+    #[getter]
+    pub fn peak(&self) -> Option<f64> {
+        (!self.samples.is_empty()).then_some(self.peak)
+    }
+
+    // Below the peak, in equity units
+    #[getter]
+    pub fn drawdown(&self) -> f64 {
+        self.samples.last().map_or(0.0, |s| s.2)
+    }
+
+    #[getter]
+    pub fn drawdown_pct(&self) -> f64 {
+        self.pct(self.drawdown())
+    }
+
+    #[getter]
+    pub fn max_drawdown(&self) -> f64 {
+        self.max_drawdown
+    }
+
+    #[getter]
+    pub fn max_drawdown_pct(&self) -> f64 {
+        self.max_drawdown_pct
+    }
+
+    #[getter]
+    pub fn time_under_water_ms(&self) -> i64 {
+        match self.samples.last() {
+            Some(&(ts, _, dd)) if dd > 0.0 => ts - self.peak_ts,
+            _ => 0,
+        }
+    }
+
+    #[getter]
+    pub fn max_time_under_water_ms(&self) -> i64 {
+        self.max_under_water_ms
+    }
+
+    // Annualized over the samples in the last window_ms (default: the
+    // tracker's); None with fewer than three samples or flat returns
+    #[pyo3(signature = (window_ms=None))]
+    pub fn sharpe(&self, window_ms: Option<i64>) -> PyResult<Option<f64>> {
+        self.ratio(window_ms, |r, mean| {
+            let var = r.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (r.len() - 1) as f64;
+            var.sqrt()
+        })
+    }
+
+    // As sharpe() with the downside deviation; None without losing periods
+    #[pyo3(signature = (window_ms=None))]
+    pub fn sortino(&self, window_ms: Option<i64>) -> PyResult<Option<f64>> {
+        self.ratio(window_ms, |r, _| {
+            let down = r.iter().map(|x| x.min(0.0).powi(2)).sum::<f64>() / r.len() as f64;
+            down.sqrt()
+        })
+    }
+
+    // The curve as a float64 numpy array of shape (N, 3): ts, equity,
+    // drawdown
+    pub fn to_array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let flat: Vec<f64> = self
+            .samples
+            .iter()
+            .flat_map(|&(ts, e, dd)| [ts as f64, e, dd])
+            .collect();
+        arrays::rows_to_ndarray(py, &flat, 3)
+    }
+
+    fn __len__(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.peak = f64::NEG_INFINITY;
+        self.peak_ts = 0;
+        self.max_drawdown = 0.0;
+        self.max_drawdown_pct = 0.0;
+        self.max_under_water_ms = 0;
+    }
+}
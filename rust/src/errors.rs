@@ -0,0 +1,86 @@
+// Errors from applying updates to an L2Book. Book internals return BookError
+// and `?` turns it into the matching Python exception at the boundary:
+//   InvalidPrice  InvalidPriceError   NaN, infinite or negative price
+//   InvalidSize   ValueError          NaN or infinite size
+//   CrossedBook   CrossedBookError    best bid >= best ask, cross_policy="raise"
+//   SequenceGap   SequenceGapError    update id gap, raise_on_gap=True
+// Levels are checked before anything is written, so a rejected snapshot or
+// delta leaves the book as it was.
+use std::fmt;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
+
+use crate::{CrossedBookError, InvalidPriceError, Levels, SequenceGapError, Side};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BookError {
+    InvalidPrice { side: Side, price: f64 },
+    InvalidSize { side: Side, price: f64, size: f64 },
+    CrossedBook { bid: f64, ask: f64 },
+    SequenceGap { last: u64, expected: u64 },
+}
+
+impl BookError {
+    // First level on either side a book must not take
+    pub(crate) fn check_levels(bids: &Levels, asks: &Levels) -> Result<(), Self> {
+        let sides = [(Side::Bid, bids), (Side::Ask, asks)];
+        for (side, levels) in sides {
+            for &(price, size) in levels {
+                if !price.is_finite() || price < 0.0 {
+                    return Err(Self::InvalidPrice { side, price });
+                }
+                if !size.is_finite() {
+                    return Err(Self::InvalidSize { side, price, size });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Bid => "bid",
+        Side::Ask => "ask",
+    }
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPrice { side, price } => {
+                write!(f, "invalid {} price {}", side_name(*side), price)
+            }
+            Self::InvalidSize { side, price, size } => write!(
+                f,
+                "invalid {} size {} at price {}",
+                side_name(*side),
+                size,
+                price
+            ),
+            Self::CrossedBook { bid, ask } => {
+                write!(f, "crossed book: best bid {} >= best ask {}", bid, ask)
+            }
+            Self::SequenceGap { last, expected } => write!(
+                f,
+                "sequence gap: last applied {}, delta expects {}",
+                last, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+impl From<BookError> for PyErr {
+    fn from(e: BookError) -> Self {
+        let msg = e.to_string();
+        match e {
+            BookError::InvalidPrice { .. } => InvalidPriceError::new_err(msg),
+            BookError::InvalidSize { .. } => PyValueError::new_err(msg),
+            BookError::CrossedBook { .. } => CrossedBookError::new_err(msg),
+            BookError::SequenceGap { .. } => SequenceGapError::new_err(msg),
+        }
+    }
+}
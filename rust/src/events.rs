@@ -0,0 +1,166 @@
+// Structured description of one book update, for strategies that react to
+// top-of-book moves instead of diffing the book in Python, and the callbacks
+// L2Book.apply_delta fires from it. Built only when asked for (return_update
+// or a registered callback): the book records the prior size of every level
+// the update touches, including levels dropped by the cross policy.
+// A level is added when it goes from empty to resting and removed the other
+// way round; size changes on resting levels only show through the best_*
+// fields. Levels are listed best first (bids descending, asks ascending).
+// BookUpdate is also the normalized book event of the parsers and FeedClient
+// (see normalized.rs): they fill exchange, symbol, kind, update_id, ts,
+// recv_ns and the update's own levels, and leave the diff fields empty.
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::Levels;
+
+// What one update did to the book
+#[derive(Default, Debug)]
+pub(crate) struct Changes {
+    // Prior size (0 when empty) of every ladder key the update touched
+    pub bids: BTreeMap<i64, f64>,
+    pub asks: BTreeMap<i64, f64>,
+    // (last applied update id, id the delta expected) on a sequence gap
+    pub gap: Option<(u64, u64)>,
+    // (best bid, best ask) prices if the delta crossed the book
+    pub cross: Option<(f64, f64)>,
+}
+
+// Python callbacks registered with L2Book.set_callbacks. Arc keeps the book
+// Clone without needing the GIL.
+#[derive(Default, Clone)]
+pub(crate) struct Hooks {
+    on_best_change: Option<Arc<Py<PyAny>>>,
+    on_cross: Option<Arc<Py<PyAny>>>,
+    on_gap: Option<Arc<Py<PyAny>>>,
+}
+
+impl Hooks {
+    pub fn new(
+        listener: Option<&Bound<'_, PyAny>>,
+        on_best_change: Option<Bound<'_, PyAny>>,
+        on_cross: Option<Bound<'_, PyAny>>,
+        on_gap: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let hook = |name: &str, explicit: Option<Bound<'_, PyAny>>| -> PyResult<_> {
+            let f = match (explicit, listener) {
+                (Some(f), _) => f,
+                (None, Some(l)) if l.hasattr(name)? => l.getattr(name)?,
+                _ => return Ok(None),
+            };
+            if !f.is_callable() {
+                return Err(PyValueError::new_err(format!("{} must be callable", name)));
+            }
+            Ok(Some(Arc::new(f.unbind())))
+        };
+        Ok(Self {
+            on_best_change: hook("on_best_change", on_best_change)?,
+            on_cross: hook("on_cross", on_cross)?,
+            on_gap: hook("on_gap", on_gap)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.on_best_change.is_none() && self.on_cross.is_none() && self.on_gap.is_none()
+    }
+
+    // Gap, then cross, then best change; the first exception propagates
+    pub fn fire(
+        &self,
+        py: Python<'_>,
+        gap: Option<(u64, u64)>,
+        cross: Option<(f64, f64)>,
+        update: Option<&BookUpdate>,
+    ) -> PyResult<()> {
+        if let (Some(f), Some(gap)) = (&self.on_gap, gap) {
+            f.call1(py, gap)?;
+        }
+        if let (Some(f), Some(cross)) = (&self.on_cross, cross) {
+            f.call1(py, cross)?;
+        }
+        if let (Some(f), Some(update)) = (&self.on_best_change, update) {
+            if update.top_changed() {
+                f.call1(py, (update.clone(),))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct BookUpdate {
+    // None when built by L2Book.apply_delta
+    pub exchange: Option<String>,
+    pub symbol: Option<String>,
+    // "snapshot" or "delta"
+    pub kind: &'static str,
+    pub update_id: Option<u64>,
+    // Exchange time in epoch ms, and monotonic_ns() when the frame was read
+    pub ts: Option<i64>,
+    pub recv_ns: Option<i64>,
+    // Levels as sent (size 0 deletes); empty when the parser applied them
+    // without keeping a copy
+    pub bids: Levels,
+    pub asks: Levels,
+    // False when the delta was stale or gapped and the book is unchanged
+    pub applied: bool,
+    pub old_best_bid: Option<(f64, f64)>,
+    pub old_best_ask: Option<(f64, f64)>,
+    pub best_bid: Option<(f64, f64)>,
+    pub best_ask: Option<(f64, f64)>,
+    pub bids_added: Levels,
+    // Removed levels carry the size they had before the update
+    pub bids_removed: Levels,
+    pub asks_added: Levels,
+    pub asks_removed: Levels,
+}
+
+#[pymethods]
+impl BookUpdate {
+    // Best bid price or size differs from before the update
+    #[getter]
+    pub fn best_bid_changed(&self) -> bool {
+        self.best_bid != self.old_best_bid
+    }
+
+    #[getter]
+    pub fn best_ask_changed(&self) -> bool {
+        self.best_ask != self.old_best_ask
+    }
+
+    #[getter]
+    pub fn top_changed(&self) -> bool {
+        self.best_bid_changed() || self.best_ask_changed()
+    }
+
+    #[getter]
+    pub fn is_snapshot(&self) -> bool {
+        self.kind == "snapshot"
+    }
+
+    fn __repr__(&self) -> String {
+        let top = |l: Option<(f64, f64)>| {
+            l.map_or_else(|| "None".into(), |(p, s)| format!("({}, {})", p, s))
+        };
+        format!(
+            "BookUpdate(exchange={:?}, symbol={:?}, kind={:?}, update_id={:?}, applied={}, best_bid={} -> {}, best_ask={} -> {}, bids +{}/-{}, asks +{}/-{})",
+            self.exchange,
+            self.symbol,
+            self.kind,
+            self.update_id,
+            if self.applied { "True" } else { "False" },
+            top(self.old_best_bid),
+            top(self.best_bid),
+            top(self.old_best_ask),
+            top(self.best_ask),
+            self.bids_added.len(),
+            self.bids_removed.len(),
+            self.asks_added.len(),
+            self.asks_removed.len()
+        )
+    }
+}
@@ -0,0 +1,312 @@
+// Exponentially weighted mean and variance. Decay is given as exactly one of
+//   alpha        per-sample smoothing factor in (0, 1]
+//   halflife     in samples: alpha = 1 - 0.5^(1/halflife)
+//   halflife_ms  time-aware: an observation's weight halves every
+//                halflife_ms, so irregular sampling is handled exactly
+// Both keep the total weight, so early values are bias-corrected (pandas
+// `adjust=True`) instead of being pulled towards an arbitrary start.
+// Observations with equal timestamps carry equal weight. With a clock, an
+// omitted ts is the clock's time.
+use std::f64::consts::LN_2;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+use crate::state::{self, persist_fields, Persist, Stateful};
+
+#[derive(Clone, Copy, Debug)]
+enum Decay {
+    // Weight kept by the history per sample
+    PerSample(f64),
+    HalfLifeMs(f64),
+}
+
+impl Persist for Decay {
+    fn save(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::PerSample(keep) => (0u8, *keep).save(out),
+            Self::HalfLifeMs(ms) => (1u8, *ms).save(out),
+        }
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        match u8::load(input)? {
+            0 => Ok(Self::PerSample(f64::load(input)?)),
+            1 => Ok(Self::HalfLifeMs(f64::load(input)?)),
+            tag => Err(state::bad_tag(tag)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Weights {
+    decay: Decay,
+    last_ts: Option<i64>,
+}
+
+persist_fields!(Weights { decay, last_ts });
+
+impl Weights {
+    fn new(alpha: Option<f64>, halflife: Option<f64>, halflife_ms: Option<f64>) -> PyResult<Self> {
+        let decay = match (alpha, halflife, halflife_ms) {
+            (Some(a), None, None) if a > 0.0 && a <= 1.0 => Decay::PerSample(1.0 - a),
+            (Some(a), None, None) => {
+                return Err(PyValueError::new_err(format!(
+                    "alpha must be in (0, 1], got {}",
+                    a
+                )))
+            }
+            (None, Some(h), None) if h > 0.0 => Decay::PerSample(0.5f64.powf(1.0 / h)),
+            (None, None, Some(h)) if h > 0.0 => Decay::HalfLifeMs(h),
+            (None, Some(_), None) | (None, None, Some(_)) => {
+                return Err(PyValueError::new_err("halflife must be positive"))
+            }
+            _ => {
+                return Err(PyValueError::new_err(
+                    "exactly one of alpha, halflife or halflife_ms must be set",
+                ))
+            }
+        };
+        Ok(Self {
+            decay,
+            last_ts: None,
+        })
+    }
+
+    // Factor applied to the history before adding an observation at `ts`
+    fn next(&mut self, ts: Option<i64>) -> PyResult<f64> {
+        match self.decay {
+            Decay::PerSample(w) => Ok(w),
+            Decay::HalfLifeMs(h) => {
+                let ts =
+                    ts.ok_or_else(|| PyValueError::new_err("ts is required with halflife_ms"))?;
+                let dt = self.last_ts.map_or(0, |last| (ts - last).max(0));
+                self.last_ts = Some(self.last_ts.map_or(ts, |last| last.max(ts)));
+                Ok((-LN_2 * dt as f64 / h).exp())
+            }
+        }
+    }
+}
+
+fn check_value(value: f64) -> PyResult<()> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(format!(
+            "value must be finite, got {}",
+            value
+        )))
+    }
+}
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Ewma {
+    weights: Weights,
+    total: f64,
+    mean: f64,
+    count: u64,
+    clock: Option<Clock>,
+}
+
+persist_fields!(Ewma {
+    weights,
+    total,
+    mean,
+    count,
+    clock
+});
+
+impl Stateful for Ewma {
+    const NAME: &'static str = "Ewma";
+}
+
+impl Ewma {
+    pub fn add(&mut self, value: f64, ts: Option<i64>) -> PyResult<f64> {
+        check_value(value)?;
+        let ts = clock::resolve(ts, &self.clock);
+        self.total = self.total * self.weights.next(ts)? + 1.0;
+        self.mean += (value - self.mean) / self.total;
+        self.count += 1;
+        Ok(self.mean)
+    }
+}
+
+#[pymethods]
+impl Ewma {
+    #[new]
+    #[pyo3(signature = (alpha=None, halflife=None, halflife_ms=None, clock=None))]
+    pub fn new(
+        alpha: Option<f64>,
+        halflife: Option<f64>,
+        halflife_ms: Option<f64>,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            weights: Weights::new(alpha, halflife, halflife_ms)?,
+            total: 0.0,
+            mean: 0.0,
+            count: 0,
+            clock,
+        })
+    }
+
+    // Add an observation; returns the updated average
+    #[pyo3(signature = (value, ts=None))]
+    pub fn update(&mut self, value: f64, ts: Option<i64>) -> PyResult<f64> {
+        self.add(value, ts)
+    }
+
+    #[getter]
+    pub fn value(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn reset(&mut self) {
+        self.weights.last_ts = None;
+        self.total = 0.0;
+        self.mean = 0.0;
+        self.count = 0;
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
+
+// West's weighted incremental algorithm with the history decayed each step
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Ewmv {
+    weights: Weights,
+    total: f64,
+    mean: f64,
+    // Weighted sum of squared deviations
+    m2: f64,
+    count: u64,
+    clock: Option<Clock>,
+}
+
+persist_fields!(Ewmv {
+    weights,
+    total,
+    mean,
+    m2,
+    count,
+    clock
+});
+
+impl Stateful for Ewmv {
+    const NAME: &'static str = "Ewmv";
+}
+
+impl Ewmv {
+    pub fn add(&mut self, value: f64, ts: Option<i64>) -> PyResult<f64> {
+        check_value(value)?;
+        let w = self.weights.next(clock::resolve(ts, &self.clock))?;
+        self.total = self.total * w + 1.0;
+        self.m2 *= w;
+        let delta = value - self.mean;
+        self.mean += delta / self.total;
+        self.m2 += delta * (value - self.mean);
+        self.count += 1;
+        Ok(self.var_now())
+    }
+
+    fn var_now(&self) -> f64 {
+        if self.total > 0.0 {
+            (self.m2 / self.total).max(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[pymethods]
+impl Ewmv {
+    #[new]
+    #[pyo3(signature = (alpha=None, halflife=None, halflife_ms=None, clock=None))]
+    pub fn new(
+        alpha: Option<f64>,
+        halflife: Option<f64>,
+        halflife_ms: Option<f64>,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            weights: Weights::new(alpha, halflife, halflife_ms)?,
+            total: 0.0,
+            mean: 0.0,
+            m2: 0.0,
+            count: 0,
+            clock,
+        })
+    }
+
+    // Add an observation; returns the updated variance
+    #[pyo3(signature = (value, ts=None))]
+    pub fn update(&mut self, value: f64, ts: Option<i64>) -> PyResult<f64> {
+        self.add(value, ts)
+    }
+
+    #[getter]
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    #[getter]
+    pub fn var(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.var_now())
+    }
+
+    #[getter]
+    pub fn std(&self) -> Option<f64> {
+        self.var().map(f64::sqrt)
+    }
+
+    // (value - mean) / std; None until the variance is positive
+    pub fn zscore(&self, value: f64) -> Option<f64> {
+        let std = self.std()?;
+        (std > 0.0).then(|| (value - self.mean) / std)
+    }
+
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn reset(&mut self) {
+        self.weights.last_ts = None;
+        self.total = 0.0;
+        self.mean = 0.0;
+        self.m2 = 0.0;
+        self.count = 0;
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
@@ -0,0 +1,1168 @@
+// Market-data feed client running on a background thread inside the
+// extension: it reads the exchange WebSocket (see ws.rs), parses messages
+// with the Rust parsers and applies them straight to per-symbol books and
+// trade tapes, so Python is off the hot path. Python reads the books through
+// SharedL2Book handles, copies the tapes, and drains a bounded event queue
+// with poll(). The thread takes the GIL only to run state callbacks or
+// format a rare parser error, and never while holding a lock.
+// Scope: one std::thread per client with blocking sockets, not a tokio
+// runtime; the extension stays free of an async runtime, and asyncio code
+// awaits events through next_event() (see aio.rs). wss:// URLs connect over
+// TLS (tls.rs); ws:// is plain TCP. Only Bybit's stream protocol is spoken.
+// Exchanges: "bybit" (V5 public topics orderbook.{depth}.{symbol} and
+// publicTrade.{symbol}; with api_key/api_secret, the private order,
+// execution and position topics on the /v5/private URL, see userstream.rs).
+//
+// Connection state: a dropped connection is retried with exponential
+// backoff (reconnect_delay_ms doubling up to max_reconnect_delay_ms, reset
+// once a session connects) and every topic is subscribed again.
+// Book state: a book is synced from its first snapshot until a sequence gap
+// or a disconnect sets needs_resync. While desynced, deltas are buffered
+// (up to max_buffer, oldest dropped) and a fresh snapshot is requested; on
+// Bybit, resubscribing the topic makes the server send one. The snapshot is
+// loaded, buffered deltas newer than it are replayed, and the book is
+// synced again. Transitions are queued as "connected", "disconnected",
+// "desynced" and "synced" events and fire the set_callbacks hooks, so a
+// strategy can pull its quotes while a book cannot be trusted.
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::IntoPyObjectExt;
+
+use crate::aio::{EventSource, NextEvent, Waiters};
+use crate::bybit;
+use crate::clock;
+use crate::events::BookUpdate;
+use crate::fees::Liquidity;
+use crate::json::{self, Value};
+use crate::normalized::{self, Fill, OrderUpdate};
+use crate::orders::OrderManager;
+use crate::position::Position;
+use crate::ring::{EventQueue, Producer};
+use crate::shared::SharedL2Book;
+use crate::snapshot::check_token;
+use crate::trades::{Trade, TradeTape};
+use crate::userstream::{self, Routing, Update};
+use crate::ws::{self, Message, WsConn};
+use crate::{L2Book, Side};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Longest the thread blocks in a read before checking its stop flag
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+// Bybit drops connections without a ping every 20s
+const HEARTBEAT: Duration = Duration::from_secs(20);
+// A requested snapshot that has not arrived by then is requested again
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Exchange {
+    Bybit,
+}
+
+impl Exchange {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bybit" => Ok(Self::Bybit),
+            other => Err(PyValueError::new_err(format!(
+                "exchange must be 'bybit', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bybit => "bybit",
+        }
+    }
+}
+
+// One entry of the event queue. kind is "connected", "disconnected",
+// "desynced" / "synced" (symbol), "book" (symbol, ts, update_id, applied,
+// status: "snapshot" or "delta"), "trade" (symbol, ts, price, size, side),
+// "error" (message) or, from a private stream, "order" (symbol, ts,
+// client_id, exchange_id, status, side, price, size), "fill" (symbol, ts,
+// client_id, exchange_id, side, price, size, fee, liquidity) and "position"
+// (symbol, ts, side, size, price: the average entry, None when flat); fields
+// that do not apply are None.
+// Book and trade events carry recv_ns, the monotonic_ns() at which their
+// frame was read.
+// "disconnected" follows every session, including a failed connect attempt.
+// The client stamps every event with its exchange.
+// Python can build events too, e.g. to push onto an EventQueue.
+const KINDS: [&str; 10] = [
+    "connected",
+    "disconnected",
+    "desynced",
+    "synced",
+    "book",
+    "trade",
+    "error",
+    "order",
+    "fill",
+    "position",
+];
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct FeedEvent {
+    pub kind: &'static str,
+    pub symbol: Option<String>,
+    pub ts: Option<i64>,
+    pub update_id: Option<u64>,
+    pub applied: bool,
+    pub price: Option<f64>,
+    pub size: Option<f64>,
+    pub side: Option<&'static str>,
+    pub message: Option<String>,
+    pub recv_ns: Option<i64>,
+    pub client_id: Option<String>,
+    pub exchange_id: Option<String>,
+    pub status: Option<String>,
+    pub fee: Option<f64>,
+    // "maker" or "taker"
+    pub liquidity: Option<&'static str>,
+    pub exchange: Option<String>,
+}
+
+impl FeedEvent {
+    fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            ..Default::default()
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            message: Some(message),
+            ..Self::new("error")
+        }
+    }
+
+    fn for_symbol(kind: &'static str, symbol: &str) -> Self {
+        Self {
+            symbol: Some(symbol.to_string()),
+            ..Self::new(kind)
+        }
+    }
+
+    fn private(update: Update, recv_ns: i64) -> Self {
+        let recv_ns = Some(recv_ns);
+        match update {
+            Update::Order {
+                symbol,
+                client_id,
+                exchange_id,
+                status,
+                side,
+                price,
+                qty,
+                ts,
+            } => Self {
+                ts,
+                client_id,
+                exchange_id,
+                status: Some(status),
+                side: side.map(Side::name),
+                price,
+                size: qty,
+                recv_ns,
+                ..Self::for_symbol("order", &symbol)
+            },
+            Update::Fill {
+                symbol,
+                client_id,
+                exchange_id,
+                side,
+                price,
+                qty,
+                fee,
+                is_maker,
+                ts,
+            } => Self {
+                ts,
+                client_id,
+                exchange_id,
+                side: Some(side.name()),
+                price: Some(price),
+                size: Some(qty),
+                fee: Some(fee),
+                liquidity: is_maker.map(|m| if m { "maker" } else { "taker" }),
+                recv_ns,
+                ..Self::for_symbol("fill", &symbol)
+            },
+            Update::Position {
+                symbol,
+                side,
+                size,
+                avg_price,
+                ts,
+            } => Self {
+                ts,
+                side: side.map(Side::name),
+                size: Some(size),
+                price: avg_price,
+                recv_ns,
+                ..Self::for_symbol("position", &symbol)
+            },
+        }
+    }
+
+    fn required<T>(&self, value: Option<T>, field: &str) -> PyResult<T> {
+        value.ok_or_else(|| PyValueError::new_err(format!("{} event has no {}", self.kind, field)))
+    }
+}
+
+// Python callbacks registered with FeedClient.set_callbacks. on_connected
+// and on_disconnected take no arguments, on_desynced and on_synced the
+// symbol. They run on the feed thread.
+#[derive(Default)]
+struct FeedHooks {
+    on_connected: Option<Arc<Py<PyAny>>>,
+    on_disconnected: Option<Arc<Py<PyAny>>>,
+    on_desynced: Option<Arc<Py<PyAny>>>,
+    on_synced: Option<Arc<Py<PyAny>>>,
+}
+
+impl FeedHooks {
+    fn new(
+        listener: Option<&Bound<'_, PyAny>>,
+        on_connected: Option<Bound<'_, PyAny>>,
+        on_disconnected: Option<Bound<'_, PyAny>>,
+        on_desynced: Option<Bound<'_, PyAny>>,
+        on_synced: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let hook = |name: &str, explicit: Option<Bound<'_, PyAny>>| -> PyResult<_> {
+            let f = match (explicit, listener) {
+                (Some(f), _) => f,
+                (None, Some(l)) if l.hasattr(name)? => l.getattr(name)?,
+                _ => return Ok(None),
+            };
+            if !f.is_callable() {
+                return Err(PyValueError::new_err(format!("{} must be callable", name)));
+            }
+            Ok(Some(Arc::new(f.unbind())))
+        };
+        Ok(Self {
+            on_connected: hook("on_connected", on_connected)?,
+            on_disconnected: hook("on_disconnected", on_disconnected)?,
+            on_desynced: hook("on_desynced", on_desynced)?,
+            on_synced: hook("on_synced", on_synced)?,
+        })
+    }
+
+    fn get(&self, kind: &str) -> Option<Arc<Py<PyAny>>> {
+        match kind {
+            "connected" => self.on_connected.clone(),
+            "disconnected" => self.on_disconnected.clone(),
+            "desynced" => self.on_desynced.clone(),
+            "synced" => self.on_synced.clone(),
+            _ => None,
+        }
+    }
+}
+
+#[pymethods]
+impl FeedEvent {
+    #[new]
+    #[pyo3(signature = (kind, symbol=None, ts=None, update_id=None, applied=false, price=None, size=None, side=None, message=None, recv_ns=None, client_id=None, exchange_id=None, status=None, fee=None, liquidity=None, exchange=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        kind: &str,
+        symbol: Option<String>,
+        ts: Option<i64>,
+        update_id: Option<u64>,
+        applied: bool,
+        price: Option<f64>,
+        size: Option<f64>,
+        side: Option<&str>,
+        message: Option<String>,
+        recv_ns: Option<i64>,
+        client_id: Option<String>,
+        exchange_id: Option<String>,
+        status: Option<String>,
+        fee: Option<f64>,
+        liquidity: Option<&str>,
+        exchange: Option<String>,
+    ) -> PyResult<Self> {
+        let Some(&kind) = KINDS.iter().find(|&&k| k == kind) else {
+            return Err(PyValueError::new_err(format!(
+                "kind must be one of {}, got '{}'",
+                KINDS.join(", "),
+                kind
+            )));
+        };
+        Ok(Self {
+            kind,
+            symbol,
+            ts,
+            update_id,
+            applied,
+            price,
+            size,
+            side: side.map(Side::parse).transpose()?.map(Side::name),
+            message,
+            recv_ns,
+            client_id,
+            exchange_id,
+            status,
+            fee,
+            liquidity: liquidity
+                .map(Liquidity::parse)
+                .transpose()?
+                .map(Liquidity::name),
+            exchange,
+        })
+    }
+
+    // The normalized event (see normalized.rs): a BookUpdate for "book", a
+    // Trade, OrderUpdate or Fill for "trade", "order" and "fill", None for
+    // connection, sync, error and position events. ValueError if the event
+    // lacks its exchange or a field the normalized class requires.
+    pub fn normalize(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        if !matches!(self.kind, "book" | "trade" | "order" | "fill") {
+            return Ok(None);
+        }
+        let exchange = self.required(self.exchange.clone(), "exchange")?;
+        let symbol = self.required(self.symbol.clone(), "symbol")?;
+        let event = match self.kind {
+            "book" => BookUpdate {
+                exchange: Some(exchange),
+                symbol: Some(symbol),
+                kind: match self.status.as_deref() {
+                    Some("snapshot") => "snapshot",
+                    _ => "delta",
+                },
+                update_id: self.update_id,
+                ts: self.ts,
+                recv_ns: self.recv_ns,
+                applied: self.applied,
+                ..Default::default()
+            }
+            .into_py_any(py)?,
+            "trade" => normalized::Trade {
+                exchange,
+                symbol,
+                price: self.required(self.price, "price")?,
+                size: self.required(self.size, "size")?,
+                side: self.side,
+                ts: self.ts,
+                recv_ns: self.recv_ns,
+            }
+            .into_py_any(py)?,
+            "order" => {
+                let venue_status = self.required(self.status.clone(), "status")?;
+                OrderUpdate {
+                    exchange,
+                    symbol,
+                    client_id: self.client_id.clone(),
+                    exchange_id: self.exchange_id.clone(),
+                    status: normalized::status_name(&venue_status),
+                    venue_status,
+                    side: self.side,
+                    price: self.price,
+                    qty: self.size,
+                    filled_qty: None,
+                    ts: self.ts,
+                    recv_ns: self.recv_ns,
+                }
+                .into_py_any(py)?
+            }
+            _ => Fill {
+                exchange,
+                symbol,
+                client_id: self.client_id.clone(),
+                exchange_id: self.exchange_id.clone(),
+                trade_id: None,
+                side: self.required(self.side, "side")?,
+                price: self.required(self.price, "price")?,
+                qty: self.required(self.size, "size")?,
+                fee: self.fee,
+                liquidity: self.liquidity,
+                ts: self.ts,
+                recv_ns: self.recv_ns,
+            }
+            .into_py_any(py)?,
+        };
+        Ok(Some(event))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FeedEvent(kind={:?}, symbol={:?}, ts={:?}, update_id={:?}, applied={}, price={:?}, size={:?}, side={:?}, message={:?}, recv_ns={:?}, client_id={:?}, exchange_id={:?}, status={:?}, fee={:?}, liquidity={:?}, exchange={:?})",
+            self.kind,
+            self.symbol,
+            self.ts,
+            self.update_id,
+            if self.applied { "True" } else { "False" },
+            self.price,
+            self.size,
+            self.side,
+            self.message,
+            self.recv_ns,
+            self.client_id,
+            self.exchange_id,
+            self.status,
+            self.fee,
+            self.liquidity,
+            self.exchange
+        )
+    }
+}
+
+// Shared between the client and its thread
+struct State {
+    running: AtomicBool,
+    connected: AtomicBool,
+    events: Mutex<VecDeque<FeedEvent>>,
+    ready: Condvar,
+    max_events: usize,
+    dropped: AtomicU64,
+    reconnects: AtomicU64,
+    hooks: Mutex<FeedHooks>,
+    // Replaces `events` when the client was given an EventQueue
+    queue: Option<Producer>,
+    // Tasks awaiting next_event()
+    waiters: Waiters,
+    // Targets for private order and execution updates
+    routing: Mutex<Routing>,
+    exchange: Exchange,
+}
+
+impl State {
+    // A full queue drops its oldest event; a full EventQueue the new one
+    fn push(&self, mut event: FeedEvent) {
+        event.exchange = Some(self.exchange.name().to_string());
+        if let Some(queue) = &self.queue {
+            if !queue.push(event) {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            return;
+        }
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if events.len() >= self.max_events {
+            events.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        events.push_back(event);
+        drop(events);
+        self.ready.notify_all();
+        self.waiters.wake();
+    }
+
+    // Queue a state event and run its callback, cloned out of the hooks lock
+    // so no lock is held while the callback has the GIL. A raising callback
+    // becomes an "error" event.
+    fn transition(&self, kind: &'static str, symbol: Option<&str>) {
+        self.push(FeedEvent {
+            symbol: symbol.map(str::to_string),
+            ..FeedEvent::new(kind)
+        });
+        let hook = self
+            .hooks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(kind);
+        let Some(f) = hook else {
+            return;
+        };
+        let failed = Python::with_gil(|py| {
+            let result = match symbol {
+                Some(s) => f.call1(py, (s,)),
+                None => f.call0(py),
+            };
+            result.err().map(|e| e.to_string())
+        });
+        if let Some(e) = failed {
+            self.push(FeedEvent::error(format!("{} callback failed: {}", kind, e)));
+        }
+    }
+}
+
+impl EventSource for State {
+    fn try_next(&self) -> PyResult<Option<FeedEvent>> {
+        Ok(self
+            .events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front())
+    }
+
+    fn waiters(&self) -> &Waiters {
+        &self.waiters
+    }
+}
+
+// Per-book resync bookkeeping, owned by the thread
+#[derive(Default)]
+struct Resync {
+    // Deltas received while the book waits for a snapshot
+    buffer: VecDeque<bybit::BookMessage>,
+    // When a snapshot was last requested (or subscribed for)
+    requested: Option<Instant>,
+}
+
+type Books = HashMap<String, Arc<RwLock<L2Book>>>;
+type Tapes = HashMap<String, Arc<Mutex<TradeTape>>>;
+
+// What the background thread owns
+struct Worker {
+    url: String,
+    topics: Vec<String>,
+    // (api_key, api_secret) for a private stream
+    credentials: Option<(String, String)>,
+    books: Books,
+    tapes: Tapes,
+    state: Arc<State>,
+    reconnect: bool,
+    reconnect_delay: Duration,
+    max_reconnect_delay: Duration,
+    max_buffer: usize,
+    resync: HashMap<String, Resync>,
+}
+
+impl Worker {
+    fn run(mut self) {
+        let mut delay = self.reconnect_delay;
+        loop {
+            if let Err(e) = self.session() {
+                self.state.push(FeedEvent::error(e.to_string()));
+            }
+            let was_connected = self.state.connected.swap(false, Ordering::AcqRel);
+            self.state.transition("disconnected", None);
+            self.desync_all();
+            if !self.reconnect || !self.state.running.load(Ordering::Acquire) {
+                break;
+            }
+            if was_connected {
+                delay = self.reconnect_delay;
+            }
+            // Sleep in short steps so stop() is not held up by the backoff
+            let until = Instant::now() + delay;
+            while self.state.running.load(Ordering::Acquire) && Instant::now() < until {
+                thread::sleep(READ_TIMEOUT.min(until - Instant::now()));
+            }
+            if !self.state.running.load(Ordering::Acquire) {
+                break;
+            }
+            delay = (delay * 2).min(self.max_reconnect_delay);
+            self.state.reconnects.fetch_add(1, Ordering::Relaxed);
+        }
+        self.state.running.store(false, Ordering::Release);
+    }
+
+    fn session(&mut self) -> io::Result<()> {
+        let mut conn = WsConn::connect(&self.url, CONNECT_TIMEOUT, READ_TIMEOUT)?;
+        if let Some((key, secret)) = &self.credentials {
+            conn.send_text(&userstream::auth_message(key, secret))?;
+        }
+        let args: Vec<String> = self.topics.iter().map(|t| format!("\"{}\"", t)).collect();
+        conn.send_text(&format!(
+            "{{\"op\":\"subscribe\",\"args\":[{}]}}",
+            args.join(",")
+        ))?;
+        // The subscription brings a snapshot for every book
+        let now = Instant::now();
+        for resync in self.resync.values_mut() {
+            resync.requested = Some(now);
+        }
+        self.state.connected.store(true, Ordering::Release);
+        self.state.transition("connected", None);
+        let mut last_ping = Instant::now();
+        while self.state.running.load(Ordering::Acquire) {
+            if last_ping.elapsed() >= HEARTBEAT {
+                conn.send_text("{\"op\":\"ping\"}")?;
+                last_ping = Instant::now();
+            }
+            match conn.read_message()? {
+                Some(Message::Text(text)) => {
+                    if let Some(topic) = self.handle(&text, clock::monotonic_ns())? {
+                        // Bybit answers a fresh subscription with a snapshot
+                        conn.send_text(&format!(
+                            "{{\"op\":\"unsubscribe\",\"args\":[\"{}\"]}}",
+                            topic
+                        ))?;
+                        conn.send_text(&format!(
+                            "{{\"op\":\"subscribe\",\"args\":[\"{}\"]}}",
+                            topic
+                        ))?;
+                    }
+                }
+                Some(Message::Binary(data)) => self.state.push(FeedEvent::error(format!(
+                    "unexpected binary message of {} bytes",
+                    data.len()
+                ))),
+                None => {}
+                Some(Message::Close) => return Ok(()),
+            }
+        }
+        conn.close();
+        Ok(())
+    }
+
+    // Nothing received after a disconnect can be trusted until the next
+    // subscription delivers a snapshot
+    fn desync_all(&mut self) {
+        let mut desynced = Vec::new();
+        for (symbol, book) in &self.books {
+            let mut book = book.write().unwrap_or_else(PoisonError::into_inner);
+            if !book.needs_resync {
+                book.needs_resync = true;
+                desynced.push(symbol.clone());
+            }
+        }
+        self.resync.clear();
+        desynced.sort();
+        for symbol in desynced {
+            self.state.transition("desynced", Some(&symbol));
+        }
+    }
+
+    // Returns an orderbook topic whose snapshot must be requested. A refused
+    // auth ends the session.
+    fn handle(&mut self, text: &str, recv_ns: i64) -> io::Result<Option<String>> {
+        let root = match json::parse(text) {
+            Ok(root) => root,
+            Err(e) => {
+                self.state
+                    .push(FeedEvent::error(format!("invalid JSON: {}", e)));
+                return Ok(None);
+            }
+        };
+        if root.get("op").and_then(Value::as_str) == Some("auth")
+            && !matches!(root.get("success"), Some(Value::Bool(true)))
+        {
+            let msg = root.get("ret_msg").and_then(Value::as_str).unwrap_or("");
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("auth failed: {}", msg),
+            ));
+        }
+        // Subscription acks and pongs carry no topic
+        let Some(topic) = root.get("topic").and_then(Value::as_str) else {
+            return Ok(None);
+        };
+        let symbol = topic.rsplit('.').next().unwrap_or_default().to_string();
+        if topic.starts_with("orderbook.") {
+            return Ok(self.on_book(&symbol, &root, recv_ns));
+        } else if topic.starts_with("publicTrade.") {
+            self.on_trades(&symbol, &root, recv_ns);
+        } else if userstream::is_private(topic) {
+            self.on_private(topic, &root, recv_ns);
+        }
+        Ok(None)
+    }
+
+    // Route into the attached OrderManager / Positions first, so Python
+    // sees them updated by the time it reads the events
+    fn on_private(&self, topic: &str, root: &Value, recv_ns: i64) {
+        let updates = userstream::parse(topic, root);
+        let routing = self
+            .state
+            .routing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let failed: Vec<String> = if routing.is_empty() || updates.is_empty() {
+            Vec::new()
+        } else {
+            Python::with_gil(|py| {
+                updates
+                    .iter()
+                    .filter_map(|u| routing.apply(py, u).err().map(|e| e.to_string()))
+                    .collect()
+            })
+        };
+        for update in updates {
+            self.state.push(FeedEvent::private(update, recv_ns));
+        }
+        for e in failed {
+            self.state
+                .push(FeedEvent::error(format!("routing failed: {}", e)));
+        }
+    }
+
+    fn on_book(&mut self, symbol: &str, root: &Value, recv_ns: i64) -> Option<String> {
+        let book = self.books.get(symbol)?;
+        let msg = match bybit::parse_book_message(root) {
+            Ok(msg) => msg,
+            Err(e) => {
+                self.state.push(FeedEvent::error(e.to_string()));
+                return None;
+            }
+        };
+        let resync = self.resync.entry(symbol.to_string()).or_default();
+        let event = FeedEvent {
+            ts: msg.ts,
+            update_id: Some(msg.update_id),
+            recv_ns: Some(recv_ns),
+            status: Some(
+                if msg.is_snapshot() {
+                    "snapshot"
+                } else {
+                    "delta"
+                }
+                .to_string(),
+            ),
+            ..FeedEvent::for_symbol("book", symbol)
+        };
+        let topic = msg.topic.clone();
+        let (before, after, result) = {
+            let mut book = book.write().unwrap_or_else(PoisonError::into_inner);
+            let before = book.needs_resync;
+            let result = if msg.is_snapshot() {
+                let snapshot_id = msg.update_id;
+                let mut result = book
+                    .load_snapshot(msg.bids, msg.asks, Some(snapshot_id))
+                    .map(|_| true)
+                    .map_err(PyErr::from);
+                resync.requested = None;
+                for d in resync.buffer.drain(..) {
+                    if d.update_id > snapshot_id && result.is_ok() {
+                        result = book
+                            .delta(d.bids, d.asks, Some(d.update_id), None, None)
+                            .map(|_| true);
+                    }
+                }
+                result
+            } else if before {
+                if resync.buffer.len() >= self.max_buffer {
+                    resync.buffer.pop_front();
+                }
+                resync.buffer.push_back(msg);
+                Ok(false)
+            } else {
+                book.delta(msg.bids, msg.asks, Some(msg.update_id), None, None)
+            };
+            (before, book.needs_resync, result)
+        };
+        match result {
+            Ok(applied) => self.state.push(FeedEvent { applied, ..event }),
+            Err(e) => self.state.push(FeedEvent::error(e.to_string())),
+        }
+        if before && !after {
+            self.state.transition("synced", Some(symbol));
+        } else if !before && after {
+            self.state.transition("desynced", Some(symbol));
+        }
+        if !after {
+            return None;
+        }
+        let resync = self.resync.entry(symbol.to_string()).or_default();
+        match resync.requested {
+            Some(at) if at.elapsed() < SNAPSHOT_TIMEOUT => None,
+            _ => {
+                resync.requested = Some(Instant::now());
+                Some(topic)
+            }
+        }
+    }
+
+    fn on_trades(&self, symbol: &str, root: &Value, recv_ns: i64) {
+        let Some(tape) = self.tapes.get(symbol) else {
+            return;
+        };
+        let trades: Vec<Trade> = root
+            .get("data")
+            .and_then(Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|t| {
+                Some(Trade {
+                    ts: t.get("T")?.as_i64()?,
+                    price: t.get("p")?.as_f64()?,
+                    size: t.get("v")?.as_f64()?,
+                    side: Side::parse(t.get("S")?.as_str()?).ok()?,
+                })
+            })
+            .collect();
+        {
+            let mut tape = tape.lock().unwrap_or_else(PoisonError::into_inner);
+            for t in &trades {
+                tape.push(*t);
+            }
+        }
+        for t in trades {
+            self.state.push(FeedEvent {
+                symbol: Some(symbol.to_string()),
+                ts: Some(t.ts),
+                price: Some(t.price),
+                size: Some(t.size),
+                side: Some(t.side.name()),
+                recv_ns: Some(recv_ns),
+                ..FeedEvent::new("trade")
+            });
+        }
+    }
+}
+
+#[pyclass(frozen)]
+pub struct FeedClient {
+    url: String,
+    topics: Vec<String>,
+    credentials: Option<(String, String)>,
+    books: Books,
+    tapes: Tapes,
+    reconnect: bool,
+    reconnect_delay: Duration,
+    max_reconnect_delay: Duration,
+    max_buffer: usize,
+    state: Arc<State>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Drop for FeedClient {
+    // The thread notices within READ_TIMEOUT and exits on its own
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::Release);
+    }
+}
+
+#[pymethods]
+impl FeedClient {
+    // topics are exchange stream names; each orderbook topic gets a book and
+    // each trade topic a rolling TradeTape of trade_window_ms. Books start
+    // out desynced (needs_resync) until their first snapshot. With queue=
+    // events go to that EventQueue instead of poll(); the client is its
+    // only producer while alive. api_key/api_secret authenticate a private
+    // stream (order, execution and position topics), which Bybit serves on
+    // its own URL, so those topics cannot be mixed with public ones; the URL
+    // must then be wss://, or ws:// to a loopback host.
+    #[new]
+    #[pyo3(signature = (
+        url,
+        topics,
+        exchange="bybit",
+        trade_window_ms=60_000,
+        max_events=100_000,
+        reconnect=true,
+        reconnect_delay_ms=500,
+        max_reconnect_delay_ms=30_000,
+        max_buffer=10_000,
+        queue=None,
+        api_key=None,
+        api_secret=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        url: &str,
+        topics: Vec<String>,
+        exchange: &str,
+        trade_window_ms: i64,
+        max_events: usize,
+        reconnect: bool,
+        reconnect_delay_ms: u64,
+        max_reconnect_delay_ms: u64,
+        max_buffer: usize,
+        queue: Option<&EventQueue>,
+        api_key: Option<String>,
+        api_secret: Option<String>,
+    ) -> PyResult<Self> {
+        let exchange = Exchange::parse(exchange)?;
+        let endpoint = ws::parse_url(url).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if max_events == 0 {
+            return Err(PyValueError::new_err("max_events must be positive"));
+        }
+        if max_buffer == 0 {
+            return Err(PyValueError::new_err("max_buffer must be positive"));
+        }
+        if reconnect_delay_ms == 0 || max_reconnect_delay_ms < reconnect_delay_ms {
+            return Err(PyValueError::new_err(format!(
+                "reconnect delays must satisfy 0 < reconnect_delay_ms <= max_reconnect_delay_ms, got {} and {}",
+                reconnect_delay_ms, max_reconnect_delay_ms
+            )));
+        }
+        let credentials = match (api_key, api_secret) {
+            (Some(key), Some(secret)) if !secret.is_empty() => {
+                check_token("api_key", &key)?;
+                // The auth frame carries the key and a signature
+                if !endpoint.tls && !endpoint.is_loopback() {
+                    return Err(PyValueError::new_err(format!(
+                        "private streams need wss://, or ws:// to a loopback host; got '{}'",
+                        url
+                    )));
+                }
+                Some((key, secret))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "api_key and api_secret must be given together",
+                ))
+            }
+        };
+        let private = topics.iter().filter(|t| userstream::is_private(t)).count();
+        if private > 0 && credentials.is_none() {
+            return Err(PyValueError::new_err(
+                "private topics need api_key and api_secret",
+            ));
+        }
+        if private > 0 && private < topics.len() {
+            return Err(PyValueError::new_err(
+                "private and public topics need separate clients",
+            ));
+        }
+        let mut books = Books::new();
+        let mut tapes = Tapes::new();
+        for topic in &topics {
+            let symbol = topic.rsplit('.').next().unwrap_or_default().to_string();
+            if topic.contains(['"', '\\']) || symbol.is_empty() {
+                return Err(PyValueError::new_err(format!("invalid topic '{}'", topic)));
+            }
+            if userstream::is_private(topic) {
+                continue;
+            }
+            if topic.starts_with("orderbook.") {
+                let book = L2Book {
+                    needs_resync: true,
+                    ..L2Book::default()
+                };
+                books.insert(symbol, Arc::new(RwLock::new(book)));
+            } else if topic.starts_with("publicTrade.") {
+                tapes.insert(
+                    symbol,
+                    Arc::new(Mutex::new(TradeTape::new(trade_window_ms, None)?)),
+                );
+            } else {
+                return Err(PyValueError::new_err(format!(
+                    "unsupported topic '{}': expected orderbook.*, publicTrade.*, order, execution or position",
+                    topic
+                )));
+            }
+        }
+        Ok(Self {
+            url: url.to_string(),
+            topics,
+            credentials,
+            books,
+            tapes,
+            reconnect,
+            reconnect_delay: Duration::from_millis(reconnect_delay_ms),
+            max_reconnect_delay: Duration::from_millis(max_reconnect_delay_ms),
+            max_buffer,
+            state: Arc::new(State {
+                running: AtomicBool::new(false),
+                connected: AtomicBool::new(false),
+                events: Mutex::new(VecDeque::new()),
+                ready: Condvar::new(),
+                max_events,
+                dropped: AtomicU64::new(0),
+                reconnects: AtomicU64::new(0),
+                hooks: Mutex::new(FeedHooks::default()),
+                queue: queue.map(EventQueue::producer).transpose()?,
+                waiters: Waiters::default(),
+                routing: Mutex::new(Routing::default()),
+                exchange,
+            }),
+            thread: Mutex::new(None),
+        })
+    }
+
+    // Connect and subscribe on the background thread. Connection failures
+    // show up as "error" and "disconnected" events; with reconnect=False the
+    // thread ends after the first session.
+    pub fn start(&self, py: Python<'_>) -> PyResult<()> {
+        let mut thread = self.thread.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.state.running.swap(true, Ordering::AcqRel) {
+            return Err(PyValueError::new_err("feed is already running"));
+        }
+        // Reap a previous session that ended on its own
+        if let Some(old) = thread.take() {
+            py.allow_threads(|| old.join()).ok();
+        }
+        let worker = Worker {
+            url: self.url.clone(),
+            topics: self.topics.clone(),
+            credentials: self.credentials.clone(),
+            books: self.books.clone(),
+            tapes: self.tapes.clone(),
+            state: Arc::clone(&self.state),
+            reconnect: self.reconnect,
+            reconnect_delay: self.reconnect_delay,
+            max_reconnect_delay: self.max_reconnect_delay,
+            max_buffer: self.max_buffer,
+            resync: HashMap::new(),
+        };
+        let handle = thread::Builder::new()
+            .name("mm-feed".into())
+            .spawn(move || worker.run())
+            .map_err(|e| {
+                self.state.running.store(false, Ordering::Release);
+                PyValueError::new_err(format!("could not start feed thread: {}", e))
+            })?;
+        *thread = Some(handle);
+        Ok(())
+    }
+
+    // Close the connection and wait for the thread to finish
+    pub fn stop(&self, py: Python<'_>) {
+        self.state.running.store(false, Ordering::Release);
+        let handle = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(handle) = handle {
+            py.allow_threads(|| handle.join()).ok();
+        }
+    }
+
+    // Drain up to max_events queued events, waiting up to timeout_ms (with
+    // the GIL released) if none are queued yet
+    #[pyo3(signature = (timeout_ms=0.0, max_events=usize::MAX))]
+    pub fn poll(
+        &self,
+        py: Python<'_>,
+        timeout_ms: f64,
+        max_events: usize,
+    ) -> PyResult<Vec<FeedEvent>> {
+        if self.state.queue.is_some() {
+            return Err(PyValueError::new_err(
+                "events go to the client's EventQueue; pop them from there",
+            ));
+        }
+        Ok(py.allow_threads(|| {
+            let mut events = self
+                .state
+                .events
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if events.is_empty() && timeout_ms > 0.0 {
+                let timeout = Duration::from_secs_f64(timeout_ms / 1000.0);
+                events = self
+                    .state
+                    .ready
+                    .wait_timeout_while(events, timeout, |e| e.is_empty())
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0;
+            }
+            let n = max_events.min(events.len());
+            events.drain(..n).collect()
+        }))
+    }
+
+    // Awaitable for the next event, woken by the feed thread:
+    // `event = await feed.next_event()`. Shares the queue with poll().
+    pub fn next_event(&self) -> PyResult<NextEvent> {
+        if self.state.queue.is_some() {
+            return Err(PyValueError::new_err(
+                "events go to the client's EventQueue; await its next_event()",
+            ));
+        }
+        Ok(NextEvent::new(Arc::clone(&self.state) as _))
+    }
+
+    // Register state callbacks as explicit callables or as same-named methods
+    // of `listener`; explicit ones win. They run on the feed thread (holding
+    // the GIL but no feed lock) right after the matching event is queued, so
+    // keep them short, e.g. cancel quotes on on_desynced. Call stop() before
+    // interpreter shutdown. set_callbacks() with no arguments clears them.
+    #[pyo3(signature = (listener=None, on_connected=None, on_disconnected=None, on_desynced=None, on_synced=None))]
+    pub fn set_callbacks(
+        &self,
+        listener: Option<&Bound<'_, PyAny>>,
+        on_connected: Option<Bound<'_, PyAny>>,
+        on_disconnected: Option<Bound<'_, PyAny>>,
+        on_desynced: Option<Bound<'_, PyAny>>,
+        on_synced: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        let hooks = FeedHooks::new(
+            listener,
+            on_connected,
+            on_disconnected,
+            on_desynced,
+            on_synced,
+        )?;
+        *self
+            .state
+            .hooks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = hooks;
+        Ok(())
+    }
+
+    // Route private order updates and fills into `orders` and fills into
+    // the Position of their symbol in `positions`; runs on the feed thread
+    // with the GIL before the matching events are queued. Routing errors
+    // become "error" events. set_routing() with no arguments clears it.
+    #[pyo3(signature = (orders=None, positions=None))]
+    pub fn set_routing(
+        &self,
+        orders: Option<Py<OrderManager>>,
+        positions: Option<HashMap<String, Py<Position>>>,
+    ) {
+        let routing = Routing {
+            orders: orders.map(Arc::new),
+            positions: positions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(symbol, p)| (symbol, Arc::new(p)))
+                .collect(),
+        };
+        *self
+            .state
+            .routing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = routing;
+    }
+
+    // Live handle to a symbol's book, written by the feed thread
+    pub fn book(&self, symbol: &str) -> Option<SharedL2Book> {
+        self.books
+            .get(symbol)
+            .map(|b| SharedL2Book::from_handle(Arc::clone(b)))
+    }
+
+    // Copy of a symbol's trade tape
+    pub fn trades(&self, symbol: &str) -> Option<TradeTape> {
+        self.tapes
+            .get(symbol)
+            .map(|t| t.lock().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .books
+            .keys()
+            .chain(self.tapes.keys())
+            .cloned()
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    #[getter]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    #[getter]
+    pub fn is_running(&self) -> bool {
+        self.state.running.load(Ordering::Acquire)
+    }
+
+    #[getter]
+    pub fn is_connected(&self) -> bool {
+        self.state.connected.load(Ordering::Acquire)
+    }
+
+    // Reconnect attempts since the client was created
+    #[getter]
+    pub fn reconnects(&self) -> u64 {
+        self.state.reconnects.load(Ordering::Relaxed)
+    }
+
+    // Events discarded because the queue was full
+    #[getter]
+    pub fn dropped(&self) -> u64 {
+        self.state.dropped.load(Ordering::Relaxed)
+    }
+}
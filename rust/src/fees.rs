@@ -0,0 +1,130 @@
+// Maker/taker fees in bps of notional with 30-day volume tiers.
+// Negative bps are rebates (fee_for returns a negative fee).
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::state::{self, persist_fields, Stateful};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+impl Liquidity {
+    pub fn parse(flag: &str) -> PyResult<Self> {
+        match flag.to_ascii_lowercase().as_str() {
+            "maker" | "m" => Ok(Self::Maker),
+            "taker" | "t" => Ok(Self::Taker),
+            other => Err(PyValueError::new_err(format!(
+                "liquidity must be 'maker' or 'taker', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Maker => "maker",
+            Self::Taker => "taker",
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct FeeModel {
+    // (min 30d volume, maker bps, taker bps), sorted by volume
+    tiers: Vec<(f64, f64, f64)>,
+    #[pyo3(get, set)]
+    pub volume_30d: f64,
+}
+
+persist_fields!(FeeModel { tiers, volume_30d });
+
+impl Stateful for FeeModel {
+    const NAME: &'static str = "FeeModel";
+}
+
+impl FeeModel {
+    fn tier(&self) -> (f64, f64, f64) {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|t| self.volume_30d >= t.0)
+            .copied()
+            .unwrap_or(self.tiers[0])
+    }
+
+    pub fn fee(&self, price: f64, qty: f64, liquidity: Liquidity) -> f64 {
+        let (_, maker, taker) = self.tier();
+        let bps = match liquidity {
+            Liquidity::Maker => maker,
+            Liquidity::Taker => taker,
+        };
+        price * qty.abs() * bps / 10_000.0
+    }
+}
+
+#[pymethods]
+impl FeeModel {
+    // tiers: optional [(min_30d_volume, maker_bps, taker_bps), ...]; the base
+    // maker/taker rates apply below the first tier
+    #[new]
+    #[pyo3(signature = (maker_bps, taker_bps, tiers=None, volume_30d=0.0))]
+    pub fn new(
+        maker_bps: f64,
+        taker_bps: f64,
+        tiers: Option<Vec<(f64, f64, f64)>>,
+        volume_30d: f64,
+    ) -> Self {
+        let mut all = vec![(f64::NEG_INFINITY, maker_bps, taker_bps)];
+        all.extend(tiers.unwrap_or_default());
+        all.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            tiers: all,
+            volume_30d,
+        }
+    }
+
+    // Fee in quote currency for a fill; liquidity is "maker" or "taker"
+    pub fn fee_for(&self, price: f64, qty: f64, liquidity: &str) -> PyResult<f64> {
+        Ok(self.fee(price, qty, Liquidity::parse(liquidity)?))
+    }
+
+    #[getter]
+    pub fn maker_bps(&self) -> f64 {
+        self.tier().1
+    }
+
+    #[getter]
+    pub fn taker_bps(&self) -> f64 {
+        self.tier().2
+    }
+
+    // Index of the active tier (0 = base rates)
+    #[getter]
+    pub fn tier_index(&self) -> usize {
+        self.tiers
+            .iter()
+            .rposition(|t| self.volume_30d >= t.0)
+            .unwrap_or(0)
+    }
+
+    pub fn add_volume(&mut self, notional: f64) {
+        self.volume_30d += notional.abs();
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
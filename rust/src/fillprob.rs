@@ -0,0 +1,348 @@
+// Probability that a passive quote at ladder level k (0 = touch) gets at
+// least partly filled within a horizon T. Per side and level it learns, from
+// book observations and public trades, with exponential forgetting:
+//   trade rate      lambda  prints at the level per second
+//   mean print size s
+//   trade-through   theta   sweeps through the level per second
+//   cancel rate     kappa   cancelled size per unit of resting size per second
+// Cancels shrink the queue ahead to Q' = Q * exp(-kappa * T); the quote fills
+// if a sweep goes through it or at least n = floor(Q' / s) + 1 prints hit it:
+//   P = 1 - exp(-theta * T) * P(N < n),  N ~ Poisson(lambda * T)
+// Trades are attributed to levels using the ladder from the last observe(),
+// so the usual feed order (trade before the depth update) works out.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::queue::QueueTracker;
+use crate::{L2Book, Side};
+
+#[derive(Default, Clone)]
+struct LevelStats {
+    trades: f64,
+    volume: f64,
+    throughs: f64,
+    cancels: f64,
+    // Resting size integrated over time, size * seconds
+    queue_time: f64,
+}
+
+impl LevelStats {
+    fn scale(&mut self, f: f64) {
+        self.trades *= f;
+        self.volume *= f;
+        self.throughs *= f;
+        self.cancels *= f;
+        self.queue_time *= f;
+    }
+}
+
+// A level as of the last observe, with the trades seen since
+struct Level {
+    price: f64,
+    size: f64,
+    prints: f64,
+    traded: f64,
+    swept: bool,
+}
+
+#[derive(Default)]
+struct SideState {
+    stats: Vec<LevelStats>,
+    last: Vec<Level>,
+}
+
+impl SideState {
+    fn account(&mut self, book: &L2Book, side: Side, dt: f64, decay: f64) {
+        for st in self.stats.iter_mut() {
+            st.scale(decay);
+        }
+        for (lvl, st) in self.last.iter().zip(self.stats.iter_mut()) {
+            st.queue_time += lvl.size * dt;
+            st.trades += lvl.prints;
+            st.volume += lvl.traded;
+            if lvl.swept {
+                st.throughs += 1.0;
+                continue;
+            }
+            // Whatever left the level without trading was cancelled
+            let cancelled = lvl.size - book.level_size(side, lvl.price) - lvl.traded;
+            if cancelled > 0.0 {
+                st.cancels += cancelled;
+            }
+        }
+    }
+
+    // Index of the first level not better than `price`
+    fn rank(&self, side: Side, price: f64) -> usize {
+        self.last
+            .iter()
+            .take_while(|l| match side {
+                Side::Bid => l.price > price,
+                Side::Ask => l.price < price,
+            })
+            .count()
+    }
+}
+
+// P(N >= n) for N ~ Poisson(mu)
+fn poisson_tail(mu: f64, n: u64) -> f64 {
+    if n == 0 {
+        return 1.0;
+    }
+    if mu <= 0.0 || n as f64 > mu + 40.0 * mu.sqrt() + 40.0 {
+        return 0.0;
+    }
+    let mut log_pmf = -mu;
+    let mut cdf = 0.0;
+    for i in 0..n {
+        if i > 0 {
+            log_pmf += mu.ln() - (i as f64).ln();
+        }
+        cdf += log_pmf.exp();
+    }
+    (1.0 - cdf).clamp(0.0, 1.0)
+}
+
+#[pyclass]
+pub struct FillProbability {
+    depth: usize,
+    halflife_ms: Option<f64>,
+    // Observed time in seconds, decayed like the level stats
+    elapsed: f64,
+    last_ts: Option<i64>,
+    bids: SideState,
+    asks: SideState,
+}
+
+impl FillProbability {
+    fn state(&self, side: Side) -> &SideState {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn check_level(&self, level: usize) -> PyResult<()> {
+        if level >= self.depth {
+            return Err(PyValueError::new_err(format!(
+                "level must be below depth {}, got {}",
+                self.depth, level
+            )));
+        }
+        Ok(())
+    }
+
+    // (lambda, mean print size, theta, kappa); None before any observed time
+    fn rates_of(&self, side: Side, level: usize) -> Option<(f64, f64, f64, f64)> {
+        if self.elapsed <= 0.0 {
+            return None;
+        }
+        let st = &self.state(side).stats[level];
+        let mean_size = if st.trades > 0.0 {
+            st.volume / st.trades
+        } else {
+            0.0
+        };
+        let kappa = if st.queue_time > 0.0 {
+            st.cancels / st.queue_time
+        } else {
+            0.0
+        };
+        Some((
+            st.trades / self.elapsed,
+            mean_size,
+            st.throughs / self.elapsed,
+            kappa,
+        ))
+    }
+
+    fn fill_prob(&self, side: Side, level: usize, ahead: f64, horizon_ms: f64) -> Option<f64> {
+        let (lambda, mean_size, theta, kappa) = self.rates_of(side, level)?;
+        let t = horizon_ms.max(0.0) / 1000.0;
+        let remaining = ahead.max(0.0) * (-kappa * t).exp();
+        let deplete = if mean_size > 0.0 {
+            poisson_tail(lambda * t, (remaining / mean_size).floor() as u64 + 1)
+        } else {
+            0.0
+        };
+        Some(1.0 - (-theta * t).exp() * (1.0 - deplete))
+    }
+
+    // Queue ahead when joining the back of a level now
+    fn level_size(&self, side: Side, level: usize) -> f64 {
+        self.state(side).last.get(level).map_or(0.0, |l| l.size)
+    }
+}
+
+#[pymethods]
+impl FillProbability {
+    // depth is how many levels per side are modelled; halflife_ms=None keeps
+    // all history with equal weight
+    #[new]
+    #[pyo3(signature = (depth=5, halflife_ms=Some(60_000.0)))]
+    pub fn new(depth: usize, halflife_ms: Option<f64>) -> PyResult<Self> {
+        if depth == 0 {
+            return Err(PyValueError::new_err("depth must be positive"));
+        }
+        if halflife_ms.is_some_and(|h| h.is_nan() || h <= 0.0) {
+            return Err(PyValueError::new_err("halflife_ms must be positive"));
+        }
+        let side = || SideState {
+            stats: vec![LevelStats::default(); depth],
+            last: Vec::new(),
+        };
+        Ok(Self {
+            depth,
+            halflife_ms,
+            elapsed: 0.0,
+            last_ts: None,
+            bids: side(),
+            asks: side(),
+        })
+    }
+
+    // Fold the interval since the previous call into the level stats and
+    // take the book's top `depth` levels as the new reference ladder.
+    // Timestamps older than the previous one are ignored.
+    pub fn observe(&mut self, book: &L2Book, ts: i64) {
+        if let Some(last) = self.last_ts {
+            if ts < last {
+                return;
+            }
+            let dt_ms = (ts - last) as f64;
+            let decay = self.halflife_ms.map_or(1.0, |h| 0.5f64.powf(dt_ms / h));
+            self.elapsed = self.elapsed * decay + dt_ms / 1000.0;
+            self.bids.account(book, Side::Bid, dt_ms / 1000.0, decay);
+            self.asks.account(book, Side::Ask, dt_ms / 1000.0, decay);
+        }
+        let top = |levels: &mut dyn Iterator<Item = (f64, f64)>| -> Vec<Level> {
+            levels
+                .take(self.depth)
+                .map(|(price, size)| Level {
+                    price,
+                    size,
+                    prints: 0.0,
+                    traded: 0.0,
+                    swept: false,
+                })
+                .collect()
+        };
+        self.bids.last = top(&mut book.bid_levels());
+        self.asks.last = top(&mut book.ask_levels());
+        self.last_ts = Some(ts);
+    }
+
+    // Public trade print; side is the aggressor ("buy" lifts asks). Without
+    // it the side is inferred from the last observed touch, and prints
+    // inside the spread are ignored.
+    #[pyo3(signature = (price, qty, side=None))]
+    pub fn on_trade(&mut self, price: f64, qty: f64, side: Option<&str>) -> PyResult<()> {
+        let hit = match side.map(Side::parse).transpose()? {
+            Some(Side::Bid) => Side::Ask,
+            Some(Side::Ask) => Side::Bid,
+            None => {
+                let best = |s: &SideState| s.last.first().map(|l| l.price);
+                match (best(&self.bids), best(&self.asks)) {
+                    (Some(b), _) if price <= b => Side::Bid,
+                    (_, Some(a)) if price >= a => Side::Ask,
+                    _ => return Ok(()),
+                }
+            }
+        };
+        let state = match hit {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+        let rank = state.rank(hit, price);
+        // Levels better than the print were swept, once per interval
+        for lvl in state.last.iter_mut().take(rank) {
+            lvl.swept = true;
+        }
+        if let Some(lvl) = state.last.get_mut(rank) {
+            if lvl.price == price {
+                lvl.prints += 1.0;
+                lvl.traded += qty;
+            }
+        }
+        Ok(())
+    }
+
+    // Fill probability within horizon_ms for a quote on `side` at `level`
+    // with queue_ahead in front of it (default: the level's visible size,
+    // i.e. joining at the back). None before any observed time.
+    #[pyo3(signature = (side, level, horizon_ms, queue_ahead=None))]
+    pub fn probability(
+        &self,
+        side: &str,
+        level: usize,
+        horizon_ms: f64,
+        queue_ahead: Option<f64>,
+    ) -> PyResult<Option<f64>> {
+        let side = Side::parse(side)?;
+        self.check_level(level)?;
+        let ahead = queue_ahead.unwrap_or_else(|| self.level_size(side, level));
+        Ok(self.fill_prob(side, level, ahead, horizon_ms))
+    }
+
+    // Probability of joining the back of each of the `depth` levels on one
+    // side, for choosing which ladder levels to quote
+    pub fn level_probabilities(&self, side: &str, horizon_ms: f64) -> PyResult<Vec<Option<f64>>> {
+        let side = Side::parse(side)?;
+        Ok((0..self.depth)
+            .map(|k| self.fill_prob(side, k, self.level_size(side, k), horizon_ms))
+            .collect())
+    }
+
+    // Probability for a resting order tracked by a QueueTracker, using its
+    // estimated queue ahead. None if the order is unknown or deeper than
+    // `depth` in the last observed ladder.
+    pub fn order_probability(
+        &self,
+        tracker: &QueueTracker,
+        order_id: u64,
+        horizon_ms: f64,
+    ) -> Option<f64> {
+        let (side, price, ahead) = tracker.order(order_id)?;
+        let level = self.state(side).rank(side, price);
+        if level >= self.depth {
+            return None;
+        }
+        self.fill_prob(side, level, ahead, horizon_ms)
+    }
+
+    // Learned rates for one level, per second: trade_rate, mean_trade_size,
+    // through_rate and cancel_rate (per unit of resting size)
+    pub fn rates<'py>(
+        &self,
+        py: Python<'py>,
+        side: &str,
+        level: usize,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let side = Side::parse(side)?;
+        self.check_level(level)?;
+        let Some((lambda, mean_size, theta, kappa)) = self.rates_of(side, level) else {
+            return Ok(None);
+        };
+        let d = PyDict::new(py);
+        d.set_item("trade_rate", lambda)?;
+        d.set_item("mean_trade_size", mean_size)?;
+        d.set_item("through_rate", theta)?;
+        d.set_item("cancel_rate", kappa)?;
+        Ok(Some(d))
+    }
+
+    #[getter]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.last_ts = None;
+        for state in [&mut self.bids, &mut self.asks] {
+            state.stats.fill(LevelStats::default());
+            state.last.clear();
+        }
+    }
+}
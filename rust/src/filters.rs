@@ -0,0 +1,149 @@
+// Exchange symbol filters: tick/lot rounding and order validation
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::state::{self, persist_fields, Stateful};
+use crate::Side;
+
+// Round to a multiple of `step`; up=false floors, up=true ceils
+pub fn round_to(value: f64, step: Option<f64>, up: bool) -> f64 {
+    match step {
+        Some(s) if s > 0.0 => {
+            // Tolerate float noise before floor/ceil
+            let units = value / s;
+            let snapped = if up {
+                (units - 1e-9).ceil()
+            } else {
+                (units + 1e-9).floor()
+            };
+            clean(snapped * s, s)
+        }
+        _ => value,
+    }
+}
+
+pub fn round_nearest(value: f64, step: f64) -> f64 {
+    if step > 0.0 {
+        clean((value / step).round() * step, step)
+    } else {
+        value
+    }
+}
+
+// Strip binary noise from a multiple of `step` (998 * 0.1 = 99.80000000000001)
+pub fn clean(value: f64, step: f64) -> f64 {
+    let mut scale = 1.0;
+    while (step * scale - (step * scale).round()).abs() > 1e-9 && scale < 1e12 {
+        scale *= 10.0;
+    }
+    (value * scale).round() / scale
+}
+
+fn on_grid(value: f64, step: f64) -> bool {
+    step <= 0.0 || ((value / step) - (value / step).round()).abs() < 1e-6
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub struct SymbolFilters {
+    pub tick_size: f64,
+    pub lot_size: f64,
+    pub min_notional: f64,
+    pub min_qty: f64,
+}
+
+persist_fields!(SymbolFilters {
+    tick_size,
+    lot_size,
+    min_notional,
+    min_qty
+});
+
+impl Stateful for SymbolFilters {
+    const NAME: &'static str = "SymbolFilters";
+}
+
+#[pymethods]
+impl SymbolFilters {
+    #[new]
+    #[pyo3(signature = (tick_size, lot_size, min_notional=0.0, min_qty=0.0))]
+    pub fn new(tick_size: f64, lot_size: f64, min_notional: f64, min_qty: f64) -> PyResult<Self> {
+        if tick_size <= 0.0 || lot_size <= 0.0 {
+            return Err(PyValueError::new_err(
+                "tick_size and lot_size must be positive",
+            ));
+        }
+        Ok(Self {
+            tick_size,
+            lot_size,
+            min_notional,
+            min_qty,
+        })
+    }
+
+    // Passive rounding: bids down, asks up; no side rounds to the nearest tick
+    #[pyo3(signature = (price, side=None))]
+    pub fn round_price(&self, price: f64, side: Option<&str>) -> PyResult<f64> {
+        Ok(match side.map(Side::parse).transpose()? {
+            Some(Side::Bid) => round_to(price, Some(self.tick_size), false),
+            Some(Side::Ask) => round_to(price, Some(self.tick_size), true),
+            None => round_nearest(price, self.tick_size),
+        })
+    }
+
+    // Quantities always round down to the lot
+    pub fn round_qty(&self, qty: f64) -> f64 {
+        round_to(qty, Some(self.lot_size), false)
+    }
+
+    // First violated filter for an order, None when it is exchange-valid
+    pub fn violation(&self, price: f64, qty: f64) -> Option<String> {
+        if price <= 0.0 || !on_grid(price, self.tick_size) {
+            return Some(format!(
+                "price {} is not a multiple of tick {}",
+                price, self.tick_size
+            ));
+        }
+        if qty <= 0.0 || !on_grid(qty, self.lot_size) {
+            return Some(format!(
+                "qty {} is not a multiple of lot {}",
+                qty, self.lot_size
+            ));
+        }
+        if qty < self.min_qty {
+            return Some(format!("qty {} below min_qty {}", qty, self.min_qty));
+        }
+        if price * qty < self.min_notional {
+            return Some(format!(
+                "notional {} below min_notional {}",
+                price * qty,
+                self.min_notional
+            ));
+        }
+        None
+    }
+
+    pub fn validate(&self, price: f64, qty: f64) -> bool {
+        self.violation(price, qty).is_none()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SymbolFilters(tick_size={:?}, lot_size={:?}, min_notional={:?}, min_qty={:?})",
+            self.tick_size, self.lot_size, self.min_notional, self.min_qty
+        )
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
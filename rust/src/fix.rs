@@ -0,0 +1,1095 @@
+// FIX 4.4 codecs for market data (W snapshot, X incremental refresh) and
+// order entry (D NewOrderSingle, F OrderCancelRequest, 8 ExecutionReport).
+// Decoding frames and splits a message in place over the input bytes (tags
+// and borrowed value slices); only the fields handed to Python are copied.
+// Framing checks BeginString, BodyLength (9) and CheckSum (10). Session
+// sequencing (MsgSeqNum 34), logon, heartbeats and resends are left to the
+// caller: encoders take the seq_num to stamp and decoded messages expose it.
+// Raw data fields (which may contain SOH) are not supported.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::IntoPyObjectExt;
+
+use crate::clock::wall_ms;
+use crate::events::BookUpdate;
+use crate::normalized::{Fill, OrderUpdate, Trade};
+use crate::{L2Book, Levels, Side};
+
+const SOH: u8 = 0x01;
+// Largest BodyLength accepted
+const MAX_MESSAGE: usize = 1 << 20;
+
+const BEGIN_STRING: u32 = 8;
+const BODY_LENGTH: u32 = 9;
+const CHECKSUM: u32 = 10;
+const AVG_PX: u32 = 6;
+const CL_ORD_ID: u32 = 11;
+const COMMISSION: u32 = 12;
+const CUM_QTY: u32 = 14;
+const EXEC_ID: u32 = 17;
+const EXEC_INST: u32 = 18;
+const HANDL_INST: u32 = 21;
+const LAST_PX: u32 = 31;
+const LAST_QTY: u32 = 32;
+const MSG_SEQ_NUM: u32 = 34;
+const MSG_TYPE: u32 = 35;
+const ORDER_ID: u32 = 37;
+const ORDER_QTY: u32 = 38;
+const ORD_STATUS: u32 = 39;
+const ORD_TYPE: u32 = 40;
+const ORIG_CL_ORD_ID: u32 = 41;
+const POSS_DUP: u32 = 43;
+const PRICE: u32 = 44;
+const SENDER_COMP_ID: u32 = 49;
+const SENDING_TIME: u32 = 52;
+const SIDE: u32 = 54;
+const SYMBOL: u32 = 55;
+const TARGET_COMP_ID: u32 = 56;
+const TEXT: u32 = 58;
+const TIME_IN_FORCE: u32 = 59;
+const TRANSACT_TIME: u32 = 60;
+const ACCOUNT: u32 = 1;
+const EXEC_TYPE: u32 = 150;
+const LEAVES_QTY: u32 = 151;
+const MD_REQ_ID: u32 = 262;
+const NO_MD_ENTRIES: u32 = 268;
+const MD_ENTRY_TYPE: u32 = 269;
+const MD_ENTRY_PX: u32 = 270;
+const MD_ENTRY_SIZE: u32 = 271;
+const MD_UPDATE_ACTION: u32 = 279;
+const MD_ENTRY_POSITION_NO: u32 = 290;
+const LAST_LIQUIDITY_IND: u32 = 851;
+
+fn invalid(msg: impl Into<String>) -> PyErr {
+    PyValueError::new_err(msg.into())
+}
+
+// ---- framing and field splitting (borrowed) ----
+
+// Length of the complete message at the start of buf, Ok(None) if more
+// bytes are needed
+fn frame_len(buf: &[u8]) -> Result<Option<usize>, String> {
+    if buf.len() < 2 {
+        return Ok(None);
+    }
+    if !buf.starts_with(b"8=") {
+        return Err("message must start with BeginString (8=)".into());
+    }
+    let Some(first) = buf.iter().position(|&b| b == SOH) else {
+        return if buf.len() > 64 {
+            Err("BeginString is not terminated".into())
+        } else {
+            Ok(None)
+        };
+    };
+    let rest = &buf[first + 1..];
+    let Some(end) = rest.iter().position(|&b| b == SOH) else {
+        return if rest.len() > 16 {
+            Err("BodyLength is not terminated".into())
+        } else {
+            Ok(None)
+        };
+    };
+    let len_field = &rest[..end];
+    let body_len: usize = len_field
+        .strip_prefix(b"9=")
+        .and_then(|v| std::str::from_utf8(v).ok())
+        .and_then(|v| v.parse().ok())
+        .ok_or("second field must be BodyLength (9=)")?;
+    if body_len > MAX_MESSAGE {
+        return Err(format!("BodyLength {} is too large", body_len));
+    }
+    let body_start = first + 1 + end + 1;
+    let total = body_start + body_len + 7;
+    if buf.len() < total {
+        return Ok(None);
+    }
+    let trailer = &buf[body_start + body_len..total];
+    if !trailer.starts_with(b"10=") || trailer[6] != SOH {
+        return Err("BodyLength does not end at the CheckSum (10=) field".into());
+    }
+    let expected = checksum(&buf[..body_start + body_len]);
+    let actual = std::str::from_utf8(&trailer[3..6])
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok());
+    if actual != Some(expected) {
+        return Err(format!(
+            "CheckSum mismatch: computed {:03}, message says {}",
+            expected,
+            String::from_utf8_lossy(&trailer[3..6])
+        ));
+    }
+    Ok(Some(total))
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().map(|&b| b as u32).sum::<u32>() % 256
+}
+
+// tag=value pairs of a framed message, values borrowed from it
+fn split_fields(msg: &[u8]) -> Result<Vec<(u32, &[u8])>, String> {
+    msg.split(|&b| b == SOH)
+        .filter(|f| !f.is_empty())
+        .map(|f| {
+            let eq = f
+                .iter()
+                .position(|&b| b == b'=')
+                .ok_or_else(|| format!("field without '=': {}", String::from_utf8_lossy(f)))?;
+            let tag = std::str::from_utf8(&f[..eq])
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(|| format!("bad tag '{}'", String::from_utf8_lossy(&f[..eq])))?;
+            Ok((tag, &f[eq + 1..]))
+        })
+        .collect()
+}
+
+fn text(v: &[u8]) -> String {
+    String::from_utf8_lossy(v).into_owned()
+}
+
+// ---- enumerations ----
+
+fn md_action_name(v: &str) -> &'static str {
+    match v {
+        "0" => "new",
+        "1" => "change",
+        "2" => "delete",
+        _ => "other",
+    }
+}
+
+fn md_action_code(name: &str) -> PyResult<&'static str> {
+    match name {
+        "new" => Ok("0"),
+        "change" => Ok("1"),
+        "delete" => Ok("2"),
+        other => Err(invalid(format!(
+            "action must be 'new', 'change' or 'delete', got '{}'",
+            other
+        ))),
+    }
+}
+
+fn md_type_name(v: &str) -> &'static str {
+    match v {
+        "0" => "bid",
+        "1" => "offer",
+        "2" => "trade",
+        _ => "other",
+    }
+}
+
+fn md_type_code(name: &str) -> PyResult<&'static str> {
+    match name {
+        "bid" | "buy" => Ok("0"),
+        "offer" | "ask" | "sell" => Ok("1"),
+        "trade" => Ok("2"),
+        other => Err(invalid(format!(
+            "entry type must be 'bid', 'offer' or 'trade', got '{}'",
+            other
+        ))),
+    }
+}
+
+// OrdStatus (39) and ExecType (150) share their codes
+const STATUSES: [(&str, &str); 14] = [
+    ("0", "new"),
+    ("1", "partially_filled"),
+    ("2", "filled"),
+    ("3", "done_for_day"),
+    ("4", "canceled"),
+    ("5", "replaced"),
+    ("6", "pending_cancel"),
+    ("8", "rejected"),
+    ("A", "pending_new"),
+    ("C", "expired"),
+    ("D", "restated"),
+    ("E", "pending_replace"),
+    ("F", "trade"),
+    ("I", "order_status"),
+];
+
+fn status_name(code: &str) -> String {
+    STATUSES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map_or_else(|| code.to_string(), |(_, n)| n.to_string())
+}
+
+fn status_code(name: &str) -> PyResult<&'static str> {
+    STATUSES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(c, _)| *c)
+        .ok_or_else(|| invalid(format!("unknown order status '{}'", name)))
+}
+
+fn side_code(side: &str) -> PyResult<&'static str> {
+    Ok(match Side::parse(side)? {
+        Side::Bid => "1",
+        Side::Ask => "2",
+    })
+}
+
+fn side_name(code: &str) -> Option<&'static str> {
+    match code {
+        "1" => Some("buy"),
+        "2" => Some("sell"),
+        _ => None,
+    }
+}
+
+fn ord_type_code(name: &str) -> PyResult<&'static str> {
+    match name {
+        "market" => Ok("1"),
+        "limit" => Ok("2"),
+        other => Err(invalid(format!(
+            "ord_type must be 'limit' or 'market', got '{}'",
+            other
+        ))),
+    }
+}
+
+fn tif_code(name: &str) -> PyResult<&'static str> {
+    match name {
+        "day" => Ok("0"),
+        "gtc" => Ok("1"),
+        "ioc" => Ok("3"),
+        "fok" => Ok("4"),
+        other => Err(invalid(format!(
+            "time_in_force must be 'day', 'gtc', 'ioc' or 'fok', got '{}'",
+            other
+        ))),
+    }
+}
+
+// ---- timestamps ----
+
+// Epoch milliseconds as UTCTimestamp YYYYMMDD-HH:MM:SS.sss
+fn utc_timestamp(ms: i64) -> String {
+    let days = ms.div_euclid(86_400_000);
+    let ms_of_day = ms.rem_euclid(86_400_000);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}-{:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
+
+// UTCTimestamp (YYYYMMDD-HH:MM:SS with optional .sss) as epoch milliseconds
+fn parse_utc_timestamp(text: &str) -> Option<i64> {
+    let b = text.as_bytes();
+    if b.len() < 17 || b[8] != b'-' || b[11] != b':' || b[14] != b':' {
+        return None;
+    }
+    let num = |r: std::ops::Range<usize>| text.get(r)?.parse::<i64>().ok();
+    let (year, month, day) = (num(0..4)?, num(4..6)?, num(6..8)?);
+    let (hour, minute, second) = (num(9..11)?, num(12..14)?, num(15..17)?);
+    let ms = match text.get(17..) {
+        None | Some("") => 0,
+        Some(frac) => {
+            let digits = frac.strip_prefix('.')?;
+            let padded = format!("{:0<3}", digits.get(..digits.len().min(3))?);
+            padded.parse::<i64>().ok()?
+        }
+    };
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    // Days since 1970-01-01 from the civil date (inverse of utc_timestamp)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(days * 86_400_000 + ((hour * 60 + minute) * 60 + second) * 1000 + ms)
+}
+
+// ---- decoded messages ----
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct MdEntry {
+    // "new", "change", "delete" (incremental refresh only)
+    pub action: Option<&'static str>,
+    // "bid", "offer", "trade" or "other"
+    pub entry_type: &'static str,
+    pub price: Option<f64>,
+    pub size: Option<f64>,
+    // Per-entry Symbol (55) on incremental refreshes
+    pub symbol: Option<String>,
+    pub position: Option<u32>,
+}
+
+#[pymethods]
+impl MdEntry {
+    fn __repr__(&self) -> String {
+        format!(
+            "MdEntry(action={:?}, entry_type={:?}, price={:?}, size={:?}, symbol={:?})",
+            self.action, self.entry_type, self.price, self.size, self.symbol
+        )
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionReport {
+    pub order_id: Option<String>,
+    pub cl_ord_id: Option<String>,
+    pub orig_cl_ord_id: Option<String>,
+    pub exec_id: Option<String>,
+    // Status names such as "new", "partially_filled", "filled", "canceled",
+    // "rejected", "trade"; unknown codes are passed through
+    pub exec_type: Option<String>,
+    pub ord_status: Option<String>,
+    pub symbol: Option<String>,
+    pub side: Option<&'static str>,
+    pub price: Option<f64>,
+    pub order_qty: Option<f64>,
+    pub last_px: Option<f64>,
+    pub last_qty: Option<f64>,
+    pub leaves_qty: Option<f64>,
+    pub cum_qty: Option<f64>,
+    pub avg_px: Option<f64>,
+    pub transact_time: Option<String>,
+    pub text: Option<String>,
+}
+
+#[pyclass(frozen)]
+#[derive(Clone, Debug)]
+pub struct FixMessage {
+    // Body fields in wire order, header and trailer included
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    fn from_fields(fields: &[(u32, &[u8])]) -> Self {
+        Self {
+            fields: fields.iter().map(|(t, v)| (*t, text(v))).collect(),
+        }
+    }
+
+    fn field(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn number(&self, tag: u32) -> Option<f64> {
+        self.field(tag)?.parse().ok()
+    }
+
+    // NoMDEntries repeating group: a new entry starts at each repeat of the
+    // group's first tag
+    fn md_entries(&self) -> Vec<MdEntry> {
+        let Some(start) = self.fields.iter().position(|(t, _)| *t == NO_MD_ENTRIES) else {
+            return Vec::new();
+        };
+        let group = &self.fields[start + 1..];
+        let Some(delimiter) = group.first().map(|(t, _)| *t) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<MdEntry> = Vec::new();
+        for (tag, value) in group {
+            if *tag == CHECKSUM {
+                break;
+            }
+            if *tag == delimiter || entries.is_empty() {
+                entries.push(MdEntry {
+                    action: None,
+                    entry_type: "other",
+                    price: None,
+                    size: None,
+                    symbol: None,
+                    position: None,
+                });
+            }
+            let entry = entries.last_mut().expect("pushed above");
+            match *tag {
+                MD_UPDATE_ACTION => entry.action = Some(md_action_name(value)),
+                MD_ENTRY_TYPE => entry.entry_type = md_type_name(value),
+                MD_ENTRY_PX => entry.price = value.parse().ok(),
+                MD_ENTRY_SIZE => entry.size = value.parse().ok(),
+                SYMBOL => entry.symbol = Some(value.clone()),
+                MD_ENTRY_POSITION_NO => entry.position = value.parse().ok(),
+                _ => {}
+            }
+        }
+        entries
+    }
+
+    // Bid and offer entries as one BookUpdate per symbol, then trades
+    fn normalize_market_data(&self, py: Python<'_>, exchange: &str) -> PyResult<Vec<PyObject>> {
+        let kind = if self.msg_type() == "W" {
+            "snapshot"
+        } else {
+            "delta"
+        };
+        let ts = self.sending_time().and_then(parse_utc_timestamp);
+        let msg_symbol = self.symbol();
+        let mut books: Vec<BookUpdate> = Vec::new();
+        let mut trades = Vec::new();
+        let book = |books: &mut Vec<BookUpdate>, symbol: &str| -> usize {
+            if let Some(i) = books
+                .iter()
+                .position(|b| b.symbol.as_deref() == Some(symbol))
+            {
+                return i;
+            }
+            books.push(BookUpdate {
+                exchange: Some(exchange.to_string()),
+                symbol: Some(symbol.to_string()),
+                kind,
+                ts,
+                ..Default::default()
+            });
+            books.len() - 1
+        };
+        // A snapshot without entries is an empty book
+        if let ("W", Some(symbol)) = (self.msg_type(), msg_symbol) {
+            book(&mut books, symbol);
+        }
+        for e in self.md_entries() {
+            let symbol = e.symbol.as_deref().or(msg_symbol).unwrap_or_default();
+            let Some(price) = e.price else {
+                continue;
+            };
+            let size = match e.action {
+                Some("delete") => 0.0,
+                _ => e.size.unwrap_or(0.0),
+            };
+            match e.entry_type {
+                "bid" => {
+                    let i = book(&mut books, symbol);
+                    books[i].bids.push((price, size));
+                }
+                "offer" => {
+                    let i = book(&mut books, symbol);
+                    books[i].asks.push((price, size));
+                }
+                "trade" if size > 0.0 => trades.push(Trade {
+                    exchange: exchange.to_string(),
+                    symbol: symbol.to_string(),
+                    price,
+                    size,
+                    ts,
+                    ..Default::default()
+                }),
+                _ => {}
+            }
+        }
+        let mut out = Vec::with_capacity(books.len() + trades.len());
+        for b in books {
+            out.push(b.into_py_any(py)?);
+        }
+        for t in trades {
+            out.push(t.into_py_any(py)?);
+        }
+        Ok(out)
+    }
+
+    // The order's state, and a Fill when the report carries an execution
+    fn normalize_execution(&self, py: Python<'_>, exchange: &str) -> PyResult<Vec<PyObject>> {
+        let Some(r) = self.execution_report() else {
+            return Ok(Vec::new());
+        };
+        let symbol = r.symbol.clone().unwrap_or_default();
+        let ts = r.transact_time.as_deref().and_then(parse_utc_timestamp);
+        let (status, venue_status) = match (&r.ord_status, self.field(ORD_STATUS)) {
+            (Some(name), Some(code)) => (name.clone(), code.to_string()),
+            _ => (
+                r.exec_type.clone().unwrap_or_default(),
+                self.field(EXEC_TYPE).unwrap_or_default().to_string(),
+            ),
+        };
+        let mut out = vec![OrderUpdate {
+            exchange: exchange.to_string(),
+            symbol: symbol.clone(),
+            client_id: r.cl_ord_id.clone(),
+            exchange_id: r.order_id.clone(),
+            status,
+            venue_status,
+            side: r.side,
+            price: r.price,
+            qty: r.order_qty,
+            filled_qty: r.cum_qty,
+            ts,
+            recv_ns: None,
+        }
+        .into_py_any(py)?];
+        if let (Some(side), Some(price), Some(qty)) = (r.side, r.last_px, r.last_qty) {
+            if qty > 0.0 {
+                let fill = Fill {
+                    exchange: exchange.to_string(),
+                    symbol,
+                    client_id: r.cl_ord_id,
+                    exchange_id: r.order_id,
+                    trade_id: r.exec_id,
+                    side,
+                    price,
+                    qty,
+                    fee: self.number(COMMISSION),
+                    // LastLiquidityInd: 1 added liquidity, 2 removed it
+                    liquidity: match self.field(LAST_LIQUIDITY_IND) {
+                        Some("1") => Some("maker"),
+                        Some("2") => Some("taker"),
+                        _ => None,
+                    },
+                    ts,
+                    recv_ns: None,
+                };
+                out.push(fill.into_py_any(py)?);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[pymethods]
+impl FixMessage {
+    #[getter]
+    pub fn msg_type(&self) -> &str {
+        self.field(MSG_TYPE).unwrap_or_default()
+    }
+
+    #[getter]
+    pub fn seq_num(&self) -> Option<u64> {
+        self.field(MSG_SEQ_NUM)?.parse().ok()
+    }
+
+    #[getter]
+    pub fn sender_comp_id(&self) -> Option<&str> {
+        self.field(SENDER_COMP_ID)
+    }
+
+    #[getter]
+    pub fn target_comp_id(&self) -> Option<&str> {
+        self.field(TARGET_COMP_ID)
+    }
+
+    #[getter]
+    pub fn sending_time(&self) -> Option<&str> {
+        self.field(SENDING_TIME)
+    }
+
+    #[getter]
+    pub fn poss_dup(&self) -> bool {
+        self.field(POSS_DUP) == Some("Y")
+    }
+
+    // Message-level Symbol (55), i.e. the first one in the message
+    #[getter]
+    pub fn symbol(&self) -> Option<&str> {
+        self.field(SYMBOL)
+    }
+
+    // First value of `tag`
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.field(tag)
+    }
+
+    // All (tag, value) pairs in wire order
+    pub fn fields(&self) -> Vec<(u32, String)> {
+        self.fields.clone()
+    }
+
+    // Entries of a W or X message
+    pub fn entries(&self) -> Vec<MdEntry> {
+        self.md_entries()
+    }
+
+    // Typed view of an ExecutionReport (35=8); None for other types
+    pub fn execution_report(&self) -> Option<ExecutionReport> {
+        if self.msg_type() != "8" {
+            return None;
+        }
+        let s = |tag| self.field(tag).map(str::to_string);
+        Some(ExecutionReport {
+            order_id: s(ORDER_ID),
+            cl_ord_id: s(CL_ORD_ID),
+            orig_cl_ord_id: s(ORIG_CL_ORD_ID),
+            exec_id: s(EXEC_ID),
+            exec_type: self.field(EXEC_TYPE).map(status_name),
+            ord_status: self.field(ORD_STATUS).map(status_name),
+            symbol: s(SYMBOL),
+            side: self.field(SIDE).and_then(side_name),
+            price: self.number(PRICE),
+            order_qty: self.number(ORDER_QTY),
+            last_px: self.number(LAST_PX),
+            last_qty: self.number(LAST_QTY),
+            leaves_qty: self.number(LEAVES_QTY),
+            cum_qty: self.number(CUM_QTY),
+            avg_px: self.number(AVG_PX),
+            transact_time: s(TRANSACT_TIME),
+            text: s(TEXT),
+        })
+    }
+
+    // Normalized events (see normalized.rs): a W or X gives a BookUpdate
+    // per symbol and a Trade per trade entry, an ExecutionReport gives an
+    // OrderUpdate plus a Fill when LastQty > 0; other types give []. ts is
+    // SendingTime for market data and TransactTime for executions.
+    // exchange defaults to the SenderCompID.
+    #[pyo3(signature = (exchange=None))]
+    pub fn normalize(&self, py: Python<'_>, exchange: Option<String>) -> PyResult<Vec<PyObject>> {
+        let exchange = exchange
+            .or_else(|| self.sender_comp_id().map(str::to_string))
+            .unwrap_or_default();
+        match self.msg_type() {
+            "W" | "X" => self.normalize_market_data(py, &exchange),
+            "8" => self.normalize_execution(py, &exchange),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.fields.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FixMessage(msg_type={:?}, seq_num={:?}, fields={})",
+            self.msg_type(),
+            self.seq_num(),
+            self.fields.len()
+        )
+    }
+}
+
+// ---- codec ----
+
+// Body builder: header fields after 8/9 and the caller's fields, then
+// BeginString, BodyLength and CheckSum wrapped around them
+struct Body(Vec<u8>);
+
+impl Body {
+    fn field(&mut self, tag: u32, value: &str) -> PyResult<()> {
+        if value.is_empty() || value.as_bytes().contains(&SOH) {
+            return Err(invalid(format!(
+                "tag {} must be non-empty and contain no SOH",
+                tag
+            )));
+        }
+        self.0.extend_from_slice(tag.to_string().as_bytes());
+        self.0.push(b'=');
+        self.0.extend_from_slice(value.as_bytes());
+        self.0.push(SOH);
+        Ok(())
+    }
+
+    fn opt(&mut self, tag: u32, value: Option<&str>) -> PyResult<()> {
+        match value {
+            Some(v) => self.field(tag, v),
+            None => Ok(()),
+        }
+    }
+
+    fn number(&mut self, tag: u32, value: f64) -> PyResult<()> {
+        if !value.is_finite() {
+            return Err(invalid(format!("tag {} must be finite", tag)));
+        }
+        self.field(tag, &value.to_string())
+    }
+}
+
+#[pyclass]
+pub struct FixCodec {
+    begin_string: String,
+    sender_comp_id: String,
+    target_comp_id: String,
+    // Stream bytes not yet framed into a message
+    buf: Vec<u8>,
+    errors: u64,
+    last_error: Option<String>,
+}
+
+impl FixCodec {
+    fn header(&self, msg_type: &str, seq_num: u64, sending_time_ms: Option<i64>) -> PyResult<Body> {
+        let mut body = Body(Vec::with_capacity(256));
+        body.field(MSG_TYPE, msg_type)?;
+        body.field(SENDER_COMP_ID, &self.sender_comp_id)?;
+        body.field(TARGET_COMP_ID, &self.target_comp_id)?;
+        body.field(MSG_SEQ_NUM, &seq_num.to_string())?;
+        body.field(
+            SENDING_TIME,
+            &utc_timestamp(sending_time_ms.unwrap_or_else(wall_ms)),
+        )?;
+        Ok(body)
+    }
+
+    fn finish<'py>(&self, py: Python<'py>, body: Body) -> Bound<'py, PyBytes> {
+        let mut out = Vec::with_capacity(body.0.len() + 32);
+        out.extend_from_slice(
+            format!("8={}\x019={}\x01", self.begin_string, body.0.len()).as_bytes(),
+        );
+        out.extend_from_slice(&body.0);
+        let sum = checksum(&out);
+        out.extend_from_slice(format!("10={:03}\x01", sum).as_bytes());
+        PyBytes::new(py, &out)
+    }
+
+    fn decode_frame(bytes: &[u8]) -> PyResult<FixMessage> {
+        let fields = split_fields(bytes).map_err(invalid)?;
+        let tags: Vec<u32> = fields.iter().take(3).map(|(t, _)| *t).collect();
+        if tags != [BEGIN_STRING, BODY_LENGTH, MSG_TYPE] {
+            return Err(invalid("message must start with 8=, 9= and 35="));
+        }
+        Ok(FixMessage::from_fields(&fields))
+    }
+
+    fn md_entry(body: &mut Body, entry_type: &str, price: f64, size: Option<f64>) -> PyResult<()> {
+        body.field(MD_ENTRY_TYPE, entry_type)?;
+        body.number(MD_ENTRY_PX, price)?;
+        match size {
+            Some(size) => body.number(MD_ENTRY_SIZE, size),
+            None => Ok(()),
+        }
+    }
+}
+
+#[pymethods]
+impl FixCodec {
+    #[new]
+    #[pyo3(signature = (sender_comp_id, target_comp_id, begin_string="FIX.4.4"))]
+    pub fn new(sender_comp_id: &str, target_comp_id: &str, begin_string: &str) -> PyResult<Self> {
+        for (name, v) in [
+            ("sender_comp_id", sender_comp_id),
+            ("target_comp_id", target_comp_id),
+            ("begin_string", begin_string),
+        ] {
+            if v.is_empty() || v.as_bytes().contains(&SOH) {
+                return Err(invalid(format!(
+                    "{} must be non-empty and contain no SOH",
+                    name
+                )));
+            }
+        }
+        Ok(Self {
+            begin_string: begin_string.to_string(),
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: target_comp_id.to_string(),
+            buf: Vec::new(),
+            errors: 0,
+            last_error: None,
+        })
+    }
+
+    // One complete message; ValueError if it is malformed, truncated or
+    // fails BodyLength/CheckSum validation
+    pub fn decode(&self, data: &[u8]) -> PyResult<FixMessage> {
+        match frame_len(data).map_err(invalid)? {
+            Some(n) if n == data.len() => Self::decode_frame(data),
+            Some(_) => Err(invalid("trailing bytes after the message")),
+            None => Err(invalid("incomplete message")),
+        }
+    }
+
+    // Stream decoding: append bytes read from the socket and return every
+    // message completed so far. A corrupt frame is skipped up to the next
+    // "8=" and counted in `errors` (see last_error); the gap shows up in the
+    // caller's MsgSeqNum check.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<FixMessage> {
+        self.buf.extend_from_slice(data);
+        let mut out = Vec::new();
+        let mut pos = 0;
+        while pos < self.buf.len() {
+            let rest = &self.buf[pos..];
+            let error = match frame_len(rest) {
+                Ok(Some(n)) => match Self::decode_frame(&rest[..n]) {
+                    Ok(msg) => {
+                        out.push(msg);
+                        pos += n;
+                        continue;
+                    }
+                    Err(e) => e.to_string(),
+                },
+                Ok(None) => break,
+                Err(e) => e,
+            };
+            self.errors += 1;
+            self.last_error = Some(error);
+            let next = rest[1..]
+                .windows(3)
+                .position(|w| w == b"\x018=")
+                .map(|i| i + 2);
+            match next {
+                Some(i) => pos += i,
+                None => pos = self.buf.len(),
+            }
+        }
+        self.buf.drain(..pos);
+        out
+    }
+
+    // Bytes buffered by feed() waiting for the rest of a message
+    #[getter]
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    #[getter]
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    #[getter]
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.clone()
+    }
+
+    #[pyo3(signature = (seq_num, cl_ord_id, symbol, side, qty, price=None, ord_type="limit", time_in_force="gtc", post_only=false, account=None, sending_time_ms=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_order_single<'py>(
+        &self,
+        py: Python<'py>,
+        seq_num: u64,
+        cl_ord_id: &str,
+        symbol: &str,
+        side: &str,
+        qty: f64,
+        price: Option<f64>,
+        ord_type: &str,
+        time_in_force: &str,
+        post_only: bool,
+        account: Option<&str>,
+        sending_time_ms: Option<i64>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let ord_type = ord_type_code(ord_type)?;
+        if ord_type == "2" && price.is_none() {
+            return Err(invalid("limit orders need a price"));
+        }
+        let now = sending_time_ms.unwrap_or_else(wall_ms);
+        let mut body = self.header("D", seq_num, Some(now))?;
+        body.opt(ACCOUNT, account)?;
+        body.field(CL_ORD_ID, cl_ord_id)?;
+        // Automated execution, no broker intervention
+        body.field(HANDL_INST, "1")?;
+        body.field(SYMBOL, symbol)?;
+        body.field(SIDE, side_code(side)?)?;
+        body.field(TRANSACT_TIME, &utc_timestamp(now))?;
+        body.number(ORDER_QTY, qty)?;
+        body.field(ORD_TYPE, ord_type)?;
+        if let Some(price) = price {
+            body.number(PRICE, price)?;
+        }
+        body.field(TIME_IN_FORCE, tif_code(time_in_force)?)?;
+        if post_only {
+            // ExecInst 6 = participate don't initiate
+            body.field(EXEC_INST, "6")?;
+        }
+        Ok(self.finish(py, body))
+    }
+
+    #[pyo3(signature = (seq_num, cl_ord_id, orig_cl_ord_id, symbol, side, qty=None, order_id=None, sending_time_ms=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn order_cancel_request<'py>(
+        &self,
+        py: Python<'py>,
+        seq_num: u64,
+        cl_ord_id: &str,
+        orig_cl_ord_id: &str,
+        symbol: &str,
+        side: &str,
+        qty: Option<f64>,
+        order_id: Option<&str>,
+        sending_time_ms: Option<i64>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let now = sending_time_ms.unwrap_or_else(wall_ms);
+        let mut body = self.header("F", seq_num, Some(now))?;
+        body.field(ORIG_CL_ORD_ID, orig_cl_ord_id)?;
+        body.opt(ORDER_ID, order_id)?;
+        body.field(CL_ORD_ID, cl_ord_id)?;
+        body.field(SYMBOL, symbol)?;
+        body.field(SIDE, side_code(side)?)?;
+        body.field(TRANSACT_TIME, &utc_timestamp(now))?;
+        if let Some(qty) = qty {
+            body.number(ORDER_QTY, qty)?;
+        }
+        Ok(self.finish(py, body))
+    }
+
+    // exec_type / ord_status take the names ExecutionReport reports
+    #[pyo3(signature = (seq_num, order_id, exec_id, exec_type, ord_status, symbol, side, leaves_qty, cum_qty, avg_px=0.0, cl_ord_id=None, price=None, order_qty=None, last_px=None, last_qty=None, text=None, sending_time_ms=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn execution_report<'py>(
+        &self,
+        py: Python<'py>,
+        seq_num: u64,
+        order_id: &str,
+        exec_id: &str,
+        exec_type: &str,
+        ord_status: &str,
+        symbol: &str,
+        side: &str,
+        leaves_qty: f64,
+        cum_qty: f64,
+        avg_px: f64,
+        cl_ord_id: Option<&str>,
+        price: Option<f64>,
+        order_qty: Option<f64>,
+        last_px: Option<f64>,
+        last_qty: Option<f64>,
+        text: Option<&str>,
+        sending_time_ms: Option<i64>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let now = sending_time_ms.unwrap_or_else(wall_ms);
+        let mut body = self.header("8", seq_num, Some(now))?;
+        body.field(ORDER_ID, order_id)?;
+        body.opt(CL_ORD_ID, cl_ord_id)?;
+        body.field(EXEC_ID, exec_id)?;
+        body.field(EXEC_TYPE, status_code(exec_type)?)?;
+        body.field(ORD_STATUS, status_code(ord_status)?)?;
+        body.field(SYMBOL, symbol)?;
+        body.field(SIDE, side_code(side)?)?;
+        for (tag, value) in [
+            (ORDER_QTY, order_qty),
+            (PRICE, price),
+            (LAST_PX, last_px),
+            (LAST_QTY, last_qty),
+        ] {
+            if let Some(v) = value {
+                body.number(tag, v)?;
+            }
+        }
+        body.number(LEAVES_QTY, leaves_qty)?;
+        body.number(CUM_QTY, cum_qty)?;
+        body.number(AVG_PX, avg_px)?;
+        body.field(TRANSACT_TIME, &utc_timestamp(now))?;
+        body.opt(TEXT, text)?;
+        Ok(self.finish(py, body))
+    }
+
+    // W: full book for one symbol, bids then offers, best first as given
+    #[pyo3(signature = (seq_num, symbol, bids, asks, md_req_id=None, sending_time_ms=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn market_data_snapshot<'py>(
+        &self,
+        py: Python<'py>,
+        seq_num: u64,
+        symbol: &str,
+        bids: Levels,
+        asks: Levels,
+        md_req_id: Option<&str>,
+        sending_time_ms: Option<i64>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let mut body = self.header("W", seq_num, sending_time_ms)?;
+        body.opt(MD_REQ_ID, md_req_id)?;
+        body.field(SYMBOL, symbol)?;
+        body.field(NO_MD_ENTRIES, &(bids.len() + asks.len()).to_string())?;
+        for (p, s) in &bids {
+            Self::md_entry(&mut body, "0", *p, Some(*s))?;
+        }
+        for (p, s) in &asks {
+            Self::md_entry(&mut body, "1", *p, Some(*s))?;
+        }
+        Ok(self.finish(py, body))
+    }
+
+    // X: entries are (action, entry_type, price, size) with action "new",
+    // "change" or "delete" and entry_type "bid", "offer" or "trade"
+    #[pyo3(signature = (seq_num, symbol, entries, md_req_id=None, sending_time_ms=None))]
+    pub fn market_data_incremental<'py>(
+        &self,
+        py: Python<'py>,
+        seq_num: u64,
+        symbol: &str,
+        entries: Vec<(String, String, f64, f64)>,
+        md_req_id: Option<&str>,
+        sending_time_ms: Option<i64>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let mut body = self.header("X", seq_num, sending_time_ms)?;
+        body.opt(MD_REQ_ID, md_req_id)?;
+        body.field(NO_MD_ENTRIES, &entries.len().to_string())?;
+        for (action, entry_type, price, size) in &entries {
+            let action = md_action_code(action)?;
+            body.field(MD_UPDATE_ACTION, action)?;
+            body.field(SYMBOL, symbol)?;
+            let size = (action != "2").then_some(*size);
+            Self::md_entry(&mut body, md_type_code(entry_type)?, *price, size)?;
+        }
+        Ok(self.finish(py, body))
+    }
+
+    // Apply a decoded W (replaces the book) or X (bid/offer entries become a
+    // delta; delete sets the level to 0) to `book`. With `symbol`, entries
+    // for other symbols are skipped. Position-only deletes (no price) and
+    // trade entries are ignored. Returns whether the book changed.
+    #[pyo3(signature = (book, msg, symbol=None))]
+    pub fn apply(
+        &self,
+        book: &mut L2Book,
+        msg: &FixMessage,
+        symbol: Option<&str>,
+    ) -> PyResult<bool> {
+        let msg_symbol = msg.symbol();
+        if let (Some(want), "W") = (symbol, msg.msg_type()) {
+            if msg_symbol != Some(want) {
+                return Ok(false);
+            }
+        }
+        let mut bids = Levels::new();
+        let mut asks = Levels::new();
+        for e in msg.md_entries() {
+            let entry_symbol = e.symbol.as_deref().or(msg_symbol);
+            if symbol.is_some_and(|want| entry_symbol != Some(want)) {
+                continue;
+            }
+            let Some(price) = e.price else {
+                continue;
+            };
+            let size = match e.action {
+                Some("delete") => 0.0,
+                _ => e.size.unwrap_or(0.0),
+            };
+            match e.entry_type {
+                "bid" => bids.push((price, size)),
+                "offer" => asks.push((price, size)),
+                _ => {}
+            }
+        }
+        match msg.msg_type() {
+            "W" => {
+                book.load_snapshot(bids, asks, None)?;
+                Ok(true)
+            }
+            "X" if bids.is_empty() && asks.is_empty() => Ok(false),
+            "X" => book.delta(bids, asks, None, None, None),
+            other => Err(invalid(format!(
+                "expected a W or X market data message, got 35={}",
+                other
+            ))),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.errors = 0;
+        self.last_error = None;
+    }
+}
@@ -0,0 +1,806 @@
+// REST order entry without Python on the request path: place, amend and
+// cancel requests are built, signed and sent from Rust with the GIL released
+// (see http.rs; https:// over TLS), and an attached OrderManager is updated
+// from the answers.
+// Endpoints and signing:
+//   bybit            POST /v5/order/create|amend|cancel, JSON body; X-BAPI-SIGN
+//                    = HMAC(timestamp + api_key + recv_window + body)
+//   binance          POST|DELETE /api/v3/order, parameters in the query string
+//                    with signature = HMAC(query) appended; no amend
+//   binance_futures  POST|PUT|DELETE /fapi/v1/order, as binance; post_only is GTX
+// Prices and quantities go out as exact decimal text: Decimal and str as
+// given, floats at 15 significant digits (0.1 + 0.2 is sent as "0.3"). With
+// filters for the symbol they must sit on its tick / lot grid, and are sent
+// with the step's decimal places.
+// Timestamps are wall time plus the offset measured by sync_time(). A request
+// refused for its timestamp (Bybit retCode 10002, Binance -1021) resyncs and
+// is re-signed and sent once more. HTTP 5xx answers are retried up to
+// max_retries times with a doubling delay, re-signed with the same client
+// order id so the exchange refuses a duplicate rather than placing twice.
+// With orders=OrderManager:
+//   place   submit() before sending, then on_ack (with the exchange id) or
+//           on_reject
+//   cancel  request_cancel() before sending, then on_canceled or
+//           on_cancel_reject
+//   amend   on_amended on success
+// A request left without an answer (network error after retries) raises
+// OSError and stays in flight for OrderManager.timed_out().
+// asyncio code awaits send(OrderRequest...) instead: same request and
+// OrderManager updates, with the round trip on a worker thread (aio.rs).
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::aio;
+use crate::arrays::Fixed;
+use crate::clock::wall_ms;
+use crate::filters::SymbolFilters;
+use crate::http::{self, Response};
+use crate::json::{self, Value};
+use crate::orders::OrderManager;
+use crate::sign::hmac_sha256_hex;
+use crate::snapshot::{check_token, Venue};
+use crate::ws;
+use crate::Side;
+
+// Retry codes for a stale or early timestamp
+const BYBIT_BAD_TIMESTAMP: i64 = 10002;
+const BINANCE_BAD_TIMESTAMP: i64 = -1021;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Action {
+    Place,
+    Amend,
+    Cancel,
+}
+
+// An exchange's answer to one request
+struct Reply {
+    status: u16,
+    code: i64,
+    message: String,
+    exchange_id: Option<String>,
+}
+
+impl Reply {
+    fn ok(&self) -> bool {
+        self.status == 200 && self.code == 0
+    }
+}
+
+impl Venue {
+    fn order_endpoint(self, action: Action) -> (&'static str, &'static str) {
+        match (self, action) {
+            (Self::Bybit, Action::Place) => ("POST", "/v5/order/create"),
+            (Self::Bybit, Action::Amend) => ("POST", "/v5/order/amend"),
+            (Self::Bybit, Action::Cancel) => ("POST", "/v5/order/cancel"),
+            (Self::Binance, Action::Cancel) => ("DELETE", "/api/v3/order"),
+            (Self::Binance, _) => ("POST", "/api/v3/order"),
+            (Self::BinanceFutures, Action::Place) => ("POST", "/fapi/v1/order"),
+            (Self::BinanceFutures, Action::Amend) => ("PUT", "/fapi/v1/order"),
+            (Self::BinanceFutures, Action::Cancel) => ("DELETE", "/fapi/v1/order"),
+        }
+    }
+
+    fn time_path(self) -> &'static str {
+        match self {
+            Self::Bybit => "/v5/market/time",
+            Self::Binance => "/api/v3/time",
+            Self::BinanceFutures => "/fapi/v1/time",
+        }
+    }
+
+    fn side_name(self, side: Side) -> &'static str {
+        match (self, side) {
+            (Self::Bybit, Side::Bid) => "Buy",
+            (Self::Bybit, Side::Ask) => "Sell",
+            (_, Side::Bid) => "BUY",
+            (_, Side::Ask) => "SELL",
+        }
+    }
+
+    fn client_id_key(self, action: Action) -> &'static str {
+        match (self, action) {
+            (Self::Bybit, _) => "orderLinkId",
+            (_, Action::Place) => "newClientOrderId",
+            _ => "origClientOrderId",
+        }
+    }
+
+    // Order type and time-in-force parameters for a placement
+    fn order_type(self, time_in_force: &str) -> PyResult<Vec<(&'static str, String)>> {
+        let tif = time_in_force.to_ascii_uppercase();
+        let param = |k: &'static str, v: &str| (k, v.to_string());
+        let post_only = tif == "POST_ONLY";
+        if !post_only && !matches!(tif.as_str(), "GTC" | "IOC" | "FOK") {
+            return Err(PyValueError::new_err(format!(
+                "time_in_force must be 'GTC', 'IOC', 'FOK' or 'post_only', got '{}'",
+                time_in_force
+            )));
+        }
+        Ok(match (self, post_only) {
+            (Self::Bybit, true) => vec![
+                param("orderType", "Limit"),
+                param("timeInForce", "PostOnly"),
+            ],
+            (Self::Bybit, false) => vec![param("orderType", "Limit"), param("timeInForce", &tif)],
+            (Self::Binance, true) => vec![param("type", "LIMIT_MAKER")],
+            (Self::BinanceFutures, true) => {
+                vec![param("type", "LIMIT"), param("timeInForce", "GTX")]
+            }
+            (_, false) => vec![param("type", "LIMIT"), param("timeInForce", &tif)],
+        })
+    }
+
+    fn bad_timestamp(self, code: i64) -> bool {
+        match self {
+            Self::Bybit => code == BYBIT_BAD_TIMESTAMP,
+            Self::Binance | Self::BinanceFutures => code == BINANCE_BAD_TIMESTAMP,
+        }
+    }
+
+    // Bybit answers HTTP 200 with a retCode; Binance uses the HTTP status
+    // and {"code", "msg"} on errors
+    fn parse_reply(self, response: &Response) -> Reply {
+        let text = String::from_utf8_lossy(&response.body);
+        let mut reply = Reply {
+            status: response.status,
+            code: 0,
+            message: String::new(),
+            exchange_id: None,
+        };
+        let Ok(root) = json::parse(&text) else {
+            if response.status != 200 {
+                reply.code = response.status as i64;
+                reply.message = text.chars().take(200).collect();
+            } else {
+                reply.code = -1;
+                reply.message = "invalid JSON in response".to_string();
+            }
+            return reply;
+        };
+        let (code, message, data) = match self {
+            Self::Bybit => ("retCode", "retMsg", root.get("result")),
+            Self::Binance | Self::BinanceFutures => ("code", "msg", Some(&root)),
+        };
+        reply.code = root.get(code).and_then(Value::as_i64).unwrap_or(0);
+        if reply.code == 0 && response.status != 200 {
+            reply.code = response.status as i64;
+        }
+        reply.message = root
+            .get(message)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        reply.exchange_id = match data.and_then(|d| d.get("orderId")) {
+            Some(Value::Str(s)) if !s.is_empty() => Some(s.to_string()),
+            Some(Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        };
+        reply
+    }
+
+    fn server_time(self, body: &[u8]) -> Result<i64, String> {
+        let text = std::str::from_utf8(body).map_err(|e| e.to_string())?;
+        let root = json::parse(text)?;
+        let key = match self {
+            Self::Bybit => "time",
+            Self::Binance | Self::BinanceFutures => "serverTime",
+        };
+        root.get(key)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| format!("missing '{}' in server time", key))
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct OrderResponse {
+    pub ok: bool,
+    pub client_id: String,
+    pub exchange_id: Option<String>,
+    // HTTP status of the last attempt
+    pub status: u16,
+    // Exchange error code (Bybit retCode, Binance code); 0 on success
+    pub code: i64,
+    pub message: String,
+    pub attempts: u32,
+    // Round trip including retries
+    pub latency_ms: f64,
+}
+
+#[pymethods]
+impl OrderResponse {
+    fn __repr__(&self) -> String {
+        format!(
+            "OrderResponse(ok={}, client_id={:?}, exchange_id={:?}, status={}, code={}, message={:?}, attempts={})",
+            if self.ok { "True" } else { "False" },
+            self.client_id,
+            self.exchange_id,
+            self.status,
+            self.code,
+            self.message,
+            self.attempts
+        )
+    }
+}
+
+// One place, amend or cancel for OrderGateway.send(), built with the
+// static methods below; they take the arguments of the OrderGateway methods
+// of the same name and check what does not depend on the venue
+#[pyclass(frozen)]
+#[derive(Clone, Debug)]
+pub struct OrderRequest {
+    action: Action,
+    #[pyo3(get)]
+    client_id: String,
+    #[pyo3(get)]
+    symbol: String,
+    side: Option<Side>,
+    price: Option<Fixed>,
+    qty: Option<Fixed>,
+    #[pyo3(get)]
+    time_in_force: String,
+}
+
+#[pymethods]
+impl OrderRequest {
+    #[staticmethod]
+    #[pyo3(signature = (client_id, symbol, side, price, qty, time_in_force="GTC"))]
+    pub fn place(
+        client_id: &str,
+        symbol: &str,
+        side: &str,
+        price: Fixed,
+        qty: Fixed,
+        time_in_force: &str,
+    ) -> PyResult<Self> {
+        let side = Side::parse(side)?;
+        check_positive("price", price)?;
+        check_positive("qty", qty)?;
+        Ok(Self {
+            action: Action::Place,
+            client_id: client_id.to_string(),
+            symbol: symbol.to_string(),
+            side: Some(side),
+            price: Some(price),
+            qty: Some(qty),
+            time_in_force: time_in_force.to_string(),
+        })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (client_id, symbol, price=None, qty=None))]
+    pub fn amend(
+        client_id: &str,
+        symbol: &str,
+        price: Option<Fixed>,
+        qty: Option<Fixed>,
+    ) -> PyResult<Self> {
+        if price.is_none() && qty.is_none() {
+            return Err(PyValueError::new_err("amend needs a price or a qty"));
+        }
+        price.map(|p| check_positive("price", p)).transpose()?;
+        qty.map(|q| check_positive("qty", q)).transpose()?;
+        Ok(Self {
+            action: Action::Amend,
+            client_id: client_id.to_string(),
+            symbol: symbol.to_string(),
+            side: None,
+            price,
+            qty,
+            time_in_force: String::new(),
+        })
+    }
+
+    #[staticmethod]
+    pub fn cancel(client_id: &str, symbol: &str) -> Self {
+        Self {
+            action: Action::Cancel,
+            client_id: client_id.to_string(),
+            symbol: symbol.to_string(),
+            side: None,
+            price: None,
+            qty: None,
+            time_in_force: String::new(),
+        }
+    }
+
+    // "place", "amend" or "cancel"
+    #[getter]
+    pub fn action(&self) -> &'static str {
+        match self.action {
+            Action::Place => "place",
+            Action::Amend => "amend",
+            Action::Cancel => "cancel",
+        }
+    }
+
+    #[getter]
+    pub fn side(&self) -> Option<&'static str> {
+        self.side.map(Side::name)
+    }
+
+    #[getter]
+    pub fn price(&self) -> Option<f64> {
+        self.price.map(Fixed::to_f64)
+    }
+
+    #[getter]
+    pub fn qty(&self) -> Option<f64> {
+        self.qty.map(Fixed::to_f64)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "OrderRequest(action={:?}, client_id={:?}, symbol={:?})",
+            self.action(),
+            self.client_id,
+            self.symbol
+        )
+    }
+}
+
+#[pyclass(frozen)]
+pub struct OrderGateway {
+    base_url: String,
+    venue: Venue,
+    category: String,
+    api_key: String,
+    api_secret: String,
+    recv_window_ms: u64,
+    timeout: Duration,
+    max_retries: u32,
+    retry_delay: Duration,
+    orders: Option<Py<OrderManager>>,
+    // (tick, lot) by symbol
+    steps: HashMap<String, (Fixed, Fixed)>,
+    // Exchange time minus wall time
+    offset_ms: AtomicI64,
+    requests: AtomicU64,
+    retries: AtomicU64,
+}
+
+impl OrderGateway {
+    fn sync(&self) -> io::Result<i64> {
+        let url = format!("{}{}", self.base_url, self.venue.time_path());
+        let sent = wall_ms();
+        let response = http::get(&url, self.timeout)?;
+        let received = wall_ms();
+        if response.status != 200 {
+            return Err(io::Error::other(format!(
+                "GET {} returned HTTP {}",
+                self.venue.time_path(),
+                response.status
+            )));
+        }
+        let server = self
+            .venue
+            .server_time(&response.body)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // The server read its clock about halfway through the round trip
+        let offset = server - (sent + received) / 2;
+        self.offset_ms.store(offset, Ordering::Relaxed);
+        Ok(offset)
+    }
+
+    // Sign and send one request; retries 5xx answers and a timestamp refusal
+    fn transmit(
+        &self,
+        action: Action,
+        params: &[(&'static str, String)],
+    ) -> io::Result<(Reply, u32, f64)> {
+        let (method, path) = self.venue.order_endpoint(action);
+        let start = Instant::now();
+        let mut delay = self.retry_delay;
+        let mut attempts = 0;
+        let mut resynced = false;
+        loop {
+            attempts += 1;
+            let timestamp = (wall_ms() + self.offset_ms.load(Ordering::Relaxed)).to_string();
+            let recv_window = self.recv_window_ms.to_string();
+            let response = match self.venue {
+                Venue::Bybit => {
+                    let fields: Vec<String> = params
+                        .iter()
+                        .map(|(k, v)| format!("\"{}\":\"{}\"", k, v))
+                        .collect();
+                    let body = format!("{{{}}}", fields.join(","));
+                    let payload = format!("{}{}{}{}", timestamp, self.api_key, recv_window, body);
+                    let sign = hmac_sha256_hex(self.api_secret.as_bytes(), payload.as_bytes());
+                    let headers = [
+                        ("Content-Type", "application/json"),
+                        ("X-BAPI-API-KEY", self.api_key.as_str()),
+                        ("X-BAPI-TIMESTAMP", timestamp.as_str()),
+                        ("X-BAPI-RECV-WINDOW", recv_window.as_str()),
+                        ("X-BAPI-SIGN", sign.as_str()),
+                    ];
+                    let url = format!("{}{}", self.base_url, path);
+                    http::request(method, &url, &headers, body.as_bytes(), self.timeout)?
+                }
+                Venue::Binance | Venue::BinanceFutures => {
+                    let mut query: Vec<String> =
+                        params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                    query.push(format!("recvWindow={}", recv_window));
+                    query.push(format!("timestamp={}", timestamp));
+                    let query = query.join("&");
+                    let sign = hmac_sha256_hex(self.api_secret.as_bytes(), query.as_bytes());
+                    let url = format!("{}{}?{}&signature={}", self.base_url, path, query, sign);
+                    let headers = [("X-MBX-APIKEY", self.api_key.as_str())];
+                    http::request(method, &url, &headers, b"", self.timeout)?
+                }
+            };
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            if response.status >= 500 && attempts <= self.max_retries {
+                self.retries.fetch_add(1, Ordering::Relaxed);
+                thread::sleep(delay);
+                delay *= 2;
+                continue;
+            }
+            let reply = self.venue.parse_reply(&response);
+            if self.venue.bad_timestamp(reply.code) && !resynced {
+                resynced = true;
+                self.retries.fetch_add(1, Ordering::Relaxed);
+                self.sync()?;
+                continue;
+            }
+            return Ok((reply, attempts, start.elapsed().as_secs_f64() * 1e3));
+        }
+    }
+
+    // Validate a request, build its parameters and record it in the
+    // OrderManager (submit / request_cancel) before it is sent
+    fn prepare(&self, py: Python<'_>, req: &OrderRequest) -> PyResult<Vec<(&'static str, String)>> {
+        let mut params = self.base_params(req.action, &req.client_id, &req.symbol)?;
+        match req.action {
+            Action::Place => {
+                let (Some(side), Some(price), Some(qty)) = (req.side, req.price, req.qty) else {
+                    unreachable!("OrderRequest.place sets side, price and qty");
+                };
+                params.push(("side", self.venue.side_name(side).to_string()));
+                params.extend(self.venue.order_type(&req.time_in_force)?);
+                let qty_key = if self.venue == Venue::Bybit {
+                    "qty"
+                } else {
+                    "quantity"
+                };
+                params.push((qty_key, self.wire(&req.symbol, "qty", qty)?));
+                params.push(("price", self.wire(&req.symbol, "price", price)?));
+                if let Some(orders) = &self.orders {
+                    orders.bind(py).try_borrow_mut()?.submit(
+                        req.client_id.clone(),
+                        req.symbol.clone(),
+                        side.name(),
+                        price.to_f64(),
+                        qty.to_f64(),
+                        wall_ms(),
+                    )?;
+                }
+            }
+            Action::Amend => match self.venue {
+                Venue::Bybit => {
+                    if let Some(qty) = req.qty {
+                        params.push(("qty", self.wire(&req.symbol, "qty", qty)?));
+                    }
+                    if let Some(price) = req.price {
+                        params.push(("price", self.wire(&req.symbol, "price", price)?));
+                    }
+                }
+                Venue::Binance => {
+                    return Err(PyValueError::new_err(
+                        "binance spot has no amend; cancel and place instead",
+                    ))
+                }
+                Venue::BinanceFutures => {
+                    let known = match &self.orders {
+                        Some(o) => o.bind(py).try_borrow()?.order(&req.client_id),
+                        None => None,
+                    };
+                    let (Some(info), Some(qty), Some(price)) = (
+                        known.clone(),
+                        req.qty
+                            .or(known.as_ref().and_then(|o| Fixed::from_f64(o.qty))),
+                        req.price
+                            .or(known.as_ref().and_then(|o| Fixed::from_f64(o.price))),
+                    ) else {
+                        return Err(PyValueError::new_err(format!(
+                            "binance_futures amend needs order {} in the OrderManager",
+                            req.client_id
+                        )));
+                    };
+                    let side = Side::parse(info.side)?;
+                    params.push(("side", self.venue.side_name(side).to_string()));
+                    params.push(("quantity", self.wire(&req.symbol, "qty", qty)?));
+                    params.push(("price", self.wire(&req.symbol, "price", price)?));
+                }
+            },
+            Action::Cancel => {
+                if let Some(orders) = &self.orders {
+                    orders
+                        .bind(py)
+                        .try_borrow_mut()?
+                        .request_cancel(&req.client_id, wall_ms())?;
+                }
+            }
+        }
+        Ok(params)
+    }
+
+    // The OrderResponse for an answered request, with the OrderManager
+    // updated from it
+    fn complete(
+        &self,
+        py: Python<'_>,
+        req: &OrderRequest,
+        (reply, attempts, latency_ms): (Reply, u32, f64),
+    ) -> PyResult<OrderResponse> {
+        let response = OrderResponse {
+            ok: reply.ok(),
+            client_id: req.client_id.clone(),
+            exchange_id: reply.exchange_id,
+            status: reply.status,
+            code: reply.code,
+            message: reply.message,
+            attempts,
+            latency_ms,
+        };
+        let Some(orders) = &self.orders else {
+            return Ok(response);
+        };
+        let mut orders = orders.bind(py).try_borrow_mut()?;
+        let (id, now) = (req.client_id.as_str(), wall_ms());
+        match (req.action, response.ok) {
+            (Action::Place, true) => {
+                orders.on_ack(id, now, response.exchange_id.clone())?;
+            }
+            (Action::Place, false) => orders.on_reject(id, now)?,
+            (Action::Amend, true) => orders.on_amended(
+                id,
+                now,
+                req.price.map(Fixed::to_f64),
+                req.qty.map(Fixed::to_f64),
+            )?,
+            (Action::Amend, false) => {}
+            (Action::Cancel, true) => {
+                orders.on_canceled(id, now)?;
+            }
+            (Action::Cancel, false) => orders.on_cancel_reject(id, now)?,
+        }
+        Ok(response)
+    }
+
+    // prepare, send with the GIL released, complete
+    fn execute(&self, py: Python<'_>, req: OrderRequest) -> PyResult<OrderResponse> {
+        let params = self.prepare(py, &req)?;
+        let sent = py.allow_threads(|| self.transmit(req.action, &params))?;
+        self.complete(py, &req, sent)
+    }
+
+    // A price or qty as sent: on the symbol's tick / lot grid with the
+    // step's decimal places when filters are known, else as given
+    fn wire(&self, symbol: &str, what: &str, value: Fixed) -> PyResult<String> {
+        let Some(&(tick, lot)) = self.steps.get(symbol) else {
+            return Ok(value.to_string());
+        };
+        let step = if what == "price" { tick } else { lot };
+        if !value.is_multiple_of(step) {
+            return Err(PyValueError::new_err(format!(
+                "{} {} is not a multiple of {} for {}",
+                what, value, step, symbol
+            )));
+        }
+        Ok(format!("{:.*}", step.exp as usize, value))
+    }
+
+    // Parameters shared by every request on one order
+    fn base_params(
+        &self,
+        action: Action,
+        client_id: &str,
+        symbol: &str,
+    ) -> PyResult<Vec<(&'static str, String)>> {
+        check_token("client_id", client_id)?;
+        check_token("symbol", symbol)?;
+        let mut params = Vec::new();
+        if self.venue == Venue::Bybit {
+            params.push(("category", self.category.clone()));
+        }
+        params.push(("symbol", symbol.to_string()));
+        params.push((self.venue.client_id_key(action), client_id.to_string()));
+        Ok(params)
+    }
+}
+
+fn check_positive(what: &str, value: Fixed) -> PyResult<()> {
+    if value.units <= 0 {
+        return Err(PyValueError::new_err(format!(
+            "{} must be positive, got {}",
+            what, value
+        )));
+    }
+    Ok(())
+}
+
+#[pymethods]
+impl OrderGateway {
+    // base_url is the REST root, e.g. "https://api.bybit.com"; plain http://
+    // is refused unless the host is loopback (a local proxy), so keys and
+    // signed requests never cross the network in the clear. category is
+    // Bybit's product type. max_retries bounds the resends of a request
+    // answered with HTTP 5xx. filters maps symbols to their SymbolFilters.
+    #[new]
+    #[pyo3(signature = (
+        base_url,
+        api_key,
+        api_secret,
+        exchange="bybit",
+        category="linear",
+        orders=None,
+        recv_window_ms=5000,
+        timeout_ms=5000,
+        max_retries=2,
+        retry_delay_ms=50,
+        filters=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        base_url: &str,
+        api_key: &str,
+        api_secret: &str,
+        exchange: &str,
+        category: &str,
+        orders: Option<Py<OrderManager>>,
+        recv_window_ms: u64,
+        timeout_ms: u64,
+        max_retries: u32,
+        retry_delay_ms: u64,
+        filters: Option<HashMap<String, SymbolFilters>>,
+    ) -> PyResult<Self> {
+        let venue = Venue::parse(exchange)?;
+        let base_url = base_url.trim_end_matches('/');
+        let endpoint =
+            ws::split_url(base_url, "http").map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if !endpoint.tls && !endpoint.is_loopback() {
+            return Err(PyValueError::new_err(format!(
+                "signed requests need https://, or http:// to a loopback host; got '{}'",
+                base_url
+            )));
+        }
+        check_token("category", category)?;
+        // Keys go into headers and the signature as they are
+        check_token("api_key", api_key)?;
+        if api_secret.is_empty() {
+            return Err(PyValueError::new_err("api_secret must not be empty"));
+        }
+        if timeout_ms == 0 || recv_window_ms == 0 {
+            return Err(PyValueError::new_err(
+                "timeout_ms and recv_window_ms must be positive",
+            ));
+        }
+        let mut steps = HashMap::new();
+        for (symbol, f) in filters.unwrap_or_default() {
+            let (Some(tick), Some(lot)) =
+                (Fixed::from_f64(f.tick_size), Fixed::from_f64(f.lot_size))
+            else {
+                return Err(PyValueError::new_err(format!(
+                    "filters for {} need a finite tick_size and lot_size",
+                    symbol
+                )));
+            };
+            steps.insert(symbol, (tick, lot));
+        }
+        Ok(Self {
+            base_url: base_url.to_string(),
+            venue,
+            category: category.to_string(),
+            api_key: api_key.to_string(),
+            api_secret: api_secret.to_string(),
+            recv_window_ms,
+            timeout: Duration::from_millis(timeout_ms),
+            max_retries,
+            retry_delay: Duration::from_millis(retry_delay_ms),
+            orders,
+            steps,
+            offset_ms: AtomicI64::new(0),
+            requests: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+        })
+    }
+
+    // Measure the exchange clock offset used for request timestamps;
+    // returns it in ms
+    pub fn sync_time(&self, py: Python<'_>) -> PyResult<i64> {
+        Ok(py.allow_threads(|| self.sync())?)
+    }
+
+    // Limit order. time_in_force is "GTC", "IOC", "FOK" or "post_only".
+    // Rejections come back as ok=False; network failures raise OSError.
+    #[pyo3(signature = (client_id, symbol, side, price, qty, time_in_force="GTC"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn place(
+        &self,
+        py: Python<'_>,
+        client_id: &str,
+        symbol: &str,
+        side: &str,
+        price: Fixed,
+        qty: Fixed,
+        time_in_force: &str,
+    ) -> PyResult<OrderResponse> {
+        let req = OrderRequest::place(client_id, symbol, side, price, qty, time_in_force)?;
+        self.execute(py, req)
+    }
+
+    // Change price and/or qty. binance_futures needs side, price and qty on
+    // every amend: missing ones come from the OrderManager. Binance spot has
+    // no amend (cancel and place instead).
+    #[pyo3(signature = (client_id, symbol, price=None, qty=None))]
+    pub fn amend(
+        &self,
+        py: Python<'_>,
+        client_id: &str,
+        symbol: &str,
+        price: Option<Fixed>,
+        qty: Option<Fixed>,
+    ) -> PyResult<OrderResponse> {
+        self.execute(py, OrderRequest::amend(client_id, symbol, price, qty)?)
+    }
+
+    pub fn cancel(&self, py: Python<'_>, client_id: &str, symbol: &str) -> PyResult<OrderResponse> {
+        self.execute(py, OrderRequest::cancel(client_id, symbol))
+    }
+
+    // Awaitable place/amend/cancel for asyncio code:
+    //   r = await gw.send(OrderRequest.place("q-1", "BTCUSDT", "buy", 100.5, 0.01))
+    // Validation errors raise here and the OrderManager sees the request
+    // at once; the HTTP round trip (and any retry sleeps) runs on a worker
+    // thread, and the answer is applied on the loop thread (see aio.rs)
+    pub fn send<'py>(slf: &Bound<'py, Self>, order: OrderRequest) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        let event_loop = aio::running_loop(py)?;
+        let params = slf.get().prepare(py, &order)?;
+        let (sender, receiver) = (slf.clone().unbind(), slf.clone().unbind());
+        aio::spawn(
+            &event_loop,
+            "mm-order-gateway",
+            move || sender.get().transmit(order.action, &params),
+            move |py, sent| {
+                let response = receiver.get().complete(py, &order, sent?)?;
+                Ok(Py::new(py, response)?.into_any())
+            },
+        )
+    }
+
+    #[getter]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    #[getter]
+    pub fn orders(&self, py: Python<'_>) -> Option<Py<OrderManager>> {
+        self.orders.as_ref().map(|o| o.clone_ref(py))
+    }
+
+    // Exchange time minus wall time, from the last sync_time()
+    #[getter]
+    pub fn time_offset_ms(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    // Order requests sent, resends included (sync_time() not counted)
+    #[getter]
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    // Resends after a 5xx answer or a timestamp refusal
+    #[getter]
+    pub fn retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+}
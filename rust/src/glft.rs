@@ -0,0 +1,238 @@
+// Guéant-Lehalle-Fernandez-Tapia market making: the closed-form
+// approximation of the optimal quotes over an infinite horizon, for fills
+// arriving at intensity λ(δ) = A·e^(-k·δ) at depth δ from mid:
+//   c1 = 1/(ξΔ) · ln(1 + ξΔ/k)
+//   c2 = sqrt(γ / (2·A·Δ·k) · (1 + ξΔ/k)^(k/(ξΔ) + 1))
+//   half spread  c1 + Δ/2 · σ · c2
+//   skew         σ · c2 per unit of inventory
+// bid at s - (half spread + q·skew), ask at s + (half spread - q·skew),
+// with Δ the quote size, q the inventory in the same units, ξ = γ by
+// default, σ per sqrt of the time unit A is measured in and k per price unit.
+// Unlike Avellaneda-Stoikov there is no terminal time, so quotes do not
+// depend on the time left in the session.
+//
+// A and k are fitted by regressing ln λ on δ: λ at each depth of a grid is
+// the rate of observed fill (or trade) distances at least that deep.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::metrics::{self, NATIVE};
+use crate::quoting::{check_positive, Quote};
+use crate::L2Book;
+
+// Least-squares fit of ln(rate) = ln(A) - k·depth over (depth, rate)
+// points with a positive rate; None with fewer than two such points or a
+// non-decaying fit
+pub(crate) fn fit_exponential(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let pts: Vec<(f64, f64)> = points
+        .iter()
+        .filter(|(_, r)| *r > 0.0)
+        .map(|&(d, r)| (d, r.ln()))
+        .collect();
+    if pts.len() < 2 {
+        return None;
+    }
+    let n = pts.len() as f64;
+    let mean_x = pts.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = pts.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = pts.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = pts.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    if sxx <= 0.0 {
+        return None;
+    }
+    let k = -sxy / sxx;
+    // Rates must fall measurably across the depths, not by rounding
+    let span = pts.iter().map(|p| (p.0 - mean_x).abs()).fold(0.0, f64::max);
+    (k * span > 1e-9).then(|| ((mean_y + k * mean_x).exp(), k))
+}
+
+// (depth, rate) on `levels` depths from 0 to the 95th percentile distance
+pub(crate) fn rate_curve(distances: &[f64], duration: f64, levels: usize) -> Vec<(f64, f64)> {
+    let mut sorted: Vec<f64> = distances.iter().map(|d| d.abs()).collect();
+    sorted.sort_by(f64::total_cmp);
+    let Some(&top) = sorted.get(sorted.len().saturating_sub(1) * 95 / 100) else {
+        return Vec::new();
+    };
+    (0..levels)
+        .map(|i| {
+            let depth = top * i as f64 / levels as f64;
+            let deeper = sorted.len() - sorted.partition_point(|d| *d < depth);
+            (depth, deeper as f64 / duration)
+        })
+        .collect()
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct GlftQuoter {
+    // gamma, a, k and delta go into ln() and divisions, so their setters
+    // keep them positive
+    #[pyo3(get)]
+    pub gamma: f64,
+    #[pyo3(get)]
+    pub a: f64,
+    #[pyo3(get)]
+    pub k: f64,
+    #[pyo3(get)]
+    pub delta: f64,
+    xi: Option<f64>,
+    #[pyo3(get, set)]
+    pub min_spread: f64,
+}
+
+impl GlftQuoter {
+    // (half spread, skew per unit of inventory)
+    fn coefficients(&self, volatility: f64) -> (f64, f64) {
+        let xd = self.xi.unwrap_or(self.gamma) * self.delta;
+        let c1 = (1.0 + xd / self.k).ln() / xd;
+        let c2 = (self.gamma / (2.0 * self.a * self.delta * self.k)
+            * (1.0 + xd / self.k).powf(self.k / xd + 1.0))
+        .sqrt();
+        (c1 + self.delta / 2.0 * volatility * c2, volatility * c2)
+    }
+
+    pub fn compute(&self, price: f64, inventory: f64, volatility: f64) -> Quote {
+        let (half, skew) = self.coefficients(volatility);
+        let reservation_price = price - inventory * skew;
+        let spread = (2.0 * half).max(self.min_spread);
+        Quote {
+            bid_price: reservation_price - spread / 2.0,
+            ask_price: reservation_price + spread / 2.0,
+            reservation_price,
+            spread,
+        }
+    }
+}
+
+#[pymethods]
+impl GlftQuoter {
+    // gamma: risk aversion; a, k: fill intensity A·e^(-k·δ); delta: quote
+    // size; xi defaults to gamma
+    #[new]
+    #[pyo3(signature = (gamma, a, k, delta=1.0, xi=None, min_spread=0.0))]
+    pub fn new(
+        gamma: f64,
+        a: f64,
+        k: f64,
+        delta: f64,
+        xi: Option<f64>,
+        min_spread: f64,
+    ) -> PyResult<Self> {
+        check_positive("gamma", gamma)?;
+        check_positive("a", a)?;
+        check_positive("k", k)?;
+        check_positive("delta", delta)?;
+        if let Some(xi) = xi {
+            check_positive("xi", xi)?;
+        }
+        Ok(Self {
+            gamma,
+            a,
+            k,
+            delta,
+            xi,
+            min_spread,
+        })
+    }
+
+    #[setter]
+    pub fn set_gamma(&mut self, gamma: f64) -> PyResult<()> {
+        check_positive("gamma", gamma)?;
+        self.gamma = gamma;
+        Ok(())
+    }
+
+    #[setter]
+    pub fn set_a(&mut self, a: f64) -> PyResult<()> {
+        check_positive("a", a)?;
+        self.a = a;
+        Ok(())
+    }
+
+    #[setter]
+    pub fn set_k(&mut self, k: f64) -> PyResult<()> {
+        check_positive("k", k)?;
+        self.k = k;
+        Ok(())
+    }
+
+    #[setter]
+    pub fn set_delta(&mut self, delta: f64) -> PyResult<()> {
+        check_positive("delta", delta)?;
+        self.delta = delta;
+        Ok(())
+    }
+
+    #[getter]
+    pub fn xi(&self) -> f64 {
+        self.xi.unwrap_or(self.gamma)
+    }
+
+    pub fn quote(&self, price: f64, inventory: f64, volatility: f64) -> Quote {
+        self.compute(price, inventory, volatility)
+    }
+
+    // (half spread, skew per unit of inventory) at this volatility
+    pub fn half_spread_and_skew(&self, volatility: f64) -> (f64, f64) {
+        self.coefficients(volatility)
+    }
+
+    // Quote around the book's mid or microprice; None for a one-sided book
+    #[pyo3(signature = (book, inventory, volatility, reference="mid"))]
+    pub fn quote_book(
+        &self,
+        book: &L2Book,
+        inventory: f64,
+        volatility: f64,
+        reference: &str,
+    ) -> PyResult<Option<Quote>> {
+        let price = match reference {
+            "mid" => book.mid()?,
+            "microprice" => book.guarded(book.weighted_microprice(1, None))?,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "reference must be 'mid' or 'microprice', got '{}'",
+                    other
+                )))
+            }
+        };
+        let quote = price.map(|p| self.compute(p, inventory, volatility));
+        if let Some(at) = book.updated_at {
+            metrics::observe_since(&NATIVE.tick_to_quote, at);
+        }
+        Ok(quote)
+    }
+
+    // Fit (A, k) from distances to mid of fills (or trades) observed over
+    // `duration` time units; None if the rates do not decay with depth
+    #[staticmethod]
+    #[pyo3(signature = (distances, duration, levels=10))]
+    pub fn fit_intensity(
+        distances: Vec<f64>,
+        duration: f64,
+        levels: usize,
+    ) -> PyResult<Option<(f64, f64)>> {
+        if duration <= 0.0 || levels < 2 {
+            return Err(PyValueError::new_err(
+                "duration must be positive and levels at least 2",
+            ));
+        }
+        Ok(fit_exponential(&rate_curve(&distances, duration, levels)))
+    }
+
+    // fit_intensity() and, if it succeeds, use the result; returns (A, k)
+    #[pyo3(signature = (distances, duration, levels=10))]
+    pub fn fit(
+        &mut self,
+        distances: Vec<f64>,
+        duration: f64,
+        levels: usize,
+    ) -> PyResult<Option<(f64, f64)>> {
+        let fitted = Self::fit_intensity(distances, duration, levels)?;
+        if let Some((a, k)) = fitted {
+            self.a = a;
+            self.k = k;
+        }
+        Ok(fitted)
+    }
+}
@@ -0,0 +1,153 @@
+// Self-exciting (Hawkes) trade arrival model with an exponential kernel,
+// one process per aggressor side:
+//   λ_s(t) = μ + Σ_i w_i · a_si · e^(-β (t - t_i))
+// over past trades i, with a_si = alpha for trades on side s and
+// alpha_cross for trades on the other side, and w_i an optional weight
+// (e.g. size in lots). The sums decay in O(1) per update. Rates are per
+// second; excitation() is the total intensity over the baseline 2μ, so a
+// burst of same-side trades (momentum ignition) shows up as a spike well
+// above 1. The process is stationary only while alpha + alpha_cross < β.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+use crate::Side;
+
+#[pyclass]
+pub struct HawkesIntensity {
+    mu: f64,
+    alpha: f64,
+    alpha_cross: f64,
+    // Decay per ms
+    beta_ms: f64,
+    clock: Option<Clock>,
+    // Excitation above mu (buy, sell) as of last_ts
+    excited: [f64; 2],
+    last_ts: Option<i64>,
+    count: u64,
+}
+
+impl HawkesIntensity {
+    // Excitation decayed to ts; earlier timestamps do not decay it
+    fn decayed(&self, ts: i64) -> [f64; 2] {
+        let dt = self.last_ts.map_or(0, |t| (ts - t).max(0));
+        let f = (-self.beta_ms * dt as f64).exp();
+        [self.excited[0] * f, self.excited[1] * f]
+    }
+
+    fn index(side: Side) -> usize {
+        match side {
+            Side::Bid => 0,
+            Side::Ask => 1,
+        }
+    }
+}
+
+#[pymethods]
+impl HawkesIntensity {
+    // mu: baseline trades per second per side; alpha / alpha_cross: jump in
+    // intensity per trade on the same / other side; beta: decay per second
+    #[new]
+    #[pyo3(signature = (mu=1.0, alpha=0.5, beta=1.0, alpha_cross=0.0, clock=None))]
+    pub fn new(
+        mu: f64,
+        alpha: f64,
+        beta: f64,
+        alpha_cross: f64,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if mu <= 0.0 || beta <= 0.0 || alpha < 0.0 || alpha_cross < 0.0 {
+            return Err(PyValueError::new_err(
+                "mu and beta must be positive, alpha and alpha_cross non-negative",
+            ));
+        }
+        if alpha + alpha_cross >= beta {
+            return Err(PyValueError::new_err(
+                "alpha + alpha_cross must be below beta (branching ratio < 1)",
+            ));
+        }
+        Ok(Self {
+            mu,
+            alpha,
+            alpha_cross,
+            beta_ms: beta / 1_000.0,
+            clock,
+            excited: [0.0; 2],
+            last_ts: None,
+            count: 0,
+        })
+    }
+
+    // side is the aggressor; weight scales this trade's jump
+    #[pyo3(signature = (side, ts=None, weight=1.0))]
+    pub fn on_trade(&mut self, side: &str, ts: Option<i64>, weight: f64) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        let ts = clock::require(ts, &self.clock)?;
+        if weight < 0.0 {
+            return Err(PyValueError::new_err("weight must be non-negative"));
+        }
+        let mut excited = self.decayed(ts);
+        let same = Self::index(side);
+        excited[same] += weight * self.alpha;
+        excited[1 - same] += weight * self.alpha_cross;
+        self.excited = excited;
+        self.last_ts = Some(self.last_ts.map_or(ts, |t| t.max(ts)));
+        self.count += 1;
+        Ok(())
+    }
+
+    // Trades per second expected at ts on one side, or on both
+    #[pyo3(signature = (side=None, ts=None))]
+    pub fn intensity(&self, side: Option<&str>, ts: Option<i64>) -> PyResult<f64> {
+        let side = side.map(Side::parse).transpose()?;
+        let [buy, sell] = self.intensities(ts);
+        Ok(match side {
+            Some(Side::Bid) => buy,
+            Some(Side::Ask) => sell,
+            None => buy + sell,
+        })
+    }
+
+    // [buy, sell] intensities at ts: the clock's time by default, else the
+    // last trade's
+    #[pyo3(signature = (ts=None))]
+    pub fn intensities(&self, ts: Option<i64>) -> [f64; 2] {
+        let ts = match self.last_ts {
+            Some(last) => clock::resolve(ts, &self.clock).unwrap_or(last),
+            None => 0,
+        };
+        let [buy, sell] = self.decayed(ts);
+        [self.mu + buy, self.mu + sell]
+    }
+
+    // Total intensity over the baseline; 1.0 when calm
+    #[pyo3(signature = (ts=None))]
+    pub fn excitation(&self, ts: Option<i64>) -> f64 {
+        let [buy, sell] = self.intensities(ts);
+        (buy + sell) / (2.0 * self.mu)
+    }
+
+    // (buy - sell) / (buy + sell) intensity at ts
+    #[pyo3(signature = (ts=None))]
+    pub fn imbalance(&self, ts: Option<i64>) -> f64 {
+        let [buy, sell] = self.intensities(ts);
+        (buy - sell) / (buy + sell)
+    }
+
+    // Expected trades triggered by each trade: (alpha + alpha_cross) / beta
+    #[getter]
+    pub fn branching_ratio(&self) -> f64 {
+        (self.alpha + self.alpha_cross) / (self.beta_ms * 1_000.0)
+    }
+
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn reset(&mut self) {
+        self.excited = [0.0; 2];
+        self.last_ts = None;
+        self.count = 0;
+    }
+}
@@ -0,0 +1,239 @@
+// Inventory hedging on a second venue. Once the exposure (inventory times
+// hedge_ratio, in units of the hedge instrument) is beyond `threshold`, the
+// hedger takes liquidity to bring it back towards `target`. The clip is the
+// largest size, up to max_clip, whose all-in cost stays within max_cost_bps:
+//   cost(q) = slippage(q) + taker fee + impact_bps_per_unit * q
+// where slippage is the average fill from walking the book against mid and
+// the linear term stands for the impact the visible book does not show.
+// Cost is non-decreasing in q, so the clip is found by bisection. The order
+// goes out as an IOC limit at the worst level the clip walks to. After a
+// hedge the next one waits min_interval_ms, which gives the venue time to
+// refill and fills time to come back. Clips below min_clip are not sent.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+use crate::fees::{FeeModel, Liquidity};
+use crate::L2Book;
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct HedgeOrder {
+    // "buy" or "sell" on the hedge venue
+    pub side: &'static str,
+    pub qty: f64,
+    pub limit_price: f64,
+    pub avg_price: f64,
+    // All-in cost of the clip in bps of mid
+    pub cost_bps: f64,
+    pub fee: f64,
+    // Total size wanted to reach the target; qty is the part sent now
+    pub wanted: f64,
+    pub ts: i64,
+}
+
+#[pymethods]
+impl HedgeOrder {
+    fn __repr__(&self) -> String {
+        format!(
+            "HedgeOrder(side={:?}, qty={}, limit_price={}, cost_bps={}, wanted={})",
+            self.side, self.qty, self.limit_price, self.cost_bps, self.wanted
+        )
+    }
+}
+
+#[pyclass]
+pub struct Hedger {
+    #[pyo3(get, set)]
+    pub threshold: f64,
+    #[pyo3(get, set)]
+    pub target: f64,
+    #[pyo3(get, set)]
+    pub max_clip: Option<f64>,
+    #[pyo3(get, set)]
+    pub min_clip: f64,
+    #[pyo3(get, set)]
+    pub max_cost_bps: f64,
+    #[pyo3(get, set)]
+    pub impact_bps_per_unit: f64,
+    #[pyo3(get, set)]
+    pub hedge_ratio: f64,
+    #[pyo3(get, set)]
+    pub min_interval_ms: i64,
+    fee_model: Option<FeeModel>,
+    clock: Option<Clock>,
+    last_ts: Option<i64>,
+    hedges: u64,
+}
+
+impl Hedger {
+    // (avg price, worst price, cost bps) of taking `qty` on `side`
+    fn cost(&self, book: &L2Book, side: &str, qty: f64, mid: f64) -> PyResult<(f64, f64, f64)> {
+        let Some((avg, worst, _)) = book.sweep_cost(side, qty)? else {
+            return Ok((mid, mid, 0.0));
+        };
+        let slippage = match side {
+            "buy" => avg - mid,
+            _ => mid - avg,
+        } / mid
+            * 10_000.0;
+        let fee_bps = self.fee_model.as_ref().map_or(0.0, |f| {
+            f.fee(avg, qty, Liquidity::Taker) / (avg * qty) * 10_000.0
+        });
+        Ok((
+            avg,
+            worst,
+            slippage + fee_bps + self.impact_bps_per_unit * qty,
+        ))
+    }
+}
+
+#[pymethods]
+impl Hedger {
+    // threshold/target/clips are in hedge instrument units; target must not
+    // exceed threshold
+    #[new]
+    #[pyo3(signature = (
+        threshold=0.0,
+        target=0.0,
+        max_clip=None,
+        max_cost_bps=10.0,
+        fee_model=None,
+        impact_bps_per_unit=0.0,
+        hedge_ratio=1.0,
+        min_interval_ms=1_000,
+        min_clip=0.0,
+        clock=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        threshold: f64,
+        target: f64,
+        max_clip: Option<f64>,
+        max_cost_bps: f64,
+        fee_model: Option<FeeModel>,
+        impact_bps_per_unit: f64,
+        hedge_ratio: f64,
+        min_interval_ms: i64,
+        min_clip: f64,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if threshold < 0.0 || target < 0.0 || target > threshold {
+            return Err(PyValueError::new_err("need 0 <= target <= threshold"));
+        }
+        if max_clip.is_some_and(|c| c <= 0.0) || min_clip < 0.0 {
+            return Err(PyValueError::new_err(
+                "max_clip must be positive and min_clip non-negative",
+            ));
+        }
+        if impact_bps_per_unit < 0.0 || hedge_ratio == 0.0 || min_interval_ms < 0 {
+            return Err(PyValueError::new_err(
+                "impact_bps_per_unit and min_interval_ms must be non-negative, hedge_ratio non-zero",
+            ));
+        }
+        Ok(Self {
+            threshold,
+            target,
+            max_clip,
+            min_clip,
+            max_cost_bps,
+            impact_bps_per_unit,
+            hedge_ratio,
+            min_interval_ms,
+            fee_model,
+            clock,
+            last_ts: None,
+            hedges: 0,
+        })
+    }
+
+    // Hedge to send for `inventory` (in traded units, long positive) against
+    // the hedge venue's book; None when within threshold, throttled, the
+    // book is one-sided or nothing fits the cost budget. A returned order
+    // starts the throttle interval.
+    #[pyo3(signature = (inventory, book, ts=None))]
+    pub fn compute(
+        &mut self,
+        inventory: f64,
+        book: &L2Book,
+        ts: Option<i64>,
+    ) -> PyResult<Option<HedgeOrder>> {
+        let ts = clock::require(ts, &self.clock)?;
+        let exposure = inventory * self.hedge_ratio;
+        if exposure.abs() <= self.threshold || !self.ready(Some(ts))? {
+            return Ok(None);
+        }
+        let Some(mid) = book.raw_mid() else {
+            return Ok(None);
+        };
+        let side = if exposure > 0.0 { "sell" } else { "buy" };
+        let wanted = exposure.abs() - self.target;
+        let depth: f64 = book.taker_levels(side)?.map(|(_, s)| s).sum();
+        let cap = wanted
+            .min(self.max_clip.unwrap_or(f64::INFINITY))
+            .min(depth);
+        let qty = if self.cost(book, side, cap, mid)?.2 <= self.max_cost_bps {
+            cap
+        } else {
+            let (mut lo, mut hi) = (0.0, cap);
+            for _ in 0..60 {
+                let q = 0.5 * (lo + hi);
+                if self.cost(book, side, q, mid)?.2 <= self.max_cost_bps {
+                    lo = q;
+                } else {
+                    hi = q;
+                }
+            }
+            lo
+        };
+        if qty <= 0.0 || qty < self.min_clip {
+            return Ok(None);
+        }
+        let (avg_price, limit_price, cost_bps) = self.cost(book, side, qty, mid)?;
+        let fee = self
+            .fee_model
+            .as_ref()
+            .map_or(0.0, |f| f.fee(avg_price, qty, Liquidity::Taker));
+        self.last_ts = Some(ts);
+        self.hedges += 1;
+        Ok(Some(HedgeOrder {
+            side,
+            qty,
+            limit_price,
+            avg_price,
+            cost_bps,
+            fee,
+            wanted,
+            ts,
+        }))
+    }
+
+    // Whether min_interval_ms has passed since the last hedge
+    #[pyo3(signature = (ts=None))]
+    pub fn ready(&self, ts: Option<i64>) -> PyResult<bool> {
+        let ts = clock::require(ts, &self.clock)?;
+        Ok(self.last_ts.is_none_or(|t| ts - t >= self.min_interval_ms))
+    }
+
+    #[getter]
+    pub fn last_hedge_ts(&self) -> Option<i64> {
+        self.last_ts
+    }
+
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.hedges
+    }
+
+    pub fn reset(&mut self) {
+        self.last_ts = None;
+        self.hedges = 0;
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Hedger(threshold={}, target={}, max_cost_bps={}, count={})",
+            self.threshold, self.target, self.max_cost_bps, self.hedges
+        )
+    }
+}
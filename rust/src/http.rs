@@ -0,0 +1,335 @@
+// Minimal blocking HTTP/1.1 client over std::net, for REST snapshots and
+// order entry. http:// is plain TCP; https:// runs over TLS (tls.rs), with
+// the server certificate and host name verified.
+// One request per connection (Connection: close); the body is delimited by
+// Content-Length, chunked transfer encoding or the end of the stream.
+// Compression is never requested.
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use crate::ws;
+
+const MAX_RESPONSE: usize = 64 << 20;
+
+pub struct Response {
+    pub status: u16,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    // Case-insensitive header lookup
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+pub fn get(url: &str, timeout: Duration) -> io::Result<Response> {
+    request("GET", url, &[], b"", timeout)
+}
+
+// `headers` are sent as given; every request but a GET gets a Content-Length
+pub fn request(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    timeout: Duration,
+) -> io::Result<Response> {
+    let endpoint = ws::split_url(url, "http")?;
+    let deadline = Instant::now() + timeout;
+    let mut stream = endpoint.connect(timeout)?;
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\n\
+         Accept-Encoding: identity\r\nConnection: close\r\n",
+        method,
+        endpoint.path,
+        endpoint.authority()
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if method != "GET" {
+        request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+    let mut request = request.into_bytes();
+    request.extend_from_slice(body);
+    stream.write_all(&request)?;
+
+    let mut raw = Vec::new();
+    let mut chunk = [0u8; 16 << 10];
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("{} {} timed out", method, url),
+            ));
+        }
+        stream.set_read_timeout(Some(left))?;
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => raw.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("{} {} timed out", method, url),
+                ))
+            }
+            Err(e) => return Err(e),
+        }
+        if raw.len() > MAX_RESPONSE {
+            return Err(invalid("response too large"));
+        }
+        // Stop early once a Content-Length body is complete, in case the
+        // server keeps the connection open anyway
+        if let Some(done) = complete_len(&raw) {
+            raw.truncate(done);
+            break;
+        }
+    }
+    parse_response(&raw)
+}
+
+// Total length of a response whose headers carry Content-Length, once all
+// of it has arrived
+fn complete_len(raw: &[u8]) -> Option<usize> {
+    let end = raw.windows(4).position(|w| w == b"\r\n\r\n")? + 4;
+    let head = String::from_utf8_lossy(&raw[..end]);
+    let len: usize = head.split("\r\n").find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        k.trim()
+            .eq_ignore_ascii_case("content-length")
+            .then(|| v.trim().parse().ok())?
+    })?;
+    (raw.len() >= end + len).then_some(end + len)
+}
+
+fn parse_response(raw: &[u8]) -> io::Result<Response> {
+    let end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid("incomplete HTTP response"))?;
+    let head = String::from_utf8_lossy(&raw[..end]);
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid(format!("bad status line '{}'", status_line)))?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            Some((k.trim().to_string(), v.trim().to_string()))
+        })
+        .collect();
+    let mut response = Response {
+        status,
+        headers,
+        body: Vec::new(),
+    };
+    let body = &raw[end + 4..];
+    response.body = if response
+        .header("transfer-encoding")
+        .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"))
+    {
+        dechunk(body)?
+    } else if let Some(len) = response.header("content-length") {
+        let len: usize = len
+            .parse()
+            .map_err(|_| invalid(format!("bad Content-Length '{}'", len)))?;
+        if body.len() < len {
+            return Err(invalid("truncated HTTP body"));
+        }
+        body[..len].to_vec()
+    } else {
+        body.to_vec()
+    };
+    Ok(response)
+}
+
+// <hex size>[;ext]\r\n<data>\r\n ... 0\r\n\r\n
+fn dechunk(mut body: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let eol = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| invalid("truncated chunked body"))?;
+        let line = String::from_utf8_lossy(&body[..eol]);
+        let digits = line.split(';').next().unwrap_or_default().trim();
+        let size = Some(digits)
+            .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|d| usize::from_str_radix(d, 16).ok())
+            .ok_or_else(|| invalid(format!("bad chunk size '{}'", line)))?;
+        body = &body[eol + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        if body.len().saturating_sub(2) < size {
+            return Err(invalid("truncated chunked body"));
+        }
+        if &body[size..size + 2] != b"\r\n" {
+            return Err(invalid("chunk not followed by CRLF"));
+        }
+        out.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn parse(raw: &str) -> io::Result<Response> {
+        parse_response(raw.as_bytes())
+    }
+
+    #[test]
+    fn chunked_bodies_are_reassembled() {
+        let r = parse(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+             4\r\nWiki\r\n5;name=value\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n\
+             0\r\nX-Trailer: ignored\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(r.body, b"Wikipedia in\r\n\r\nchunks.");
+        // Upper-case hex, padded sizes and mixed-case header values
+        let r = parse("HTTP/1.1 200 OK\r\ntransfer-encoding: gzip, Chunked\r\n\r\n0A \r\n0123456789\r\n0\r\n\r\n")
+            .unwrap();
+        assert_eq!(r.body, b"0123456789");
+        let r = parse("HTTP/1.1 204 No Content\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n")
+            .unwrap();
+        assert!(r.body.is_empty());
+    }
+
+    #[test]
+    fn malformed_chunked_bodies_are_rejected() {
+        for body in [
+            "",
+            "4\r\nWik",
+            "4\r\nWiki",
+            "4\r\nWikiXY0\r\n\r\n",
+            "zz\r\nWiki\r\n0\r\n\r\n",
+            "+4\r\nWiki\r\n0\r\n\r\n",
+            "ffffffffffffffff\r\nWiki\r\n0\r\n\r\n",
+            "4\r\nWiki\r\n",
+        ] {
+            let raw = format!(
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{}",
+                body
+            );
+            let Err(err) = parse(&raw) else {
+                panic!("accepted {:?}", body)
+            };
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn content_length_and_close_delimited_bodies() {
+        let r = parse(
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 3\r\nContent-Length: 2\r\n\r\n{}extra",
+        )
+        .unwrap();
+        assert_eq!((r.status, r.body.as_slice()), (429, &b"{}"[..]));
+        assert_eq!(r.header("retry-after"), Some("3"));
+        assert_eq!(r.header("x-missing"), None);
+        let r = parse("HTTP/1.0 200 OK\r\n\r\nuntil close").unwrap();
+        assert_eq!(r.body, b"until close");
+        assert!(parse("HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nshort").is_err());
+        assert!(parse("HTTP/1.1 200 OK\r\nContent-Length: -1\r\n\r\n").is_err());
+        assert!(parse("HTTP/1.1 OK\r\n\r\n").is_err());
+        assert!(parse("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n").is_err());
+    }
+
+    #[test]
+    fn complete_len_waits_for_the_whole_body() {
+        let head = "HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n";
+        assert_eq!(complete_len(head.as_bytes()), None);
+        assert_eq!(complete_len(format!("{}abcd", head).as_bytes()), None);
+        assert_eq!(
+            complete_len(format!("{}abcdefg", head).as_bytes()),
+            Some(head.len() + 5)
+        );
+        assert_eq!(complete_len(b"HTTP/1.1 200 OK\r\nContent-Len"), None);
+        assert_eq!(complete_len(b"HTTP/1.1 200 OK\r\n\r\nbody"), None);
+    }
+
+    // One connection: read the request, then send `reply` in pieces
+    fn serve(reply: &'static [&'static [u8]], close: bool) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/api/v3/depth?symbol=BTCUSDT",
+            listener.local_addr().unwrap()
+        );
+        let server = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut req = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = conn.read(&mut buf).unwrap();
+                req.extend_from_slice(&buf[..n]);
+            }
+            for piece in reply {
+                conn.write_all(piece).unwrap();
+                conn.flush().unwrap();
+                thread::sleep(Duration::from_millis(5));
+            }
+            if !close {
+                // Held open: the client must stop at Content-Length
+                thread::sleep(Duration::from_millis(500));
+            }
+            String::from_utf8(req).unwrap()
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn chunked_response_over_a_socket() {
+        let (url, server) = serve(
+            &[
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nf\r\n{\"last",
+                b"UpdateId\"\r\n",
+                b"4\r\n:42}\r\n0\r\n\r\n",
+            ],
+            true,
+        );
+        let r = get(&url, Duration::from_secs(5)).unwrap();
+        assert_eq!(r.body, b"{\"lastUpdateId\":42}");
+        let req = server.join().unwrap();
+        assert!(req.starts_with("GET /api/v3/depth?symbol=BTCUSDT HTTP/1.1\r\n"));
+        assert!(req.contains("Accept-Encoding: identity\r\n"));
+        assert!(!req.contains("Content-Length"));
+    }
+
+    #[test]
+    fn content_length_response_ends_without_waiting_for_close() {
+        let (url, server) = serve(
+            &[b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nab", b"cd"],
+            false,
+        );
+        let started = Instant::now();
+        let r = get(&url, Duration::from_secs(5)).unwrap();
+        assert_eq!(r.body, b"abcd");
+        assert!(started.elapsed() < Duration::from_millis(400));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,218 @@
+// Iceberg detection from the joint book + trade stream. A hidden order
+// shows a small display size that is replenished at the same price as soon
+// as it trades. Between two observe() calls the detector sums the volume
+// printed at each level of the previous ladder; if the prints took at least
+// everything visible there and the next book still shows size at that
+// price, the level refilled:
+//   revealed += traded - visible_before + visible_after
+// i.e. the volume that came out of hiding. After min_refills refills the
+// level is flagged, with hidden_size = revealed so far (a lower bound on
+// the reserve) and display_size = the mean refill. A level is forgotten
+// when it leaves the book or goes expiry_ms without refilling.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{L2Book, Levels, Side};
+
+pub(crate) fn same_price(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(1.0)
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct Iceberg {
+    // Resting side, "buy" or "sell"
+    pub side: &'static str,
+    pub price: f64,
+    pub refills: u32,
+    pub display_size: f64,
+    pub hidden_size: f64,
+    pub last_ts: i64,
+}
+
+#[pymethods]
+impl Iceberg {
+    fn __repr__(&self) -> String {
+        format!(
+            "Iceberg(side={:?}, price={}, refills={}, display_size={}, hidden_size={})",
+            self.side, self.price, self.refills, self.display_size, self.hidden_size
+        )
+    }
+}
+
+struct Candidate {
+    side: Side,
+    price: f64,
+    refills: u32,
+    displayed: f64,
+    revealed: f64,
+    last_ts: i64,
+}
+
+#[pyclass]
+pub struct IcebergDetector {
+    depth: usize,
+    min_refills: u32,
+    expiry_ms: i64,
+    // Top levels per side as of the last observe
+    bids: Levels,
+    asks: Levels,
+    // (hit side, price, volume) printed since the last observe
+    prints: Vec<(Side, f64, f64)>,
+    candidates: Vec<Candidate>,
+}
+
+impl IcebergDetector {
+    fn ladder(&self, side: Side) -> &Levels {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn candidate(&self, side: Side, price: f64) -> Option<&Candidate> {
+        self.candidates
+            .iter()
+            .find(|c| c.side == side && same_price(c.price, price))
+    }
+
+    fn refill(&mut self, side: Side, price: f64, revealed: f64, after: f64, ts: i64) {
+        let i = match self
+            .candidates
+            .iter()
+            .position(|c| c.side == side && same_price(c.price, price))
+        {
+            Some(i) => i,
+            None => {
+                self.candidates.push(Candidate {
+                    side,
+                    price,
+                    refills: 0,
+                    displayed: 0.0,
+                    revealed: 0.0,
+                    last_ts: ts,
+                });
+                self.candidates.len() - 1
+            }
+        };
+        let c = &mut self.candidates[i];
+        c.refills += 1;
+        c.displayed += after;
+        c.revealed += revealed;
+        c.last_ts = ts;
+    }
+
+    fn flagged(&self, c: &Candidate) -> Option<Iceberg> {
+        (c.refills >= self.min_refills).then(|| Iceberg {
+            side: c.side.name(),
+            price: c.price,
+            refills: c.refills,
+            display_size: c.displayed / c.refills as f64,
+            hidden_size: c.revealed,
+            last_ts: c.last_ts,
+        })
+    }
+}
+
+#[pymethods]
+impl IcebergDetector {
+    // depth bounds the levels watched per side; min_refills is how many
+    // refills flag a level
+    #[new]
+    #[pyo3(signature = (depth=10, min_refills=2, expiry_ms=60_000))]
+    pub fn new(depth: usize, min_refills: u32, expiry_ms: i64) -> PyResult<Self> {
+        if depth == 0 || min_refills == 0 || expiry_ms <= 0 {
+            return Err(PyValueError::new_err(
+                "depth, min_refills and expiry_ms must be positive",
+            ));
+        }
+        Ok(Self {
+            depth,
+            min_refills,
+            expiry_ms,
+            bids: Vec::new(),
+            asks: Vec::new(),
+            prints: Vec::new(),
+            candidates: Vec::new(),
+        })
+    }
+
+    // Public trade print; side is the aggressor ("buy" lifts asks). Without
+    // it the side is inferred from the last observed touch, and prints
+    // inside the spread are ignored.
+    #[pyo3(signature = (price, qty, side=None))]
+    pub fn on_trade(&mut self, price: f64, qty: f64, side: Option<&str>) -> PyResult<()> {
+        let hit = match side.map(Side::parse).transpose()? {
+            Some(Side::Bid) => Side::Ask,
+            Some(Side::Ask) => Side::Bid,
+            None => match (self.bids.first(), self.asks.first()) {
+                (Some((b, _)), _) if price <= *b => Side::Bid,
+                (_, Some((a, _))) if price >= *a => Side::Ask,
+                _ => return Ok(()),
+            },
+        };
+        match self
+            .prints
+            .iter_mut()
+            .find(|(s, p, _)| *s == hit && same_price(*p, price))
+        {
+            Some((_, _, v)) => *v += qty,
+            None => self.prints.push((hit, price, qty)),
+        }
+        Ok(())
+    }
+
+    // Compare the book with the previous observation and the prints since,
+    // then take its top `depth` levels as the new reference
+    pub fn observe(&mut self, book: &L2Book, ts: i64) {
+        for (side, price, traded) in std::mem::take(&mut self.prints) {
+            let before = self
+                .ladder(side)
+                .iter()
+                .find(|(p, _)| same_price(*p, price))
+                .map(|(_, s)| *s);
+            let after = book.level_size(side, price);
+            if let Some(before) = before {
+                if after > 0.0 && traded >= before * (1.0 - 1e-9) {
+                    self.refill(side, price, traded - before + after, after, ts);
+                }
+            }
+        }
+        let expiry_ms = self.expiry_ms;
+        self.candidates
+            .retain(|c| book.level_size(c.side, c.price) > 0.0 && ts - c.last_ts <= expiry_ms);
+        self.bids = book.bid_levels().take(self.depth).collect();
+        self.asks = book.ask_levels().take(self.depth).collect();
+    }
+
+    // Flagged levels, largest hidden size first; side limits them to
+    // "buy"/"bid" or "sell"/"ask" resting orders
+    #[pyo3(signature = (side=None))]
+    pub fn icebergs(&self, side: Option<&str>) -> PyResult<Vec<Iceberg>> {
+        let side = side.map(Side::parse).transpose()?;
+        let mut out: Vec<Iceberg> = self
+            .candidates
+            .iter()
+            .filter(|c| side.is_none_or(|s| s == c.side))
+            .filter_map(|c| self.flagged(c))
+            .collect();
+        out.sort_by(|a, b| b.hidden_size.total_cmp(&a.hidden_size));
+        Ok(out)
+    }
+
+    // Hidden size revealed at a flagged level; None if it is not flagged
+    pub fn hidden_size(&self, side: &str, price: f64) -> PyResult<Option<f64>> {
+        let side = Side::parse(side)?;
+        Ok(self
+            .candidate(side, price)
+            .and_then(|c| self.flagged(c))
+            .map(|i| i.hidden_size))
+    }
+
+    pub fn reset(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.prints.clear();
+        self.candidates.clear();
+    }
+}
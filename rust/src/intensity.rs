@@ -0,0 +1,159 @@
+// Online estimate of the fill intensity λ(δ) = A·e^(-k·δ) used by the
+// Avellaneda-Stoikov (kappa = k) and GLFT (A, k) quote models. Trades are
+// kept as (ts, side, distance from mid) over a trailing window_ms; fit()
+// regresses ln λ on depth over the window as GlftQuoter.fit_intensity()
+// does, with A per time_unit_ms. Buy trades lift asks, so they measure how
+// deep our asks get filled; sell trades measure bids.
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+use crate::glft::{fit_exponential, rate_curve, GlftQuoter};
+use crate::Side;
+
+#[pyclass]
+pub struct IntensityEstimator {
+    window_ms: i64,
+    time_unit_ms: i64,
+    levels: usize,
+    min_samples: usize,
+    clock: Option<Clock>,
+    // (ts, aggressor side, distance)
+    samples: VecDeque<(i64, Side, f64)>,
+    first_ts: Option<i64>,
+    last_ts: i64,
+}
+
+impl IntensityEstimator {
+    fn push(&mut self, ts: i64, side: Side, distance: f64) {
+        self.last_ts = self.last_ts.max(ts);
+        self.first_ts.get_or_insert(ts);
+        self.samples.push_back((ts, side, distance.abs()));
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self
+            .samples
+            .front()
+            .is_some_and(|(t, _, _)| self.last_ts - t > self.window_ms)
+        {
+            self.samples.pop_front();
+        }
+    }
+}
+
+#[pymethods]
+impl IntensityEstimator {
+    // A comes out per time_unit_ms (default per second); levels is the
+    // depth grid of the regression
+    #[new]
+    #[pyo3(signature = (window_ms=300_000, time_unit_ms=1_000, levels=10, min_samples=50, clock=None))]
+    pub fn new(
+        window_ms: i64,
+        time_unit_ms: i64,
+        levels: usize,
+        min_samples: usize,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if window_ms <= 0 || time_unit_ms <= 0 {
+            return Err(PyValueError::new_err(
+                "window_ms and time_unit_ms must be positive",
+            ));
+        }
+        if levels < 2 {
+            return Err(PyValueError::new_err("levels must be at least 2"));
+        }
+        Ok(Self {
+            window_ms,
+            time_unit_ms,
+            levels,
+            min_samples,
+            clock,
+            samples: VecDeque::new(),
+            first_ts: None,
+            last_ts: i64::MIN,
+        })
+    }
+
+    // Public trade at `price` with `mid` just before it; side is the
+    // aggressor
+    #[pyo3(signature = (price, mid, side, ts=None))]
+    pub fn on_trade(&mut self, price: f64, mid: f64, side: &str, ts: Option<i64>) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        let ts = clock::require(ts, &self.clock)?;
+        self.push(ts, side, price - mid);
+        Ok(())
+    }
+
+    // Distance to mid recorded elsewhere, e.g. of own fills
+    #[pyo3(signature = (distance, side, ts=None))]
+    pub fn add_distance(&mut self, distance: f64, side: &str, ts: Option<i64>) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        let ts = clock::require(ts, &self.clock)?;
+        self.push(ts, side, distance);
+        Ok(())
+    }
+
+    // (A, k) over the window, from trades of one aggressor side or both
+    // (then A counts both sides); None below min_samples or without decay
+    #[pyo3(signature = (side=None))]
+    pub fn fit(&self, side: Option<&str>) -> PyResult<Option<(f64, f64)>> {
+        let side = side.map(Side::parse).transpose()?;
+        let distances: Vec<f64> = self
+            .samples
+            .iter()
+            .filter(|(_, s, _)| side.is_none_or(|side| side == *s))
+            .map(|(_, _, d)| *d)
+            .collect();
+        let Some(first) = self.first_ts else {
+            return Ok(None);
+        };
+        if distances.len() < self.min_samples.max(2) {
+            return Ok(None);
+        }
+        // Observed span: the window once full, the elapsed time before
+        let span = (self.last_ts - first).clamp(1, self.window_ms);
+        let duration = span as f64 / self.time_unit_ms as f64;
+        Ok(fit_exponential(&rate_curve(
+            &distances,
+            duration,
+            self.levels,
+        )))
+    }
+
+    // Fit and update the quoter's a and k; returns the fit
+    #[pyo3(signature = (quoter, side=None))]
+    pub fn apply(
+        &self,
+        mut quoter: PyRefMut<'_, GlftQuoter>,
+        side: Option<&str>,
+    ) -> PyResult<Option<(f64, f64)>> {
+        let fitted = self.fit(side)?;
+        if let Some((a, k)) = fitted {
+            quoter.a = a;
+            quoter.k = k;
+        }
+        Ok(fitted)
+    }
+
+    // Age the window without a trade; ts defaults to the clock's time
+    #[pyo3(signature = (ts=None))]
+    pub fn advance(&mut self, ts: Option<i64>) -> PyResult<()> {
+        self.last_ts = self.last_ts.max(clock::require(ts, &self.clock)?);
+        self.evict();
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.first_ts = None;
+        self.last_ts = i64::MIN;
+    }
+}
@@ -0,0 +1,173 @@
+// L2Book invariant checks, for validate() and the debug_checks mode:
+//   unsorted           a side out of order (bids descending, asks ascending)
+//   duplicate_level    two levels on one side at the same price
+//   non_positive_size  a level with size <= 0
+//   non_finite         a NaN or infinite price or size
+//   crossed            best bid >= best ask, unless allowed
+//   excess_levels      more levels on a side than max_levels
+// The checks read the book the way its getters do, after price decoding, so
+// they hold whatever the ladder keys are. Most cannot fail through the update
+// paths, which reject non-finite and negative input (see errors.rs); what
+// gets through in practice is crosses.
+use pyo3::prelude::*;
+
+use crate::L2Book;
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct BookIssue {
+    pub kind: &'static str,
+    // "bid" or "ask"; None for crossed
+    pub side: Option<&'static str>,
+    pub price: Option<f64>,
+    pub size: Option<f64>,
+}
+
+#[pymethods]
+impl BookIssue {
+    fn __repr__(&self) -> String {
+        format!(
+            "BookIssue(kind={:?}, side={:?}, price={:?}, size={:?})",
+            self.kind, self.side, self.price, self.size
+        )
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct BookValidation {
+    pub ok: bool,
+    pub crossed: bool,
+    pub best_bid: Option<(f64, f64)>,
+    pub best_ask: Option<(f64, f64)>,
+    pub bid_levels: usize,
+    pub ask_levels: usize,
+    // In check order: bids, asks, then the cross
+    pub issues: Vec<BookIssue>,
+}
+
+impl BookValidation {
+    // One line naming the first few issues, for error messages
+    pub(crate) fn summary(&self) -> String {
+        let mut parts: Vec<String> = self
+            .issues
+            .iter()
+            .take(3)
+            .map(|i| match (i.side, i.price) {
+                (Some(side), Some(price)) => format!("{} {} at {}", i.kind, side, price),
+                _ => i.kind.to_owned(),
+            })
+            .collect();
+        if self.issues.len() > 3 {
+            parts.push(format!("{} more", self.issues.len() - 3));
+        }
+        parts.join(", ")
+    }
+}
+
+#[pymethods]
+impl BookValidation {
+    fn __bool__(&self) -> bool {
+        self.ok
+    }
+
+    // Issue kinds present, in first-seen order
+    pub fn kinds(&self) -> Vec<&'static str> {
+        let mut out = Vec::new();
+        for i in &self.issues {
+            if !out.contains(&i.kind) {
+                out.push(i.kind);
+            }
+        }
+        out
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BookValidation(ok={}, bid_levels={}, ask_levels={}, issues={:?})",
+            self.ok,
+            self.bid_levels,
+            self.ask_levels,
+            self.kinds()
+        )
+    }
+}
+
+// Issues on one side, levels best first; `better(a, b)` is a's price
+// strictly ahead of b's in book order
+fn check_side(
+    side: &'static str,
+    levels: impl Iterator<Item = (f64, f64)>,
+    better: fn(f64, f64) -> bool,
+    max_levels: Option<usize>,
+    issues: &mut Vec<BookIssue>,
+) -> usize {
+    let issue = |kind, price, size| BookIssue {
+        kind,
+        side: Some(side),
+        price: Some(price),
+        size: Some(size),
+    };
+    let mut prev: Option<f64> = None;
+    let mut count = 0;
+    for (price, size) in levels {
+        count += 1;
+        if !price.is_finite() || !size.is_finite() {
+            issues.push(issue("non_finite", price, size));
+        }
+        if size <= 0.0 {
+            issues.push(issue("non_positive_size", price, size));
+        }
+        match prev {
+            Some(p) if p == price => issues.push(issue("duplicate_level", price, size)),
+            Some(p) if !better(p, price) => issues.push(issue("unsorted", price, size)),
+            _ => {}
+        }
+        prev = Some(price);
+    }
+    if max_levels.is_some_and(|max| count > max) {
+        issues.push(BookIssue {
+            kind: "excess_levels",
+            side: Some(side),
+            price: None,
+            size: None,
+        });
+    }
+    count
+}
+
+pub(crate) fn check(book: &L2Book, allow_crossed: bool) -> BookValidation {
+    let mut issues = Vec::new();
+    let bid_levels = check_side(
+        "bid",
+        book.bid_levels(),
+        |a, b| a > b,
+        book.max_levels,
+        &mut issues,
+    );
+    let ask_levels = check_side(
+        "ask",
+        book.ask_levels(),
+        |a, b| a < b,
+        book.max_levels,
+        &mut issues,
+    );
+    let crossed = book.is_crossed();
+    if crossed && !allow_crossed {
+        issues.push(BookIssue {
+            kind: "crossed",
+            side: None,
+            price: None,
+            size: None,
+        });
+    }
+    BookValidation {
+        ok: issues.is_empty(),
+        crossed,
+        best_bid: book.best_bid(),
+        best_ask: book.best_ask(),
+        bid_levels,
+        ask_levels,
+        issues,
+    }
+}
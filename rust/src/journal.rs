@@ -0,0 +1,1044 @@
+// Compact binary journal for raw market-data capture. Records are
+// fixed-layout little-endian, buffered into chunks and written as one zstd
+// frame per chunk, so a journal is a plain .zst stream (`zstd -d` works).
+//
+// Record header, 32 bytes:
+//   0  ts         i64
+//   8  kind       u8   0 symbol definition, 1 snapshot, 2 delta, 3 trade
+//   9  side       u8   trades: 1 buy, 2 sell
+//   10 symbol_id  u16
+//   12 n_bids     u32  symbol definitions: name length in bytes
+//   16 n_asks     u32
+//   20 reserved   u32
+//   24 update_id  u64  u64::MAX when absent
+// followed by (price, size) f64 pairs for book records, (price, qty) f64
+// for trades, or the UTF-8 name padded to 8 bytes for symbol definitions.
+// Symbol ids are scoped to a chunk, so independently written journals can be
+// appended to one another.
+//
+// The writer also appends one checkpoint per chunk to `{path}.idx`: the
+// 8-byte magic "MMJIDX01", then 40-byte entries
+//   0  offset   u64  first byte of the chunk's frame in the journal
+//   8  end      u64  one past its last byte
+//   16 min_ts   i64
+//   24 max_ts   i64
+//   32 records  u64
+// Readers use the entries that tile the journal from byte 0 and scan
+// whatever follows, so a missing or stale index only costs speed. They keep
+// the file open and load one frame at a time, so memory stays at a chunk
+// however long the journal is.
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::arrow::{ArrowBatch, Batch, Column};
+use crate::clock::Clock;
+use crate::trades::{Trade, TradeTape};
+use crate::{zstd, L2Book, Levels, Side};
+
+const HEADER: usize = 32;
+const KIND_SYMBOL: u8 = 0;
+const KIND_SNAPSHOT: u8 = 1;
+const KIND_DELTA: u8 = 2;
+const KIND_TRADE: u8 = 3;
+const NO_UPDATE_ID: u64 = u64::MAX;
+const INDEX_MAGIC: &[u8] = b"MMJIDX01";
+const INDEX_ENTRY: usize = 40;
+
+// Time span and position of one chunk
+#[derive(Clone, Copy, Debug)]
+struct Checkpoint {
+    offset: u64,
+    end: u64,
+    min_ts: i64,
+    max_ts: i64,
+    records: u64,
+}
+
+impl Checkpoint {
+    fn new(offset: u64) -> Self {
+        Self {
+            offset,
+            end: offset,
+            min_ts: i64::MAX,
+            max_ts: i64::MIN,
+            records: 0,
+        }
+    }
+
+    fn include(&mut self, ts: i64) {
+        self.min_ts = self.min_ts.min(ts);
+        self.max_ts = self.max_ts.max(ts);
+        self.records += 1;
+    }
+
+    fn encode(&self) -> [u8; INDEX_ENTRY] {
+        let mut out = [0u8; INDEX_ENTRY];
+        out[0..8].copy_from_slice(&self.offset.to_le_bytes());
+        out[8..16].copy_from_slice(&self.end.to_le_bytes());
+        out[16..24].copy_from_slice(&self.min_ts.to_le_bytes());
+        out[24..32].copy_from_slice(&self.max_ts.to_le_bytes());
+        out[32..40].copy_from_slice(&self.records.to_le_bytes());
+        out
+    }
+
+    fn decode(b: &[u8]) -> Self {
+        let word = |i: usize| u64::from_le_bytes(b[i..i + 8].try_into().unwrap());
+        Self {
+            offset: word(0),
+            end: word(8),
+            min_ts: word(16) as i64,
+            max_ts: word(24) as i64,
+            records: word(32),
+        }
+    }
+}
+
+fn index_path(path: &Path) -> PathBuf {
+    let mut p = path.as_os_str().to_owned();
+    p.push(".idx");
+    p.into()
+}
+
+// Entries of `path`'s index that tile the first bytes of a journal of
+// `len` bytes; anything unreadable or inconsistent ends the prefix
+fn load_index(path: &Path, len: u64) -> Vec<Checkpoint> {
+    let Ok(raw) = fs::read(index_path(path)) else {
+        return Vec::new();
+    };
+    let Some(entries) = raw.strip_prefix(INDEX_MAGIC) else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    let mut expected = 0;
+    for cp in entries.chunks_exact(INDEX_ENTRY).map(Checkpoint::decode) {
+        if cp.offset != expected || cp.end <= cp.offset || cp.end > len {
+            break;
+        }
+        expected = cp.end;
+        out.push(cp);
+    }
+    out
+}
+
+#[pyclass]
+pub struct JournalWriter {
+    file: Option<File>,
+    path: PathBuf,
+    chunk: Vec<u8>,
+    chunk_bytes: usize,
+    // Symbol ids defined in the current chunk
+    symbols: HashMap<String, u16>,
+    records: u64,
+    index: File,
+    // Span of the buffered chunk; offsets are filled in on flush
+    span: Checkpoint,
+}
+
+impl JournalWriter {
+    fn header(&mut self, ts: i64, kind: u8, side: u8, symbol: u16, n: (u32, u32), update_id: u64) {
+        self.chunk.extend_from_slice(&ts.to_le_bytes());
+        self.chunk.extend_from_slice(&[kind, side]);
+        self.chunk.extend_from_slice(&symbol.to_le_bytes());
+        self.chunk.extend_from_slice(&n.0.to_le_bytes());
+        self.chunk.extend_from_slice(&n.1.to_le_bytes());
+        self.chunk.extend_from_slice(&[0; 4]);
+        self.chunk.extend_from_slice(&update_id.to_le_bytes());
+    }
+
+    // Id of `symbol` in the current chunk, defining it on first use
+    fn symbol_id(&mut self, symbol: &str, ts: i64) -> PyResult<u16> {
+        if let Some(id) = self.symbols.get(symbol) {
+            return Ok(*id);
+        }
+        if self.symbols.len() > u16::MAX as usize {
+            self.flush()?;
+        }
+        let id = self.symbols.len() as u16;
+        let name = symbol.as_bytes();
+        self.header(ts, KIND_SYMBOL, 0, id, (name.len() as u32, 0), NO_UPDATE_ID);
+        self.chunk.extend_from_slice(name);
+        self.chunk.resize(self.chunk.len().next_multiple_of(8), 0);
+        self.symbols.insert(symbol.to_owned(), id);
+        Ok(id)
+    }
+
+    fn write_book(
+        &mut self,
+        kind: u8,
+        symbol: &str,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        let id = self.symbol_id(symbol, ts)?;
+        let n = (bids.len() as u32, asks.len() as u32);
+        self.header(ts, kind, 0, id, n, update_id.unwrap_or(NO_UPDATE_ID));
+        for (p, s) in bids.into_iter().chain(asks) {
+            self.chunk.extend_from_slice(&p.to_le_bytes());
+            self.chunk.extend_from_slice(&s.to_le_bytes());
+        }
+        self.written(ts)
+    }
+
+    fn written(&mut self, ts: i64) -> PyResult<()> {
+        self.records += 1;
+        self.span.include(ts);
+        if self.chunk.len() >= self.chunk_bytes {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl JournalWriter {
+    // Appends to an existing journal
+    #[new]
+    #[pyo3(signature = (path, chunk_bytes=1 << 20))]
+    pub fn new(path: PathBuf, chunk_bytes: usize) -> PyResult<Self> {
+        if chunk_bytes == 0 {
+            return Err(PyValueError::new_err("chunk_bytes must be positive"));
+        }
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let mut index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(index_path(&path))?;
+        if index.metadata()?.len() == 0 {
+            index.write_all(INDEX_MAGIC)?;
+        }
+        Ok(Self {
+            file: Some(file),
+            path,
+            chunk: Vec::new(),
+            chunk_bytes,
+            symbols: HashMap::new(),
+            records: 0,
+            index,
+            span: Checkpoint::new(0),
+        })
+    }
+
+    #[pyo3(signature = (symbol, ts, bids, asks, update_id=None))]
+    pub fn write_snapshot(
+        &mut self,
+        symbol: &str,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        self.write_book(KIND_SNAPSHOT, symbol, ts, bids, asks, update_id)
+    }
+
+    #[pyo3(signature = (symbol, ts, bids, asks, update_id=None))]
+    pub fn write_delta(
+        &mut self,
+        symbol: &str,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        self.write_book(KIND_DELTA, symbol, ts, bids, asks, update_id)
+    }
+
+    pub fn write_trade(
+        &mut self,
+        symbol: &str,
+        ts: i64,
+        price: f64,
+        qty: f64,
+        side: &str,
+    ) -> PyResult<()> {
+        let side = match Side::parse(side)? {
+            Side::Bid => 1,
+            Side::Ask => 2,
+        };
+        let id = self.symbol_id(symbol, ts)?;
+        self.header(ts, KIND_TRADE, side, id, (0, 0), NO_UPDATE_ID);
+        self.chunk.extend_from_slice(&price.to_le_bytes());
+        self.chunk.extend_from_slice(&qty.to_le_bytes());
+        self.written(ts)
+    }
+
+    // Compress the buffered chunk, append it to the file and checkpoint it
+    // in the index
+    pub fn flush(&mut self) -> PyResult<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let Some(file) = self.file.as_mut() else {
+            return Err(PyValueError::new_err("journal is closed"));
+        };
+        let frame = zstd::compress(&self.chunk);
+        let offset = file.metadata()?.len();
+        file.write_all(&frame)?;
+        let span = Checkpoint {
+            offset,
+            end: offset + frame.len() as u64,
+            ..self.span
+        };
+        self.index.write_all(&span.encode())?;
+        self.chunk.clear();
+        self.symbols.clear();
+        self.span = Checkpoint::new(0);
+        Ok(())
+    }
+
+    pub fn close(&mut self) -> PyResult<()> {
+        if self.file.is_some() {
+            self.flush()?;
+            self.file = None;
+        }
+        Ok(())
+    }
+
+    #[getter]
+    fn records(&self) -> u64 {
+        self.records
+    }
+
+    #[getter]
+    fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+}
+
+impl Drop for JournalWriter {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub struct JournalRecord {
+    // "snapshot", "delta" or "trade"
+    pub kind: &'static str,
+    pub symbol: String,
+    pub ts: i64,
+    pub bids: Levels,
+    pub asks: Levels,
+    pub update_id: Option<u64>,
+    // Trades only
+    pub price: Option<f64>,
+    pub qty: Option<f64>,
+    pub side: Option<&'static str>,
+}
+
+#[pymethods]
+impl JournalRecord {
+    fn __repr__(&self) -> String {
+        match self.kind {
+            "trade" => format!(
+                "JournalRecord(trade, {}, ts={}, price={}, qty={}, side={})",
+                self.symbol,
+                self.ts,
+                self.price.unwrap_or(f64::NAN),
+                self.qty.unwrap_or(f64::NAN),
+                self.side.unwrap_or("?")
+            ),
+            kind => format!(
+                "JournalRecord({}, {}, ts={}, bids={}, asks={}, update_id={})",
+                kind,
+                self.symbol,
+                self.ts,
+                self.bids.len(),
+                self.asks.len(),
+                self.update_id
+                    .map_or_else(|| "None".to_owned(), |u| u.to_string())
+            ),
+        }
+    }
+}
+
+// Columns of JournalReader.to_arrow, in default order
+const COLUMNS: [&str; 8] = [
+    "ts",
+    "seq",
+    "symbol",
+    "kind",
+    "side",
+    "price",
+    "size",
+    "update_id",
+];
+
+// Record filter and column selection applied while reading
+struct Query {
+    // Indices into COLUMNS, in output order
+    columns: Vec<usize>,
+    start_ts: Option<i64>,
+    end_ts: Option<i64>,
+    symbols: Option<HashSet<String>>,
+}
+
+impl Query {
+    fn new(
+        columns: Option<Vec<String>>,
+        start_ts: Option<i64>,
+        end_ts: Option<i64>,
+        symbols: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        let columns = match columns {
+            None => (0..COLUMNS.len()).collect(),
+            Some(names) => {
+                let mut idx = Vec::with_capacity(names.len());
+                for name in &names {
+                    let i = COLUMNS.iter().position(|c| c == name).ok_or_else(|| {
+                        PyValueError::new_err(format!(
+                            "unknown column '{}', expected one of {:?}",
+                            name, COLUMNS
+                        ))
+                    })?;
+                    if idx.contains(&i) {
+                        return Err(PyValueError::new_err(format!(
+                            "column '{}' selected twice",
+                            name
+                        )));
+                    }
+                    idx.push(i);
+                }
+                idx
+            }
+        };
+        Ok(Self {
+            columns,
+            start_ts,
+            end_ts,
+            symbols: symbols.map(|s| s.into_iter().collect()),
+        })
+    }
+
+    fn overlaps(&self, cp: &Checkpoint) -> bool {
+        self.start_ts.is_none_or(|t| cp.max_ts >= t) && self.end_ts.is_none_or(|t| cp.min_ts < t)
+    }
+
+    // start_ts inclusive, end_ts exclusive
+    fn matches(&self, rec: &JournalRecord) -> bool {
+        self.start_ts.is_none_or(|t| rec.ts >= t)
+            && self.end_ts.is_none_or(|t| rec.ts < t)
+            && self
+                .symbols
+                .as_ref()
+                .is_none_or(|s| s.contains(&rec.symbol))
+    }
+}
+
+// Column buffers for JournalReader.to_arrow; symbols are interned so the
+// per-row cost is the same for every column
+#[derive(Default)]
+struct Rows {
+    ts: Vec<i64>,
+    seq: Vec<i64>,
+    symbol: Vec<u32>,
+    kind: Vec<&'static str>,
+    side: Vec<&'static str>,
+    price: Vec<f64>,
+    size: Vec<f64>,
+    update_id: Vec<Option<u64>>,
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+}
+
+impl Rows {
+    fn row(
+        &mut self,
+        seq: i64,
+        rec: &JournalRecord,
+        symbol: u32,
+        side: &'static str,
+        (price, size): (f64, f64),
+    ) {
+        self.ts.push(rec.ts);
+        self.seq.push(seq);
+        self.symbol.push(symbol);
+        self.kind.push(rec.kind);
+        self.side.push(side);
+        self.price.push(price);
+        self.size.push(size);
+        self.update_id.push(rec.update_id);
+    }
+
+    fn push(&mut self, seq: i64, rec: &JournalRecord) {
+        let symbol = match self.ids.get(&rec.symbol) {
+            Some(id) => *id,
+            None => {
+                let id = self.names.len() as u32;
+                self.names.push(rec.symbol.clone());
+                self.ids.insert(rec.symbol.clone(), id);
+                id
+            }
+        };
+        if rec.kind == "trade" {
+            let trade = (rec.price.unwrap_or(0.0), rec.qty.unwrap_or(0.0));
+            self.row(seq, rec, symbol, rec.side.unwrap_or("sell"), trade);
+            return;
+        }
+        for level in &rec.bids {
+            self.row(seq, rec, symbol, "bid", *level);
+        }
+        for level in &rec.asks {
+            self.row(seq, rec, symbol, "ask", *level);
+        }
+    }
+
+    fn into_batch(mut self, columns: &[usize]) -> Batch {
+        let columns = columns.iter().map(|&i| match COLUMNS[i] {
+            "ts" => Column::i64("ts", std::mem::take(&mut self.ts)),
+            "seq" => Column::i64("seq", std::mem::take(&mut self.seq)),
+            "symbol" => Column::utf8(
+                "symbol",
+                self.symbol
+                    .iter()
+                    .map(|&id| self.names[id as usize].as_str()),
+            ),
+            "kind" => Column::utf8("kind", self.kind.iter().copied()),
+            "side" => Column::utf8("side", self.side.iter().copied()),
+            "price" => Column::f64("price", std::mem::take(&mut self.price)),
+            "size" => Column::f64("size", std::mem::take(&mut self.size)),
+            "update_id" => Column::opt_u64("update_id", self.update_id.iter().copied()),
+            other => unreachable!("unknown column {}", other),
+        });
+        Batch::new(columns.collect())
+    }
+}
+
+#[pyclass]
+pub struct JournalReader {
+    path: PathBuf,
+    frames: Frames,
+    cursor: Cursor,
+    // Valid prefix of the on-disk index
+    index: Vec<Checkpoint>,
+}
+
+// The open journal file, read a frame at a time. Cursors share it, each
+// seeking to its own offset under the lock.
+struct Frames {
+    file: Mutex<BufReader<File>>,
+}
+
+impl Frames {
+    // Decompress the frame at `*pos` into `out` and step past it; Ok(false)
+    // at the end of the file
+    fn load(&self, pos: &mut u64, out: &mut Vec<u8>) -> Result<bool, String> {
+        let mut raw = Vec::new();
+        {
+            let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+            file.seek(SeekFrom::Start(*pos))
+                .map_err(|e| e.to_string())?;
+            if !zstd::read_frame(&mut *file, &mut raw).map_err(|e| e.to_string())? {
+                return Ok(false);
+            }
+        }
+        zstd::decompress_frame(&raw, &mut 0, out)?;
+        *pos += raw.len() as u64;
+        Ok(true)
+    }
+}
+
+// Read position within a journal's bytes
+#[derive(Default)]
+struct Cursor {
+    // Offset of the next zstd frame
+    pos: u64,
+    chunk: Vec<u8>,
+    cpos: usize,
+    symbols: HashMap<u16, String>,
+    // Record read ahead by seek()
+    pending: Option<JournalRecord>,
+}
+
+fn f64_at(b: &[u8], at: usize) -> f64 {
+    f64::from_le_bytes(b[at..at + 8].try_into().unwrap())
+}
+
+impl Cursor {
+    fn at(offset: u64) -> Self {
+        Self {
+            pos: offset,
+            ..Default::default()
+        }
+    }
+
+    // Whether the next record starts a new frame
+    fn at_frame_boundary(&self) -> bool {
+        self.cpos >= self.chunk.len() && self.pending.is_none()
+    }
+
+    fn next(&mut self, frames: &Frames, path: &Path) -> PyResult<Option<JournalRecord>> {
+        if let Some(rec) = self.pending.take() {
+            return Ok(Some(rec));
+        }
+        let corrupt = |what: &str, pos: u64| {
+            PyValueError::new_err(format!(
+                "corrupt journal {}: {} (frame ending at byte {})",
+                path.display(),
+                what,
+                pos
+            ))
+        };
+        loop {
+            if self.cpos >= self.chunk.len() {
+                self.chunk.clear();
+                self.cpos = 0;
+                self.symbols.clear();
+                if !frames
+                    .load(&mut self.pos, &mut self.chunk)
+                    .map_err(|e| corrupt(&e, self.pos))?
+                {
+                    return Ok(None);
+                }
+                continue;
+            }
+            let b = &self.chunk[self.cpos..];
+            if b.len() < HEADER {
+                return Err(corrupt("truncated record header", self.pos));
+            }
+            let ts = i64::from_le_bytes(b[0..8].try_into().unwrap());
+            let (kind, side) = (b[8], b[9]);
+            let id = u16::from_le_bytes(b[10..12].try_into().unwrap());
+            let n_bids = u32::from_le_bytes(b[12..16].try_into().unwrap()) as usize;
+            let n_asks = u32::from_le_bytes(b[16..20].try_into().unwrap()) as usize;
+            let update_id = u64::from_le_bytes(b[24..32].try_into().unwrap());
+            let payload = match kind {
+                KIND_SYMBOL => n_bids.next_multiple_of(8),
+                KIND_SNAPSHOT | KIND_DELTA => (n_bids + n_asks) * 16,
+                KIND_TRADE => 16,
+                k => return Err(corrupt(&format!("unknown record kind {}", k), self.pos)),
+            };
+            if b.len() < HEADER + payload {
+                return Err(corrupt("truncated record payload", self.pos));
+            }
+            let body = &b[HEADER..HEADER + payload];
+            self.cpos += HEADER + payload;
+            if kind == KIND_SYMBOL {
+                let name = String::from_utf8_lossy(&body[..n_bids]).into_owned();
+                self.symbols.insert(id, name);
+                continue;
+            }
+            let Some(symbol) = self.symbols.get(&id).cloned() else {
+                return Err(corrupt(&format!("undefined symbol id {}", id), self.pos));
+            };
+            let mut rec = JournalRecord {
+                kind: "trade",
+                symbol,
+                ts,
+                bids: Vec::new(),
+                asks: Vec::new(),
+                update_id: None,
+                price: None,
+                qty: None,
+                side: None,
+            };
+            if kind == KIND_TRADE {
+                rec.price = Some(f64_at(body, 0));
+                rec.qty = Some(f64_at(body, 8));
+                rec.side = Some(if side == 1 { "buy" } else { "sell" });
+            } else {
+                let mut levels =
+                    (0..n_bids + n_asks).map(|i| (f64_at(body, i * 16), f64_at(body, i * 16 + 8)));
+                rec.kind = if kind == KIND_SNAPSHOT {
+                    "snapshot"
+                } else {
+                    "delta"
+                };
+                rec.bids = levels.by_ref().take(n_bids).collect();
+                rec.asks = levels.collect();
+                rec.update_id = (update_id != NO_UPDATE_ID).then_some(update_id);
+            }
+            return Ok(Some(rec));
+        }
+    }
+}
+
+impl JournalReader {
+    pub(crate) fn next_record(&mut self) -> PyResult<Option<JournalRecord>> {
+        self.cursor.next(&self.frames, &self.path)
+    }
+
+    // Hand `rec` out again on the next read
+    pub(crate) fn unread(&mut self, rec: JournalRecord) {
+        self.cursor.pending = Some(rec);
+    }
+
+    // Records of the frame at `offset`, with its checkpoint
+    fn frame(&self, offset: u64) -> PyResult<(Checkpoint, Vec<JournalRecord>)> {
+        let mut cursor = Cursor::at(offset);
+        let mut span = Checkpoint::new(offset);
+        let mut records = Vec::new();
+        loop {
+            match cursor.next(&self.frames, &self.path)? {
+                Some(rec) => {
+                    span.include(rec.ts);
+                    records.push(rec);
+                }
+                None => break,
+            }
+            if cursor.at_frame_boundary() {
+                break;
+            }
+        }
+        span.end = cursor.pos;
+        Ok((span, records))
+    }
+
+    // End of the indexed prefix
+    fn indexed_end(&self) -> u64 {
+        self.index.last().map_or(0, |cp| cp.end)
+    }
+}
+
+#[pymethods]
+impl JournalReader {
+    #[new]
+    pub fn new(path: PathBuf) -> PyResult<Self> {
+        let file = File::open(&path)?;
+        let index = load_index(&path, file.metadata()?.len());
+        Ok(Self {
+            path,
+            frames: Frames {
+                file: Mutex::new(BufReader::new(file)),
+            },
+            cursor: Cursor::default(),
+            index,
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<JournalRecord>> {
+        self.next_record()
+    }
+
+    // Back to the first record
+    pub fn rewind(&mut self) {
+        self.cursor = Cursor::default();
+    }
+
+    // Position before the first record with ts >= `ts`, using the index to
+    // skip whole chunks (journals are assumed to be written in time order).
+    // Returns False, leaving the reader at the end, if there is none.
+    pub fn seek(&mut self, py: Python<'_>, ts: i64) -> PyResult<bool> {
+        let start = self
+            .index
+            .iter()
+            .find(|cp| cp.max_ts >= ts)
+            .map_or(self.indexed_end(), |cp| cp.offset);
+        py.allow_threads(|| {
+            let mut cursor = Cursor::at(start);
+            while let Some(rec) = cursor.next(&self.frames, &self.path)? {
+                if rec.ts >= ts {
+                    cursor.pending = Some(rec);
+                    break;
+                }
+            }
+            let found = cursor.pending.is_some();
+            self.cursor = cursor;
+            Ok(found)
+        })
+    }
+
+    // Rewrite `{path}.idx` from the journal itself, for journals written
+    // before indexing or whose index is stale. Returns the number of
+    // checkpoints.
+    pub fn build_index(&mut self, py: Python<'_>) -> PyResult<usize> {
+        let index = py.allow_threads(|| {
+            let mut index = Vec::new();
+            let mut offset = 0;
+            loop {
+                let (span, _) = self.frame(offset)?;
+                if span.end == offset {
+                    break;
+                }
+                offset = span.end;
+                index.push(span);
+            }
+            Ok::<_, PyErr>(index)
+        })?;
+        let mut raw = INDEX_MAGIC.to_vec();
+        index
+            .iter()
+            .for_each(|cp| raw.extend_from_slice(&cp.encode()));
+        fs::write(index_path(&self.path), raw)?;
+        self.index = index;
+        Ok(self.index.len())
+    }
+
+    // Usable index entries as (min_ts, max_ts, byte offset)
+    #[getter]
+    pub fn checkpoints(&self) -> Vec<(i64, i64, u64)> {
+        self.index
+            .iter()
+            .map(|cp| (cp.min_ts, cp.max_ts, cp.offset))
+            .collect()
+    }
+
+    // Apply the remaining records to the books/tapes keyed by symbol (symbols
+    // without one are skipped), calling `callback(record)` after each. With
+    // `speed`, records are paced by their timestamps: 1.0 is real time, 10.0
+    // ten times faster; without it replay runs flat out. A replay `clock` is
+    // set to each record's ts before it is applied. Deltas go through the
+    // same checks as L2Book.apply_delta: a skipped update id flags the book
+    // for resync (or raises with raise_on_gap) and its cross_policy and
+    // debug_checks hold. Returns the number of records replayed.
+    #[pyo3(signature = (books=None, tapes=None, speed=None, callback=None, clock=None))]
+    pub fn replay(
+        &mut self,
+        py: Python<'_>,
+        books: Option<&Bound<'_, PyDict>>,
+        tapes: Option<&Bound<'_, PyDict>>,
+        speed: Option<f64>,
+        callback: Option<PyObject>,
+        clock: Option<Clock>,
+    ) -> PyResult<u64> {
+        if speed.is_some_and(|s| s.is_nan() || s <= 0.0) {
+            return Err(PyValueError::new_err("speed must be positive"));
+        }
+        let started = Instant::now();
+        let mut first_ts = None;
+        let mut count = 0;
+        while let Some(rec) = self.next_record()? {
+            if let Some(speed) = speed {
+                let first = *first_ts.get_or_insert(rec.ts);
+                let due =
+                    Duration::from_secs_f64(((rec.ts - first).max(0) as f64) / speed / 1000.0);
+                let elapsed = started.elapsed();
+                if due > elapsed {
+                    py.allow_threads(|| std::thread::sleep(due - elapsed));
+                    py.check_signals()?;
+                }
+            }
+            if let Some(clock) = &clock {
+                clock.set(rec.ts);
+            }
+            if rec.kind == "trade" {
+                if let Some(tape) = tapes
+                    .map(|t| t.get_item(&rec.symbol))
+                    .transpose()?
+                    .flatten()
+                {
+                    let side = if rec.side == Some("buy") {
+                        Side::Bid
+                    } else {
+                        Side::Ask
+                    };
+                    tape.downcast::<TradeTape>()?.borrow_mut().push(Trade {
+                        ts: rec.ts,
+                        price: rec.price.unwrap_or(0.0),
+                        size: rec.qty.unwrap_or(0.0),
+                        side,
+                    });
+                }
+            } else if let Some(book) = books
+                .map(|b| b.get_item(&rec.symbol))
+                .transpose()?
+                .flatten()
+            {
+                let book = book.downcast::<L2Book>()?;
+                let mut book = book.borrow_mut();
+                let (bids, asks) = (rec.bids.clone(), rec.asks.clone());
+                if rec.kind == "snapshot" {
+                    book.load_snapshot(bids, asks, rec.update_id)?;
+                } else {
+                    book.delta(bids, asks, rec.update_id, None, None)?;
+                }
+            }
+            count += 1;
+            if let Some(cb) = &callback {
+                cb.call1(py, (rec,))?;
+            }
+        }
+        Ok(count)
+    }
+
+    // The journal as an Arrow batch (iteration position untouched), one
+    // row per book level or trade: ts, seq (record number in the journal;
+    // rows of one snapshot or delta share it), symbol, kind, side ("bid" /
+    // "ask" for levels, the aggressor "buy"/"sell" for trades), price, size
+    // and update_id (null when absent). Records outside
+    // start_ts <= ts < end_ts or not in `symbols` are skipped while reading;
+    // `columns` selects and orders the output columns.
+    #[pyo3(signature = (columns=None, start_ts=None, end_ts=None, symbols=None))]
+    pub fn to_arrow(
+        &self,
+        py: Python<'_>,
+        columns: Option<Vec<String>>,
+        start_ts: Option<i64>,
+        end_ts: Option<i64>,
+        symbols: Option<Vec<String>>,
+    ) -> PyResult<ArrowBatch> {
+        let query = Query::new(columns, start_ts, end_ts, symbols)?;
+        py.allow_threads(|| {
+            let mut rows = Rows::default();
+            let mut seq = 0;
+            let mut take = |rec: &JournalRecord, seq: &mut i64| {
+                if query.matches(rec) {
+                    rows.push(*seq, rec);
+                }
+                *seq += 1;
+            };
+            // Indexed chunks outside the time range are skipped unread
+            for cp in &self.index {
+                if !query.overlaps(cp) {
+                    seq += cp.records as i64;
+                    continue;
+                }
+                for rec in self.frame(cp.offset)?.1 {
+                    take(&rec, &mut seq);
+                }
+            }
+            let mut cursor = Cursor::at(self.indexed_end());
+            while let Some(rec) = cursor.next(&self.frames, &self.path)? {
+                take(&rec, &mut seq);
+            }
+            Ok(ArrowBatch::new(rows.into_batch(&query.columns)))
+        })
+    }
+
+    // to_arrow() as a polars DataFrame (polars imported at call time)
+    #[pyo3(signature = (columns=None, start_ts=None, end_ts=None, symbols=None))]
+    pub fn to_polars<'py>(
+        &self,
+        py: Python<'py>,
+        columns: Option<Vec<String>>,
+        start_ts: Option<i64>,
+        end_ts: Option<i64>,
+        symbols: Option<Vec<String>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let batch = Bound::new(py, self.to_arrow(py, columns, start_ts, end_ts, symbols)?)?;
+        ArrowBatch::to_polars(&batch)
+    }
+
+    #[getter]
+    fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+}
+
+// Load a journal straight into a polars DataFrame; filtering and column
+// selection as in JournalReader.to_arrow
+#[pyfunction]
+#[pyo3(signature = (path, columns=None, start_ts=None, end_ts=None, symbols=None))]
+pub fn read_journal_to_polars<'py>(
+    py: Python<'py>,
+    path: PathBuf,
+    columns: Option<Vec<String>>,
+    start_ts: Option<i64>,
+    end_ts: Option<i64>,
+    symbols: Option<Vec<String>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    JournalReader::new(path)?.to_polars(py, columns, start_ts, end_ts, symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrow::ArrowBatch;
+
+    fn record(kind: &'static str, symbol: &str, ts: i64) -> JournalRecord {
+        JournalRecord {
+            kind,
+            symbol: symbol.to_owned(),
+            ts,
+            bids: Vec::new(),
+            asks: Vec::new(),
+            update_id: None,
+            price: None,
+            qty: None,
+            side: None,
+        }
+    }
+
+    fn query(start_ts: Option<i64>, end_ts: Option<i64>, symbols: Option<&[&str]>) -> Query {
+        Query {
+            columns: Vec::new(),
+            start_ts,
+            end_ts,
+            symbols: symbols.map(|s| s.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn time_range_is_half_open_for_records_and_chunks() {
+        let q = query(Some(10), Some(20), None);
+        let hits: Vec<i64> = [9, 10, 19, 20]
+            .into_iter()
+            .filter(|&ts| q.matches(&record("delta", "BTC", ts)))
+            .collect();
+        assert_eq!(hits, [10, 19]);
+
+        let chunk = |min_ts, max_ts| Checkpoint {
+            min_ts,
+            max_ts,
+            ..Checkpoint::new(0)
+        };
+        assert!(q.overlaps(&chunk(0, 10)));
+        assert!(q.overlaps(&chunk(19, 30)));
+        assert!(q.overlaps(&chunk(0, 100)));
+        assert!(!q.overlaps(&chunk(0, 9)));
+        assert!(!q.overlaps(&chunk(20, 30)));
+        // An empty chunk's span never overlaps a bounded query
+        assert!(!q.overlaps(&Checkpoint::new(0)));
+        assert!(query(None, None, None).overlaps(&chunk(5, 5)));
+    }
+
+    #[test]
+    fn symbol_filter() {
+        let q = query(None, None, Some(&["ETH", "SOL"]));
+        assert!(q.matches(&record("trade", "ETH", 0)));
+        assert!(!q.matches(&record("trade", "BTC", 0)));
+        let none = query(None, None, Some(&[]));
+        assert!(!none.matches(&record("trade", "ETH", 0)));
+    }
+
+    #[test]
+    fn rows_have_one_entry_per_level_or_trade() {
+        let mut rows = Rows::default();
+        let mut snap = record("snapshot", "BTC", 1);
+        snap.bids = vec![(100.0, 1.0), (99.0, 2.0)];
+        snap.asks = vec![(101.0, 3.0)];
+        snap.update_id = Some(7);
+        rows.push(0, &snap);
+        let mut trade = record("trade", "ETH", 2);
+        (trade.price, trade.qty, trade.side) = (Some(10.0), Some(0.5), Some("buy"));
+        rows.push(1, &trade);
+        // A delta without levels contributes no rows
+        rows.push(2, &record("delta", "BTC", 3));
+        rows.push(3, &trade);
+
+        assert_eq!(rows.seq, [0, 0, 0, 1, 3]);
+        assert_eq!(rows.side, ["bid", "bid", "ask", "buy", "buy"]);
+        assert_eq!(rows.price, [100.0, 99.0, 101.0, 10.0, 10.0]);
+        assert_eq!(rows.size, [1.0, 2.0, 3.0, 0.5, 0.5]);
+        assert_eq!(rows.update_id, [Some(7), Some(7), Some(7), None, None]);
+        // Symbols are interned in order of first appearance
+        assert_eq!(
+            (rows.symbol.as_slice(), rows.names.as_slice()),
+            (
+                &[0, 0, 0, 1, 1][..],
+                &["BTC".to_owned(), "ETH".to_owned()][..]
+            )
+        );
+
+        let batch = ArrowBatch::new(rows.into_batch(&[6, 2, 0]));
+        assert_eq!(batch.num_rows(), 5);
+        assert_eq!(batch.column_names(), ["size", "symbol", "ts"]);
+    }
+}
@@ -0,0 +1,505 @@
+// Minimal borrowed JSON reader for exchange market-data messages.
+// Only what the feed parsers need: strings without escapes stay borrowed and
+// numbers are kept as raw text so u64 ids do not lose precision through f64.
+use std::borrow::Cow;
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyString};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Null,
+    Bool(bool),
+    Number(&'a str),
+    Str(Cow<'a, str>),
+    Array(Vec<Value<'a>>),
+    Object(Vec<(Cow<'a, str>, Value<'a>)>),
+}
+
+impl<'a> Value<'a> {
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    // Exchanges send prices both as JSON numbers and as quoted decimals
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            Value::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            Value::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            Value::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+// Deepest array/object nesting accepted. Exchange messages need a handful of
+// levels; the cap keeps the recursive descent off the end of a reader
+// thread's stack on hostile input
+const MAX_DEPTH: usize = 128;
+
+pub fn parse(text: &str) -> Result<Value<'_>, String> {
+    let mut p = Parser {
+        src: text,
+        bytes: text.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = p.value()?;
+    p.skip_ws();
+    if p.pos != p.bytes.len() {
+        return Err(p.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+// Parse a message and map syntax errors to ValueError
+pub fn parse_py(text: &str) -> PyResult<Value<'_>> {
+    parse(text).map_err(|e| PyValueError::new_err(format!("invalid JSON: {}", e)))
+}
+
+// Raw feed messages arrive as str, bytes or bytearray
+pub fn message_text(msg: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(s) = msg.downcast::<PyString>() {
+        return Ok(s.to_str()?.to_owned());
+    }
+    let raw = if let Ok(b) = msg.downcast::<PyBytes>() {
+        b.as_bytes().to_vec()
+    } else if let Ok(b) = msg.downcast::<PyByteArray>() {
+        b.to_vec()
+    } else {
+        return Err(PyTypeError::new_err(
+            "message must be str, bytes or bytearray",
+        ));
+    };
+    String::from_utf8(raw)
+        .map_err(|e| PyValueError::new_err(format!("message is not UTF-8: {}", e)))
+}
+
+// (price, size) pairs from [["price", "size", ...], ...]
+pub fn levels(v: Option<&Value<'_>>) -> Result<Vec<(f64, f64)>, String> {
+    let Some(v) = v else {
+        return Ok(Vec::new());
+    };
+    let items = v.as_array().ok_or("levels must be an array")?;
+    items
+        .iter()
+        .map(|lvl| {
+            let pair = lvl.as_array().ok_or("level must be an array")?;
+            match (
+                pair.first().and_then(Value::as_f64),
+                pair.get(1).and_then(Value::as_f64),
+            ) {
+                (Some(p), Some(s)) => Ok((p, s)),
+                _ => Err("level must hold numeric price and size".to_string()),
+            }
+        })
+        .collect()
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, what: &str) -> String {
+        format!("{} at offset {}", what, self.pos)
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), String> {
+        if self.bytes.get(self.pos) == Some(&b) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", b as char)))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value<'a>) -> Result<Value<'a>, String> {
+        if self.src[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    // Parse a nested container one level down
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Value<'a>, String>,
+    ) -> Result<Value<'a>, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn value(&mut self) -> Result<Value<'a>, String> {
+        self.skip_ws();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => Ok(Value::Str(self.string()?)),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value<'a>, String> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.skip_ws();
+            self.expect(b':')?;
+            let value = self.value()?;
+            fields.push((key, value));
+            self.skip_ws();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value<'a>, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value<'a>, String> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+        let raw = &self.src[start..self.pos];
+        if !is_number(raw.as_bytes()) {
+            return Err(self.error("invalid number"));
+        }
+        Ok(Value::Number(raw))
+    }
+
+    fn string(&mut self) -> Result<Cow<'a, str>, String> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        // Fast path: no escapes, borrow straight from the input
+        while let Some(&b) = self.bytes.get(self.pos) {
+            match b {
+                b'"' => {
+                    let s = &self.src[start..self.pos];
+                    self.pos += 1;
+                    return Ok(Cow::Borrowed(s));
+                }
+                b'\\' => break,
+                _ => self.pos += 1,
+            }
+        }
+        let mut out = String::from(&self.src[start..self.pos]);
+        loop {
+            let Some(&b) = self.bytes.get(self.pos) else {
+                return Err(self.error("unterminated string"));
+            };
+            match b {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(Cow::Owned(out));
+                }
+                b'\\' => {
+                    let esc = *self
+                        .bytes
+                        .get(self.pos + 1)
+                        .ok_or_else(|| self.error("bad escape"))?;
+                    self.pos += 2;
+                    match esc {
+                        b'"' => out.push('"'),
+                        b'\\' => out.push('\\'),
+                        b'/' => out.push('/'),
+                        b'b' => out.push('\u{8}'),
+                        b'f' => out.push('\u{c}'),
+                        b'n' => out.push('\n'),
+                        b'r' => out.push('\r'),
+                        b't' => out.push('\t'),
+                        b'u' => out.push(self.unicode_escape()?),
+                        _ => return Err(self.error("bad escape")),
+                    }
+                }
+                _ => {
+                    // Copy one UTF-8 character
+                    let ch = self.src[self.pos..].chars().next().unwrap();
+                    out.push(ch);
+                    self.pos += ch.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let hex = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("bad \\u escape"))?;
+        let code = u32::from_str_radix(hex, 16).map_err(|_| self.error("bad \\u escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let hi = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&hi) && self.src[self.pos..].starts_with("\\u") {
+            self.pos += 2;
+            let lo = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&lo) {
+                return Err(self.error("bad \\u escape"));
+            }
+            0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+        } else {
+            hi
+        };
+        char::from_u32(code).ok_or_else(|| self.error("bad \\u escape"))
+    }
+}
+
+// JSON's number grammar: -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+fn is_number(raw: &[u8]) -> bool {
+    let digits = |i: usize| raw[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut i = usize::from(raw.first() == Some(&b'-'));
+    match digits(i) {
+        0 => return false,
+        n if n > 1 && raw[i] == b'0' => return false,
+        n => i += n,
+    }
+    if raw.get(i) == Some(&b'.') {
+        match digits(i + 1) {
+            0 => return false,
+            n => i += 1 + n,
+        }
+    }
+    if let Some(b'e' | b'E') = raw.get(i) {
+        i += 1;
+        if let Some(b'+' | b'-') = raw.get(i) {
+            i += 1;
+        }
+        match digits(i) {
+            0 => return false,
+            n => i += n,
+        }
+    }
+    i == raw.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(text: &str) -> Result<Cow<'_, str>, String> {
+        match parse(text)? {
+            Value::Str(s) => Ok(s),
+            other => panic!("not a string: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn strings_borrow_unless_escaped() {
+        assert!(matches!(
+            string(r#""BTCUSDT""#),
+            Ok(Cow::Borrowed("BTCUSDT"))
+        ));
+        assert!(matches!(string(r#""ünï""#), Ok(Cow::Borrowed("ünï"))));
+        let s = string(r#""a\"b\\c\/d\b\f\n\r\te""#).unwrap();
+        assert!(matches!(s, Cow::Owned(_)));
+        assert_eq!(s, "a\"b\\c/d\u{8}\u{c}\n\r\te");
+        // Text after the first escape, multi-byte characters included, is copied
+        assert_eq!(string(r#""x\nü€y""#).unwrap(), "x\nü€y");
+    }
+
+    #[test]
+    fn unicode_escapes_and_surrogate_pairs() {
+        assert_eq!(string(r#""\u0041\u00e9\u20AC""#).unwrap(), "Aé€");
+        assert_eq!(string(r#""\ud83d\ude00!""#).unwrap(), "😀!");
+        for bad in [
+            r#""\ud83d""#, // lone high surrogate
+            r#""\ud83dx""#,
+            r#""\ud83d\u0041""#, // high surrogate without its low half
+            r#""\ude00""#,       // lone low surrogate
+            r#""\u12""#,
+            r#""\u+041""#,
+            r#""\uzzzz""#,
+        ] {
+            assert!(
+                parse(bad).unwrap_err().starts_with("bad \\u escape"),
+                "{}",
+                bad
+            );
+        }
+        for bad in [r#""\x""#, r#""\"#, r#""abc"#, r#""a\nb"#] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn numbers_keep_their_text() {
+        for ok in [
+            "0",
+            "-0",
+            "7",
+            "-12",
+            "0.5",
+            "-0.25",
+            "1e5",
+            "1E+5",
+            "2.5e-3",
+            "123456789012345678901",
+        ] {
+            assert_eq!(parse(ok), Ok(Value::Number(ok)), "{}", ok);
+        }
+        // u64 ids beyond f64's 53-bit mantissa survive
+        assert_eq!(
+            parse("18446744073709551615").unwrap().as_u64(),
+            Some(u64::MAX)
+        );
+        assert_eq!(
+            parse("9007199254740993").unwrap().as_i64(),
+            Some(9_007_199_254_740_993)
+        );
+        assert_eq!(parse("-3").unwrap().as_u64(), None);
+        assert_eq!(parse("1.5e2").unwrap().as_f64(), Some(150.0));
+        assert_eq!(parse(r#""0.1""#).unwrap().as_f64(), Some(0.1));
+        for bad in [
+            "01", "-", "1.", ".5", "+1", "1e", "1e+", "--1", "1.2.3", "1-2", "0x10", "-01",
+        ] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn containers_literals_and_whitespace() {
+        let v = parse(" {\"a\" : [1, true, false, null, {}], \"b\":{\"c\":[]}}\r\n\t").unwrap();
+        assert_eq!(
+            v.get("a").and_then(Value::as_array),
+            Some(
+                &[
+                    Value::Number("1"),
+                    Value::Bool(true),
+                    Value::Bool(false),
+                    Value::Null,
+                    Value::Object(Vec::new())
+                ][..]
+            )
+        );
+        assert_eq!(
+            v.get("b").and_then(|b| b.get("c")),
+            Some(&Value::Array(Vec::new()))
+        );
+        assert_eq!(v.get("missing"), None);
+        for bad in [
+            "",
+            "[1,]",
+            "{\"a\"}",
+            "{\"a\":1,}",
+            "[1 2]",
+            "tru",
+            "nul",
+            "{} {}",
+            "{1:2}",
+        ] {
+            assert!(parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn nesting_is_capped() {
+        let ok = "[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH);
+        assert!(parse(&ok).is_ok());
+        let deep = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        assert!(parse(&deep).unwrap_err().starts_with("nesting too deep"));
+        let deep = "{\"a\":".repeat(100_000);
+        assert!(parse(&deep).unwrap_err().starts_with("nesting too deep"));
+    }
+
+    #[test]
+    fn levels_accept_numbers_and_quoted_decimals() {
+        let v = parse(r#"[["100.5", "2"], [99, 0.25, "extra"]]"#).unwrap();
+        assert_eq!(levels(Some(&v)), Ok(vec![(100.5, 2.0), (99.0, 0.25)]));
+        assert_eq!(levels(None), Ok(Vec::new()));
+        assert!(levels(Some(&parse(r#"[["x", "1"]]"#).unwrap())).is_err());
+        assert!(levels(Some(&parse(r#"[["1"]]"#).unwrap())).is_err());
+        assert!(levels(Some(&parse("{}").unwrap())).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+// Kalman filter over fair-value observations (typically microprice).
+//   level  1-D random walk: x_t = x_t-1 + w,  w ~ N(0, process_noise * dt)
+//   trend  2-D local linear trend: the level drifts by a slope that itself
+//          random-walks with trend_noise * dt
+// Observations are z = level + v, v ~ N(0, observation_noise); a per-update
+// noise can be passed for observations of varying quality (e.g. wider books).
+// dt is the elapsed seconds when timestamps (ms) are given, else 1 per
+// update, so noises are per second or per update accordingly. The state
+// starts from the first observation with the observation variance.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::L2Book;
+
+#[pyclass]
+pub struct FairValueKalman {
+    process_noise: f64,
+    observation_noise: f64,
+    // Some(noise) switches on the trend state
+    trend_noise: Option<f64>,
+    // State (level, slope) and its covariance [[p00, p01], [p01, p11]]
+    level: f64,
+    slope: f64,
+    p00: f64,
+    p01: f64,
+    p11: f64,
+    last_ts: Option<i64>,
+    innovation: Option<f64>,
+    count: u64,
+}
+
+fn check_noise(name: &str, v: f64) -> PyResult<()> {
+    if v.is_nan() || v < 0.0 || v.is_infinite() {
+        return Err(PyValueError::new_err(format!(
+            "{} must be finite and non-negative, got {}",
+            name, v
+        )));
+    }
+    Ok(())
+}
+
+impl FairValueKalman {
+    fn elapsed(&mut self, ts: Option<i64>) -> f64 {
+        match (ts, self.last_ts) {
+            (Some(ts), Some(last)) => {
+                self.last_ts = Some(ts.max(last));
+                (ts - last).max(0) as f64 / 1000.0
+            }
+            (Some(ts), None) => {
+                self.last_ts = Some(ts);
+                0.0
+            }
+            (None, _) => 1.0,
+        }
+    }
+
+    fn predict(&mut self, dt: f64) {
+        match self.trend_noise {
+            None => self.p00 += self.process_noise * dt,
+            Some(q) => {
+                // F = [[1, dt], [0, 1]]; P = F P F' + Q
+                self.level += self.slope * dt;
+                self.p00 += dt * (2.0 * self.p01 + dt * self.p11) + self.process_noise * dt;
+                self.p01 += dt * self.p11;
+                self.p11 += q * dt;
+            }
+        }
+    }
+
+    pub fn observe(&mut self, z: f64, ts: Option<i64>, r: f64) -> f64 {
+        let dt = self.elapsed(ts);
+        if self.count == 0 {
+            self.level = z;
+            self.p00 = r;
+            self.p11 = self.trend_noise.map_or(0.0, |_| r);
+            self.count = 1;
+            self.innovation = Some(0.0);
+            return self.level;
+        }
+        self.predict(dt);
+        let innovation = z - self.level;
+        let s = self.p00 + r;
+        if s > 0.0 {
+            let (k0, k1) = (self.p00 / s, self.p01 / s);
+            self.level += k0 * innovation;
+            if self.trend_noise.is_some() {
+                self.slope += k1 * innovation;
+            }
+            // P = (I - K H) P
+            let (p00, p01) = (self.p00, self.p01);
+            self.p00 -= k0 * p00;
+            self.p01 -= k0 * p01;
+            self.p11 -= k1 * p01;
+        }
+        self.innovation = Some(innovation);
+        self.count += 1;
+        self.level
+    }
+}
+
+#[pymethods]
+impl FairValueKalman {
+    // trend=True adds the slope state, driven by trend_noise
+    #[new]
+    #[pyo3(signature = (process_noise=1e-4, observation_noise=1e-2, trend=false, trend_noise=1e-6))]
+    pub fn new(
+        process_noise: f64,
+        observation_noise: f64,
+        trend: bool,
+        trend_noise: f64,
+    ) -> PyResult<Self> {
+        check_noise("process_noise", process_noise)?;
+        check_noise("observation_noise", observation_noise)?;
+        check_noise("trend_noise", trend_noise)?;
+        Ok(Self {
+            process_noise,
+            observation_noise,
+            trend_noise: trend.then_some(trend_noise),
+            level: 0.0,
+            slope: 0.0,
+            p00: 0.0,
+            p01: 0.0,
+            p11: 0.0,
+            last_ts: None,
+            innovation: None,
+            count: 0,
+        })
+    }
+
+    // Add an observation; returns the filtered fair value
+    #[pyo3(signature = (value, ts=None, observation_noise=None))]
+    pub fn update(
+        &mut self,
+        value: f64,
+        ts: Option<i64>,
+        observation_noise: Option<f64>,
+    ) -> PyResult<f64> {
+        if !value.is_finite() {
+            return Err(PyValueError::new_err(format!(
+                "value must be finite, got {}",
+                value
+            )));
+        }
+        let r = observation_noise.unwrap_or(self.observation_noise);
+        check_noise("observation_noise", r)?;
+        Ok(self.observe(value, ts, r))
+    }
+
+    // Observe the book's reference price (microprice by default); None and
+    // no update while the book is one-sided
+    #[pyo3(signature = (book, ts=None, reference="microprice"))]
+    pub fn update_book(
+        &mut self,
+        book: &L2Book,
+        ts: Option<i64>,
+        reference: &str,
+    ) -> PyResult<Option<f64>> {
+        match book.reference_price(reference)? {
+            Some(price) => Ok(Some(self.observe(price, ts, self.observation_noise))),
+            None => Ok(None),
+        }
+    }
+
+    // Level projected `dt` ahead (seconds with timestamps, else updates)
+    #[pyo3(signature = (dt=1.0))]
+    pub fn forecast(&self, dt: f64) -> Option<f64> {
+        (self.count > 0).then_some(self.level + self.slope * dt)
+    }
+
+    #[getter]
+    pub fn value(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.level)
+    }
+
+    #[getter]
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.p00)
+    }
+
+    #[getter]
+    pub fn std(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    // Slope per second (or per update); None without trend
+    #[getter]
+    pub fn trend(&self) -> Option<f64> {
+        self.trend_noise.and((self.count > 0).then_some(self.slope))
+    }
+
+    // Last observation minus the predicted level
+    #[getter]
+    pub fn innovation(&self) -> Option<f64> {
+        self.innovation
+    }
+
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn reset(&mut self) {
+        self.level = 0.0;
+        self.slope = 0.0;
+        self.p00 = 0.0;
+        self.p01 = 0.0;
+        self.p11 = 0.0;
+        self.last_ts = None;
+        self.innovation = None;
+        self.count = 0;
+    }
+}
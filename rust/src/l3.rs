@@ -0,0 +1,227 @@
+// Market-by-order book: individual resting orders with time priority per level
+use std::collections::{BTreeMap, HashMap};
+
+use ordered_float::OrderedFloat;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::{L2Book, Levels, Side};
+
+struct Order {
+    side: Side,
+    price: f64,
+    size: f64,
+    // Arrival stamp; lower means earlier in the level queue
+    priority: u64,
+}
+
+#[derive(Default)]
+struct Level {
+    // priority -> order_id, in queue order
+    queue: BTreeMap<u64, u64>,
+    total: f64,
+}
+
+#[pyclass]
+#[derive(Default)]
+pub struct L3Book {
+    orders: HashMap<u64, Order>,
+    bids: BTreeMap<OrderedFloat<f64>, Level>,
+    asks: BTreeMap<OrderedFloat<f64>, Level>,
+    next_priority: u64,
+}
+
+impl L3Book {
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<OrderedFloat<f64>, Level> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    fn enqueue(&mut self, order_id: u64, side: Side, price: f64, size: f64) {
+        let priority = self.next_priority;
+        self.next_priority += 1;
+        let level = self.side_mut(side).entry(OrderedFloat(price)).or_default();
+        level.queue.insert(priority, order_id);
+        level.total += size;
+        self.orders.insert(
+            order_id,
+            Order {
+                side,
+                price,
+                size,
+                priority,
+            },
+        );
+    }
+
+    fn dequeue(&mut self, order_id: u64) -> Option<Order> {
+        let order = self.orders.remove(&order_id)?;
+        let ladder = self.side_mut(order.side);
+        if let Some(level) = ladder.get_mut(&OrderedFloat(order.price)) {
+            level.queue.remove(&order.priority);
+            level.total -= order.size;
+            if level.queue.is_empty() {
+                ladder.remove(&OrderedFloat(order.price));
+            }
+        }
+        Some(order)
+    }
+
+    fn levels(&self, side: Side) -> Box<dyn Iterator<Item = (f64, f64)> + '_> {
+        match side {
+            Side::Bid => Box::new(self.bids.iter().rev().map(|(p, l)| (p.0, l.total))),
+            Side::Ask => Box::new(self.asks.iter().map(|(p, l)| (p.0, l.total))),
+        }
+    }
+}
+
+#[pymethods]
+impl L3Book {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.orders.clear();
+        self.bids.clear();
+        self.asks.clear();
+    }
+
+    pub fn add(&mut self, order_id: u64, side: &str, price: f64, size: f64) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        if size <= 0.0 {
+            return Err(PyValueError::new_err("order size must be positive"));
+        }
+        if self.orders.contains_key(&order_id) {
+            return Err(PyValueError::new_err(format!(
+                "duplicate order id {}",
+                order_id
+            )));
+        }
+        self.enqueue(order_id, side, price, size);
+        Ok(())
+    }
+
+    // Size reductions keep queue priority; a price change or size increase
+    // sends the order to the back of its (new) level
+    #[pyo3(signature = (order_id, size, price=None))]
+    pub fn modify(&mut self, order_id: u64, size: f64, price: Option<f64>) -> PyResult<()> {
+        let order = self
+            .orders
+            .get_mut(&order_id)
+            .ok_or_else(|| PyKeyError::new_err(order_id))?;
+        if size <= 0.0 {
+            self.dequeue(order_id);
+            return Ok(());
+        }
+        let new_price = price.unwrap_or(order.price);
+        if new_price == order.price && size <= order.size {
+            let (side, price, diff) = (order.side, order.price, order.size - size);
+            order.size = size;
+            if let Some(level) = self.side_mut(side).get_mut(&OrderedFloat(price)) {
+                level.total -= diff;
+            }
+            return Ok(());
+        }
+        let side = order.side;
+        self.dequeue(order_id);
+        self.enqueue(order_id, side, new_price, size);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, order_id: u64) -> bool {
+        self.dequeue(order_id).is_some()
+    }
+
+    // Fill against a resting order; returns its remaining size
+    pub fn execute(&mut self, order_id: u64, qty: f64) -> PyResult<f64> {
+        let order = self
+            .orders
+            .get(&order_id)
+            .ok_or_else(|| PyKeyError::new_err(order_id))?;
+        let remaining = order.size - qty;
+        if remaining <= 0.0 {
+            self.dequeue(order_id);
+            return Ok(0.0);
+        }
+        self.modify(order_id, remaining, None)?;
+        Ok(remaining)
+    }
+
+    // (side, price, size)
+    pub fn order(&self, order_id: u64) -> Option<(&'static str, f64, f64)> {
+        self.orders
+            .get(&order_id)
+            .map(|o| (o.side.name(), o.price, o.size))
+    }
+
+    // (orders ahead, quantity ahead) at the order's price level
+    pub fn queue_position(&self, order_id: u64) -> Option<(usize, f64)> {
+        let order = self.orders.get(&order_id)?;
+        let ladder = match order.side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        let level = ladder.get(&OrderedFloat(order.price))?;
+        let ahead: Vec<u64> = level
+            .queue
+            .range(..order.priority)
+            .map(|(_, id)| *id)
+            .collect();
+        let qty = ahead
+            .iter()
+            .filter_map(|id| self.orders.get(id))
+            .map(|o| o.size)
+            .sum();
+        Some((ahead.len(), qty))
+    }
+
+    // Order ids resting at a price, front of the queue first
+    pub fn orders_at(&self, side: &str, price: f64) -> PyResult<Vec<u64>> {
+        let ladder = match Side::parse(side)? {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        Ok(ladder
+            .get(&OrderedFloat(price))
+            .map(|l| l.queue.values().copied().collect())
+            .unwrap_or_default())
+    }
+
+    #[getter]
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.levels(Side::Bid).next()
+    }
+
+    #[getter]
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.levels(Side::Ask).next()
+    }
+
+    // Aggregated (price, total size) per level
+    pub fn depth(&self, n: usize) -> (Levels, Levels) {
+        (
+            self.levels(Side::Bid).take(n).collect(),
+            self.levels(Side::Ask).take(n).collect(),
+        )
+    }
+
+    // Project into an L2Book
+    pub fn to_l2(&self) -> PyResult<L2Book> {
+        let mut book = L2Book::default();
+        let (bids, asks) = self.depth(usize::MAX);
+        book.load_snapshot(bids, asks, None)?;
+        Ok(book)
+    }
+
+    fn __len__(&self) -> usize {
+        self.orders.len()
+    }
+
+    fn __contains__(&self, order_id: u64) -> bool {
+        self.orders.contains_key(&order_id)
+    }
+}
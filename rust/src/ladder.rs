@@ -0,0 +1,221 @@
+// Multi-level quote ladders: N levels per side at increasing distance from
+// the touch prices, with a size profile, tick/lot rounding and a
+// min-notional filter.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::filters::{clean, round_to};
+use crate::quoting::Quote;
+use crate::Levels;
+
+#[derive(Clone, Debug)]
+enum Spacing {
+    // offset_i = first + i * step
+    Arithmetic { first_bps: f64, step_bps: f64 },
+    // offset_i = first * ratio^i
+    Geometric { first_bps: f64, ratio: f64 },
+    Custom(Vec<f64>),
+}
+
+#[derive(Clone, Debug)]
+enum SizeProfile {
+    Flat,
+    // size_i = base * (1 + i * growth)
+    Pyramid { growth: f64 },
+    Custom(Vec<f64>),
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct LadderGenerator {
+    levels: usize,
+    spacing: Spacing,
+    profile: SizeProfile,
+    base_size: f64,
+    tick_size: Option<f64>,
+    lot_size: Option<f64>,
+    min_notional: f64,
+}
+
+impl LadderGenerator {
+    fn offset_bps(&self, i: usize) -> f64 {
+        match &self.spacing {
+            Spacing::Arithmetic {
+                first_bps,
+                step_bps,
+            } => first_bps + i as f64 * step_bps,
+            Spacing::Geometric { first_bps, ratio } => first_bps * ratio.powi(i as i32),
+            Spacing::Custom(offsets) => offsets[i],
+        }
+    }
+
+    fn size(&self, i: usize) -> f64 {
+        match &self.profile {
+            SizeProfile::Flat => self.base_size,
+            SizeProfile::Pyramid { growth } => self.base_size * (1.0 + i as f64 * growth),
+            SizeProfile::Custom(mults) => self.base_size * mults[i],
+        }
+    }
+
+    fn side(&self, reference: f64, is_bid: bool, size_mult: f64) -> Levels {
+        let mut out: Levels = Vec::with_capacity(self.levels);
+        for i in 0..self.levels {
+            let off = self.offset_bps(i) / 10_000.0;
+            let raw = if is_bid {
+                reference * (1.0 - off)
+            } else {
+                reference * (1.0 + off)
+            };
+            // Round away from the touch so rounding never tightens a quote
+            let mut price = round_to(raw, self.tick_size, !is_bid);
+            // Levels collapsing onto an earlier tick are pushed one tick out
+            if let Some(&(last, _)) = out.last() {
+                let behind = if is_bid { price < last } else { price > last };
+                if !behind {
+                    match self.tick_size {
+                        Some(t) if is_bid => price = clean(last - t, t),
+                        Some(t) => price = clean(last + t, t),
+                        None => continue,
+                    }
+                }
+            }
+            let size = round_to(self.size(i) * size_mult, self.lot_size, false);
+            if size <= 0.0 || price <= 0.0 || price * size < self.min_notional {
+                continue;
+            }
+            out.push((price, size));
+        }
+        out
+    }
+}
+
+#[pymethods]
+impl LadderGenerator {
+    // spacing: "arithmetic" (first_bps + i*step_bps), "geometric"
+    // (first_bps * ratio^i) or "custom" (offsets_bps). size_profile: "flat",
+    // "pyramid" (base * (1 + i*growth)) or "custom" (size_multipliers).
+    #[new]
+    #[pyo3(signature = (
+        levels,
+        base_size,
+        spacing="arithmetic",
+        first_bps=5.0,
+        step_bps=5.0,
+        ratio=1.5,
+        offsets_bps=None,
+        size_profile="flat",
+        growth=0.5,
+        size_multipliers=None,
+        tick_size=None,
+        lot_size=None,
+        min_notional=0.0
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        levels: usize,
+        base_size: f64,
+        spacing: &str,
+        first_bps: f64,
+        step_bps: f64,
+        ratio: f64,
+        offsets_bps: Option<Vec<f64>>,
+        size_profile: &str,
+        growth: f64,
+        size_multipliers: Option<Vec<f64>>,
+        tick_size: Option<f64>,
+        lot_size: Option<f64>,
+        min_notional: f64,
+    ) -> PyResult<Self> {
+        let spacing = match spacing {
+            "arithmetic" => Spacing::Arithmetic {
+                first_bps,
+                step_bps,
+            },
+            "geometric" => Spacing::Geometric { first_bps, ratio },
+            "custom" => Spacing::Custom(
+                offsets_bps
+                    .ok_or_else(|| PyValueError::new_err("custom spacing requires offsets_bps"))?,
+            ),
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "spacing must be 'arithmetic', 'geometric' or 'custom', got '{}'",
+                    other
+                )))
+            }
+        };
+        let profile = match size_profile {
+            "flat" => SizeProfile::Flat,
+            "pyramid" => SizeProfile::Pyramid { growth },
+            "custom" => SizeProfile::Custom(size_multipliers.ok_or_else(|| {
+                PyValueError::new_err("custom size_profile requires size_multipliers")
+            })?),
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "size_profile must be 'flat', 'pyramid' or 'custom', got '{}'",
+                    other
+                )))
+            }
+        };
+        let short = |n: Option<usize>| n.is_some_and(|n| n < levels);
+        if short(spacing_len(&spacing)) || short(profile_len(&profile)) {
+            return Err(PyValueError::new_err(format!(
+                "custom lists need at least {} entries",
+                levels
+            )));
+        }
+        Ok(Self {
+            levels,
+            spacing,
+            profile,
+            base_size,
+            tick_size,
+            lot_size,
+            min_notional,
+        })
+    }
+
+    // Ladders anchored at separate bid/ask reference prices, e.g. the quote
+    // engine's bid and ask. Returns (bids best-first, asks best-first).
+    #[pyo3(signature = (bid_price, ask_price, bid_size_mult=1.0, ask_size_mult=1.0))]
+    pub fn generate(
+        &self,
+        bid_price: f64,
+        ask_price: f64,
+        bid_size_mult: f64,
+        ask_size_mult: f64,
+    ) -> (Levels, Levels) {
+        (
+            self.side(bid_price, true, bid_size_mult),
+            self.side(ask_price, false, ask_size_mult),
+        )
+    }
+
+    #[pyo3(signature = (quote, bid_size_mult=1.0, ask_size_mult=1.0))]
+    pub fn generate_from_quote(
+        &self,
+        quote: &Quote,
+        bid_size_mult: f64,
+        ask_size_mult: f64,
+    ) -> (Levels, Levels) {
+        self.generate(
+            quote.bid_price,
+            quote.ask_price,
+            bid_size_mult,
+            ask_size_mult,
+        )
+    }
+}
+
+fn spacing_len(s: &Spacing) -> Option<usize> {
+    match s {
+        Spacing::Custom(v) => Some(v.len()),
+        _ => None,
+    }
+}
+
+fn profile_len(p: &SizeProfile) -> Option<usize> {
+    match p {
+        SizeProfile::Custom(v) => Some(v.len()),
+        _ => None,
+    }
+}
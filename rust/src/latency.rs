@@ -0,0 +1,238 @@
+// Latency distributions for the simulation layer, in milliseconds:
+//   fixed      always `ms`
+//   normal     mean `ms`, std `std_ms`
+//   lognormal  median `ms`, log-space std `sigma` (heavy right tail)
+//   empirical  drawn from `samples` (optionally weighted histogram bins)
+// Samples are clamped at zero. A small seeded PRNG keeps runs reproducible:
+// the same seed replays the same latency sequence after reset().
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::state::{self, persist_fields, Persist, Stateful};
+
+#[derive(Clone, Debug)]
+enum Dist {
+    Fixed(f64),
+    Normal { mean: f64, std: f64 },
+    LogNormal { mu: f64, sigma: f64 },
+    // Sample values with their cumulative weights, normalized to 1.0
+    Empirical { values: Vec<f64>, cdf: Vec<f64> },
+}
+
+impl Persist for Dist {
+    fn save(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Fixed(ms) => (0u8, *ms).save(out),
+            Self::Normal { mean, std } => (1u8, *mean, *std).save(out),
+            Self::LogNormal { mu, sigma } => (2u8, *mu, *sigma).save(out),
+            Self::Empirical { values, cdf } => {
+                3u8.save(out);
+                values.save(out);
+                cdf.save(out);
+            }
+        }
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        Ok(match u8::load(input)? {
+            0 => Self::Fixed(f64::load(input)?),
+            1 => {
+                let (mean, std) = Persist::load(input)?;
+                Self::Normal { mean, std }
+            }
+            2 => {
+                let (mu, sigma) = Persist::load(input)?;
+                Self::LogNormal { mu, sigma }
+            }
+            3 => Self::Empirical {
+                values: Persist::load(input)?,
+                cdf: Persist::load(input)?,
+            },
+            tag => return Err(state::bad_tag(tag)),
+        })
+    }
+}
+
+impl Dist {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Fixed(_) => "fixed",
+            Self::Normal { .. } => "normal",
+            Self::LogNormal { .. } => "lognormal",
+            Self::Empirical { .. } => "empirical",
+        }
+    }
+}
+
+// SplitMix64; also drives the synthetic feed
+#[derive(Clone, Debug)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+persist_fields!(Rng { state });
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in (0, 1]
+    pub(crate) fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    // Box-Muller
+    pub(crate) fn std_normal(&mut self) -> f64 {
+        let (u1, u2) = (self.uniform(), self.uniform());
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    pub(crate) fn exponential(&mut self, mean: f64) -> f64 {
+        -mean * self.uniform().ln()
+    }
+}
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct LatencyModel {
+    dist: Dist,
+    seed: u64,
+    rng: Rng,
+}
+
+persist_fields!(LatencyModel { dist, seed, rng });
+
+impl Stateful for LatencyModel {
+    const NAME: &'static str = "LatencyModel";
+}
+
+impl LatencyModel {
+    // Whole milliseconds, for scheduling against ms timestamps
+    pub fn sample_ms(&mut self) -> i64 {
+        self.sample().round() as i64
+    }
+}
+
+#[pymethods]
+impl LatencyModel {
+    #[new]
+    #[pyo3(signature = (kind="fixed", ms=0.0, std_ms=0.0, sigma=0.5, samples=None, weights=None, seed=0))]
+    pub fn new(
+        kind: &str,
+        ms: f64,
+        std_ms: f64,
+        sigma: f64,
+        samples: Option<Vec<f64>>,
+        weights: Option<Vec<f64>>,
+        seed: u64,
+    ) -> PyResult<Self> {
+        let dist = match kind.to_ascii_lowercase().as_str() {
+            "fixed" => Dist::Fixed(ms),
+            "normal" if std_ms >= 0.0 => Dist::Normal {
+                mean: ms,
+                std: std_ms,
+            },
+            "normal" => return Err(PyValueError::new_err("std_ms must be non-negative")),
+            "lognormal" if ms > 0.0 && sigma >= 0.0 => Dist::LogNormal { mu: ms.ln(), sigma },
+            "lognormal" => {
+                return Err(PyValueError::new_err(
+                    "lognormal needs a positive median ms and non-negative sigma",
+                ))
+            }
+            "empirical" => {
+                let values = samples.unwrap_or_default();
+                let weights = weights.unwrap_or_else(|| vec![1.0; values.len()]);
+                if values.is_empty() || weights.len() != values.len() {
+                    return Err(PyValueError::new_err(
+                        "empirical needs samples and one weight per sample",
+                    ));
+                }
+                if weights.iter().any(|w| *w < 0.0) {
+                    return Err(PyValueError::new_err("weights must be non-negative"));
+                }
+                let total: f64 = weights.iter().sum();
+                if total <= 0.0 {
+                    return Err(PyValueError::new_err("weights must not all be zero"));
+                }
+                let mut acc = 0.0;
+                let cdf = weights
+                    .iter()
+                    .map(|w| {
+                        acc += w / total;
+                        acc
+                    })
+                    .collect();
+                Dist::Empirical { values, cdf }
+            }
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "kind must be 'fixed', 'normal', 'lognormal' or 'empirical', got '{}'",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            dist,
+            seed,
+            rng: Rng::new(seed),
+        })
+    }
+
+    #[getter]
+    fn kind(&self) -> &'static str {
+        self.dist.name()
+    }
+
+    pub fn sample(&mut self) -> f64 {
+        let v = match self.dist.clone() {
+            Dist::Fixed(ms) => ms,
+            Dist::Normal { mean, std } => mean + std * self.rng.std_normal(),
+            Dist::LogNormal { mu, sigma } => (mu + sigma * self.rng.std_normal()).exp(),
+            Dist::Empirical { values, cdf } => {
+                let u = self.rng.uniform();
+                let i = cdf.partition_point(|c| *c < u).min(values.len() - 1);
+                values[i]
+            }
+        };
+        v.max(0.0)
+    }
+
+    pub fn samples(&mut self, n: usize) -> Vec<f64> {
+        (0..n).map(|_| self.sample()).collect()
+    }
+
+    // Restart the random sequence from the seed
+    pub fn reset(&mut self) {
+        self.rng = Rng::new(self.seed);
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LatencyModel(kind={:?}, seed={})",
+            self.dist.name(),
+            self.seed
+        )
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
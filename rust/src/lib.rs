@@ -1,114 +1,630 @@
-use indexmap::IndexMap;
-use ordered_float::OrderedFloat;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyModuleMethods;
+use pyo3::types::{PyModuleMethods, PyTuple};
+use pyo3::IntoPyObjectExt;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use arrays::{Fixed, LevelsInput};
+use clock::Clock;
+use errors::BookError;
+use events::{BookUpdate, Changes, Hooks};
+use filters::SymbolFilters;
+use state::{persist_fields, Persist, Stateful};
+
+mod aio;
+mod arrays;
+mod arrow;
+mod backtest;
+mod band;
+mod bars;
+mod basis;
+mod binance;
+mod blender;
+mod bybit;
+mod checksum;
+mod clock;
+mod consolidated;
+mod cvd;
+mod depth;
+mod equity;
+mod errors;
+mod events;
+mod ewma;
+mod feed;
+mod fees;
+mod fillprob;
+mod filters;
+mod fix;
+mod gateway;
+mod glft;
+mod hawkes;
+mod hedge;
+mod http;
+mod iceberg;
+mod intensity;
+mod invariants;
+mod journal;
+mod json;
+mod kalman;
+mod l3;
+mod ladder;
+mod latency;
+mod limits;
+mod manager;
+mod markout;
+mod metrics;
+mod native;
+mod normalized;
+mod ofi;
+mod orderdiff;
+mod orderid;
+mod orders;
+mod paramsweep;
+mod parquet;
+mod pnl;
+mod portfolio;
+mod position;
+mod queue;
+mod quoting;
+mod ratelimit;
+mod recorder;
+mod rejects;
+mod requote;
+mod ring;
+mod risk;
+mod rolling;
+mod sbe;
+mod shape;
+mod shared;
+mod shm;
+mod sign;
+mod sim;
+mod skew;
+mod snapshot;
+mod spread;
+mod state;
+mod stp;
+mod sweep;
+mod synthetic;
+mod tls;
+mod tracker;
+mod trades;
+mod userstream;
+mod vol;
+mod vpin;
+mod walkforward;
+mod ws;
+mod xml;
+mod zstd;
+
+create_exception!(mm_orderbook, SequenceGapError, PyException);
+create_exception!(mm_orderbook, CrossedBookError, PyException);
+create_exception!(mm_orderbook, StaleBookError, PyException);
+create_exception!(mm_orderbook, InvalidTransitionError, PyException);
+create_exception!(mm_orderbook, BookInvariantError, PyException);
+create_exception!(mm_orderbook, InvalidPriceError, PyValueError);
+
+type Levels = Vec<(f64, f64)>;
+// Levels as decimal.Decimal (price, size) pairs
+type DecimalLevels = Vec<(PyObject, PyObject)>;
+// (bids, asks, update_id, prev_update_id)
+type DeltaUpdate = (Levels, Levels, Option<u64>, Option<u64>);
+// (best bid, best ask) as (price, size)
+type Top = (Option<(f64, f64)>, Option<(f64, f64)>);
+// (impact bps, best price left, average fill price, filled qty)
+type Impact = (f64, Option<f64>, f64, f64);
+// Price ladder keyed by PriceCodec keys, so both sides stay sorted
+// incrementally (bids are read back-to-front, asks front-to-back)
+type Ladder = BTreeMap<i64, f64>;
+
+// Work walking fewer levels than this keeps the GIL: getting it back after
+// allow_threads can mean waiting out another thread's switch interval
+// (5ms by default), far longer than a small update takes
+const RELEASE_GIL_LEVELS: usize = 64;
+
+// Run `f` with the GIL released if it touches at least RELEASE_GIL_LEVELS
+// levels. `f` must not touch Python objects.
+fn without_gil<T: Send>(py: Python<'_>, levels: usize, f: impl FnOnce() -> T + Send) -> T {
+    if levels >= RELEASE_GIL_LEVELS {
+        py.allow_threads(f)
+    } else {
+        f()
+    }
+}
+
+// How prices map onto ladder keys. Keys sort exactly like prices.
+//   Float: the f64 bit pattern made order-preserving (any price is exact)
+//   Ticks: integer tick count, so 0.1 + 0.2 and 0.3 land on the same level
+#[derive(Default, Clone, Copy, Debug)]
+enum PriceCodec {
+    #[default]
+    Float,
+    // price = key * tick_units / scale, with tick_size = tick_units / scale
+    Ticks {
+        tick_size: f64,
+        tick_units: f64,
+        scale: f64,
+    },
+}
+
+impl PriceCodec {
+    fn ticks(tick_size: f64) -> PyResult<Self> {
+        if !(tick_size > 0.0 && tick_size.is_finite()) {
+            return Err(PyValueError::new_err("tick_size must be positive"));
+        }
+        let mut scale = 1.0;
+        while (tick_size * scale - (tick_size * scale).round()).abs() > 1e-9 && scale < 1e12 {
+            scale *= 10.0;
+        }
+        Ok(Self::Ticks {
+            tick_size,
+            tick_units: (tick_size * scale).round(),
+            scale,
+        })
+    }
+
+    fn key(self, price: f64) -> i64 {
+        match self {
+            Self::Float => {
+                let bits = price.to_bits() as i64;
+                if bits < 0 {
+                    bits ^ i64::MAX
+                } else {
+                    bits
+                }
+            }
+            Self::Ticks { tick_size, .. } => (price / tick_size).round() as i64,
+        }
+    }
+
+    fn price(self, key: i64) -> f64 {
+        match self {
+            Self::Float => {
+                let bits = if key < 0 { key ^ i64::MAX } else { key };
+                f64::from_bits(bits as u64)
+            }
+            Self::Ticks {
+                tick_units, scale, ..
+            } => key as f64 * tick_units / scale,
+        }
+    }
+
+    // Tick key of an exact decimal price, in integer arithmetic and rounded
+    // half away from zero like key(); None for Float, whose keys come from
+    // the f64 itself
+    fn fixed_key(self, price: Fixed) -> Option<i64> {
+        let Self::Ticks {
+            tick_units, scale, ..
+        } = self
+        else {
+            return None;
+        };
+        // price / tick_size = units * scale / (10^exp * tick_units)
+        let num = (price.units as i128).checked_mul(scale as i128)?;
+        let den = 10i128
+            .checked_pow(price.exp)?
+            .checked_mul(tick_units as i128)?;
+        let (q, r) = (num / den, (num % den).abs());
+        let q = if r >= den - r { q + num.signum() } else { q };
+        i64::try_from(q).ok()
+    }
+
+    fn tick_size(self) -> Option<f64> {
+        match self {
+            Self::Float => None,
+            Self::Ticks { tick_size, .. } => Some(tick_size),
+        }
+    }
+
+    // Exact decimal text of a key's price: integer arithmetic with ticks,
+    // the shortest round-tripping form of the f64 otherwise
+    fn price_text(self, key: i64) -> String {
+        match self {
+            Self::Float => self.price(key).to_string(),
+            Self::Ticks {
+                tick_units, scale, ..
+            } => {
+                let units = key as i128 * tick_units as i128;
+                let decimals = scale.log10().round() as u32;
+                let (sign, units) = (if units < 0 { "-" } else { "" }, units.unsigned_abs());
+                let pow = 10u128.pow(decimals);
+                match decimals {
+                    0 => format!("{}{}", sign, units),
+                    d => format!(
+                        "{}{}.{:0width$}",
+                        sign,
+                        units / pow,
+                        units % pow,
+                        width = d as usize
+                    ),
+                }
+            }
+        }
+    }
+}
+
+impl Persist for PriceCodec {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.tick_size().save(out);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        match Option::<f64>::load(input)? {
+            Some(tick_size) => Self::ticks(tick_size),
+            None => Ok(Self::Float),
+        }
+    }
+}
+
+// Book side; "buy"/"bid" and "sell"/"ask" are accepted from Python
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+impl Side {
+    pub fn parse(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "buy" | "bid" => Ok(Self::Bid),
+            "sell" | "ask" => Ok(Self::Ask),
+            other => Err(PyValueError::new_err(format!(
+                "side must be 'buy' or 'sell', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bid => "buy",
+            Self::Ask => "sell",
+        }
+    }
+}
+
+impl Persist for Side {
+    fn save(&self, out: &mut Vec<u8>) {
+        (*self as u8).save(out);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        match u8::load(input)? {
+            0 => Ok(Self::Bid),
+            1 => Ok(Self::Ask),
+            tag => Err(state::bad_tag(tag)),
+        }
+    }
+}
+
+// What apply_delta does when a delta leaves best_bid >= best_ask
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CrossPolicy {
+    #[default]
+    Ignore,
+    Raise,
+    // The side touched by the delta is newer: drop the stale crossing levels
+    // from the opposite side
+    DropOlderSide,
+}
+
+impl CrossPolicy {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "raise" => Ok(Self::Raise),
+            "drop_older_side" => Ok(Self::DropOlderSide),
+            other => Err(PyValueError::new_err(format!(
+                "cross_policy must be 'ignore', 'raise' or 'drop_older_side', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ignore => "ignore",
+            Self::Raise => "raise",
+            Self::DropOlderSide => "drop_older_side",
+        }
+    }
+}
+
+impl Persist for CrossPolicy {
+    fn save(&self, out: &mut Vec<u8>) {
+        (*self as u8).save(out);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        match u8::load(input)? {
+            0 => Ok(Self::Ignore),
+            1 => Ok(Self::Raise),
+            2 => Ok(Self::DropOlderSide),
+            tag => Err(state::bad_tag(tag)),
+        }
+    }
+}
 
 #[pyclass]
 #[derive(Default, Clone)]
 pub struct L2Book {
-    // Используем OrderedFloat<f64> в качестве ключа, чтобы разрешить хеширование
-    bids: IndexMap<OrderedFloat<f64>, f64>,
-    asks: IndexMap<OrderedFloat<f64>, f64>,
+    bids: Ladder,
+    asks: Ladder,
+    // Sequence tracking: last applied exchange update id and gap state
+    last_update_id: Option<u64>,
+    needs_resync: bool,
+    gap_count: u64,
+    raise_on_gap: bool,
+    cross_policy: CrossPolicy,
+    // When attached, derived prices (mid, microprice) are snapped to the tick
+    filters: Option<SymbolFilters>,
+    codec: PriceCodec,
+    // Levels kept per side; the furthest from the touch are evicted
+    max_levels: Option<usize>,
+    // Monotonic time of the last applied update, for tick-to-quote latency
+    updated_at: Option<Instant>,
+    // Staleness: clock time (ns) of the last applied update to each side
+    // (bids, asks) and to the book; past max_age_ms, mid and microprice are
+    // withheld
+    side_updated_ns: [Option<i64>; 2],
+    updated_ns: Option<i64>,
+    max_age_ms: Option<f64>,
+    raise_on_stale: bool,
+    clock: Option<Clock>,
+    hooks: Hooks,
+    // Validate after every snapshot and delta, raising BookInvariantError
+    debug_checks: bool,
+}
+
+persist_fields!(L2Book {
+    bids,
+    asks,
+    last_update_id,
+    needs_resync,
+    gap_count,
+    raise_on_gap,
+    cross_policy,
+    filters,
+    codec,
+    max_levels,
+    side_updated_ns,
+    updated_ns,
+    max_age_ms,
+    raise_on_stale,
+    clock,
+    debug_checks,
+} skip { updated_at, hooks });
+
+impl Stateful for L2Book {
+    const NAME: &'static str = "L2Book";
 }
 
-#[pymethods]
 impl L2Book {
-    #[new]
-    pub fn new() -> Self {
-        Self {
-            bids: IndexMap::new(),
-            asks: IndexMap::new(),
+    // Bids from best (highest) to worst
+    fn bid_levels(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.bids
+            .iter()
+            .rev()
+            .map(|(k, s)| (self.codec.price(*k), *s))
+    }
+
+    // Asks from best (lowest) to worst
+    fn ask_levels(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.asks.iter().map(|(k, s)| (self.codec.price(*k), *s))
+    }
+
+    // Top-n levels of one side, best first
+    fn top(&self, side: Side, n: usize) -> Levels {
+        match side {
+            Side::Bid => self.bid_levels().take(n).collect(),
+            Side::Ask => self.ask_levels().take(n).collect(),
         }
     }
 
-    pub fn clear(&mut self) {
-        self.bids.clear();
-        self.asks.clear();
+    // One side's top three levels for __repr__, "..." marking the rest
+    fn repr_side(&self, side: Side, total: usize) -> String {
+        let levels = self.top(side, 3);
+        let shown = format!("{:?}", levels);
+        if total > levels.len() {
+            format!("{}, ...]", &shown[..shown.len() - 1])
+        } else {
+            shown
+        }
     }
 
-    pub fn apply_snapshot(&mut self, bids: Vec<(f64, f64)>, asks: Vec<(f64, f64)>) -> PyResult<()> {
-        self.clear();
-        // Insert bids (descending order)
-        let mut bb = bids;
-        bb.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
-        for (p, s) in bb.into_iter() {
-            if s > 0.0 {
-                // Оборачиваем ключ p в OrderedFloat
-                self.bids.insert(OrderedFloat(p), s);
-            }
+    // Levels a walk of `depth` per side touches, for without_gil
+    fn walked(&self, depth: usize) -> usize {
+        depth.min(self.bids.len()) + depth.min(self.asks.len())
+    }
+
+    fn set_level(side: &mut Ladder, key: i64, size: f64) {
+        if size > 0.0 {
+            side.insert(key, size);
+        } else {
+            side.remove(&key);
         }
-        // Insert asks (ascending order)
-        let mut aa = asks;
-        aa.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-        for (p, s) in aa.into_iter() {
-            if s > 0.0 {
-                // Оборачиваем ключ p в OrderedFloat
-                self.asks.insert(OrderedFloat(p), s);
+    }
+
+    // Drop the worst levels beyond max_levels on each side
+    fn truncate(&mut self) {
+        let Some(max) = self.max_levels else {
+            return;
+        };
+        while self.bids.len() > max {
+            self.bids.pop_first();
+        }
+        while self.asks.len() > max {
+            self.asks.pop_last();
+        }
+    }
+
+    fn now_ns(&self) -> i64 {
+        self.clock
+            .as_ref()
+            .map_or_else(clock::monotonic_ns, Clock::now_ns)
+    }
+
+    fn stamp(&mut self, bids: bool, asks: bool) {
+        let now = self.now_ns();
+        self.updated_ns = Some(now);
+        if bids {
+            self.side_updated_ns[0] = Some(now);
+        }
+        if asks {
+            self.side_updated_ns[1] = Some(now);
+        }
+    }
+
+    fn age_ns(&self, stamp: Option<i64>) -> Option<i64> {
+        stamp.map(|at| (self.now_ns() - at).max(0))
+    }
+
+    fn stale(&self) -> bool {
+        self.max_age_ms.is_some_and(|max| {
+            self.age_ns(self.updated_ns)
+                .is_none_or(|age| age as f64 / 1e6 > max)
+        })
+    }
+
+    // `value` unless the book is stale: then None, or StaleBookError with
+    // raise_on_stale
+    pub(crate) fn guarded<T>(&self, value: Option<T>) -> PyResult<Option<T>> {
+        if !self.stale() {
+            return Ok(value);
+        }
+        if self.raise_on_stale {
+            return Err(StaleBookError::new_err(format!(
+                "book not updated for {} (max_age_ms={})",
+                self.age_ns(self.updated_ns)
+                    .map_or("ever".to_owned(), |ns| format!("{:.3}ms", ns as f64 / 1e6)),
+                self.max_age_ms.unwrap_or_default()
+            )));
+        }
+        Ok(None)
+    }
+
+    fn debug_check(&self) -> PyResult<()> {
+        if !self.debug_checks {
+            return Ok(());
+        }
+        let report = invariants::check(self, false);
+        if report.ok {
+            return Ok(());
+        }
+        Err(BookInvariantError::new_err(format!(
+            "book invariants violated: {}",
+            report.summary()
+        )))
+    }
+
+    // Mid without the staleness guard
+    pub(crate) fn raw_mid(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some((bp, _)), Some((ap, _))) if bp > 0.0 && ap > 0.0 => {
+                Some(self.snap((bp + ap) / 2.0))
             }
+            _ => None,
         }
-        Ok(())
     }
 
-    // Delta format: (price, size). size<=0 removes the level
-    pub fn apply_delta(&mut self, bids: Vec<(f64, f64)>, asks: Vec<(f64, f64)>) -> PyResult<()> {
+    pub(crate) fn load_snapshot(
+        &mut self,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+    ) -> Result<(), BookError> {
+        BookError::check_levels(&bids, &asks)?;
+        self.clear();
+        self.last_update_id = update_id;
+        self.needs_resync = false;
+        self.updated_at = Some(Instant::now());
+        self.stamp(true, true);
         for (p, s) in bids.into_iter() {
             if s > 0.0 {
-                // Оборачиваем ключ p в OrderedFloat
-                self.bids.insert(OrderedFloat(p), s);
-            } else {
-                // Оборачиваем ключ p в OrderedFloat для поиска и удаления
-                self.bids.swap_remove(&OrderedFloat(p));
+                self.bids.insert(self.codec.key(p), s);
             }
         }
         for (p, s) in asks.into_iter() {
             if s > 0.0 {
-                // Оборачиваем ключ p в OrderedFloat
-                self.asks.insert(OrderedFloat(p), s);
-            } else {
-                // Оборачиваем ключ p в OrderedFloat для поиска и удаления
-                self.asks.swap_remove(&OrderedFloat(p));
+                self.asks.insert(self.codec.key(p), s);
             }
         }
-        // Re-sort to ensure order
-        self.reorder();
+        self.truncate();
         Ok(())
     }
 
-    #[getter]
-    pub fn best_bid(&self) -> Option<(f64, f64)> {
-        // Разыменовываем ключ p, чтобы вернуть f64
-        self.bids.iter().next().map(|(p, s)| (p.0, *s))
+    // Apply (price, size) updates without any sequence checks
+    pub(crate) fn apply_levels(&mut self, bids: Levels, asks: Levels) -> Result<(), BookError> {
+        BookError::check_levels(&bids, &asks)?;
+        self.write_levels(bids, asks);
+        Ok(())
     }
 
-    #[getter]
-    pub fn best_ask(&self) -> Option<(f64, f64)> {
-        // Разыменовываем ключ p, чтобы вернуть f64
-        self.asks.iter().next().map(|(p, s)| (p.0, *s))
+    // apply_levels for levels already checked
+    fn write_levels(&mut self, bids: Levels, asks: Levels) {
+        self.updated_at = Some(Instant::now());
+        self.stamp(!bids.is_empty(), !asks.is_empty());
+        for (p, s) in bids.into_iter() {
+            Self::set_level(&mut self.bids, self.codec.key(p), s);
+        }
+        for (p, s) in asks.into_iter() {
+            Self::set_level(&mut self.asks, self.codec.key(p), s);
+        }
+        self.truncate();
     }
 
-    pub fn mid(&self) -> Option<f64> {
-        match (self.best_bid(), self.best_ask()) {
-            (Some((bp, _)), Some((ap, _))) if bp > 0.0 && ap > 0.0 => Some((bp + ap) / 2.0),
-            _ => None,
-        }
+    // Visible size resting at exactly `price` on one side
+    pub(crate) fn level_size(&self, side: Side, price: f64) -> f64 {
+        let ladder = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        ladder.get(&self.codec.key(price)).copied().unwrap_or(0.0)
     }
 
-    pub fn microprice(&self) -> Option<f64> {
-        let (bid, ask) = (self.best_bid(), self.best_ask());
-        if let (Some((bp, bs)), Some((ap, asz))) = (bid, ask) {
-            let total = bs + asz;
-            if total > 0.0 {
-                let bw = asz / total;
-                let aw = bs / total;
-                return Some(bp * bw + ap * aw);
-            }
+    // Levels a market order of the given side would consume
+    fn taker_levels(&self, side: &str) -> PyResult<Box<dyn Iterator<Item = (f64, f64)> + '_>> {
+        Ok(match Side::parse(side)? {
+            Side::Bid => Box::new(self.ask_levels()),
+            Side::Ask => Box::new(self.bid_levels()),
+        })
+    }
+
+    // (Σ w·size, Σ w·size·price) over the top `depth` levels of one side,
+    // w = exp(-decay * |price - mid| / mid * 1e4) or 1 without decay
+    fn weighted_side(&self, side: Side, depth: usize, decay: Option<f64>) -> (f64, f64) {
+        let mid = decay.and(self.raw_mid());
+        let levels: Box<dyn Iterator<Item = (f64, f64)>> = match side {
+            Side::Bid => Box::new(self.bid_levels()),
+            Side::Ask => Box::new(self.ask_levels()),
+        };
+        levels.take(depth).fold((0.0, 0.0), |(wq, wpq), (p, s)| {
+            let w = match (decay, mid) {
+                (Some(k), Some(m)) => (-k * (p - m).abs() / m * 10_000.0).exp(),
+                _ => 1.0,
+            };
+            (wq + w * s, wpq + w * s * p)
+        })
+    }
+
+    // Size-weighted microprice; see L2Book.microprice
+    pub(crate) fn weighted_microprice(&self, depth: usize, decay: Option<f64>) -> Option<f64> {
+        let (bq, bpq) = self.weighted_side(Side::Bid, depth, decay);
+        let (aq, apq) = self.weighted_side(Side::Ask, depth, decay);
+        let total = bq + aq;
+        if bq > 0.0 && aq > 0.0 && total > 0.0 {
+            let (bp, ap) = (bpq / bq, apq / aq);
+            return Some(self.snap(bp * (aq / total) + ap * (bq / total)));
         }
-        self.mid()
+        self.raw_mid()
     }
 
-    pub fn imbalance(&self, depth: usize) -> f64 {
-        let bid_vol: f64 = self.bids.iter().take(depth).map(|(_, s)| *s).sum();
-        let ask_vol: f64 = self.asks.iter().take(depth).map(|(_, s)| *s).sum();
+    // Size-weighted imbalance; see L2Book.imbalance
+    fn weighted_imbalance(&self, depth: usize, decay: Option<f64>) -> f64 {
+        let (bid_vol, _) = self.weighted_side(Side::Bid, depth, decay);
+        let (ask_vol, _) = self.weighted_side(Side::Ask, depth, decay);
         let tot = bid_vol + ask_vol;
         if tot == 0.0 {
             0.0
@@ -117,26 +633,1034 @@ impl L2Book {
         }
     }
 
-    fn reorder(&mut self) {
-        // Rebuild keeping order: bids desc, asks asc
-        let mut bb: Vec<(f64, f64)> = self.bids.iter().map(|(p, s)| (p.0, *s)).collect();
-        let mut aa: Vec<(f64, f64)> = self.asks.iter().map(|(p, s)| (p.0, *s)).collect();
-        bb.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
-        // FIX: Asks should be ascending
-        aa.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    // See L2Book.vwap_for_qty
+    fn sweep_cost(&self, side: &str, qty: f64) -> PyResult<Option<(f64, f64, f64)>> {
+        let mut remaining = qty;
+        let mut notional = 0.0;
+        let mut worst = None;
+        for (p, s) in self.taker_levels(side)? {
+            if remaining <= 0.0 {
+                break;
+            }
+            let take = s.min(remaining);
+            notional += take * p;
+            remaining -= take;
+            worst = Some(p);
+        }
+        let filled = qty - remaining.max(0.0);
+        Ok(worst
+            .filter(|_| filled > 0.0)
+            .map(|w| (notional / filled, w, filled)))
+    }
+
+    fn crc(
+        &self,
+        exchange: &str,
+        depth: Option<usize>,
+        price_decimals: Option<usize>,
+        size_decimals: Option<usize>,
+    ) -> PyResult<i64> {
+        match exchange.to_ascii_lowercase().as_str() {
+            "okx" => {
+                let n = depth.unwrap_or(25);
+                Ok(checksum::okx(
+                    &self.top(Side::Bid, n),
+                    &self.top(Side::Ask, n),
+                    price_decimals,
+                    size_decimals,
+                ))
+            }
+            "kraken" => {
+                let n = depth.unwrap_or(10);
+                Ok(checksum::kraken(
+                    &self.top(Side::Bid, n),
+                    &self.top(Side::Ask, n),
+                    price_decimals,
+                    size_decimals,
+                ))
+            }
+            other => Err(PyValueError::new_err(format!(
+                "unsupported checksum exchange '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn snap(&self, price: f64) -> f64 {
+        match &self.filters {
+            Some(f) => filters::round_nearest(price, f.tick_size),
+            None => price,
+        }
+    }
+
+    fn reference_price(&self, reference: &str) -> PyResult<Option<f64>> {
+        match reference.to_ascii_lowercase().as_str() {
+            "mid" => Ok(self.raw_mid()),
+            "microprice" => Ok(self.weighted_microprice(1, None)),
+            "bid" | "best_bid" => Ok(self.best_bid().map(|(p, _)| p)),
+            other => Err(PyValueError::new_err(format!(
+                "reference must be 'mid', 'microprice' or 'best_bid', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    // Enforce the cross policy after a delta. new_bid/new_ask are the most
+    // aggressive prices the delta inserted on each side; dropped levels are
+    // recorded in `changes`.
+    fn resolve_cross(
+        &mut self,
+        new_bid: Option<f64>,
+        new_ask: Option<f64>,
+        mut changes: Option<&mut Changes>,
+    ) -> Result<(), BookError> {
+        if !self.is_crossed() {
+            return Ok(());
+        }
+        match self.cross_policy {
+            CrossPolicy::Ignore => {}
+            CrossPolicy::Raise => {
+                return Err(BookError::CrossedBook {
+                    bid: self.best_bid().map_or(f64::NAN, |(p, _)| p),
+                    ask: self.best_ask().map_or(f64::NAN, |(p, _)| p),
+                });
+            }
+            CrossPolicy::DropOlderSide => {
+                if let Some(bid) = new_bid {
+                    let bid = self.codec.key(bid);
+                    self.asks.retain(|k, s| {
+                        if let (false, Some(t)) = (*k > bid, changes.as_mut()) {
+                            t.asks.entry(*k).or_insert(*s);
+                        }
+                        *k > bid
+                    });
+                }
+                if let Some(ask) = new_ask {
+                    let ask = self.codec.key(ask);
+                    self.bids.retain(|k, s| {
+                        if let (false, Some(t)) = (*k < ask, changes.as_mut()) {
+                            t.bids.entry(*k).or_insert(*s);
+                        }
+                        *k < ask
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Record the current size of each level about to be overwritten
+    fn touch(&self, changes: &mut Changes, bids: &Levels, asks: &Levels) {
+        for (p, _) in bids {
+            let key = self.codec.key(*p);
+            let size = self.bids.get(&key).copied().unwrap_or(0.0);
+            changes.bids.entry(key).or_insert(size);
+        }
+        for (p, _) in asks {
+            let key = self.codec.key(*p);
+            let size = self.asks.get(&key).copied().unwrap_or(0.0);
+            changes.asks.entry(key).or_insert(size);
+        }
+    }
+
+    // Compare touched levels with the book now
+    fn book_update(
+        &self,
+        kind: &'static str,
+        update_id: Option<u64>,
+        applied: bool,
+        old_best: Top,
+        changes: Changes,
+    ) -> BookUpdate {
+        let diff = |ladder: &Ladder, before: BTreeMap<i64, f64>| {
+            let (mut added, mut removed) = (Vec::new(), Vec::new());
+            for (key, old) in before {
+                let new = ladder.get(&key).copied().unwrap_or(0.0);
+                if old <= 0.0 && new > 0.0 {
+                    added.push((self.codec.price(key), new));
+                } else if old > 0.0 && new <= 0.0 {
+                    removed.push((self.codec.price(key), old));
+                }
+            }
+            (added, removed)
+        };
+        let (mut bids_added, mut bids_removed) = diff(&self.bids, changes.bids);
+        let (asks_added, asks_removed) = diff(&self.asks, changes.asks);
+        bids_added.reverse();
+        bids_removed.reverse();
+        BookUpdate {
+            kind,
+            update_id,
+            applied,
+            old_best_bid: old_best.0,
+            old_best_ask: old_best.1,
+            best_bid: self.best_bid(),
+            best_ask: self.best_ask(),
+            bids_added,
+            bids_removed,
+            asks_added,
+            asks_removed,
+            ..Default::default()
+        }
+    }
+
+    // apply_delta without the Python return or callbacks; level changes, gaps
+    // and crosses are recorded in `changes` when given
+    pub(crate) fn delta(
+        &mut self,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+        prev_update_id: Option<u64>,
+        mut changes: Option<&mut Changes>,
+    ) -> PyResult<bool> {
+        // Bad levels are rejected before they can open or close a gap
+        BookError::check_levels(&bids, &asks)?;
+        let (gaps, last) = (self.gap_count, self.last_update_id);
+        let in_sequence = self.check_sequence(update_id, prev_update_id);
+        if let (true, Some(c), Some(last), Some(uid)) =
+            (self.gap_count > gaps, changes.as_mut(), last, update_id)
+        {
+            c.gap = Some((last, prev_update_id.unwrap_or(uid - 1)));
+        }
+        if !in_sequence? {
+            metrics::NATIVE.book_skipped.inc(1.0);
+            return Ok(false);
+        }
+        metrics::NATIVE.book_deltas.inc(1.0);
+        let new_bid = bids
+            .iter()
+            .filter(|l| l.1 > 0.0)
+            .map(|l| l.0)
+            .reduce(f64::max);
+        let new_ask = asks
+            .iter()
+            .filter(|l| l.1 > 0.0)
+            .map(|l| l.0)
+            .reduce(f64::min);
+        if let Some(c) = changes.as_mut() {
+            self.touch(c, &bids, &asks);
+        }
+        self.write_levels(bids, asks);
+        if update_id.is_some() {
+            self.last_update_id = update_id;
+        }
+        if let (Some(c), Some((bid, _)), Some((ask, _))) =
+            (changes.as_mut(), self.best_bid(), self.best_ask())
+        {
+            c.cross = (bid >= ask).then_some((bid, ask));
+        }
+        self.resolve_cross(new_bid, new_ask, changes)?;
+        self.debug_check()?;
+        Ok(true)
+    }
+
+    // Decide whether a delta carrying the given ids may be applied.
+    // Ok(false) means the delta is stale or the book is waiting for a resync.
+    fn check_sequence(
+        &mut self,
+        update_id: Option<u64>,
+        prev_update_id: Option<u64>,
+    ) -> Result<bool, BookError> {
+        if self.needs_resync {
+            return Ok(false);
+        }
+        let (Some(last), Some(uid)) = (self.last_update_id, update_id) else {
+            return Ok(true);
+        };
+        if uid <= last {
+            return Ok(false);
+        }
+        let expected = prev_update_id.unwrap_or(uid - 1);
+        if expected != last {
+            self.needs_resync = true;
+            self.gap_count += 1;
+            metrics::NATIVE.book_gaps.inc(1.0);
+            if self.raise_on_gap {
+                return Err(BookError::SequenceGap { last, expected });
+            }
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+#[pymethods]
+impl L2Book {
+    #[new]
+    // tick_size switches the ladder to integer-tick keys: incoming prices are
+    // rounded to the nearest tick and converted back only on output.
+    // max_levels bounds each side, evicting the levels furthest from the touch.
+    // max_age_ms withholds mid/microprice (None, or StaleBookError with
+    // raise_on_stale) once the last update is older; ages are measured on
+    // `clock` (a ReplayClock) or monotonic_ns() without one.
+    // debug_checks runs validate() after every snapshot and delta and raises
+    // BookInvariantError on a violation, crosses included; it costs a full
+    // walk of the book per update.
+    #[pyo3(signature = (raise_on_gap=false, cross_policy="ignore", tick_size=None, max_levels=None, max_age_ms=None, raise_on_stale=false, clock=None, debug_checks=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        raise_on_gap: bool,
+        cross_policy: &str,
+        tick_size: Option<f64>,
+        max_levels: Option<usize>,
+        max_age_ms: Option<f64>,
+        raise_on_stale: bool,
+        clock: Option<Clock>,
+        debug_checks: bool,
+    ) -> PyResult<Self> {
+        if max_levels == Some(0) {
+            return Err(PyValueError::new_err("max_levels must be positive"));
+        }
+        let mut book = Self {
+            raise_on_gap,
+            cross_policy: CrossPolicy::parse(cross_policy)?,
+            codec: match tick_size {
+                Some(t) => PriceCodec::ticks(t)?,
+                None => PriceCodec::Float,
+            },
+            max_levels,
+            raise_on_stale,
+            clock,
+            debug_checks,
+            ..Default::default()
+        };
+        book.set_max_age_ms(max_age_ms)?;
+        Ok(book)
+    }
+
+    #[getter]
+    pub fn tick_size(&self) -> Option<f64> {
+        self.codec.tick_size()
+    }
+
+    #[getter]
+    pub fn max_levels(&self) -> Option<usize> {
+        self.max_levels
+    }
+
+    #[getter]
+    pub fn debug_checks(&self) -> bool {
+        self.debug_checks
+    }
+
+    pub fn clear(&mut self) {
         self.bids.clear();
         self.asks.clear();
-        for (p, s) in bb.into_iter() {
-            self.bids.insert(OrderedFloat(p), s);
+        self.last_update_id = None;
+    }
+
+    // bids/asks: list of (price, size) or a float64 N×2 array; prices and
+    // sizes may be floats, Decimals or decimal strings.
+    // return_update=True returns a BookUpdate diffing the old and new book.
+    // Large snapshots are loaded with the GIL released.
+    #[pyo3(signature = (bids, asks, update_id=None, return_update=false))]
+    pub fn apply_snapshot(
+        &mut self,
+        py: Python<'_>,
+        bids: LevelsInput,
+        asks: LevelsInput,
+        update_id: Option<u64>,
+        return_update: bool,
+    ) -> PyResult<Option<BookUpdate>> {
+        let (bids, asks) = (bids.levels(self.codec), asks.levels(self.codec));
+        let levels = self.walked(usize::MAX) + bids.len() + asks.len();
+        let update = without_gil(py, levels, || {
+            let before = return_update.then(|| {
+                let old_best = (self.best_bid(), self.best_ask());
+                let mut changes = Changes {
+                    bids: self.bids.clone(),
+                    asks: self.asks.clone(),
+                    ..Default::default()
+                };
+                self.touch(&mut changes, &bids, &asks);
+                (old_best, changes)
+            });
+            self.load_snapshot(bids, asks, update_id)?;
+            metrics::NATIVE.book_snapshots.inc(1.0);
+            Ok::<_, BookError>(before.map(|(old_best, changes)| {
+                self.book_update("snapshot", update_id, true, old_best, changes)
+            }))
+        })?;
+        self.debug_check()?;
+        Ok(update)
+    }
+
+    // Top-N levels (bids, asks) with decimal.Decimal prices and sizes. With
+    // tick_size the prices come from the integer tick keys, so they carry the
+    // tick's decimals exactly ("100.10" for tick 0.01).
+    #[pyo3(signature = (depth=usize::MAX))]
+    pub fn to_decimal<'py>(
+        &self,
+        py: Python<'py>,
+        depth: usize,
+    ) -> PyResult<(DecimalLevels, DecimalLevels)> {
+        let decimal = py.import("decimal")?.getattr("Decimal")?;
+        let convert = |ladder: Box<dyn Iterator<Item = (&i64, &f64)> + '_>| {
+            ladder
+                .take(depth)
+                .map(|(k, s)| {
+                    Ok((
+                        decimal.call1((self.codec.price_text(*k),))?.unbind(),
+                        decimal.call1((s.to_string(),))?.unbind(),
+                    ))
+                })
+                .collect::<PyResult<Vec<_>>>()
+        };
+        Ok((
+            convert(Box::new(self.bids.iter().rev()))?,
+            convert(Box::new(self.asks.iter()))?,
+        ))
+    }
+
+    // Top-N levels as two contiguous float64 N×2 numpy arrays (bids, asks)
+    #[pyo3(signature = (depth=usize::MAX))]
+    pub fn to_numpy<'py>(
+        &self,
+        py: Python<'py>,
+        depth: usize,
+    ) -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyAny>)> {
+        let (bids, asks) = self.depth(py, depth);
+        Ok((
+            arrays::levels_to_ndarray(py, &bids)?,
+            arrays::levels_to_ndarray(py, &asks)?,
+        ))
+    }
+
+    // Delta format: (price, size), in any form apply_snapshot takes.
+    // size<=0 removes the level.
+    // With update ids, stale deltas are skipped and a gap flags the book for
+    // resync (or raises SequenceGapError); returns whether the delta was
+    // applied, or with return_update=True a BookUpdate describing the change.
+    // Registered callbacks run after the book is released, so they may read it.
+    // Large deltas are applied with the GIL released.
+    #[pyo3(signature = (bids, asks, update_id=None, prev_update_id=None, return_update=false))]
+    pub fn apply_delta(
+        slf: &Bound<'_, Self>,
+        bids: LevelsInput,
+        asks: LevelsInput,
+        update_id: Option<u64>,
+        prev_update_id: Option<u64>,
+        return_update: bool,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let mut guard = slf.borrow_mut();
+        let book = &mut *guard;
+        let (bids, asks) = (bids.levels(book.codec), asks.levels(book.codec));
+        let levels = bids.len() + asks.len();
+        if !return_update && book.hooks.is_empty() {
+            return without_gil(py, levels, || {
+                book.delta(bids, asks, update_id, prev_update_id, None)
+            })?
+            .into_py_any(py);
         }
-        for (p, s) in aa.into_iter() {
-            self.asks.insert(OrderedFloat(p), s);
+        let hooks = book.hooks.clone();
+        let (update, gap, cross) = without_gil(py, levels, || {
+            let old_best = (book.best_bid(), book.best_ask());
+            let mut changes = Changes::default();
+            let applied = book.delta(bids, asks, update_id, prev_update_id, Some(&mut changes));
+            let (gap, cross) = (changes.gap, changes.cross);
+            let update = applied
+                .map(|applied| book.book_update("delta", update_id, applied, old_best, changes));
+            (update, gap, cross)
+        });
+        drop(guard);
+        hooks.fire(py, gap, cross, update.as_ref().ok())?;
+        let update = update?;
+        if return_update {
+            update.into_py_any(py)
+        } else {
+            update.applied.into_py_any(py)
         }
     }
+
+    // Callbacks fired by apply_delta once the update is applied:
+    //   on_best_change(update)              best bid or ask price/size moved
+    //   on_cross(best_bid, best_ask)        the delta crossed the book (before
+    //                                       the cross policy acts)
+    //   on_gap(last_update_id, expected)    a sequence gap was detected
+    // `listener` may be any object with methods of those names; explicit
+    // callables take precedence. Each call replaces all callbacks, so
+    // set_callbacks() with no arguments clears them.
+    #[pyo3(signature = (listener=None, on_best_change=None, on_cross=None, on_gap=None))]
+    pub fn set_callbacks(
+        &mut self,
+        listener: Option<&Bound<'_, PyAny>>,
+        on_best_change: Option<Bound<'_, PyAny>>,
+        on_cross: Option<Bound<'_, PyAny>>,
+        on_gap: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        self.hooks = Hooks::new(listener, on_best_change, on_cross, on_gap)?;
+        Ok(())
+    }
+
+    // Check the book's invariants (see invariants.rs); the report is falsy
+    // when any fail. allow_crossed accepts a crossed or locked book.
+    #[pyo3(signature = (allow_crossed=false))]
+    pub fn validate(&self, allow_crossed: bool) -> invariants::BookValidation {
+        invariants::check(self, allow_crossed)
+    }
+
+    // best_bid >= best_ask (locked books count as crossed)
+    pub fn is_crossed(&self) -> bool {
+        match (self.best_bid(), self.best_ask()) {
+            (Some((bp, _)), Some((ap, _))) => bp >= ap,
+            _ => false,
+        }
+    }
+
+    #[getter]
+    pub fn get_filters(&self) -> Option<SymbolFilters> {
+        self.filters.clone()
+    }
+
+    #[setter]
+    pub fn set_filters(&mut self, filters: Option<SymbolFilters>) {
+        self.filters = filters;
+    }
+
+    #[getter]
+    pub fn get_cross_policy(&self) -> &'static str {
+        self.cross_policy.name()
+    }
+
+    #[setter]
+    pub fn set_cross_policy(&mut self, policy: &str) -> PyResult<()> {
+        self.cross_policy = CrossPolicy::parse(policy)?;
+        Ok(())
+    }
+
+    // Apply many buffered deltas in one call with the GIL released.
+    // Each update is (bids, asks[, update_id[, prev_update_id]]).
+    // Returns how many deltas were applied (stale/gapped ones are skipped).
+    pub fn apply_deltas_batch(
+        &mut self,
+        py: Python<'_>,
+        updates: Vec<Bound<'_, PyTuple>>,
+    ) -> PyResult<usize> {
+        let batch = updates
+            .iter()
+            .map(extract_delta)
+            .collect::<PyResult<Vec<DeltaUpdate>>>()?;
+        py.allow_threads(|| {
+            let mut applied = 0;
+            for (bids, asks, update_id, prev_update_id) in batch {
+                if self.delta(bids, asks, update_id, prev_update_id, None)? {
+                    applied += 1;
+                }
+            }
+            Ok(applied)
+        })
+    }
+
+    // monotonic_ns() of the last applied snapshot or delta
+    #[getter]
+    pub fn recv_ns(&self) -> Option<i64> {
+        self.updated_at.map(clock::instant_ns)
+    }
+
+    #[getter]
+    pub fn last_update_id(&self) -> Option<u64> {
+        self.last_update_id
+    }
+
+    #[getter]
+    pub fn needs_resync(&self) -> bool {
+        self.needs_resync
+    }
+
+    #[getter]
+    pub fn gap_count(&self) -> u64 {
+        self.gap_count
+    }
+
+    #[getter]
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bid_levels().next()
+    }
+
+    #[getter]
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.ask_levels().next()
+    }
+
+    // Top-N levels per side: bids descending, asks ascending
+    pub fn bids(&self, py: Python<'_>, n: usize) -> Levels {
+        without_gil(py, n.min(self.bids.len()), || self.top(Side::Bid, n))
+    }
+
+    pub fn asks(&self, py: Python<'_>, n: usize) -> Levels {
+        without_gil(py, n.min(self.asks.len()), || self.top(Side::Ask, n))
+    }
+
+    pub fn depth(&self, py: Python<'_>, n: usize) -> (Levels, Levels) {
+        without_gil(py, self.walked(n), || {
+            (self.top(Side::Bid, n), self.top(Side::Ask, n))
+        })
+    }
+
+    // Levels as an Arrow batch (side, level, price, size), bids then asks,
+    // best first. depth=None exports the whole book.
+    #[pyo3(signature = (depth=None))]
+    pub fn to_arrow(&self, py: Python<'_>, depth: Option<usize>) -> arrow::ArrowBatch {
+        let n = depth.unwrap_or(usize::MAX);
+        arrow::ArrowBatch::new(without_gil(py, self.walked(n), || {
+            arrow::levels_batch(&self.top(Side::Bid, n), &self.top(Side::Ask, n))
+        }))
+    }
+
+    // CRC32 book checksum in the exchange's format ("okx": 25 levels, signed;
+    // "kraken": 10 levels, unsigned). depth overrides the exchange default.
+    #[pyo3(signature = (exchange="okx", depth=None, price_decimals=None, size_decimals=None))]
+    pub fn checksum(
+        &self,
+        py: Python<'_>,
+        exchange: &str,
+        depth: Option<usize>,
+        price_decimals: Option<usize>,
+        size_decimals: Option<usize>,
+    ) -> PyResult<i64> {
+        without_gil(py, self.walked(depth.unwrap_or(25)), || {
+            self.crc(exchange, depth, price_decimals, size_decimals)
+        })
+    }
+
+    #[pyo3(signature = (expected, exchange="okx", depth=None, price_decimals=None, size_decimals=None))]
+    pub fn verify_checksum(
+        &self,
+        py: Python<'_>,
+        expected: i64,
+        exchange: &str,
+        depth: Option<usize>,
+        price_decimals: Option<usize>,
+        size_decimals: Option<usize>,
+    ) -> PyResult<bool> {
+        let actual = self.checksum(py, exchange, depth, price_decimals, size_decimals)?;
+        // Accept either signed or unsigned 32-bit renderings of the same crc
+        Ok(actual as u32 == expected as u32)
+    }
+
+    // Size resting at exactly `price` on `side` ("bid"/"ask"); 0.0 if there
+    // is no such level. In tick mode the price is rounded to the tick first.
+    pub fn size_at(&self, side: &str, price: f64) -> PyResult<f64> {
+        Ok(self.level_size(Side::parse(side)?, price))
+    }
+
+    // Rank of the level at exactly `price` within its side, 0 being the best;
+    // None if there is no such level
+    pub fn level_index(&self, side: &str, price: f64) -> PyResult<Option<usize>> {
+        let key = self.codec.key(price);
+        Ok(match Side::parse(side)? {
+            Side::Bid => self
+                .bids
+                .contains_key(&key)
+                .then(|| self.bids.range(key..).count() - 1),
+            Side::Ask => self
+                .asks
+                .contains_key(&key)
+                .then(|| self.asks.range(..key).count()),
+        })
+    }
+
+    // Walk the opposite side for a market order of `qty`:
+    // (average fill price, worst price touched, filled qty), None if nothing fills
+    pub fn vwap_for_qty(
+        &self,
+        py: Python<'_>,
+        side: &str,
+        qty: f64,
+    ) -> PyResult<Option<(f64, f64, f64)>> {
+        // The walk length is unknown up front; the whole side bounds it
+        without_gil(py, self.walked(usize::MAX), || self.sweep_cost(side, qty))
+    }
+
+    // Sweep the opposite side with a market order worth `notional` (quote):
+    // (impact in bps, best price left on that side, average fill price,
+    // filled qty). The impact is how far the touch moves against the order,
+    // so it is >= 0 for both sides; if the sweep empties the side, the best
+    // price left is None and the impact is measured to the last level taken.
+    // None when there is nothing to take.
+    pub fn impact(&self, py: Python<'_>, side: &str, notional: f64) -> PyResult<Option<Impact>> {
+        if !(notional.is_finite() && notional > 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "notional must be positive, got {}",
+                notional
+            )));
+        }
+        let buy = Side::parse(side)? == Side::Bid;
+        Ok(without_gil(py, self.walked(usize::MAX), || {
+            let levels: Box<dyn Iterator<Item = (f64, f64)>> = if buy {
+                Box::new(self.ask_levels())
+            } else {
+                Box::new(self.bid_levels())
+            };
+            let mut levels = levels.peekable();
+            let (touch, _) = *levels.peek()?;
+            let (mut remaining, mut qty, mut cost, mut last) = (notional, 0.0, 0.0, touch);
+            let mut after = None;
+            for (p, s) in levels {
+                if remaining <= 0.0 {
+                    after = Some(p);
+                    break;
+                }
+                let take = s.min(remaining / p);
+                remaining -= take * p;
+                qty += take;
+                cost += take * p;
+                last = p;
+                if take < s {
+                    after = Some(p);
+                    break;
+                }
+            }
+            let moved = (after.unwrap_or(last) - touch) / touch * 10_000.0;
+            Some((if buy { moved } else { -moved }, after, cost / qty, qty))
+        }))
+    }
+
+    // Cumulative (base size, quote notional) resting on `side` ("bid"/"ask")
+    // within `bps` basis points of mid; None without a mid
+    pub fn liquidity_within_bps(
+        &self,
+        py: Python<'_>,
+        side: &str,
+        bps: f64,
+    ) -> PyResult<Option<(f64, f64)>> {
+        if !(bps.is_finite() && bps >= 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "bps must be non-negative, got {}",
+                bps
+            )));
+        }
+        let side = Side::parse(side)?;
+        let Some(mid) = self.raw_mid() else {
+            return Ok(None);
+        };
+        let band = mid * bps / 10_000.0;
+        Ok(Some(without_gil(py, self.walked(usize::MAX), || {
+            let levels: Box<dyn Iterator<Item = (f64, f64)>> = match side {
+                Side::Bid => Box::new(self.bid_levels().take_while(|(p, _)| *p >= mid - band)),
+                Side::Ask => Box::new(self.ask_levels().take_while(|(p, _)| *p <= mid + band)),
+            };
+            levels.fold((0.0, 0.0), |(q, n), (p, s)| (q + s, n + s * p))
+        })))
+    }
+
+    // None (or StaleBookError) when the book is stale; see max_age_ms
+    pub fn mid(&self) -> PyResult<Option<f64>> {
+        self.guarded(self.raw_mid())
+    }
+
+    // Size-weighted microprice over `depth` levels per side. With `decay`,
+    // each level is weighted by exp(-decay * distance from mid in bps).
+    // Guarded against staleness like mid().
+    #[pyo3(signature = (depth=1, decay=None))]
+    pub fn microprice(
+        &self,
+        py: Python<'_>,
+        depth: usize,
+        decay: Option<f64>,
+    ) -> PyResult<Option<f64>> {
+        let price = without_gil(py, self.walked(depth), || {
+            self.weighted_microprice(depth, decay)
+        });
+        self.guarded(price)
+    }
+
+    // Mean of the bid and ask size-weighted average prices over the top
+    // `depth` levels. With `decay`, level i (0 = best) is further weighted
+    // by exp(-decay * i). Guarded against staleness like mid().
+    #[pyo3(signature = (depth=5, decay=None))]
+    pub fn weighted_mid(
+        &self,
+        py: Python<'_>,
+        depth: usize,
+        decay: Option<f64>,
+    ) -> PyResult<Option<f64>> {
+        if decay.is_some_and(|k| k.is_nan() || k < 0.0) {
+            return Err(PyValueError::new_err("decay must be non-negative"));
+        }
+        let avg = |levels: &mut dyn Iterator<Item = (f64, f64)>| {
+            let (wq, wpq) =
+                levels
+                    .take(depth)
+                    .enumerate()
+                    .fold((0.0, 0.0), |(wq, wpq), (i, (p, s))| {
+                        let w = decay.map_or(1.0, |k| (-k * i as f64).exp()) * s;
+                        (wq + w, wpq + w * p)
+                    });
+            (wq > 0.0).then(|| wpq / wq)
+        };
+        let price = without_gil(py, self.walked(depth), || {
+            match (avg(&mut self.bid_levels()), avg(&mut self.ask_levels())) {
+                (Some(b), Some(a)) => Some(self.snap((b + a) / 2.0)),
+                _ => None,
+            }
+        });
+        self.guarded(price)
+    }
+
+    // Depth profile and shape descriptors over the top `depth` levels (see
+    // BookShape); None without a mid
+    #[pyo3(signature = (depth=10))]
+    pub fn shape(&self, py: Python<'_>, depth: usize) -> Option<shape::BookShape> {
+        let mid = self.raw_mid()?;
+        Some(without_gil(py, self.walked(depth), || {
+            shape::BookShape::new(
+                &self.top(Side::Bid, depth),
+                &self.top(Side::Ask, depth),
+                mid,
+                depth,
+            )
+        }))
+    }
+
+    // Milliseconds since the last applied update to `side` ("bid"/"ask"),
+    // or to either side; None before the first update
+    #[pyo3(signature = (side=None))]
+    pub fn age_ms(&self, side: Option<&str>) -> PyResult<Option<f64>> {
+        let stamp = match side.map(Side::parse).transpose()? {
+            None => self.updated_ns,
+            Some(Side::Bid) => self.side_updated_ns[0],
+            Some(Side::Ask) => self.side_updated_ns[1],
+        };
+        Ok(self.age_ns(stamp).map(|ns| ns as f64 / 1e6))
+    }
+
+    // Older than max_age_ms (or never updated while a limit is set)
+    #[getter]
+    pub fn is_stale(&self) -> bool {
+        self.stale()
+    }
+
+    #[getter]
+    pub fn max_age_ms(&self) -> Option<f64> {
+        self.max_age_ms
+    }
+
+    #[setter]
+    pub fn set_max_age_ms(&mut self, max_age_ms: Option<f64>) -> PyResult<()> {
+        if max_age_ms.is_some_and(|ms| ms.is_nan() || ms < 0.0) {
+            return Err(PyValueError::new_err("max_age_ms must be non-negative"));
+        }
+        self.max_age_ms = max_age_ms;
+        Ok(())
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some((bp, _)), Some((ap, _))) => Some(ap - bp),
+            _ => None,
+        }
+    }
+
+    // Spread in basis points of the reference price ("mid", "microprice", "best_bid")
+    #[pyo3(signature = (reference="mid"))]
+    pub fn spread_bps(&self, reference: &str) -> PyResult<Option<f64>> {
+        let reference = self.reference_price(reference)?;
+        Ok(match (self.spread(), reference) {
+            (Some(spread), Some(r)) if r > 0.0 => Some(spread / r * 10_000.0),
+            _ => None,
+        })
+    }
+
+    #[pyo3(signature = (reference="mid"))]
+    pub fn half_spread_bps(&self, reference: &str) -> PyResult<Option<f64>> {
+        Ok(self.spread_bps(reference)?.map(|bps| bps / 2.0))
+    }
+
+    #[pyo3(signature = (depth, decay=None))]
+    pub fn imbalance(&self, py: Python<'_>, depth: usize, decay: Option<f64>) -> f64 {
+        without_gil(py, self.walked(depth), || {
+            self.weighted_imbalance(depth, decay)
+        })
+    }
+
+    // Total levels on both sides
+    fn __len__(&self) -> usize {
+        self.bids.len() + self.asks.len()
+    }
+
+    // True once both sides have a level, i.e. the book can be quoted off
+    fn __bool__(&self) -> bool {
+        !self.bids.is_empty() && !self.asks.is_empty()
+    }
+
+    // Books are equal when they hold the same levels, compared as decoded
+    // prices; settings, update ids and timing are not compared. Books are
+    // mutable, so they are unhashable.
+    fn __eq__(&self, other: &Self) -> bool {
+        self.bid_levels().eq(other.bid_levels()) && self.ask_levels().eq(other.ask_levels())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "L2Book(bids={}, asks={}, levels=({}, {}), last_update_id={})",
+            self.repr_side(Side::Bid, self.bids.len()),
+            self.repr_side(Side::Ask, self.asks.len()),
+            self.bids.len(),
+            self.asks.len(),
+            self.last_update_id
+                .map_or_else(|| "None".to_owned(), |id| id.to_string())
+        )
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
+
+fn extract_delta(t: &Bound<'_, PyTuple>) -> PyResult<DeltaUpdate> {
+    if !(2..=4).contains(&t.len()) {
+        return Err(PyValueError::new_err(
+            "delta must be (bids, asks[, update_id[, prev_update_id]])",
+        ));
+    }
+    let opt_id = |i: usize| -> PyResult<Option<u64>> {
+        match t.get_item(i) {
+            Ok(v) => v.extract(),
+            Err(_) => Ok(None),
+        }
+    };
+    Ok((
+        t.get_item(0)?.extract()?,
+        t.get_item(1)?.extract()?,
+        opt_id(2)?,
+        opt_id(3)?,
+    ))
 }
 
 #[pymodule]
 fn mm_orderbook(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<L2Book>()?;
+    m.add_class::<BookUpdate>()?;
+    m.add_class::<normalized::Trade>()?;
+    m.add_class::<normalized::OrderUpdate>()?;
+    m.add_class::<normalized::Fill>()?;
+    m.add_class::<invariants::BookValidation>()?;
+    m.add_class::<invariants::BookIssue>()?;
+    m.add_class::<shape::BookShape>()?;
+    m.add_class::<arrow::ArrowBatch>()?;
+    m.add_class::<l3::L3Book>()?;
+    m.add_class::<manager::BookManager>()?;
+    m.add_class::<shared::SharedL2Book>()?;
+    m.add_class::<shm::ShmBook>()?;
+    m.add_class::<feed::FeedClient>()?;
+    m.add_class::<feed::FeedEvent>()?;
+    m.add_class::<ring::EventQueue>()?;
+    m.add_class::<aio::NextEvent>()?;
+    m.add_class::<snapshot::SnapshotFetcher>()?;
+    m.add_class::<gateway::OrderGateway>()?;
+    m.add_class::<gateway::OrderResponse>()?;
+    m.add_class::<gateway::OrderRequest>()?;
+    m.add_class::<consolidated::ConsolidatedBook>()?;
+    m.add_class::<spread::CrossVenueSpread>()?;
+    m.add_class::<basis::BasisTracker>()?;
+    m.add_class::<kalman::FairValueKalman>()?;
+    m.add_class::<blender::FairValueBlender>()?;
+    m.add_class::<markout::MarkoutAnalyzer>()?;
+    m.add_class::<queue::QueueTracker>()?;
+    m.add_class::<fillprob::FillProbability>()?;
+    m.add_class::<iceberg::IcebergDetector>()?;
+    m.add_class::<iceberg::Iceberg>()?;
+    m.add_class::<sweep::SweepDetector>()?;
+    m.add_class::<sweep::Sweep>()?;
+    m.add_class::<ofi::OfiCalculator>()?;
+    m.add_class::<trades::TradeTape>()?;
+    m.add_class::<hawkes::HawkesIntensity>()?;
+    m.add_class::<cvd::CvdTracker>()?;
+    m.add_class::<vpin::Vpin>()?;
+    m.add_class::<vol::VolEstimator>()?;
+    m.add_class::<rolling::RollingStats>()?;
+    m.add_class::<ewma::Ewma>()?;
+    m.add_class::<ewma::Ewmv>()?;
+    m.add_class::<bars::BarBuilder>()?;
+    m.add_class::<metrics::Metrics>()?;
+    m.add_class::<tracker::LatencyTracker>()?;
+    m.add_class::<quoting::Quote>()?;
+    m.add_class::<quoting::QuoteEngine>()?;
+    m.add_class::<glft::GlftQuoter>()?;
+    m.add_class::<intensity::IntensityEstimator>()?;
+    m.add_class::<skew::InventorySkew>()?;
+    m.add_class::<skew::SkewAdjustment>()?;
+    m.add_class::<ladder::LadderGenerator>()?;
+    m.add_class::<requote::RequoteGate>()?;
+    m.add_class::<filters::SymbolFilters>()?;
+    m.add_class::<risk::RiskGuard>()?;
+    m.add_class::<limits::LimitBook>()?;
+    m.add_class::<limits::SymbolLimits>()?;
+    m.add_class::<limits::LimitDecision>()?;
+    m.add_class::<portfolio::PortfolioRisk>()?;
+    m.add_class::<band::PriceBandGuard>()?;
+    m.add_class::<rejects::RejectMonitor>()?;
+    m.add_class::<position::Position>()?;
+    m.add_class::<pnl::PnlAttribution>()?;
+    m.add_class::<pnl::PnlBucket>()?;
+    m.add_class::<equity::EquityTracker>()?;
+    m.add_class::<hedge::Hedger>()?;
+    m.add_class::<hedge::HedgeOrder>()?;
+    m.add_class::<fees::FeeModel>()?;
+    m.add_class::<ratelimit::RateLimiter>()?;
+    m.add_class::<orders::OrderManager>()?;
+    m.add_class::<orders::OrderInfo>()?;
+    m.add_class::<orderdiff::OrderDiff>()?;
+    m.add_class::<orderdiff::OrderAction>()?;
+    m.add_class::<orderid::OrderIdGen>()?;
+    m.add_class::<stp::StpChecker>()?;
+    m.add_class::<stp::StpDecision>()?;
+    m.add_class::<sim::SimExchange>()?;
+    m.add_class::<sim::SimFill>()?;
+    m.add_class::<backtest::Backtester>()?;
+    m.add_class::<backtest::BacktestReport>()?;
+    m.add_class::<paramsweep::SweepRunner>()?;
+    m.add_class::<walkforward::WalkForward>()?;
+    m.add_class::<walkforward::WalkForwardReport>()?;
+    m.add_class::<walkforward::WalkForwardFold>()?;
+    m.add_class::<synthetic::SyntheticFeed>()?;
+    m.add_class::<clock::ReplayClock>()?;
+    m.add_class::<clock::ClockSkew>()?;
+    m.add_function(wrap_pyfunction!(clock::py_monotonic_ns, m)?)?;
+    m.add_function(wrap_pyfunction!(state::restore_state, m)?)?;
+    m.add_class::<latency::LatencyModel>()?;
+    m.add_class::<recorder::Recorder>()?;
+    m.add_class::<journal::JournalWriter>()?;
+    m.add_class::<journal::JournalReader>()?;
+    m.add_class::<journal::JournalRecord>()?;
+    m.add_function(wrap_pyfunction!(journal::read_journal_to_polars, m)?)?;
+    m.add_class::<binance::BinanceBookSync>()?;
+    m.add_class::<bybit::BybitBookParser>()?;
+    m.add_class::<bybit::BybitBookMessage>()?;
+    m.add_class::<depth::DepthParser>()?;
+    m.add_class::<depth::DepthMessage>()?;
+    m.add_function(wrap_pyfunction!(depth::parse_depth_message, m)?)?;
+    m.add_class::<fix::FixCodec>()?;
+    m.add_class::<fix::FixMessage>()?;
+    m.add_class::<fix::MdEntry>()?;
+    m.add_class::<fix::ExecutionReport>()?;
+    m.add_class::<sbe::SbeDecoder>()?;
+    m.add_class::<sbe::SbeMessage>()?;
+    m.add("SequenceGapError", m.py().get_type::<SequenceGapError>())?;
+    m.add("CrossedBookError", m.py().get_type::<CrossedBookError>())?;
+    m.add("StaleBookError", m.py().get_type::<StaleBookError>())?;
+    m.add(
+        "InvalidTransitionError",
+        m.py().get_type::<InvalidTransitionError>(),
+    )?;
+    m.add(
+        "BookInvariantError",
+        m.py().get_type::<BookInvariantError>(),
+    )?;
+    m.add("InvalidPriceError", m.py().get_type::<InvalidPriceError>())?;
+    // Whether wss:// and https:// URLs are usable in this build
+    m.add("HAS_TLS", tls::ENABLED)?;
     Ok(())
 }
@@ -0,0 +1,249 @@
+// Pre-trade limits the OMS consults before accepting an order. Per symbol
+// (falling back to a default): order size, open orders per side, and the
+// worst-case position if the order and every open order on its side fill,
+// in base units and in notional. Across symbols: gross and net position
+// notional with the same worst case for the order's symbol. Positions are
+// pushed in with set_position(); open orders come from an OrderManager when
+// one is passed to check().
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::orders::OrderManager;
+use crate::Side;
+
+#[pyclass]
+#[derive(Clone, Debug, Default)]
+pub struct SymbolLimits {
+    // None disables the check
+    #[pyo3(get, set)]
+    pub max_position: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_notional: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_order_qty: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_open_orders: Option<usize>,
+}
+
+#[pymethods]
+impl SymbolLimits {
+    #[new]
+    #[pyo3(signature = (max_position=None, max_notional=None, max_order_qty=None, max_open_orders=None))]
+    pub fn new(
+        max_position: Option<f64>,
+        max_notional: Option<f64>,
+        max_order_qty: Option<f64>,
+        max_open_orders: Option<usize>,
+    ) -> Self {
+        Self {
+            max_position,
+            max_notional,
+            max_order_qty,
+            max_open_orders,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SymbolLimits(max_position={:?}, max_notional={:?}, max_order_qty={:?}, max_open_orders={:?})",
+            self.max_position, self.max_notional, self.max_order_qty, self.max_open_orders
+        )
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct LimitDecision {
+    pub allowed: bool,
+    // First limit the order would breach
+    pub reason: Option<String>,
+}
+
+#[pymethods]
+impl LimitDecision {
+    fn __bool__(&self) -> bool {
+        self.allowed
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LimitDecision(allowed={}, reason={:?})",
+            self.allowed, self.reason
+        )
+    }
+}
+
+#[pyclass]
+pub struct LimitBook {
+    default: SymbolLimits,
+    symbols: HashMap<String, SymbolLimits>,
+    #[pyo3(get, set)]
+    pub max_gross_notional: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_net_notional: Option<f64>,
+    // (signed qty, mark price) per symbol
+    positions: HashMap<String, (f64, f64)>,
+}
+
+impl LimitBook {
+    fn breach(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: f64,
+        qty: f64,
+        orders: Option<&OrderManager>,
+    ) -> PyResult<Option<String>> {
+        let limits = self.symbols.get(symbol).unwrap_or(&self.default);
+        if let Some(limit) = limits.max_order_qty {
+            if qty > limit {
+                return Ok(Some(format!(
+                    "order qty {} exceeds max_order_qty {}",
+                    qty, limit
+                )));
+            }
+        }
+        let open = match orders {
+            Some(om) => om.open_orders(Some(symbol), Some(side.name()), None)?,
+            None => Vec::new(),
+        };
+        if let Some(limit) = limits.max_open_orders {
+            if open.len() >= limit {
+                return Ok(Some(format!(
+                    "{} open {} orders on {} (max_open_orders {})",
+                    open.len(),
+                    side.name(),
+                    symbol,
+                    limit
+                )));
+            }
+        }
+        let pending = qty + open.iter().map(|o| o.qty - o.filled).sum::<f64>();
+        let (current, _) = self.positions.get(symbol).copied().unwrap_or((0.0, price));
+        let worst = match side {
+            Side::Bid => current + pending,
+            Side::Ask => current - pending,
+        };
+        if let Some(limit) = limits.max_position {
+            if worst.abs() > limit {
+                return Ok(Some(format!(
+                    "worst-case position {} exceeds max_position {}",
+                    worst, limit
+                )));
+            }
+        }
+        let notional = worst * price;
+        if let Some(limit) = limits.max_notional {
+            if notional.abs() > limit {
+                return Ok(Some(format!(
+                    "worst-case notional {:.4} exceeds max_notional {}",
+                    notional.abs(),
+                    limit
+                )));
+            }
+        }
+        let others = self
+            .positions
+            .iter()
+            .filter(|(s, _)| *s != symbol)
+            .map(|(_, (q, p))| q * p);
+        let (gross, net) =
+            others.fold((notional.abs(), notional), |(g, n), x| (g + x.abs(), n + x));
+        if let Some(limit) = self.max_gross_notional {
+            if gross > limit {
+                return Ok(Some(format!(
+                    "portfolio gross notional {:.4} exceeds max_gross_notional {}",
+                    gross, limit
+                )));
+            }
+        }
+        if let Some(limit) = self.max_net_notional {
+            if net.abs() > limit {
+                return Ok(Some(format!(
+                    "portfolio net notional {:.4} exceeds max_net_notional {}",
+                    net.abs(),
+                    limit
+                )));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[pymethods]
+impl LimitBook {
+    // default applies to symbols without their own limits
+    #[new]
+    #[pyo3(signature = (default=None, max_gross_notional=None, max_net_notional=None))]
+    pub fn new(
+        default: Option<SymbolLimits>,
+        max_gross_notional: Option<f64>,
+        max_net_notional: Option<f64>,
+    ) -> Self {
+        Self {
+            default: default.unwrap_or_default(),
+            symbols: HashMap::new(),
+            max_gross_notional,
+            max_net_notional,
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn set_limits(&mut self, symbol: String, limits: SymbolLimits) {
+        self.symbols.insert(symbol, limits);
+    }
+
+    // The symbol's limits, or the default
+    pub fn limits(&self, symbol: &str) -> SymbolLimits {
+        self.symbols.get(symbol).unwrap_or(&self.default).clone()
+    }
+
+    // Signed position and the price to value it at
+    pub fn set_position(&mut self, symbol: String, qty: f64, mark: f64) {
+        self.positions.insert(symbol, (qty, mark));
+    }
+
+    // Whether an order may be sent; pass the OrderManager so its open
+    // orders count towards the open-order and worst-case checks
+    #[pyo3(signature = (symbol, side, price, qty, orders=None))]
+    pub fn check(
+        &self,
+        symbol: &str,
+        side: &str,
+        price: f64,
+        qty: f64,
+        orders: Option<PyRef<'_, OrderManager>>,
+    ) -> PyResult<LimitDecision> {
+        let side = Side::parse(side)?;
+        if qty <= 0.0 || price <= 0.0 {
+            return Err(PyValueError::new_err(
+                "order price and qty must be positive",
+            ));
+        }
+        let reason = self.breach(symbol, side, price, qty, orders.as_deref())?;
+        Ok(LimitDecision {
+            allowed: reason.is_none(),
+            reason,
+        })
+    }
+
+    // Signed position notional per symbol
+    pub fn exposures(&self) -> HashMap<String, f64> {
+        self.positions
+            .iter()
+            .map(|(s, (q, p))| (s.clone(), q * p))
+            .collect()
+    }
+
+    #[getter]
+    pub fn gross_notional(&self) -> f64 {
+        self.positions.values().map(|(q, p)| (q * p).abs()).sum()
+    }
+
+    #[getter]
+    pub fn net_notional(&self) -> f64 {
+        self.positions.values().map(|(q, p)| q * p).sum()
+    }
+}
@@ -0,0 +1,186 @@
+// Many L2Books keyed by symbol, so per-symbol routing stays on the Rust side
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+
+use crate::arrays::LevelsInput;
+use crate::state::{self, Persist, Stateful};
+use crate::{CrossPolicy, L2Book, Levels};
+
+#[pyclass]
+pub struct BookManager {
+    books: HashMap<String, Py<L2Book>>,
+    raise_on_gap: bool,
+    cross_policy: CrossPolicy,
+}
+
+// Books in symbol order, so equal managers save equal bytes
+impl Persist for BookManager {
+    fn save(&self, out: &mut Vec<u8>) {
+        let mut symbols: Vec<&String> = self.books.keys().collect();
+        symbols.sort();
+        symbols.len().save(out);
+        Python::with_gil(|py| {
+            for symbol in symbols {
+                symbol.save(out);
+                self.books[symbol].borrow(py).save(out);
+            }
+        });
+        self.raise_on_gap.save(out);
+        self.cross_policy.save(out);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        let books: Vec<(String, L2Book)> = Persist::load(input)?;
+        let books = Python::with_gil(|py| {
+            books
+                .into_iter()
+                .map(|(symbol, book)| Ok((symbol, Py::new(py, book)?)))
+                .collect::<PyResult<_>>()
+        })?;
+        Ok(Self {
+            books,
+            raise_on_gap: Persist::load(input)?,
+            cross_policy: Persist::load(input)?,
+        })
+    }
+}
+
+impl Stateful for BookManager {
+    const NAME: &'static str = "BookManager";
+}
+
+impl BookManager {
+    fn book(&self, symbol: &str) -> PyResult<&Py<L2Book>> {
+        self.books
+            .get(symbol)
+            .ok_or_else(|| PyKeyError::new_err(symbol.to_string()))
+    }
+}
+
+#[pymethods]
+impl BookManager {
+    #[new]
+    #[pyo3(signature = (raise_on_gap=false, cross_policy="ignore"))]
+    pub fn new(raise_on_gap: bool, cross_policy: &str) -> PyResult<Self> {
+        Ok(Self {
+            books: HashMap::new(),
+            raise_on_gap,
+            cross_policy: CrossPolicy::parse(cross_policy)?,
+        })
+    }
+
+    // Creates the book on first snapshot
+    #[pyo3(signature = (symbol, bids, asks, update_id=None))]
+    pub fn apply_snapshot(
+        &mut self,
+        py: Python<'_>,
+        symbol: &str,
+        bids: LevelsInput,
+        asks: LevelsInput,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        if !self.books.contains_key(symbol) {
+            let book = L2Book {
+                raise_on_gap: self.raise_on_gap,
+                cross_policy: self.cross_policy,
+                ..Default::default()
+            };
+            let book = Py::new(py, book)?;
+            self.books.insert(symbol.to_string(), book);
+        }
+        self.book(symbol)?
+            .borrow_mut(py)
+            .apply_snapshot(py, bids, asks, update_id, false)?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (symbol, bids, asks, update_id=None, prev_update_id=None))]
+    pub fn apply_delta(
+        &self,
+        py: Python<'_>,
+        symbol: &str,
+        bids: LevelsInput,
+        asks: LevelsInput,
+        update_id: Option<u64>,
+        prev_update_id: Option<u64>,
+    ) -> PyResult<bool> {
+        // Through L2Book.apply_delta so the book's callbacks fire
+        let book = self.book(symbol)?.bind(py);
+        L2Book::apply_delta(book, bids, asks, update_id, prev_update_id, false)?.extract(py)
+    }
+
+    pub fn get(&self, py: Python<'_>, symbol: &str) -> Option<Py<L2Book>> {
+        self.books.get(symbol).map(|b| b.clone_ref(py))
+    }
+
+    pub fn remove(&mut self, symbol: &str) -> bool {
+        self.books.remove(symbol).is_some()
+    }
+
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.books.keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
+    pub fn mids(&self, py: Python<'_>) -> PyResult<HashMap<String, Option<f64>>> {
+        self.books
+            .iter()
+            .map(|(sym, b)| Ok((sym.clone(), b.borrow(py).mid()?)))
+            .collect()
+    }
+
+    // symbol -> (bids, asks), all levels unless depth is given
+    #[pyo3(signature = (depth=None))]
+    pub fn snapshot_all(
+        &self,
+        py: Python<'_>,
+        depth: Option<usize>,
+    ) -> HashMap<String, (Levels, Levels)> {
+        let n = depth.unwrap_or(usize::MAX);
+        self.books
+            .iter()
+            .map(|(sym, b)| (sym.clone(), b.borrow(py).depth(py, n)))
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.books.len()
+    }
+
+    fn __contains__(&self, symbol: &str) -> bool {
+        self.books.contains_key(symbol)
+    }
+
+    // Pickle support; see state.rs. copy.copy shares the books,
+    // copy.deepcopy copies each one.
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self, py: Python<'_>) -> Self {
+        Self {
+            books: self
+                .books
+                .iter()
+                .map(|(s, b)| (s.clone(), b.clone_ref(py)))
+                .collect(),
+            raise_on_gap: self.raise_on_gap,
+            cross_policy: self.cross_policy,
+        }
+    }
+
+    fn __deepcopy__(&self, py: Python<'_>, _memo: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let mut books = HashMap::new();
+        for (symbol, book) in &self.books {
+            books.insert(symbol.clone(), Py::new(py, book.borrow(py).clone())?);
+        }
+        Ok(Self {
+            books,
+            raise_on_gap: self.raise_on_gap,
+            cross_policy: self.cross_policy,
+        })
+    }
+}
@@ -0,0 +1,289 @@
+// Markouts of own fills against the mid history: for each horizon h,
+//   buy   (mid(t + h) - price) / price * 1e4
+//   sell  (price - mid(t + h)) / price * 1e4
+// in bps, positive when the fill made money, where mid(t) is the last mid at
+// or before t. A horizon resolves once a mid at or past t + h arrives. Mids
+// are kept in a ring buffer covering history_ms, so fills reported late (up
+// to history_ms after the fact) still resolve against the right mids.
+// Resolved markouts are averaged by side, by quote level and by UTC
+// time-of-day bucket, and kept as rows until take_markouts().
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::{L2Book, Side};
+
+const DAY_MS: i64 = 86_400_000;
+
+// (ts, side, price, qty, level, markout bps per horizon)
+type MarkoutRow = (i64, &'static str, f64, f64, Option<u32>, Vec<Option<f64>>);
+
+struct Fill {
+    ts: i64,
+    side: Side,
+    price: f64,
+    qty: f64,
+    level: Option<u32>,
+    markouts: Vec<Option<f64>>,
+    pending: usize,
+}
+
+// (count, sum of bps) per horizon
+type Agg = Vec<(u64, f64)>;
+
+#[pyclass]
+pub struct MarkoutAnalyzer {
+    horizons: Vec<i64>,
+    history_ms: i64,
+    tod_bucket_ms: i64,
+    max_records: usize,
+    mids: VecDeque<(i64, f64)>,
+    fills: HashMap<u64, Fill>,
+    // (target ts, fill id, horizon index) not yet resolved
+    pending: BTreeSet<(i64, u64, usize)>,
+    next_id: u64,
+    by_side: BTreeMap<&'static str, Agg>,
+    by_level: BTreeMap<Option<u32>, Agg>,
+    by_tod: BTreeMap<i64, Agg>,
+    rows: VecDeque<MarkoutRow>,
+}
+
+impl MarkoutAnalyzer {
+    // Last mid at or before `ts`
+    fn mid_at(&self, ts: i64) -> Option<f64> {
+        let i = self.mids.partition_point(|(t, _)| *t <= ts);
+        i.checked_sub(1).map(|i| self.mids[i].1)
+    }
+
+    fn record(agg: &mut Agg, n: usize, h: usize, bps: f64) {
+        if agg.is_empty() {
+            agg.resize(n, (0, 0.0));
+        }
+        agg[h].0 += 1;
+        agg[h].1 += bps;
+    }
+
+    // Returns whether the fill is now fully resolved
+    fn resolve(&mut self, id: u64, h: usize, target: i64) -> bool {
+        let mid = self.mid_at(target);
+        let n = self.horizons.len();
+        let Some(fill) = self.fills.get_mut(&id) else {
+            return false;
+        };
+        fill.pending -= 1;
+        // Nothing in history that old: the horizon stays None
+        if let Some(mid) = mid {
+            let bps = match fill.side {
+                Side::Bid => (mid - fill.price) / fill.price * 10_000.0,
+                Side::Ask => (fill.price - mid) / fill.price * 10_000.0,
+            };
+            fill.markouts[h] = Some(bps);
+            let tod = fill.ts.rem_euclid(DAY_MS) / self.tod_bucket_ms * self.tod_bucket_ms;
+            Self::record(self.by_side.entry(fill.side.name()).or_default(), n, h, bps);
+            Self::record(self.by_level.entry(fill.level).or_default(), n, h, bps);
+            Self::record(self.by_tod.entry(tod).or_default(), n, h, bps);
+        }
+        if fill.pending > 0 {
+            return false;
+        }
+        if let Some(f) = self.fills.remove(&id) {
+            self.rows
+                .push_back((f.ts, f.side.name(), f.price, f.qty, f.level, f.markouts));
+            if self.rows.len() > self.max_records {
+                self.rows.pop_front();
+            }
+        }
+        true
+    }
+
+    // Resolve every horizon whose target is at or before the latest mid;
+    // returns how many fills completed
+    fn resolve_due(&mut self) -> usize {
+        let Some(&(now, _)) = self.mids.back() else {
+            return 0;
+        };
+        let mut done = 0;
+        while let Some(&(target, id, h)) = self.pending.first() {
+            if target > now {
+                break;
+            }
+            self.pending.pop_first();
+            done += usize::from(self.resolve(id, h, target));
+        }
+        done
+    }
+}
+
+#[pymethods]
+impl MarkoutAnalyzer {
+    // history_ms is how late a fill may be reported and still resolve;
+    // tod_bucket_minutes sizes the time-of-day buckets
+    #[new]
+    #[pyo3(signature = (horizons_ms=vec![100, 1_000, 10_000], history_ms=60_000, tod_bucket_minutes=60, max_records=100_000))]
+    pub fn new(
+        horizons_ms: Vec<i64>,
+        history_ms: i64,
+        tod_bucket_minutes: i64,
+        max_records: usize,
+    ) -> PyResult<Self> {
+        if horizons_ms.is_empty() || horizons_ms.iter().any(|h| *h < 0) {
+            return Err(PyValueError::new_err(
+                "horizons_ms must be a non-empty list of non-negative ms",
+            ));
+        }
+        if history_ms < 0 || !(1..=1440).contains(&tod_bucket_minutes) {
+            return Err(PyValueError::new_err(format!(
+                "history_ms must be non-negative and tod_bucket_minutes in [1, 1440], got {} / {}",
+                history_ms, tod_bucket_minutes
+            )));
+        }
+        Ok(Self {
+            horizons: horizons_ms,
+            history_ms,
+            tod_bucket_ms: tod_bucket_minutes * 60_000,
+            max_records,
+            mids: VecDeque::new(),
+            fills: HashMap::new(),
+            pending: BTreeSet::new(),
+            next_id: 0,
+            by_side: BTreeMap::new(),
+            by_level: BTreeMap::new(),
+            by_tod: BTreeMap::new(),
+            rows: VecDeque::new(),
+        })
+    }
+
+    // Record a mid; returns how many fills were fully resolved by it.
+    // Mids older than the latest one are ignored.
+    pub fn on_mid(&mut self, ts: i64, mid: f64) -> usize {
+        if mid.is_nan() || mid <= 0.0 || self.mids.back().is_some_and(|(t, _)| ts < *t) {
+            return 0;
+        }
+        self.mids.push_back((ts, mid));
+        // Keep one mid before the cutoff so as-of lookups at the edge work
+        let cutoff = ts - self.history_ms;
+        while self.mids.len() > 1 && self.mids[1].0 <= cutoff {
+            self.mids.pop_front();
+        }
+        self.resolve_due()
+    }
+
+    // on_mid with the book's reference price; one-sided books are skipped
+    #[pyo3(signature = (book, ts, reference="mid"))]
+    pub fn update(&mut self, book: &L2Book, ts: i64, reference: &str) -> PyResult<usize> {
+        Ok(match book.reference_price(reference)? {
+            Some(mid) => self.on_mid(ts, mid),
+            None => 0,
+        })
+    }
+
+    // Record an own fill; level is the quote level it came from, if known
+    #[pyo3(signature = (ts, side, price, qty, level=None))]
+    pub fn add_fill(
+        &mut self,
+        ts: i64,
+        side: &str,
+        price: f64,
+        qty: f64,
+        level: Option<u32>,
+    ) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        if !(price > 0.0 && qty > 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "price and qty must be positive, got {} x {}",
+                price, qty
+            )));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.fills.insert(
+            id,
+            Fill {
+                ts,
+                side,
+                price,
+                qty,
+                level,
+                markouts: vec![None; self.horizons.len()],
+                pending: self.horizons.len(),
+            },
+        );
+        for (i, h) in self.horizons.iter().enumerate() {
+            self.pending.insert((ts + h, id, i));
+        }
+        // A late fill may already be resolvable from history
+        self.resolve_due();
+        Ok(())
+    }
+
+    // Average markout per group: {key: [(mean_bps, count), ...]} with one
+    // entry per horizon (mean None before any resolves). by is "side"
+    // ("buy"/"sell"), "level" (quote level or None) or "tod" (bucket start,
+    // minutes after UTC midnight).
+    #[pyo3(signature = (by="side"))]
+    pub fn summary<'py>(&self, py: Python<'py>, by: &str) -> PyResult<Bound<'py, PyDict>> {
+        let n = self.horizons.len();
+        let row = |agg: &Agg| -> Vec<(Option<f64>, u64)> {
+            (0..n)
+                .map(|h| match agg.get(h) {
+                    Some(&(c, s)) if c > 0 => (Some(s / c as f64), c),
+                    _ => (None, 0),
+                })
+                .collect()
+        };
+        let d = PyDict::new(py);
+        match by.to_ascii_lowercase().as_str() {
+            "side" => {
+                for (k, agg) in &self.by_side {
+                    d.set_item(*k, row(agg))?;
+                }
+            }
+            "level" => {
+                for (k, agg) in &self.by_level {
+                    d.set_item(*k, row(agg))?;
+                }
+            }
+            "tod" => {
+                for (k, agg) in &self.by_tod {
+                    d.set_item(k / 60_000, row(agg))?;
+                }
+            }
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "by must be 'side', 'level' or 'tod', got '{}'",
+                    other
+                )))
+            }
+        }
+        Ok(d)
+    }
+
+    // Drain fully resolved fills as (ts, side, price, qty, level, [bps per
+    // horizon]); a horizon older than the mid history is None
+    pub fn take_markouts(&mut self) -> Vec<MarkoutRow> {
+        self.rows.drain(..).collect()
+    }
+
+    #[getter]
+    pub fn horizons_ms(&self) -> Vec<i64> {
+        self.horizons.clone()
+    }
+
+    // Fills with at least one horizon still open
+    #[getter]
+    pub fn pending(&self) -> usize {
+        self.fills.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.mids.clear();
+        self.fills.clear();
+        self.pending.clear();
+        self.by_side.clear();
+        self.by_level.clear();
+        self.by_tod.clear();
+        self.rows.clear();
+    }
+}
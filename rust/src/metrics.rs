@@ -0,0 +1,589 @@
+// Process-wide metrics with Prometheus text exposition. Counters and gauges
+// are atomics and histograms use HDR-style log-linear buckets (16 per power
+// of two, so quantiles are within ~6%; LatencyTracker histograms can be
+// finer), so native hot paths record without
+// the GIL and without locks once a series is registered. Natively
+// instrumented series:
+//   mm_book_updates_total{kind}          snapshots / applied deltas
+//   mm_book_updates_skipped_total        stale or gapped deltas
+//   mm_book_sequence_gaps_total
+//   mm_tick_to_quote_us                  book update -> QuoteEngine.quote_book
+//   mm_orders_total{event}               OrderManager lifecycle events
+//   mm_order_ack_latency_ms              submit -> ack, in order timestamps
+//   mm_ratelimit_requests_total{result}  RateLimiter.try_acquire
+// Histogram `le` bounds are the powers of two; a bucket counts values
+// strictly below its bound.
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Instant;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+// Sub-buckets per power of two, as a power of two, for registry histograms
+const SUB_BITS: u32 = 4;
+const OCTAVES: usize = 48;
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Default)]
+pub struct AtomicF64(AtomicU64);
+
+impl AtomicF64 {
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, v: f64) {
+        self.0.store(v.to_bits(), Ordering::Relaxed);
+    }
+
+    fn add(&self, v: f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + v).to_bits())
+            });
+    }
+}
+
+pub struct Histogram {
+    sub_bits: u32,
+    // Index 0 holds values below 1, then 2^sub_bits buckets per octave
+    counts: Vec<AtomicU64>,
+    sum: AtomicF64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    pub fn new(sub_bits: u32) -> Self {
+        Self {
+            sub_bits,
+            counts: (0..1 + (OCTAVES << sub_bits))
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            sum: AtomicF64::default(),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn sub(&self) -> usize {
+        1 << self.sub_bits
+    }
+
+    fn index(&self, v: f64) -> usize {
+        if v.is_nan() || v < 1.0 {
+            return 0;
+        }
+        let bits = v.to_bits();
+        let octave = ((bits >> 52) & 0x7ff) as usize - 1023;
+        if octave >= OCTAVES {
+            return OCTAVES * self.sub();
+        }
+        let sub = ((bits >> (52 - self.sub_bits)) as usize) & (self.sub() - 1);
+        1 + octave * self.sub() + sub
+    }
+
+    // [low, high) of a bucket
+    fn bounds(&self, i: usize) -> (f64, f64) {
+        if i == 0 {
+            return (0.0, 1.0);
+        }
+        let (octave, sub) = ((i - 1) / self.sub(), (i - 1) % self.sub());
+        let base = 2f64.powi(octave as i32);
+        let step = base / self.sub() as f64;
+        (base + step * sub as f64, base + step * (sub + 1) as f64)
+    }
+
+    pub fn observe(&self, v: f64) {
+        self.counts[self.index(v)].fetch_add(1, Ordering::Relaxed);
+        self.sum.add(v);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Midpoint of the bucket holding the q-th value
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let total = self.count.load(Ordering::Relaxed);
+        if total == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, c) in self.counts.iter().enumerate() {
+            seen += c.load(Ordering::Relaxed);
+            if seen >= rank {
+                let (lo, hi) = self.bounds(i);
+                return Some((lo + hi) / 2.0);
+            }
+        }
+        None
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum.get()
+    }
+
+    // (low, high, count) of the non-empty buckets
+    pub fn buckets(&self) -> Vec<(f64, f64, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let n = c.load(Ordering::Relaxed);
+                let (lo, hi) = self.bounds(i);
+                (n > 0).then_some((lo, hi, n))
+            })
+            .collect()
+    }
+
+    pub fn reset(&self) {
+        self.counts
+            .iter()
+            .for_each(|c| c.store(0, Ordering::Relaxed));
+        self.sum.set(0.0);
+        self.count.store(0, Ordering::Relaxed);
+    }
+}
+
+pub enum Metric {
+    Counter(AtomicF64),
+    Gauge(AtomicF64),
+    Histogram(Histogram),
+}
+
+impl Metric {
+    pub fn inc(&self, v: f64) {
+        if let Self::Counter(c) = self {
+            c.add(v);
+        }
+    }
+
+    pub fn set(&self, v: f64) {
+        if let Self::Gauge(g) = self {
+            g.set(v);
+        }
+    }
+
+    pub fn observe(&self, v: f64) {
+        if let Self::Histogram(h) = self {
+            h.observe(v);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    // name -> (type, help, series by labels)
+    families: RwLock<BTreeMap<String, Family>>,
+}
+
+struct Family {
+    kind: &'static str,
+    help: String,
+    series: BTreeMap<Labels, Arc<Metric>>,
+}
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::default);
+
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+// Get or register a series; errors if the name is taken by another type.
+// New histograms get 2^sub_bits buckets per power of two.
+fn series(
+    kind: &'static str,
+    name: &str,
+    labels: Labels,
+    help: Option<&str>,
+    sub_bits: u32,
+) -> Result<Arc<Metric>, String> {
+    let lookup = |families: &BTreeMap<String, Family>| -> Result<Option<Arc<Metric>>, String> {
+        match families.get(name) {
+            Some(f) if f.kind != kind => {
+                Err(format!("metric {} is a {}, not a {}", name, f.kind, kind))
+            }
+            Some(f) => Ok(f.series.get(&labels).cloned()),
+            None => Ok(None),
+        }
+    };
+    {
+        let families = REGISTRY.families.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(m) = lookup(&families)? {
+            return Ok(m);
+        }
+    }
+    if !valid_name(name) || labels.iter().any(|(k, _)| !valid_name(k)) {
+        return Err(format!("invalid metric or label name in {}", name));
+    }
+    let mut families = REGISTRY.families.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(m) = lookup(&families)? {
+        return Ok(m);
+    }
+    let family = families.entry(name.to_owned()).or_insert_with(|| Family {
+        kind,
+        help: String::new(),
+        series: BTreeMap::new(),
+    });
+    if let Some(help) = help {
+        family.help = help.to_owned();
+    }
+    let metric = Arc::new(match kind {
+        "counter" => Metric::Counter(AtomicF64::default()),
+        "gauge" => Metric::Gauge(AtomicF64::default()),
+        _ => Metric::Histogram(Histogram::new(sub_bits)),
+    });
+    family.series.insert(labels, metric.clone());
+    Ok(metric)
+}
+
+fn native(kind: &'static str, name: &str, labels: &[(&str, &str)], help: &str) -> Arc<Metric> {
+    let labels = labels
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    series(kind, name, labels, Some(help), SUB_BITS).expect("native metric names are unique")
+}
+
+// Handles for the natively instrumented series, registered on first use
+pub struct Native {
+    pub book_snapshots: Arc<Metric>,
+    pub book_deltas: Arc<Metric>,
+    pub book_skipped: Arc<Metric>,
+    pub book_gaps: Arc<Metric>,
+    pub tick_to_quote: Arc<Metric>,
+    pub orders_submitted: Arc<Metric>,
+    pub orders_acked: Arc<Metric>,
+    pub orders_rejected: Arc<Metric>,
+    pub orders_filled: Arc<Metric>,
+    pub orders_canceled: Arc<Metric>,
+    pub order_ack_latency: Arc<Metric>,
+    pub ratelimit_allowed: Arc<Metric>,
+    pub ratelimit_throttled: Arc<Metric>,
+}
+
+pub static NATIVE: LazyLock<Native> = LazyLock::new(|| {
+    let updates = "Order book updates applied";
+    let orders = "Order lifecycle events seen by OrderManager";
+    let ratelimit = "RateLimiter.try_acquire calls";
+    Native {
+        book_snapshots: native(
+            "counter",
+            "mm_book_updates_total",
+            &[("kind", "snapshot")],
+            updates,
+        ),
+        book_deltas: native(
+            "counter",
+            "mm_book_updates_total",
+            &[("kind", "delta")],
+            updates,
+        ),
+        book_skipped: native(
+            "counter",
+            "mm_book_updates_skipped_total",
+            &[],
+            "Deltas skipped as stale or while awaiting resync",
+        ),
+        book_gaps: native(
+            "counter",
+            "mm_book_sequence_gaps_total",
+            &[],
+            "Sequence gaps detected",
+        ),
+        tick_to_quote: native(
+            "histogram",
+            "mm_tick_to_quote_us",
+            &[],
+            "Microseconds from the last book update to quote_book",
+        ),
+        orders_submitted: native("counter", "mm_orders_total", &[("event", "submit")], orders),
+        orders_acked: native("counter", "mm_orders_total", &[("event", "ack")], orders),
+        orders_rejected: native("counter", "mm_orders_total", &[("event", "reject")], orders),
+        orders_filled: native("counter", "mm_orders_total", &[("event", "fill")], orders),
+        orders_canceled: native("counter", "mm_orders_total", &[("event", "cancel")], orders),
+        order_ack_latency: native(
+            "histogram",
+            "mm_order_ack_latency_ms",
+            &[],
+            "Milliseconds from submit to ack, in order timestamps",
+        ),
+        ratelimit_allowed: native(
+            "counter",
+            "mm_ratelimit_requests_total",
+            &[("result", "allowed")],
+            ratelimit,
+        ),
+        ratelimit_throttled: native(
+            "counter",
+            "mm_ratelimit_requests_total",
+            &[("result", "throttled")],
+            ratelimit,
+        ),
+    }
+});
+
+// Microseconds since `since`, into the tick-to-quote histogram
+pub fn observe_since(metric: &Metric, since: Instant) {
+    metric.observe(since.elapsed().as_secs_f64() * 1e6);
+}
+
+fn fmt_value(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".into()
+    } else if v.is_infinite() {
+        if v > 0.0 { "+Inf" } else { "-Inf" }.into()
+    } else {
+        v.to_string()
+    }
+}
+
+fn fmt_labels(labels: &Labels, extra: Option<(&str, &str)>) -> String {
+    let parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .chain(extra)
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", k, v)
+        })
+        .collect();
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+pub fn render() -> String {
+    let families = REGISTRY.families.read().unwrap_or_else(|e| e.into_inner());
+    let mut out = String::new();
+    for (name, family) in families.iter() {
+        if !family.help.is_empty() {
+            let help = family.help.replace('\\', "\\\\").replace('\n', "\\n");
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+        }
+        let _ = writeln!(out, "# TYPE {} {}", name, family.kind);
+        for (labels, metric) in &family.series {
+            match metric.as_ref() {
+                Metric::Counter(v) | Metric::Gauge(v) => {
+                    let _ = writeln!(
+                        out,
+                        "{}{} {}",
+                        name,
+                        fmt_labels(labels, None),
+                        fmt_value(v.get())
+                    );
+                }
+                Metric::Histogram(h) => {
+                    let last = h
+                        .counts
+                        .iter()
+                        .rposition(|c| c.load(Ordering::Relaxed) > 0)
+                        .map_or(0, |i| i.div_ceil(h.sub()));
+                    let mut cumulative = h.counts[0].load(Ordering::Relaxed);
+                    for octave in 0..=last.min(OCTAVES) {
+                        if octave > 0 {
+                            let from = 1 + (octave - 1) * h.sub();
+                            cumulative += h.counts[from..from + h.sub()]
+                                .iter()
+                                .map(|c| c.load(Ordering::Relaxed))
+                                .sum::<u64>();
+                        }
+                        let le = fmt_value(2f64.powi(octave as i32));
+                        let _ = writeln!(
+                            out,
+                            "{}_bucket{} {}",
+                            name,
+                            fmt_labels(labels, Some(("le", &le))),
+                            cumulative
+                        );
+                    }
+                    let count = h.count.load(Ordering::Relaxed);
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{} {}",
+                        name,
+                        fmt_labels(labels, Some(("le", "+Inf"))),
+                        count
+                    );
+                    let _ = writeln!(
+                        out,
+                        "{}_sum{} {}",
+                        name,
+                        fmt_labels(labels, None),
+                        fmt_value(h.sum.get())
+                    );
+                    let _ = writeln!(out, "{}_count{} {}", name, fmt_labels(labels, None), count);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn labels_of(labels: Option<BTreeMap<String, String>>) -> Labels {
+    labels.map(|l| l.into_iter().collect()).unwrap_or_default()
+}
+
+fn py_series(
+    kind: &'static str,
+    name: &str,
+    labels: Option<BTreeMap<String, String>>,
+    help: Option<&str>,
+) -> PyResult<Arc<Metric>> {
+    series(kind, name, labels_of(labels), help, SUB_BITS).map_err(PyValueError::new_err)
+}
+
+// Registered histogram with a given precision, for LatencyTracker
+pub fn histogram(
+    name: &str,
+    labels: Option<BTreeMap<String, String>>,
+    help: Option<&str>,
+    sub_bits: u32,
+) -> PyResult<Arc<Metric>> {
+    series("histogram", name, labels_of(labels), help, sub_bits).map_err(PyValueError::new_err)
+}
+
+// Existing series, without registering
+fn find(name: &str, labels: Option<BTreeMap<String, String>>) -> Option<Arc<Metric>> {
+    let families = REGISTRY.families.read().unwrap_or_else(|e| e.into_inner());
+    families.get(name)?.series.get(&labels_of(labels)).cloned()
+}
+
+// Handle on the process-wide registry; every instance sees the same metrics
+#[pyclass(frozen)]
+pub struct Metrics;
+
+#[pymethods]
+impl Metrics {
+    #[new]
+    pub fn new() -> Self {
+        // Register the native series so they render before first use
+        LazyLock::force(&NATIVE);
+        Self
+    }
+
+    #[pyo3(signature = (name, value=1.0, labels=None, help=None))]
+    pub fn inc(
+        &self,
+        name: &str,
+        value: f64,
+        labels: Option<BTreeMap<String, String>>,
+        help: Option<&str>,
+    ) -> PyResult<()> {
+        if value < 0.0 {
+            return Err(PyValueError::new_err("counters can only increase"));
+        }
+        py_series("counter", name, labels, help)?.inc(value);
+        Ok(())
+    }
+
+    #[pyo3(signature = (name, value, labels=None, help=None))]
+    pub fn set(
+        &self,
+        name: &str,
+        value: f64,
+        labels: Option<BTreeMap<String, String>>,
+        help: Option<&str>,
+    ) -> PyResult<()> {
+        py_series("gauge", name, labels, help)?.set(value);
+        Ok(())
+    }
+
+    #[pyo3(signature = (name, value, labels=None, help=None))]
+    pub fn observe(
+        &self,
+        name: &str,
+        value: f64,
+        labels: Option<BTreeMap<String, String>>,
+        help: Option<&str>,
+    ) -> PyResult<()> {
+        py_series("histogram", name, labels, help)?.observe(value);
+        Ok(())
+    }
+
+    // Counter/gauge value, or a histogram's observation count
+    #[pyo3(signature = (name, labels=None))]
+    pub fn value(&self, name: &str, labels: Option<BTreeMap<String, String>>) -> Option<f64> {
+        Some(match find(name, labels)?.as_ref() {
+            Metric::Counter(v) | Metric::Gauge(v) => v.get(),
+            Metric::Histogram(h) => h.count.load(Ordering::Relaxed) as f64,
+        })
+    }
+
+    // Approximate histogram quantile, q in [0, 1]
+    #[pyo3(signature = (name, q, labels=None))]
+    pub fn quantile(
+        &self,
+        name: &str,
+        q: f64,
+        labels: Option<BTreeMap<String, String>>,
+    ) -> Option<f64> {
+        match find(name, labels)?.as_ref() {
+            Metric::Histogram(h) => h.quantile(q),
+            _ => None,
+        }
+    }
+
+    pub fn render_prometheus(&self) -> String {
+        render()
+    }
+
+    // Serve GET /metrics on a background thread; returns the bound port
+    // (pass 0 for any free port)
+    #[pyo3(signature = (port=9464, host="127.0.0.1"))]
+    pub fn serve(&self, port: u16, host: &str) -> PyResult<u16> {
+        let listener = TcpListener::bind((host, port))?;
+        let port = listener.local_addr()?.port();
+        std::thread::Builder::new()
+            .name("mm-metrics".into())
+            .spawn(move || {
+                for mut stream in listener.incoming().flatten() {
+                    let mut buf = [0u8; 2048];
+                    let n = stream.read(&mut buf).unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]);
+                    let path = request.split_whitespace().nth(1).unwrap_or("");
+                    let (status, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+                        ("200 OK", render())
+                    } else {
+                        ("404 Not Found", "not found\n".to_owned())
+                    };
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                }
+            })?;
+        Ok(port)
+    }
+
+    // Zero every series (registrations and help text are kept)
+    pub fn reset(&self) {
+        let families = REGISTRY.families.read().unwrap_or_else(|e| e.into_inner());
+        for m in families.values().flat_map(|f| f.series.values()) {
+            match m.as_ref() {
+                Metric::Counter(v) | Metric::Gauge(v) => v.set(0.0),
+                Metric::Histogram(h) => h.reset(),
+            }
+        }
+    }
+}
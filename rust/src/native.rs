@@ -0,0 +1,355 @@
+// Backtests without Python in the loop. A template is a built-in strategy
+// configured from a dict of float parameters; on every event for a symbol
+// it is shown the book and its inventory and names the bid and ask it
+// wants. The replay keeps those resting: an unchanged quote keeps its queue
+// place, a changed one is cancelled and re-sent post-only (a quote that
+// would cross is dropped until the next event). Matching is the
+// SimExchange's, on a plain L2Book per symbol; there is no latency model.
+// Nothing here touches Python, so runs can go off the GIL and in parallel.
+//
+// Templates: "symmetric" quotes a fixed distance around mid, "avellaneda"
+// quotes Avellaneda-Stoikov with the reservation price skewed by inventory.
+// Backtester.run_template runs one as a baseline; SweepRunner runs a grid.
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::backtest::{Event, EventKind};
+use crate::fees::FeeModel;
+use crate::filters::round_to;
+use crate::iceberg::same_price;
+use crate::position::Position;
+use crate::quoting::QuoteEngine;
+use crate::sim::{Matcher, SimFill};
+use crate::{L2Book, Side};
+
+// (price, qty); None leaves that side empty
+pub(crate) type Quote = Option<(f64, f64)>;
+
+pub(crate) trait Template: Send {
+    fn quotes(&mut self, book: &L2Book, inventory: f64, ts: i64) -> (Quote, Quote);
+}
+
+// Parameter dict for a template; every key must be consumed
+pub(crate) struct Params(HashMap<String, f64>);
+
+impl Params {
+    fn get(&mut self, key: &str, default: f64) -> f64 {
+        self.0.remove(key).unwrap_or(default)
+    }
+
+    fn finish(self, template: &str) -> PyResult<()> {
+        match self.0.keys().min() {
+            Some(key) => Err(PyValueError::new_err(format!(
+                "unknown parameter '{}' for template '{}'",
+                key, template
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+// Quotes `half_spread_bps` either side of mid, `size` each, pulling the side
+// that would take |inventory| past max_position
+struct Symmetric {
+    half_spread_bps: f64,
+    size: f64,
+    max_position: f64,
+    tick_size: Option<f64>,
+}
+
+impl Template for Symmetric {
+    fn quotes(&mut self, book: &L2Book, inventory: f64, _ts: i64) -> (Quote, Quote) {
+        let Some(mid) = book.raw_mid() else {
+            return (None, None);
+        };
+        let half = mid * self.half_spread_bps / 10_000.0;
+        let bid = round_to(mid - half, self.tick_size, false);
+        let ask = round_to(mid + half, self.tick_size, true);
+        (
+            (inventory + self.size <= self.max_position + 1e-12).then_some((bid, self.size)),
+            (inventory - self.size >= -self.max_position - 1e-12).then_some((ask, self.size)),
+        )
+    }
+}
+
+// Avellaneda-Stoikov quotes around mid (QuoteEngine) with the inventory
+// skew of the reservation price; `size` per side and the same max_position
+// cut-off as Symmetric. With volatility = 0 the volatility (price units per
+// sqrt second, like horizon in seconds) is an EWMA of squared mid changes
+// per second with half-life vol_halflife_ms; until two mids are seen the
+// spread is the volatility-free part alone.
+struct AvellanedaStoikov {
+    engine: QuoteEngine,
+    volatility: Option<f64>,
+    vol_halflife_ms: f64,
+    size: f64,
+    max_position: f64,
+    tick_size: Option<f64>,
+    // (ts, mid) of the last event, and the variance rate estimate
+    last_mid: Option<(i64, f64)>,
+    variance: Option<f64>,
+}
+
+impl AvellanedaStoikov {
+    fn update_variance(&mut self, ts: i64, mid: f64) {
+        if let Some((t0, m0)) = self.last_mid {
+            let dt_ms = (ts - t0) as f64;
+            if dt_ms > 0.0 {
+                let sample = (mid - m0).powi(2) / (dt_ms / 1_000.0);
+                let alpha = 1.0 - (-dt_ms * std::f64::consts::LN_2 / self.vol_halflife_ms).exp();
+                self.variance = Some(match self.variance {
+                    Some(v) => v + alpha * (sample - v),
+                    None => sample,
+                });
+            }
+        }
+        if self.last_mid.is_none_or(|(t0, _)| ts > t0) {
+            self.last_mid = Some((ts, mid));
+        }
+    }
+}
+
+impl Template for AvellanedaStoikov {
+    fn quotes(&mut self, book: &L2Book, inventory: f64, ts: i64) -> (Quote, Quote) {
+        let Some(mid) = book.raw_mid() else {
+            return (None, None);
+        };
+        self.update_variance(ts, mid);
+        let sigma = self
+            .volatility
+            .unwrap_or_else(|| self.variance.unwrap_or(0.0).sqrt());
+        let q = self
+            .engine
+            .compute(mid, inventory, sigma, self.engine.horizon);
+        let bid = round_to(q.bid_price, self.tick_size, false);
+        let ask = round_to(q.ask_price, self.tick_size, true);
+        (
+            (inventory + self.size <= self.max_position + 1e-12).then_some((bid, self.size)),
+            (inventory - self.size >= -self.max_position - 1e-12).then_some((ask, self.size)),
+        )
+    }
+}
+
+pub(crate) fn template(name: &str, params: &HashMap<String, f64>) -> PyResult<Box<dyn Template>> {
+    let mut p = Params(params.clone());
+    let built: Box<dyn Template> = match name {
+        "symmetric" => {
+            let t = Symmetric {
+                half_spread_bps: p.get("half_spread_bps", 5.0),
+                size: p.get("size", 1.0),
+                max_position: p.get("max_position", f64::INFINITY),
+                tick_size: Some(p.get("tick_size", 0.0)).filter(|t| *t > 0.0),
+            };
+            if t.half_spread_bps < 0.0 || t.size <= 0.0 || t.max_position < 0.0 {
+                return Err(PyValueError::new_err(
+                    "half_spread_bps and max_position must be non-negative, size positive",
+                ));
+            }
+            Box::new(t)
+        }
+        "avellaneda" => {
+            let engine = QuoteEngine::new(
+                p.get("gamma", 0.1),
+                p.get("kappa", 1.5),
+                p.get("horizon", 1.0),
+                p.get("min_spread", 0.0),
+            )?;
+            let t = AvellanedaStoikov {
+                engine,
+                volatility: Some(p.get("volatility", 0.0)).filter(|v| *v > 0.0),
+                vol_halflife_ms: p.get("vol_halflife_ms", 60_000.0),
+                size: p.get("size", 1.0),
+                max_position: p.get("max_position", f64::INFINITY),
+                tick_size: Some(p.get("tick_size", 0.0)).filter(|t| *t > 0.0),
+                last_mid: None,
+                variance: None,
+            };
+            if t.vol_halflife_ms <= 0.0 || t.size <= 0.0 || t.max_position < 0.0 {
+                return Err(PyValueError::new_err(
+                    "vol_halflife_ms and size must be positive, max_position non-negative",
+                ));
+            }
+            Box::new(t)
+        }
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "unknown template '{}'",
+                other
+            )))
+        }
+    };
+    p.finish(name)?;
+    Ok(built)
+}
+
+// Matching engine and fee/position settings shared by every run
+#[derive(Clone)]
+pub(crate) struct NativeConfig {
+    pub(crate) fee_model: Option<FeeModel>,
+    pub(crate) queue_power: f64,
+    pub(crate) position_mode: String,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct NativeResult {
+    pub(crate) realized_pnl: f64,
+    pub(crate) unrealized_pnl: f64,
+    pub(crate) fees: f64,
+    pub(crate) volume: f64,
+    pub(crate) total_pnl: f64,
+    pub(crate) fills: usize,
+    // Peak-to-trough of total PnL marked at mid after each event
+    pub(crate) max_drawdown: f64,
+    // Largest |position| held in any symbol
+    pub(crate) max_inventory: f64,
+    pub(crate) positions: BTreeMap<String, Position>,
+    // Every fill, only when the replay records them
+    pub(crate) trades: Vec<(String, SimFill)>,
+}
+
+struct SymbolState {
+    book: L2Book,
+    matcher: Matcher,
+    position: Position,
+    template: Box<dyn Template>,
+}
+
+impl SymbolState {
+    fn drain(&mut self) -> Vec<SimFill> {
+        let fills = self.matcher.take_fills();
+        for f in &fills {
+            let signed = match f.side {
+                "buy" => f.qty,
+                _ => -f.qty,
+            };
+            self.position.apply_fill(f.price, signed, f.fee);
+        }
+        fills
+    }
+
+    fn pnl(&self) -> f64 {
+        let unrealized = self
+            .book
+            .raw_mid()
+            .map_or(0.0, |m| self.position.unrealized_pnl(m));
+        self.position.realized_pnl() + unrealized - self.position.fees()
+    }
+
+    fn quote(&mut self, ts: i64) {
+        let (bid, ask) = self
+            .template
+            .quotes(&self.book, self.position.net_qty(), ts);
+        self.requote(Side::Bid, bid);
+        self.requote(Side::Ask, ask);
+    }
+
+    // Keep one resting order per side at the wanted price
+    fn requote(&mut self, side: Side, want: Quote) {
+        let resting: Vec<(u64, f64)> = self
+            .matcher
+            .open_orders()
+            .filter(|(_, s, _, _)| *s == side)
+            .map(|(id, _, price, _)| (id, price))
+            .collect();
+        if let ([(_, price)], Some((p, _))) = (resting.as_slice(), want) {
+            if same_price(*price, p) {
+                return;
+            }
+        }
+        for (id, _) in resting {
+            self.matcher.cancel(id);
+        }
+        if let Some((price, qty)) = want.filter(|(_, q)| *q > 0.0) {
+            let id = self.matcher.reserve_id();
+            self.matcher
+                .place_limit(&self.book, id, side, price, qty, true);
+        }
+    }
+}
+
+// One run of template `name` over `events`, which must be in time order;
+// each symbol gets its own template instance. `warmup` events, which come
+// before them, only build up the books, which are quoted just before the
+// first of `events`. `record` keeps every fill.
+pub(crate) fn replay(
+    warmup: &[&Event],
+    events: &[&Event],
+    config: &NativeConfig,
+    name: &str,
+    params: &HashMap<String, f64>,
+    record: bool,
+) -> PyResult<NativeResult> {
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut states: Vec<SymbolState> = Vec::new();
+    let mut result = NativeResult::default();
+    let mut peak = 0.0_f64;
+    let mut started = warmup.is_empty();
+    let stream = warmup
+        .iter()
+        .map(|ev| (ev, false))
+        .chain(events.iter().map(|ev| (ev, true)));
+    for (ev, live) in stream {
+        if live && !started {
+            started = true;
+            states.iter_mut().for_each(|st| st.quote(ev.ts));
+        }
+        let i = match index.get(ev.symbol.as_str()) {
+            Some(i) => *i,
+            None => {
+                states.push(SymbolState {
+                    book: L2Book::default(),
+                    matcher: Matcher::new(config.fee_model.clone(), config.queue_power)?,
+                    position: Position::new(&config.position_mode, None)?,
+                    template: template(name, params)?,
+                });
+                index.insert(ev.symbol.as_str(), states.len() - 1);
+                states.len() - 1
+            }
+        };
+        let st = &mut states[i];
+        match &ev.kind {
+            EventKind::Snapshot(b, a) => {
+                st.matcher
+                    .apply_snapshot(&mut st.book, b.clone(), a.clone(), ev.ts)?;
+            }
+            EventKind::Delta(b, a) => {
+                st.matcher
+                    .apply_delta(&mut st.book, b.clone(), a.clone(), ev.ts)?;
+            }
+            EventKind::Trade(p, q) => st.matcher.on_trade(*p, *q, ev.ts),
+        }
+        if !live {
+            continue;
+        }
+        let fills = st.drain();
+        result.fills += fills.len();
+        if record {
+            let symbol = &ev.symbol;
+            result
+                .trades
+                .extend(fills.into_iter().map(|f| (symbol.clone(), f)));
+        }
+        st.quote(ev.ts);
+        result.max_inventory = result.max_inventory.max(st.position.net_qty().abs());
+        let equity: f64 = states.iter().map(SymbolState::pnl).sum();
+        peak = peak.max(equity);
+        result.max_drawdown = result.max_drawdown.max(peak - equity);
+    }
+    for (symbol, i) in index {
+        let st = &states[i];
+        result
+            .positions
+            .insert(symbol.to_owned(), st.position.clone());
+        result.realized_pnl += st.position.realized_pnl();
+        result.unrealized_pnl += st
+            .book
+            .raw_mid()
+            .map_or(0.0, |m| st.position.unrealized_pnl(m));
+        result.fees += st.position.fees();
+        result.volume += st.position.volume();
+    }
+    result.total_pnl = result.realized_pnl + result.unrealized_pnl - result.fees;
+    Ok(result)
+}
@@ -0,0 +1,239 @@
+// Exchange-agnostic events, so strategy code handles one schema whatever the
+// venue or wire format. Every parser can hand its output over in this form
+// (DepthMessage.normalize, BybitBookMessage.normalize, FixMessage.normalize,
+// SbeDecoder.normalize) and so can FeedClient (FeedEvent.normalize):
+//   BookUpdate   see events.rs; exchange, symbol, kind, update_id, ts,
+//                recv_ns, bids, asks, applied
+//   Trade        a public trade: price, size, aggressor side
+//   OrderUpdate  the state of one of our orders
+//   Fill         one execution of one of our orders
+// Common fields: exchange (lowercase venue name, or the counterparty's
+// SenderCompID for FIX), symbol as the venue spells it, ts in epoch ms from
+// the exchange and recv_ns, the monotonic_ns() at which the frame was read
+// (None when the parser never saw the socket). Sides are "buy"/"sell";
+// order statuses use the FIX OrdStatus names ("new", "partially_filled",
+// "filled", "canceled", "rejected", "expired", ...), with the venue's own
+// spelling kept in venue_status.
+use pyo3::prelude::*;
+
+use crate::fees::Liquidity;
+use crate::Side;
+
+// Venue order status in the FIX vocabulary: Bybit's orderStatus values are
+// mapped, FIX names pass through and anything else becomes snake_case
+pub(crate) fn status_name(venue_status: &str) -> String {
+    match venue_status {
+        "Cancelled" | "PartiallyFilledCanceled" | "Deactivated" => "canceled".to_string(),
+        other => {
+            let mut out = String::with_capacity(other.len() + 4);
+            for (i, c) in other.chars().enumerate() {
+                if c.is_ascii_uppercase() && i > 0 {
+                    out.push('_');
+                }
+                out.push(c.to_ascii_lowercase());
+            }
+            out
+        }
+    }
+}
+
+fn side_name(side: Option<&str>) -> PyResult<Option<&'static str>> {
+    Ok(side.map(Side::parse).transpose()?.map(Side::name))
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct Trade {
+    pub exchange: String,
+    pub symbol: String,
+    pub price: f64,
+    pub size: f64,
+    // Aggressor side; None when the venue does not say
+    pub side: Option<&'static str>,
+    pub ts: Option<i64>,
+    pub recv_ns: Option<i64>,
+}
+
+#[pymethods]
+impl Trade {
+    #[new]
+    #[pyo3(signature = (exchange, symbol, price, size, side=None, ts=None, recv_ns=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        exchange: String,
+        symbol: String,
+        price: f64,
+        size: f64,
+        side: Option<&str>,
+        ts: Option<i64>,
+        recv_ns: Option<i64>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            exchange,
+            symbol,
+            price,
+            size,
+            side: side_name(side)?,
+            ts,
+            recv_ns,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Trade(exchange={:?}, symbol={:?}, price={}, size={}, side={:?}, ts={:?})",
+            self.exchange, self.symbol, self.price, self.size, self.side, self.ts
+        )
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct OrderUpdate {
+    pub exchange: String,
+    pub symbol: String,
+    pub client_id: Option<String>,
+    pub exchange_id: Option<String>,
+    pub status: String,
+    pub venue_status: String,
+    pub side: Option<&'static str>,
+    pub price: Option<f64>,
+    pub qty: Option<f64>,
+    // Cumulative filled quantity, when the venue reports it
+    pub filled_qty: Option<f64>,
+    pub ts: Option<i64>,
+    pub recv_ns: Option<i64>,
+}
+
+#[pymethods]
+impl OrderUpdate {
+    // status is normalized; venue_status defaults to status as given
+    #[new]
+    #[pyo3(signature = (exchange, symbol, status, client_id=None, exchange_id=None, side=None, price=None, qty=None, filled_qty=None, ts=None, recv_ns=None, venue_status=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        exchange: String,
+        symbol: String,
+        status: &str,
+        client_id: Option<String>,
+        exchange_id: Option<String>,
+        side: Option<&str>,
+        price: Option<f64>,
+        qty: Option<f64>,
+        filled_qty: Option<f64>,
+        ts: Option<i64>,
+        recv_ns: Option<i64>,
+        venue_status: Option<String>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            exchange,
+            symbol,
+            client_id,
+            exchange_id,
+            status: status_name(status),
+            venue_status: venue_status.unwrap_or_else(|| status.to_string()),
+            side: side_name(side)?,
+            price,
+            qty,
+            filled_qty,
+            ts,
+            recv_ns,
+        })
+    }
+
+    // No further updates will follow for this order
+    #[getter]
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "filled" | "canceled" | "rejected" | "expired" | "done_for_day"
+        )
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "OrderUpdate(exchange={:?}, symbol={:?}, client_id={:?}, status={:?}, side={:?}, price={:?}, qty={:?}, filled_qty={:?})",
+            self.exchange,
+            self.symbol,
+            self.client_id,
+            self.status,
+            self.side,
+            self.price,
+            self.qty,
+            self.filled_qty
+        )
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct Fill {
+    pub exchange: String,
+    pub symbol: String,
+    pub client_id: Option<String>,
+    pub exchange_id: Option<String>,
+    // Venue execution id, e.g. FIX ExecID
+    pub trade_id: Option<String>,
+    pub side: &'static str,
+    pub price: f64,
+    pub qty: f64,
+    // In the venue's fee currency; negative is a rebate
+    pub fee: Option<f64>,
+    // "maker" or "taker"
+    pub liquidity: Option<&'static str>,
+    pub ts: Option<i64>,
+    pub recv_ns: Option<i64>,
+}
+
+#[pymethods]
+impl Fill {
+    #[new]
+    #[pyo3(signature = (exchange, symbol, side, price, qty, client_id=None, exchange_id=None, trade_id=None, fee=None, liquidity=None, ts=None, recv_ns=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        exchange: String,
+        symbol: String,
+        side: &str,
+        price: f64,
+        qty: f64,
+        client_id: Option<String>,
+        exchange_id: Option<String>,
+        trade_id: Option<String>,
+        fee: Option<f64>,
+        liquidity: Option<&str>,
+        ts: Option<i64>,
+        recv_ns: Option<i64>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            exchange,
+            symbol,
+            client_id,
+            exchange_id,
+            trade_id,
+            side: Side::parse(side)?.name(),
+            price,
+            qty,
+            fee,
+            liquidity: liquidity
+                .map(Liquidity::parse)
+                .transpose()?
+                .map(Liquidity::name),
+            ts,
+            recv_ns,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Fill(exchange={:?}, symbol={:?}, client_id={:?}, side={:?}, price={}, qty={}, fee={:?}, liquidity={:?})",
+            self.exchange,
+            self.symbol,
+            self.client_id,
+            self.side,
+            self.price,
+            self.qty,
+            self.fee,
+            self.liquidity
+        )
+    }
+}
@@ -0,0 +1,173 @@
+// Cont-Kukanov-Stoikov order flow imbalance over the best levels:
+//   e_n = 1{Pb_n >= Pb_n-1} qb_n - 1{Pb_n <= Pb_n-1} qb_n-1
+//       - 1{Pa_n <= Pa_n-1} qa_n + 1{Pa_n >= Pa_n-1} qa_n-1
+// summed over a rolling window of events and/or milliseconds.
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+use crate::state::{self, persist_fields, Stateful};
+use crate::L2Book;
+
+type Top = (f64, f64, f64, f64);
+
+#[pyclass]
+#[derive(Clone)]
+pub struct OfiCalculator {
+    window_events: Option<usize>,
+    window_ms: Option<i64>,
+    prev: Option<Top>,
+    events: VecDeque<(i64, f64)>,
+    sum: f64,
+    last_ts: i64,
+    clock: Option<Clock>,
+}
+
+persist_fields!(OfiCalculator {
+    window_events,
+    window_ms,
+    prev,
+    events,
+    sum,
+    last_ts,
+    clock
+});
+
+impl Stateful for OfiCalculator {
+    const NAME: &'static str = "OfiCalculator";
+}
+
+impl OfiCalculator {
+    fn event(prev: Top, cur: Top) -> f64 {
+        let (pb0, qb0, pa0, qa0) = prev;
+        let (pb, qb, pa, qa) = cur;
+        let mut e = 0.0;
+        if pb >= pb0 {
+            e += qb;
+        }
+        if pb <= pb0 {
+            e -= qb0;
+        }
+        if pa <= pa0 {
+            e -= qa;
+        }
+        if pa >= pa0 {
+            e += qa0;
+        }
+        e
+    }
+
+    fn evict(&mut self) {
+        if let Some(n) = self.window_events {
+            while self.events.len() > n {
+                if let Some((_, e)) = self.events.pop_front() {
+                    self.sum -= e;
+                }
+            }
+        }
+        if let Some(ms) = self.window_ms {
+            let cutoff = self.last_ts - ms;
+            while self.events.front().is_some_and(|(ts, _)| *ts <= cutoff) {
+                if let Some((_, e)) = self.events.pop_front() {
+                    self.sum -= e;
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, cur: Top, ts: Option<i64>) -> Option<f64> {
+        let prev = self.prev.replace(cur)?;
+        let e = Self::event(prev, cur);
+        self.last_ts = clock::resolve(ts, &self.clock).unwrap_or(self.last_ts);
+        self.events.push_back((self.last_ts, e));
+        self.sum += e;
+        self.evict();
+        Some(e)
+    }
+}
+
+#[pymethods]
+impl OfiCalculator {
+    // window_events bounds the window by update count, window_ms by the
+    // timestamps passed to update() (or the clock's time when omitted); at
+    // least one is required
+    #[new]
+    #[pyo3(signature = (window_events=Some(100), window_ms=None, clock=None))]
+    pub fn new(
+        window_events: Option<usize>,
+        window_ms: Option<i64>,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if window_events.is_none() && window_ms.is_none() {
+            return Err(PyValueError::new_err(
+                "either window_events or window_ms must be set",
+            ));
+        }
+        Ok(Self {
+            window_events,
+            window_ms,
+            prev: None,
+            events: VecDeque::new(),
+            sum: 0.0,
+            last_ts: 0,
+            clock,
+        })
+    }
+
+    // Feed the current book state; returns this update's OFI contribution
+    // (None for the first state or a one-sided book)
+    #[pyo3(signature = (book, ts=None))]
+    pub fn update(&mut self, book: &L2Book, ts: Option<i64>) -> Option<f64> {
+        let ((pb, qb), (pa, qa)) = (book.best_bid()?, book.best_ask()?);
+        self.push((pb, qb, pa, qa), ts)
+    }
+
+    #[pyo3(signature = (bid_price, bid_size, ask_price, ask_size, ts=None))]
+    pub fn update_top(
+        &mut self,
+        bid_price: f64,
+        bid_size: f64,
+        ask_price: f64,
+        ask_size: f64,
+        ts: Option<i64>,
+    ) -> Option<f64> {
+        self.push((bid_price, bid_size, ask_price, ask_size), ts)
+    }
+
+    // Rolling OFI sum
+    pub fn ofi(&self) -> f64 {
+        self.sum
+    }
+
+    // OFI scaled by the average top-of-book depth of the last state
+    pub fn normalized_ofi(&self) -> Option<f64> {
+        let (_, qb, _, qa) = self.prev?;
+        let depth = (qb + qa) / 2.0;
+        (depth > 0.0).then(|| self.sum / depth)
+    }
+
+    pub fn reset(&mut self) {
+        self.prev = None;
+        self.events.clear();
+        self.sum = 0.0;
+    }
+
+    fn __len__(&self) -> usize {
+        self.events.len()
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
@@ -0,0 +1,245 @@
+// Reconciles resting quotes with a freshly computed ladder using as few
+// requests as possible. Per side, both lists are taken best first and:
+//   1. a resting order whose price matches a target (within
+//      price_tolerance) keeps its place; its size is amended when it is off
+//      by more than size_tolerance,
+//   2. the remaining orders and targets are paired best first and each
+//      order is moved with one price amend,
+//   3. whatever is left over is cancelled or placed.
+// Exchange constraints turn amends into cancel + place: amend_price=False,
+// amend_size=False, or amend_size_up=False for venues where a size increase
+// loses queue priority or is not allowed. Cancels come first in the output
+// (they free margin and never fail on rate), then amends, then places.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::orders::OrderManager;
+use crate::{Levels, Side};
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct OrderAction {
+    // "amend_price", "amend_size", "cancel" or "place"
+    pub action: &'static str,
+    pub side: &'static str,
+    // Resting order to amend or cancel; None for places
+    pub client_id: Option<String>,
+    // Target price and size after the action; a price amend may carry a new
+    // size too. For cancels, the resting order's.
+    pub price: f64,
+    pub qty: f64,
+}
+
+#[pymethods]
+impl OrderAction {
+    fn __repr__(&self) -> String {
+        format!(
+            "OrderAction(action={:?}, side={:?}, client_id={:?}, price={:?}, qty={:?})",
+            self.action, self.side, self.client_id, self.price, self.qty
+        )
+    }
+}
+
+struct Resting {
+    client_id: String,
+    price: f64,
+    qty: f64,
+}
+
+#[pyclass(frozen)]
+pub struct OrderDiff {
+    price_tolerance: f64,
+    size_tolerance: f64,
+    amend_price: bool,
+    amend_size: bool,
+    amend_size_up: bool,
+}
+
+impl OrderDiff {
+    fn action(
+        action: &'static str,
+        side: Side,
+        id: Option<&str>,
+        price: f64,
+        qty: f64,
+    ) -> OrderAction {
+        OrderAction {
+            action,
+            side: side.name(),
+            client_id: id.map(str::to_owned),
+            price,
+            qty,
+        }
+    }
+
+    // Size after amending `old` towards `new`: unchanged within
+    // size_tolerance, `new` if the venue allows that amend, else None
+    fn resized(&self, old: f64, new: f64) -> Option<f64> {
+        if (old - new).abs() <= self.size_tolerance {
+            Some(old)
+        } else {
+            (self.amend_size && (self.amend_size_up || new <= old)).then_some(new)
+        }
+    }
+
+    fn side(
+        &self,
+        side: Side,
+        mut resting: Vec<Resting>,
+        targets: &Levels,
+        out: &mut [Vec<OrderAction>; 3],
+    ) {
+        let [cancels, amends, places] = out;
+        // Best first: bids descending, asks ascending
+        resting.sort_by(|a, b| match side {
+            Side::Bid => b.price.total_cmp(&a.price),
+            Side::Ask => a.price.total_cmp(&b.price),
+        });
+        let mut open: Vec<Option<Resting>> = resting.into_iter().map(Some).collect();
+        let mut unmatched: Vec<(f64, f64)> = Vec::new();
+        for &(price, qty) in targets {
+            let hit = open.iter_mut().find(|r| {
+                r.as_ref()
+                    .is_some_and(|r| (r.price - price).abs() <= self.price_tolerance)
+            });
+            let Some(r) = hit.and_then(Option::take) else {
+                unmatched.push((price, qty));
+                continue;
+            };
+            match self.resized(r.qty, qty) {
+                Some(q) if q == r.qty => {}
+                Some(q) => amends.push(Self::action(
+                    "amend_size",
+                    side,
+                    Some(&r.client_id),
+                    r.price,
+                    q,
+                )),
+                None => {
+                    cancels.push(Self::action(
+                        "cancel",
+                        side,
+                        Some(&r.client_id),
+                        r.price,
+                        r.qty,
+                    ));
+                    places.push(Self::action("place", side, None, price, qty));
+                }
+            }
+        }
+        let mut left = open.into_iter().flatten();
+        for (price, qty) in unmatched {
+            let r = if self.amend_price { left.next() } else { None };
+            match r.map(|r| (self.resized(r.qty, qty), r)) {
+                Some((Some(q), r)) => amends.push(Self::action(
+                    "amend_price",
+                    side,
+                    Some(&r.client_id),
+                    price,
+                    q,
+                )),
+                Some((None, r)) => {
+                    cancels.push(Self::action(
+                        "cancel",
+                        side,
+                        Some(&r.client_id),
+                        r.price,
+                        r.qty,
+                    ));
+                    places.push(Self::action("place", side, None, price, qty));
+                }
+                None => places.push(Self::action("place", side, None, price, qty)),
+            }
+        }
+        for r in left {
+            cancels.push(Self::action(
+                "cancel",
+                side,
+                Some(&r.client_id),
+                r.price,
+                r.qty,
+            ));
+        }
+    }
+
+    fn run(
+        &self,
+        resting: Vec<(String, Side, f64, f64)>,
+        bids: &Levels,
+        asks: &Levels,
+    ) -> Vec<OrderAction> {
+        let mut out = [Vec::new(), Vec::new(), Vec::new()];
+        for (side, targets) in [(Side::Bid, bids), (Side::Ask, asks)] {
+            let orders = resting
+                .iter()
+                .filter(|(_, s, _, _)| *s == side)
+                .map(|(id, _, price, qty)| Resting {
+                    client_id: id.clone(),
+                    price: *price,
+                    qty: *qty,
+                })
+                .collect();
+            self.side(side, orders, targets, &mut out);
+        }
+        out.into_iter().flatten().collect()
+    }
+}
+
+#[pymethods]
+impl OrderDiff {
+    #[new]
+    #[pyo3(signature = (price_tolerance=0.0, size_tolerance=0.0, amend_price=true, amend_size=true, amend_size_up=true))]
+    pub fn new(
+        price_tolerance: f64,
+        size_tolerance: f64,
+        amend_price: bool,
+        amend_size: bool,
+        amend_size_up: bool,
+    ) -> PyResult<Self> {
+        if price_tolerance < 0.0 || size_tolerance < 0.0 {
+            return Err(PyValueError::new_err("tolerances must be non-negative"));
+        }
+        // Leave room for float noise in "same price"
+        Ok(Self {
+            price_tolerance: price_tolerance + 1e-9,
+            size_tolerance: size_tolerance + 1e-12,
+            amend_price,
+            amend_size,
+            amend_size_up,
+        })
+    }
+
+    // resting: [(client_id, side, price, qty)] with qty the remaining size;
+    // bids/asks: target ladder as [(price, size)], e.g. from LadderGenerator
+    pub fn diff(
+        &self,
+        resting: Vec<(String, String, f64, f64)>,
+        bids: Levels,
+        asks: Levels,
+    ) -> PyResult<Vec<OrderAction>> {
+        let resting = resting
+            .into_iter()
+            .map(|(id, side, price, qty)| Ok((id, Side::parse(&side)?, price, qty)))
+            .collect::<PyResult<_>>()?;
+        Ok(self.run(resting, &bids, &asks))
+    }
+
+    // diff() against the symbol's live orders in an OrderManager; orders
+    // with a cancel in flight are left alone
+    pub fn diff_orders(
+        &self,
+        orders: &OrderManager,
+        symbol: &str,
+        bids: Levels,
+        asks: Levels,
+    ) -> PyResult<Vec<OrderAction>> {
+        let mut resting = Vec::new();
+        for o in orders.open_orders(Some(symbol), None, None)? {
+            if !o.cancel_pending {
+                let side = Side::parse(o.side)?;
+                resting.push((o.client_id, side, o.price, o.qty - o.filled));
+            }
+        }
+        Ok(self.run(resting, &bids, &asks))
+    }
+}
@@ -0,0 +1,431 @@
+// Client order state machine:
+//   NEW -> ACKED -> PARTIALLY_FILLED -> FILLED
+//   NEW -> REJECTED, and any live state -> CANCELED
+// A fill may arrive before its ack (implicit ack), and late or duplicate acks
+// and cancel confirmations are tolerated; anything else raises
+// InvalidTransitionError. Submits and cancels are tracked as in-flight until
+// the exchange answers, so timed_out() can report lost requests.
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::metrics::NATIVE;
+use crate::{InvalidTransitionError, Side};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum OrderState {
+    New,
+    Acked,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Rejected,
+}
+
+impl OrderState {
+    fn name(self) -> &'static str {
+        match self {
+            Self::New => "NEW",
+            Self::Acked => "ACKED",
+            Self::PartiallyFilled => "PARTIALLY_FILLED",
+            Self::Filled => "FILLED",
+            Self::Canceled => "CANCELED",
+            Self::Rejected => "REJECTED",
+        }
+    }
+
+    fn is_live(self) -> bool {
+        matches!(self, Self::New | Self::Acked | Self::PartiallyFilled)
+    }
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub struct OrderInfo {
+    pub client_id: String,
+    pub symbol: String,
+    pub side: &'static str,
+    pub price: f64,
+    pub qty: f64,
+    pub filled: f64,
+    pub avg_fill_price: f64,
+    pub state: &'static str,
+    pub exchange_id: Option<String>,
+    pub cancel_pending: bool,
+    pub created_ms: i64,
+    pub updated_ms: i64,
+}
+
+#[pymethods]
+impl OrderInfo {
+    #[getter]
+    fn remaining(&self) -> f64 {
+        self.qty - self.filled
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "OrderInfo(client_id={:?}, symbol={:?}, side={:?}, price={:?}, qty={:?}, filled={:?}, state={:?})",
+            self.client_id, self.symbol, self.side, self.price, self.qty, self.filled, self.state
+        )
+    }
+}
+
+struct Order {
+    symbol: String,
+    side: Side,
+    price: f64,
+    qty: f64,
+    filled: f64,
+    notional: f64,
+    state: OrderState,
+    exchange_id: Option<String>,
+    // Send time of the request still awaiting an answer
+    submit_sent: Option<i64>,
+    cancel_sent: Option<i64>,
+    created_ms: i64,
+    updated_ms: i64,
+    // Submission order, for stable listing
+    seq: u64,
+}
+
+#[pyclass]
+pub struct OrderManager {
+    #[pyo3(get, set)]
+    pub ack_timeout_ms: i64,
+    orders: HashMap<String, Order>,
+    next_seq: u64,
+}
+
+impl OrderManager {
+    fn get_mut(&mut self, client_id: &str) -> PyResult<&mut Order> {
+        self.orders
+            .get_mut(client_id)
+            .ok_or_else(|| PyKeyError::new_err(client_id.to_owned()))
+    }
+
+    // Live orders on one symbol: (client_id, side, price)
+    pub(crate) fn live_on<'a>(
+        &'a self,
+        symbol: &'a str,
+    ) -> impl Iterator<Item = (&'a str, Side, f64)> + 'a {
+        self.orders
+            .iter()
+            .filter(move |(_, o)| o.state.is_live() && o.symbol == symbol)
+            .map(|(id, o)| (id.as_str(), o.side, o.price))
+    }
+
+    fn info(client_id: &str, o: &Order) -> OrderInfo {
+        OrderInfo {
+            client_id: client_id.to_owned(),
+            symbol: o.symbol.clone(),
+            side: o.side.name(),
+            price: o.price,
+            qty: o.qty,
+            filled: o.filled,
+            avg_fill_price: if o.filled > 0.0 {
+                o.notional / o.filled
+            } else {
+                0.0
+            },
+            state: o.state.name(),
+            exchange_id: o.exchange_id.clone(),
+            cancel_pending: o.cancel_sent.is_some(),
+            created_ms: o.created_ms,
+            updated_ms: o.updated_ms,
+        }
+    }
+}
+
+fn invalid(client_id: &str, event: &str, state: OrderState) -> PyErr {
+    InvalidTransitionError::new_err(format!(
+        "order {}: {} not allowed in state {}",
+        client_id,
+        event,
+        state.name()
+    ))
+}
+
+#[pymethods]
+impl OrderManager {
+    #[new]
+    #[pyo3(signature = (ack_timeout_ms=5000))]
+    pub fn new(ack_timeout_ms: i64) -> Self {
+        Self {
+            ack_timeout_ms,
+            orders: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    pub fn submit(
+        &mut self,
+        client_id: String,
+        symbol: String,
+        side: &str,
+        price: f64,
+        qty: f64,
+        ts_ms: i64,
+    ) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        if qty <= 0.0 {
+            return Err(PyValueError::new_err("order qty must be positive"));
+        }
+        if self.orders.contains_key(&client_id) {
+            return Err(PyValueError::new_err(format!(
+                "duplicate client order id {}",
+                client_id
+            )));
+        }
+        self.orders.insert(
+            client_id,
+            Order {
+                symbol,
+                side,
+                price,
+                qty,
+                filled: 0.0,
+                notional: 0.0,
+                state: OrderState::New,
+                exchange_id: None,
+                submit_sent: Some(ts_ms),
+                cancel_sent: None,
+                created_ms: ts_ms,
+                updated_ms: ts_ms,
+                seq: self.next_seq,
+            },
+        );
+        self.next_seq += 1;
+        NATIVE.orders_submitted.inc(1.0);
+        Ok(())
+    }
+
+    // Returns False for a late or duplicate ack (already acked or filled)
+    #[pyo3(signature = (client_id, ts_ms, exchange_id=None))]
+    pub fn on_ack(
+        &mut self,
+        client_id: &str,
+        ts_ms: i64,
+        exchange_id: Option<String>,
+    ) -> PyResult<bool> {
+        let o = self.get_mut(client_id)?;
+        if matches!(o.state, OrderState::Canceled | OrderState::Rejected) {
+            return Err(invalid(client_id, "ack", o.state));
+        }
+        if exchange_id.is_some() {
+            o.exchange_id = exchange_id;
+        }
+        o.submit_sent = None;
+        if o.state != OrderState::New {
+            return Ok(false);
+        }
+        o.state = OrderState::Acked;
+        o.updated_ms = ts_ms;
+        NATIVE.orders_acked.inc(1.0);
+        NATIVE
+            .order_ack_latency
+            .observe((ts_ms - o.created_ms) as f64);
+        Ok(true)
+    }
+
+    pub fn on_reject(&mut self, client_id: &str, ts_ms: i64) -> PyResult<()> {
+        let o = self.get_mut(client_id)?;
+        if o.state != OrderState::New {
+            return Err(invalid(client_id, "reject", o.state));
+        }
+        o.state = OrderState::Rejected;
+        o.submit_sent = None;
+        o.cancel_sent = None;
+        o.updated_ms = ts_ms;
+        NATIVE.orders_rejected.inc(1.0);
+        Ok(())
+    }
+
+    // Returns the new state name
+    pub fn on_fill(
+        &mut self,
+        client_id: &str,
+        qty: f64,
+        price: f64,
+        ts_ms: i64,
+    ) -> PyResult<&'static str> {
+        let o = self.get_mut(client_id)?;
+        if !o.state.is_live() {
+            return Err(invalid(client_id, "fill", o.state));
+        }
+        if qty <= 0.0 {
+            return Err(PyValueError::new_err("fill qty must be positive"));
+        }
+        let remaining = o.qty - o.filled;
+        if qty > remaining + 1e-9 {
+            return Err(PyValueError::new_err(format!(
+                "order {}: fill {} exceeds remaining {}",
+                client_id, qty, remaining
+            )));
+        }
+        if o.state == OrderState::New {
+            // Implicit ack
+            NATIVE
+                .order_ack_latency
+                .observe((ts_ms - o.created_ms) as f64);
+        }
+        NATIVE.orders_filled.inc(1.0);
+        o.filled += qty;
+        o.notional += qty * price;
+        o.submit_sent = None;
+        o.updated_ms = ts_ms;
+        if o.qty - o.filled <= 1e-9 {
+            o.state = OrderState::Filled;
+            o.cancel_sent = None;
+        } else {
+            o.state = OrderState::PartiallyFilled;
+        }
+        Ok(o.state.name())
+    }
+
+    // Returns False if a cancel is already in flight
+    pub fn request_cancel(&mut self, client_id: &str, ts_ms: i64) -> PyResult<bool> {
+        let o = self.get_mut(client_id)?;
+        if !o.state.is_live() {
+            return Err(invalid(client_id, "cancel request", o.state));
+        }
+        if o.cancel_sent.is_some() {
+            return Ok(false);
+        }
+        o.cancel_sent = Some(ts_ms);
+        Ok(true)
+    }
+
+    // Solicited or unsolicited (IOC expiry, self-trade prevention) cancel.
+    // Returns False for a duplicate confirmation.
+    pub fn on_canceled(&mut self, client_id: &str, ts_ms: i64) -> PyResult<bool> {
+        let o = self.get_mut(client_id)?;
+        match o.state {
+            OrderState::Canceled => Ok(false),
+            state if state.is_live() => {
+                o.state = OrderState::Canceled;
+                o.submit_sent = None;
+                o.cancel_sent = None;
+                o.updated_ms = ts_ms;
+                NATIVE.orders_canceled.inc(1.0);
+                Ok(true)
+            }
+            state => Err(invalid(client_id, "cancel", state)),
+        }
+    }
+
+    // Cancel refused (usually already filled); the order keeps its state
+    pub fn on_cancel_reject(&mut self, client_id: &str, ts_ms: i64) -> PyResult<()> {
+        let o = self.get_mut(client_id)?;
+        o.cancel_sent = None;
+        o.updated_ms = ts_ms;
+        Ok(())
+    }
+
+    // Price and/or qty changed by an accepted amend. The new qty must stay
+    // above what is already filled.
+    #[pyo3(signature = (client_id, ts_ms, price=None, qty=None))]
+    pub fn on_amended(
+        &mut self,
+        client_id: &str,
+        ts_ms: i64,
+        price: Option<f64>,
+        qty: Option<f64>,
+    ) -> PyResult<()> {
+        let o = self.get_mut(client_id)?;
+        if !o.state.is_live() {
+            return Err(invalid(client_id, "amend", o.state));
+        }
+        if let Some(qty) = qty {
+            if qty <= o.filled + 1e-9 {
+                return Err(PyValueError::new_err(format!(
+                    "order {}: amended qty {} does not exceed filled {}",
+                    client_id, qty, o.filled
+                )));
+            }
+            o.qty = qty;
+        }
+        if let Some(price) = price {
+            o.price = price;
+        }
+        o.updated_ms = ts_ms;
+        Ok(())
+    }
+
+    pub fn order(&self, client_id: &str) -> Option<OrderInfo> {
+        self.orders.get(client_id).map(|o| Self::info(client_id, o))
+    }
+
+    pub fn state(&self, client_id: &str) -> PyResult<&'static str> {
+        self.orders
+            .get(client_id)
+            .map(|o| o.state.name())
+            .ok_or_else(|| PyKeyError::new_err(client_id.to_owned()))
+    }
+
+    // Live orders, optionally filtered, in submission order
+    #[pyo3(signature = (symbol=None, side=None, price=None))]
+    pub fn open_orders(
+        &self,
+        symbol: Option<&str>,
+        side: Option<&str>,
+        price: Option<f64>,
+    ) -> PyResult<Vec<OrderInfo>> {
+        let side = side.map(Side::parse).transpose()?;
+        let mut open: Vec<(&String, &Order)> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| {
+                o.state.is_live()
+                    && symbol.is_none_or(|s| o.symbol == s)
+                    && side.is_none_or(|s| o.side == s)
+                    && price.is_none_or(|p| (o.price - p).abs() < 1e-9)
+            })
+            .collect();
+        open.sort_by_key(|(_, o)| o.seq);
+        Ok(open.into_iter().map(|(id, o)| Self::info(id, o)).collect())
+    }
+
+    // In-flight requests with no answer after ack_timeout_ms:
+    // [(client_id, "submit" | "cancel", sent_ms)]
+    pub fn timed_out(&self, now_ms: i64) -> Vec<(String, &'static str, i64)> {
+        let mut out = Vec::new();
+        for (id, o) in &self.orders {
+            for (kind, sent) in [("submit", o.submit_sent), ("cancel", o.cancel_sent)] {
+                if let Some(sent) = sent {
+                    if now_ms - sent >= self.ack_timeout_ms {
+                        out.push((o.seq, id.clone(), kind, sent));
+                    }
+                }
+            }
+        }
+        out.sort_by_key(|e| e.0);
+        out.into_iter()
+            .map(|(_, id, kind, sent)| (id, kind, sent))
+            .collect()
+    }
+
+    #[getter]
+    pub fn in_flight(&self) -> usize {
+        self.orders
+            .values()
+            .map(|o| o.submit_sent.is_some() as usize + o.cancel_sent.is_some() as usize)
+            .sum()
+    }
+
+    // Forget finished orders; returns how many were dropped
+    pub fn purge_terminal(&mut self) -> usize {
+        let before = self.orders.len();
+        self.orders.retain(|_, o| o.state.is_live());
+        before - self.orders.len()
+    }
+
+    fn __len__(&self) -> usize {
+        self.orders.len()
+    }
+
+    fn __contains__(&self, client_id: &str) -> bool {
+        self.orders.contains_key(client_id)
+    }
+}
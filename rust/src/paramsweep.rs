@@ -0,0 +1,155 @@
+// Parameter grid search over a Backtester's data. Each grid point is a
+// native template run (see native.rs): no Python callbacks, so the runs go
+// off the GIL and across a rayon pool, one run per task. The grid is the
+// cartesian product of {parameter: [values]}, expanded in sorted key order
+// with the last key varying fastest. Results come back as a column dict,
+// one row per grid point in that order, ready for pandas or polars.
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+
+use crate::backtest::{Backtester, Event};
+use crate::native::{self, NativeConfig, NativeResult};
+
+// Grid points in order, each as {parameter: value}
+pub(crate) fn expand(grid: &HashMap<String, Vec<f64>>) -> Vec<HashMap<String, f64>> {
+    let mut keys: Vec<&String> = grid.keys().collect();
+    keys.sort();
+    let mut points = vec![HashMap::new()];
+    for key in keys {
+        points = points
+            .into_iter()
+            .flat_map(|p| {
+                grid[key].iter().map(move |v| {
+                    let mut p = p.clone();
+                    p.insert(key.clone(), *v);
+                    p
+                })
+            })
+            .collect();
+    }
+    points
+}
+
+// A pool of `threads` workers; None means rayon's global pool
+pub(crate) fn thread_pool(threads: Option<usize>) -> PyResult<Option<rayon::ThreadPool>> {
+    match threads {
+        Some(0) => Err(PyValueError::new_err("threads must be positive")),
+        Some(n) => rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map(Some)
+            .map_err(|e| PyValueError::new_err(e.to_string())),
+        None => Ok(None),
+    }
+}
+
+// One native run per grid point, in parallel; call without the GIL
+pub(crate) fn run_grid(
+    pool: Option<&rayon::ThreadPool>,
+    warmup: &[&Event],
+    events: &[&Event],
+    config: &NativeConfig,
+    strategy: &str,
+    points: &[HashMap<String, f64>],
+) -> PyResult<Vec<NativeResult>> {
+    let run = || {
+        points
+            .par_iter()
+            .map(|p| native::replay(warmup, events, config, strategy, p, false))
+            .collect()
+    };
+    match pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    }
+}
+
+#[pyclass]
+pub struct SweepRunner {
+    // None runs on rayon's global pool (one thread per core)
+    pool: Option<rayon::ThreadPool>,
+    threads: Option<usize>,
+}
+
+#[pymethods]
+impl SweepRunner {
+    #[new]
+    #[pyo3(signature = (threads=None))]
+    pub fn new(threads: Option<usize>) -> PyResult<Self> {
+        let pool = thread_pool(threads)?;
+        Ok(Self { pool, threads })
+    }
+
+    // Runs template `strategy` once per grid point over the data loaded into
+    // `backtester`, with its fee model, queue_power and position_mode.
+    // Returns {parameter or metric: [value per grid point]}; metrics are
+    // total_pnl, realized_pnl, unrealized_pnl, fees, volume, fills,
+    // max_drawdown and max_inventory.
+    pub fn run<'py>(
+        &self,
+        py: Python<'py>,
+        backtester: PyRef<'py, Backtester>,
+        strategy: &str,
+        grid: HashMap<String, Vec<f64>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let points = expand(&grid);
+        // Bad names and values fail here rather than inside the pool
+        for p in &points {
+            native::template(strategy, p)?;
+        }
+        let bt: &Backtester = &backtester;
+        let config = bt.native_config();
+        let results = py.allow_threads(|| {
+            let events = bt.sorted_events();
+            run_grid(self.pool.as_ref(), &[], &events, &config, strategy, &points)
+        })?;
+
+        let d = PyDict::new(py);
+        let mut keys: Vec<&String> = grid.keys().collect();
+        keys.sort();
+        for key in keys {
+            d.set_item(key, points.iter().map(|p| p[key]).collect::<Vec<_>>())?;
+        }
+        for (name, values) in [
+            (
+                "total_pnl",
+                results.iter().map(|r| r.total_pnl).collect::<Vec<_>>(),
+            ),
+            (
+                "realized_pnl",
+                results.iter().map(|r| r.realized_pnl).collect(),
+            ),
+            (
+                "unrealized_pnl",
+                results.iter().map(|r| r.unrealized_pnl).collect(),
+            ),
+            ("fees", results.iter().map(|r| r.fees).collect()),
+            ("volume", results.iter().map(|r| r.volume).collect()),
+            (
+                "max_drawdown",
+                results.iter().map(|r| r.max_drawdown).collect(),
+            ),
+            (
+                "max_inventory",
+                results.iter().map(|r| r.max_inventory).collect(),
+            ),
+        ] {
+            d.set_item(name, values)?;
+        }
+        d.set_item("fills", results.iter().map(|r| r.fills).collect::<Vec<_>>())?;
+        Ok(d)
+    }
+
+    #[getter]
+    pub fn threads(&self) -> usize {
+        self.threads.unwrap_or_else(rayon::current_num_threads)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SweepRunner(threads={})", self.threads())
+    }
+}
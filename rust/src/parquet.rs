@@ -0,0 +1,619 @@
+// Minimal Parquet writer: flat schema of REQUIRED/OPTIONAL INT64, DOUBLE and
+// UTF8 columns, PLAIN encoding, uncompressed v1 data pages, one page per
+// column chunk. That is all the recorder needs, and keeps the extension free
+// of the arrow/parquet dependency tree. Metadata is Thrift compact protocol
+// as defined in parquet.thrift.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8] = b"PAR1";
+
+// parquet.thrift enums
+const TYPE_INT64: i32 = 2;
+const TYPE_DOUBLE: i32 = 5;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const REQUIRED: i32 = 0;
+const OPTIONAL: i32 = 1;
+const CONVERTED_UTF8: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const PAGE_DATA: i32 = 0;
+
+pub enum Values {
+    I64(Vec<i64>),
+    F64(Vec<f64>),
+    Str(Vec<String>),
+}
+
+// One column of a buffered row group. Optional columns keep a presence flag
+// per row and only store the present values.
+pub struct Column {
+    pub name: &'static str,
+    pub optional: bool,
+    pub values: Values,
+    pub present: Vec<bool>,
+}
+
+impl Column {
+    pub fn new(name: &'static str, values: Values) -> Self {
+        Self {
+            name,
+            optional: false,
+            values,
+            present: Vec::new(),
+        }
+    }
+
+    pub fn optional(name: &'static str, values: Values) -> Self {
+        Self {
+            optional: true,
+            ..Self::new(name, values)
+        }
+    }
+
+    fn physical_type(&self) -> i32 {
+        match self.values {
+            Values::I64(_) => TYPE_INT64,
+            Values::F64(_) => TYPE_DOUBLE,
+            Values::Str(_) => TYPE_BYTE_ARRAY,
+        }
+    }
+
+    pub fn push_i64(&mut self, v: Option<i64>) {
+        if let Values::I64(vals) = &mut self.values {
+            vals.extend(v);
+        }
+        self.mark(v.is_some());
+    }
+
+    pub fn push_f64(&mut self, v: f64) {
+        if let Values::F64(vals) = &mut self.values {
+            vals.push(v);
+        }
+        self.mark(true);
+    }
+
+    pub fn push_str(&mut self, v: Option<&str>) {
+        if let Values::Str(vals) = &mut self.values {
+            vals.extend(v.map(str::to_owned));
+        }
+        self.mark(v.is_some());
+    }
+
+    fn mark(&mut self, present: bool) {
+        if self.optional {
+            self.present.push(present);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match &mut self.values {
+            Values::I64(v) => v.clear(),
+            Values::F64(v) => v.clear(),
+            Values::Str(v) => v.clear(),
+        }
+        self.present.clear();
+    }
+
+    // PLAIN values, preceded by RLE definition levels for optional columns
+    fn page_data(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if self.optional {
+            let levels = rle_bits(&self.present);
+            out.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+            out.extend_from_slice(&levels);
+        }
+        match &self.values {
+            Values::I64(v) => v
+                .iter()
+                .for_each(|x| out.extend_from_slice(&x.to_le_bytes())),
+            Values::F64(v) => v
+                .iter()
+                .for_each(|x| out.extend_from_slice(&x.to_le_bytes())),
+            Values::Str(v) => v.iter().for_each(|s| {
+                out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                out.extend_from_slice(s.as_bytes());
+            }),
+        }
+        out
+    }
+}
+
+// RLE/bit-packing hybrid with bit width 1, using RLE runs only
+fn rle_bits(bits: &[bool]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bits.len() {
+        let run = bits[i..].iter().take_while(|b| **b == bits[i]).count();
+        varint(&mut out, (run as u64) << 1);
+        out.push(bits[i] as u8);
+        i += run;
+    }
+    out
+}
+
+fn varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+// Thrift compact protocol, just the parts parquet metadata uses
+struct Thrift {
+    buf: Vec<u8>,
+    // Last field id per open struct
+    last: Vec<i16>,
+}
+
+const T_I32: u8 = 5;
+const T_I64: u8 = 6;
+const T_BINARY: u8 = 8;
+const T_LIST: u8 = 9;
+const T_STRUCT: u8 = 12;
+
+impl Thrift {
+    fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            last: vec![0],
+        }
+    }
+
+    fn zigzag(&mut self, v: i64) {
+        varint(&mut self.buf, ((v << 1) ^ (v >> 63)) as u64);
+    }
+
+    fn field(&mut self, id: i16, ty: u8) {
+        let last = self.last.last_mut().expect("open struct");
+        let delta = id - *last;
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | ty);
+        } else {
+            self.buf.push(ty);
+            let id = id as i64;
+            varint(&mut self.buf, ((id << 1) ^ (id >> 63)) as u64);
+        }
+        *last = id;
+    }
+
+    fn i32(&mut self, id: i16, v: i32) {
+        self.field(id, T_I32);
+        self.zigzag(v as i64);
+    }
+
+    fn i64(&mut self, id: i16, v: i64) {
+        self.field(id, T_I64);
+        self.zigzag(v);
+    }
+
+    fn string(&mut self, s: &str) {
+        varint(&mut self.buf, s.len() as u64);
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn string_field(&mut self, id: i16, s: &str) {
+        self.field(id, T_BINARY);
+        self.string(s);
+    }
+
+    fn list(&mut self, id: i16, elem: u8, n: usize) {
+        self.field(id, T_LIST);
+        if n < 15 {
+            self.buf.push(((n as u8) << 4) | elem);
+        } else {
+            self.buf.push(0xF0 | elem);
+            varint(&mut self.buf, n as u64);
+        }
+    }
+
+    fn struct_field(&mut self, id: i16) {
+        self.field(id, T_STRUCT);
+        self.begin();
+    }
+
+    // Start a struct that is a list element (or the top level)
+    fn begin(&mut self) {
+        self.last.push(0);
+    }
+
+    fn end(&mut self) {
+        self.buf.push(0);
+        self.last.pop();
+    }
+}
+
+struct ChunkMeta {
+    physical_type: i32,
+    name: &'static str,
+    num_values: i64,
+    size: i64,
+    offset: i64,
+}
+
+struct RowGroupMeta {
+    chunks: Vec<ChunkMeta>,
+    num_rows: i64,
+}
+
+// (name, physical type, optional, utf8)
+type SchemaField = (&'static str, i32, bool, bool);
+
+pub struct ParquetWriter {
+    out: BufWriter<File>,
+    path: PathBuf,
+    offset: u64,
+    schema: Vec<SchemaField>,
+    row_groups: Vec<RowGroupMeta>,
+}
+
+impl ParquetWriter {
+    pub fn create(path: &Path, columns: &[Column]) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        let schema = columns
+            .iter()
+            .map(|c| {
+                let utf8 = matches!(c.values, Values::Str(_));
+                (c.name, c.physical_type(), c.optional, utf8)
+            })
+            .collect();
+        Ok(Self {
+            out,
+            path: path.to_owned(),
+            offset: MAGIC.len() as u64,
+            schema,
+            row_groups: Vec::new(),
+        })
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn write_row_group(&mut self, columns: &[Column], num_rows: usize) -> io::Result<()> {
+        if num_rows == 0 {
+            return Ok(());
+        }
+        let mut chunks = Vec::with_capacity(columns.len());
+        for col in columns {
+            let data = col.page_data();
+            let mut header = Thrift::new();
+            header.begin();
+            header.i32(1, PAGE_DATA);
+            header.i32(2, data.len() as i32);
+            header.i32(3, data.len() as i32);
+            header.struct_field(5);
+            header.i32(1, num_rows as i32);
+            header.i32(2, ENCODING_PLAIN);
+            header.i32(3, ENCODING_RLE);
+            header.i32(4, ENCODING_RLE);
+            header.end();
+            header.end();
+            let start = self.offset as i64;
+            self.out.write_all(&header.buf)?;
+            self.out.write_all(&data)?;
+            let size = (header.buf.len() + data.len()) as i64;
+            self.offset += size as u64;
+            chunks.push(ChunkMeta {
+                physical_type: col.physical_type(),
+                name: col.name,
+                num_values: num_rows as i64,
+                size,
+                offset: start,
+            });
+        }
+        self.row_groups.push(RowGroupMeta {
+            chunks,
+            num_rows: num_rows as i64,
+        });
+        Ok(())
+    }
+
+    // Write the footer and close the file
+    pub fn finish(mut self) -> io::Result<PathBuf> {
+        let mut t = Thrift::new();
+        t.begin();
+        t.i32(1, 1);
+        t.list(2, T_STRUCT, self.schema.len() + 1);
+        t.begin();
+        t.string_field(4, "schema");
+        t.i32(5, self.schema.len() as i32);
+        t.end();
+        for (name, ty, optional, utf8) in &self.schema {
+            t.begin();
+            t.i32(1, *ty);
+            t.i32(3, if *optional { OPTIONAL } else { REQUIRED });
+            t.string_field(4, name);
+            if *utf8 {
+                t.i32(6, CONVERTED_UTF8);
+            }
+            t.end();
+        }
+        t.i64(3, self.row_groups.iter().map(|g| g.num_rows).sum());
+        t.list(4, T_STRUCT, self.row_groups.len());
+        for group in &self.row_groups {
+            t.begin();
+            t.list(1, T_STRUCT, group.chunks.len());
+            for c in &group.chunks {
+                t.begin();
+                t.i64(2, c.offset);
+                t.struct_field(3);
+                t.i32(1, c.physical_type);
+                t.list(2, T_I32, 2);
+                t.zigzag(ENCODING_PLAIN as i64);
+                t.zigzag(ENCODING_RLE as i64);
+                t.list(3, T_BINARY, 1);
+                t.string(c.name);
+                t.i32(4, 0);
+                t.i64(5, c.num_values);
+                t.i64(6, c.size);
+                t.i64(7, c.size);
+                t.i64(9, c.offset);
+                t.end();
+                t.end();
+            }
+            t.i64(2, group.chunks.iter().map(|c| c.size).sum());
+            t.i64(3, group.num_rows);
+            t.end();
+        }
+        t.string_field(6, "mm_orderbook");
+        t.end();
+        self.out.write_all(&t.buf)?;
+        self.out.write_all(&(t.buf.len() as u32).to_le_bytes())?;
+        self.out.write_all(MAGIC)?;
+        self.out.flush()?;
+        Ok(self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Decoded Thrift compact value
+    #[derive(Debug, PartialEq)]
+    enum T {
+        Int(i64),
+        Bin(Vec<u8>),
+        List(Vec<T>),
+        Struct(Vec<(i16, T)>),
+    }
+
+    impl T {
+        fn field(&self, id: i16) -> &T {
+            let T::Struct(fields) = self else {
+                panic!("not a struct: {:?}", self)
+            };
+            &fields.iter().find(|(i, _)| *i == id).expect("field").1
+        }
+
+        fn int(&self) -> i64 {
+            match self {
+                T::Int(v) => *v,
+                _ => panic!("not an int: {:?}", self),
+            }
+        }
+
+        fn items(&self) -> &[T] {
+            match self {
+                T::List(items) => items,
+                _ => panic!("not a list: {:?}", self),
+            }
+        }
+    }
+
+    fn read_varint(buf: &[u8], pos: &mut usize) -> u64 {
+        let mut v = 0;
+        let mut shift = 0;
+        loop {
+            let b = buf[*pos];
+            *pos += 1;
+            v |= ((b & 0x7F) as u64) << shift;
+            if b < 0x80 {
+                return v;
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_zigzag(buf: &[u8], pos: &mut usize) -> i64 {
+        let v = read_varint(buf, pos);
+        (v >> 1) as i64 ^ -((v & 1) as i64)
+    }
+
+    fn read_value(buf: &[u8], pos: &mut usize, ty: u8) -> T {
+        match ty {
+            T_I32 | T_I64 => T::Int(read_zigzag(buf, pos)),
+            T_BINARY => {
+                let n = read_varint(buf, pos) as usize;
+                *pos += n;
+                T::Bin(buf[*pos - n..*pos].to_vec())
+            }
+            T_LIST => {
+                let head = buf[*pos];
+                *pos += 1;
+                let n = match head >> 4 {
+                    15 => read_varint(buf, pos) as usize,
+                    n => n as usize,
+                };
+                T::List((0..n).map(|_| read_value(buf, pos, head & 0xF)).collect())
+            }
+            T_STRUCT => read_struct(buf, pos),
+            _ => panic!("unexpected thrift type {}", ty),
+        }
+    }
+
+    fn read_struct(buf: &[u8], pos: &mut usize) -> T {
+        let mut fields = Vec::new();
+        let mut last = 0;
+        loop {
+            let b = buf[*pos];
+            *pos += 1;
+            if b == 0 {
+                return T::Struct(fields);
+            }
+            let id = match b >> 4 {
+                0 => read_zigzag(buf, pos) as i16,
+                delta => last + delta as i16,
+            };
+            last = id;
+            fields.push((id, read_value(buf, pos, b & 0xF)));
+        }
+    }
+
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("ts", Values::I64(Vec::new())),
+            Column::new("price", Values::F64(Vec::new())),
+            Column::optional("update_id", Values::I64(Vec::new())),
+            Column::optional("side", Values::Str(Vec::new())),
+        ]
+    }
+
+    fn fill(cols: &mut [Column], rows: std::ops::Range<i64>) {
+        for i in rows {
+            cols[0].push_i64(Some(i));
+            cols[1].push_f64(i as f64 * 0.5);
+            cols[2].push_i64((i % 3 != 0).then_some(i * 10));
+            cols[3].push_str((i % 2 == 0).then_some(if i % 4 == 0 { "bid" } else { "ask" }));
+        }
+    }
+
+    #[test]
+    fn definition_levels_are_rle_runs() {
+        assert!(rle_bits(&[]).is_empty());
+        assert_eq!(rle_bits(&[true, true, true]), [6, 1]);
+        assert_eq!(rle_bits(&[true, false, false]), [2, 1, 4, 0]);
+        // A run of 200 needs a two-byte varint header
+        let mut bits = vec![false; 200];
+        bits.push(true);
+        assert_eq!(rle_bits(&bits), [0x90, 0x03, 0, 2, 1]);
+    }
+
+    #[test]
+    fn thrift_uses_long_forms_for_far_fields_and_long_lists() {
+        let mut t = Thrift::new();
+        t.begin();
+        t.i32(1, -3);
+        t.i64(20, 1 << 40);
+        t.list(21, T_I32, 20);
+        (0..20).for_each(|v| t.zigzag(v));
+        t.struct_field(22);
+        t.string_field(1, "x");
+        t.end();
+        t.end();
+        let mut pos = 0;
+        let value = read_struct(&t.buf, &mut pos);
+        assert_eq!(pos, t.buf.len());
+        assert_eq!(value.field(1).int(), -3);
+        assert_eq!(value.field(20).int(), 1 << 40);
+        let list: Vec<i64> = value.field(21).items().iter().map(T::int).collect();
+        assert_eq!(list, (0..20).collect::<Vec<_>>());
+        assert_eq!(value.field(22).field(1), &T::Bin(b"x".to_vec()));
+    }
+
+    #[test]
+    fn column_chunks_tile_the_file_one_page_each() {
+        let dir = std::env::temp_dir().join(format!("mm-parquet-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pages.parquet");
+        let mut cols = columns();
+        let mut w = ParquetWriter::create(&path, &cols).unwrap();
+        fill(&mut cols, 0..5);
+        w.write_row_group(&cols, 5).unwrap();
+        cols.iter_mut().for_each(Column::clear);
+        w.write_row_group(&cols, 0).unwrap();
+        fill(&mut cols, 5..305);
+        w.write_row_group(&cols, 300).unwrap();
+        let written = w.bytes_written();
+        w.finish().unwrap();
+        let file = std::fs::read(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(file.starts_with(MAGIC) && file.ends_with(MAGIC));
+        let footer_len =
+            u32::from_le_bytes(file[file.len() - 8..file.len() - 4].try_into().unwrap());
+        let footer_start = file.len() - 8 - footer_len as usize;
+        assert_eq!(footer_start as u64, written);
+        let mut pos = footer_start;
+        let meta = read_struct(&file, &mut pos);
+        assert_eq!(pos, file.len() - 8);
+        assert_eq!(meta.field(2).items().len(), 5);
+        assert_eq!(meta.field(3).int(), 305);
+
+        // The empty row group was dropped; the rest lie back to back
+        let groups = meta.field(4).items();
+        assert_eq!(groups.len(), 2);
+        let mut expected = MAGIC.len() as i64;
+        for (group, rows) in groups.iter().zip([0..5i64, 5..305]) {
+            let n = rows.end - rows.start;
+            assert_eq!(group.field(3).int(), n);
+            let mut group_bytes = 0;
+            for chunk in group.field(1).items() {
+                let cm = chunk.field(3);
+                let offset = cm.field(9).int();
+                assert_eq!((offset, chunk.field(2).int()), (expected, expected));
+                assert_eq!(cm.field(5).int(), n);
+                let size = cm.field(7).int();
+                expected += size;
+                group_bytes += size;
+
+                // Page header plus its data fill the chunk exactly
+                let mut pos = offset as usize;
+                let header = read_struct(&file, &mut pos);
+                let data_len = header.field(3).int();
+                assert_eq!(header.field(2).int(), data_len);
+                assert_eq!(pos as i64 + data_len, offset + size);
+                assert_eq!(header.field(5).field(1).int(), n);
+                let data = &file[pos..pos + data_len as usize];
+
+                let name = match cm.field(3).items() {
+                    [T::Bin(name)] => String::from_utf8(name.clone()).unwrap(),
+                    other => panic!("bad path {:?}", other),
+                };
+                let mut at = 0;
+                let mut present: Vec<bool> = rows.clone().map(|_| true).collect();
+                if name == "update_id" || name == "side" {
+                    let len = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+                    present = rows
+                        .clone()
+                        .map(|i| {
+                            if name == "side" {
+                                i % 2 == 0
+                            } else {
+                                i % 3 != 0
+                            }
+                        })
+                        .collect();
+                    assert_eq!(&data[4..4 + len], rle_bits(&present));
+                    at = 4 + len;
+                }
+                let values = &data[at..];
+                let kept = rows
+                    .clone()
+                    .zip(&present)
+                    .filter(|(_, p)| **p)
+                    .map(|(i, _)| i);
+                let mut want = Vec::new();
+                for i in kept {
+                    match name.as_str() {
+                        "ts" => want.extend_from_slice(&i.to_le_bytes()),
+                        "price" => want.extend_from_slice(&(i as f64 * 0.5).to_le_bytes()),
+                        "update_id" => want.extend_from_slice(&(i * 10).to_le_bytes()),
+                        _ => {
+                            let s = if i % 4 == 0 { "bid" } else { "ask" };
+                            want.extend_from_slice(&3u32.to_le_bytes());
+                            want.extend_from_slice(s.as_bytes());
+                        }
+                    }
+                }
+                assert_eq!(values, want, "{}", name);
+            }
+            assert_eq!(group.field(2).int(), group_bytes);
+        }
+        assert_eq!(expected as usize, footer_start);
+    }
+}
@@ -0,0 +1,288 @@
+// PnL attribution per symbol and time bucket (an hour by default). Every
+// fill is valued against the mid at fill time and the position is marked
+// to market on every mid, which splits PnL exactly into
+//   spread     (mid - price) * qty for buys, (price - mid) * qty for sells:
+//              the edge captured when the fill happened
+//   inventory  position * mid change between marks: what holding the
+//              inventory made or lost as the price moved
+//   fees       paid, and rebates received, as separate positive numbers
+// with spread + inventory - fees + rebates the mark-to-market PnL; once the
+// position is flat again that is the realized PnL, which each bucket also
+// reports (average cost, gross of fees) for reconciliation.
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::fees::{FeeModel, Liquidity};
+use crate::position::Position;
+use crate::Side;
+
+const HOUR_MS: i64 = 3_600_000;
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub struct PnlBucket {
+    pub symbol: String,
+    // Bucket start, ms since the epoch
+    pub start_ms: i64,
+    pub spread: f64,
+    pub inventory: f64,
+    pub fees: f64,
+    pub rebates: f64,
+    pub realized: f64,
+    pub volume: f64,
+    pub fills: u64,
+}
+
+impl PnlBucket {
+    fn add(&mut self, other: &PnlBucket) {
+        self.spread += other.spread;
+        self.inventory += other.inventory;
+        self.fees += other.fees;
+        self.rebates += other.rebates;
+        self.realized += other.realized;
+        self.volume += other.volume;
+        self.fills += other.fills;
+    }
+}
+
+#[pymethods]
+impl PnlBucket {
+    // spread + inventory - fees + rebates
+    #[getter]
+    pub fn total(&self) -> f64 {
+        self.spread + self.inventory - self.fees + self.rebates
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PnlBucket(symbol={:?}, start_ms={}, spread={}, inventory={}, fees={}, rebates={}, total={})",
+            self.symbol,
+            self.start_ms,
+            self.spread,
+            self.inventory,
+            self.fees,
+            self.rebates,
+            self.total()
+        )
+    }
+}
+
+struct Book {
+    position: Position,
+    mark: Option<f64>,
+}
+
+#[pyclass]
+pub struct PnlAttribution {
+    bucket_ms: i64,
+    fee_model: Option<FeeModel>,
+    symbols: HashMap<String, Book>,
+    buckets: BTreeMap<(String, i64), PnlBucket>,
+}
+
+impl PnlAttribution {
+    fn bucket(&mut self, symbol: &str, ts: i64) -> &mut PnlBucket {
+        let start = ts.div_euclid(self.bucket_ms) * self.bucket_ms;
+        self.buckets
+            .entry((symbol.to_owned(), start))
+            .or_insert_with(|| PnlBucket {
+                symbol: symbol.to_owned(),
+                start_ms: start,
+                ..Default::default()
+            })
+    }
+
+    fn book(&mut self, symbol: &str) -> PyResult<&mut Book> {
+        if !self.symbols.contains_key(symbol) {
+            let book = Book {
+                position: Position::new("average", None)?,
+                mark: None,
+            };
+            self.symbols.insert(symbol.to_owned(), book);
+        }
+        Ok(self.symbols.get_mut(symbol).expect("inserted above"))
+    }
+
+    fn mark(&mut self, symbol: &str, ts: i64, mid: f64) -> PyResult<()> {
+        let book = self.book(symbol)?;
+        let pnl = book
+            .mark
+            .map_or(0.0, |m| book.position.net_qty() * (mid - m));
+        book.mark = Some(mid);
+        self.bucket(symbol, ts).inventory += pnl;
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PnlAttribution {
+    // fee_model prices fills reported with a liquidity flag but no fee
+    #[new]
+    #[pyo3(signature = (bucket_ms=HOUR_MS, fee_model=None))]
+    pub fn new(bucket_ms: i64, fee_model: Option<FeeModel>) -> PyResult<Self> {
+        if bucket_ms <= 0 {
+            return Err(PyValueError::new_err("bucket_ms must be positive"));
+        }
+        Ok(Self {
+            bucket_ms,
+            fee_model,
+            symbols: HashMap::new(),
+            buckets: BTreeMap::new(),
+        })
+    }
+
+    // Mark-price (mid) update; moves inventory PnL into ts's bucket
+    pub fn on_mark(&mut self, symbol: &str, ts: i64, mid: f64) -> PyResult<()> {
+        self.mark(symbol, ts, mid)
+    }
+
+    // Batch of (ts, mid) marks for one symbol, in time order
+    pub fn on_marks(&mut self, symbol: &str, marks: Vec<(i64, f64)>) -> PyResult<()> {
+        for (ts, mid) in marks {
+            self.mark(symbol, ts, mid)?;
+        }
+        Ok(())
+    }
+
+    // Own fill; side is ours ("buy"/"sell"). mid defaults to the last mark
+    // (the fill price before any). fee: positive paid, negative rebate;
+    // without it a liquidity flag prices it with the fee model. Returns the
+    // spread captured.
+    #[pyo3(signature = (symbol, ts, price, qty, side, mid=None, fee=None, liquidity=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn on_fill(
+        &mut self,
+        symbol: &str,
+        ts: i64,
+        price: f64,
+        qty: f64,
+        side: &str,
+        mid: Option<f64>,
+        fee: Option<f64>,
+        liquidity: Option<&str>,
+    ) -> PyResult<f64> {
+        if qty <= 0.0 {
+            return Err(PyValueError::new_err("fill qty must be positive"));
+        }
+        let signed = match Side::parse(side)? {
+            Side::Bid => qty,
+            Side::Ask => -qty,
+        };
+        let fee = match (fee, liquidity, &mut self.fee_model) {
+            (Some(f), _, _) => f,
+            (None, Some(flag), Some(model)) => {
+                let f = model.fee(price, qty, Liquidity::parse(flag)?);
+                model.add_volume(price * qty);
+                f
+            }
+            (None, Some(_), None) => {
+                return Err(PyValueError::new_err(
+                    "liquidity given but no fee_model attached",
+                ))
+            }
+            (None, None, _) => 0.0,
+        };
+        let mid = match mid {
+            Some(m) => m,
+            None => self.book(symbol)?.mark.unwrap_or(price),
+        };
+        self.mark(symbol, ts, mid)?;
+        let book = self.book(symbol)?;
+        let realized = book.position.realized_pnl();
+        book.position.apply_fill(price, signed, 0.0);
+        let realized = book.position.realized_pnl() - realized;
+        let spread = (mid - price) * signed;
+        let bucket = self.bucket(symbol, ts);
+        bucket.spread += spread;
+        if fee >= 0.0 {
+            bucket.fees += fee;
+        } else {
+            bucket.rebates -= fee;
+        }
+        bucket.realized += realized;
+        bucket.volume += price * qty;
+        bucket.fills += 1;
+        Ok(spread)
+    }
+
+    // Buckets in (symbol, start) order, optionally for one symbol
+    #[pyo3(signature = (symbol=None))]
+    pub fn report(&self, symbol: Option<&str>) -> Vec<PnlBucket> {
+        self.buckets
+            .values()
+            .filter(|b| symbol.is_none_or(|s| b.symbol == s))
+            .cloned()
+            .collect()
+    }
+
+    // Sum over all buckets of a symbol, or of every symbol (symbol "*");
+    // start_ms is the first bucket's
+    #[pyo3(signature = (symbol=None))]
+    pub fn totals(&self, symbol: Option<&str>) -> PnlBucket {
+        let mut out = PnlBucket {
+            symbol: symbol.unwrap_or("*").to_owned(),
+            ..Default::default()
+        };
+        for (i, b) in self
+            .buckets
+            .values()
+            .filter(|b| symbol.is_none_or(|s| b.symbol == s))
+            .enumerate()
+        {
+            if i == 0 {
+                out.start_ms = b.start_ms;
+            }
+            out.add(b);
+        }
+        out
+    }
+
+    // report() as {column: [values]}, e.g. for pandas.DataFrame or
+    // polars.DataFrame
+    #[pyo3(signature = (symbol=None))]
+    pub fn columns<'py>(
+        &self,
+        py: Python<'py>,
+        symbol: Option<&str>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let rows = self.report(symbol);
+        let d = PyDict::new(py);
+        d.set_item(
+            "symbol",
+            rows.iter().map(|b| b.symbol.as_str()).collect::<Vec<_>>(),
+        )?;
+        d.set_item(
+            "start_ms",
+            rows.iter().map(|b| b.start_ms).collect::<Vec<_>>(),
+        )?;
+        for (name, values) in [
+            ("spread", rows.iter().map(|b| b.spread).collect::<Vec<_>>()),
+            ("inventory", rows.iter().map(|b| b.inventory).collect()),
+            ("fees", rows.iter().map(|b| b.fees).collect()),
+            ("rebates", rows.iter().map(|b| b.rebates).collect()),
+            ("total", rows.iter().map(PnlBucket::total).collect()),
+            ("realized", rows.iter().map(|b| b.realized).collect()),
+            ("volume", rows.iter().map(|b| b.volume).collect()),
+        ] {
+            d.set_item(name, values)?;
+        }
+        d.set_item("fills", rows.iter().map(|b| b.fills).collect::<Vec<_>>())?;
+        Ok(d)
+    }
+
+    // Signed position per symbol
+    pub fn positions(&self) -> HashMap<String, f64> {
+        self.symbols
+            .iter()
+            .map(|(s, b)| (s.clone(), b.position.net_qty()))
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.symbols.clear();
+        self.buckets.clear();
+    }
+}
@@ -0,0 +1,300 @@
+// Portfolio-level risk across symbols. Positions are pushed in with their
+// marks and turned into signed notionals n_i = qty_i * mark_i, from which
+//   gross    Σ |n_i|
+//   net      Σ n_i
+//   beta     Σ beta_i n_i, the exposure to the market factor
+//   VaR      z * sqrt(nᵀ Σ n), parametric over the horizon the covariance
+//            of fractional returns is estimated for
+// with z the standard normal quantile at `confidence`. Component VaR splits
+// the total by symbol (z n_i (Σn)_i / σ, summing to VaR), and var_with()
+// prices a trade before it is sent so one VaR budget can gate every symbol.
+// The symbol list fixes the covariance row order.
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::arrays::MatrixInput;
+
+// Standard normal quantile (Acklam's rational approximation, |error| < 1.2e-9)
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    let tail = |q: f64| {
+        let q = (-2.0 * q.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < 0.02425 {
+        tail(p)
+    } else if p > 1.0 - 0.02425 {
+        -tail(1.0 - p)
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[pyclass]
+pub struct PortfolioRisk {
+    symbols: Vec<String>,
+    index: HashMap<String, usize>,
+    // (qty, mark) per symbol
+    positions: Vec<(f64, f64)>,
+    betas: Vec<f64>,
+    // Row-major n x n
+    covariance: Option<Vec<f64>>,
+    z: f64,
+    #[pyo3(get, set)]
+    pub var_budget: Option<f64>,
+}
+
+impl PortfolioRisk {
+    fn slot(&self, symbol: &str) -> PyResult<usize> {
+        self.index
+            .get(symbol)
+            .copied()
+            .ok_or_else(|| PyValueError::new_err(format!("unknown symbol '{}'", symbol)))
+    }
+
+    fn notionals(&self) -> Vec<f64> {
+        self.positions.iter().map(|(q, m)| q * m).collect()
+    }
+
+    // Σn for the given notionals
+    fn cov_times(&self, n: &[f64]) -> Option<Vec<f64>> {
+        let cov = self.covariance.as_ref()?;
+        Some(
+            cov.chunks_exact(n.len())
+                .map(|row| row.iter().zip(n).map(|(c, x)| c * x).sum())
+                .collect(),
+        )
+    }
+
+    fn var_of(&self, n: &[f64]) -> Option<f64> {
+        let sn = self.cov_times(n)?;
+        let variance: f64 = n.iter().zip(&sn).map(|(a, b)| a * b).sum();
+        Some(self.z * variance.max(0.0).sqrt())
+    }
+}
+
+#[pymethods]
+impl PortfolioRisk {
+    // covariance: n x n covariance of fractional returns over the VaR
+    // horizon, rows in `symbols` order (list of rows or float64 array);
+    // betas default to 1
+    #[new]
+    #[pyo3(signature = (symbols, covariance=None, betas=None, confidence=0.99, var_budget=None))]
+    pub fn new(
+        symbols: Vec<String>,
+        covariance: Option<MatrixInput>,
+        betas: Option<HashMap<String, f64>>,
+        confidence: f64,
+        var_budget: Option<f64>,
+    ) -> PyResult<Self> {
+        if !(confidence > 0.5 && confidence < 1.0) {
+            return Err(PyValueError::new_err("confidence must be in (0.5, 1)"));
+        }
+        let index: HashMap<String, usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), i))
+            .collect();
+        if index.len() != symbols.len() {
+            return Err(PyValueError::new_err("symbols must be unique"));
+        }
+        let n = symbols.len();
+        let mut risk = Self {
+            symbols,
+            index,
+            positions: vec![(0.0, 0.0); n],
+            betas: vec![1.0; n],
+            covariance: None,
+            z: normal_quantile(confidence),
+            var_budget,
+        };
+        if let Some(cov) = covariance {
+            risk.set_covariance(cov)?;
+        }
+        if let Some(betas) = betas {
+            risk.set_betas(betas)?;
+        }
+        Ok(risk)
+    }
+
+    // Must be symmetric with a non-negative diagonal
+    pub fn set_covariance(&mut self, covariance: MatrixInput) -> PyResult<()> {
+        let n = self.symbols.len();
+        if covariance.rows != n || covariance.cols != n {
+            return Err(PyValueError::new_err(format!(
+                "covariance must be {} x {}, got {} x {}",
+                n, n, covariance.rows, covariance.cols
+            )));
+        }
+        let c = &covariance.values;
+        for i in 0..n {
+            if c[i * n + i].is_nan() || c[i * n + i] < 0.0 {
+                return Err(PyValueError::new_err(
+                    "covariance diagonal must be non-negative",
+                ));
+            }
+            for j in 0..i {
+                let (a, b) = (c[i * n + j], c[j * n + i]);
+                if (a - b).abs() > 1e-12 * a.abs().max(b.abs()).max(1.0) {
+                    return Err(PyValueError::new_err("covariance must be symmetric"));
+                }
+            }
+        }
+        self.covariance = Some(covariance.values);
+        Ok(())
+    }
+
+    // Symbols left out keep their beta
+    pub fn set_betas(&mut self, betas: HashMap<String, f64>) -> PyResult<()> {
+        for (symbol, beta) in betas {
+            let i = self.slot(&symbol)?;
+            self.betas[i] = beta;
+        }
+        Ok(())
+    }
+
+    // Signed quantity (long positive) marked at `mark`
+    pub fn set_position(&mut self, symbol: &str, qty: f64, mark: f64) -> PyResult<()> {
+        let i = self.slot(symbol)?;
+        self.positions[i] = (qty, mark);
+        Ok(())
+    }
+
+    pub fn set_mark(&mut self, symbol: &str, mark: f64) -> PyResult<()> {
+        let i = self.slot(symbol)?;
+        self.positions[i].1 = mark;
+        Ok(())
+    }
+
+    #[getter]
+    pub fn symbols(&self) -> Vec<String> {
+        self.symbols.clone()
+    }
+
+    // Signed notional per symbol, in `symbols` order
+    pub fn exposures(&self) -> Vec<f64> {
+        self.notionals()
+    }
+
+    #[getter]
+    pub fn gross_notional(&self) -> f64 {
+        self.notionals().iter().map(|n| n.abs()).sum()
+    }
+
+    #[getter]
+    pub fn net_notional(&self) -> f64 {
+        self.notionals().iter().sum()
+    }
+
+    #[getter]
+    pub fn beta_exposure(&self) -> f64 {
+        self.notionals()
+            .iter()
+            .zip(&self.betas)
+            .map(|(n, b)| n * b)
+            .sum()
+    }
+
+    // None without a covariance matrix
+    #[getter]
+    pub fn var(&self) -> Option<f64> {
+        self.var_of(&self.notionals())
+    }
+
+    // Component VaR per symbol; sums to var. All zero when flat.
+    pub fn contributions(&self) -> Option<Vec<f64>> {
+        let n = self.notionals();
+        let sn = self.cov_times(&n)?;
+        let sigma = n
+            .iter()
+            .zip(&sn)
+            .map(|(a, b)| a * b)
+            .sum::<f64>()
+            .max(0.0)
+            .sqrt();
+        Some(
+            n.iter()
+                .zip(&sn)
+                .map(|(a, b)| {
+                    if sigma > 0.0 {
+                        self.z * a * b / sigma
+                    } else {
+                        0.0
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    // VaR if `qty` (signed) more of `symbol` were held, at its mark or `price`
+    #[pyo3(signature = (symbol, qty, price=None))]
+    pub fn var_with(&self, symbol: &str, qty: f64, price: Option<f64>) -> PyResult<Option<f64>> {
+        let i = self.slot(symbol)?;
+        let mut n = self.notionals();
+        n[i] += qty * price.unwrap_or(self.positions[i].1);
+        Ok(self.var_of(&n))
+    }
+
+    // Whether the trade keeps VaR within var_budget; always true without a
+    // budget or covariance, and for trades that reduce VaR
+    #[pyo3(signature = (symbol, qty, price=None))]
+    pub fn allows(&self, symbol: &str, qty: f64, price: Option<f64>) -> PyResult<bool> {
+        let (Some(budget), Some(after)) = (self.var_budget, self.var_with(symbol, qty, price)?)
+        else {
+            return Ok(true);
+        };
+        Ok(after <= budget || self.var().is_some_and(|v| after <= v))
+    }
+
+    // Current VaR beyond var_budget
+    #[getter]
+    pub fn breached(&self) -> bool {
+        match (self.var_budget, self.var()) {
+            (Some(budget), Some(var)) => var > budget,
+            _ => false,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PortfolioRisk(symbols={}, gross_notional={}, var={:?})",
+            self.symbols.len(),
+            self.gross_notional(),
+            self.var()
+        )
+    }
+}
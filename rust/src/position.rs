@@ -0,0 +1,252 @@
+// Net position with average entry price and realized/unrealized PnL for
+// linear contracts. "average" mode keeps one blended entry price, "fifo"
+// closes the oldest open lots first.
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::fees::{FeeModel, Liquidity};
+use crate::state::{self, persist_fields, Persist, Stateful};
+use crate::{L2Book, Side};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum CostMode {
+    Average,
+    Fifo,
+}
+
+impl Persist for CostMode {
+    fn save(&self, out: &mut Vec<u8>) {
+        (*self as u8).save(out);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        match u8::load(input)? {
+            0 => Ok(Self::Average),
+            1 => Ok(Self::Fifo),
+            tag => Err(state::bad_tag(tag)),
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Position {
+    mode: CostMode,
+    // Open lots as (price, signed qty); a single lot in average mode
+    lots: VecDeque<(f64, f64)>,
+    realized_pnl: f64,
+    fees: f64,
+    volume: f64,
+    fill_count: u64,
+    // Prices fills that carry a liquidity flag but no explicit fee
+    fee_model: Option<FeeModel>,
+}
+
+persist_fields!(Position {
+    mode,
+    lots,
+    realized_pnl,
+    fees,
+    volume,
+    fill_count,
+    fee_model
+});
+
+impl Stateful for Position {
+    const NAME: &'static str = "Position";
+}
+
+impl Position {
+    fn qty(&self) -> f64 {
+        self.lots.iter().map(|l| l.1).sum()
+    }
+
+    pub fn apply_fill(&mut self, price: f64, signed_qty: f64, fee: f64) {
+        self.fees += fee;
+        self.volume += signed_qty.abs() * price;
+        self.fill_count += 1;
+
+        let mut remaining = signed_qty;
+        // Close against open lots of the opposite sign
+        while remaining != 0.0 {
+            let Some(lot) = self.lots.front_mut() else {
+                break;
+            };
+            if lot.1.signum() == remaining.signum() {
+                break;
+            }
+            let closed = remaining.abs().min(lot.1.abs());
+            let dir = lot.1.signum();
+            self.realized_pnl += (price - lot.0) * closed * dir;
+            lot.1 -= closed * dir;
+            remaining += closed * dir;
+            if lot.1.abs() < 1e-12 {
+                self.lots.pop_front();
+            }
+        }
+        if remaining == 0.0 {
+            return;
+        }
+        match self.mode {
+            CostMode::Fifo => self.lots.push_back((price, remaining)),
+            CostMode::Average => match self.lots.front_mut() {
+                Some(lot) => {
+                    let total = lot.1 + remaining;
+                    lot.0 = (lot.0 * lot.1 + price * remaining) / total;
+                    lot.1 = total;
+                }
+                None => self.lots.push_back((price, remaining)),
+            },
+        }
+    }
+}
+
+#[pymethods]
+impl Position {
+    // mode: "average" (average cost) or "fifo"
+    #[new]
+    #[pyo3(signature = (mode="average", fee_model=None))]
+    pub fn new(mode: &str, fee_model: Option<FeeModel>) -> PyResult<Self> {
+        let mode = match mode {
+            "average" => CostMode::Average,
+            "fifo" => CostMode::Fifo,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "mode must be 'average' or 'fifo', got '{}'",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            mode,
+            lots: VecDeque::new(),
+            realized_pnl: 0.0,
+            fees: 0.0,
+            volume: 0.0,
+            fill_count: 0,
+            fee_model,
+        })
+    }
+
+    // fee is in quote currency, positive = paid, negative = rebate. Without an
+    // explicit fee, a liquidity flag ("maker"/"taker") prices it with the
+    // attached fee model. Returns the fee charged.
+    #[pyo3(signature = (price, qty, side, fee=None, liquidity=None))]
+    pub fn on_fill(
+        &mut self,
+        price: f64,
+        qty: f64,
+        side: &str,
+        fee: Option<f64>,
+        liquidity: Option<&str>,
+    ) -> PyResult<f64> {
+        if qty <= 0.0 {
+            return Err(PyValueError::new_err("fill qty must be positive"));
+        }
+        let signed = match Side::parse(side)? {
+            Side::Bid => qty,
+            Side::Ask => -qty,
+        };
+        let fee = match (fee, liquidity, &mut self.fee_model) {
+            (Some(f), _, _) => f,
+            (None, Some(flag), Some(model)) => {
+                let f = model.fee(price, qty, Liquidity::parse(flag)?);
+                model.add_volume(price * qty);
+                f
+            }
+            (None, Some(_), None) => {
+                return Err(PyValueError::new_err(
+                    "liquidity given but no fee_model attached",
+                ))
+            }
+            (None, None, _) => 0.0,
+        };
+        self.apply_fill(price, signed, fee);
+        Ok(fee)
+    }
+
+    #[getter]
+    pub fn get_fee_model(&self) -> Option<FeeModel> {
+        self.fee_model.clone()
+    }
+
+    #[setter]
+    pub fn set_fee_model(&mut self, model: Option<FeeModel>) {
+        self.fee_model = model;
+    }
+
+    // Signed net position
+    #[getter]
+    pub fn net_qty(&self) -> f64 {
+        self.qty()
+    }
+
+    // Volume-weighted entry price of the open position
+    #[getter]
+    pub fn avg_price(&self) -> Option<f64> {
+        let qty = self.qty();
+        (qty != 0.0).then(|| self.lots.iter().map(|l| l.0 * l.1).sum::<f64>() / qty)
+    }
+
+    // Gross of fees
+    #[getter]
+    pub fn realized_pnl(&self) -> f64 {
+        self.realized_pnl
+    }
+
+    #[getter]
+    pub fn fees(&self) -> f64 {
+        self.fees
+    }
+
+    #[getter]
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    #[getter]
+    pub fn fill_count(&self) -> u64 {
+        self.fill_count
+    }
+
+    pub fn unrealized_pnl(&self, mark: f64) -> f64 {
+        self.lots.iter().map(|(p, q)| (mark - p) * q).sum()
+    }
+
+    // Realized + unrealized - fees
+    pub fn total_pnl(&self, mark: f64) -> f64 {
+        self.realized_pnl + self.unrealized_pnl(mark) - self.fees
+    }
+
+    // Mark at the book mid; None if the book has no mid
+    pub fn unrealized_pnl_book(&self, book: &L2Book) -> PyResult<Option<f64>> {
+        Ok(book.mid()?.map(|m| self.unrealized_pnl(m)))
+    }
+
+    pub fn notional(&self, mark: f64) -> f64 {
+        self.qty() * mark
+    }
+
+    pub fn reset(&mut self) {
+        self.lots.clear();
+        self.realized_pnl = 0.0;
+        self.fees = 0.0;
+        self.volume = 0.0;
+        self.fill_count = 0;
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
@@ -0,0 +1,193 @@
+// Queue position estimate for own resting orders from the public L2 stream.
+// At placement everything visible at the level is ahead of us. Trades at our
+// price eat the queue from the front; other size decreases are cancels,
+// split between ahead/behind with prob ahead^p / (ahead^p + behind^p).
+use std::collections::HashMap;
+
+use ordered_float::OrderedFloat;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{Levels, Side};
+
+struct TrackedOrder {
+    side: Side,
+    price: f64,
+    size: f64,
+    ahead: f64,
+    // Last visible level size (includes our own order)
+    level_size: f64,
+    // Traded volume not yet reflected in a level update
+    traded: f64,
+    placed_ts: Option<i64>,
+}
+
+impl TrackedOrder {
+    fn behind(&self) -> f64 {
+        (self.level_size - self.ahead - self.size).max(0.0)
+    }
+}
+
+#[pyclass]
+pub struct QueueTracker {
+    orders: HashMap<u64, TrackedOrder>,
+    power: f64,
+}
+
+impl QueueTracker {
+    // (side, price, qty ahead) of a tracked order
+    pub(crate) fn order(&self, order_id: u64) -> Option<(Side, f64, f64)> {
+        self.orders
+            .get(&order_id)
+            .map(|o| (o.side, o.price, o.ahead))
+    }
+
+    fn update_level(&mut self, side: Side, price: f64, new_size: f64) {
+        let power = self.power;
+        for o in self.orders.values_mut() {
+            if o.side != side || OrderedFloat(o.price) != OrderedFloat(price) {
+                continue;
+            }
+            // Level size excluding our own order can not go below zero
+            let new_size = new_size.max(o.size);
+            let mut decrease = o.level_size - new_size;
+            // Part of the decrease was already accounted for by trades
+            let absorbed = o.traded.min(decrease.max(0.0));
+            decrease -= absorbed;
+            o.traded = 0.0;
+            if decrease > 0.0 {
+                let fa = o.ahead.powf(power);
+                let fb = o.behind().powf(power);
+                let prob_ahead = if fa + fb > 0.0 { fa / (fa + fb) } else { 0.0 };
+                o.ahead = (o.ahead - decrease * prob_ahead).max(0.0);
+            }
+            o.level_size = new_size;
+        }
+    }
+}
+
+#[pymethods]
+impl QueueTracker {
+    // power shapes the cancel model: 1.0 proportional, higher values assume
+    // cancels come mostly from the larger part of the queue
+    #[new]
+    #[pyo3(signature = (power=1.0))]
+    pub fn new(power: f64) -> PyResult<Self> {
+        if power <= 0.0 {
+            return Err(PyValueError::new_err("power must be positive"));
+        }
+        Ok(Self {
+            orders: HashMap::new(),
+            power,
+        })
+    }
+
+    // level_size is the visible size at `price` before our order joined
+    #[pyo3(signature = (order_id, side, price, size, level_size, ts=None))]
+    pub fn add_order(
+        &mut self,
+        order_id: u64,
+        side: &str,
+        price: f64,
+        size: f64,
+        level_size: f64,
+        ts: Option<i64>,
+    ) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        self.orders.insert(
+            order_id,
+            TrackedOrder {
+                side,
+                price,
+                size,
+                ahead: level_size.max(0.0),
+                level_size: level_size.max(0.0) + size,
+                traded: 0.0,
+                placed_ts: ts,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn remove_order(&mut self, order_id: u64) -> bool {
+        self.orders.remove(&order_id).is_some()
+    }
+
+    // Our own partial fills shrink the order but not the queue ahead
+    pub fn set_order_size(&mut self, order_id: u64, size: f64) -> bool {
+        match self.orders.get_mut(&order_id) {
+            Some(o) => {
+                o.level_size -= o.size - size;
+                o.size = size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn on_level_update(&mut self, side: &str, price: f64, size: f64) -> PyResult<()> {
+        self.update_level(Side::parse(side)?, price, size);
+        Ok(())
+    }
+
+    // Same (price, size) format as L2Book.apply_delta
+    pub fn apply_delta(&mut self, bids: Levels, asks: Levels) {
+        for (p, s) in bids {
+            self.update_level(Side::Bid, p, s);
+        }
+        for (p, s) in asks {
+            self.update_level(Side::Ask, p, s);
+        }
+    }
+
+    // Public trade print. Returns estimated fills of tracked orders as
+    // (order_id, qty): volume beyond the queue ahead reaches our order, and a
+    // print through our price means the whole level was swept.
+    pub fn on_trade(&mut self, price: f64, qty: f64) -> Vec<(u64, f64)> {
+        let mut fills = Vec::new();
+        for (id, o) in self.orders.iter_mut() {
+            let through = match o.side {
+                Side::Bid => price < o.price,
+                Side::Ask => price > o.price,
+            };
+            if through {
+                o.ahead = 0.0;
+                fills.push((*id, o.size));
+            } else if price == o.price {
+                let reach = qty - o.ahead;
+                o.ahead = (o.ahead - qty).max(0.0);
+                o.traded += qty;
+                if reach > 0.0 {
+                    fills.push((*id, reach.min(o.size)));
+                }
+            }
+        }
+        fills.sort_by_key(|f| f.0);
+        fills
+    }
+
+    // (qty ahead, qty behind) for a tracked order
+    pub fn position(&self, order_id: u64) -> Option<(f64, f64)> {
+        self.orders.get(&order_id).map(|o| (o.ahead, o.behind()))
+    }
+
+    // Fraction of the original queue already consumed, 0..1
+    pub fn progress(&self, order_id: u64) -> Option<f64> {
+        self.orders.get(&order_id).map(|o| {
+            let others = o.level_size - o.size;
+            if others <= 0.0 {
+                1.0
+            } else {
+                1.0 - (o.ahead / others).min(1.0)
+            }
+        })
+    }
+
+    pub fn placed_ts(&self, order_id: u64) -> Option<i64> {
+        self.orders.get(&order_id).and_then(|o| o.placed_ts)
+    }
+
+    fn __len__(&self) -> usize {
+        self.orders.len()
+    }
+}
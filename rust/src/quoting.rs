@@ -0,0 +1,145 @@
+// Avellaneda-Stoikov market making:
+//   reservation r = s - q·γ·σ²·τ
+//   spread      δ = γ·σ²·τ + (2/γ)·ln(1 + γ/κ)
+// quotes at r ± δ/2, with τ the remaining horizon.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::metrics::{self, NATIVE};
+use crate::L2Book;
+
+#[pyclass(get_all)]
+#[derive(Clone, Copy, Debug)]
+pub struct Quote {
+    pub bid_price: f64,
+    pub ask_price: f64,
+    pub reservation_price: f64,
+    pub spread: f64,
+}
+
+#[pymethods]
+impl Quote {
+    fn __repr__(&self) -> String {
+        format!(
+            "Quote(bid_price={:?}, ask_price={:?}, reservation_price={:?}, spread={:?})",
+            self.bid_price, self.ask_price, self.reservation_price, self.spread
+        )
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct QuoteEngine {
+    // Set through set_gamma / set_kappa, which keep them positive
+    #[pyo3(get)]
+    pub gamma: f64,
+    #[pyo3(get)]
+    pub kappa: f64,
+    #[pyo3(get, set)]
+    pub horizon: f64,
+    #[pyo3(get, set)]
+    pub min_spread: f64,
+}
+
+// γ divides and γ/κ goes into ln(1 + γ/κ), so both must be positive
+pub(crate) fn check_positive(name: &str, value: f64) -> PyResult<()> {
+    if value > 0.0 {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(format!(
+            "{} must be positive, got {}",
+            name, value
+        )))
+    }
+}
+
+impl QuoteEngine {
+    pub fn compute(&self, price: f64, inventory: f64, volatility: f64, tau: f64) -> Quote {
+        let var_tau = volatility * volatility * tau.max(0.0);
+        let reservation_price = price - inventory * self.gamma * var_tau;
+        let spread = (self.gamma * var_tau
+            + (2.0 / self.gamma) * (1.0 + self.gamma / self.kappa).ln())
+        .max(self.min_spread);
+        Quote {
+            bid_price: reservation_price - spread / 2.0,
+            ask_price: reservation_price + spread / 2.0,
+            reservation_price,
+            spread,
+        }
+    }
+}
+
+#[pymethods]
+impl QuoteEngine {
+    // gamma: risk aversion, kappa: order-arrival decay, horizon: τ used when
+    // quote() is not given time_left (same time unit as volatility)
+    #[new]
+    #[pyo3(signature = (gamma, kappa, horizon=1.0, min_spread=0.0))]
+    pub fn new(gamma: f64, kappa: f64, horizon: f64, min_spread: f64) -> PyResult<Self> {
+        check_positive("gamma", gamma)?;
+        check_positive("kappa", kappa)?;
+        Ok(Self {
+            gamma,
+            kappa,
+            horizon,
+            min_spread,
+        })
+    }
+
+    #[setter]
+    pub fn set_gamma(&mut self, gamma: f64) -> PyResult<()> {
+        check_positive("gamma", gamma)?;
+        self.gamma = gamma;
+        Ok(())
+    }
+
+    #[setter]
+    pub fn set_kappa(&mut self, kappa: f64) -> PyResult<()> {
+        check_positive("kappa", kappa)?;
+        self.kappa = kappa;
+        Ok(())
+    }
+
+    #[pyo3(signature = (price, inventory, volatility, time_left=None))]
+    pub fn quote(
+        &self,
+        price: f64,
+        inventory: f64,
+        volatility: f64,
+        time_left: Option<f64>,
+    ) -> Quote {
+        self.compute(
+            price,
+            inventory,
+            volatility,
+            time_left.unwrap_or(self.horizon),
+        )
+    }
+
+    // Quote around the book's mid or microprice; None for a one-sided book
+    #[pyo3(signature = (book, inventory, volatility, time_left=None, reference="mid"))]
+    pub fn quote_book(
+        &self,
+        book: &L2Book,
+        inventory: f64,
+        volatility: f64,
+        time_left: Option<f64>,
+        reference: &str,
+    ) -> PyResult<Option<Quote>> {
+        let price = match reference {
+            "mid" => book.mid()?,
+            "microprice" => book.guarded(book.weighted_microprice(1, None))?,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "reference must be 'mid' or 'microprice', got '{}'",
+                    other
+                )))
+            }
+        };
+        let quote = price.map(|p| self.quote(p, inventory, volatility, time_left));
+        if let Some(at) = book.updated_at {
+            metrics::observe_since(&NATIVE.tick_to_quote, at);
+        }
+        Ok(quote)
+    }
+}
@@ -0,0 +1,145 @@
+// Token-bucket rate limiting for order actions. Several buckets can be
+// composed (e.g. 10 orders/s and 1200 weight/min); an acquire succeeds only
+// if every bucket can pay, and then all of them are charged atomically.
+// Uses the monotonic clock; the pyclass is frozen with an internal mutex so
+// one limiter can be shared by several Python threads.
+use std::sync::Mutex;
+use std::time::Instant;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::metrics::NATIVE;
+
+struct Bucket {
+    capacity: f64,
+    // tokens per second
+    rate: f64,
+    tokens: f64,
+}
+
+struct State {
+    buckets: Vec<Bucket>,
+    last: Instant,
+}
+
+impl State {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+        for b in &mut self.buckets {
+            b.tokens = (b.tokens + dt * b.rate).min(b.capacity);
+        }
+    }
+}
+
+#[pyclass(frozen)]
+pub struct RateLimiter {
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    fn with_state<T>(&self, f: impl FnOnce(&mut State) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.refill();
+        f(&mut state)
+    }
+
+    // Per-bucket cost: `weights` overrides `weight` bucket by bucket
+    fn costs(&self, n: usize, weight: f64, weights: &Option<Vec<f64>>) -> PyResult<Vec<f64>> {
+        match weights {
+            Some(w) if w.len() != n => Err(PyValueError::new_err(format!(
+                "weights must have one entry per bucket ({})",
+                n
+            ))),
+            Some(w) => Ok(w.clone()),
+            None => Ok(vec![weight; n]),
+        }
+    }
+}
+
+#[pymethods]
+impl RateLimiter {
+    // buckets: [(capacity, period_seconds), ...], e.g. [(10, 1.0), (1200, 60.0)].
+    // Buckets start full.
+    #[new]
+    pub fn new(buckets: Vec<(f64, f64)>) -> PyResult<Self> {
+        if buckets.is_empty() || buckets.iter().any(|(c, p)| *c <= 0.0 || *p <= 0.0) {
+            return Err(PyValueError::new_err(
+                "need at least one bucket with positive capacity and period",
+            ));
+        }
+        let buckets = buckets
+            .into_iter()
+            .map(|(capacity, period)| Bucket {
+                capacity,
+                rate: capacity / period,
+                tokens: capacity,
+            })
+            .collect();
+        Ok(Self {
+            state: Mutex::new(State {
+                buckets,
+                last: Instant::now(),
+            }),
+        })
+    }
+
+    #[pyo3(signature = (weight=1.0, weights=None))]
+    pub fn try_acquire(&self, weight: f64, weights: Option<Vec<f64>>) -> PyResult<bool> {
+        self.with_state(|s| {
+            let costs = self.costs(s.buckets.len(), weight, &weights)?;
+            if s.buckets.iter().zip(&costs).any(|(b, c)| b.tokens < *c) {
+                NATIVE.ratelimit_throttled.inc(1.0);
+                return Ok(false);
+            }
+            for (b, c) in s.buckets.iter_mut().zip(&costs) {
+                b.tokens -= c;
+            }
+            NATIVE.ratelimit_allowed.inc(1.0);
+            Ok(true)
+        })
+    }
+
+    // Seconds until try_acquire(weight) would succeed (0.0 if it would now)
+    #[pyo3(signature = (weight=1.0, weights=None))]
+    pub fn time_until_available(&self, weight: f64, weights: Option<Vec<f64>>) -> PyResult<f64> {
+        self.with_state(|s| {
+            let costs = self.costs(s.buckets.len(), weight, &weights)?;
+            let mut wait: f64 = 0.0;
+            for (b, c) in s.buckets.iter().zip(&costs) {
+                if *c > b.capacity {
+                    return Ok(f64::INFINITY);
+                }
+                wait = wait.max((c - b.tokens).max(0.0) / b.rate);
+            }
+            Ok(wait)
+        })
+    }
+
+    // Current tokens per bucket
+    pub fn available(&self) -> Vec<f64> {
+        self.with_state(|s| s.buckets.iter().map(|b| b.tokens).collect())
+    }
+
+    // Sync with exchange-reported usage: set a bucket's used amount
+    pub fn set_used(&self, bucket: usize, used: f64) -> PyResult<()> {
+        self.with_state(|s| {
+            let b = s
+                .buckets
+                .get_mut(bucket)
+                .ok_or_else(|| PyValueError::new_err("bucket index out of range"))?;
+            b.tokens = (b.capacity - used).clamp(0.0, b.capacity);
+            Ok(())
+        })
+    }
+
+    pub fn reset(&self) {
+        self.with_state(|s| {
+            for b in &mut s.buckets {
+                b.tokens = b.capacity;
+            }
+        })
+    }
+}
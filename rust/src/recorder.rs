@@ -0,0 +1,303 @@
+// Market-data recorder writing Parquet, partitioned per stream and symbol:
+//   {base_dir}/book/symbol={symbol}/{first_ts}-{n}.parquet
+//   {base_dir}/trades/symbol={symbol}/{first_ts}-{n}.parquet
+// Book rows are one level each (ts, seq, kind, side, price, size, update_id);
+// rows of one snapshot or delta share `seq`. Trades are (ts, price, qty,
+// side). Rows are buffered into row groups; a file is closed and a new one
+// started once it exceeds rotate_bytes or spans rotate_ms of record time.
+// Files are only readable once closed, so call close() (or drop the
+// recorder) when done.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::parquet::{Column, ParquetWriter, Values};
+use crate::{Levels, Side};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stream {
+    Book,
+    Trades,
+}
+
+impl Stream {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Book => "book",
+            Self::Trades => "trades",
+        }
+    }
+
+    fn columns(self) -> Vec<Column> {
+        match self {
+            Self::Book => vec![
+                Column::new("ts", Values::I64(Vec::new())),
+                Column::new("seq", Values::I64(Vec::new())),
+                Column::new("kind", Values::Str(Vec::new())),
+                Column::new("side", Values::Str(Vec::new())),
+                Column::new("price", Values::F64(Vec::new())),
+                Column::new("size", Values::F64(Vec::new())),
+                Column::optional("update_id", Values::I64(Vec::new())),
+            ],
+            Self::Trades => vec![
+                Column::new("ts", Values::I64(Vec::new())),
+                Column::new("price", Values::F64(Vec::new())),
+                Column::new("qty", Values::F64(Vec::new())),
+                Column::optional("side", Values::Str(Vec::new())),
+            ],
+        }
+    }
+}
+
+struct Partition {
+    dir: PathBuf,
+    columns: Vec<Column>,
+    rows: usize,
+    writer: Option<ParquetWriter>,
+    first_ts: i64,
+    seq: i64,
+}
+
+impl Partition {
+    fn flush(&mut self) -> PyResult<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        if self.writer.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let mut n = 0;
+            let path = loop {
+                let p = self.dir.join(format!("{}-{}.parquet", self.first_ts, n));
+                if !p.exists() {
+                    break p;
+                }
+                n += 1;
+            };
+            self.writer = Some(ParquetWriter::create(&path, &self.columns)?);
+        }
+        if let Some(w) = self.writer.as_mut() {
+            w.write_row_group(&self.columns, self.rows)?;
+        }
+        self.columns.iter_mut().for_each(Column::clear);
+        self.rows = 0;
+        Ok(())
+    }
+
+    // Flush and finish the current file, if any
+    fn close(&mut self) -> PyResult<Option<String>> {
+        self.flush()?;
+        match self.writer.take() {
+            Some(w) => Ok(Some(w.finish()?.to_string_lossy().into_owned())),
+            None => Ok(None),
+        }
+    }
+
+    fn buffered_bytes(&self) -> u64 {
+        // Rough: 8 bytes per numeric cell, strings are short tags
+        (self.rows * self.columns.len() * 8) as u64
+    }
+}
+
+#[pyclass]
+pub struct Recorder {
+    base_dir: PathBuf,
+    rotate_bytes: u64,
+    rotate_ms: i64,
+    row_group_rows: usize,
+    partitions: BTreeMap<(Stream, String), Partition>,
+    files: Vec<String>,
+}
+
+impl Recorder {
+    // Partition for a record at `ts`, rotating first if the file is too old
+    fn partition(&mut self, stream: Stream, symbol: &str, ts: i64) -> PyResult<&mut Partition> {
+        let key = (stream, symbol.to_owned());
+        if !self.partitions.contains_key(&key) {
+            let dir = self
+                .base_dir
+                .join(stream.name())
+                .join(format!("symbol={}", symbol));
+            self.partitions.insert(
+                key.clone(),
+                Partition {
+                    dir,
+                    columns: stream.columns(),
+                    rows: 0,
+                    writer: None,
+                    first_ts: ts,
+                    seq: 0,
+                },
+            );
+        }
+        let part = self.partitions.get_mut(&key).expect("inserted above");
+        let started = part.writer.is_some() || part.rows > 0;
+        if started && ts - part.first_ts >= self.rotate_ms {
+            if let Some(path) = part.close()? {
+                self.files.push(path);
+            }
+        }
+        let part = self.partitions.get_mut(&key).expect("inserted above");
+        if part.writer.is_none() && part.rows == 0 {
+            part.first_ts = ts;
+        }
+        Ok(part)
+    }
+
+    // Write a full row group, and rotate once the file is large enough
+    fn after_append(&mut self, stream: Stream, symbol: &str) -> PyResult<()> {
+        let (row_group_rows, rotate_bytes) = (self.row_group_rows, self.rotate_bytes);
+        let Some(part) = self.partitions.get_mut(&(stream, symbol.to_owned())) else {
+            return Ok(());
+        };
+        if part.rows >= row_group_rows {
+            part.flush()?;
+        }
+        let written = part.writer.as_ref().map_or(0, |w| w.bytes_written());
+        if written + part.buffered_bytes() >= rotate_bytes {
+            if let Some(path) = part.close()? {
+                self.files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn record_book(
+        &mut self,
+        kind: &'static str,
+        symbol: &str,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        let part = self.partition(Stream::Book, symbol, ts)?;
+        let seq = part.seq;
+        part.seq += 1;
+        let rows = bids
+            .into_iter()
+            .map(|l| ("bid", l))
+            .chain(asks.into_iter().map(|l| ("ask", l)));
+        for (side, (price, size)) in rows {
+            let c = &mut part.columns;
+            c[0].push_i64(Some(ts));
+            c[1].push_i64(Some(seq));
+            c[2].push_str(Some(kind));
+            c[3].push_str(Some(side));
+            c[4].push_f64(price);
+            c[5].push_f64(size);
+            c[6].push_i64(update_id.map(|u| u as i64));
+            part.rows += 1;
+        }
+        self.after_append(Stream::Book, symbol)
+    }
+}
+
+#[pymethods]
+impl Recorder {
+    #[new]
+    #[pyo3(signature = (base_dir, rotate_bytes=256 * 1024 * 1024, rotate_ms=3_600_000, row_group_rows=100_000))]
+    pub fn new(
+        base_dir: PathBuf,
+        rotate_bytes: u64,
+        rotate_ms: i64,
+        row_group_rows: usize,
+    ) -> PyResult<Self> {
+        if rotate_bytes == 0 || rotate_ms <= 0 || row_group_rows == 0 {
+            return Err(PyValueError::new_err(
+                "rotate_bytes, rotate_ms and row_group_rows must be positive",
+            ));
+        }
+        Ok(Self {
+            base_dir,
+            rotate_bytes,
+            rotate_ms,
+            row_group_rows,
+            partitions: BTreeMap::new(),
+            files: Vec::new(),
+        })
+    }
+
+    #[pyo3(signature = (symbol, ts, bids, asks, update_id=None))]
+    pub fn record_snapshot(
+        &mut self,
+        symbol: &str,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        self.record_book("snapshot", symbol, ts, bids, asks, update_id)
+    }
+
+    #[pyo3(signature = (symbol, ts, bids, asks, update_id=None))]
+    pub fn record_delta(
+        &mut self,
+        symbol: &str,
+        ts: i64,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        self.record_book("delta", symbol, ts, bids, asks, update_id)
+    }
+
+    #[pyo3(signature = (symbol, ts, price, qty, side=None))]
+    pub fn record_trade(
+        &mut self,
+        symbol: &str,
+        ts: i64,
+        price: f64,
+        qty: f64,
+        side: Option<&str>,
+    ) -> PyResult<()> {
+        let side = side.map(Side::parse).transpose()?;
+        let part = self.partition(Stream::Trades, symbol, ts)?;
+        let c = &mut part.columns;
+        c[0].push_i64(Some(ts));
+        c[1].push_f64(price);
+        c[2].push_f64(qty);
+        c[3].push_str(side.map(Side::name));
+        part.rows += 1;
+        self.after_append(Stream::Trades, symbol)
+    }
+
+    // Write buffered rows as row groups (files stay open)
+    pub fn flush(&mut self) -> PyResult<()> {
+        for part in self.partitions.values_mut() {
+            part.flush()?;
+        }
+        Ok(())
+    }
+
+    // Finish every open file; returns all files written so far
+    pub fn close(&mut self) -> PyResult<Vec<String>> {
+        for part in self.partitions.values_mut() {
+            if let Some(path) = part.close()? {
+                self.files.push(path);
+            }
+        }
+        Ok(self.files.clone())
+    }
+
+    // Completed files, in the order they were closed
+    #[getter]
+    fn files(&self) -> Vec<String> {
+        self.files.clone()
+    }
+
+    #[getter]
+    fn base_dir(&self) -> PathBuf {
+        self.base_dir.clone()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        for part in self.partitions.values_mut() {
+            let _ = part.close();
+        }
+    }
+}
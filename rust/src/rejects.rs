@@ -0,0 +1,204 @@
+// Order-path storm detection. Counts order submits, rejects, failed cancels
+// and ack timeouts over a trailing window_ms and reports a breach when a
+// count, or the reject rate (rejects / submits, once min_orders were sent
+// in the window), exceeds its threshold. A RiskGuard passed in is halted
+// with the breach as reason, so an exchange-side incident stops quoting
+// without the strategy polling; the guard latches, the monitor does not.
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+use crate::risk::RiskGuard;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Event {
+    Order,
+    Reject,
+    CancelReject,
+    Timeout,
+}
+
+#[pyclass]
+pub struct RejectMonitor {
+    window_ms: i64,
+    #[pyo3(get, set)]
+    pub max_rejects: Option<u64>,
+    #[pyo3(get, set)]
+    pub max_cancel_rejects: Option<u64>,
+    #[pyo3(get, set)]
+    pub max_timeouts: Option<u64>,
+    #[pyo3(get, set)]
+    pub max_reject_rate: Option<f64>,
+    min_orders: u64,
+    guard: Option<Py<RiskGuard>>,
+    clock: Option<Clock>,
+    events: VecDeque<(i64, Event)>,
+    // Per-event counts over the window, in Event order
+    counts: [u64; 4],
+    last_breach: Option<String>,
+}
+
+impl RejectMonitor {
+    fn count(&self, event: Event) -> u64 {
+        self.counts[event as usize]
+    }
+
+    fn evict(&mut self, ts: i64) {
+        while let Some(&(t, e)) = self.events.front() {
+            if ts - t <= self.window_ms {
+                break;
+            }
+            self.counts[e as usize] -= 1;
+            self.events.pop_front();
+        }
+    }
+
+    fn breach(&self) -> Option<String> {
+        let window = self.window_ms;
+        for (event, limit, name) in [
+            (Event::Reject, self.max_rejects, "rejects"),
+            (
+                Event::CancelReject,
+                self.max_cancel_rejects,
+                "cancel rejects",
+            ),
+            (Event::Timeout, self.max_timeouts, "ack timeouts"),
+        ] {
+            let n = self.count(event);
+            match limit {
+                Some(limit) if n > limit => {
+                    return Some(format!("{} {} in {} ms (max {})", n, name, window, limit))
+                }
+                _ => {}
+            }
+        }
+        let orders = self.count(Event::Order);
+        if let Some(limit) = self.max_reject_rate {
+            let rate = self.count(Event::Reject) as f64 / orders.max(1) as f64;
+            if orders >= self.min_orders && rate > limit {
+                return Some(format!(
+                    "reject rate {:.3} over {} orders in {} ms (max_reject_rate {})",
+                    rate, orders, window, limit
+                ));
+            }
+        }
+        None
+    }
+
+    fn record(&mut self, py: Python<'_>, event: Event, ts: Option<i64>) -> PyResult<bool> {
+        let ts = clock::require(ts, &self.clock)?;
+        self.evict(ts);
+        self.events.push_back((ts, event));
+        self.counts[event as usize] += 1;
+        self.check(py)
+    }
+
+    fn check(&mut self, py: Python<'_>) -> PyResult<bool> {
+        let Some(reason) = self.breach() else {
+            return Ok(false);
+        };
+        if let Some(guard) = &self.guard {
+            guard.try_borrow_mut(py)?.halt(&reason);
+        }
+        self.last_breach = Some(reason);
+        Ok(true)
+    }
+}
+
+#[pymethods]
+impl RejectMonitor {
+    // Thresholds left at None are off
+    #[new]
+    #[pyo3(signature = (
+        window_ms=10_000,
+        max_rejects=None,
+        max_cancel_rejects=None,
+        max_timeouts=None,
+        max_reject_rate=None,
+        min_orders=20,
+        guard=None,
+        clock=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        window_ms: i64,
+        max_rejects: Option<u64>,
+        max_cancel_rejects: Option<u64>,
+        max_timeouts: Option<u64>,
+        max_reject_rate: Option<f64>,
+        min_orders: u64,
+        guard: Option<Py<RiskGuard>>,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if window_ms <= 0 {
+            return Err(PyValueError::new_err("window_ms must be positive"));
+        }
+        Ok(Self {
+            window_ms,
+            max_rejects,
+            max_cancel_rejects,
+            max_timeouts,
+            max_reject_rate,
+            min_orders,
+            guard,
+            clock,
+            events: VecDeque::new(),
+            counts: [0; 4],
+            last_breach: None,
+        })
+    }
+
+    // Each recorder returns whether a threshold is exceeded after the event
+    #[pyo3(signature = (ts=None))]
+    pub fn on_order(&mut self, py: Python<'_>, ts: Option<i64>) -> PyResult<bool> {
+        self.record(py, Event::Order, ts)
+    }
+
+    #[pyo3(signature = (ts=None))]
+    pub fn on_reject(&mut self, py: Python<'_>, ts: Option<i64>) -> PyResult<bool> {
+        self.record(py, Event::Reject, ts)
+    }
+
+    #[pyo3(signature = (ts=None))]
+    pub fn on_cancel_reject(&mut self, py: Python<'_>, ts: Option<i64>) -> PyResult<bool> {
+        self.record(py, Event::CancelReject, ts)
+    }
+
+    #[pyo3(signature = (ts=None))]
+    pub fn on_timeout(&mut self, py: Python<'_>, ts: Option<i64>) -> PyResult<bool> {
+        self.record(py, Event::Timeout, ts)
+    }
+
+    // Age the window without an event (e.g. on a timer)
+    #[pyo3(signature = (ts=None))]
+    pub fn evaluate(&mut self, py: Python<'_>, ts: Option<i64>) -> PyResult<bool> {
+        let ts = clock::require(ts, &self.clock)?;
+        self.evict(ts);
+        self.check(py)
+    }
+
+    // (orders, rejects, cancel rejects, timeouts) in the window as of the
+    // last event or evaluate()
+    pub fn counts(&self) -> (u64, u64, u64, u64) {
+        let [o, r, c, t] = self.counts;
+        (o, r, c, t)
+    }
+
+    #[getter]
+    pub fn last_breach(&self) -> Option<String> {
+        self.last_breach.clone()
+    }
+
+    #[getter]
+    pub fn guard(&self, py: Python<'_>) -> Option<Py<RiskGuard>> {
+        self.guard.as_ref().map(|g| g.clone_ref(py))
+    }
+
+    pub fn reset(&mut self) {
+        self.events.clear();
+        self.counts = [0; 4];
+        self.last_breach = None;
+    }
+}
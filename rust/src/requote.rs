@@ -0,0 +1,192 @@
+// Quote throttling. Before sending a new two-sided quote for a symbol the
+// strategy asks the gate, which lets it through only if
+//   - min_interval_ms has passed since the last send for the symbol,
+//   - some side moved by at least min_improvement_bps from the price last
+//     sent (a side appearing or being pulled always counts), and
+//   - fewer than max_amends sends happened in the trailing per_ms.
+// The first quote for a symbol only has to pass the rate check. A quote
+// that passes is recorded as sent; record() registers sends made outside
+// the gate (e.g. a forced requote after a fill) so the limits still see
+// them. Times come from `ts` or the gate's clock.
+use std::collections::{HashMap, VecDeque};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+
+#[derive(Default)]
+struct Sent {
+    bid: Option<f64>,
+    ask: Option<f64>,
+    last_ts: Option<i64>,
+    // Send times within the rate window, oldest first
+    times: VecDeque<i64>,
+}
+
+// Move from `old` to `new` in bps; infinite when a side appears or goes
+fn move_bps(old: Option<f64>, new: Option<f64>) -> f64 {
+    match (old, new) {
+        (Some(o), Some(n)) => (n - o).abs() / o * 10_000.0,
+        (None, None) => 0.0,
+        _ => f64::INFINITY,
+    }
+}
+
+#[pyclass]
+pub struct RequoteGate {
+    min_interval_ms: i64,
+    min_improvement_bps: f64,
+    max_amends: usize,
+    per_ms: i64,
+    clock: Option<Clock>,
+    symbols: HashMap<String, Sent>,
+    // Suppressed quotes by reason
+    suppressed: HashMap<&'static str, u64>,
+}
+
+impl RequoteGate {
+    fn reason(
+        &mut self,
+        symbol: &str,
+        bid: Option<f64>,
+        ask: Option<f64>,
+        ts: i64,
+    ) -> Option<&'static str> {
+        let per_ms = self.per_ms;
+        let sent = self.symbols.entry(symbol.to_owned()).or_default();
+        while sent.times.front().is_some_and(|t| ts - t >= per_ms) {
+            sent.times.pop_front();
+        }
+        if let Some(last) = sent.last_ts {
+            if ts - last < self.min_interval_ms {
+                return Some("interval");
+            }
+            let moved = move_bps(sent.bid, bid).max(move_bps(sent.ask, ask));
+            if moved < self.min_improvement_bps {
+                return Some("improvement");
+            }
+        }
+        (sent.times.len() >= self.max_amends).then_some("rate")
+    }
+
+    fn sent(&mut self, symbol: &str, bid: Option<f64>, ask: Option<f64>, ts: i64) {
+        let sent = self.symbols.entry(symbol.to_owned()).or_default();
+        sent.bid = bid;
+        sent.ask = ask;
+        sent.last_ts = Some(ts);
+        sent.times.push_back(ts);
+    }
+}
+
+#[pymethods]
+impl RequoteGate {
+    #[new]
+    #[pyo3(signature = (min_interval_ms=100, min_improvement_bps=1.0, max_amends=10, per_ms=1_000, clock=None))]
+    pub fn new(
+        min_interval_ms: i64,
+        min_improvement_bps: f64,
+        max_amends: usize,
+        per_ms: i64,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if min_interval_ms < 0 || min_improvement_bps < 0.0 {
+            return Err(PyValueError::new_err(
+                "min_interval_ms and min_improvement_bps must be non-negative",
+            ));
+        }
+        if max_amends == 0 || per_ms <= 0 {
+            return Err(PyValueError::new_err(
+                "max_amends and per_ms must be positive",
+            ));
+        }
+        Ok(Self {
+            min_interval_ms,
+            min_improvement_bps,
+            max_amends,
+            per_ms,
+            clock,
+            symbols: HashMap::new(),
+            suppressed: HashMap::new(),
+        })
+    }
+
+    // Whether to send the quote (None for a side not quoted); if so it is
+    // recorded as sent
+    #[pyo3(signature = (symbol, bid, ask, ts=None))]
+    pub fn should_send(
+        &mut self,
+        symbol: &str,
+        bid: Option<f64>,
+        ask: Option<f64>,
+        ts: Option<i64>,
+    ) -> PyResult<bool> {
+        let ts = clock::require(ts, &self.clock)?;
+        match self.reason(symbol, bid, ask, ts) {
+            Some(reason) => {
+                *self.suppressed.entry(reason).or_default() += 1;
+                Ok(false)
+            }
+            None => {
+                self.sent(symbol, bid, ask, ts);
+                Ok(true)
+            }
+        }
+    }
+
+    // Dry run: why the quote would be held back ("interval", "improvement"
+    // or "rate"), or None if it would be sent
+    #[pyo3(signature = (symbol, bid, ask, ts=None))]
+    pub fn check(
+        &mut self,
+        symbol: &str,
+        bid: Option<f64>,
+        ask: Option<f64>,
+        ts: Option<i64>,
+    ) -> PyResult<Option<&'static str>> {
+        let ts = clock::require(ts, &self.clock)?;
+        Ok(self.reason(symbol, bid, ask, ts))
+    }
+
+    // Register a quote sent without asking the gate
+    #[pyo3(signature = (symbol, bid, ask, ts=None))]
+    pub fn record(
+        &mut self,
+        symbol: &str,
+        bid: Option<f64>,
+        ask: Option<f64>,
+        ts: Option<i64>,
+    ) -> PyResult<()> {
+        let ts = clock::require(ts, &self.clock)?;
+        self.sent(symbol, bid, ask, ts);
+        Ok(())
+    }
+
+    // (bid, ask) last sent for the symbol
+    pub fn last_quote(&self, symbol: &str) -> Option<(Option<f64>, Option<f64>)> {
+        self.symbols
+            .get(symbol)
+            .filter(|s| s.last_ts.is_some())
+            .map(|s| (s.bid, s.ask))
+    }
+
+    // Suppressed quotes by reason since construction or reset()
+    pub fn suppressed(&self) -> HashMap<&'static str, u64> {
+        self.suppressed.clone()
+    }
+
+    // Forget one symbol's history (e.g. after its orders were cancelled), or
+    // everything
+    #[pyo3(signature = (symbol=None))]
+    pub fn reset(&mut self, symbol: Option<&str>) {
+        match symbol {
+            Some(s) => {
+                self.symbols.remove(s);
+            }
+            None => {
+                self.symbols.clear();
+                self.suppressed.clear();
+            }
+        }
+    }
+}
@@ -0,0 +1,369 @@
+// Bounded single-producer single-consumer ring buffer, and EventQueue, its
+// Python face carrying FeedEvents from the feed thread (or one Python
+// producer) to the strategy loop without a lock on the hot path.
+//
+// Ring: `tail` is only advanced by the producer and `head` by the consumer,
+// each publishing with Release what the other reads with Acquire; indices
+// grow without bound and are masked into a power-of-two slot array. Nothing
+// enforces single ownership inside Ring itself, so EventQueue does:
+//   producer  one at a time: a FeedClient (queue=...) claims it for its
+//             lifetime; otherwise Python push() claims it, and pushes from
+//             Python threads are serialized by the GIL
+//   consumer  pop/drain take a flag for their duration; a second thread
+//             consuming concurrently gets RuntimeError
+// A full queue rejects the new event (push returns False; the feed counts
+// it in dropped), since only the consumer may free slots.
+// A blocked pop parks its thread after flagging that it sleeps; the producer
+// unparks it after publishing, and the consumer rechecks after flagging, so a
+// wakeup is never lost.
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::aio::{EventSource, NextEvent, Waiters};
+use crate::feed::FeedEvent;
+
+// Keeps head and tail on separate cache lines
+#[repr(align(128))]
+struct Padded<T>(T);
+
+pub(crate) struct Ring<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    mask: usize,
+    // Next index to read
+    head: Padded<AtomicUsize>,
+    // Next index to write
+    tail: Padded<AtomicUsize>,
+}
+
+// Slots are handed between the two sides through head/tail
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    // Capacity is rounded up to a power of two
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1).next_power_of_two();
+        Self {
+            slots: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            mask: capacity - 1,
+            head: Padded(AtomicUsize::new(0)),
+            tail: Padded(AtomicUsize::new(0)),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        let head = self.head.0.load(Ordering::Acquire);
+        let tail = self.tail.0.load(Ordering::Acquire);
+        tail.wrapping_sub(head).min(self.capacity())
+    }
+
+    // The value back if the ring is full.
+    // SAFETY: callers must be the only producer.
+    pub(crate) unsafe fn push(&self, value: T) -> Result<(), T> {
+        let tail = self.tail.0.load(Ordering::Relaxed);
+        let head = self.head.0.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == self.capacity() {
+            return Err(value);
+        }
+        // SAFETY: the slot is free (the consumer has moved past it) and only
+        // this producer writes slots
+        unsafe { (*self.slots[tail & self.mask].get()).write(value) };
+        self.tail.0.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    // SAFETY: callers must be the only consumer.
+    pub(crate) unsafe fn pop(&self) -> Option<T> {
+        let head = self.head.0.load(Ordering::Relaxed);
+        let tail = self.tail.0.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // SAFETY: the producer published this slot before advancing tail,
+        // and it is read once before head moves past it
+        let value = unsafe { (*self.slots[head & self.mask].get()).assume_init_read() };
+        self.head.0.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        // SAFETY: &mut self, so no other producer or consumer exists
+        while unsafe { self.pop() }.is_some() {}
+    }
+}
+
+const NO_PRODUCER: u8 = 0;
+const PYTHON_PRODUCER: u8 = 1;
+const FEED_PRODUCER: u8 = 2;
+
+struct Inner {
+    ring: Ring<FeedEvent>,
+    producer: AtomicU8,
+    consuming: AtomicBool,
+    // The parked consumer, set while `sleeping`
+    sleeping: AtomicBool,
+    waiter: Mutex<Option<Thread>>,
+    // Tasks awaiting next_event()
+    waiters: Waiters,
+    dropped: AtomicU64,
+}
+
+impl Inner {
+    // Producer side; false when full
+    fn push(&self, event: FeedEvent) -> bool {
+        // SAFETY: the caller holds the producer role
+        if unsafe { self.ring.push(event) }.is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        // Orders the tail store before the sleeping check; pairs with wait
+        fence(Ordering::SeqCst);
+        if self.sleeping.load(Ordering::SeqCst) {
+            if let Some(t) = self
+                .waiter
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_ref()
+            {
+                t.unpark();
+            }
+        }
+        self.waiters.wake();
+        true
+    }
+
+    // Wait until an event is queued or `deadline` passes
+    fn wait(&self, deadline: Instant) {
+        *self.waiter.lock().unwrap_or_else(PoisonError::into_inner) = Some(thread::current());
+        self.sleeping.store(true, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        while self.ring.len() == 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::park_timeout(deadline - now);
+        }
+        self.sleeping.store(false, Ordering::SeqCst);
+    }
+}
+
+impl EventSource for Inner {
+    fn try_next(&self) -> PyResult<Option<FeedEvent>> {
+        Ok(Consumer::claim(self)?.pop())
+    }
+
+    fn waiters(&self) -> &Waiters {
+        &self.waiters
+    }
+}
+
+// The feed thread's claim on the producer role, released on drop
+pub(crate) struct Producer(Arc<Inner>);
+
+impl Producer {
+    pub(crate) fn push(&self, event: FeedEvent) -> bool {
+        self.0.push(event)
+    }
+}
+
+impl Drop for Producer {
+    fn drop(&mut self) {
+        self.0.producer.store(NO_PRODUCER, Ordering::Release);
+    }
+}
+
+// Holds the consumer role for one pop/drain
+struct Consumer<'a>(&'a Inner);
+
+impl<'a> Consumer<'a> {
+    fn claim(inner: &'a Inner) -> PyResult<Self> {
+        if inner.consuming.swap(true, Ordering::Acquire) {
+            return Err(PyRuntimeError::new_err(
+                "EventQueue is being consumed by another thread",
+            ));
+        }
+        Ok(Self(inner))
+    }
+
+    fn pop(&self) -> Option<FeedEvent> {
+        // SAFETY: the consuming flag makes this the only consumer
+        unsafe { self.0.ring.pop() }
+    }
+}
+
+impl Drop for Consumer<'_> {
+    fn drop(&mut self) {
+        self.0.consuming.store(false, Ordering::Release);
+    }
+}
+
+// Longest a blocked pop stays in Rust before checking for KeyboardInterrupt
+const SIGNAL_CHECK: Duration = Duration::from_millis(50);
+
+#[pyclass(frozen)]
+pub struct EventQueue {
+    inner: Arc<Inner>,
+}
+
+impl EventQueue {
+    // Claim the producer role for a feed thread
+    pub(crate) fn producer(&self) -> PyResult<Producer> {
+        self.inner
+            .producer
+            .compare_exchange(
+                NO_PRODUCER,
+                FEED_PRODUCER,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .map_err(|_| PyValueError::new_err("EventQueue already has a producer"))?;
+        Ok(Producer(Arc::clone(&self.inner)))
+    }
+
+    // Wait up to `timeout` (forever with None) for an event, releasing the
+    // GIL and checking signals every SIGNAL_CHECK; true once one is queued
+    fn wait(&self, py: Python<'_>, timeout: Option<Duration>) -> PyResult<bool> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        loop {
+            if self.inner.ring.len() > 0 {
+                return Ok(true);
+            }
+            let now = Instant::now();
+            if deadline.is_some_and(|d| now >= d) {
+                return Ok(false);
+            }
+            let slice = deadline.map_or(now + SIGNAL_CHECK, |d| d.min(now + SIGNAL_CHECK));
+            py.allow_threads(|| self.inner.wait(slice));
+            py.check_signals()?;
+        }
+    }
+}
+
+fn timeout(timeout_ms: Option<f64>) -> PyResult<Option<Duration>> {
+    match timeout_ms {
+        Some(ms) if ms.is_nan() || ms < 0.0 => {
+            Err(PyValueError::new_err("timeout_ms must be non-negative"))
+        }
+        Some(ms) if ms.is_infinite() => Ok(None),
+        Some(ms) => Ok(Some(Duration::from_secs_f64(ms / 1000.0))),
+        None => Ok(None),
+    }
+}
+
+#[pymethods]
+impl EventQueue {
+    // capacity is rounded up to a power of two
+    #[new]
+    #[pyo3(signature = (capacity=65_536))]
+    pub fn new(capacity: usize) -> PyResult<Self> {
+        if capacity == 0 {
+            return Err(PyValueError::new_err("capacity must be positive"));
+        }
+        Ok(Self {
+            inner: Arc::new(Inner {
+                ring: Ring::new(capacity),
+                producer: AtomicU8::new(NO_PRODUCER),
+                consuming: AtomicBool::new(false),
+                sleeping: AtomicBool::new(false),
+                waiter: Mutex::new(None),
+                waiters: Waiters::default(),
+                dropped: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    // Queue an event from Python; False (and counted in dropped) when full.
+    // Not allowed while a FeedClient produces into the queue.
+    pub fn push(&self, event: FeedEvent) -> PyResult<bool> {
+        match self.inner.producer.compare_exchange(
+            NO_PRODUCER,
+            PYTHON_PRODUCER,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) | Err(PYTHON_PRODUCER) => Ok(self.inner.push(event)),
+            Err(_) => Err(PyValueError::new_err("EventQueue is fed by a FeedClient")),
+        }
+    }
+
+    // Next event, or None. timeout_ms=0 never blocks; otherwise waits up to
+    // timeout_ms (forever with None) with the GIL released.
+    #[pyo3(signature = (timeout_ms=None))]
+    pub fn pop(&self, py: Python<'_>, timeout_ms: Option<f64>) -> PyResult<Option<FeedEvent>> {
+        let consumer = Consumer::claim(&self.inner)?;
+        if let Some(event) = consumer.pop() {
+            return Ok(Some(event));
+        }
+        if self.wait(py, timeout(timeout_ms)?)? {
+            return Ok(consumer.pop());
+        }
+        Ok(None)
+    }
+
+    // pop(timeout_ms=0)
+    pub fn pop_nowait(&self) -> PyResult<Option<FeedEvent>> {
+        Ok(Consumer::claim(&self.inner)?.pop())
+    }
+
+    // Awaitable for the next event: `event = await queue.next_event()`
+    pub fn next_event(&self) -> NextEvent {
+        NextEvent::new(Arc::clone(&self.inner) as _)
+    }
+
+    // Up to max_events queued events in order, waiting up to timeout_ms for
+    // the first as in FeedClient.poll
+    #[pyo3(signature = (max_events=usize::MAX, timeout_ms=0.0))]
+    pub fn drain(
+        &self,
+        py: Python<'_>,
+        max_events: usize,
+        timeout_ms: Option<f64>,
+    ) -> PyResult<Vec<FeedEvent>> {
+        let consumer = Consumer::claim(&self.inner)?;
+        if self.inner.ring.len() == 0 && max_events > 0 {
+            self.wait(py, timeout(timeout_ms)?)?;
+        }
+        let n = max_events.min(self.inner.ring.len());
+        Ok((0..n).map_while(|_| consumer.pop()).collect())
+    }
+
+    #[getter]
+    pub fn capacity(&self) -> usize {
+        self.inner.ring.capacity()
+    }
+
+    // Events rejected because the queue was full
+    #[getter]
+    pub fn dropped(&self) -> u64 {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.ring.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "EventQueue(len={}, capacity={}, dropped={})",
+            self.inner.ring.len(),
+            self.capacity(),
+            self.dropped()
+        )
+    }
+}
@@ -0,0 +1,201 @@
+// Kill switch: latches `halted` with a reason as soon as any configured
+// limit is breached; only reset() clears it. Timestamps are caller-supplied
+// milliseconds so the guard behaves the same live and in replay.
+use std::collections::VecDeque;
+
+use pyo3::prelude::*;
+
+#[pyclass]
+pub struct RiskGuard {
+    // Limits; None disables the check
+    #[pyo3(get, set)]
+    pub max_loss: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_drawdown: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_position_notional: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_reject_rate: Option<f64>,
+    #[pyo3(get, set)]
+    pub max_feed_age_ms: Option<i64>,
+    reject_window: usize,
+    min_orders: usize,
+
+    realized_pnl: f64,
+    unrealized_pnl: f64,
+    peak_equity: f64,
+    position_notional: f64,
+    // true = rejected, over the last reject_window orders
+    orders: VecDeque<bool>,
+    rejects: usize,
+    last_feed_ts: Option<i64>,
+
+    halted: bool,
+    trip_reason: Option<String>,
+}
+
+impl RiskGuard {
+    fn equity(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl
+    }
+
+    fn breach(&self, now_ms: Option<i64>) -> Option<String> {
+        let equity = self.equity();
+        if let Some(limit) = self.max_loss {
+            if equity <= -limit {
+                return Some(format!("loss {:.4} exceeds max_loss {}", -equity, limit));
+            }
+        }
+        if let Some(limit) = self.max_drawdown {
+            let dd = self.drawdown();
+            if dd >= limit {
+                return Some(format!("drawdown {:.4} exceeds max_drawdown {}", dd, limit));
+            }
+        }
+        if let Some(limit) = self.max_position_notional {
+            if self.position_notional.abs() > limit {
+                return Some(format!(
+                    "position notional {:.4} exceeds max_position_notional {}",
+                    self.position_notional.abs(),
+                    limit
+                ));
+            }
+        }
+        if let (Some(limit), Some(rate)) = (self.max_reject_rate, self.reject_rate()) {
+            if self.orders.len() >= self.min_orders && rate > limit {
+                return Some(format!(
+                    "reject rate {:.3} exceeds max_reject_rate {}",
+                    rate, limit
+                ));
+            }
+        }
+        if let (Some(limit), Some(now)) = (self.max_feed_age_ms, now_ms) {
+            match self.last_feed_ts {
+                Some(ts) if now - ts > limit => {
+                    return Some(format!(
+                        "feed stale for {} ms (max_feed_age_ms {})",
+                        now - ts,
+                        limit
+                    ))
+                }
+                None => return Some("no market data received".to_string()),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+#[pymethods]
+impl RiskGuard {
+    #[new]
+    #[pyo3(signature = (
+        max_loss=None,
+        max_drawdown=None,
+        max_position_notional=None,
+        max_reject_rate=None,
+        max_feed_age_ms=None,
+        reject_window=100,
+        min_orders=20
+    ))]
+    pub fn new(
+        max_loss: Option<f64>,
+        max_drawdown: Option<f64>,
+        max_position_notional: Option<f64>,
+        max_reject_rate: Option<f64>,
+        max_feed_age_ms: Option<i64>,
+        reject_window: usize,
+        min_orders: usize,
+    ) -> Self {
+        Self {
+            max_loss,
+            max_drawdown,
+            max_position_notional,
+            max_reject_rate,
+            max_feed_age_ms,
+            reject_window: reject_window.max(1),
+            min_orders,
+            realized_pnl: 0.0,
+            unrealized_pnl: 0.0,
+            peak_equity: 0.0,
+            position_notional: 0.0,
+            orders: VecDeque::new(),
+            rejects: 0,
+            last_feed_ts: None,
+            halted: false,
+            trip_reason: None,
+        }
+    }
+
+    #[pyo3(signature = (realized, unrealized=0.0))]
+    pub fn update_pnl(&mut self, realized: f64, unrealized: f64) {
+        self.realized_pnl = realized;
+        self.unrealized_pnl = unrealized;
+        self.peak_equity = self.peak_equity.max(self.equity());
+    }
+
+    pub fn update_position(&mut self, notional: f64) {
+        self.position_notional = notional;
+    }
+
+    pub fn record_order(&mut self, rejected: bool) {
+        self.orders.push_back(rejected);
+        self.rejects += rejected as usize;
+        if self.orders.len() > self.reject_window {
+            if let Some(true) = self.orders.pop_front() {
+                self.rejects -= 1;
+            }
+        }
+    }
+
+    pub fn on_feed(&mut self, ts_ms: i64) {
+        self.last_feed_ts = Some(self.last_feed_ts.map_or(ts_ms, |t| t.max(ts_ms)));
+    }
+
+    // Run every check; trips (and stays) halted on the first breach.
+    // Feed staleness is only checked when now_ms is given.
+    #[pyo3(signature = (now_ms=None))]
+    pub fn evaluate(&mut self, now_ms: Option<i64>) -> bool {
+        if !self.halted {
+            if let Some(reason) = self.breach(now_ms) {
+                self.halted = true;
+                self.trip_reason = Some(reason);
+            }
+        }
+        self.halted
+    }
+
+    // Manual kill switch
+    pub fn halt(&mut self, reason: &str) {
+        self.halted = true;
+        self.trip_reason = Some(reason.to_string());
+    }
+
+    pub fn reset(&mut self) {
+        self.halted = false;
+        self.trip_reason = None;
+        self.orders.clear();
+        self.rejects = 0;
+        self.peak_equity = self.equity();
+    }
+
+    #[getter]
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    #[getter]
+    pub fn trip_reason(&self) -> Option<String> {
+        self.trip_reason.clone()
+    }
+
+    #[getter]
+    pub fn drawdown(&self) -> f64 {
+        (self.peak_equity - self.equity()).max(0.0)
+    }
+
+    #[getter]
+    pub fn reject_rate(&self) -> Option<f64> {
+        (!self.orders.is_empty()).then(|| self.rejects as f64 / self.orders.len() as f64)
+    }
+}
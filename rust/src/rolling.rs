@@ -0,0 +1,267 @@
+// Rolling-window statistics over a stream of floats, windowed by count
+// and/or milliseconds. Mean and variance come from running sums (shifted by
+// the first value of the window to limit cancellation), min/max from
+// monotonic deques, both O(1) amortized per update. Median and percentiles
+// use a sorted copy of the window kept up to date by binary search, so one
+// update costs O(log n) plus a memmove, and a quantile query is O(1).
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::clock::{self, Clock};
+use crate::state::{self, persist_fields, Stateful};
+
+#[pyclass]
+#[derive(Clone)]
+pub struct RollingStats {
+    window: Option<usize>,
+    window_ms: Option<i64>,
+    // (ts, value) in arrival order
+    values: VecDeque<(i64, f64)>,
+    sorted: Vec<f64>,
+    // Monotonic (seq, value) queues: increasing for min, decreasing for max
+    min_queue: VecDeque<(u64, f64)>,
+    max_queue: VecDeque<(u64, f64)>,
+    pushed: u64,
+    shift: f64,
+    sum: f64,
+    sum_sq: f64,
+    last_ts: i64,
+    clock: Option<Clock>,
+}
+
+persist_fields!(RollingStats {
+    window,
+    window_ms,
+    values,
+    sorted,
+    min_queue,
+    max_queue,
+    pushed,
+    shift,
+    sum,
+    sum_sq,
+    last_ts,
+    clock
+});
+
+impl Stateful for RollingStats {
+    const NAME: &'static str = "RollingStats";
+}
+
+impl RollingStats {
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn evict_one(&mut self) {
+        let Some((_, v)) = self.values.pop_front() else {
+            return;
+        };
+        let i = self.sorted.partition_point(|x| *x < v);
+        self.sorted.remove(i);
+        let d = v - self.shift;
+        self.sum -= d;
+        self.sum_sq -= d * d;
+        let first_live = self.pushed - self.values.len() as u64;
+        for q in [&mut self.min_queue, &mut self.max_queue] {
+            while q.front().is_some_and(|(seq, _)| *seq < first_live) {
+                q.pop_front();
+            }
+        }
+        if self.values.is_empty() {
+            // Reset running sums so float error does not accumulate
+            self.sum = 0.0;
+            self.sum_sq = 0.0;
+        }
+    }
+
+    fn quantile(&self, q: f64) -> Option<f64> {
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+        let rank = q * (n - 1) as f64;
+        let lo = rank.floor() as usize;
+        let hi = (lo + 1).min(n - 1);
+        let frac = rank - lo as f64;
+        Some(self.sorted[lo] + (self.sorted[hi] - self.sorted[lo]) * frac)
+    }
+
+    fn evict(&mut self) {
+        if let Some(n) = self.window {
+            while self.values.len() > n {
+                self.evict_one();
+            }
+        }
+        if let Some(ms) = self.window_ms {
+            let cutoff = self.last_ts - ms;
+            while self.values.front().is_some_and(|(ts, _)| *ts <= cutoff) {
+                self.evict_one();
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl RollingStats {
+    // window bounds the window by count, window_ms by the timestamps passed
+    // to push() (or the clock's time when omitted); at least one is required
+    #[new]
+    #[pyo3(signature = (window=Some(100), window_ms=None, clock=None))]
+    pub fn new(
+        window: Option<usize>,
+        window_ms: Option<i64>,
+        clock: Option<Clock>,
+    ) -> PyResult<Self> {
+        if window.is_none() && window_ms.is_none() {
+            return Err(PyValueError::new_err(
+                "either window or window_ms must be set",
+            ));
+        }
+        if window == Some(0) || window_ms.is_some_and(|ms| ms <= 0) {
+            return Err(PyValueError::new_err("window sizes must be positive"));
+        }
+        Ok(Self {
+            window,
+            window_ms,
+            values: VecDeque::new(),
+            sorted: Vec::new(),
+            min_queue: VecDeque::new(),
+            max_queue: VecDeque::new(),
+            pushed: 0,
+            shift: 0.0,
+            sum: 0.0,
+            sum_sq: 0.0,
+            last_ts: 0,
+            clock,
+        })
+    }
+
+    #[pyo3(signature = (value, ts=None))]
+    pub fn push(&mut self, value: f64, ts: Option<i64>) -> PyResult<()> {
+        if !value.is_finite() {
+            return Err(PyValueError::new_err(format!(
+                "value must be finite, got {}",
+                value
+            )));
+        }
+        if self.values.is_empty() {
+            self.shift = value;
+        }
+        self.last_ts = clock::resolve(ts, &self.clock).unwrap_or(self.last_ts);
+        let seq = self.pushed;
+        self.pushed += 1;
+        self.values.push_back((self.last_ts, value));
+        let i = self.sorted.partition_point(|x| *x < value);
+        self.sorted.insert(i, value);
+        let d = value - self.shift;
+        self.sum += d;
+        self.sum_sq += d * d;
+        while self.min_queue.back().is_some_and(|(_, v)| *v >= value) {
+            self.min_queue.pop_back();
+        }
+        self.min_queue.push_back((seq, value));
+        while self.max_queue.back().is_some_and(|(_, v)| *v <= value) {
+            self.max_queue.pop_back();
+        }
+        self.max_queue.push_back((seq, value));
+        self.evict();
+        Ok(())
+    }
+
+    // Push several values sharing one timestamp
+    #[pyo3(signature = (values, ts=None))]
+    pub fn extend(&mut self, values: Vec<f64>, ts: Option<i64>) -> PyResult<()> {
+        values.into_iter().try_for_each(|v| self.push(v, ts))
+    }
+
+    // Drop values that fell out of the time window as of `ts`, without
+    // pushing a new one; `ts` defaults to the clock's time
+    #[pyo3(signature = (ts=None))]
+    pub fn advance(&mut self, ts: Option<i64>) -> PyResult<()> {
+        self.last_ts = self.last_ts.max(clock::require(ts, &self.clock)?);
+        self.evict();
+        Ok(())
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum + self.shift * self.values.len() as f64
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let n = self.values.len() as f64;
+        (n > 0.0).then(|| self.shift + self.sum / n)
+    }
+
+    // ddof=0 is the population variance, ddof=1 the sample variance
+    #[pyo3(signature = (ddof=0))]
+    pub fn var(&self, ddof: usize) -> Option<f64> {
+        let n = self.values.len();
+        if n <= ddof {
+            return None;
+        }
+        let ss = self.sum_sq - self.sum * self.sum / n as f64;
+        Some(ss.max(0.0) / (n - ddof) as f64)
+    }
+
+    #[pyo3(signature = (ddof=0))]
+    pub fn std(&self, ddof: usize) -> Option<f64> {
+        self.var(ddof).map(f64::sqrt)
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min_queue.front().map(|(_, v)| *v)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.max_queue.front().map(|(_, v)| *v)
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    // q in [0, 100], linearly interpolated between ranks like numpy
+    pub fn percentile(&self, q: f64) -> PyResult<Option<f64>> {
+        if !(0.0..=100.0).contains(&q) {
+            return Err(PyValueError::new_err(format!(
+                "percentile must be in [0, 100], got {}",
+                q
+            )));
+        }
+        Ok(self.quantile(q / 100.0))
+    }
+
+    #[getter]
+    pub fn last(&self) -> Option<f64> {
+        self.values.back().map(|(_, v)| *v)
+    }
+
+    pub fn reset(&mut self) {
+        self.values.clear();
+        self.sorted.clear();
+        self.min_queue.clear();
+        self.max_queue.clear();
+        self.sum = 0.0;
+        self.sum_sq = 0.0;
+    }
+
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
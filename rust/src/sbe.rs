@@ -0,0 +1,1071 @@
+// Simple Binary Encoding (FIX SBE 1.0) decoding driven by an XML message
+// schema (see xml.rs), so binary market-data feeds go straight into L2Book
+// without JSON. Supported: primitive types and fixed-length char arrays,
+// constants, composites (a composite with mantissa and exponent members
+// decodes to a float), enums, sets, repeating groups (nested, with their own
+// dimension composites) and var-length data; both byte orders.
+// Schema evolution: fields past a block's encoded blockLength decode as
+// None, and the header's blockLength is used to find the groups, so older
+// and newer versions of a message both decode.
+// map_book() tells apply() how a template's levels map onto a book: either
+// separate bid and ask groups, or one group with a side field (plus an
+// optional action field whose delete values remove the level).
+use std::collections::HashMap;
+use std::fs;
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+use crate::events::BookUpdate;
+use crate::xml::{self, Element};
+use crate::{L2Book, Levels};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Prim {
+    Char,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl Prim {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" => Self::Char,
+            "int8" => Self::I8,
+            "int16" => Self::I16,
+            "int32" => Self::I32,
+            "int64" => Self::I64,
+            "uint8" => Self::U8,
+            "uint16" => Self::U16,
+            "uint32" => Self::U32,
+            "uint64" => Self::U64,
+            "float" => Self::F32,
+            "double" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Char | Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::I64 | Self::U64 | Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Ty {
+    Prim {
+        prim: Prim,
+        length: usize,
+        constant: Option<String>,
+        null: Option<f64>,
+    },
+    Composite(Vec<(String, usize, Ty)>),
+    Enum {
+        encoding: Prim,
+        values: Vec<(String, String)>,
+    },
+    Set {
+        encoding: Prim,
+        choices: Vec<(u32, String)>,
+    },
+    // varData: length prefix then bytes; text when a characterEncoding is set
+    Var {
+        length: Prim,
+        text: bool,
+    },
+}
+
+impl Ty {
+    fn size(&self) -> usize {
+        match self {
+            Ty::Prim {
+                constant: Some(_), ..
+            } => 0,
+            Ty::Prim { prim, length, .. } => prim.size() * length,
+            Ty::Composite(members) => members
+                .iter()
+                .map(|(_, off, ty)| off + ty.size())
+                .max()
+                .unwrap_or(0),
+            Ty::Enum { encoding, .. } | Ty::Set { encoding, .. } => encoding.size(),
+            Ty::Var { length, .. } => length.size(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    name: String,
+    offset: usize,
+    ty: Ty,
+    // presence="constant" fields carry their value in the schema
+    constant: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Block {
+    fields: Vec<Field>,
+    groups: Vec<Group>,
+    data: Vec<(String, Ty)>,
+}
+
+#[derive(Clone, Debug)]
+struct Group {
+    name: String,
+    dimension: Ty,
+    block: Block,
+}
+
+#[derive(Clone, Debug)]
+struct Message {
+    name: String,
+    id: u16,
+    block: Block,
+}
+
+// Decoded value
+#[derive(Clone, Debug, PartialEq)]
+enum Val {
+    Null,
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Set(Vec<String>),
+    Record(Vec<(String, Val)>),
+    List(Vec<Vec<(String, Val)>>),
+}
+
+impl Val {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Val::Int(v) => Some(*v as f64),
+            Val::UInt(v) => Some(*v as f64),
+            Val::Float(v) => Some(*v),
+            Val::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Val::Int(v) => Some(*v),
+            Val::UInt(v) => i64::try_from(*v).ok(),
+            Val::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Val::Int(v) => u64::try_from(*v).ok(),
+            Val::UInt(v) => Some(*v),
+            Val::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    // Text used to match side and action values: enum names, chars, numbers
+    fn label(&self) -> Option<String> {
+        match self {
+            Val::Int(v) => Some(v.to_string()),
+            Val::UInt(v) => Some(v.to_string()),
+            Val::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    fn to_py(&self, py: Python<'_>) -> PyResult<PyObject> {
+        Ok(match self {
+            Val::Null => py.None(),
+            Val::Int(v) => v.into_pyobject(py)?.into_any().unbind(),
+            Val::UInt(v) => v.into_pyobject(py)?.into_any().unbind(),
+            Val::Float(v) => v.into_pyobject(py)?.into_any().unbind(),
+            Val::Str(s) => s.into_pyobject(py)?.into_any().unbind(),
+            Val::Bytes(b) => PyBytes::new(py, b).into_any().unbind(),
+            Val::Set(names) => PyList::new(py, names)?.into_any().unbind(),
+            Val::Record(fields) => record_to_py(py, fields)?.into_any().unbind(),
+            Val::List(entries) => {
+                let list = PyList::empty(py);
+                for entry in entries {
+                    list.append(record_to_py(py, entry)?)?;
+                }
+                list.into_any().unbind()
+            }
+        })
+    }
+}
+
+fn record_to_py<'py>(py: Python<'py>, fields: &[(String, Val)]) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    for (name, v) in fields {
+        dict.set_item(name, v.to_py(py)?)?;
+    }
+    Ok(dict)
+}
+
+fn get<'a>(record: &'a [(String, Val)], name: &str) -> Option<&'a Val> {
+    record.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+}
+
+// ---- schema loading ----
+
+struct Schema {
+    big_endian: bool,
+    header: Ty,
+    messages: Vec<Message>,
+}
+
+fn attr_usize(el: &Element, name: &str) -> Result<Option<usize>, String> {
+    el.attr(name)
+        .map(|v| {
+            v.trim()
+                .parse()
+                .map_err(|_| format!("{} '{}' is not a number", name, v))
+        })
+        .transpose()
+}
+
+fn required<'a>(el: &'a Element, name: &str) -> Result<&'a str, String> {
+    el.attr(name)
+        .ok_or_else(|| format!("<{}> is missing '{}'", el.name, name))
+}
+
+struct Loader<'a> {
+    // Type definitions by name, resolved lazily
+    defs: HashMap<String, &'a Element>,
+    resolved: HashMap<String, Ty>,
+}
+
+impl<'a> Loader<'a> {
+    fn ty(&mut self, name: &str, depth: usize) -> Result<Ty, String> {
+        if let Some(prim) = Prim::parse(name) {
+            return Ok(Ty::Prim {
+                prim,
+                length: 1,
+                constant: None,
+                null: None,
+            });
+        }
+        if let Some(ty) = self.resolved.get(name) {
+            return Ok(ty.clone());
+        }
+        if depth > 32 {
+            return Err(format!("type '{}' is nested too deeply", name));
+        }
+        let el = *self
+            .defs
+            .get(name)
+            .ok_or_else(|| format!("unknown type '{}'", name))?;
+        let ty = self.define(el, depth + 1)?;
+        self.resolved.insert(name.to_string(), ty.clone());
+        Ok(ty)
+    }
+
+    fn encoding(&mut self, el: &Element, depth: usize) -> Result<Prim, String> {
+        match self.ty(required(el, "encodingType")?, depth)? {
+            Ty::Prim { prim, .. } => Ok(prim),
+            _ => Err(format!("'{}' needs a primitive encodingType", el.name)),
+        }
+    }
+
+    fn define(&mut self, el: &'a Element, depth: usize) -> Result<Ty, String> {
+        match el.local() {
+            "type" => {
+                let base = self.ty(required(el, "primitiveType")?, depth)?;
+                let Ty::Prim { prim, .. } = base else {
+                    return Err(format!("type '{}' needs a primitive", el.name));
+                };
+                let constant = (el.attr("presence") == Some("constant")).then(|| el.text.clone());
+                Ok(Ty::Prim {
+                    prim,
+                    length: attr_usize(el, "length")?.unwrap_or(1),
+                    constant,
+                    null: el.attr("nullValue").and_then(|v| v.parse().ok()),
+                })
+            }
+            "composite" => {
+                let mut members = Vec::new();
+                let mut offset = 0;
+                for m in &el.children {
+                    let ty = match m.local() {
+                        "ref" => self.ty(required(m, "type")?, depth)?,
+                        _ => self.define(m, depth + 1)?,
+                    };
+                    let at = attr_usize(m, "offset")?.unwrap_or(offset);
+                    offset = at + ty.size();
+                    let name = required(m, "name")?.to_string();
+                    // varData in a data encoding composite
+                    if name == "varData" {
+                        let length = members
+                            .iter()
+                            .find(|(n, _, _): &&(String, usize, Ty)| n == "length")
+                            .and_then(|(_, _, t)| match t {
+                                Ty::Prim { prim, .. } => Some(*prim),
+                                _ => None,
+                            })
+                            .ok_or("varData composite needs a length member")?;
+                        return Ok(Ty::Var {
+                            length,
+                            text: m.attr("characterEncoding").is_some(),
+                        });
+                    }
+                    members.push((name, at, ty));
+                }
+                Ok(Ty::Composite(members))
+            }
+            "enum" => Ok(Ty::Enum {
+                encoding: self.encoding(el, depth)?,
+                values: el
+                    .elements("validValue")
+                    .map(|v| Ok((v.text.clone(), required(v, "name")?.to_string())))
+                    .collect::<Result<_, String>>()?,
+            }),
+            "set" => Ok(Ty::Set {
+                encoding: self.encoding(el, depth)?,
+                choices: el
+                    .elements("choice")
+                    .map(|c| {
+                        let bit = c
+                            .text
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("choice '{}' needs a bit number", c.name))?;
+                        Ok((bit, required(c, "name")?.to_string()))
+                    })
+                    .collect::<Result<_, String>>()?,
+            }),
+            other => Err(format!("unexpected <{}> in types", other)),
+        }
+    }
+
+    fn block(&mut self, el: &Element) -> Result<Block, String> {
+        let mut block = Block::default();
+        let mut offset = 0;
+        for child in &el.children {
+            match child.local() {
+                "field" => {
+                    let ty = self.ty(required(child, "type")?, 0)?;
+                    let constant = if child.attr("presence") == Some("constant") {
+                        // valueRef="Enum.Value" or inline text
+                        Some(child.attr("valueRef").map_or_else(
+                            || child.text.clone(),
+                            |r| r.rsplit('.').next().unwrap_or(r).to_string(),
+                        ))
+                    } else {
+                        match &ty {
+                            Ty::Prim {
+                                constant: Some(c), ..
+                            } => Some(c.clone()),
+                            _ => None,
+                        }
+                    };
+                    let at = attr_usize(child, "offset")?.unwrap_or(offset);
+                    let size = if constant.is_some() { 0 } else { ty.size() };
+                    offset = at + size;
+                    block.fields.push(Field {
+                        name: required(child, "name")?.to_string(),
+                        offset: at,
+                        ty,
+                        constant,
+                    });
+                }
+                "group" => {
+                    let dimension = self.ty(
+                        child.attr("dimensionType").unwrap_or("groupSizeEncoding"),
+                        0,
+                    )?;
+                    block.groups.push(Group {
+                        name: required(child, "name")?.to_string(),
+                        dimension,
+                        block: self.block(child)?,
+                    });
+                }
+                "data" => {
+                    let ty = self.ty(required(child, "type")?, 0)?;
+                    if !matches!(ty, Ty::Var { .. }) {
+                        return Err(format!(
+                            "data '{}' needs a length + varData composite",
+                            child.attr("name").unwrap_or_default()
+                        ));
+                    }
+                    block.data.push((required(child, "name")?.to_string(), ty));
+                }
+                _ => {}
+            }
+        }
+        Ok(block)
+    }
+}
+
+fn load_schema(text: &str) -> Result<Schema, String> {
+    let root = xml::parse(text)?;
+    if root.local() != "messageSchema" {
+        return Err(format!(
+            "root element must be messageSchema, got '{}'",
+            root.name
+        ));
+    }
+    let mut defs = HashMap::new();
+    for types in root.elements("types") {
+        for t in &types.children {
+            if let Some(name) = t.attr("name") {
+                defs.insert(name.to_string(), t);
+            }
+        }
+    }
+    let mut loader = Loader {
+        defs,
+        resolved: HashMap::new(),
+    };
+    let header = loader.ty(root.attr("headerType").unwrap_or("messageHeader"), 0)?;
+    let messages = root
+        .elements("message")
+        .map(|m| {
+            Ok(Message {
+                name: required(m, "name")?.to_string(),
+                id: required(m, "id")?
+                    .parse()
+                    .map_err(|_| "message id must be a number".to_string())?,
+                block: loader.block(m)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(Schema {
+        big_endian: root.attr("byteOrder") == Some("bigEndian"),
+        header,
+        messages,
+    })
+}
+
+// ---- decoding ----
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, at: usize) -> Result<[u8; N], String> {
+        let slice = self
+            .buf
+            .get(at..at + N)
+            .ok_or_else(|| format!("truncated message: need {} bytes at offset {}", N, at))?;
+        let mut out = [0u8; N];
+        out.copy_from_slice(slice);
+        if self.big_endian {
+            out.reverse();
+        }
+        Ok(out)
+    }
+
+    // Raw value, before null handling
+    fn prim(&self, at: usize, prim: Prim) -> Result<Val, String> {
+        Ok(match prim {
+            Prim::Char => Val::Str(char::from(self.bytes::<1>(at)?[0]).to_string()),
+            Prim::I8 => Val::Int(i8::from_le_bytes(self.bytes(at)?) as i64),
+            Prim::I16 => Val::Int(i16::from_le_bytes(self.bytes(at)?) as i64),
+            Prim::I32 => Val::Int(i32::from_le_bytes(self.bytes(at)?) as i64),
+            Prim::I64 => Val::Int(i64::from_le_bytes(self.bytes(at)?)),
+            Prim::U8 => Val::UInt(u8::from_le_bytes(self.bytes(at)?) as u64),
+            Prim::U16 => Val::UInt(u16::from_le_bytes(self.bytes(at)?) as u64),
+            Prim::U32 => Val::UInt(u32::from_le_bytes(self.bytes(at)?) as u64),
+            Prim::U64 => Val::UInt(u64::from_le_bytes(self.bytes(at)?)),
+            Prim::F32 => Val::Float(f32::from_le_bytes(self.bytes(at)?) as f64),
+            Prim::F64 => Val::Float(f64::from_le_bytes(self.bytes(at)?)),
+        })
+    }
+
+    fn is_null(prim: Prim, v: &Val, null: Option<f64>) -> bool {
+        if let Some(n) = null {
+            return v.as_f64() == Some(n);
+        }
+        match (prim, v) {
+            (Prim::Char, Val::Str(s)) => s == "\0",
+            (Prim::I8, Val::Int(x)) => *x == i8::MIN as i64,
+            (Prim::I16, Val::Int(x)) => *x == i16::MIN as i64,
+            (Prim::I32, Val::Int(x)) => *x == i32::MIN as i64,
+            (Prim::I64, Val::Int(x)) => *x == i64::MIN,
+            (Prim::U8, Val::UInt(x)) => *x == u8::MAX as u64,
+            (Prim::U16, Val::UInt(x)) => *x == u16::MAX as u64,
+            (Prim::U32, Val::UInt(x)) => *x == u32::MAX as u64,
+            (Prim::U64, Val::UInt(x)) => *x == u64::MAX,
+            (_, Val::Float(x)) => x.is_nan(),
+            _ => false,
+        }
+    }
+
+    fn value(&self, at: usize, ty: &Ty) -> Result<Val, String> {
+        match ty {
+            Ty::Prim {
+                prim,
+                constant: Some(c),
+                ..
+            } => Ok(constant_val(*prim, c)),
+            Ty::Prim {
+                prim: Prim::Char,
+                length,
+                ..
+            } if *length > 1 => {
+                let raw = self
+                    .buf
+                    .get(at..at + length)
+                    .ok_or_else(|| format!("truncated message at offset {}", at))?;
+                let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+                Ok(match end {
+                    0 => Val::Null,
+                    _ => Val::Str(String::from_utf8_lossy(&raw[..end]).into_owned()),
+                })
+            }
+            Ty::Prim { prim, length, .. } if *length > 1 => {
+                let raw = self
+                    .buf
+                    .get(at..at + prim.size() * length)
+                    .ok_or_else(|| format!("truncated message at offset {}", at))?;
+                Ok(Val::Bytes(raw.to_vec()))
+            }
+            Ty::Prim { prim, null, .. } => {
+                let v = self.prim(at, *prim)?;
+                Ok(if Self::is_null(*prim, &v, *null) {
+                    Val::Null
+                } else {
+                    v
+                })
+            }
+            Ty::Composite(members) => {
+                let fields = members
+                    .iter()
+                    .map(|(name, off, t)| Ok((name.clone(), self.value(at + off, t)?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                // Decimal composites decode to a number
+                if let (Some(m), Some(e)) = (get(&fields, "mantissa"), get(&fields, "exponent")) {
+                    return Ok(match (m.as_f64(), e.as_i64()) {
+                        (Some(m), Some(e)) => Val::Float(pow10(m, e)),
+                        _ => Val::Null,
+                    });
+                }
+                Ok(Val::Record(fields))
+            }
+            Ty::Enum { encoding, values } => {
+                let raw = self.prim(at, *encoding)?;
+                if Self::is_null(*encoding, &raw, None) {
+                    return Ok(Val::Null);
+                }
+                let label = raw.label().unwrap_or_default();
+                Ok(values
+                    .iter()
+                    .find(|(v, _)| *v == label)
+                    .map_or(raw, |(_, name)| Val::Str(name.clone())))
+            }
+            Ty::Set { encoding, choices } => {
+                let bits = self.prim(at, *encoding)?.as_u64().unwrap_or(0);
+                Ok(Val::Set(
+                    choices
+                        .iter()
+                        .filter(|(bit, _)| *bit < 64 && bits & (1 << bit) != 0)
+                        .map(|(_, name)| name.clone())
+                        .collect(),
+                ))
+            }
+            Ty::Var { .. } => Err("varData outside a data field".into()),
+        }
+    }
+
+    // Fields at `start` within block_len bytes, then groups and data;
+    // returns the record and the offset just past it
+    fn block(
+        &self,
+        start: usize,
+        block_len: usize,
+        block: &Block,
+        depth: usize,
+    ) -> Result<(Vec<(String, Val)>, usize), String> {
+        if depth > 16 {
+            return Err("groups nested too deeply".into());
+        }
+        let mut record = Vec::with_capacity(block.fields.len() + block.groups.len());
+        for f in &block.fields {
+            let v = match &f.constant {
+                Some(c) => constant_val(Prim::Char, c),
+                None if f.offset + f.ty.size() > block_len => Val::Null,
+                None => self.value(start + f.offset, &f.ty)?,
+            };
+            record.push((f.name.clone(), v));
+        }
+        let mut pos = start + block_len;
+        for g in &block.groups {
+            let dim = match self.value(pos, &g.dimension)? {
+                Val::Record(dim) => dim,
+                _ => return Err(format!("group '{}' has no dimension composite", g.name)),
+            };
+            let entry_len = get(&dim, "blockLength")
+                .and_then(Val::as_u64)
+                .ok_or_else(|| format!("group '{}' dimension has no blockLength", g.name))?
+                as usize;
+            let count = get(&dim, "numInGroup")
+                .and_then(Val::as_u64)
+                .ok_or_else(|| format!("group '{}' dimension has no numInGroup", g.name))?
+                as usize;
+            pos += g.dimension.size();
+            if count.saturating_mul(entry_len) > self.buf.len().saturating_sub(pos) {
+                return Err(format!(
+                    "truncated message: group '{}' claims {} entries",
+                    g.name, count
+                ));
+            }
+            let mut entries = Vec::with_capacity(count);
+            for _ in 0..count {
+                let (entry, end) = self.block(pos, entry_len, &g.block, depth + 1)?;
+                entries.push(entry);
+                pos = end;
+            }
+            record.push((g.name.clone(), Val::List(entries)));
+        }
+        for (name, ty) in &block.data {
+            let Ty::Var { length, text } = ty else {
+                continue;
+            };
+            let n = self.prim(pos, *length)?.as_u64().unwrap_or(0) as usize;
+            pos += length.size();
+            let raw = self
+                .buf
+                .get(pos..pos + n)
+                .ok_or_else(|| format!("truncated message: data '{}'", name))?;
+            pos += n;
+            let v = if *text {
+                Val::Str(String::from_utf8_lossy(raw).into_owned())
+            } else {
+                Val::Bytes(raw.to_vec())
+            };
+            record.push((name.clone(), v));
+        }
+        Ok((record, pos))
+    }
+}
+
+// v * 10^e, dividing for negative exponents so decimals stay exact
+fn pow10(v: f64, e: i64) -> f64 {
+    let e = e.clamp(-308, 308) as i32;
+    if e < 0 {
+        v / 10f64.powi(-e)
+    } else {
+        v * 10f64.powi(e)
+    }
+}
+
+fn constant_val(prim: Prim, text: &str) -> Val {
+    let parsed = match prim {
+        Prim::F32 | Prim::F64 => text.parse().ok().map(Val::Float),
+        Prim::Char => None,
+        _ => text.parse().ok().map(Val::Int),
+    };
+    parsed.unwrap_or_else(|| Val::Str(text.to_string()))
+}
+
+// One decoded message
+struct Decoded {
+    message: usize,
+    schema_id: u64,
+    version: u64,
+    length: usize,
+    record: Vec<(String, Val)>,
+}
+
+// ---- book mapping ----
+
+#[derive(Clone, Debug)]
+enum Exponent {
+    Const(i32),
+    Field(String),
+}
+
+#[derive(Clone, Debug)]
+enum Layout {
+    Sides {
+        bids: String,
+        asks: String,
+    },
+    Single {
+        group: String,
+        side: String,
+        bid_values: Vec<String>,
+        ask_values: Vec<String>,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct BookMap {
+    layout: Layout,
+    price: String,
+    size: String,
+    action: Option<String>,
+    delete_values: Vec<String>,
+    price_exponent: Option<Exponent>,
+    size_exponent: Option<Exponent>,
+    update_id: Option<String>,
+    first_update_id: Option<String>,
+    snapshot: bool,
+}
+
+impl BookMap {
+    fn id(root: &[(String, Val)], name: &Option<String>) -> PyResult<Option<u64>> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        get(root, name)
+            .and_then(Val::as_u64)
+            .map(Some)
+            .ok_or_else(|| PyValueError::new_err(format!("update id field '{}' is missing", name)))
+    }
+
+    fn exponent(exp: &Option<Exponent>, root: &[(String, Val)]) -> Result<i64, String> {
+        match exp {
+            None => Ok(0),
+            Some(Exponent::Const(e)) => Ok(*e as i64),
+            Some(Exponent::Field(name)) => get(root, name)
+                .and_then(Val::as_i64)
+                .ok_or_else(|| format!("exponent field '{}' is missing", name)),
+        }
+    }
+
+    fn entries<'r>(
+        root: &'r [(String, Val)],
+        group: &str,
+    ) -> Result<&'r [Vec<(String, Val)>], String> {
+        match get(root, group) {
+            Some(Val::List(entries)) => Ok(entries),
+            _ => Err(format!("message has no group '{}'", group)),
+        }
+    }
+
+    // (bids, asks) with deletes as size 0
+    fn levels(&self, root: &[(String, Val)]) -> Result<(Levels, Levels), String> {
+        let (px_exp, qty_exp) = (
+            Self::exponent(&self.price_exponent, root)?,
+            Self::exponent(&self.size_exponent, root)?,
+        );
+        let level = |entry: &[(String, Val)]| -> Option<(f64, f64)> {
+            let price = pow10(get(entry, &self.price)?.as_f64()?, px_exp);
+            let deleted = self.action.as_ref().is_some_and(|a| {
+                get(entry, a)
+                    .and_then(Val::label)
+                    .is_some_and(|l| self.delete_values.contains(&l))
+            });
+            let size = match deleted {
+                true => 0.0,
+                false => pow10(
+                    get(entry, &self.size).and_then(Val::as_f64).unwrap_or(0.0),
+                    qty_exp,
+                ),
+            };
+            Some((price, size))
+        };
+        let mut bids = Levels::new();
+        let mut asks = Levels::new();
+        match &self.layout {
+            Layout::Sides { bids: b, asks: a } => {
+                bids.extend(Self::entries(root, b)?.iter().filter_map(|e| level(e)));
+                asks.extend(Self::entries(root, a)?.iter().filter_map(|e| level(e)));
+            }
+            Layout::Single {
+                group,
+                side,
+                bid_values,
+                ask_values,
+            } => {
+                for e in Self::entries(root, group)? {
+                    let Some(label) = get(e, side).and_then(Val::label) else {
+                        continue;
+                    };
+                    let Some(lvl) = level(e) else {
+                        continue;
+                    };
+                    if bid_values.contains(&label) {
+                        bids.push(lvl);
+                    } else if ask_values.contains(&label) {
+                        asks.push(lvl);
+                    }
+                }
+            }
+        }
+        Ok((bids, asks))
+    }
+}
+
+#[pyclass(frozen, get_all)]
+pub struct SbeMessage {
+    pub name: String,
+    pub template_id: u16,
+    pub schema_id: u64,
+    pub version: u64,
+    // Bytes the message occupied, header included
+    pub length: usize,
+    // Field, group (list of dicts) and data values by name
+    pub fields: Py<PyDict>,
+}
+
+#[pymethods]
+impl SbeMessage {
+    fn __repr__(&self) -> String {
+        format!(
+            "SbeMessage(name={:?}, template_id={}, length={})",
+            self.name, self.template_id, self.length
+        )
+    }
+}
+
+#[pyclass]
+pub struct SbeDecoder {
+    schema: Schema,
+    maps: HashMap<u16, BookMap>,
+}
+
+impl SbeDecoder {
+    fn decode_one(&self, data: &[u8]) -> Result<Decoded, String> {
+        let r = Reader {
+            buf: data,
+            big_endian: self.schema.big_endian,
+        };
+        let header = match r.value(0, &self.schema.header)? {
+            Val::Record(h) => h,
+            _ => return Err("message header must be a composite".into()),
+        };
+        let num = |name: &str| {
+            get(&header, name)
+                .and_then(Val::as_u64)
+                .ok_or_else(|| format!("message header has no '{}'", name))
+        };
+        let template = num("templateId")?;
+        let message = self
+            .schema
+            .messages
+            .iter()
+            .position(|m| u64::from(m.id) == template)
+            .ok_or_else(|| format!("unknown templateId {}", template))?;
+        let start = self.schema.header.size();
+        let (record, end) = r.block(
+            start,
+            num("blockLength")? as usize,
+            &self.schema.messages[message].block,
+            0,
+        )?;
+        Ok(Decoded {
+            message,
+            schema_id: num("schemaId").unwrap_or(0),
+            version: num("version").unwrap_or(0),
+            length: end,
+            record,
+        })
+    }
+
+    fn to_message(&self, py: Python<'_>, d: Decoded) -> PyResult<SbeMessage> {
+        let m = &self.schema.messages[d.message];
+        Ok(SbeMessage {
+            name: m.name.clone(),
+            template_id: m.id,
+            schema_id: d.schema_id,
+            version: d.version,
+            length: d.length,
+            fields: record_to_py(py, &d.record)?.unbind(),
+        })
+    }
+
+    fn template(&self, name: &str) -> PyResult<u16> {
+        self.schema
+            .messages
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.id)
+            .ok_or_else(|| PyKeyError::new_err(format!("unknown message '{}'", name)))
+    }
+}
+
+fn exponent(v: Option<&Bound<'_, PyAny>>) -> PyResult<Option<Exponent>> {
+    let Some(v) = v else {
+        return Ok(None);
+    };
+    if let Ok(e) = v.extract::<i32>() {
+        return Ok(Some(Exponent::Const(e)));
+    }
+    Ok(Some(Exponent::Field(v.extract::<String>().map_err(
+        |_| PyValueError::new_err("exponent must be an int or a root field name"),
+    )?)))
+}
+
+#[pymethods]
+impl SbeDecoder {
+    // schema: SBE XML message schema text
+    #[new]
+    pub fn new(schema: &str) -> PyResult<Self> {
+        let schema = load_schema(schema)
+            .map_err(|e| PyValueError::new_err(format!("invalid SBE schema: {}", e)))?;
+        Ok(Self {
+            schema,
+            maps: HashMap::new(),
+        })
+    }
+
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        Self::new(&fs::read_to_string(path)?)
+    }
+
+    // [(template_id, name), ...] in schema order
+    pub fn messages(&self) -> Vec<(u16, String)> {
+        self.schema
+            .messages
+            .iter()
+            .map(|m| (m.id, m.name.clone()))
+            .collect()
+    }
+
+    // Decode the message at the start of `data`
+    pub fn decode(&self, py: Python<'_>, data: &[u8]) -> PyResult<SbeMessage> {
+        let d = self.decode_one(data).map_err(PyValueError::new_err)?;
+        self.to_message(py, d)
+    }
+
+    // Decode back-to-back messages filling `data`
+    pub fn decode_all(&self, py: Python<'_>, data: &[u8]) -> PyResult<Vec<SbeMessage>> {
+        let mut out = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            let d = self
+                .decode_one(&data[pos..])
+                .map_err(PyValueError::new_err)?;
+            pos += d.length.max(1);
+            out.push(self.to_message(py, d)?);
+        }
+        Ok(out)
+    }
+
+    // Describe how `template` (message name) updates a book. Either
+    // bid_group + ask_group, or group + side with bid_values / ask_values
+    // (matched against enum names, chars or numbers as text). Exponents are
+    // an int or the name of a root field (e.g. Binance's priceExponent);
+    // decimal composites need none. update_id names a root field passed to
+    // the book's sequence check; with first_update_id (Binance U..u diffs)
+    // a diff continues the book when it starts at or before last + 1 and
+    // ends after last. snapshot=True replaces the book.
+    #[pyo3(signature = (template, price, size, bid_group=None, ask_group=None, group=None, side=None, bid_values=vec!["Bid".to_string(), "0".to_string()], ask_values=vec!["Offer".to_string(), "Ask".to_string(), "1".to_string()], action=None, delete_values=vec!["Delete".to_string(), "2".to_string()], price_exponent=None, size_exponent=None, update_id=None, first_update_id=None, snapshot=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn map_book(
+        &mut self,
+        template: &str,
+        price: String,
+        size: String,
+        bid_group: Option<String>,
+        ask_group: Option<String>,
+        group: Option<String>,
+        side: Option<String>,
+        bid_values: Vec<String>,
+        ask_values: Vec<String>,
+        action: Option<String>,
+        delete_values: Vec<String>,
+        price_exponent: Option<&Bound<'_, PyAny>>,
+        size_exponent: Option<&Bound<'_, PyAny>>,
+        update_id: Option<String>,
+        first_update_id: Option<String>,
+        snapshot: bool,
+    ) -> PyResult<()> {
+        let id = self.template(template)?;
+        let layout = match (bid_group, ask_group, group, side) {
+            (Some(bids), Some(asks), None, None) => Layout::Sides { bids, asks },
+            (None, None, Some(group), Some(side)) => Layout::Single {
+                group,
+                side,
+                bid_values,
+                ask_values,
+            },
+            _ => {
+                return Err(PyValueError::new_err(
+                    "give either bid_group and ask_group, or group and side",
+                ))
+            }
+        };
+        self.maps.insert(
+            id,
+            BookMap {
+                layout,
+                price,
+                size,
+                action,
+                delete_values,
+                price_exponent: exponent(price_exponent)?,
+                size_exponent: exponent(size_exponent)?,
+                update_id,
+                first_update_id,
+                snapshot,
+            },
+        );
+        Ok(())
+    }
+
+    // Decode the message at the start of `data` and apply it to `book` if
+    // its template was mapped. Returns whether the book changed; unmapped
+    // templates return False.
+    pub fn apply(&self, book: &mut L2Book, data: &[u8]) -> PyResult<bool> {
+        let d = self.decode_one(data).map_err(PyValueError::new_err)?;
+        let id = self.schema.messages[d.message].id;
+        let Some(map) = self.maps.get(&id) else {
+            return Ok(false);
+        };
+        let (bids, asks) = map.levels(&d.record).map_err(PyValueError::new_err)?;
+        let update_id = BookMap::id(&d.record, &map.update_id)?;
+        if map.snapshot {
+            book.load_snapshot(bids, asks, update_id)?;
+            return Ok(true);
+        }
+        let prev = match (
+            BookMap::id(&d.record, &map.first_update_id)?,
+            book.last_update_id,
+            update_id,
+        ) {
+            (Some(first), Some(last), Some(u)) if first <= last + 1 && last < u => Some(last),
+            (Some(first), _, _) => Some(first.saturating_sub(1)),
+            _ => None,
+        };
+        book.delta(bids, asks, update_id, prev, None)
+    }
+
+    // The message at the start of `data` as a normalized BookUpdate (see
+    // normalized.rs), without touching a book; None for unmapped templates.
+    // symbol defaults to the message's "symbol" field, if it has one.
+    #[pyo3(signature = (data, exchange, symbol=None))]
+    pub fn normalize(
+        &self,
+        data: &[u8],
+        exchange: &str,
+        symbol: Option<String>,
+    ) -> PyResult<Option<BookUpdate>> {
+        let d = self.decode_one(data).map_err(PyValueError::new_err)?;
+        let id = self.schema.messages[d.message].id;
+        let Some(map) = self.maps.get(&id) else {
+            return Ok(None);
+        };
+        let (bids, asks) = map.levels(&d.record).map_err(PyValueError::new_err)?;
+        Ok(Some(BookUpdate {
+            exchange: Some(exchange.to_string()),
+            symbol: symbol.or_else(|| get(&d.record, "symbol").and_then(Val::label)),
+            kind: if map.snapshot { "snapshot" } else { "delta" },
+            update_id: BookMap::id(&d.record, &map.update_id)?,
+            bids,
+            asks,
+            ..Default::default()
+        }))
+    }
+}
@@ -0,0 +1,132 @@
+// Book shape features for models, computed in one call per tick:
+//   bid_cum / ask_cum  cumulative size over the top `depth` levels, padded
+//                      with the last value when a side is shallower
+//   ratios             bid_cum / (bid_cum + ask_cum) per level
+//   slope              least-squares cumulative size per bp of distance
+//                      from mid
+//   convexity          quadratic term of a least-squares parabola through
+//                      the same points: positive when depth builds up
+//                      faster away from the touch
+// Descriptors are None with too few levels to fit (2 for slope, 3 for
+// convexity); features() flattens everything with NaN in their place.
+use pyo3::prelude::*;
+
+use crate::Levels;
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct BookShape {
+    pub bid_cum: Vec<f64>,
+    pub ask_cum: Vec<f64>,
+    pub ratios: Vec<f64>,
+    pub bid_slope: Option<f64>,
+    pub ask_slope: Option<f64>,
+    pub bid_convexity: Option<f64>,
+    pub ask_convexity: Option<f64>,
+}
+
+// Cumulative sizes padded to `depth`
+fn cumulative(levels: &Levels, depth: usize) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut cum: Vec<f64> = levels
+        .iter()
+        .map(|(_, s)| {
+            total += s;
+            total
+        })
+        .collect();
+    cum.resize(depth, total);
+    cum
+}
+
+// (slope, convexity) of cumulative size against distance from mid in bps
+fn fit(levels: &Levels, mid: f64) -> (Option<f64>, Option<f64>) {
+    let mut total = 0.0;
+    let points: Vec<(f64, f64)> = levels
+        .iter()
+        .map(|(p, s)| {
+            total += s;
+            ((p - mid).abs() / mid * 10_000.0, total)
+        })
+        .collect();
+    let n = points.len() as f64;
+    if points.len() < 2 {
+        return (None, None);
+    }
+    // Centre x for conditioning; neither coefficient depends on the shift
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut s2, mut s3, mut s4, mut t1, mut t2) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (x, y) in &points {
+        let (x, y) = (x - mean_x, y - mean_y);
+        s2 += x * x;
+        s3 += x * x * x;
+        s4 += x * x * x * x;
+        t1 += x * y;
+        t2 += x * x * y;
+    }
+    if s2 <= 0.0 {
+        return (None, None);
+    }
+    let slope = t1 / s2;
+    // Normal equations for y = b x + c (x^2 - s2/n) on centred data
+    let (v, w) = (s4 - s2 * s2 / n, s3);
+    let det = s2 * v - w * w;
+    let convexity =
+        (points.len() >= 3 && det.abs() > f64::EPSILON * s2 * v).then(|| (s2 * t2 - w * t1) / det);
+    (Some(slope), convexity)
+}
+
+impl BookShape {
+    pub fn new(bids: &Levels, asks: &Levels, mid: f64, depth: usize) -> Self {
+        let (bid_cum, ask_cum) = (cumulative(bids, depth), cumulative(asks, depth));
+        let ratios = bid_cum
+            .iter()
+            .zip(&ask_cum)
+            .map(|(b, a)| if b + a > 0.0 { b / (b + a) } else { 0.5 })
+            .collect();
+        let (bid_slope, bid_convexity) = fit(bids, mid);
+        let (ask_slope, ask_convexity) = fit(asks, mid);
+        Self {
+            bid_cum,
+            ask_cum,
+            ratios,
+            bid_slope,
+            ask_slope,
+            bid_convexity,
+            ask_convexity,
+        }
+    }
+}
+
+#[pymethods]
+impl BookShape {
+    // bid_cum, ask_cum, ratios, then bid/ask slope and bid/ask convexity:
+    // 3 * depth + 4 floats
+    pub fn features(&self) -> Vec<f64> {
+        let mut out = Vec::with_capacity(self.ratios.len() * 3 + 4);
+        out.extend(&self.bid_cum);
+        out.extend(&self.ask_cum);
+        out.extend(&self.ratios);
+        for d in [
+            self.bid_slope,
+            self.ask_slope,
+            self.bid_convexity,
+            self.ask_convexity,
+        ] {
+            out.push(d.unwrap_or(f64::NAN));
+        }
+        out
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BookShape(depth={}, bid_slope={:?}, ask_slope={:?}, bid_convexity={:?}, ask_convexity={:?})",
+            self.ratios.len(),
+            self.bid_slope,
+            self.ask_slope,
+            self.bid_convexity,
+            self.ask_convexity
+        )
+    }
+}
@@ -0,0 +1,202 @@
+// L2Book behind an RwLock, for a book written by one thread (a Rust feed
+// thread, or Python) while others read it. The class is frozen, so pyo3's
+// borrow flag never rejects a concurrent call; the lock orders them instead.
+// Deadlock freedom: the lock is never waited on while holding the GIL, and
+// the GIL is never taken while holding the lock. An uncontended lock is
+// taken with the GIL held (cheap); a contended one, or work over
+// RELEASE_GIL_LEVELS levels, runs entirely inside allow_threads.
+use std::sync::{Arc, PoisonError, RwLock, TryLockError};
+
+use pyo3::prelude::*;
+
+use crate::arrays::LevelsInput;
+use crate::events::Hooks;
+use crate::{L2Book, Levels, Side, RELEASE_GIL_LEVELS};
+
+#[pyclass(frozen)]
+pub struct SharedL2Book {
+    book: Arc<RwLock<L2Book>>,
+}
+
+impl SharedL2Book {
+    // Wrap a book that a Rust-side writer (e.g. the feed thread) also holds
+    pub(crate) fn from_handle(book: Arc<RwLock<L2Book>>) -> Self {
+        Self { book }
+    }
+
+    // A panic mid-update poisons the lock; the book is still usable (a
+    // snapshot resyncs it), so poisoning is ignored
+    fn read<T: Send>(
+        &self,
+        py: Python<'_>,
+        levels: usize,
+        f: impl FnOnce(&L2Book) -> T + Send,
+    ) -> T {
+        if levels < RELEASE_GIL_LEVELS {
+            match self.book.try_read() {
+                Ok(book) => return f(&book),
+                Err(TryLockError::Poisoned(e)) => return f(&e.into_inner()),
+                Err(TryLockError::WouldBlock) => {}
+            }
+        }
+        py.allow_threads(|| f(&self.book.read().unwrap_or_else(PoisonError::into_inner)))
+    }
+
+    fn write<T: Send>(
+        &self,
+        py: Python<'_>,
+        levels: usize,
+        f: impl FnOnce(&mut L2Book) -> T + Send,
+    ) -> T {
+        if levels < RELEASE_GIL_LEVELS {
+            match self.book.try_write() {
+                Ok(mut book) => return f(&mut book),
+                Err(TryLockError::Poisoned(e)) => return f(&mut e.into_inner()),
+                Err(TryLockError::WouldBlock) => {}
+            }
+        }
+        py.allow_threads(|| f(&mut self.book.write().unwrap_or_else(PoisonError::into_inner)))
+    }
+}
+
+#[pymethods]
+impl SharedL2Book {
+    #[new]
+    #[pyo3(signature = (raise_on_gap=false, cross_policy="ignore", tick_size=None))]
+    pub fn new(raise_on_gap: bool, cross_policy: &str, tick_size: Option<f64>) -> PyResult<Self> {
+        Ok(Self {
+            book: Arc::new(RwLock::new(L2Book::new(
+                raise_on_gap,
+                cross_policy,
+                tick_size,
+                None,
+                None,
+                false,
+                None,
+                false,
+            )?)),
+        })
+    }
+
+    #[pyo3(signature = (bids, asks, update_id=None))]
+    pub fn apply_snapshot(
+        &self,
+        py: Python<'_>,
+        bids: LevelsInput,
+        asks: LevelsInput,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        let levels = bids.len() + asks.len();
+        self.write(py, levels, |book| {
+            let (bids, asks) = (bids.levels(book.codec), asks.levels(book.codec));
+            book.load_snapshot(bids, asks, update_id)?;
+            crate::metrics::NATIVE.book_snapshots.inc(1.0);
+            Ok(())
+        })
+    }
+
+    // Same semantics as L2Book.apply_delta without return_update; the
+    // shared book has no callbacks
+    #[pyo3(signature = (bids, asks, update_id=None, prev_update_id=None))]
+    pub fn apply_delta(
+        &self,
+        py: Python<'_>,
+        bids: Levels,
+        asks: Levels,
+        update_id: Option<u64>,
+        prev_update_id: Option<u64>,
+    ) -> PyResult<bool> {
+        let levels = bids.len() + asks.len();
+        self.write(py, levels, |book| {
+            book.delta(bids, asks, update_id, prev_update_id, None)
+        })
+    }
+
+    // Consistent copy as a plain L2Book, for analytics that need several
+    // reads to agree with each other
+    pub fn snapshot(&self, py: Python<'_>) -> L2Book {
+        self.read(py, RELEASE_GIL_LEVELS, |book| L2Book {
+            hooks: Hooks::default(),
+            ..book.clone()
+        })
+    }
+
+    pub fn clear(&self, py: Python<'_>) {
+        self.write(py, 0, |book| book.clear())
+    }
+
+    #[getter]
+    pub fn best_bid(&self, py: Python<'_>) -> Option<(f64, f64)> {
+        self.read(py, 1, |book| book.best_bid())
+    }
+
+    #[getter]
+    pub fn best_ask(&self, py: Python<'_>) -> Option<(f64, f64)> {
+        self.read(py, 1, |book| book.best_ask())
+    }
+
+    #[getter]
+    pub fn last_update_id(&self, py: Python<'_>) -> Option<u64> {
+        self.read(py, 0, |book| book.last_update_id)
+    }
+
+    #[getter]
+    pub fn needs_resync(&self, py: Python<'_>) -> bool {
+        self.read(py, 0, |book| book.needs_resync)
+    }
+
+    #[getter]
+    pub fn gap_count(&self, py: Python<'_>) -> u64 {
+        self.read(py, 0, |book| book.gap_count)
+    }
+
+    pub fn bids(&self, py: Python<'_>, n: usize) -> Levels {
+        self.read(py, n.saturating_mul(2), |book| book.top(Side::Bid, n))
+    }
+
+    pub fn asks(&self, py: Python<'_>, n: usize) -> Levels {
+        self.read(py, n.saturating_mul(2), |book| book.top(Side::Ask, n))
+    }
+
+    pub fn depth(&self, py: Python<'_>, n: usize) -> (Levels, Levels) {
+        self.read(py, n.saturating_mul(2), |book| {
+            (book.top(Side::Bid, n), book.top(Side::Ask, n))
+        })
+    }
+
+    pub fn mid(&self, py: Python<'_>) -> PyResult<Option<f64>> {
+        self.read(py, 2, |book| book.mid())
+    }
+
+    pub fn spread(&self, py: Python<'_>) -> Option<f64> {
+        self.read(py, 2, |book| book.spread())
+    }
+
+    pub fn is_crossed(&self, py: Python<'_>) -> bool {
+        self.read(py, 2, |book| book.is_crossed())
+    }
+
+    #[pyo3(signature = (depth=1, decay=None))]
+    pub fn microprice(&self, py: Python<'_>, depth: usize, decay: Option<f64>) -> Option<f64> {
+        self.read(py, depth.saturating_mul(2), |book| {
+            book.weighted_microprice(depth, decay)
+        })
+    }
+
+    #[pyo3(signature = (depth, decay=None))]
+    pub fn imbalance(&self, py: Python<'_>, depth: usize, decay: Option<f64>) -> f64 {
+        self.read(py, depth.saturating_mul(2), |book| {
+            book.weighted_imbalance(depth, decay)
+        })
+    }
+
+    pub fn vwap_for_qty(
+        &self,
+        py: Python<'_>,
+        side: &str,
+        qty: f64,
+    ) -> PyResult<Option<(f64, f64, f64)>> {
+        Side::parse(side)?;
+        self.read(py, RELEASE_GIL_LEVELS, |book| book.sweep_cost(side, qty))
+    }
+}
@@ -0,0 +1,529 @@
+// L2Book mirrored into a memory-mapped file, for bots that run the feed and
+// the strategies in separate processes. One process creates the ShmBook and
+// applies updates to a private L2Book; after every update the top `levels`
+// of each side are published into the mapping. Any number of processes open
+// the same path read-only and read consistent copies without locks or
+// serialization.
+//
+// Consistency is a seqlock: the writer makes `seq` odd, writes, then makes it
+// even again; a reader copies what it needs between two reads of `seq` and
+// retries if they differ or are odd. Readers never block the writer. Every
+// slot is an AtomicU64 (f64 as bits), so torn reads are retried, never UB.
+//
+// Layout, in u64 slots (native byte order: one machine only):
+//   0 magic  1 version  2 levels  3 seq  4 last_update_id (u64::MAX = None)
+//   5 needs_resync  6 gap_count  7 tick_size bits (0 = none)
+//   8 published_ns (monotonic_ns scale)  9 bid count  10 ask count
+//   11 retired (set when create() replaced the file)  12..16 reserved
+//   then `levels` (price, size) bid pairs, best first, then the asks
+// create() builds the file under a temporary name and renames it over the
+// path, so processes that mapped an older file keep a valid mapping of the
+// old inode (never a truncated one). The old file is marked retired, and
+// reads from it raise until the reader reopens the path.
+// Put the file on a RAM-backed filesystem (/dev/shm on Linux) so publishing
+// never touches a disk.
+use std::fs::{self, File, OpenOptions};
+use std::hint;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::arrays::LevelsInput;
+use crate::clock::monotonic_ns;
+use crate::{L2Book, Levels, Side};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"MMSHMBK\0");
+const VERSION: u64 = 1;
+const HEADER: usize = 16;
+
+// Named header slots; slot 0 is the magic
+const VERSION_SLOT: usize = 1;
+const LEVELS_SLOT: usize = 2;
+const SEQ_SLOT: usize = 3;
+const UPDATE_ID_SLOT: usize = 4;
+const RESYNC_SLOT: usize = 5;
+const GAPS_SLOT: usize = 6;
+const TICK_SLOT: usize = 7;
+const PUBLISHED_SLOT: usize = 8;
+const BID_COUNT_SLOT: usize = 9;
+const ASK_COUNT_SLOT: usize = 10;
+const RETIRED_SLOT: usize = 11;
+
+// A reader gives up on a seqlock held odd this long: the writer died
+// mid-publish
+const STALL: Duration = Duration::from_millis(500);
+
+#[cfg(unix)]
+mod sys {
+    use std::ffi::c_void;
+    use std::os::raw::c_int;
+
+    pub const PROT_READ: c_int = 1;
+    pub const PROT_WRITE: c_int = 2;
+    pub const MAP_SHARED: c_int = 1;
+
+    extern "C" {
+        pub fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: i64,
+        ) -> *mut c_void;
+        pub fn munmap(addr: *mut c_void, len: usize) -> c_int;
+    }
+}
+
+// A shared mapping of a whole file as u64 slots
+struct Mapping {
+    ptr: *mut AtomicU64,
+    slots: usize,
+}
+
+// The mapping is only accessed through atomics
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    #[cfg(unix)]
+    fn new(file: &File, slots: usize, writable: bool) -> PyResult<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let prot = if writable {
+            sys::PROT_READ | sys::PROT_WRITE
+        } else {
+            sys::PROT_READ
+        };
+        // SAFETY: a fresh shared mapping of `file`, which is at least
+        // slots * 8 bytes long; it is unmapped in Drop
+        let ptr = unsafe {
+            sys::mmap(
+                std::ptr::null_mut(),
+                slots * 8,
+                prot,
+                sys::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr as isize == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self {
+            ptr: ptr.cast(),
+            slots,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn new(_file: &File, _slots: usize, _writable: bool) -> PyResult<Self> {
+        Err(PyRuntimeError::new_err("ShmBook needs a Unix platform"))
+    }
+
+    fn slot(&self, i: usize) -> &AtomicU64 {
+        assert!(i < self.slots);
+        // SAFETY: in bounds of the mapping, which outlives &self; mmap
+        // returns page-aligned memory
+        unsafe { &*self.ptr.add(i) }
+    }
+
+    fn get(&self, i: usize) -> u64 {
+        self.slot(i).load(Ordering::Relaxed)
+    }
+
+    fn set(&self, i: usize, v: u64) {
+        self.slot(i).store(v, Ordering::Relaxed)
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: the mapping created in new, unmapped once
+        unsafe {
+            sys::munmap(self.ptr.cast(), self.slots * 8);
+        }
+    }
+}
+
+// What a reader copies out under the seqlock
+struct View {
+    bids: Levels,
+    asks: Levels,
+    last_update_id: Option<u64>,
+    needs_resync: bool,
+    gap_count: u64,
+    published_ns: Option<i64>,
+}
+
+#[pyclass]
+pub struct ShmBook {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    levels: usize,
+    map: Mapping,
+    // The writer's full book; None when opened read-only
+    book: Option<L2Book>,
+    tick_size: Option<f64>,
+}
+
+impl ShmBook {
+    fn slots(levels: usize) -> usize {
+        HEADER + levels * 4
+    }
+
+    fn writer(&mut self) -> PyResult<&mut L2Book> {
+        self.book
+            .as_mut()
+            .ok_or_else(|| PyRuntimeError::new_err("ShmBook is open read-only"))
+    }
+
+    // Copy the writer's book into the mapping under the seqlock
+    fn publish(&self) {
+        let Some(book) = &self.book else {
+            return;
+        };
+        let m = &self.map;
+        let seq = m.get(SEQ_SLOT);
+        m.set(SEQ_SLOT, seq + 1);
+        fence(Ordering::Release);
+        let sides = [(Side::Bid, HEADER), (Side::Ask, HEADER + self.levels * 2)];
+        let mut counts = [0; 2];
+        for (i, (side, base)) in sides.into_iter().enumerate() {
+            let top = book.top(side, self.levels);
+            for (j, (price, size)) in top.iter().enumerate() {
+                m.set(base + 2 * j, price.to_bits());
+                m.set(base + 2 * j + 1, size.to_bits());
+            }
+            counts[i] = top.len() as u64;
+        }
+        m.set(BID_COUNT_SLOT, counts[0]);
+        m.set(ASK_COUNT_SLOT, counts[1]);
+        m.set(UPDATE_ID_SLOT, book.last_update_id.unwrap_or(u64::MAX));
+        m.set(RESYNC_SLOT, book.needs_resync as u64);
+        m.set(GAPS_SLOT, book.gap_count);
+        m.set(PUBLISHED_SLOT, monotonic_ns() as u64);
+        m.slot(SEQ_SLOT).store(seq + 2, Ordering::Release);
+    }
+
+    // A valid ShmBook file at `path`, mapped writable so create() can
+    // retire it once the replacement is in place
+    fn existing(path: &str) -> Option<Mapping> {
+        let file = OpenOptions::new().read(true).write(true).open(path).ok()?;
+        if file.metadata().ok()?.len() < (HEADER * 8) as u64 {
+            return None;
+        }
+        let map = Mapping::new(&file, HEADER, true).ok()?;
+        (map.slot(0).load(Ordering::Acquire) == MAGIC).then_some(map)
+    }
+
+    // Read `depth` levels per side and the header consistently
+    fn view(&self, depth: usize) -> PyResult<View> {
+        let m = &self.map;
+        if m.get(RETIRED_SLOT) != 0 {
+            return Err(PyRuntimeError::new_err(format!(
+                "ShmBook {} was re-created; open it again",
+                self.path
+            )));
+        }
+        let depth = depth.min(self.levels);
+        let started = Instant::now();
+        let mut spins = 0u32;
+        loop {
+            let before = m.slot(SEQ_SLOT).load(Ordering::Acquire);
+            if before & 1 == 0 {
+                let side = |base: usize, count: u64| -> Levels {
+                    (0..(count as usize).min(depth))
+                        .map(|j| {
+                            (
+                                f64::from_bits(m.get(base + 2 * j)),
+                                f64::from_bits(m.get(base + 2 * j + 1)),
+                            )
+                        })
+                        .collect()
+                };
+                let bids = side(HEADER, m.get(BID_COUNT_SLOT));
+                let asks = side(HEADER + self.levels * 2, m.get(ASK_COUNT_SLOT));
+                let update_id = m.get(UPDATE_ID_SLOT);
+                let view = View {
+                    bids,
+                    asks,
+                    last_update_id: (update_id != u64::MAX).then_some(update_id),
+                    needs_resync: m.get(RESYNC_SLOT) != 0,
+                    gap_count: m.get(GAPS_SLOT),
+                    published_ns: (before != 0).then(|| m.get(PUBLISHED_SLOT) as i64),
+                };
+                fence(Ordering::Acquire);
+                if m.get(SEQ_SLOT) == before {
+                    return Ok(view);
+                }
+            }
+            spins += 1;
+            if spins.is_multiple_of(1024) {
+                if started.elapsed() > STALL {
+                    return Err(PyRuntimeError::new_err(
+                        "ShmBook writer stalled mid-publish",
+                    ));
+                }
+                std::thread::yield_now();
+            } else {
+                hint::spin_loop();
+            }
+        }
+    }
+
+    fn best(&self, side: Side) -> PyResult<Option<(f64, f64)>> {
+        let view = self.view(1)?;
+        Ok(match side {
+            Side::Bid => view.bids.first().copied(),
+            Side::Ask => view.asks.first().copied(),
+        })
+    }
+}
+
+#[pymethods]
+impl ShmBook {
+    // Create the file at `path` (replacing any existing one, see the top of
+    // this file) and become its writer. Levels beyond `levels` per side are
+    // kept in the writer's book but not published. Other settings are as
+    // for L2Book.
+    #[staticmethod]
+    #[pyo3(signature = (path, levels=50, raise_on_gap=false, cross_policy="ignore", tick_size=None))]
+    pub fn create(
+        path: &str,
+        levels: usize,
+        raise_on_gap: bool,
+        cross_policy: &str,
+        tick_size: Option<f64>,
+    ) -> PyResult<Self> {
+        if levels == 0 {
+            return Err(PyValueError::new_err("levels must be positive"));
+        }
+        let book = L2Book::new(
+            raise_on_gap,
+            cross_policy,
+            tick_size,
+            None,
+            None,
+            false,
+            None,
+            false,
+        )?;
+        let tmp = format!("{}.{}.tmp", path, std::process::id());
+        let slots = Self::slots(levels);
+        let made = (|| -> PyResult<Mapping> {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&tmp)?;
+            file.set_len((slots * 8) as u64)?;
+            let map = Mapping::new(&file, slots, true)?;
+            map.set(VERSION_SLOT, VERSION);
+            map.set(LEVELS_SLOT, levels as u64);
+            map.set(UPDATE_ID_SLOT, u64::MAX);
+            map.set(TICK_SLOT, tick_size.map_or(0, f64::to_bits));
+            map.slot(0).store(MAGIC, Ordering::Release);
+            Ok(map)
+        })();
+        let old = Self::existing(path);
+        let map = match made.and_then(|map| Ok(fs::rename(&tmp, path).map(|_| map)?)) {
+            Ok(map) => map,
+            Err(e) => {
+                let _ = fs::remove_file(&tmp);
+                return Err(e);
+            }
+        };
+        if let Some(old) = old {
+            old.slot(RETIRED_SLOT).store(1, Ordering::Release);
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            levels,
+            map,
+            book: Some(book),
+            tick_size,
+        })
+    }
+
+    // Open a file made by create() for reading
+    #[staticmethod]
+    pub fn open(path: &str) -> PyResult<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        let invalid = || PyValueError::new_err(format!("{} is not a ShmBook file", path));
+        if len < HEADER * 8 {
+            return Err(invalid());
+        }
+        let header = Mapping::new(&file, HEADER, false)?;
+        if header.slot(0).load(Ordering::Acquire) != MAGIC {
+            return Err(invalid());
+        }
+        if header.get(VERSION_SLOT) != VERSION {
+            return Err(PyValueError::new_err(format!(
+                "unsupported ShmBook version {}",
+                header.get(VERSION_SLOT)
+            )));
+        }
+        let levels = header.get(LEVELS_SLOT) as usize;
+        let tick = header.get(TICK_SLOT);
+        if len < Self::slots(levels) * 8 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            levels,
+            map: Mapping::new(&file, Self::slots(levels), false)?,
+            book: None,
+            tick_size: (tick != 0).then(|| f64::from_bits(tick)),
+        })
+    }
+
+    #[getter]
+    pub fn is_writer(&self) -> bool {
+        self.book.is_some()
+    }
+
+    // Writer only; as L2Book.apply_snapshot, then published
+    #[pyo3(signature = (bids, asks, update_id=None))]
+    pub fn apply_snapshot(
+        &mut self,
+        bids: LevelsInput,
+        asks: LevelsInput,
+        update_id: Option<u64>,
+    ) -> PyResult<()> {
+        let writer = self.writer()?;
+        let (bids, asks) = (bids.levels(writer.codec), asks.levels(writer.codec));
+        writer.load_snapshot(bids, asks, update_id)?;
+        self.publish();
+        Ok(())
+    }
+
+    // Writer only; as L2Book.apply_delta without return_update. Gap state
+    // is published too, so readers see needs_resync.
+    #[pyo3(signature = (bids, asks, update_id=None, prev_update_id=None))]
+    pub fn apply_delta(
+        &mut self,
+        bids: LevelsInput,
+        asks: LevelsInput,
+        update_id: Option<u64>,
+        prev_update_id: Option<u64>,
+    ) -> PyResult<bool> {
+        let writer = self.writer()?;
+        let (bids, asks) = (bids.levels(writer.codec), asks.levels(writer.codec));
+        let result = writer.delta(bids, asks, update_id, prev_update_id, None);
+        self.publish();
+        result
+    }
+
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.writer()?.clear();
+        self.publish();
+        Ok(())
+    }
+
+    // Publish count; it changes with every update, so readers can poll it
+    // to skip unchanged books
+    #[getter]
+    pub fn seq(&self) -> u64 {
+        self.map.slot(SEQ_SLOT).load(Ordering::Acquire) / 2
+    }
+
+    // Consistent copy of the published levels as a plain L2Book
+    pub fn snapshot(&self) -> PyResult<L2Book> {
+        let view = self.view(self.levels)?;
+        let mut book = L2Book::new(
+            false,
+            "ignore",
+            self.tick_size,
+            None,
+            None,
+            false,
+            None,
+            false,
+        )?;
+        book.load_snapshot(view.bids, view.asks, view.last_update_id)?;
+        book.needs_resync = view.needs_resync;
+        book.gap_count = view.gap_count;
+        Ok(book)
+    }
+
+    pub fn depth(&self, n: usize) -> PyResult<(Levels, Levels)> {
+        let view = self.view(n)?;
+        Ok((view.bids, view.asks))
+    }
+
+    pub fn bids(&self, n: usize) -> PyResult<Levels> {
+        Ok(self.view(n)?.bids)
+    }
+
+    pub fn asks(&self, n: usize) -> PyResult<Levels> {
+        Ok(self.view(n)?.asks)
+    }
+
+    #[getter]
+    pub fn best_bid(&self) -> PyResult<Option<(f64, f64)>> {
+        self.best(Side::Bid)
+    }
+
+    #[getter]
+    pub fn best_ask(&self) -> PyResult<Option<(f64, f64)>> {
+        self.best(Side::Ask)
+    }
+
+    pub fn mid(&self) -> PyResult<Option<f64>> {
+        let view = self.view(1)?;
+        Ok(match (view.bids.first(), view.asks.first()) {
+            (Some((bid, _)), Some((ask, _))) => Some((bid + ask) / 2.0),
+            _ => None,
+        })
+    }
+
+    pub fn spread(&self) -> PyResult<Option<f64>> {
+        let view = self.view(1)?;
+        Ok(match (view.bids.first(), view.asks.first()) {
+            (Some((bid, _)), Some((ask, _))) => Some(ask - bid),
+            _ => None,
+        })
+    }
+
+    #[getter]
+    pub fn last_update_id(&self) -> PyResult<Option<u64>> {
+        Ok(self.view(0)?.last_update_id)
+    }
+
+    #[getter]
+    pub fn needs_resync(&self) -> PyResult<bool> {
+        Ok(self.view(0)?.needs_resync)
+    }
+
+    #[getter]
+    pub fn gap_count(&self) -> PyResult<u64> {
+        Ok(self.view(0)?.gap_count)
+    }
+
+    // Since the last publish, on the monotonic_ns() scale; None before the
+    // first. Comparable across processes on one host.
+    #[getter]
+    pub fn age_ms(&self) -> PyResult<Option<f64>> {
+        Ok(self
+            .view(0)?
+            .published_ns
+            .map(|at| (monotonic_ns() - at).max(0) as f64 / 1e6))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ShmBook(path={:?}, levels={}, writer={}, seq={})",
+            self.path,
+            self.levels,
+            self.is_writer(),
+            self.seq()
+        )
+    }
+}
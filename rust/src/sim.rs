@@ -0,0 +1,428 @@
+// Matching-engine simulator for offline evaluation. Recorded L2 deltas and
+// trades drive a book; own orders are matched against it:
+//   - crossing limit and market orders fill as taker, walking the book levels
+//     (the book is not depleted: the next recorded delta is the truth)
+//   - resting limit orders join the back of their level and track queue
+//     position with QueueTracker; trades at our price fill whatever reaches
+//     past the queue ahead, and trades or a book moving through our price
+//     fill the whole remainder as maker
+// Fills are queued and drained with take_fills().
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::errors::BookError;
+use crate::fees::{FeeModel, Liquidity};
+use crate::queue::QueueTracker;
+use crate::{L2Book, Levels, Side};
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub struct SimFill {
+    pub order_id: u64,
+    pub side: &'static str,
+    pub price: f64,
+    pub qty: f64,
+    // "maker" or "taker"
+    pub liquidity: &'static str,
+    pub fee: f64,
+    pub ts: i64,
+}
+
+#[pymethods]
+impl SimFill {
+    fn __repr__(&self) -> String {
+        format!(
+            "SimFill(order_id={}, side={:?}, price={:?}, qty={:?}, liquidity={:?}, fee={:?}, ts={})",
+            self.order_id, self.side, self.price, self.qty, self.liquidity, self.fee, self.ts
+        )
+    }
+}
+
+struct SimOrder {
+    side: Side,
+    price: f64,
+    remaining: f64,
+}
+
+// Own orders, queue positions and fills against a book passed in by the
+// caller, so the same matching runs on a Python-owned book (SimExchange)
+// and on a plain one (native replays off the GIL)
+pub(crate) struct Matcher {
+    // Resting limit orders by id
+    orders: BTreeMap<u64, SimOrder>,
+    queue: QueueTracker,
+    fee_model: Option<FeeModel>,
+    fills: Vec<SimFill>,
+    next_id: u64,
+    // Timestamp of the last market-data event (or delayed order arrival)
+    pub(crate) now: i64,
+}
+
+impl Matcher {
+    pub(crate) fn new(fee_model: Option<FeeModel>, queue_power: f64) -> PyResult<Self> {
+        Ok(Self {
+            orders: BTreeMap::new(),
+            queue: QueueTracker::new(queue_power)?,
+            fee_model,
+            fills: Vec::new(),
+            next_id: 1,
+            now: 0,
+        })
+    }
+
+    fn record(&mut self, order_id: u64, side: Side, price: f64, qty: f64, liquidity: Liquidity) {
+        let fee = match self.fee_model.as_mut() {
+            Some(model) => {
+                let fee = model.fee(price, qty, liquidity);
+                model.add_volume(price * qty);
+                fee
+            }
+            None => 0.0,
+        };
+        self.fills.push(SimFill {
+            order_id,
+            side: side.name(),
+            price,
+            qty,
+            liquidity: match liquidity {
+                Liquidity::Maker => "maker",
+                Liquidity::Taker => "taker",
+            },
+            fee,
+            ts: self.now,
+        });
+    }
+
+    // Walk the opposite side up to `limit`; returns the unfilled quantity
+    fn take(
+        &mut self,
+        book: &L2Book,
+        order_id: u64,
+        side: Side,
+        qty: f64,
+        limit: Option<f64>,
+    ) -> f64 {
+        let within = |p: f64| match (side, limit) {
+            (_, None) => true,
+            (Side::Bid, Some(l)) => p <= l,
+            (Side::Ask, Some(l)) => p >= l,
+        };
+        let levels: Levels = match side {
+            Side::Bid => book.ask_levels().take_while(|(p, _)| within(*p)).collect(),
+            Side::Ask => book.bid_levels().take_while(|(p, _)| within(*p)).collect(),
+        };
+        let mut remaining = qty;
+        for (price, size) in levels {
+            if remaining <= 1e-12 {
+                break;
+            }
+            let q = remaining.min(size);
+            self.record(order_id, side, price, q, Liquidity::Taker);
+            remaining -= q;
+        }
+        remaining.max(0.0)
+    }
+
+    // Maker fill of a resting order; drops it once complete
+    fn fill_resting(&mut self, order_id: u64, qty: f64) {
+        let Some(o) = self.orders.get_mut(&order_id) else {
+            return;
+        };
+        let q = qty.min(o.remaining);
+        if q <= 0.0 {
+            return;
+        }
+        o.remaining -= q;
+        let (side, price, left) = (o.side, o.price, o.remaining);
+        self.record(order_id, side, price, q, Liquidity::Maker);
+        if left <= 1e-12 {
+            self.orders.remove(&order_id);
+            self.queue.remove_order(order_id);
+        } else {
+            self.queue.set_order_size(order_id, left);
+        }
+    }
+
+    // Ids are handed out at submission, so an order delayed in flight can be
+    // referred to (and cancelled) before it reaches the matching engine
+    pub(crate) fn reserve_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    // Returns false when a post-only order would cross
+    pub(crate) fn place_limit(
+        &mut self,
+        book: &L2Book,
+        id: u64,
+        side: Side,
+        price: f64,
+        qty: f64,
+        post_only: bool,
+    ) -> bool {
+        let crosses = match side {
+            Side::Bid => book.best_ask().is_some_and(|a| a.0 <= price),
+            Side::Ask => book.best_bid().is_some_and(|b| b.0 >= price),
+        };
+        if crosses && post_only {
+            return false;
+        }
+        let remaining = if crosses {
+            self.take(book, id, side, qty, Some(price))
+        } else {
+            qty
+        };
+        if remaining > 1e-12 {
+            let level = book.level_size(side, price);
+            // Side is already parsed, so add_order can not fail
+            let _ = self
+                .queue
+                .add_order(id, side.name(), price, remaining, level, Some(self.now));
+            self.orders.insert(
+                id,
+                SimOrder {
+                    side,
+                    price,
+                    remaining,
+                },
+            );
+        }
+        true
+    }
+
+    pub(crate) fn place_market(&mut self, book: &L2Book, id: u64, side: Side, qty: f64) {
+        self.take(book, id, side, qty, None);
+    }
+
+    // Resting orders the book has moved through fill at their own price
+    fn sweep_crossed(&mut self, book: &L2Book) {
+        let best_bid = book.best_bid().map(|l| l.0);
+        let best_ask = book.best_ask().map(|l| l.0);
+        let crossed: Vec<(u64, f64)> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| match o.side {
+                Side::Bid => best_ask.is_some_and(|a| a <= o.price),
+                Side::Ask => best_bid.is_some_and(|b| b >= o.price),
+            })
+            .map(|(id, o)| (*id, o.remaining))
+            .collect();
+        for (id, qty) in crossed {
+            self.fill_resting(id, qty);
+        }
+    }
+
+    pub(crate) fn apply_snapshot(
+        &mut self,
+        book: &mut L2Book,
+        bids: Levels,
+        asks: Levels,
+        ts: i64,
+    ) -> Result<(), BookError> {
+        BookError::check_levels(&bids, &asks)?;
+        self.now = ts;
+        self.queue.apply_delta(bids.clone(), asks.clone());
+        book.load_snapshot(bids, asks, None)?;
+        self.sweep_crossed(book);
+        Ok(())
+    }
+
+    pub(crate) fn apply_delta(
+        &mut self,
+        book: &mut L2Book,
+        bids: Levels,
+        asks: Levels,
+        ts: i64,
+    ) -> Result<(), BookError> {
+        BookError::check_levels(&bids, &asks)?;
+        self.now = ts;
+        self.queue.apply_delta(bids.clone(), asks.clone());
+        book.apply_levels(bids, asks)?;
+        self.sweep_crossed(book);
+        Ok(())
+    }
+
+    pub(crate) fn on_trade(&mut self, price: f64, qty: f64, ts: i64) {
+        self.now = ts;
+        for (id, q) in self.queue.on_trade(price, qty) {
+            self.fill_resting(id, q);
+        }
+    }
+
+    pub(crate) fn cancel(&mut self, order_id: u64) -> bool {
+        self.queue.remove_order(order_id);
+        self.orders.remove(&order_id).is_some()
+    }
+
+    pub(crate) fn cancel_all(&mut self) -> usize {
+        let n = self.orders.len();
+        for id in std::mem::take(&mut self.orders).into_keys() {
+            self.queue.remove_order(id);
+        }
+        n
+    }
+
+    // Resting orders as (order_id, side, price, remaining)
+    pub(crate) fn open_orders(&self) -> impl Iterator<Item = (u64, Side, f64, f64)> + '_ {
+        self.orders
+            .iter()
+            .map(|(id, o)| (*id, o.side, o.price, o.remaining))
+    }
+
+    pub(crate) fn take_fills(&mut self) -> Vec<SimFill> {
+        std::mem::take(&mut self.fills)
+    }
+}
+
+#[pyclass]
+pub struct SimExchange {
+    pub(crate) book: Py<L2Book>,
+    pub(crate) engine: Matcher,
+}
+
+impl SimExchange {
+    pub(crate) fn place_limit(
+        &mut self,
+        py: Python<'_>,
+        id: u64,
+        side: Side,
+        price: f64,
+        qty: f64,
+        post_only: bool,
+    ) -> bool {
+        let book = self.book.borrow(py);
+        self.engine
+            .place_limit(&book, id, side, price, qty, post_only)
+    }
+
+    pub(crate) fn place_market(&mut self, py: Python<'_>, id: u64, side: Side, qty: f64) {
+        let book = self.book.borrow(py);
+        self.engine.place_market(&book, id, side, qty);
+    }
+}
+
+pub(crate) fn check_qty(qty: f64) -> PyResult<()> {
+    if qty <= 0.0 {
+        return Err(PyValueError::new_err("order qty must be positive"));
+    }
+    Ok(())
+}
+
+#[pymethods]
+impl SimExchange {
+    #[new]
+    #[pyo3(signature = (book=None, fee_model=None, queue_power=1.0))]
+    pub fn new(
+        py: Python<'_>,
+        book: Option<Py<L2Book>>,
+        fee_model: Option<FeeModel>,
+        queue_power: f64,
+    ) -> PyResult<Self> {
+        let book = match book {
+            Some(b) => b,
+            None => Py::new(py, L2Book::default())?,
+        };
+        Ok(Self {
+            book,
+            engine: Matcher::new(fee_model, queue_power)?,
+        })
+    }
+
+    #[getter]
+    fn book(&self, py: Python<'_>) -> Py<L2Book> {
+        self.book.clone_ref(py)
+    }
+
+    #[getter]
+    fn fee_model(&self) -> Option<FeeModel> {
+        self.engine.fee_model.clone()
+    }
+
+    #[getter]
+    fn now(&self) -> i64 {
+        self.engine.now
+    }
+
+    pub fn apply_snapshot(
+        &mut self,
+        py: Python<'_>,
+        bids: Levels,
+        asks: Levels,
+        ts: i64,
+    ) -> PyResult<()> {
+        let mut book = self.book.borrow_mut(py);
+        Ok(self.engine.apply_snapshot(&mut book, bids, asks, ts)?)
+    }
+
+    pub fn apply_delta(
+        &mut self,
+        py: Python<'_>,
+        bids: Levels,
+        asks: Levels,
+        ts: i64,
+    ) -> PyResult<()> {
+        let mut book = self.book.borrow_mut(py);
+        Ok(self.engine.apply_delta(&mut book, bids, asks, ts)?)
+    }
+
+    pub fn on_trade(&mut self, price: f64, qty: f64, ts: i64) {
+        self.engine.on_trade(price, qty, ts);
+    }
+
+    // Returns the order id, or None when a post-only order would cross.
+    // Any part that crosses fills as taker; the rest rests on the book.
+    #[pyo3(signature = (side, price, qty, post_only=false))]
+    pub fn submit_limit(
+        &mut self,
+        py: Python<'_>,
+        side: &str,
+        price: f64,
+        qty: f64,
+        post_only: bool,
+    ) -> PyResult<Option<u64>> {
+        let side = Side::parse(side)?;
+        check_qty(qty)?;
+        let id = self.engine.reserve_id();
+        Ok(self
+            .place_limit(py, id, side, price, qty, post_only)
+            .then_some(id))
+    }
+
+    // Fills immediately against the book with slippage; any quantity beyond
+    // the visible depth is dropped. Returns the order id.
+    pub fn submit_market(&mut self, py: Python<'_>, side: &str, qty: f64) -> PyResult<u64> {
+        let side = Side::parse(side)?;
+        check_qty(qty)?;
+        let id = self.engine.reserve_id();
+        self.place_market(py, id, side, qty);
+        Ok(id)
+    }
+
+    pub fn cancel(&mut self, order_id: u64) -> bool {
+        self.engine.cancel(order_id)
+    }
+
+    pub fn cancel_all(&mut self) -> usize {
+        self.engine.cancel_all()
+    }
+
+    // Resting orders as (order_id, side, price, remaining)
+    pub fn open_orders(&self) -> Vec<(u64, &'static str, f64, f64)> {
+        self.engine
+            .open_orders()
+            .map(|(id, side, price, qty)| (id, side.name(), price, qty))
+            .collect()
+    }
+
+    // (qty ahead, qty behind) for a resting order
+    pub fn queue_position(&self, order_id: u64) -> Option<(f64, f64)> {
+        self.engine.queue.position(order_id)
+    }
+
+    pub fn take_fills(&mut self) -> Vec<SimFill> {
+        self.engine.take_fills()
+    }
+}
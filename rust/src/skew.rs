@@ -0,0 +1,141 @@
+// Inventory skew: map the position's distance from target onto a price shift
+// (both quotes move against the inventory) and bid/ask size multipliers.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::quoting::Quote;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SkewCurve {
+    Linear,
+    Sigmoid,
+    Exponential,
+}
+
+impl SkewCurve {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "linear" => Ok(Self::Linear),
+            "sigmoid" => Ok(Self::Sigmoid),
+            "exponential" => Ok(Self::Exponential),
+            other => Err(PyValueError::new_err(format!(
+                "curve must be 'linear', 'sigmoid' or 'exponential', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Sigmoid => "sigmoid",
+            Self::Exponential => "exponential",
+        }
+    }
+
+    // x in [-1, 1] -> [-1, 1], odd and monotonic
+    fn eval(self, x: f64, k: f64) -> f64 {
+        match self {
+            Self::Linear => x,
+            Self::Sigmoid => (k * x).tanh() / k.tanh(),
+            Self::Exponential => x.signum() * ((k * x.abs()).exp() - 1.0) / (k.exp() - 1.0),
+        }
+    }
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Copy, Debug)]
+pub struct SkewAdjustment {
+    // Curve output in [-1, 1]; positive when long of target
+    pub skew: f64,
+    // Shift applied to both quotes, in bps (negative when long)
+    pub price_shift_bps: f64,
+    pub bid_size_mult: f64,
+    pub ask_size_mult: f64,
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct InventorySkew {
+    #[pyo3(get, set)]
+    pub max_position: f64,
+    #[pyo3(get, set)]
+    pub target: f64,
+    #[pyo3(get, set)]
+    pub max_price_skew_bps: f64,
+    #[pyo3(get, set)]
+    pub max_size_skew: f64,
+    #[pyo3(get, set)]
+    pub steepness: f64,
+    curve: SkewCurve,
+}
+
+#[pymethods]
+impl InventorySkew {
+    // max_size_skew = 1.0 shrinks the inventory-increasing side to zero at
+    // max_position; steepness shapes the sigmoid/exponential curves
+    #[new]
+    #[pyo3(signature = (max_position, target=0.0, curve="linear", max_price_skew_bps=10.0, max_size_skew=1.0, steepness=3.0))]
+    pub fn new(
+        max_position: f64,
+        target: f64,
+        curve: &str,
+        max_price_skew_bps: f64,
+        max_size_skew: f64,
+        steepness: f64,
+    ) -> PyResult<Self> {
+        if max_position <= 0.0 || steepness <= 0.0 {
+            return Err(PyValueError::new_err(
+                "max_position and steepness must be positive",
+            ));
+        }
+        Ok(Self {
+            max_position,
+            target,
+            max_price_skew_bps,
+            max_size_skew,
+            steepness,
+            curve: SkewCurve::parse(curve)?,
+        })
+    }
+
+    #[getter]
+    pub fn get_curve(&self) -> &'static str {
+        self.curve.name()
+    }
+
+    #[setter]
+    pub fn set_curve(&mut self, curve: &str) -> PyResult<()> {
+        self.curve = SkewCurve::parse(curve)?;
+        Ok(())
+    }
+
+    pub fn adjustment(&self, position: f64) -> SkewAdjustment {
+        let x = ((position - self.target) / self.max_position).clamp(-1.0, 1.0);
+        let skew = self.curve.eval(x, self.steepness);
+        SkewAdjustment {
+            skew,
+            price_shift_bps: -skew * self.max_price_skew_bps,
+            bid_size_mult: (1.0 - skew * self.max_size_skew).max(0.0),
+            ask_size_mult: (1.0 + skew * self.max_size_skew).max(0.0),
+        }
+    }
+
+    // Shift a QuoteEngine quote by the skew (spread is preserved)
+    pub fn apply(&self, quote: &Quote, position: f64) -> Quote {
+        let shift = self.adjustment(position).price_shift_bps / 10_000.0;
+        let r = quote.reservation_price * (1.0 + shift);
+        Quote {
+            bid_price: r - quote.spread / 2.0,
+            ask_price: r + quote.spread / 2.0,
+            reservation_price: r,
+            spread: quote.spread,
+        }
+    }
+
+    // (bid size, ask size) from a base quote size
+    pub fn sizes(&self, position: f64, base_size: f64) -> (f64, f64) {
+        let adj = self.adjustment(position);
+        (base_size * adj.bid_size_mult, base_size * adj.ask_size_mult)
+    }
+}
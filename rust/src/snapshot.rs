@@ -0,0 +1,335 @@
+// REST depth snapshots for resync, fetched and loaded without Python in the
+// loop: the request weight is charged to a shared RateLimiter (waiting up to
+// max_wait_ms for tokens), the HTTP round trip runs with the GIL released
+// (see http.rs; https:// over TLS), and the levels go straight into a book.
+// When weight_bucket names one of the limiter's buckets, the weight the
+// exchange reports as used (Binance X-MBX-USED-WEIGHT-1M, Bybit
+// X-Bapi-Limit minus X-Bapi-Limit-Status) overwrites that bucket after each
+// response, and a 429/418 empties it.
+// Endpoints:
+//   bybit            GET /v5/market/orderbook?category=&symbol=&limit=  (weight 1)
+//   binance          GET /api/v3/depth?symbol=&limit=   (5/25/50/250 by limit)
+//   binance_futures  GET /fapi/v1/depth?symbol=&limit=  (2/5/10 by limit)
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::arrays::LevelsInput;
+use crate::binance::BinanceBookSync;
+use crate::http::{self, Response};
+use crate::json::{self, Value};
+use crate::ratelimit::RateLimiter;
+use crate::shared::SharedL2Book;
+use crate::ws;
+use crate::{L2Book, Levels};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Venue {
+    Bybit,
+    Binance,
+    BinanceFutures,
+}
+
+struct Snapshot {
+    bids: Levels,
+    asks: Levels,
+    update_id: u64,
+}
+
+impl Venue {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bybit" => Ok(Self::Bybit),
+            "binance" => Ok(Self::Binance),
+            "binance_futures" => Ok(Self::BinanceFutures),
+            other => Err(PyValueError::new_err(format!(
+                "exchange must be 'bybit', 'binance' or 'binance_futures', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn path(self, symbol: &str, limit: usize, category: &str) -> String {
+        match self {
+            Self::Bybit => format!(
+                "/v5/market/orderbook?category={}&symbol={}&limit={}",
+                category, symbol, limit
+            ),
+            Self::Binance => format!("/api/v3/depth?symbol={}&limit={}", symbol, limit),
+            Self::BinanceFutures => format!("/fapi/v1/depth?symbol={}&limit={}", symbol, limit),
+        }
+    }
+
+    fn weight(self, limit: usize) -> f64 {
+        match self {
+            Self::Bybit => 1.0,
+            Self::Binance => match limit {
+                0..=100 => 5.0,
+                101..=500 => 25.0,
+                501..=1000 => 50.0,
+                _ => 250.0,
+            },
+            Self::BinanceFutures => match limit {
+                0..=100 => 2.0,
+                101..=500 => 5.0,
+                _ => 10.0,
+            },
+        }
+    }
+
+    // Weight the exchange says is used in its current window
+    fn used_weight(self, response: &Response) -> Option<f64> {
+        let number = |name: &str| response.header(name)?.parse::<f64>().ok();
+        match self {
+            Self::Bybit => Some(number("x-bapi-limit")? - number("x-bapi-limit-status")?),
+            Self::Binance | Self::BinanceFutures => number("x-mbx-used-weight-1m"),
+        }
+    }
+
+    fn parse_body(self, body: &[u8]) -> Result<Snapshot, String> {
+        let text = std::str::from_utf8(body).map_err(|e| e.to_string())?;
+        let root = json::parse(text)?;
+        let (data, bids, asks, id) = match self {
+            Self::Bybit => {
+                let code = root.get("retCode").and_then(Value::as_i64).unwrap_or(0);
+                if code != 0 {
+                    let msg = root.get("retMsg").and_then(Value::as_str).unwrap_or("");
+                    return Err(format!("retCode {}: {}", code, msg));
+                }
+                let result = root.get("result").ok_or("missing 'result'")?;
+                (result, "b", "a", "u")
+            }
+            Self::Binance | Self::BinanceFutures => (&root, "bids", "asks", "lastUpdateId"),
+        };
+        Ok(Snapshot {
+            bids: json::levels(data.get(bids))?,
+            asks: json::levels(data.get(asks))?,
+            update_id: data
+                .get(id)
+                .and_then(Value::as_u64)
+                .ok_or_else(|| format!("missing update id '{}'", id))?,
+        })
+    }
+}
+
+// Symbols, categories and order ids end up in query strings and JSON
+// bodies unescaped
+pub(crate) fn check_token(what: &str, value: &str) -> PyResult<()> {
+    if value.is_empty()
+        || !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(PyValueError::new_err(format!(
+            "invalid {} '{}'",
+            what, value
+        )));
+    }
+    Ok(())
+}
+
+#[pyclass(frozen)]
+pub struct SnapshotFetcher {
+    base_url: String,
+    venue: Venue,
+    category: String,
+    limiter: Option<Py<RateLimiter>>,
+    weight_bucket: Option<usize>,
+    timeout: Duration,
+    max_wait: Duration,
+    requests: AtomicU64,
+    throttled: AtomicU64,
+}
+
+impl SnapshotFetcher {
+    // Charge `weight` to the limiter, sleeping (without the GIL) until the
+    // tokens are there or max_wait would be exceeded
+    fn acquire(&self, weight: f64) -> io::Result<()> {
+        let Some(limiter) = &self.limiter else {
+            return Ok(());
+        };
+        let limiter = limiter.get();
+        let deadline = Instant::now() + self.max_wait;
+        loop {
+            if limiter.try_acquire(weight, None).unwrap_or(false) {
+                return Ok(());
+            }
+            let wait = limiter
+                .time_until_available(weight, None)
+                .unwrap_or(f64::INFINITY);
+            let left = deadline.saturating_duration_since(Instant::now());
+            if !wait.is_finite() || wait > left.as_secs_f64() {
+                self.throttled.fetch_add(1, Ordering::Relaxed);
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "rate limit: weight {} not available within max_wait_ms",
+                        weight
+                    ),
+                ));
+            }
+            thread::sleep(Duration::from_secs_f64(wait).max(Duration::from_millis(1)));
+        }
+    }
+
+    fn fetch_snapshot(&self, symbol: &str, limit: usize) -> io::Result<Snapshot> {
+        self.acquire(self.venue.weight(limit))?;
+        let path = self.venue.path(symbol, limit, &self.category);
+        let response = http::get(&format!("{}{}", self.base_url, path), self.timeout)?;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let (Some(limiter), Some(bucket)) = (&self.limiter, self.weight_bucket) {
+            let used = match response.status {
+                // Rate limited or IP banned: nothing left until the window rolls
+                429 | 418 => Some(f64::INFINITY),
+                _ => self.venue.used_weight(&response),
+            };
+            if let Some(used) = used {
+                limiter.get().set_used(bucket, used).ok();
+            }
+        }
+        if response.status != 200 {
+            let body = String::from_utf8_lossy(&response.body);
+            return Err(io::Error::other(format!(
+                "GET {} returned HTTP {}: {}",
+                path,
+                response.status,
+                body.chars().take(200).collect::<String>()
+            )));
+        }
+        self.venue
+            .parse_body(&response.body)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[pymethods]
+impl SnapshotFetcher {
+    // base_url is the REST root, e.g. "https://api.bybit.com". category is
+    // Bybit's product type (linear, inverse, spot, option). weight_bucket
+    // must index a bucket of `limiter`.
+    #[new]
+    #[pyo3(signature = (base_url, exchange="bybit", category="linear", limiter=None, weight_bucket=None, timeout_ms=5000, max_wait_ms=10_000))]
+    pub fn new(
+        base_url: &str,
+        exchange: &str,
+        category: &str,
+        limiter: Option<Py<RateLimiter>>,
+        weight_bucket: Option<usize>,
+        timeout_ms: u64,
+        max_wait_ms: u64,
+    ) -> PyResult<Self> {
+        let venue = Venue::parse(exchange)?;
+        let base_url = base_url.trim_end_matches('/');
+        ws::split_url(base_url, "http").map_err(|e| PyValueError::new_err(e.to_string()))?;
+        check_token("category", category)?;
+        if timeout_ms == 0 {
+            return Err(PyValueError::new_err("timeout_ms must be positive"));
+        }
+        if let Some(bucket) = weight_bucket {
+            let buckets = limiter.as_ref().map_or(0, |l| l.get().available().len());
+            if bucket >= buckets {
+                return Err(PyValueError::new_err(format!(
+                    "weight_bucket {} needs a limiter with more than {} buckets",
+                    bucket, buckets
+                )));
+            }
+        }
+        Ok(Self {
+            base_url: base_url.to_string(),
+            venue,
+            category: category.to_string(),
+            limiter,
+            weight_bucket,
+            timeout: Duration::from_millis(timeout_ms),
+            max_wait: Duration::from_millis(max_wait_ms),
+            requests: AtomicU64::new(0),
+            throttled: AtomicU64::new(0),
+        })
+    }
+
+    // Request weight of a snapshot of `limit` levels on this exchange
+    #[pyo3(signature = (limit=50))]
+    pub fn weight(&self, limit: usize) -> f64 {
+        self.venue.weight(limit)
+    }
+
+    // (bids, asks, update_id). Network and HTTP failures raise OSError
+    // subclasses; running out of rate-limit budget raises TimeoutError.
+    #[pyo3(signature = (symbol, limit=50))]
+    pub fn fetch(
+        &self,
+        py: Python<'_>,
+        symbol: &str,
+        limit: usize,
+    ) -> PyResult<(Levels, Levels, u64)> {
+        check_token("symbol", symbol)?;
+        let s = py.allow_threads(|| self.fetch_snapshot(symbol, limit))?;
+        Ok((s.bids, s.asks, s.update_id))
+    }
+
+    // Fetch and load into an L2Book or SharedL2Book (returns True), or hand
+    // to a BinanceBookSync, which replays its buffered diffs (returns
+    // is_synced)
+    #[pyo3(signature = (book, symbol, limit=50))]
+    pub fn apply(
+        &self,
+        py: Python<'_>,
+        book: &Bound<'_, PyAny>,
+        symbol: &str,
+        limit: usize,
+    ) -> PyResult<bool> {
+        check_token("symbol", symbol)?;
+        let is_book = book.is_instance_of::<L2Book>()
+            || book.is_instance_of::<SharedL2Book>()
+            || book.is_instance_of::<BinanceBookSync>();
+        if !is_book {
+            return Err(PyTypeError::new_err(
+                "book must be an L2Book, SharedL2Book or BinanceBookSync",
+            ));
+        }
+        let s = py.allow_threads(|| self.fetch_snapshot(symbol, limit))?;
+        if let Ok(book) = book.downcast::<L2Book>() {
+            book.borrow_mut().apply_snapshot(
+                py,
+                LevelsInput::from(s.bids),
+                LevelsInput::from(s.asks),
+                Some(s.update_id),
+                false,
+            )?;
+            Ok(true)
+        } else if let Ok(shared) = book.downcast::<SharedL2Book>() {
+            shared.get().apply_snapshot(
+                py,
+                LevelsInput::from(s.bids),
+                LevelsInput::from(s.asks),
+                Some(s.update_id),
+            )?;
+            Ok(true)
+        } else {
+            let sync = book.downcast::<BinanceBookSync>()?;
+            sync.borrow_mut()
+                .apply_snapshot(py, s.update_id, s.bids, s.asks)
+        }
+    }
+
+    #[getter]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // Snapshots requested (any HTTP status)
+    #[getter]
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    // Fetches refused because the limiter had no budget within max_wait_ms
+    #[getter]
+    pub fn throttled(&self) -> u64 {
+        self.throttled.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,160 @@
+// Spread between the same instrument's reference prices on two venues,
+//   price  ref_a - ref_b
+//   bps    (ref_a - ref_b) / ref_b * 1e4
+// with its rolling mean / std (RollingStats, windowed by updates and/or ms)
+// and the z-score of the latest spread against that window. The books are
+// held by reference and read on each update(), so nothing is copied.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::rolling::RollingStats;
+use crate::L2Book;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Units {
+    Price,
+    Bps,
+}
+
+impl Units {
+    fn parse(s: &str) -> PyResult<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "price" => Ok(Self::Price),
+            "bps" => Ok(Self::Bps),
+            other => Err(PyValueError::new_err(format!(
+                "units must be 'price' or 'bps', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Price => "price",
+            Self::Bps => "bps",
+        }
+    }
+}
+
+#[pyclass]
+pub struct CrossVenueSpread {
+    book_a: Py<L2Book>,
+    book_b: Py<L2Book>,
+    units: Units,
+    reference: String,
+    min_samples: usize,
+    stats: RollingStats,
+}
+
+#[pymethods]
+impl CrossVenueSpread {
+    // reference is any L2Book reference price ("mid", "microprice", ...);
+    // zscore stays None until the window holds min_samples spreads
+    #[new]
+    #[pyo3(signature = (book_a, book_b, window=Some(100), window_ms=None, units="bps", reference="mid", min_samples=20))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        py: Python<'_>,
+        book_a: Py<L2Book>,
+        book_b: Py<L2Book>,
+        window: Option<usize>,
+        window_ms: Option<i64>,
+        units: &str,
+        reference: &str,
+        min_samples: usize,
+    ) -> PyResult<Self> {
+        book_a.borrow(py).reference_price(reference)?;
+        Ok(Self {
+            book_a,
+            book_b,
+            units: Units::parse(units)?,
+            reference: reference.to_string(),
+            min_samples: min_samples.max(2),
+            stats: RollingStats::new(window, window_ms, None)?,
+        })
+    }
+
+    // Read both books and record their spread; returns the z-score (None
+    // while either book is one-sided or the window is short)
+    #[pyo3(signature = (ts=None))]
+    pub fn update(&mut self, py: Python<'_>, ts: Option<i64>) -> PyResult<Option<f64>> {
+        let a = self.book_a.borrow(py).reference_price(&self.reference)?;
+        let b = self.book_b.borrow(py).reference_price(&self.reference)?;
+        match (a, b) {
+            (Some(a), Some(b)) => self.update_prices(a, b, ts),
+            _ => Ok(None),
+        }
+    }
+
+    // Same as update() with reference prices supplied directly
+    #[pyo3(signature = (price_a, price_b, ts=None))]
+    pub fn update_prices(
+        &mut self,
+        price_a: f64,
+        price_b: f64,
+        ts: Option<i64>,
+    ) -> PyResult<Option<f64>> {
+        if !(price_a > 0.0 && price_b > 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "prices must be positive, got {} / {}",
+                price_a, price_b
+            )));
+        }
+        let spread = match self.units {
+            Units::Price => price_a - price_b,
+            Units::Bps => (price_a - price_b) / price_b * 10_000.0,
+        };
+        self.stats.push(spread, ts)?;
+        Ok(self.zscore())
+    }
+
+    // Latest spread
+    #[getter]
+    pub fn spread(&self) -> Option<f64> {
+        self.stats.last()
+    }
+
+    #[getter]
+    pub fn mean(&self) -> Option<f64> {
+        self.stats.mean()
+    }
+
+    // Sample std (ddof=1) of the window
+    #[getter]
+    pub fn std(&self) -> Option<f64> {
+        self.stats.std(1)
+    }
+
+    // (spread - mean) / std over the window including the latest spread
+    #[getter]
+    pub fn zscore(&self) -> Option<f64> {
+        if !self.is_ready() {
+            return None;
+        }
+        let (spread, mean, std) = (self.spread()?, self.mean()?, self.std()?);
+        (std > 0.0).then(|| (spread - mean) / std)
+    }
+
+    #[getter]
+    pub fn is_ready(&self) -> bool {
+        self.stats.len() >= self.min_samples
+    }
+
+    #[getter]
+    pub fn count(&self) -> usize {
+        self.stats.len()
+    }
+
+    #[getter]
+    fn units(&self) -> &'static str {
+        self.units.name()
+    }
+
+    pub fn reset(&mut self) {
+        self.stats.reset();
+    }
+
+    fn __len__(&self) -> usize {
+        self.stats.len()
+    }
+}
@@ -0,0 +1,321 @@
+// Pickle support for stateful classes. A class saves its fields into a byte
+// string: a header (magic, format version, class name), then each field in
+// declaration order, little-endian, lengths as u64 and options/enums led by
+// a tag byte. __reduce__ hands pickle (_restore_state, (bytes,)), so classes
+// need neither a no-argument constructor nor a module path of their own.
+// What only makes sense inside one process is not saved: book callbacks and
+// the tick-to-quote Instant come back empty, and a ReplayClock comes back as
+// a fresh replay clock at the saved time, no longer shared with anything.
+// copy.copy/deepcopy clone the Rust value instead and keep those links.
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::clock::Clock;
+
+const MAGIC: &[u8; 4] = b"MMST";
+const VERSION: u8 = 1;
+
+pub(crate) trait Persist: Sized {
+    fn save(&self, out: &mut Vec<u8>);
+    fn load(input: &mut &[u8]) -> PyResult<Self>;
+}
+
+// A class pickle can restore; NAME is written into the header
+pub(crate) trait Stateful: Persist {
+    const NAME: &'static str;
+}
+
+fn truncated() -> PyErr {
+    PyValueError::new_err("state is truncated")
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> PyResult<&'a [u8]> {
+    if input.len() < n {
+        return Err(truncated());
+    }
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Ok(head)
+}
+
+pub(crate) fn bad_tag(tag: u8) -> PyErr {
+    PyValueError::new_err(format!("invalid state tag {}", tag))
+}
+
+// Implements Persist field by field, in the order given; fields listed after
+// `skip` are not saved and load as Default
+macro_rules! persist_fields {
+    ($ty:ty { $($field:ident),* $(,)? } $(skip { $($skip:ident),* $(,)? })?) => {
+        impl $crate::state::Persist for $ty {
+            fn save(&self, out: &mut Vec<u8>) {
+                $( $crate::state::Persist::save(&self.$field, out); )*
+            }
+
+            fn load(input: &mut &[u8]) -> pyo3::PyResult<Self> {
+                Ok(Self {
+                    $( $field: $crate::state::Persist::load(input)?, )*
+                    $($( $skip: Default::default(), )*)?
+                })
+            }
+        }
+    };
+}
+pub(crate) use persist_fields;
+
+macro_rules! persist_number {
+    ($($ty:ty),*) => {$(
+        impl Persist for $ty {
+            fn save(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn load(input: &mut &[u8]) -> PyResult<Self> {
+                let bytes = take(input, std::mem::size_of::<$ty>())?;
+                Ok(<$ty>::from_le_bytes(bytes.try_into().expect("sized")))
+            }
+        }
+    )*};
+}
+persist_number!(u8, u32, u64, i64, f64);
+
+impl Persist for usize {
+    fn save(&self, out: &mut Vec<u8>) {
+        (*self as u64).save(out);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        Ok(u64::load(input)? as usize)
+    }
+}
+
+impl Persist for bool {
+    fn save(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        match u8::load(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(bad_tag(tag)),
+        }
+    }
+}
+
+impl Persist for String {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.len().save(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        let n = usize::load(input)?;
+        String::from_utf8(take(input, n)?.to_vec())
+            .map_err(|_| PyValueError::new_err("state holds invalid UTF-8"))
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.is_some().save(out);
+        if let Some(v) = self {
+            v.save(out);
+        }
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        Ok(match bool::load(input)? {
+            true => Some(T::load(input)?),
+            false => None,
+        })
+    }
+}
+
+// Collections are a length and the items. Every item takes at least a byte,
+// so a length beyond the bytes left is corrupt and fails before allocating.
+fn load_items<'a, 'b, T: Persist>(
+    input: &'a mut &'b [u8],
+) -> PyResult<impl Iterator<Item = PyResult<T>> + use<'a, 'b, T>> {
+    let n = usize::load(input)?;
+    if n > input.len() {
+        return Err(truncated());
+    }
+    Ok((0..n).map(move |_| T::load(input)))
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.len().save(out);
+        self.iter().for_each(|v| v.save(out));
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        load_items(input)?.collect()
+    }
+}
+
+impl<T: Persist> Persist for VecDeque<T> {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.len().save(out);
+        self.iter().for_each(|v| v.save(out));
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        load_items(input)?.collect()
+    }
+}
+
+impl<K: Persist + Ord, V: Persist> Persist for BTreeMap<K, V> {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.len().save(out);
+        for (k, v) in self {
+            k.save(out);
+            v.save(out);
+        }
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        load_items::<(K, V)>(input)?.collect()
+    }
+}
+
+impl<K: Persist + Eq + Hash, V: Persist> Persist for HashMap<K, V> {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.len().save(out);
+        for (k, v) in self {
+            k.save(out);
+            v.save(out);
+        }
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        load_items::<(K, V)>(input)?.collect()
+    }
+}
+
+impl<T: Persist, const N: usize> Persist for [T; N] {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.iter().for_each(|v| v.save(out));
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        let items = (0..N)
+            .map(|_| T::load(input))
+            .collect::<PyResult<Vec<T>>>()?;
+        Ok(items.try_into().ok().expect("N items"))
+    }
+}
+
+macro_rules! persist_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: Persist),*> Persist for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn save(&self, out: &mut Vec<u8>) {
+                let ($($name,)*) = self;
+                $( $name.save(out); )*
+            }
+
+            fn load(input: &mut &[u8]) -> PyResult<Self> {
+                Ok(($($name::load(input)?,)*))
+            }
+        }
+    };
+}
+persist_tuple!(A, B);
+persist_tuple!(A, B, C);
+persist_tuple!(A, B, C, D);
+
+// The wall clock, or a replay clock's current time
+impl Persist for Clock {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.is_replay().then(|| self.now_ms()).save(out);
+    }
+
+    fn load(input: &mut &[u8]) -> PyResult<Self> {
+        Ok(match Option::<i64>::load(input)? {
+            Some(ms) => Clock::replay(ms),
+            None => Clock::default(),
+        })
+    }
+}
+
+pub(crate) fn dump<T: Stateful>(value: &T) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+    T::NAME.to_owned().save(&mut out);
+    value.save(&mut out);
+    out
+}
+
+// (class name, fields) from a dumped state
+fn header(mut input: &[u8]) -> PyResult<(String, &[u8])> {
+    if take(&mut input, 4)? != MAGIC {
+        return Err(PyValueError::new_err("not an mm_orderbook state"));
+    }
+    let version = u8::load(&mut input)?;
+    if version != VERSION {
+        return Err(PyValueError::new_err(format!(
+            "unsupported state version {}",
+            version
+        )));
+    }
+    let name = String::load(&mut input)?;
+    Ok((name, input))
+}
+
+fn restore<T: Stateful>(mut input: &[u8]) -> PyResult<T> {
+    let value = T::load(&mut input)?;
+    if !input.is_empty() {
+        return Err(PyValueError::new_err(format!(
+            "{} bytes left after {} state",
+            input.len(),
+            T::NAME
+        )));
+    }
+    Ok(value)
+}
+
+// (callable, args) for __reduce__
+pub(crate) type Reduced<'py> = (Bound<'py, PyAny>, (Bound<'py, PyBytes>,));
+
+pub(crate) fn reduce<'py, T: Stateful>(py: Python<'py>, value: &T) -> PyResult<Reduced<'py>> {
+    let restore = py.import("mm_orderbook")?.getattr("_restore_state")?;
+    Ok((restore, (PyBytes::new(py, &dump(value)),)))
+}
+
+// Rebuild any stateful class from the bytes __reduce__ produced
+#[pyfunction(name = "_restore_state")]
+pub fn restore_state(py: Python<'_>, state: &[u8]) -> PyResult<PyObject> {
+    let (name, fields) = header(state)?;
+    macro_rules! classes {
+        ($($ty:ty),* $(,)?) => {
+            match name.as_str() {
+                $( <$ty as Stateful>::NAME => Py::new(py, restore::<$ty>(fields)?)?.into_any(), )*
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown state class '{}'",
+                        other
+                    )))
+                }
+            }
+        };
+    }
+    Ok(classes!(
+        crate::L2Book,
+        crate::manager::BookManager,
+        crate::filters::SymbolFilters,
+        crate::fees::FeeModel,
+        crate::position::Position,
+        crate::latency::LatencyModel,
+        crate::rolling::RollingStats,
+        crate::ewma::Ewma,
+        crate::ewma::Ewmv,
+        crate::trades::TradeTape,
+        crate::ofi::OfiCalculator,
+        crate::vol::VolEstimator,
+    ))
+}
@@ -0,0 +1,138 @@
+// Self-trade prevention: before sending an order, check it against our own
+// resting orders on the opposite side of the same symbol. A buy crosses any
+// own sell at or below its price; a sell crosses any own buy at or above.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::filters::round_to;
+use crate::orders::OrderManager;
+use crate::Side;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum StpMode {
+    // Move the new order one tick behind our best crossing order
+    Reprice,
+    CancelResting,
+    Reject,
+}
+
+impl StpMode {
+    fn parse(mode: &str) -> PyResult<Self> {
+        match mode.to_ascii_lowercase().as_str() {
+            "reprice" => Ok(Self::Reprice),
+            "cancel_resting" => Ok(Self::CancelResting),
+            "reject" => Ok(Self::Reject),
+            other => Err(PyValueError::new_err(format!(
+                "stp mode must be 'reprice', 'cancel_resting' or 'reject', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Reprice => "reprice",
+            Self::CancelResting => "cancel_resting",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub struct StpDecision {
+    // "none", "reprice", "cancel_resting" or "reject"
+    pub action: &'static str,
+    // Client ids of our resting orders the candidate would trade against
+    pub conflicts: Vec<String>,
+    // Non-crossing price when action is "reprice"
+    pub price: Option<f64>,
+}
+
+#[pymethods]
+impl StpDecision {
+    fn __repr__(&self) -> String {
+        format!(
+            "StpDecision(action={:?}, conflicts={:?}, price={:?})",
+            self.action, self.conflicts, self.price
+        )
+    }
+}
+
+#[pyclass]
+pub struct StpChecker {
+    mode: StpMode,
+    tick_size: Option<f64>,
+}
+
+#[pymethods]
+impl StpChecker {
+    // Repricing needs a tick to step away from our own order
+    #[new]
+    #[pyo3(signature = (mode="cancel_resting", tick_size=None))]
+    pub fn new(mode: &str, tick_size: Option<f64>) -> PyResult<Self> {
+        let mode = StpMode::parse(mode)?;
+        if tick_size.is_some_and(|t| t <= 0.0) {
+            return Err(PyValueError::new_err("tick_size must be positive"));
+        }
+        if mode == StpMode::Reprice && tick_size.is_none() {
+            return Err(PyValueError::new_err("reprice mode requires tick_size"));
+        }
+        Ok(Self { mode, tick_size })
+    }
+
+    #[getter]
+    fn mode(&self) -> &'static str {
+        self.mode.name()
+    }
+
+    #[getter]
+    fn tick_size(&self) -> Option<f64> {
+        self.tick_size
+    }
+
+    pub fn check(
+        &self,
+        orders: &OrderManager,
+        symbol: &str,
+        side: &str,
+        price: f64,
+    ) -> PyResult<StpDecision> {
+        let side = Side::parse(side)?;
+        let mut crossing: Vec<(&str, f64)> = orders
+            .live_on(symbol)
+            .filter(|(_, s, p)| {
+                *s != side
+                    && match side {
+                        Side::Bid => *p <= price,
+                        Side::Ask => *p >= price,
+                    }
+            })
+            .map(|(id, _, p)| (id, p))
+            .collect();
+        // Most aggressive conflicts first
+        crossing.sort_by(|a, b| match side {
+            Side::Bid => a.1.total_cmp(&b.1),
+            Side::Ask => b.1.total_cmp(&a.1),
+        });
+        let Some(&(_, nearest)) = crossing.first() else {
+            return Ok(StpDecision {
+                action: "none",
+                conflicts: Vec::new(),
+                price: None,
+            });
+        };
+        let price = match (self.mode, self.tick_size) {
+            (StpMode::Reprice, Some(tick)) => Some(match side {
+                Side::Bid => round_to(nearest - tick, Some(tick), false),
+                Side::Ask => round_to(nearest + tick, Some(tick), true),
+            }),
+            _ => None,
+        };
+        Ok(StpDecision {
+            action: self.mode.name(),
+            conflicts: crossing.into_iter().map(|(id, _)| id.to_owned()).collect(),
+            price,
+        })
+    }
+}
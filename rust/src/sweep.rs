@@ -0,0 +1,288 @@
+// Sweep detection on the public trade stream. Prints are grouped per
+// aggressor side into bursts: a burst starts with a print and takes every
+// same-side print within window_ms of that first print. Once a burst has
+// traded at min_levels distinct prices and at least min_notional, it is
+// flagged once as a Sweep and on_sweep fires; later prints in the burst only
+// extend it. Independently, any single print of at least large_notional
+// fires on_large_trade. active(ts) tells whether a sweep ended within the
+// last cooldown_ms, i.e. whether quotes should stay pulled.
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::iceberg::same_price;
+use crate::Side;
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct Sweep {
+    // Aggressor side, "buy" (lifting asks) or "sell"
+    pub side: &'static str,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub levels: usize,
+    pub qty: f64,
+    pub notional: f64,
+    pub first_price: f64,
+    pub last_price: f64,
+}
+
+#[pymethods]
+impl Sweep {
+    #[getter]
+    pub fn vwap(&self) -> f64 {
+        self.notional / self.qty
+    }
+
+    // Price distance travelled by the burst, in bps of the first print
+    #[getter]
+    pub fn move_bps(&self) -> f64 {
+        (self.last_price - self.first_price).abs() / self.first_price * 10_000.0
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Sweep(side={:?}, levels={}, qty={}, notional={}, first_price={}, last_price={})",
+            self.side, self.levels, self.qty, self.notional, self.first_price, self.last_price
+        )
+    }
+}
+
+struct Burst {
+    start_ts: i64,
+    last_ts: i64,
+    prices: Vec<f64>,
+    qty: f64,
+    notional: f64,
+    first_price: f64,
+    last_price: f64,
+    flagged: bool,
+}
+
+impl Burst {
+    fn new(ts: i64, price: f64) -> Self {
+        Self {
+            start_ts: ts,
+            last_ts: ts,
+            prices: Vec::new(),
+            qty: 0.0,
+            notional: 0.0,
+            first_price: price,
+            last_price: price,
+            flagged: false,
+        }
+    }
+
+    fn sweep(&self, side: Side) -> Sweep {
+        Sweep {
+            side: side.name(),
+            start_ts: self.start_ts,
+            end_ts: self.last_ts,
+            levels: self.prices.len(),
+            qty: self.qty,
+            notional: self.notional,
+            first_price: self.first_price,
+            last_price: self.last_price,
+        }
+    }
+}
+
+// Python callbacks registered with SweepDetector.set_callbacks
+#[derive(Default)]
+struct SweepHooks {
+    on_sweep: Option<Arc<Py<PyAny>>>,
+    on_large_trade: Option<Arc<Py<PyAny>>>,
+}
+
+impl SweepHooks {
+    fn new(
+        listener: Option<&Bound<'_, PyAny>>,
+        on_sweep: Option<Bound<'_, PyAny>>,
+        on_large_trade: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let hook = |name: &str, explicit: Option<Bound<'_, PyAny>>| -> PyResult<_> {
+            let f = match (explicit, listener) {
+                (Some(f), _) => f,
+                (None, Some(l)) if l.hasattr(name)? => l.getattr(name)?,
+                _ => return Ok(None),
+            };
+            if !f.is_callable() {
+                return Err(PyValueError::new_err(format!("{} must be callable", name)));
+            }
+            Ok(Some(Arc::new(f.unbind())))
+        };
+        Ok(Self {
+            on_sweep: hook("on_sweep", on_sweep)?,
+            on_large_trade: hook("on_large_trade", on_large_trade)?,
+        })
+    }
+}
+
+#[pyclass]
+pub struct SweepDetector {
+    window_ms: i64,
+    min_levels: usize,
+    min_notional: f64,
+    large_notional: Option<f64>,
+    cooldown_ms: i64,
+    // Current burst per aggressor side, buy then sell
+    bursts: [Option<Burst>; 2],
+    last_sweep: Option<Sweep>,
+    count: u64,
+    hooks: SweepHooks,
+}
+
+impl SweepDetector {
+    // Add a print to its side's burst; returns the sweep if it just
+    // qualified
+    fn push(&mut self, ts: i64, price: f64, qty: f64, side: Side) -> Option<Sweep> {
+        let slot = match side {
+            Side::Bid => &mut self.bursts[0],
+            Side::Ask => &mut self.bursts[1],
+        };
+        if slot
+            .as_ref()
+            .is_none_or(|b| ts - b.start_ts > self.window_ms)
+        {
+            *slot = Some(Burst::new(ts, price));
+        }
+        let burst = slot.as_mut()?;
+        burst.last_ts = ts;
+        burst.last_price = price;
+        burst.qty += qty;
+        burst.notional += price * qty;
+        if !burst.prices.iter().any(|p| same_price(*p, price)) {
+            burst.prices.push(price);
+        }
+        let sweep = burst.sweep(side);
+        if burst.flagged {
+            // Keep the last sweep current while the burst runs on
+            self.last_sweep = Some(sweep);
+            return None;
+        }
+        if burst.prices.len() < self.min_levels || burst.notional < self.min_notional {
+            return None;
+        }
+        burst.flagged = true;
+        self.count += 1;
+        self.last_sweep = Some(sweep.clone());
+        Some(sweep)
+    }
+}
+
+#[pymethods]
+impl SweepDetector {
+    // min_levels distinct prices and min_notional within window_ms make a
+    // sweep; large_notional (off by default) flags single prints
+    #[new]
+    #[pyo3(signature = (window_ms=100, min_levels=3, min_notional=0.0, large_notional=None, cooldown_ms=1_000))]
+    pub fn new(
+        window_ms: i64,
+        min_levels: usize,
+        min_notional: f64,
+        large_notional: Option<f64>,
+        cooldown_ms: i64,
+    ) -> PyResult<Self> {
+        if window_ms <= 0 || min_levels == 0 {
+            return Err(PyValueError::new_err(
+                "window_ms and min_levels must be positive",
+            ));
+        }
+        if min_notional < 0.0 || cooldown_ms < 0 || large_notional.is_some_and(|n| n <= 0.0) {
+            return Err(PyValueError::new_err(
+                "min_notional and cooldown_ms must be non-negative, large_notional positive",
+            ));
+        }
+        Ok(Self {
+            window_ms,
+            min_levels,
+            min_notional,
+            large_notional,
+            cooldown_ms,
+            bursts: [None, None],
+            last_sweep: None,
+            count: 0,
+            hooks: SweepHooks::default(),
+        })
+    }
+
+    // side is the aggressor: "buy" or "sell". Returns the Sweep this print
+    // completed, if any. Callbacks run after the detector is released, so
+    // they may read it; the first exception propagates.
+    pub fn on_trade(
+        slf: &Bound<'_, Self>,
+        ts: i64,
+        price: f64,
+        qty: f64,
+        side: &str,
+    ) -> PyResult<Option<Sweep>> {
+        let py = slf.py();
+        let side = Side::parse(side)?;
+        let mut det = slf.borrow_mut();
+        let sweep = det.push(ts, price, qty, side);
+        let large = det.large_notional.is_some_and(|n| price * qty >= n);
+        let on_large = det.hooks.on_large_trade.clone().filter(|_| large);
+        let on_sweep = det.hooks.on_sweep.clone().filter(|_| sweep.is_some());
+        drop(det);
+        if let Some(f) = on_large {
+            f.call1(py, (ts, price, qty, side.name()))?;
+        }
+        if let (Some(f), Some(sweep)) = (on_sweep, &sweep) {
+            f.call1(py, (sweep.clone(),))?;
+        }
+        Ok(sweep)
+    }
+
+    // Batch of (ts, price, qty, side) tuples; returns the sweeps found
+    pub fn on_trades(
+        slf: &Bound<'_, Self>,
+        trades: Vec<(i64, f64, f64, String)>,
+    ) -> PyResult<Vec<Sweep>> {
+        let mut out = Vec::new();
+        for (ts, price, qty, side) in trades {
+            out.extend(Self::on_trade(slf, ts, price, qty, &side)?);
+        }
+        Ok(out)
+    }
+
+    // Callbacks fired by on_trade:
+    //   on_sweep(sweep)                       a burst qualified as a sweep
+    //   on_large_trade(ts, price, qty, side)  one print of large_notional
+    // `listener` may be any object with methods of those names; explicit
+    // callables take precedence. set_callbacks() with no arguments clears them.
+    #[pyo3(signature = (listener=None, on_sweep=None, on_large_trade=None))]
+    pub fn set_callbacks(
+        &mut self,
+        listener: Option<&Bound<'_, PyAny>>,
+        on_sweep: Option<Bound<'_, PyAny>>,
+        on_large_trade: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        self.hooks = SweepHooks::new(listener, on_sweep, on_large_trade)?;
+        Ok(())
+    }
+
+    // Whether a sweep ended within cooldown_ms of `ts`
+    pub fn active(&self, ts: i64) -> bool {
+        self.last_sweep
+            .as_ref()
+            .is_some_and(|s| ts - s.end_ts <= self.cooldown_ms)
+    }
+
+    #[getter]
+    pub fn last_sweep(&self) -> Option<Sweep> {
+        self.last_sweep.clone()
+    }
+
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    // Clears bursts and the last sweep; callbacks stay registered
+    pub fn reset(&mut self) {
+        self.bursts = [None, None];
+        self.last_sweep = None;
+        self.count = 0;
+    }
+}
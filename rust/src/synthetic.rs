@@ -0,0 +1,527 @@
+// Synthetic L2 feed for stress tests where no recorded data exists. The
+// stream opens with a snapshot, then each event is either a book update or,
+// with probability trade_prob, a trade followed by the delta it leaves.
+//   mid       log random walk at event times, volatility_bps per sqrt second
+//   regimes   optional [(volatility_bps, mean_duration_ms)], switched as a
+//             Markov chain with exponential holding times
+//   book      `levels` per side around the mid, spread_ticks wide at the
+//             touch; level k holds depth * (1 + depth_slope * k) times
+//             mean-one lognormal noise (size_noise in log space)
+//   updates   the ladder recentres on the mid (levels falling out go to 0,
+//             new ones come in) and one level, biased to the touch, resizes
+//   trades    aggressor side at even odds, exponential size with mean
+//             trade_size, capped at the touch it hits
+//   timing    Hawkes arrivals with exponential kernel (burst_decay_ms) and
+//             branching ratio `burstiness`, thinned as in Ogata; the base
+//             rate is scaled so the long-run rate stays event_rate per second
+// Levels are kept by tick index, so prices are exact multiples of tick_size
+// and deltas only carry levels that changed. Same seed, same stream.
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, VecDeque};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::backtest::Backtester;
+use crate::filters;
+use crate::journal::{JournalRecord, JournalWriter};
+use crate::latency::Rng;
+use crate::Levels;
+
+// Generator state; rebuilt from the seed on reset()
+struct State {
+    rng: Rng,
+    // Time in ms since start_ts, fractional between events
+    t: f64,
+    mid: f64,
+    regime: usize,
+    regime_until: f64,
+    // Hawkes excitation at time t, in events per ms
+    excite: f64,
+    bids: BTreeMap<i64, f64>,
+    asks: BTreeMap<i64, f64>,
+    update_id: u64,
+    started: bool,
+}
+
+struct Config {
+    symbol: String,
+    start_ts: i64,
+    mid: f64,
+    tick_size: f64,
+    spread_ticks: i64,
+    levels: usize,
+    depth: f64,
+    depth_slope: f64,
+    size_noise: f64,
+    // (volatility_bps, mean_duration_ms)
+    regimes: Vec<(f64, f64)>,
+    event_rate: f64,
+    burstiness: f64,
+    burst_decay_ms: f64,
+    trade_prob: f64,
+    trade_size: f64,
+    seed: u64,
+}
+
+// Changed (tick index, size) per side; size 0 removes the level
+type Changes = (Vec<(i64, f64)>, Vec<(i64, f64)>);
+
+impl Config {
+    fn state(&self) -> State {
+        let mut rng = Rng::new(self.seed);
+        let regime_until = self.holding_time(&mut rng, 0);
+        let mut s = State {
+            rng,
+            t: 0.0,
+            mid: self.mid,
+            regime: 0,
+            regime_until,
+            excite: 0.0,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            update_id: 0,
+            started: false,
+        };
+        self.recentre(&mut s);
+        s
+    }
+
+    fn holding_time(&self, rng: &mut Rng, regime: usize) -> f64 {
+        if self.regimes.len() > 1 {
+            rng.exponential(self.regimes[regime].1)
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    fn level_size(&self, rng: &mut Rng, k: usize) -> f64 {
+        let noise = (self.size_noise * rng.std_normal() - 0.5 * self.size_noise.powi(2)).exp();
+        self.depth * (1.0 + self.depth_slope * k as f64) * noise
+    }
+
+    // Touch tick indices (bid, ask) around `mid`
+    fn touch(&self, mid: f64) -> (i64, i64) {
+        let bid = (mid / self.tick_size - 0.5 * self.spread_ticks as f64).round() as i64;
+        (bid, bid + self.spread_ticks)
+    }
+
+    fn price(&self, index: i64) -> f64 {
+        filters::clean(index as f64 * self.tick_size, self.tick_size)
+    }
+
+    fn ts(&self, s: &State) -> i64 {
+        self.start_ts + s.t.floor() as i64
+    }
+
+    // Move both ladders onto the window around the mid
+    fn recentre(&self, s: &mut State) -> Changes {
+        let (bid, ask) = self.touch(s.mid);
+        let n = self.levels as i64;
+        let mut changes: Changes = (Vec::new(), Vec::new());
+        for (ladder, lo, hi, out, is_bid) in [
+            (&mut s.bids, bid - n + 1, bid, &mut changes.0, true),
+            (&mut s.asks, ask, ask + n - 1, &mut changes.1, false),
+        ] {
+            let gone: Vec<i64> = ladder
+                .keys()
+                .filter(|i| **i < lo || **i > hi)
+                .copied()
+                .collect();
+            for i in gone {
+                ladder.remove(&i);
+                out.push((i, 0.0));
+            }
+            for i in lo..=hi {
+                if let Entry::Vacant(e) = ladder.entry(i) {
+                    let k = if is_bid { hi - i } else { i - lo };
+                    let size = *e.insert(self.level_size(&mut s.rng, k as usize));
+                    out.push((i, size));
+                }
+            }
+        }
+        changes
+    }
+
+    // Next Hawkes arrival; switches regime and moves the mid over the gap
+    fn advance(&self, s: &mut State) {
+        let base = self.event_rate / 1_000.0 * (1.0 - self.burstiness);
+        let start = s.t;
+        loop {
+            // Excitation only decays between arrivals, so the current
+            // intensity bounds the rest of the gap
+            let bound = base + s.excite;
+            let wait = s.rng.exponential(1.0 / bound);
+            s.t += wait;
+            s.excite *= (-wait / self.burst_decay_ms).exp();
+            if s.rng.uniform() * bound <= base + s.excite {
+                break;
+            }
+        }
+        s.excite += self.burstiness / self.burst_decay_ms;
+        while s.t >= s.regime_until {
+            let next = (s.rng.next_u64() % (self.regimes.len() as u64 - 1)) as usize;
+            s.regime = if next >= s.regime { next + 1 } else { next };
+            s.regime_until += self.holding_time(&mut s.rng, s.regime);
+        }
+        let sigma = self.regimes[s.regime].0 / 10_000.0 * ((s.t - start) / 1_000.0).sqrt();
+        s.mid *= (sigma * s.rng.std_normal() - 0.5 * sigma * sigma).exp();
+    }
+
+    fn levels(&self, changes: &[(i64, f64)], bids: bool) -> Levels {
+        let mut out: Levels = changes.iter().map(|(i, q)| (self.price(*i), *q)).collect();
+        if bids {
+            out.sort_by(|a, b| b.0.total_cmp(&a.0));
+        } else {
+            out.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        out
+    }
+
+    fn book(&self, s: &State, kind: &'static str, bids: Levels, asks: Levels) -> JournalRecord {
+        JournalRecord {
+            kind,
+            symbol: self.symbol.clone(),
+            ts: self.ts(s),
+            bids,
+            asks,
+            update_id: Some(s.update_id),
+            price: None,
+            qty: None,
+            side: None,
+        }
+    }
+
+    fn snapshot(&self, s: &State) -> JournalRecord {
+        let bids = s.bids.iter().rev().map(|(i, q)| (self.price(*i), *q));
+        let asks = s.asks.iter().map(|(i, q)| (self.price(*i), *q));
+        self.book(s, "snapshot", bids.collect(), asks.collect())
+    }
+
+    // Trade against the touch on a random side; None if that side is empty
+    fn trade(&self, s: &mut State, changes: &mut Changes) -> Option<JournalRecord> {
+        let buy = s.rng.uniform() <= 0.5;
+        let (ladder, out) = if buy {
+            (&mut s.asks, &mut changes.1)
+        } else {
+            (&mut s.bids, &mut changes.0)
+        };
+        let (&index, &size) = if buy {
+            ladder.iter().next()?
+        } else {
+            ladder.iter().next_back()?
+        };
+        let qty = s.rng.exponential(self.trade_size).min(size);
+        if qty < size {
+            ladder.insert(index, size - qty);
+            out.push((index, size - qty));
+        } else {
+            ladder.remove(&index);
+            out.push((index, 0.0));
+        }
+        Some(JournalRecord {
+            kind: "trade",
+            symbol: self.symbol.clone(),
+            ts: self.ts(s),
+            bids: Vec::new(),
+            asks: Vec::new(),
+            update_id: None,
+            price: Some(self.price(index)),
+            qty: Some(qty),
+            side: Some(if buy { "buy" } else { "sell" }),
+        })
+    }
+
+    // Generate the next event's records into `out`
+    fn step(&self, s: &mut State, out: &mut VecDeque<JournalRecord>) {
+        if !s.started {
+            s.started = true;
+            s.update_id += 1;
+            out.push_back(self.snapshot(s));
+            return;
+        }
+        self.advance(s);
+        let mut changes: Changes = (Vec::new(), Vec::new());
+        let trade = match s.rng.uniform() < self.trade_prob {
+            true => self.trade(s, &mut changes),
+            false => None,
+        };
+        if let Some(trade) = trade {
+            out.push_back(trade);
+        } else {
+            // Also refills a side that trades emptied
+            changes = self.recentre(s);
+            // One resting level resizes, most often near the touch
+            let k = (s.rng.exponential(2.0) as usize).min(self.levels - 1);
+            let (bid, ask) = self.touch(s.mid);
+            let (index, ladder, side) = if s.rng.uniform() <= 0.5 {
+                (bid - k as i64, &mut s.bids, &mut changes.0)
+            } else {
+                (ask + k as i64, &mut s.asks, &mut changes.1)
+            };
+            let size = self.level_size(&mut s.rng, k);
+            ladder.insert(index, size);
+            side.retain(|(i, _)| *i != index);
+            side.push((index, size));
+        }
+        if changes.0.is_empty() && changes.1.is_empty() {
+            return;
+        }
+        s.update_id += 1;
+        let bids = self.levels(&changes.0, true);
+        let asks = self.levels(&changes.1, false);
+        out.push_back(self.book(s, "delta", bids, asks));
+    }
+}
+
+#[pyclass]
+pub struct SyntheticFeed {
+    config: Config,
+    state: State,
+    pending: VecDeque<JournalRecord>,
+}
+
+impl SyntheticFeed {
+    fn peek(&mut self) -> &JournalRecord {
+        while self.pending.is_empty() {
+            self.config.step(&mut self.state, &mut self.pending);
+        }
+        &self.pending[0]
+    }
+
+    fn pop(&mut self) -> JournalRecord {
+        self.peek();
+        self.pending.pop_front().expect("peek generated a record")
+    }
+}
+
+#[pymethods]
+impl SyntheticFeed {
+    // volatility_bps is per sqrt second and ignored when regimes are given;
+    // event_rate is events per second, each a book update or a trade
+    #[new]
+    #[pyo3(signature = (
+        symbol="SYN".to_owned(),
+        start_ts=0,
+        mid=100.0,
+        tick_size=0.01,
+        spread_ticks=1,
+        levels=10,
+        depth=1.0,
+        depth_slope=0.5,
+        size_noise=0.2,
+        volatility_bps=2.0,
+        regimes=None,
+        event_rate=50.0,
+        burstiness=0.0,
+        burst_decay_ms=100.0,
+        trade_prob=0.2,
+        trade_size=0.5,
+        seed=0
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        symbol: String,
+        start_ts: i64,
+        mid: f64,
+        tick_size: f64,
+        spread_ticks: i64,
+        levels: usize,
+        depth: f64,
+        depth_slope: f64,
+        size_noise: f64,
+        volatility_bps: f64,
+        regimes: Option<Vec<(f64, f64)>>,
+        event_rate: f64,
+        burstiness: f64,
+        burst_decay_ms: f64,
+        trade_prob: f64,
+        trade_size: f64,
+        seed: u64,
+    ) -> PyResult<Self> {
+        if !(mid > 0.0 && tick_size > 0.0 && depth > 0.0 && trade_size > 0.0) {
+            return Err(PyValueError::new_err(
+                "mid, tick_size, depth and trade_size must be positive",
+            ));
+        }
+        if spread_ticks < 1 || levels < 1 {
+            return Err(PyValueError::new_err(
+                "spread_ticks and levels must be at least 1",
+            ));
+        }
+        if depth_slope < 0.0 || size_noise < 0.0 || volatility_bps < 0.0 {
+            return Err(PyValueError::new_err(
+                "depth_slope, size_noise and volatility_bps must be non-negative",
+            ));
+        }
+        if !(event_rate > 0.0 && burst_decay_ms > 0.0) {
+            return Err(PyValueError::new_err(
+                "event_rate and burst_decay_ms must be positive",
+            ));
+        }
+        if !(0.0..1.0).contains(&burstiness) || !(0.0..=1.0).contains(&trade_prob) {
+            return Err(PyValueError::new_err(
+                "burstiness must be in [0, 1) and trade_prob in [0, 1]",
+            ));
+        }
+        let regimes = match regimes {
+            Some(r) if r.is_empty() => return Err(PyValueError::new_err("regimes is empty")),
+            Some(r) if r.iter().any(|(v, d)| *v < 0.0 || *d <= 0.0) => {
+                return Err(PyValueError::new_err(
+                    "regime volatilities must be non-negative and durations positive",
+                ))
+            }
+            Some(r) => r,
+            None => vec![(volatility_bps, f64::INFINITY)],
+        };
+        let config = Config {
+            symbol,
+            start_ts,
+            mid,
+            tick_size,
+            spread_ticks,
+            levels,
+            depth,
+            depth_slope,
+            size_noise,
+            regimes,
+            event_rate,
+            burstiness,
+            burst_decay_ms,
+            trade_prob,
+            trade_size,
+            seed,
+        };
+        let state = config.state();
+        Ok(Self {
+            config,
+            state,
+            pending: VecDeque::new(),
+        })
+    }
+
+    // The full book at the latest generated update_id. The delta after a
+    // trade is generated with it, so like an exchange snapshot this can be
+    // ahead of the stream: skip deltas at or below its update_id.
+    pub fn snapshot(&self) -> JournalRecord {
+        self.config.snapshot(&self.state)
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    // Never exhausted
+    fn __next__(&mut self) -> JournalRecord {
+        self.pop()
+    }
+
+    pub fn take(&mut self, n: usize) -> Vec<JournalRecord> {
+        (0..n).map(|_| self.pop()).collect()
+    }
+
+    // Records with ts < end_ts; later ones stay queued
+    pub fn until(&mut self, end_ts: i64) -> Vec<JournalRecord> {
+        let mut out = Vec::new();
+        while self.peek().ts < end_ts {
+            out.push(self.pop());
+        }
+        out
+    }
+
+    // Feed records with ts < end_ts into a Backtester; returns the count
+    pub fn load_into(&mut self, backtester: &mut Backtester, end_ts: i64) -> PyResult<usize> {
+        let records = self.until(end_ts);
+        for rec in &records {
+            let symbol = rec.symbol.clone();
+            match rec.kind {
+                "snapshot" => {
+                    backtester.add_snapshot(symbol, rec.ts, rec.bids.clone(), rec.asks.clone())?
+                }
+                "delta" => {
+                    backtester.add_delta(symbol, rec.ts, rec.bids.clone(), rec.asks.clone())?
+                }
+                _ => backtester.add_trade(
+                    symbol,
+                    rec.ts,
+                    rec.price.unwrap_or(0.0),
+                    rec.qty.unwrap_or(0.0),
+                ),
+            }
+        }
+        Ok(records.len())
+    }
+
+    // Write records with ts < end_ts to a journal; returns the count
+    pub fn write_to(&mut self, writer: &mut JournalWriter, end_ts: i64) -> PyResult<usize> {
+        let records = self.until(end_ts);
+        for rec in &records {
+            let (bids, asks) = (rec.bids.clone(), rec.asks.clone());
+            match rec.kind {
+                "snapshot" => {
+                    writer.write_snapshot(&rec.symbol, rec.ts, bids, asks, rec.update_id)?
+                }
+                "delta" => writer.write_delta(&rec.symbol, rec.ts, bids, asks, rec.update_id)?,
+                _ => writer.write_trade(
+                    &rec.symbol,
+                    rec.ts,
+                    rec.price.unwrap_or(0.0),
+                    rec.qty.unwrap_or(0.0),
+                    rec.side.unwrap_or("buy"),
+                )?,
+            }
+        }
+        Ok(records.len())
+    }
+
+    // Back to the first record of the seeded stream
+    pub fn reset(&mut self) {
+        self.state = self.config.state();
+        self.pending.clear();
+    }
+
+    #[getter]
+    pub fn symbol(&self) -> String {
+        self.config.symbol.clone()
+    }
+
+    #[getter]
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
+
+    #[getter]
+    pub fn tick_size(&self) -> f64 {
+        self.config.tick_size
+    }
+
+    // Latent mid the book is centred on
+    #[getter]
+    pub fn mid(&self) -> f64 {
+        self.state.mid
+    }
+
+    // Timestamp of the latest generated event
+    #[getter]
+    pub fn now(&self) -> i64 {
+        self.config.ts(&self.state)
+    }
+
+    // Index into regimes (0 without regimes)
+    #[getter]
+    pub fn regime(&self) -> usize {
+        self.state.regime
+    }
+
+    #[getter]
+    pub fn volatility_bps(&self) -> f64 {
+        self.config.regimes[self.state.regime].0
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SyntheticFeed(symbol={:?}, mid={}, regime={}, seed={})",
+            self.config.symbol, self.state.mid, self.state.regime, self.config.seed
+        )
+    }
+}
@@ -0,0 +1,347 @@
+// Client connections for ws.rs and http.rs: plain TCP, or TLS over the
+// system OpenSSL (libssl 3) for wss:// and https://. The FFI is hand-rolled
+// like the mmap calls in shm.rs, so there is no extra crate; only the few
+// libssl entry points below are used.
+// TLS is built on unix with the default `tls` cargo feature. Without it
+// (Windows, or `--no-default-features` on a host without libssl) the
+// extension still builds, and wss:// / https:// URLs are rejected up front.
+// Peers are verified against the default trust store (honouring the
+// SSL_CERT_FILE / SSL_CERT_DIR environment variables) and the host name or
+// IP address in the URL; SNI is sent for names. Streams are blocking: a
+// socket read timeout surfaces as WouldBlock, as for a plain TcpStream, and
+// OpenSSL keeps any partial record for the next read.
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+#[cfg(all(unix, feature = "tls"))]
+use std::ffi::{CStr, CString};
+#[cfg(all(unix, feature = "tls"))]
+use std::net::IpAddr;
+#[cfg(all(unix, feature = "tls"))]
+use std::os::raw::{c_char, c_int, c_long, c_ulong};
+
+// Whether this build can open wss:// and https:// connections
+pub const ENABLED: bool = cfg!(all(unix, feature = "tls"));
+
+// The error for a TLS URL in a build without TLS
+pub fn unsupported(url: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "'{}' needs TLS, which this build lacks (it needs unix and the 'tls' feature)",
+            url
+        ),
+    )
+}
+
+#[cfg(all(unix, feature = "tls"))]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+mod sys {
+    use std::os::raw::{c_char, c_int, c_long, c_ulong, c_void};
+
+    pub enum SSL_METHOD {}
+    pub enum SSL_CTX {}
+    pub enum SSL {}
+
+    pub const SSL_VERIFY_PEER: c_int = 1;
+    // Treat a peer closing without close_notify as a clean EOF, like the
+    // plain path; HTTP bodies delimited by the end of the stream need it
+    pub const SSL_OP_IGNORE_UNEXPECTED_EOF: u64 = 1 << 7;
+    pub const SSL_CTRL_SET_TLSEXT_HOSTNAME: c_int = 55;
+    pub const TLSEXT_NAMETYPE_HOST_NAME: c_long = 0;
+
+    pub const SSL_ERROR_WANT_READ: c_int = 2;
+    pub const SSL_ERROR_WANT_WRITE: c_int = 3;
+    pub const SSL_ERROR_SYSCALL: c_int = 5;
+    pub const SSL_ERROR_ZERO_RETURN: c_int = 6;
+
+    #[link(name = "ssl")]
+    extern "C" {
+        pub fn TLS_client_method() -> *const SSL_METHOD;
+        pub fn SSL_CTX_new(method: *const SSL_METHOD) -> *mut SSL_CTX;
+        pub fn SSL_CTX_free(ctx: *mut SSL_CTX);
+        pub fn SSL_CTX_set_default_verify_paths(ctx: *mut SSL_CTX) -> c_int;
+        pub fn SSL_CTX_set_verify(ctx: *mut SSL_CTX, mode: c_int, callback: *const c_void);
+        pub fn SSL_CTX_set_options(ctx: *mut SSL_CTX, options: u64) -> u64;
+        pub fn SSL_new(ctx: *mut SSL_CTX) -> *mut SSL;
+        pub fn SSL_free(ssl: *mut SSL);
+        pub fn SSL_set_fd(ssl: *mut SSL, fd: c_int) -> c_int;
+        pub fn SSL_set1_host(ssl: *mut SSL, host: *const c_char) -> c_int;
+        pub fn SSL_ctrl(ssl: *mut SSL, cmd: c_int, larg: c_long, parg: *mut c_void) -> c_long;
+        pub fn SSL_connect(ssl: *mut SSL) -> c_int;
+        pub fn SSL_read(ssl: *mut SSL, buf: *mut c_void, num: c_int) -> c_int;
+        pub fn SSL_write(ssl: *mut SSL, buf: *const c_void, num: c_int) -> c_int;
+        pub fn SSL_shutdown(ssl: *mut SSL) -> c_int;
+        pub fn SSL_get_error(ssl: *const SSL, ret: c_int) -> c_int;
+        pub fn SSL_get_verify_result(ssl: *const SSL) -> c_long;
+    }
+
+    #[link(name = "crypto")]
+    extern "C" {
+        pub fn ERR_get_error() -> c_ulong;
+        pub fn ERR_clear_error();
+        pub fn ERR_error_string_n(e: c_ulong, buf: *mut c_char, len: usize);
+        pub fn X509_verify_cert_error_string(n: c_long) -> *const c_char;
+    }
+}
+
+pub enum Stream {
+    Plain(TcpStream),
+    #[cfg(all(unix, feature = "tls"))]
+    Tls(TlsStream),
+}
+
+#[cfg(all(unix, feature = "tls"))]
+pub struct TlsStream {
+    ctx: *mut sys::SSL_CTX,
+    ssl: *mut sys::SSL,
+    // Owns the socket the SSL object reads and writes
+    tcp: TcpStream,
+}
+
+// The SSL objects are only ever used by the thread that owns the stream
+#[cfg(all(unix, feature = "tls"))]
+unsafe impl Send for TlsStream {}
+
+// Connect to host:port, with a TLS handshake on top when `tls` is set
+pub fn connect(host: &str, port: u16, tls: bool, timeout: Duration) -> io::Result<Stream> {
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| invalid(format!("could not resolve '{}'", host)))?;
+    let tcp = TcpStream::connect_timeout(&addr, timeout)?;
+    tcp.set_nodelay(true)?;
+    if !tls {
+        return Ok(Stream::Plain(tcp));
+    }
+    #[cfg(all(unix, feature = "tls"))]
+    {
+        tcp.set_read_timeout(Some(timeout))?;
+        tcp.set_write_timeout(Some(timeout))?;
+        let stream = TlsStream::handshake(tcp, host)?;
+        stream.tcp.set_write_timeout(None)?;
+        Ok(Stream::Tls(stream))
+    }
+    #[cfg(not(all(unix, feature = "tls")))]
+    Err(unsupported(&format!("{}:{}", host, port)))
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+// Drain OpenSSL's error queue into one message
+#[cfg(all(unix, feature = "tls"))]
+fn ssl_error(context: &str) -> io::Error {
+    let mut parts = Vec::new();
+    loop {
+        // SAFETY: reads and pops this thread's error queue
+        let code: c_ulong = unsafe { sys::ERR_get_error() };
+        if code == 0 {
+            break;
+        }
+        let mut buf = [0 as c_char; 256];
+        // SAFETY: writes at most buf.len() bytes, NUL-terminated
+        unsafe { sys::ERR_error_string_n(code, buf.as_mut_ptr(), buf.len()) };
+        // SAFETY: NUL-terminated by ERR_error_string_n
+        parts.push(
+            unsafe { CStr::from_ptr(buf.as_ptr()) }
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+    if parts.is_empty() {
+        io::Error::other(context.to_string())
+    } else {
+        io::Error::other(format!("{}: {}", context, parts.join("; ")))
+    }
+}
+
+#[cfg(all(unix, feature = "tls"))]
+impl TlsStream {
+    fn handshake(tcp: TcpStream, host: &str) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let name = CString::new(host).map_err(|_| invalid(format!("bad host name '{}'", host)))?;
+        // SAFETY: plain constructor calls; each object is owned by `stream`
+        // as soon as it exists, so Drop frees it on every error path below
+        let mut stream = unsafe {
+            sys::ERR_clear_error();
+            let ctx = sys::SSL_CTX_new(sys::TLS_client_method());
+            if ctx.is_null() {
+                return Err(ssl_error("TLS context"));
+            }
+            Self {
+                ctx,
+                ssl: std::ptr::null_mut(),
+                tcp,
+            }
+        };
+        // SAFETY: ctx and ssl are valid until Drop; `name` outlives the calls
+        // that read it (libssl copies it)
+        unsafe {
+            sys::SSL_CTX_set_verify(stream.ctx, sys::SSL_VERIFY_PEER, std::ptr::null());
+            sys::SSL_CTX_set_options(stream.ctx, sys::SSL_OP_IGNORE_UNEXPECTED_EOF);
+            if sys::SSL_CTX_set_default_verify_paths(stream.ctx) != 1 {
+                return Err(ssl_error("TLS trust store"));
+            }
+            stream.ssl = sys::SSL_new(stream.ctx);
+            if stream.ssl.is_null() {
+                return Err(ssl_error("TLS session"));
+            }
+            // SSL_set1_host takes an IP address literal too; SNI is for names
+            if sys::SSL_set1_host(stream.ssl, name.as_ptr()) != 1 {
+                return Err(ssl_error("TLS host check"));
+            }
+            if host.parse::<IpAddr>().is_err()
+                && sys::SSL_ctrl(
+                    stream.ssl,
+                    sys::SSL_CTRL_SET_TLSEXT_HOSTNAME,
+                    sys::TLSEXT_NAMETYPE_HOST_NAME,
+                    name.as_ptr() as *mut _,
+                ) != 1
+            {
+                return Err(ssl_error("TLS server name"));
+            }
+            if sys::SSL_set_fd(stream.ssl, stream.tcp.as_raw_fd()) != 1 {
+                return Err(ssl_error("TLS socket"));
+            }
+            let ret = sys::SSL_connect(stream.ssl);
+            if ret != 1 {
+                let verify: c_long = sys::SSL_get_verify_result(stream.ssl);
+                if verify != 0 {
+                    let reason = CStr::from_ptr(sys::X509_verify_cert_error_string(verify));
+                    sys::ERR_clear_error();
+                    return Err(invalid(format!(
+                        "TLS handshake with {}: certificate verify failed ({})",
+                        host,
+                        reason.to_string_lossy()
+                    )));
+                }
+                return Err(stream.error(ret, &format!("TLS handshake with {}", host)));
+            }
+        }
+        Ok(stream)
+    }
+
+    // The io::Error for a failed SSL_read/SSL_write/SSL_connect
+    fn error(&self, ret: c_int, context: &str) -> io::Error {
+        // SAFETY: ssl is valid; must be called right after the failing call
+        match unsafe { sys::SSL_get_error(self.ssl, ret) } {
+            sys::SSL_ERROR_WANT_READ | sys::SSL_ERROR_WANT_WRITE => {
+                // SAFETY: discards this thread's queued errors
+                unsafe { sys::ERR_clear_error() };
+                io::Error::new(io::ErrorKind::WouldBlock, format!("{}: timed out", context))
+            }
+            sys::SSL_ERROR_SYSCALL => {
+                let os = io::Error::last_os_error();
+                let err = ssl_error(context);
+                if os.raw_os_error().is_some_and(|e| e != 0) {
+                    os
+                } else {
+                    err
+                }
+            }
+            _ => ssl_error(context),
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "tls"))]
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(c_int::MAX as usize) as c_int;
+        // SAFETY: ssl is valid and buf holds at least len bytes
+        let ret = unsafe {
+            sys::ERR_clear_error();
+            sys::SSL_read(self.ssl, buf.as_mut_ptr().cast(), len)
+        };
+        if ret > 0 {
+            return Ok(ret as usize);
+        }
+        // SAFETY: right after the failing SSL_read
+        if unsafe { sys::SSL_get_error(self.ssl, ret) } == sys::SSL_ERROR_ZERO_RETURN {
+            return Ok(0);
+        }
+        Err(self.error(ret, "TLS read"))
+    }
+}
+
+#[cfg(all(unix, feature = "tls"))]
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let len = buf.len().min(c_int::MAX as usize) as c_int;
+        // SAFETY: ssl is valid and buf holds at least len bytes
+        let ret = unsafe {
+            sys::ERR_clear_error();
+            sys::SSL_write(self.ssl, buf.as_ptr().cast(), len)
+        };
+        if ret > 0 {
+            Ok(ret as usize)
+        } else {
+            Err(self.error(ret, "TLS write"))
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, feature = "tls"))]
+impl Drop for TlsStream {
+    fn drop(&mut self) {
+        // SAFETY: both objects were created in handshake and are freed once;
+        // SSL_free drops the session's reference to ctx first
+        unsafe {
+            if !self.ssl.is_null() {
+                sys::SSL_shutdown(self.ssl);
+                sys::SSL_free(self.ssl);
+            }
+            sys::SSL_CTX_free(self.ctx);
+            sys::ERR_clear_error();
+        }
+    }
+}
+
+impl Stream {
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Stream::Plain(tcp) => tcp.set_read_timeout(timeout),
+            #[cfg(all(unix, feature = "tls"))]
+            Stream::Tls(tls) => tls.tcp.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(tcp) => tcp.read(buf),
+            #[cfg(all(unix, feature = "tls"))]
+            Stream::Tls(tls) => tls.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(tcp) => tcp.write(buf),
+            #[cfg(all(unix, feature = "tls"))]
+            Stream::Tls(tls) => tls.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(tcp) => tcp.flush(),
+            #[cfg(all(unix, feature = "tls"))]
+            Stream::Tls(tls) => tls.flush(),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Rolling-window analytics over the public trade stream
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::arrow::{ArrowBatch, Batch, Column};
+use crate::clock::{self, Clock};
+use crate::state::{self, persist_fields, Stateful};
+use crate::Side;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Trade {
+    pub ts: i64,
+    pub price: f64,
+    pub size: f64,
+    // Aggressor side
+    pub side: Side,
+}
+
+persist_fields!(Trade {
+    ts,
+    price,
+    size,
+    side
+});
+
+#[pyclass]
+#[derive(Clone)]
+pub struct TradeTape {
+    window_ms: i64,
+    trades: VecDeque<Trade>,
+    // Indices (into the logical stream) of trades with decreasing size, for the rolling max
+    max_queue: VecDeque<(u64, f64)>,
+    pushed: u64,
+    notional: f64,
+    buy_volume: f64,
+    sell_volume: f64,
+    last_ts: Option<i64>,
+    clock: Option<Clock>,
+}
+
+persist_fields!(TradeTape {
+    window_ms,
+    trades,
+    max_queue,
+    pushed,
+    notional,
+    buy_volume,
+    sell_volume,
+    last_ts,
+    clock
+});
+
+impl Stateful for TradeTape {
+    const NAME: &'static str = "TradeTape";
+}
+
+impl TradeTape {
+    pub fn push(&mut self, trade: Trade) {
+        let seq = self.pushed;
+        self.pushed += 1;
+        self.notional += trade.price * trade.size;
+        match trade.side {
+            Side::Bid => self.buy_volume += trade.size,
+            Side::Ask => self.sell_volume += trade.size,
+        }
+        while self.max_queue.back().is_some_and(|(_, s)| *s <= trade.size) {
+            self.max_queue.pop_back();
+        }
+        self.max_queue.push_back((seq, trade.size));
+        self.trades.push_back(trade);
+        self.last_ts = Some(self.last_ts.map_or(trade.ts, |t| t.max(trade.ts)));
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let Some(last) = self.last_ts else {
+            return;
+        };
+        let cutoff = last - self.window_ms;
+        while self.trades.front().is_some_and(|t| t.ts <= cutoff) {
+            let t = self.trades.pop_front().unwrap();
+            self.notional -= t.price * t.size;
+            match t.side {
+                Side::Bid => self.buy_volume -= t.size,
+                Side::Ask => self.sell_volume -= t.size,
+            }
+        }
+        let first_live = self.pushed - self.trades.len() as u64;
+        while self
+            .max_queue
+            .front()
+            .is_some_and(|(seq, _)| *seq < first_live)
+        {
+            self.max_queue.pop_front();
+        }
+        if self.trades.is_empty() {
+            // Reset running sums so float error does not accumulate
+            self.notional = 0.0;
+            self.buy_volume = 0.0;
+            self.sell_volume = 0.0;
+        }
+    }
+}
+
+#[pymethods]
+impl TradeTape {
+    #[new]
+    #[pyo3(signature = (window_ms=60_000, clock=None))]
+    pub fn new(window_ms: i64, clock: Option<Clock>) -> PyResult<Self> {
+        if window_ms <= 0 {
+            return Err(PyValueError::new_err("window_ms must be positive"));
+        }
+        Ok(Self {
+            window_ms,
+            trades: VecDeque::new(),
+            max_queue: VecDeque::new(),
+            pushed: 0,
+            notional: 0.0,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+            last_ts: None,
+            clock,
+        })
+    }
+
+    // side is the aggressor: "buy" or "sell"
+    pub fn add_trade(&mut self, ts: i64, price: f64, size: f64, side: &str) -> PyResult<()> {
+        let side = Side::parse(side)?;
+        self.push(Trade {
+            ts,
+            price,
+            size,
+            side,
+        });
+        Ok(())
+    }
+
+    // Batch of (ts, price, size, side) tuples
+    pub fn add_trades(&mut self, trades: Vec<(i64, f64, f64, String)>) -> PyResult<()> {
+        for (ts, price, size, side) in trades {
+            self.add_trade(ts, price, size, &side)?;
+        }
+        Ok(())
+    }
+
+    // Advance the window without a trade (e.g. on a timer); `ts` defaults
+    // to the clock's time
+    #[pyo3(signature = (ts=None))]
+    pub fn advance(&mut self, ts: Option<i64>) -> PyResult<()> {
+        let ts = clock::require(ts, &self.clock)?;
+        self.last_ts = Some(self.last_ts.map_or(ts, |t| t.max(ts)));
+        self.evict();
+        Ok(())
+    }
+
+    pub fn vwap(&self) -> Option<f64> {
+        let volume = self.volume();
+        (volume > 0.0).then(|| self.notional / volume)
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.buy_volume + self.sell_volume
+    }
+
+    pub fn buy_volume(&self) -> f64 {
+        self.buy_volume
+    }
+
+    pub fn sell_volume(&self) -> f64 {
+        self.sell_volume
+    }
+
+    pub fn trade_count(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn largest_trade(&self) -> Option<f64> {
+        self.max_queue.front().map(|(_, s)| *s)
+    }
+
+    pub fn last_price(&self) -> Option<f64> {
+        self.trades.back().map(|t| t.price)
+    }
+
+    #[getter]
+    pub fn window_ms(&self) -> i64 {
+        self.window_ms
+    }
+
+    pub fn clear(&mut self) {
+        self.trades.clear();
+        self.max_queue.clear();
+        self.notional = 0.0;
+        self.buy_volume = 0.0;
+        self.sell_volume = 0.0;
+        self.last_ts = None;
+    }
+
+    // Trades in the window as an Arrow batch: ts, price, size, side
+    pub fn to_arrow(&self) -> ArrowBatch {
+        let t = &self.trades;
+        ArrowBatch::new(Batch::new(vec![
+            Column::i64("ts", t.iter().map(|t| t.ts).collect()),
+            Column::f64("price", t.iter().map(|t| t.price).collect()),
+            Column::f64("size", t.iter().map(|t| t.size).collect()),
+            Column::utf8("side", t.iter().map(|t| t.side.name())),
+        ]))
+    }
+
+    fn __len__(&self) -> usize {
+        self.trades.len()
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
@@ -0,0 +1,230 @@
+// Bybit V5 private stream (wss://.../v5/private) for FeedClient: the auth
+// request and the order, execution and position topics (optionally with a
+// category suffix, e.g. "order.linear").
+//   auth       {"op":"auth","args":[api_key, expires, HMAC(secret,
+//              "GET/realtime" + expires)]}, sent before subscribing
+//   order      status changes, routed into an OrderManager:
+//              New -> on_ack, Rejected -> on_reject, Cancelled /
+//              PartiallyFilledCanceled / Deactivated -> on_canceled
+//   execution  fills (execType "Trade"), routed into the OrderManager
+//              (on_fill) and the symbol's Position (on_fill with the fee)
+//   position   the exchange's view of a position, passed on as an event only
+// Updates are parsed without the GIL; routing takes it once per message and
+// only when an OrderManager or Position is attached. Orders the manager does
+// not know (placed elsewhere) are skipped.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+
+use crate::clock::wall_ms;
+use crate::json::Value;
+use crate::orders::OrderManager;
+use crate::position::Position;
+use crate::sign::hmac_sha256_hex;
+use crate::Side;
+
+pub const TOPICS: [&str; 3] = ["order", "execution", "position"];
+
+// How long an auth request stays valid
+const AUTH_EXPIRY_MS: i64 = 10_000;
+
+pub fn is_private(topic: &str) -> bool {
+    TOPICS.contains(&topic.split('.').next().unwrap_or_default())
+}
+
+pub fn auth_message(api_key: &str, api_secret: &str) -> String {
+    let expires = wall_ms() + AUTH_EXPIRY_MS;
+    let signature = hmac_sha256_hex(
+        api_secret.as_bytes(),
+        format!("GET/realtime{}", expires).as_bytes(),
+    );
+    format!(
+        "{{\"op\":\"auth\",\"args\":[\"{}\",{},\"{}\"]}}",
+        api_key, expires, signature
+    )
+}
+
+#[derive(Clone, Debug)]
+pub enum Update {
+    Order {
+        symbol: String,
+        client_id: Option<String>,
+        exchange_id: Option<String>,
+        // Bybit orderStatus, e.g. "New", "PartiallyFilled", "Cancelled"
+        status: String,
+        side: Option<Side>,
+        price: Option<f64>,
+        qty: Option<f64>,
+        ts: Option<i64>,
+    },
+    Fill {
+        symbol: String,
+        client_id: Option<String>,
+        exchange_id: Option<String>,
+        side: Side,
+        price: f64,
+        qty: f64,
+        fee: f64,
+        is_maker: Option<bool>,
+        ts: Option<i64>,
+    },
+    Position {
+        symbol: String,
+        // None when flat
+        side: Option<Side>,
+        size: f64,
+        avg_price: Option<f64>,
+        ts: Option<i64>,
+    },
+}
+
+fn text(v: &Value, key: &str) -> Option<String> {
+    v.get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn number(v: &Value, key: &str) -> Option<f64> {
+    v.get(key).and_then(Value::as_f64)
+}
+
+fn side(v: &Value) -> Option<Side> {
+    Side::parse(v.get("side")?.as_str()?).ok()
+}
+
+// The updates in one private message; entries missing required fields are
+// skipped
+pub fn parse(topic: &str, root: &Value) -> Vec<Update> {
+    let kind = topic.split('.').next().unwrap_or_default();
+    let items = root
+        .get("data")
+        .and_then(Value::as_array)
+        .unwrap_or_default();
+    items
+        .iter()
+        .filter_map(|d| {
+            let symbol = text(d, "symbol")?;
+            match kind {
+                "order" => Some(Update::Order {
+                    symbol,
+                    client_id: text(d, "orderLinkId"),
+                    exchange_id: text(d, "orderId"),
+                    status: text(d, "orderStatus")?,
+                    side: side(d),
+                    price: number(d, "price"),
+                    qty: number(d, "qty"),
+                    ts: d.get("updatedTime").and_then(Value::as_i64),
+                }),
+                "execution" => {
+                    // Funding and delivery settlements are not fills
+                    if d.get("execType").and_then(Value::as_str) != Some("Trade") {
+                        return None;
+                    }
+                    Some(Update::Fill {
+                        symbol,
+                        client_id: text(d, "orderLinkId"),
+                        exchange_id: text(d, "orderId"),
+                        side: side(d)?,
+                        price: number(d, "execPrice")?,
+                        qty: number(d, "execQty")?,
+                        fee: number(d, "execFee").unwrap_or(0.0),
+                        is_maker: match d.get("isMaker") {
+                            Some(Value::Bool(b)) => Some(*b),
+                            _ => None,
+                        },
+                        ts: d.get("execTime").and_then(Value::as_i64),
+                    })
+                }
+                "position" => Some(Update::Position {
+                    symbol,
+                    side: side(d),
+                    size: number(d, "size")?,
+                    avg_price: number(d, "avgPrice").filter(|p| *p > 0.0),
+                    ts: d.get("updatedTime").and_then(Value::as_i64),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// Where FeedClient.set_routing sends order updates and fills
+#[derive(Clone, Default)]
+pub struct Routing {
+    pub orders: Option<Arc<Py<OrderManager>>>,
+    pub positions: HashMap<String, Arc<Py<Position>>>,
+}
+
+impl Routing {
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_none() && self.positions.is_empty()
+    }
+
+    // Apply one update; an error (e.g. an InvalidTransitionError from the
+    // manager) is returned for the caller to report
+    pub fn apply(&self, py: Python<'_>, update: &Update) -> PyResult<()> {
+        let known = |id: &Option<String>| -> Option<(String, Bound<'_, OrderManager>)> {
+            let orders = self.orders.as_ref()?.bind(py).clone();
+            let id = id.clone()?;
+            orders.borrow().order(&id).is_some().then_some((id, orders))
+        };
+        match update {
+            Update::Order {
+                client_id,
+                exchange_id,
+                status,
+                ts,
+                ..
+            } => {
+                let Some((id, orders)) = known(client_id) else {
+                    return Ok(());
+                };
+                let ts = ts.unwrap_or_else(wall_ms);
+                let mut orders = orders.try_borrow_mut()?;
+                match status.as_str() {
+                    "New" => {
+                        orders.on_ack(&id, ts, exchange_id.clone())?;
+                    }
+                    "Rejected" => orders.on_reject(&id, ts)?,
+                    "Cancelled" | "PartiallyFilledCanceled" | "Deactivated" => {
+                        orders.on_canceled(&id, ts)?;
+                    }
+                    // Fills arrive on the execution topic
+                    _ => {}
+                }
+            }
+            Update::Fill {
+                symbol,
+                client_id,
+                side,
+                price,
+                qty,
+                fee,
+                ts,
+                ..
+            } => {
+                if let Some((id, orders)) = known(client_id) {
+                    orders.try_borrow_mut()?.on_fill(
+                        &id,
+                        *qty,
+                        *price,
+                        ts.unwrap_or_else(wall_ms),
+                    )?;
+                }
+                if let Some(position) = self.positions.get(symbol) {
+                    position.bind(py).try_borrow_mut()?.on_fill(
+                        *price,
+                        *qty,
+                        side.name(),
+                        Some(*fee),
+                        None,
+                    )?;
+                }
+            }
+            Update::Position { .. } => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+// Rolling volatility from a stream of mid/microprice samples, all O(1) per
+// tick. Return-based estimators use log returns between consecutive samples
+// and are per-sample volatilities:
+//   ewma       σ² ← λσ² + (1-λ)r²  (RiskMetrics, seeded with the first r²)
+//   realized   sqrt(Σr² / n) over the last `window` returns
+// Range-based estimators use completed OHLC bars of `bar_ms` over the last
+// `bars` bars and are per-bar volatilities:
+//   parkinson     σ² = mean(ln(H/L)²) / (4 ln 2)
+//   garman_klass  σ² = mean(½ ln(H/L)² - (2 ln 2 - 1) ln(C/O)²)
+// Scale by sqrt(samples or bars per horizon) to get other horizons.
+use std::collections::VecDeque;
+use std::f64::consts::LN_2;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::state::{self, persist_fields, Stateful};
+use crate::L2Book;
+
+#[derive(Clone, Copy)]
+struct Bar {
+    start: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+}
+
+persist_fields!(Bar {
+    start,
+    open,
+    high,
+    low,
+    close
+});
+
+#[pyclass]
+#[derive(Clone)]
+pub struct VolEstimator {
+    window: usize,
+    lambda: f64,
+    bar_ms: i64,
+    bars: usize,
+    last_price: Option<f64>,
+    ewma_var: Option<f64>,
+    // Squared log returns in the realized window
+    returns: VecDeque<f64>,
+    sum_r2: f64,
+    samples: u64,
+    bar: Option<Bar>,
+    // (ln(H/L)², GK term) per completed bar
+    closed: VecDeque<(f64, f64)>,
+    sum_hl: f64,
+    sum_gk: f64,
+}
+
+persist_fields!(VolEstimator {
+    window,
+    lambda,
+    bar_ms,
+    bars,
+    last_price,
+    ewma_var,
+    returns,
+    sum_r2,
+    samples,
+    bar,
+    closed,
+    sum_hl,
+    sum_gk
+});
+
+impl Stateful for VolEstimator {
+    const NAME: &'static str = "VolEstimator";
+}
+
+impl VolEstimator {
+    fn close_bar(&mut self, bar: Bar) {
+        let hl = (bar.high / bar.low).ln().powi(2);
+        let co = (bar.close / bar.open).ln().powi(2);
+        let gk = 0.5 * hl - (2.0 * LN_2 - 1.0) * co;
+        self.closed.push_back((hl, gk));
+        self.sum_hl += hl;
+        self.sum_gk += gk;
+        if self.closed.len() > self.bars {
+            let (hl, gk) = self.closed.pop_front().unwrap_or_default();
+            self.sum_hl -= hl;
+            self.sum_gk -= gk;
+        }
+    }
+}
+
+#[pymethods]
+impl VolEstimator {
+    #[new]
+    #[pyo3(signature = (window=100, ewma_lambda=0.94, bar_ms=1000, bars=60))]
+    pub fn new(window: usize, ewma_lambda: f64, bar_ms: i64, bars: usize) -> PyResult<Self> {
+        if window == 0 || bar_ms <= 0 || bars == 0 {
+            return Err(PyValueError::new_err(
+                "window, bar_ms and bars must be positive",
+            ));
+        }
+        if !(0.0..1.0).contains(&ewma_lambda) {
+            return Err(PyValueError::new_err("ewma_lambda must be in [0, 1)"));
+        }
+        Ok(Self {
+            window,
+            lambda: ewma_lambda,
+            bar_ms,
+            bars,
+            last_price: None,
+            ewma_var: None,
+            returns: VecDeque::with_capacity(window + 1),
+            sum_r2: 0.0,
+            samples: 0,
+            bar: None,
+            closed: VecDeque::with_capacity(bars + 1),
+            sum_hl: 0.0,
+            sum_gk: 0.0,
+        })
+    }
+
+    // Add a price sample at `ts` (ms)
+    pub fn add(&mut self, ts: i64, price: f64) -> PyResult<()> {
+        if !(price > 0.0 && price.is_finite()) {
+            return Err(PyValueError::new_err(format!(
+                "price must be positive and finite, got {}",
+                price
+            )));
+        }
+        self.samples += 1;
+        if let Some(prev) = self.last_price.replace(price) {
+            let r2 = (price / prev).ln().powi(2);
+            self.ewma_var = Some(match self.ewma_var {
+                Some(v) => self.lambda * v + (1.0 - self.lambda) * r2,
+                None => r2,
+            });
+            self.returns.push_back(r2);
+            self.sum_r2 += r2;
+            if self.returns.len() > self.window {
+                self.sum_r2 -= self.returns.pop_front().unwrap_or(0.0);
+            }
+        }
+
+        let start = ts - ts.rem_euclid(self.bar_ms);
+        match self.bar.as_mut() {
+            Some(bar) if ts < bar.start + self.bar_ms => {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+            }
+            _ => {
+                if let Some(done) = self.bar.take() {
+                    self.close_bar(done);
+                }
+                self.bar = Some(Bar {
+                    start,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Sample the book's mid or microprice; returns False for a one-sided book
+    #[pyo3(signature = (book, ts, reference="mid"))]
+    pub fn update(&mut self, book: &L2Book, ts: i64, reference: &str) -> PyResult<bool> {
+        match book.reference_price(reference)? {
+            Some(price) => self.add(ts, price).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    pub fn ewma(&self) -> Option<f64> {
+        self.ewma_var.map(f64::sqrt)
+    }
+
+    pub fn realized(&self) -> Option<f64> {
+        let n = self.returns.len();
+        (n > 0).then(|| (self.sum_r2.max(0.0) / n as f64).sqrt())
+    }
+
+    pub fn parkinson(&self) -> Option<f64> {
+        let n = self.closed.len();
+        (n > 0).then(|| (self.sum_hl.max(0.0) / (4.0 * LN_2 * n as f64)).sqrt())
+    }
+
+    pub fn garman_klass(&self) -> Option<f64> {
+        let n = self.closed.len();
+        (n > 0).then(|| (self.sum_gk.max(0.0) / n as f64).sqrt())
+    }
+
+    // Samples seen since construction or reset()
+    #[getter]
+    pub fn count(&self) -> u64 {
+        self.samples
+    }
+
+    #[getter]
+    pub fn bar_count(&self) -> usize {
+        self.closed.len()
+    }
+
+    // True once both the realized window and the bar window are full
+    #[getter]
+    pub fn is_ready(&self) -> bool {
+        self.returns.len() >= self.window && self.closed.len() >= self.bars
+    }
+
+    pub fn reset(&mut self) {
+        self.last_price = None;
+        self.ewma_var = None;
+        self.returns.clear();
+        self.sum_r2 = 0.0;
+        self.samples = 0;
+        self.bar = None;
+        self.closed.clear();
+        self.sum_hl = 0.0;
+        self.sum_gk = 0.0;
+    }
+
+    // Pickle support; see state.rs
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<state::Reduced<'py>> {
+        state::reduce(py, self)
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+}
@@ -0,0 +1,100 @@
+// VPIN (Easley, Lopez de Prado, O'Hara): trades are poured into equal-volume
+// buckets, and VPIN = Σ|V_buy - V_sell| / (n · V) over the last n buckets.
+// Trades come with their aggressor side, so no bulk volume classification.
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::Side;
+
+#[pyclass]
+pub struct Vpin {
+    bucket_volume: f64,
+    window: usize,
+    // |buy - sell| of completed buckets
+    buckets: VecDeque<f64>,
+    imbalance_sum: f64,
+    cur_buy: f64,
+    cur_sell: f64,
+}
+
+#[pymethods]
+impl Vpin {
+    #[new]
+    #[pyo3(signature = (bucket_volume, window=50))]
+    pub fn new(bucket_volume: f64, window: usize) -> PyResult<Self> {
+        if bucket_volume <= 0.0 || window == 0 {
+            return Err(PyValueError::new_err(
+                "bucket_volume and window must be positive",
+            ));
+        }
+        Ok(Self {
+            bucket_volume,
+            window,
+            buckets: VecDeque::with_capacity(window + 1),
+            imbalance_sum: 0.0,
+            cur_buy: 0.0,
+            cur_sell: 0.0,
+        })
+    }
+
+    // Add a trade (aggressor side); a trade larger than the bucket remainder
+    // spills into the next buckets. Returns how many buckets it completed.
+    pub fn add_trade(&mut self, size: f64, side: &str) -> PyResult<usize> {
+        let side = Side::parse(side)?;
+        let mut left = size;
+        let mut completed = 0;
+        while left > 0.0 {
+            let room = self.bucket_volume - self.cur_buy - self.cur_sell;
+            let take = left.min(room);
+            match side {
+                Side::Bid => self.cur_buy += take,
+                Side::Ask => self.cur_sell += take,
+            }
+            left -= take;
+            if take >= room {
+                let imb = (self.cur_buy - self.cur_sell).abs();
+                self.buckets.push_back(imb);
+                self.imbalance_sum += imb;
+                if self.buckets.len() > self.window {
+                    self.imbalance_sum -= self.buckets.pop_front().unwrap_or(0.0);
+                }
+                self.cur_buy = 0.0;
+                self.cur_sell = 0.0;
+                completed += 1;
+            }
+        }
+        Ok(completed)
+    }
+
+    // VPIN over the completed buckets (None before the first bucket closes)
+    pub fn vpin(&self) -> Option<f64> {
+        let n = self.buckets.len();
+        (n > 0).then(|| self.imbalance_sum / (n as f64 * self.bucket_volume))
+    }
+
+    // True once `window` buckets have completed
+    #[getter]
+    pub fn is_ready(&self) -> bool {
+        self.buckets.len() >= self.window
+    }
+
+    #[getter]
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // Fill level of the open bucket, 0..1
+    #[getter]
+    pub fn current_fill(&self) -> f64 {
+        (self.cur_buy + self.cur_sell) / self.bucket_volume
+    }
+
+    pub fn reset(&mut self) {
+        self.buckets.clear();
+        self.imbalance_sum = 0.0;
+        self.cur_buy = 0.0;
+        self.cur_sell = 0.0;
+    }
+}
@@ -0,0 +1,284 @@
+// Walk-forward evaluation of a strategy template. The backtester's data is
+// cut into consecutive folds, each a training window followed by a test
+// window:
+//   rolling   train [s, s + train_ms)            test [s + train_ms, + test_ms)
+//   anchored  train [first, s + train_ms)        test as above
+// with s advancing by step_ms (default test_ms, so test windows tile the
+// data). Every grid point is run on the training window in parallel; the
+// best by `metric` is then run once on the test window, so each test result
+// is out of sample. Events before a window still replay into the books, just
+// without quoting, so a window starting between snapshots sees a full book.
+// Folds whose test window would start past the data are not run; the last
+// test window may be cut short by the end of the data.
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::backtest::{Backtester, Event};
+use crate::native::{self, NativeResult};
+use crate::paramsweep::{expand, run_grid, thread_pool};
+
+#[derive(Clone, Copy, Debug)]
+enum Metric {
+    TotalPnl,
+    RealizedPnl,
+    // total_pnl / max_drawdown, total_pnl itself without a drawdown
+    Calmar,
+}
+
+impl Metric {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "total_pnl" => Ok(Self::TotalPnl),
+            "realized_pnl" => Ok(Self::RealizedPnl),
+            "calmar" => Ok(Self::Calmar),
+            other => Err(PyValueError::new_err(format!(
+                "metric must be 'total_pnl', 'realized_pnl' or 'calmar', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn score(self, r: &NativeResult) -> f64 {
+        match self {
+            Self::TotalPnl => r.total_pnl,
+            Self::RealizedPnl => r.realized_pnl,
+            Self::Calmar if r.max_drawdown > 0.0 => r.total_pnl / r.max_drawdown,
+            Self::Calmar => r.total_pnl,
+        }
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct WalkForwardFold {
+    pub train_start: i64,
+    pub train_end: i64,
+    pub test_start: i64,
+    pub test_end: i64,
+    // Best grid point on the training window
+    pub params: HashMap<String, f64>,
+    pub train_score: f64,
+    pub train_pnl: f64,
+    pub test_score: f64,
+    pub test_pnl: f64,
+    pub test_fees: f64,
+    pub test_fills: usize,
+    pub test_max_drawdown: f64,
+}
+
+#[pymethods]
+impl WalkForwardFold {
+    fn __repr__(&self) -> String {
+        format!(
+            "WalkForwardFold(test=[{}, {}), params={:?}, train_score={}, test_pnl={})",
+            self.test_start, self.test_end, self.params, self.train_score, self.test_pnl
+        )
+    }
+}
+
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub struct WalkForwardReport {
+    pub folds: Vec<WalkForwardFold>,
+    // Out-of-sample totals over all test windows
+    pub oos_pnl: f64,
+    pub oos_fees: f64,
+    pub oos_fills: usize,
+    // Worst single-fold drawdown out of sample
+    pub oos_max_drawdown: f64,
+}
+
+#[pymethods]
+impl WalkForwardReport {
+    // Share of folds with positive test PnL; None without folds
+    #[getter]
+    pub fn positive_folds(&self) -> Option<f64> {
+        (!self.folds.is_empty()).then(|| {
+            self.folds.iter().filter(|f| f.test_pnl > 0.0).count() as f64 / self.folds.len() as f64
+        })
+    }
+
+    // Walk-forward efficiency: test PnL per ms over training PnL per ms.
+    // Near 1 the fitted edge carries out of sample; near 0 or negative it
+    // was fitted noise. None unless training PnL is positive.
+    #[getter]
+    pub fn efficiency(&self) -> Option<f64> {
+        let (mut train, mut train_ms, mut test_ms) = (0.0, 0.0, 0.0);
+        for f in &self.folds {
+            train += f.train_pnl;
+            train_ms += (f.train_end - f.train_start) as f64;
+            test_ms += (f.test_end - f.test_start) as f64;
+        }
+        (train > 0.0 && test_ms > 0.0).then(|| (self.oos_pnl / test_ms) / (train / train_ms))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "WalkForwardReport(folds={}, oos_pnl={}, efficiency={:?})",
+            self.folds.len(),
+            self.oos_pnl,
+            self.efficiency()
+        )
+    }
+}
+
+// Events of [start, end) and those before it, from time-sorted events
+fn window<'a, 'e>(
+    events: &'a [&'e Event],
+    start: i64,
+    end: i64,
+) -> (&'a [&'e Event], &'a [&'e Event]) {
+    let a = events.partition_point(|e| e.ts < start);
+    let b = events.partition_point(|e| e.ts < end);
+    (&events[..a], &events[a..b])
+}
+
+#[pyclass]
+pub struct WalkForward {
+    #[pyo3(get)]
+    pub train_ms: i64,
+    #[pyo3(get)]
+    pub test_ms: i64,
+    #[pyo3(get)]
+    pub step_ms: i64,
+    #[pyo3(get)]
+    pub anchored: bool,
+    metric: Metric,
+    pool: Option<rayon::ThreadPool>,
+}
+
+impl WalkForward {
+    // (train_start, train_end, test_start, test_end) over [first, last]
+    fn folds(&self, first: i64, last: i64) -> Vec<(i64, i64, i64, i64)> {
+        let mut out = Vec::new();
+        let mut start = first;
+        while start + self.train_ms <= last {
+            let test_start = start + self.train_ms;
+            let train_start = if self.anchored { first } else { start };
+            let test_end = (test_start + self.test_ms).min(last + 1);
+            out.push((train_start, test_start, test_start, test_end));
+            start += self.step_ms;
+        }
+        out
+    }
+}
+
+#[pymethods]
+impl WalkForward {
+    // metric picks the training winner: "total_pnl", "realized_pnl" or
+    // "calmar"; threads as in SweepRunner
+    #[new]
+    #[pyo3(signature = (train_ms, test_ms, step_ms=None, anchored=false, metric="total_pnl", threads=None))]
+    pub fn new(
+        train_ms: i64,
+        test_ms: i64,
+        step_ms: Option<i64>,
+        anchored: bool,
+        metric: &str,
+        threads: Option<usize>,
+    ) -> PyResult<Self> {
+        let step_ms = step_ms.unwrap_or(test_ms);
+        if train_ms <= 0 || test_ms <= 0 || step_ms <= 0 {
+            return Err(PyValueError::new_err(
+                "train_ms, test_ms and step_ms must be positive",
+            ));
+        }
+        Ok(Self {
+            train_ms,
+            test_ms,
+            step_ms,
+            anchored,
+            metric: Metric::parse(metric)?,
+            pool: thread_pool(threads)?,
+        })
+    }
+
+    // The folds run() would use, as (train_start, train_end, test_start,
+    // test_end); ends are exclusive
+    pub fn windows(&self, backtester: PyRef<'_, Backtester>) -> Vec<(i64, i64, i64, i64)> {
+        let events = backtester.sorted_events();
+        match (events.first(), events.last()) {
+            (Some(first), Some(last)) => self.folds(first.ts, last.ts),
+            _ => Vec::new(),
+        }
+    }
+
+    // Fit `strategy` over `grid` ({parameter: [values]}) on each training
+    // window and evaluate the winner on the following test window
+    pub fn run(
+        &self,
+        py: Python<'_>,
+        backtester: PyRef<'_, Backtester>,
+        strategy: &str,
+        grid: HashMap<String, Vec<f64>>,
+    ) -> PyResult<WalkForwardReport> {
+        let points = expand(&grid);
+        for p in &points {
+            native::template(strategy, p)?;
+        }
+        let bt: &Backtester = &backtester;
+        let config = bt.native_config();
+        let folds = py.allow_threads(|| {
+            let events = bt.sorted_events();
+            let (Some(first), Some(last)) = (events.first(), events.last()) else {
+                return Ok(Vec::new());
+            };
+            let mut folds = Vec::new();
+            for (train_start, train_end, test_start, test_end) in self.folds(first.ts, last.ts) {
+                let (warmup, train) = window(&events, train_start, train_end);
+                let results = run_grid(
+                    self.pool.as_ref(),
+                    warmup,
+                    train,
+                    &config,
+                    strategy,
+                    &points,
+                )?;
+                // First of equal scores, so ties go to the earlier grid point
+                let best = results.iter().enumerate().fold(0, |best, (i, r)| {
+                    if self.metric.score(r) > self.metric.score(&results[best]) {
+                        i
+                    } else {
+                        best
+                    }
+                });
+                let (warmup, test) = window(&events, test_start, test_end);
+                let oos = native::replay(warmup, test, &config, strategy, &points[best], false)?;
+                folds.push(WalkForwardFold {
+                    train_start,
+                    train_end,
+                    test_start,
+                    test_end,
+                    params: points[best].clone(),
+                    train_score: self.metric.score(&results[best]),
+                    train_pnl: results[best].total_pnl,
+                    test_score: self.metric.score(&oos),
+                    test_pnl: oos.total_pnl,
+                    test_fees: oos.fees,
+                    test_fills: oos.fills,
+                    test_max_drawdown: oos.max_drawdown,
+                });
+            }
+            Ok::<_, PyErr>(folds)
+        })?;
+        Ok(WalkForwardReport {
+            oos_pnl: folds.iter().map(|f| f.test_pnl).sum(),
+            oos_fees: folds.iter().map(|f| f.test_fees).sum(),
+            oos_fills: folds.iter().map(|f| f.test_fills).sum(),
+            oos_max_drawdown: folds
+                .iter()
+                .map(|f| f.test_max_drawdown)
+                .fold(0.0, f64::max),
+            folds,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "WalkForward(train_ms={}, test_ms={}, step_ms={}, anchored={}, metric={:?})",
+            self.train_ms, self.test_ms, self.step_ms, self.anchored, self.metric
+        )
+    }
+}
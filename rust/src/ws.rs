@@ -0,0 +1,403 @@
+// Minimal blocking WebSocket client (RFC 6455) over std::net, for the feed
+// thread. ws:// is plain TCP; wss:// runs over TLS (tls.rs), with the
+// server certificate and host name verified.
+// Reads use a short socket timeout so the owner can check its stop flag and
+// send heartbeats between messages; partial frames are kept across reads.
+// Pings are answered and fragmented messages reassembled internally.
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::net::IpAddr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::tls::{self, Stream};
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_MESSAGE: usize = 64 << 20;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Close,
+}
+
+pub struct WsConn {
+    stream: Stream,
+    // Bytes read but not yet consumed as frames
+    buf: Vec<u8>,
+    // Fragments of a message in progress: (opcode, payload so far)
+    partial: Option<(u8, Vec<u8>)>,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+// Masking keys and handshake nonces only need to be unpredictable to
+// intermediaries, so std's randomly seeded hasher is enough
+fn random_u64() -> u64 {
+    let mut h = RandomState::new().build_hasher();
+    h.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos()),
+    );
+    h.finish()
+}
+
+// Where a URL points; `tls` for the wss:// / https:// forms
+pub(crate) struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+    pub tls: bool,
+}
+
+impl Endpoint {
+    // localhost, 127.0.0.0/8 or ::1: traffic never leaves the machine
+    pub fn is_loopback(&self) -> bool {
+        self.host.eq_ignore_ascii_case("localhost")
+            || self.host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    }
+
+    // host:port for the Host header
+    pub fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    pub fn connect(&self, timeout: Duration) -> io::Result<Stream> {
+        tls::connect(&self.host, self.port, self.tls, timeout)
+    }
+}
+
+// ws://host[:port][/path] or wss://...
+pub(crate) fn parse_url(url: &str) -> io::Result<Endpoint> {
+    split_url(url, "ws")
+}
+
+// {scheme}://host[:port][/path], or {scheme}s://... over TLS; IPv6
+// addresses go in brackets
+pub(crate) fn split_url(url: &str, scheme: &str) -> io::Result<Endpoint> {
+    let (rest, tls) = if let Some(rest) = url.strip_prefix(&format!("{}://", scheme)) {
+        (rest, false)
+    } else if let Some(rest) = url.strip_prefix(&format!("{}s://", scheme)) {
+        if !tls::ENABLED {
+            return Err(tls::unsupported(url));
+        }
+        (rest, true)
+    } else {
+        return Err(invalid(format!(
+            "url must start with {0}:// or {0}s://, got '{1}'",
+            scheme, url
+        )));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(v6) => match v6.split_once(']') {
+            Some((h, "")) => (h, None),
+            Some((h, p)) => (h, Some(p.strip_prefix(':').unwrap_or(p))),
+            None => return Err(invalid(format!("bad host in '{}'", url))),
+        },
+        None => match authority.rsplit_once(':') {
+            Some((h, p)) => (h, Some(p)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(p) => p
+            .parse()
+            .map_err(|_| invalid(format!("bad port in '{}'", url)))?,
+        None if tls => 443,
+        None => 80,
+    };
+    if host.is_empty() {
+        return Err(invalid(format!("missing host in '{}'", url)));
+    }
+    Ok(Endpoint {
+        host: host.to_string(),
+        port,
+        path: path.to_string(),
+        tls,
+    })
+}
+
+impl WsConn {
+    pub fn connect(url: &str, timeout: Duration, read_timeout: Duration) -> io::Result<Self> {
+        let endpoint = parse_url(url)?;
+        let stream = endpoint.connect(timeout)?;
+        stream.set_read_timeout(Some(read_timeout))?;
+        let mut conn = Self {
+            stream,
+            buf: Vec::new(),
+            partial: None,
+        };
+        conn.handshake(&endpoint, timeout)?;
+        Ok(conn)
+    }
+
+    fn handshake(&mut self, endpoint: &Endpoint, timeout: Duration) -> io::Result<()> {
+        let nonce: Vec<u8> = [random_u64(), random_u64()]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let key = base64(&nonce);
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            endpoint.path,
+            endpoint.authority(),
+            key
+        );
+        self.stream.write_all(request.as_bytes())?;
+
+        let deadline = Instant::now() + timeout;
+        let end = loop {
+            if let Some(i) = self.buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "handshake timed out",
+                ));
+            }
+            self.fill()?;
+        };
+        let head = String::from_utf8_lossy(&self.buf[..end]).into_owned();
+        // Anything after the headers is already frame data
+        self.buf.drain(..end);
+
+        let mut lines = head.split("\r\n");
+        let status = lines.next().unwrap_or_default();
+        if status.split_whitespace().nth(1) != Some("101") {
+            return Err(invalid(format!("handshake rejected: {}", status)));
+        }
+        let expected = base64(&sha1(format!("{}{}", key, GUID).as_bytes()));
+        let accepted = lines.filter_map(|l| l.split_once(':')).any(|(k, v)| {
+            k.trim().eq_ignore_ascii_case("sec-websocket-accept") && v.trim() == expected
+        });
+        if !accepted {
+            return Err(invalid("handshake: bad Sec-WebSocket-Accept"));
+        }
+        Ok(())
+    }
+
+    // Read whatever the socket has; timeouts are not errors
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0u8; 64 * 1024];
+        match self.stream.read(&mut chunk) {
+            Ok(0) => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed",
+            )),
+            Ok(n) => {
+                self.buf.extend_from_slice(&chunk[..n]);
+                Ok(true)
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // One complete frame from the buffer: (fin, opcode, payload)
+    fn take_frame(&mut self) -> io::Result<Option<(bool, u8, Vec<u8>)>> {
+        let b = &self.buf;
+        if b.len() < 2 {
+            return Ok(None);
+        }
+        let (fin, opcode, masked) = (b[0] & 0x80 != 0, b[0] & 0x0F, b[1] & 0x80 != 0);
+        let (len, mut at) = match b[1] & 0x7F {
+            126 if b.len() >= 4 => (u16::from_be_bytes([b[2], b[3]]) as usize, 4),
+            127 if b.len() >= 10 => {
+                let mut raw = [0u8; 8];
+                raw.copy_from_slice(&b[2..10]);
+                (u64::from_be_bytes(raw) as usize, 10)
+            }
+            126 | 127 => return Ok(None),
+            n => (n as usize, 2),
+        };
+        if len > MAX_MESSAGE {
+            return Err(invalid(format!("frame of {} bytes is too large", len)));
+        }
+        let mask = if masked {
+            if b.len() < at + 4 {
+                return Ok(None);
+            }
+            at += 4;
+            Some([b[at - 4], b[at - 3], b[at - 2], b[at - 1]])
+        } else {
+            None
+        };
+        if b.len() < at + len {
+            return Ok(None);
+        }
+        let mut payload: Vec<u8> = self.buf.drain(..at + len).skip(at).collect();
+        if let Some(mask) = mask {
+            for (i, x) in payload.iter_mut().enumerate() {
+                *x ^= mask[i % 4];
+            }
+        }
+        Ok(Some((fin, opcode, payload)))
+    }
+
+    // Next data message, or None if the read timeout passed without one
+    pub fn read_message(&mut self) -> io::Result<Option<Message>> {
+        loop {
+            while let Some((fin, opcode, payload)) = self.take_frame()? {
+                match opcode {
+                    OP_PING => self.send_frame(OP_PONG, &payload)?,
+                    OP_PONG => {}
+                    OP_CLOSE => {
+                        // Echo the status code back; the peer closes the socket
+                        let _ = self.send_frame(OP_CLOSE, &payload[..payload.len().min(2)]);
+                        return Ok(Some(Message::Close));
+                    }
+                    OP_TEXT | OP_BINARY | OP_CONTINUATION => {
+                        let (op, mut data) = match (opcode, self.partial.take()) {
+                            (OP_CONTINUATION, Some(p)) => p,
+                            (OP_CONTINUATION, None) => {
+                                return Err(invalid("continuation without a message"))
+                            }
+                            (op, _) => (op, Vec::new()),
+                        };
+                        data.extend_from_slice(&payload);
+                        if data.len() > MAX_MESSAGE {
+                            return Err(invalid("message is too large"));
+                        }
+                        if !fin {
+                            self.partial = Some((op, data));
+                            continue;
+                        }
+                        return Ok(Some(if op == OP_TEXT {
+                            Message::Text(
+                                String::from_utf8(data)
+                                    .map_err(|_| invalid("text message is not UTF-8"))?,
+                            )
+                        } else {
+                            Message::Binary(data)
+                        }));
+                    }
+                    other => return Err(invalid(format!("unknown opcode {}", other))),
+                }
+            }
+            if !self.fill()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    pub fn send_text(&mut self, text: &str) -> io::Result<()> {
+        self.send_frame(OP_TEXT, text.as_bytes())
+    }
+
+    pub fn close(&mut self) {
+        // 1000: normal closure
+        let _ = self.send_frame(OP_CLOSE, &1000u16.to_be_bytes());
+    }
+
+    // Client frames are always masked
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(0x80 | opcode);
+        match payload.len() {
+            n if n < 126 => frame.push(0x80 | n as u8),
+            n if n <= u16::MAX as usize => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+            n => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(n as u64).to_be_bytes());
+            }
+        }
+        let mask = (random_u64() as u32).to_be_bytes();
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, x)| x ^ mask[i % 4]));
+        self.stream.write_all(&frame)
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// FIPS 180-4; only used for the handshake accept key
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    msg.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+    for block in msg.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, wi) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*wi);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, t);
+        }
+        for (x, y) in h.iter_mut().zip([a, b, c, d, e]) {
+            *x = x.wrapping_add(y);
+        }
+    }
+    let mut out = [0u8; 20];
+    for (i, x) in h.iter().enumerate() {
+        out[4 * i..4 * i + 4].copy_from_slice(&x.to_be_bytes());
+    }
+    out
+}
@@ -0,0 +1,218 @@
+// Minimal XML reader for schema files (SBE message schemas). Elements,
+// attributes, text and the five predefined entities plus numeric character
+// references; the prolog, comments, processing instructions, CDATA and
+// DOCTYPE are skipped. Namespace prefixes are kept in `name`; `local` strips
+// them.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    // Name without the namespace prefix
+    pub fn local(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or(&self.name)
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn elements<'a>(&'a self, local: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.local() == local)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Element, String> {
+    let mut p = Parser { src: text, pos: 0 };
+    p.skip_misc()?;
+    let root = p.element()?;
+    p.skip_misc()?;
+    if p.pos != p.src.len() {
+        return Err(p.error("content after the root element"));
+    }
+    Ok(root)
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> String {
+        let line = self.src[..self.pos].matches('\n').count() + 1;
+        format!("{} at line {}", what, line)
+    }
+
+    fn rest(&self) -> &str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.src.len() - trimmed.len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("missing '{}'", end))),
+        }
+    }
+
+    // Whitespace, comments, <?...?> and <!DOCTYPE ...> between elements
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_ws();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let end = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '='))
+            .unwrap_or(self.rest().len());
+        if end == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = self.rest()[..end].to_string();
+        self.pos += end;
+        Ok(name)
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        if !self.rest().starts_with('<') {
+            return Err(self.error("expected '<'"));
+        }
+        self.pos += 1;
+        let mut el = Element {
+            name: self.name()?,
+            ..Default::default()
+        };
+        loop {
+            self.skip_ws();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(el);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = self.name()?;
+            self.skip_ws();
+            if !self.rest().starts_with('=') {
+                return Err(self.error(&format!("attribute '{}' has no value", key)));
+            }
+            self.pos += 1;
+            self.skip_ws();
+            let quote = self
+                .rest()
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| self.error("attribute value must be quoted"))?;
+            self.pos += 1;
+            let end = self
+                .rest()
+                .find(quote)
+                .ok_or_else(|| self.error("unterminated attribute value"))?;
+            let value = unescape(&self.rest()[..end]).map_err(|e| self.error(&e))?;
+            self.pos += end + 1;
+            el.attrs.push((key, value));
+        }
+        // Content up to the matching close tag
+        loop {
+            let lt = self
+                .rest()
+                .find('<')
+                .ok_or_else(|| self.error(&format!("unclosed element '{}'", el.name)))?;
+            let chunk = unescape(&self.rest()[..lt]).map_err(|e| self.error(&e))?;
+            el.text.push_str(&chunk);
+            self.pos += lt;
+            if self.rest().starts_with("</") {
+                self.pos += 2;
+                let name = self.name()?;
+                if name != el.name {
+                    return Err(self.error(&format!("'{}' closed by '</{}>'", el.name, name)));
+                }
+                self.skip_ws();
+                if !self.rest().starts_with('>') {
+                    return Err(self.error("expected '>'"));
+                }
+                self.pos += 1;
+                el.text = el.text.trim().to_string();
+                return Ok(el);
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<![CDATA[") {
+                self.pos += 9;
+                let end = self
+                    .rest()
+                    .find("]]>")
+                    .ok_or_else(|| self.error("unterminated CDATA"))?;
+                el.text.push_str(&self.rest()[..end]);
+                self.pos += end + 3;
+            } else if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else {
+                el.children.push(self.element()?);
+            }
+        }
+    }
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    if !s.contains('&') {
+        return Ok(s.to_string());
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest.find(';').ok_or("unterminated entity")?;
+        let entity = &rest[..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+                    .ok_or_else(|| format!("unknown entity '&{};'", entity))?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
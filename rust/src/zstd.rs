@@ -0,0 +1,730 @@
+// Self-contained zstd (RFC 8878) frames for the market-data journal.
+// The compressor is deliberately simple: greedy LZ matching on 4-byte hashes,
+// raw literals and sequences coded with the predefined FSE tables. That is
+// enough for fixed-layout records, which repeat heavily. Output is a standard
+// zstd frame (`zstd -d` reads it). The decoder accepts the same subset:
+// raw/RLE blocks, raw/RLE literals and predefined-mode sequences.
+use std::io::{self, Read};
+
+const MAGIC: u32 = 0xFD2F_B528;
+const MAX_BLOCK: usize = 128 * 1024;
+const MIN_MATCH: usize = 4;
+const HASH_LOG: u32 = 16;
+
+// Predefined distributions, RFC 8878 section 3.1.1.3.2.2
+const LL_DIST: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+const ML_DIST: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+const OF_DIST: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+const LL_LOG: u32 = 6;
+const ML_LOG: u32 = 6;
+const OF_LOG: u32 = 5;
+
+const LL_BASE: [u32; 36] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 18, 20, 22, 24, 28, 32, 40, 48, 64,
+    128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+];
+const LL_BITS: [u32; 36] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 4, 6, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16,
+];
+const ML_BASE: [u32; 53] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+    28, 29, 30, 31, 32, 33, 34, 35, 37, 39, 41, 43, 47, 51, 59, 67, 83, 99, 131, 259, 515, 1027,
+    2051, 4099, 8195, 16387, 32771, 65539,
+];
+const ML_BITS: [u32; 53] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+];
+
+#[derive(Clone, Copy, Default)]
+struct Cell {
+    symbol: u8,
+    nb_bits: u32,
+    baseline: u32,
+}
+
+// FSE decoding table; the encoder walks the same cells backwards
+struct Fse {
+    cells: Vec<Cell>,
+    // Decoder states per symbol, for encoding
+    states: Vec<Vec<u32>>,
+}
+
+impl Fse {
+    fn new(dist: &[i16], log: u32) -> Self {
+        let size = 1usize << log;
+        let mut cells = vec![Cell::default(); size];
+        let mut high = size - 1;
+        for (s, &p) in dist.iter().enumerate() {
+            if p == -1 {
+                cells[high].symbol = s as u8;
+                high -= 1;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut pos = 0;
+        for (s, &p) in dist.iter().enumerate() {
+            for _ in 0..p.max(0) {
+                cells[pos].symbol = s as u8;
+                pos = (pos + step) & (size - 1);
+                while pos > high {
+                    pos = (pos + step) & (size - 1);
+                }
+            }
+        }
+        let mut next: Vec<u32> = dist.iter().map(|&p| p.max(1) as u32).collect();
+        let mut states = vec![Vec::new(); dist.len()];
+        for (u, cell) in cells.iter_mut().enumerate() {
+            let s = cell.symbol as usize;
+            let n = next[s];
+            next[s] += 1;
+            cell.nb_bits = log - (31 - n.leading_zeros());
+            cell.baseline = (n << cell.nb_bits) - size as u32;
+            states[s].push(u as u32);
+        }
+        Self { cells, states }
+    }
+
+    // Move the encoder from decoder state `state` to a state decoding `symbol`
+    fn encode(&self, w: &mut BitWriter, state: &mut u32, symbol: u8) {
+        for &u in &self.states[symbol as usize] {
+            let c = self.cells[u as usize];
+            if *state >= c.baseline && *state < c.baseline + (1 << c.nb_bits) {
+                w.add((*state - c.baseline) as u64, c.nb_bits);
+                *state = u;
+                return;
+            }
+        }
+        unreachable!("FSE state ranges cover the table");
+    }
+}
+
+struct Tables {
+    ll: Fse,
+    ml: Fse,
+    of: Fse,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: std::sync::OnceLock<Tables> = std::sync::OnceLock::new();
+    TABLES.get_or_init(|| Tables {
+        ll: Fse::new(&LL_DIST, LL_LOG),
+        ml: Fse::new(&ML_DIST, ML_LOG),
+        of: Fse::new(&OF_DIST, OF_LOG),
+    })
+}
+
+// Forward bit stream, read back to front by the decoder
+struct BitWriter {
+    buf: Vec<u8>,
+    acc: u64,
+    n: u32,
+}
+
+impl BitWriter {
+    fn add(&mut self, v: u64, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+        self.acc |= (v & ((1u64 << bits) - 1)) << self.n;
+        self.n += bits;
+        while self.n >= 8 {
+            self.buf.push(self.acc as u8);
+            self.acc >>= 8;
+            self.n -= 8;
+        }
+    }
+
+    fn close(mut self) -> Vec<u8> {
+        self.add(1, 1);
+        if self.n > 0 {
+            self.buf.push(self.acc as u8);
+        }
+        self.buf
+    }
+}
+
+struct BitReader<'a> {
+    buf: &'a [u8],
+    // Bits not yet consumed; reading moves towards the start
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(buf: &'a [u8]) -> Result<Self, String> {
+        let last = *buf.last().ok_or("empty bitstream")?;
+        if last == 0 {
+            return Err("bitstream without end mark".into());
+        }
+        let pos = (buf.len() - 1) * 8 + (7 - last.leading_zeros() as usize);
+        Ok(Self { buf, pos })
+    }
+
+    fn read(&mut self, n: u32) -> Result<u32, String> {
+        if n == 0 {
+            return Ok(0);
+        }
+        let n = n as usize;
+        if n > self.pos {
+            return Err("bitstream overrun".into());
+        }
+        self.pos -= n;
+        let start = self.pos >> 3;
+        let mut word = [0u8; 8];
+        let end = (start + 8).min(self.buf.len());
+        word[..end - start].copy_from_slice(&self.buf[start..end]);
+        let v = u64::from_le_bytes(word) >> (self.pos & 7);
+        Ok((v & ((1u64 << n) - 1)) as u32)
+    }
+}
+
+// Largest code whose base does not exceed `v`
+fn code(base: &[u32], v: u32) -> usize {
+    base.partition_point(|b| *b <= v) - 1
+}
+
+struct Sequence {
+    lit_len: u32,
+    match_len: u32,
+    offset: u32,
+}
+
+fn encode_sequences(seqs: &[Sequence]) -> Vec<u8> {
+    let t = tables();
+    let codes: Vec<(u8, u8, u8)> = seqs
+        .iter()
+        .map(|s| {
+            let ll = code(&LL_BASE, s.lit_len) as u8;
+            let ml = code(&ML_BASE, s.match_len) as u8;
+            let of = 31 - (s.offset + 3).leading_zeros();
+            (ll, ml, of as u8)
+        })
+        .collect();
+    let mut w = BitWriter {
+        buf: Vec::new(),
+        acc: 0,
+        n: 0,
+    };
+    let extras = |w: &mut BitWriter, s: &Sequence, (ll, ml, of): (u8, u8, u8)| {
+        w.add(
+            (s.lit_len - LL_BASE[ll as usize]) as u64,
+            LL_BITS[ll as usize],
+        );
+        w.add(
+            (s.match_len - ML_BASE[ml as usize]) as u64,
+            ML_BITS[ml as usize],
+        );
+        w.add((s.offset + 3 - (1 << of)) as u64, of as u32);
+    };
+    let last = seqs.len() - 1;
+    let (ll, ml, of) = codes[last];
+    let mut ll_state = t.ll.states[ll as usize][0];
+    let mut ml_state = t.ml.states[ml as usize][0];
+    let mut of_state = t.of.states[of as usize][0];
+    extras(&mut w, &seqs[last], codes[last]);
+    for n in (0..last).rev() {
+        let (ll, ml, of) = codes[n];
+        t.of.encode(&mut w, &mut of_state, of);
+        t.ml.encode(&mut w, &mut ml_state, ml);
+        t.ll.encode(&mut w, &mut ll_state, ll);
+        extras(&mut w, &seqs[n], codes[n]);
+    }
+    w.add(ml_state as u64, ML_LOG);
+    w.add(of_state as u64, OF_LOG);
+    w.add(ll_state as u64, LL_LOG);
+    w.close()
+}
+
+// Compressed block body for src[start..end], or None if it would not be
+// smaller than storing the block raw
+fn compress_block(src: &[u8], start: usize, end: usize, table: &mut [u32]) -> Option<Vec<u8>> {
+    let hash = |i: usize| {
+        let v = u32::from_le_bytes(src[i..i + 4].try_into().unwrap());
+        (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_LOG)) as usize
+    };
+    let mut seqs = Vec::new();
+    let mut literals = Vec::new();
+    let mut anchor = start;
+    let mut i = start;
+    while i + MIN_MATCH <= end {
+        let h = hash(i);
+        let cand = table[h] as usize;
+        table[h] = i as u32 + 1;
+        // table holds position + 1 so zero means empty
+        if cand > 0 && src[cand - 1..cand + 3] == src[i..i + 4] {
+            let cand = cand - 1;
+            let mut len = MIN_MATCH;
+            while i + len < end && src[cand + len] == src[i + len] {
+                len += 1;
+            }
+            literals.extend_from_slice(&src[anchor..i]);
+            seqs.push(Sequence {
+                lit_len: (i - anchor) as u32,
+                match_len: len as u32,
+                offset: (i - cand) as u32,
+            });
+            // Index a couple of positions inside the match
+            for j in [i + 1, i + len / 2] {
+                if j + MIN_MATCH <= end && j < i + len {
+                    table[hash(j)] = j as u32 + 1;
+                }
+            }
+            i += len;
+            anchor = i;
+        } else {
+            i += 1;
+        }
+    }
+    // Hash the block tail too, so the next block can match into it
+    while i + MIN_MATCH <= src.len().min(end + MIN_MATCH - 1) && i + MIN_MATCH <= end {
+        table[hash(i)] = i as u32 + 1;
+        i += 1;
+    }
+    if seqs.is_empty() {
+        return None;
+    }
+    literals.extend_from_slice(&src[anchor..end]);
+
+    let mut out = Vec::with_capacity(end - start);
+    // Raw literals header
+    let n = literals.len();
+    if n < 32 {
+        out.push((n << 3) as u8);
+    } else if n < 4096 {
+        out.extend_from_slice(&(((n << 4) | 0b0100) as u16).to_le_bytes());
+    } else {
+        let v = (n << 4) | 0b1100;
+        out.extend_from_slice(&[v as u8, (v >> 8) as u8, (v >> 16) as u8]);
+    }
+    out.extend_from_slice(&literals);
+    let count = seqs.len();
+    if count < 128 {
+        out.push(count as u8);
+    } else if count < 0x7F00 {
+        out.extend_from_slice(&[((count >> 8) + 128) as u8, count as u8]);
+    } else {
+        out.push(255);
+        out.extend_from_slice(&((count - 0x7F00) as u16).to_le_bytes());
+    }
+    // All three symbol streams use the predefined tables
+    out.push(0);
+    out.extend_from_slice(&encode_sequences(&seqs));
+    (out.len() < end - start).then_some(out)
+}
+
+fn block_header(out: &mut Vec<u8>, last: bool, kind: u32, size: usize) {
+    let h = last as u32 | (kind << 1) | ((size as u32) << 3);
+    out.extend_from_slice(&h.to_le_bytes()[..3]);
+}
+
+// One single-segment frame holding `src`
+pub fn compress(src: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(src.len() / 2 + 16);
+    out.extend_from_slice(&MAGIC.to_le_bytes());
+    // 8-byte content size, single segment, no checksum, no dictionary
+    out.push(0xE0);
+    out.extend_from_slice(&(src.len() as u64).to_le_bytes());
+    if src.is_empty() {
+        block_header(&mut out, true, 0, 0);
+        return out;
+    }
+    let mut table = vec![0u32; 1 << HASH_LOG];
+    let mut start = 0;
+    while start < src.len() {
+        let end = (start + MAX_BLOCK).min(src.len());
+        let last = end == src.len();
+        match compress_block(src, start, end, &mut table) {
+            Some(body) => {
+                block_header(&mut out, last, 2, body.len());
+                out.extend_from_slice(&body);
+            }
+            None => {
+                block_header(&mut out, last, 0, end - start);
+                out.extend_from_slice(&src[start..end]);
+            }
+        }
+        start = end;
+    }
+    out
+}
+
+fn take<'a>(data: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], String> {
+    let s = data
+        .get(*pos..*pos + n)
+        .ok_or_else(|| format!("truncated zstd data at offset {}", *pos))?;
+    *pos += n;
+    Ok(s)
+}
+
+fn le(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0u64, |acc, b| (acc << 8) | *b as u64)
+}
+
+fn decode_block(
+    block: &[u8],
+    out: &mut Vec<u8>,
+    frame_start: usize,
+    rep: &mut [u32; 3],
+) -> Result<(), String> {
+    let mut pos = 0;
+    let b0 = *block.first().ok_or("empty block")?;
+    let lit_type = b0 & 3;
+    if lit_type > 1 {
+        return Err("Huffman-coded literals are not supported".into());
+    }
+    let (n, header) = match (b0 >> 2) & 3 {
+        0 | 2 => ((b0 >> 3) as usize, 1),
+        1 => ((le(take(block, &mut 0, 2)?) >> 4) as usize, 2),
+        _ => ((le(take(block, &mut 0, 3)?) >> 4) as usize, 3),
+    };
+    pos += header;
+    let literals: Vec<u8> = if lit_type == 0 {
+        take(block, &mut pos, n)?.to_vec()
+    } else {
+        vec![take(block, &mut pos, 1)?[0]; n]
+    };
+
+    let s0 = *take(block, &mut pos, 1)?.first().unwrap() as usize;
+    let count = match s0 {
+        0 => 0,
+        1..=127 => s0,
+        128..=254 => ((s0 - 128) << 8) + take(block, &mut pos, 1)?[0] as usize,
+        _ => le(take(block, &mut pos, 2)?) as usize + 0x7F00,
+    };
+    let mut lit = 0;
+    if count > 0 {
+        if take(block, &mut pos, 1)?[0] != 0 {
+            return Err("only predefined sequence tables are supported".into());
+        }
+        let t = tables();
+        let mut r = BitReader::new(&block[pos..])?;
+        let mut ll_state = r.read(LL_LOG)?;
+        let mut of_state = r.read(OF_LOG)?;
+        let mut ml_state = r.read(ML_LOG)?;
+        for i in 0..count {
+            let llc = t.ll.cells[ll_state as usize];
+            let mlc = t.ml.cells[ml_state as usize];
+            let ofc = t.of.cells[of_state as usize];
+            let ov = (1u32 << ofc.symbol) + r.read(ofc.symbol as u32)?;
+            let ml = ML_BASE[mlc.symbol as usize] + r.read(ML_BITS[mlc.symbol as usize])?;
+            let ll = LL_BASE[llc.symbol as usize] + r.read(LL_BITS[llc.symbol as usize])?;
+            let offset = if ov > 3 {
+                *rep = [ov - 3, rep[0], rep[1]];
+                rep[0]
+            } else {
+                let idx = ov as usize - 1 + (ll == 0) as usize;
+                let o = if idx == 3 {
+                    rep[0].wrapping_sub(1)
+                } else {
+                    rep[idx]
+                };
+                match idx {
+                    0 => {}
+                    1 => *rep = [o, rep[0], rep[2]],
+                    _ => *rep = [o, rep[0], rep[1]],
+                }
+                o
+            };
+            if i + 1 < count {
+                ll_state = llc.baseline + r.read(llc.nb_bits)?;
+                ml_state = mlc.baseline + r.read(mlc.nb_bits)?;
+                of_state = ofc.baseline + r.read(ofc.nb_bits)?;
+            }
+            let lits = literals
+                .get(lit..lit + ll as usize)
+                .ok_or("literal length past end of literals")?;
+            out.extend_from_slice(lits);
+            lit += ll as usize;
+            let offset = offset as usize;
+            if offset == 0 || offset > out.len() - frame_start {
+                return Err("match offset outside the frame".into());
+            }
+            let from = out.len() - offset;
+            for k in 0..ml as usize {
+                out.push(out[from + k]);
+            }
+        }
+        if r.pos != 0 {
+            return Err("trailing bits in sequence stream".into());
+        }
+    }
+    out.extend_from_slice(&literals[lit..]);
+    Ok(())
+}
+
+// Append exactly `n` bytes from `src` to `out`; returns where they start.
+// take() keeps a corrupt size from allocating before the data is there
+fn pull(src: &mut impl Read, out: &mut Vec<u8>, n: usize) -> io::Result<usize> {
+    let at = out.len();
+    if (&mut *src).take(n as u64).read_to_end(out)? < n {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "truncated zstd frame",
+        ));
+    }
+    Ok(at)
+}
+
+// Copy the next frame (or skippable frame) from `src` to `out` undecoded,
+// walking only its headers, so a file can be read one frame at a time.
+// Ok(false) at a clean end of input.
+pub fn read_frame(src: &mut impl Read, out: &mut Vec<u8>) -> io::Result<bool> {
+    let start = out.len();
+    if (&mut *src).take(4).read_to_end(out)? == 0 {
+        return Ok(false);
+    }
+    if out.len() - start < 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "truncated zstd frame",
+        ));
+    }
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let magic = le(&out[start..start + 4]) as u32;
+    if magic & 0xFFFF_FFF0 == 0x184D_2A50 {
+        let at = pull(src, out, 4)?;
+        let size = le(&out[at..]) as usize;
+        pull(src, out, size)?;
+        return Ok(true);
+    }
+    if magic != MAGIC {
+        return Err(invalid("bad zstd magic"));
+    }
+    let at = pull(src, out, 1)?;
+    let desc = out[at];
+    let fcs = match desc >> 6 {
+        0 => (desc & 0x20 != 0) as usize,
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    let window = (desc & 0x20 == 0) as usize;
+    let dict = [0, 1, 2, 4][(desc & 3) as usize];
+    pull(src, out, window + dict + fcs)?;
+    loop {
+        let at = pull(src, out, 3)?;
+        let h = le(&out[at..]) as u32;
+        let (last, kind, size) = (h & 1 == 1, (h >> 1) & 3, (h >> 3) as usize);
+        match kind {
+            0 | 2 => pull(src, out, size)?,
+            1 => pull(src, out, 1)?,
+            _ => return Err(invalid("reserved zstd block type")),
+        };
+        if last {
+            break;
+        }
+    }
+    if desc & 0x04 != 0 {
+        pull(src, out, 4)?;
+    }
+    Ok(true)
+}
+
+// Decode the frame starting at `*pos`, appending to `out`. Skippable frames
+// are passed over.
+pub fn decompress_frame(data: &[u8], pos: &mut usize, out: &mut Vec<u8>) -> Result<(), String> {
+    let magic = le(take(data, pos, 4)?) as u32;
+    if magic & 0xFFFF_FFF0 == 0x184D_2A50 {
+        let size = le(take(data, pos, 4)?) as usize;
+        take(data, pos, size)?;
+        return Ok(());
+    }
+    if magic != MAGIC {
+        return Err(format!("bad zstd magic at offset {}", *pos - 4));
+    }
+    let desc = take(data, pos, 1)?[0];
+    let single = desc & 0x20 != 0;
+    if desc & 3 != 0 {
+        return Err("zstd dictionaries are not supported".into());
+    }
+    if !single {
+        take(data, pos, 1)?;
+    }
+    let fcs = match desc >> 6 {
+        0 => single as usize,
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    take(data, pos, fcs)?;
+    let frame_start = out.len();
+    let mut rep = [1, 4, 8];
+    loop {
+        let h = le(take(data, pos, 3)?) as u32;
+        let (last, kind, size) = (h & 1 == 1, (h >> 1) & 3, (h >> 3) as usize);
+        match kind {
+            0 => out.extend_from_slice(take(data, pos, size)?),
+            1 => {
+                let b = take(data, pos, 1)?[0];
+                out.resize(out.len() + size, b);
+            }
+            2 => decode_block(take(data, pos, size)?, out, frame_start, &mut rep)?,
+            _ => return Err("reserved zstd block type".into()),
+        }
+        if last {
+            break;
+        }
+    }
+    if desc & 0x04 != 0 {
+        // Content checksum (xxhash64) is skipped, not verified
+        take(data, pos, 4)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+        let (mut pos, mut out) = (0, Vec::new());
+        while pos < data.len() {
+            decompress_frame(data, &mut pos, &mut out)?;
+        }
+        Ok(out)
+    }
+
+    // Frame with descriptor `desc`, the header bytes after it and raw blocks
+    fn frame(desc: u8, header: &[u8], blocks: &[(u32, &[u8], usize)]) -> Vec<u8> {
+        let mut out = MAGIC.to_le_bytes().to_vec();
+        out.push(desc);
+        out.extend_from_slice(header);
+        for (i, &(kind, body, size)) in blocks.iter().enumerate() {
+            block_header(&mut out, i + 1 == blocks.len(), kind, size);
+            out.extend_from_slice(body);
+        }
+        out
+    }
+
+    // Fixed-layout records like the journal's, with some noise
+    fn records(n: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut x = 0x2545_F491_4F6C_DD1Du64;
+        for i in 0..n as u64 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            out.extend_from_slice(&(1_700_000_000_000 + i * 100).to_le_bytes());
+            out.extend_from_slice(&[2, 0, 1, 0]);
+            out.extend_from_slice(&(x % 50_000).to_le_bytes());
+            out.extend_from_slice(&(100.0 + (x % 7) as f64 * 0.5).to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn roundtrips_empty_incompressible_and_repetitive_input() {
+        assert_eq!(decompress(&compress(b"")).unwrap(), b"");
+        let mut x = 0x9E37_79B9u32;
+        let noise: Vec<u8> = (0..1000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect();
+        let packed = compress(&noise);
+        // Raw block: header, block header, the bytes
+        assert_eq!(packed.len(), 4 + 1 + 8 + 3 + noise.len());
+        assert_eq!(decompress(&packed).unwrap(), noise);
+
+        let recs = records(2000);
+        let packed = compress(&recs);
+        assert!(packed.len() < recs.len() / 2);
+        assert_eq!(decompress(&packed).unwrap(), recs);
+    }
+
+    #[test]
+    fn input_larger_than_a_block_spans_several() {
+        let recs = records(20_000);
+        assert!(recs.len() > 2 * MAX_BLOCK);
+        assert_eq!(decompress(&compress(&recs)).unwrap(), recs);
+    }
+
+    #[test]
+    fn decodes_raw_and_rle_blocks_with_optional_header_fields() {
+        // Single segment, 1-byte content size
+        let f = frame(0x20, &[7], &[(0, b"abc", 3), (1, b"z", 4)]);
+        assert_eq!(decompress(&f).unwrap(), b"abczzzz");
+        // Window descriptor instead of single segment, no content size
+        let f = frame(0x00, &[0x50], &[(1, b"\0", 5)]);
+        assert_eq!(decompress(&f).unwrap(), [0; 5]);
+        // 2-byte content size and a content checksum, which is skipped
+        let mut f = frame(0x44, &[0x50, 2, 0], &[(0, b"hi", 2)]);
+        f.extend_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(decompress(&f).unwrap(), b"hi");
+    }
+
+    #[test]
+    fn skippable_frames_are_passed_over() {
+        let mut data = 0x184D_2A53u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(b"xyz");
+        data.extend_from_slice(&compress(b"payload"));
+        assert_eq!(decompress(&data).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn rejects_reserved_blocks_dictionaries_and_truncation() {
+        let f = frame(0x20, &[1], &[(3, b"", 0)]);
+        assert_eq!(decompress(&f).unwrap_err(), "reserved zstd block type");
+        let f = frame(0x21, &[9, 1], &[(0, b"a", 1)]);
+        assert_eq!(
+            decompress(&f).unwrap_err(),
+            "zstd dictionaries are not supported"
+        );
+        assert!(decompress(b"not zstd")
+            .unwrap_err()
+            .starts_with("bad zstd magic"));
+        let packed = compress(&records(100));
+        for cut in [3, 10, packed.len() / 2, packed.len() - 1] {
+            assert!(decompress(&packed[..cut]).is_err(), "cut at {}", cut);
+        }
+    }
+
+    #[test]
+    fn read_frame_splits_a_stream_into_frames() {
+        let first = compress(&records(300));
+        let mut second = frame(0x04, &[0x50], &[(1, b"q", 9), (0, b"end", 3)]);
+        second.extend_from_slice(&[0; 4]);
+        let mut skip = 0x184D_2A50u32.to_le_bytes().to_vec();
+        skip.extend_from_slice(&[2, 0, 0, 0, 0xAA, 0xBB]);
+        let stream = [first.clone(), skip.clone(), second.clone()].concat();
+
+        let mut src = &stream[..];
+        let mut got = Vec::new();
+        let mut buf = Vec::new();
+        while read_frame(&mut src, &mut buf).unwrap() {
+            got.push(std::mem::take(&mut buf));
+        }
+        assert_eq!(got, [first, skip, second]);
+        // A clean end stays clean
+        assert!(!read_frame(&mut src, &mut buf).unwrap());
+
+        for cut in [2, 7, stream.len() - 1] {
+            let mut src = &stream[stream.len() - cut..];
+            let mut buf = Vec::new();
+            let err = loop {
+                match read_frame(&mut src, &mut buf) {
+                    Ok(true) => buf.clear(),
+                    Ok(false) => panic!("truncated stream read cleanly (cut {})", cut),
+                    Err(e) => break e,
+                }
+            };
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
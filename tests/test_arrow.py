@@ -0,0 +1,177 @@
+"""
+Unit tests for the mm_orderbook Arrow exports (C data interface via the
+Arrow PyCapsule protocol).
+"""
+
+import ctypes
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+class ArrowSchema(ctypes.Structure):
+    pass
+
+
+ArrowSchema._fields_ = [
+    ("format", ctypes.c_char_p),
+    ("name", ctypes.c_char_p),
+    ("metadata", ctypes.c_char_p),
+    ("flags", ctypes.c_int64),
+    ("n_children", ctypes.c_int64),
+    ("children", ctypes.POINTER(ctypes.POINTER(ArrowSchema))),
+    ("dictionary", ctypes.POINTER(ArrowSchema)),
+    ("release", ctypes.CFUNCTYPE(None, ctypes.POINTER(ArrowSchema))),
+    ("private_data", ctypes.c_void_p),
+]
+
+
+class ArrowArray(ctypes.Structure):
+    pass
+
+
+ArrowArray._fields_ = [
+    ("length", ctypes.c_int64),
+    ("null_count", ctypes.c_int64),
+    ("offset", ctypes.c_int64),
+    ("n_buffers", ctypes.c_int64),
+    ("n_children", ctypes.c_int64),
+    ("buffers", ctypes.POINTER(ctypes.c_void_p)),
+    ("children", ctypes.POINTER(ctypes.POINTER(ArrowArray))),
+    ("dictionary", ctypes.POINTER(ArrowArray)),
+    ("release", ctypes.CFUNCTYPE(None, ctypes.POINTER(ArrowArray))),
+    ("private_data", ctypes.c_void_p),
+]
+
+
+class ArrowArrayStream(ctypes.Structure):
+    _fields_ = [
+        ("get_schema", ctypes.CFUNCTYPE(ctypes.c_int, ctypes.c_void_p, ctypes.POINTER(ArrowSchema))),
+        ("get_next", ctypes.CFUNCTYPE(ctypes.c_int, ctypes.c_void_p, ctypes.POINTER(ArrowArray))),
+        ("get_last_error", ctypes.c_void_p),
+        ("release", ctypes.CFUNCTYPE(None, ctypes.c_void_p)),
+        ("private_data", ctypes.c_void_p),
+    ]
+
+
+_get_pointer = ctypes.pythonapi.PyCapsule_GetPointer
+_get_pointer.restype = ctypes.c_void_p
+_get_pointer.argtypes = [ctypes.py_object, ctypes.c_char_p]
+
+
+def capsule_struct(capsule, name, cls):
+    return cls.from_address(_get_pointer(capsule, name))
+
+
+def read_column(fmt, arr):
+    """Values of one exported child array, decoded from its raw buffers."""
+    n, bufs = arr.length, arr.buffers
+    valid = [True] * n
+    if bufs[0]:
+        bits = ctypes.cast(bufs[0], ctypes.POINTER(ctypes.c_uint8))
+        valid = [bool(bits[i // 8] >> (i % 8) & 1) for i in range(n)]
+    if fmt == b"U":
+        offsets = ctypes.cast(bufs[1], ctypes.POINTER(ctypes.c_int64))
+        data = ctypes.cast(bufs[2], ctypes.POINTER(ctypes.c_char))
+        values = [data[offsets[i]:offsets[i + 1]].decode() for i in range(n)]
+    else:
+        ctype = {b"l": ctypes.c_int64, b"L": ctypes.c_uint64, b"g": ctypes.c_double}[fmt]
+        ptr = ctypes.cast(bufs[1], ctypes.POINTER(ctype))
+        values = [ptr[i] for i in range(n)]
+    return [v if ok else None for v, ok in zip(values, valid)]
+
+
+def read_capsules(schema_capsule, array_capsule):
+    schema = capsule_struct(schema_capsule, b"arrow_schema", ArrowSchema)
+    array = capsule_struct(array_capsule, b"arrow_array", ArrowArray)
+    assert schema.format == b"+s" and schema.n_children == array.n_children
+    out = {}
+    for i in range(schema.n_children):
+        child = schema.children[i].contents
+        out[child.name.decode()] = (child.format, read_column(child.format, array.children[i].contents))
+    return array.length, out
+
+
+def test_book_and_trade_tape_export_through_c_data_interface():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0), (99.5, 2.0)], [(100.5, 3.0)])
+    batch = book.to_arrow()
+    assert batch.num_rows == len(batch) == 3
+    assert batch.column_names == ["side", "level", "price", "size"]
+    rows, cols = read_capsules(*batch.__arrow_c_array__())
+    assert rows == 3
+    assert cols == {
+        "side": (b"U", ["bid", "bid", "ask"]),
+        "level": (b"l", [0, 1, 0]),
+        "price": (b"g", [100.0, 99.5, 100.5]),
+        "size": (b"g", [1.0, 2.0, 3.0]),
+    }
+    assert book.to_arrow(depth=1).to_pydict() == {
+        "side": ["bid", "ask"], "level": [0, 0], "price": [100.0, 100.5], "size": [1.0, 3.0]}
+    assert mm.L2Book().to_arrow().num_rows == 0
+
+    tape = mm.TradeTape(window_ms=1000)
+    tape.add_trades([(1, 100.0, 0.5, "buy"), (2, 100.5, 0.25, "sell")])
+    _, cols = read_capsules(*tape.to_arrow().__arrow_c_array__())
+    assert cols["ts"] == (b"l", [1, 2]) and cols["side"] == (b"U", ["buy", "sell"])
+    assert tape.to_arrow().column("size") == [0.5, 0.25]
+    with pytest.raises(KeyError):
+        tape.to_arrow().column("nope")
+
+
+def test_journal_export_and_stream_release(tmp_path):
+    path = tmp_path / "md.zst"
+    w = mm.JournalWriter(str(path))
+    w.write_snapshot("BTC", 0, [(100.0, 1.0)], [(101.0, 1.5)], update_id=7)
+    w.write_trade("BTC", 20, 100.5, 0.3, "buy")
+    w.write_delta("ETH", 30, [(9.9, 4.0)], [])
+    w.close()
+
+    reader = mm.JournalReader(str(path))
+    next(reader)                                  # export ignores the iteration position
+    batch = reader.to_arrow()
+    assert batch.to_pydict() == {
+        "ts": [0, 0, 20, 30],
+        "seq": [0, 0, 1, 2],
+        "symbol": ["BTC", "BTC", "BTC", "ETH"],
+        "kind": ["snapshot", "snapshot", "trade", "delta"],
+        "side": ["bid", "ask", "buy", "bid"],
+        "price": [100.0, 101.0, 100.5, 9.9],
+        "size": [1.0, 1.5, 0.3, 4.0],
+        "update_id": [7, 7, None, None],
+    }
+    assert next(reader).kind == "trade"
+
+    schema, array = batch.__arrow_c_array__()
+    _, cols = read_capsules(schema, array)
+    assert cols["update_id"] == (b"L", [7, 7, None, None])
+    nullable = capsule_struct(schema, b"arrow_schema", ArrowSchema).children[7].contents
+    assert nullable.flags & 2
+
+    # Stream: one batch, then a released array marks the end
+    capsule = batch.__arrow_c_stream__()
+    stream = capsule_struct(capsule, b"arrow_array_stream", ArrowArrayStream)
+    s, a = ArrowSchema(), ArrowArray()
+    assert stream.get_schema(ctypes.addressof(stream), ctypes.byref(s)) == 0 and s.n_children == 8
+    assert stream.get_next(ctypes.addressof(stream), ctypes.byref(a)) == 0 and a.length == 4
+    end = ArrowArray()
+    assert stream.get_next(ctypes.addressof(stream), ctypes.byref(end)) == 0 and not end.release
+    # A consumer owns what it took out: release it and the batch stays intact
+    a.release(ctypes.byref(a))
+    s.release(ctypes.byref(s))
+    assert not a.release and not s.release
+    del stream, capsule                           # releases the stream itself
+    assert batch.column("symbol")[-1] == "ETH"
+
+
+def test_pyarrow_and_polars_read_exports_zero_copy():
+    pa = pytest.importorskip("pyarrow")
+    pl = pytest.importorskip("polars")
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0), (99.5, 2.0)], [(100.5, 3.0)])
+    rb = pa.record_batch(book.to_arrow())
+    assert rb.column("price").to_pylist() == [100.0, 99.5, 100.5]
+    df = pl.DataFrame(book.to_arrow())
+    assert df["side"].to_list() == ["bid", "bid", "ask"]
+    assert book.to_arrow().to_polars().shape == (3, 4)
@@ -0,0 +1,566 @@
+"""
+Unit tests for Rust-backed order book and trade-flow signals in mm_orderbook.
+"""
+
+import math
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def test_ofi_event_window():
+    ofi = mm.OfiCalculator(window_events=2)
+
+    assert ofi.update_top(100.0, 1.0, 101.0, 1.0) is None
+    # Bid size grows at the same price: +1
+    assert ofi.update_top(100.0, 2.0, 101.0, 1.0) == 1.0
+    # Bid steps up (+1), ask size grows at the same price (-3 + 1)
+    assert ofi.update_top(100.5, 1.0, 101.0, 3.0) == -1.0
+    # Ask steps down: -1
+    assert ofi.update_top(100.5, 1.0, 100.8, 1.0) == -1.0
+
+    # Only the last two events remain in the window
+    assert ofi.ofi() == -2.0
+    assert len(ofi) == 2
+
+
+def test_ofi_time_window_from_book():
+    ofi = mm.OfiCalculator(window_events=None, window_ms=1000)
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)])
+    ofi.update(book, 0)
+
+    book.apply_delta([(100.0, 3.0)], [])
+    assert ofi.update(book, 500) == 2.0
+    book.apply_delta([(100.0, 4.0)], [])
+    assert ofi.update(book, 1600) == 1.0
+    # The event at ts=500 has aged out
+    assert ofi.ofi() == 1.0
+
+
+def test_microprice_and_imbalance_depth_and_decay():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 2.0), (99.5, 1.0)], [(100.5, 1.5), (101.0, 0.8)])
+
+    # Defaults keep the top-of-book behaviour
+    assert book.microprice() == pytest.approx(100.0 * (1.5 / 3.5) + 100.5 * (2.0 / 3.5))
+    assert book.imbalance(5) == pytest.approx((3.0 - 2.3) / 5.3)
+
+    # Two levels: size-weighted side prices
+    bp = (100.0 * 2.0 + 99.5 * 1.0) / 3.0
+    ap = (100.5 * 1.5 + 101.0 * 0.8) / 2.3
+    assert book.microprice(2) == pytest.approx(bp * (2.3 / 5.3) + ap * (3.0 / 5.3))
+
+    # Zero decay is a no-op, positive decay discounts far levels
+    assert book.imbalance(5, 0.0) == pytest.approx(book.imbalance(5))
+    decayed = book.microprice(2, decay=0.05)
+    assert book.microprice() < decayed < book.microprice(2)
+
+
+def test_trade_tape_rolling_window():
+    tape = mm.TradeTape(window_ms=1000)
+    tape.add_trade(0, 100.0, 5.0, "buy")
+    tape.add_trade(500, 101.0, 1.0, "sell")
+    tape.add_trade(900, 102.0, 2.0, "buy")
+
+    assert tape.vwap() == pytest.approx((500.0 + 101.0 + 204.0) / 8.0)
+    assert tape.buy_volume() == 7.0
+    assert tape.sell_volume() == 1.0
+    assert tape.trade_count() == 3
+    assert tape.largest_trade() == 5.0
+
+    # The ts=0 trade falls out of the window
+    tape.add_trade(1200, 100.0, 1.0, "sell")
+    assert tape.trade_count() == 3
+    assert tape.largest_trade() == 2.0
+
+    tape.advance(5000)
+    assert tape.vwap() is None
+    assert tape.largest_trade() is None
+
+
+def test_cvd_tracker_windows_and_resets():
+    cvd = mm.CvdTracker(reset_interval_ms=10_000)
+    cvd.add_trade(1000, 2.0, "buy")
+    cvd.add_trade(2000, 1.0, "sell")
+    cvd.add_trade(3000, 3.0, "buy")
+
+    assert cvd.cvd() == 4.0
+    assert cvd.cvd_window(1500) == 2.0
+    assert cvd.slope(10_000) == pytest.approx(1.0)
+
+    # Crossing the 10s boundary starts a new period; windows are unaffected
+    cvd.add_trade(10_500, 1.0, "sell")
+    assert cvd.cvd() == -1.0
+    assert cvd.cvd_window(10_000) == 3.0
+
+    cvd.reset()
+    assert cvd.cvd() == 0.0
+
+
+def test_vpin_volume_buckets():
+    vpin = mm.Vpin(10.0, window=2)
+
+    assert vpin.add_trade(6.0, "buy") == 0
+    assert vpin.vpin() is None
+    assert vpin.add_trade(4.0, "sell") == 1
+    assert vpin.vpin() == pytest.approx(0.2)
+
+    # 25 buys close two full buckets and half-fill a third
+    assert vpin.add_trade(25.0, "buy") == 2
+    assert vpin.is_ready
+    assert vpin.vpin() == pytest.approx(1.0)
+    assert vpin.current_fill == pytest.approx(0.5)
+
+
+def test_vol_estimator_return_based():
+    vol = mm.VolEstimator(window=2, ewma_lambda=0.5)
+    assert vol.ewma() is None and vol.realized() is None
+    vol.add(0, 100.0)
+    vol.add(1, 101.0)
+    r1 = math.log(101.0 / 100.0)
+    assert vol.ewma() == pytest.approx(abs(r1))
+    vol.add(2, 100.0)
+    r2 = math.log(100.0 / 101.0)
+    assert vol.ewma() == pytest.approx(math.sqrt(0.5 * r1**2 + 0.5 * r2**2))
+    vol.add(3, 100.0)
+    # Window of 2 returns: r2 and 0
+    assert vol.realized() == pytest.approx(math.sqrt(r2**2 / 2))
+    assert vol.count == 4
+
+    book = mm.L2Book()
+    assert vol.update(book, 4) is False
+    book.apply_snapshot([(99.0, 1.0)], [(101.0, 1.0)])
+    assert vol.update(book, 5) is True
+    with pytest.raises(ValueError):
+        vol.add(6, 0.0)
+    with pytest.raises(ValueError):
+        mm.VolEstimator(ewma_lambda=1.0)
+
+
+def test_vol_estimator_range_based():
+    vol = mm.VolEstimator(bar_ms=1000, bars=2)
+    for ts, p in [(0, 100.0), (300, 102.0), (600, 99.0), (900, 101.0)]:
+        vol.add(ts, p)
+    # The first bar only closes when a later one starts
+    assert vol.parkinson() is None
+    vol.add(1500, 101.0)
+    assert vol.bar_count == 1
+    hl = math.log(102.0 / 99.0) ** 2
+    co = math.log(101.0 / 100.0) ** 2
+    assert vol.parkinson() == pytest.approx(math.sqrt(hl / (4 * math.log(2))))
+    assert vol.garman_klass() == pytest.approx(math.sqrt(0.5 * hl - (2 * math.log(2) - 1) * co))
+
+    # Flat bars dilute the estimate; only the last 2 bars count
+    vol.add(2500, 101.0)
+    vol.add(3500, 101.0)
+    assert vol.bar_count == 2
+    assert vol.parkinson() == 0.0
+    vol.reset()
+    assert vol.count == 0 and vol.parkinson() is None
+
+
+def test_rolling_stats_count_window():
+    rs = mm.RollingStats(window=4)
+    assert rs.mean() is None and rs.median() is None and rs.min() is None
+    rs.extend([5.0, 1.0, 3.0, 2.0, 4.0])
+    # 5.0 has been evicted: window is [1, 3, 2, 4]
+    assert len(rs) == 4 and rs.last == 4.0
+    assert rs.sum() == 10.0
+    assert rs.mean() == 2.5
+    assert rs.var() == pytest.approx(1.25)
+    assert rs.std(ddof=1) == pytest.approx(math.sqrt(5.0 / 3.0))
+    assert (rs.min(), rs.max()) == (1.0, 4.0)
+    assert rs.median() == 2.5
+    assert rs.percentile(0) == 1.0 and rs.percentile(100) == 4.0
+    assert rs.percentile(25) == pytest.approx(1.75)
+    with pytest.raises(ValueError):
+        rs.percentile(101)
+    with pytest.raises(ValueError):
+        rs.push(float("nan"))
+
+    rs.push(0.5)
+    rs.push(0.5)
+    assert (rs.min(), rs.max()) == (0.5, 4.0)
+    rs.push(0.5)
+    assert rs.max() == 4.0
+    rs.push(0.5)
+    assert rs.max() == 0.5 and rs.std() == 0.0
+
+
+def test_rolling_stats_time_window():
+    rs = mm.RollingStats(window=None, window_ms=1000)
+    rs.push(1.0, 0)
+    rs.push(3.0, 500)
+    rs.push(2.0, 900)
+    assert rs.median() == 2.0
+    rs.push(10.0, 1200)
+    # ts=0 aged out
+    assert len(rs) == 3 and rs.min() == 2.0 and rs.mean() == 5.0
+    rs.advance(2000)
+    assert len(rs) == 1 and rs.max() == 10.0
+    rs.reset()
+    assert len(rs) == 0 and rs.var() is None
+    with pytest.raises(ValueError):
+        mm.RollingStats(window=None)
+
+
+def test_ewma_alpha_halflife_and_bias_correction():
+    e = mm.Ewma(alpha=0.5)
+    assert e.value is None
+    assert e.update(10.0) == 10.0
+    # Weights 0.5 and 1 (pandas adjust=True): (5 + 20) / 1.5
+    assert e.update(20.0) == pytest.approx(25.0 / 1.5)
+    assert e.count == 2
+
+    h = mm.Ewma(halflife=1.0)
+    h.update(0.0)
+    assert h.update(3.0) == pytest.approx(2.0)
+
+    with pytest.raises(ValueError):
+        mm.Ewma()
+    with pytest.raises(ValueError):
+        mm.Ewma(alpha=0.5, halflife=2.0)
+    with pytest.raises(ValueError):
+        mm.Ewma(alpha=1.5)
+
+
+def test_ewma_time_aware_decay():
+    e = mm.Ewma(halflife_ms=1000)
+    e.update(0.0, 0)
+    # One half-life later the old value weighs 0.5 against 1
+    assert e.update(3.0, 1000) == pytest.approx(2.0)
+    # Same timestamp: equal weight with the history total (1.5 vs 1)
+    assert e.update(0.0, 1000) == pytest.approx(1.2)
+    # A long gap forgets the history almost entirely
+    assert e.update(7.0, 60_000) == pytest.approx(7.0)
+    with pytest.raises(ValueError):
+        e.update(1.0)
+
+    e.reset()
+    assert e.value is None and e.count == 0
+
+
+def test_ewmv_variance_and_zscore():
+    v = mm.Ewmv(alpha=0.1)
+    assert v.var is None and v.zscore(1.0) is None
+    v.update(1.0)
+    assert v.var == 0.0 and v.zscore(2.0) is None
+    v.update(3.0)
+    # Weights 0.9 and 1: mean 2.0526, weighted population variance
+    w = [0.9, 1.0]
+    xs = [1.0, 3.0]
+    mean = sum(a * b for a, b in zip(w, xs)) / sum(w)
+    var = sum(a * (x - mean) ** 2 for a, x in zip(w, xs)) / sum(w)
+    assert v.mean == pytest.approx(mean)
+    assert v.var == pytest.approx(var)
+    assert v.std == pytest.approx(math.sqrt(var))
+    assert v.zscore(mean + 2 * math.sqrt(var)) == pytest.approx(2.0)
+
+    t = mm.Ewmv(halflife_ms=500)
+    for ts, x in [(0, 1.0), (100, 2.0), (700, 1.5), (2000, 4.0)]:
+        t.update(x, ts)
+    assert t.count == 4 and t.var > 0
+
+
+def test_bar_builder_time_bars():
+    bb = mm.BarBuilder("time", 1000)
+    assert bb.add_trade(100, 10.0, 1.0, "buy") == 0
+    bb.add_trade(400, 12.0, 2.0, "sell")
+    bb.add_trade(900, 9.0, 1.0)
+    assert bb.current() == (0, 1000, 10.0, 12.0, 9.0, 9.0, 4.0, 43.0 / 4.0, 3, 1.0)
+    # A trade in a later interval closes the bar; the empty interval is skipped
+    assert bb.add_trade(2500, 11.0, 1.0) == 1
+    assert bb.advance(2999) == 0
+    assert bb.advance(3000) == 1
+    bars = bb.take_bars()
+    assert [b[:2] for b in bars] == [(0, 1000), (2000, 3000)]
+    assert dict(zip(mm.BarBuilder.COLUMNS, bars[0]))["vwap"] == pytest.approx(10.75)
+    assert len(bb) == 0 and bb.current() is None
+
+
+def test_bar_builder_tick_volume_and_dollar_bars():
+    tick = mm.BarBuilder("tick", 2)
+    for ts, p in enumerate([1.0, 2.0, 3.0, 4.0, 5.0]):
+        tick.add_trade(ts, p, 1.0)
+    assert [(b[2], b[5]) for b in tick.take_bars()] == [(1.0, 2.0), (3.0, 4.0)]
+    assert tick.flush() == 1 and tick.take_bars()[0][8] == 1
+
+    vol = mm.BarBuilder("volume", 10.0)
+    vol.add_trade(0, 100.0, 4.0, "buy")
+    # 25 lots overshoot: split into 6 + 10 + 9
+    assert vol.add_trade(1, 101.0, 25.0, "buy") == 2
+    bars = vol.take_bars()
+    assert [b[6] for b in bars] == [10.0, 10.0]
+    assert bars[0][7] == pytest.approx((400.0 + 606.0) / 10.0)
+    assert bars[1][9] == 10.0
+    assert vol.current()[6] == pytest.approx(9.0)
+
+    dollar = mm.BarBuilder("dollar", 1000.0)
+    assert dollar.add_trade(0, 100.0, 25.0) == 2
+    assert [b[6] for b in dollar.take_bars()] == [10.0, 10.0]
+    assert dollar.current()[6] == pytest.approx(5.0)
+
+    with pytest.raises(ValueError):
+        mm.BarBuilder("range", 1.0)
+    with pytest.raises(ValueError):
+        mm.BarBuilder("volume", 0.0)
+
+
+def test_bar_builder_numpy_rows():
+    np = pytest.importorskip("numpy")
+    bb = mm.BarBuilder("tick", 1)
+    bb.add_trade(5, 10.0, 2.0, "buy")
+    bb.add_trade(6, 11.0, 1.0, "sell")
+    arr = bb.take_array()
+    assert arr.shape == (2, len(mm.BarBuilder.COLUMNS))
+    assert np.allclose(arr[:, 5], [10.0, 11.0])
+    assert len(bb) == 0
+
+
+def test_cross_venue_spread_zscore():
+    a = mm.L2Book()
+    b = mm.L2Book()
+    spread = mm.CrossVenueSpread(a, b, window=50, min_samples=3)
+    assert spread.update() is None and spread.count == 0
+
+    spreads = []
+    for i in range(10):
+        mid_a = 100.0 + 0.01 * (i % 3)
+        a.apply_snapshot([(mid_a - 0.05, 1.0)], [(mid_a + 0.05, 1.0)])
+        b.apply_snapshot([(99.95, 1.0)], [(100.05, 1.0)])
+        z = spread.update(ts=i)
+        spreads.append((mid_a - 100.0) / 100.0 * 10_000)
+        assert (z is None) == (i < 2)
+
+    mean = sum(spreads) / len(spreads)
+    std = math.sqrt(sum((s - mean) ** 2 for s in spreads) / (len(spreads) - 1))
+    assert spread.spread == pytest.approx(spreads[-1])
+    assert spread.mean == pytest.approx(mean)
+    assert spread.std == pytest.approx(std)
+    assert spread.zscore == pytest.approx((spreads[-1] - mean) / std)
+    assert spread.is_ready and len(spread) == 10
+
+    # A dislocation shows up as a large z-score
+    assert spread.update_prices(100.5, 100.0) > 2.5
+
+    spread.reset()
+    assert spread.zscore is None and spread.count == 0
+
+
+def test_cross_venue_spread_price_units_and_validation():
+    a = mm.L2Book()
+    b = mm.L2Book()
+    spread = mm.CrossVenueSpread(a, b, window=None, window_ms=1_000, units="price", min_samples=2)
+    assert spread.units == "price"
+    spread.update_prices(101.0, 100.0, ts=0)
+    spread.update_prices(100.5, 100.0, ts=500)
+    assert spread.mean == pytest.approx(0.75)
+    spread.update_prices(100.0, 100.0, ts=1_200)
+    # The ts=0 sample left the 1s window
+    assert spread.mean == pytest.approx(0.25)
+
+    with pytest.raises(ValueError):
+        mm.CrossVenueSpread(a, b, units="pct")
+    with pytest.raises(ValueError):
+        mm.CrossVenueSpread(a, b, reference="last")
+    with pytest.raises(ValueError):
+        spread.update_prices(0.0, 100.0)
+
+
+def test_fair_value_kalman_level_filter():
+    kf = mm.FairValueKalman(process_noise=1.0, observation_noise=1.0)
+    assert kf.value is None and kf.forecast() is None
+    assert kf.update(100.0) == 100.0
+    assert kf.variance == 1.0
+    # Predict P = 1 + 1, gain 2/3, posterior P = 2/3
+    assert kf.update(103.0) == pytest.approx(102.0)
+    assert kf.variance == pytest.approx(2.0 / 3.0)
+    assert kf.innovation == pytest.approx(3.0)
+    assert kf.trend is None
+
+    # A noisier observation moves the estimate less
+    a = mm.FairValueKalman(process_noise=1.0, observation_noise=1.0)
+    b = mm.FairValueKalman(process_noise=1.0, observation_noise=1.0)
+    for f in (a, b):
+        f.update(100.0)
+    assert a.update(101.0) > b.update(101.0, observation_noise=10.0)
+
+    # Timestamps scale process noise by elapsed seconds
+    kf = mm.FairValueKalman(process_noise=1.0, observation_noise=1.0)
+    kf.update(100.0, ts=0)
+    kf.update(100.0, ts=500)
+    assert kf.variance == pytest.approx(1.5 / 2.5)
+
+    book = mm.L2Book()
+    assert kf.update_book(book) is None
+    book.apply_snapshot([(99.0, 1.0)], [(101.0, 3.0)])
+    assert kf.update_book(book, ts=1_500) is not None and kf.count == 3
+
+    kf.reset()
+    assert (kf.value, kf.count) == (None, 0)
+    with pytest.raises(ValueError):
+        mm.FairValueKalman(process_noise=-1.0)
+
+
+def test_fair_value_kalman_trend():
+    kf = mm.FairValueKalman(process_noise=1e-4, observation_noise=1e-2, trend=True, trend_noise=1e-4)
+    for i in range(200):
+        kf.update(100.0 + 0.05 * i)
+    assert kf.trend == pytest.approx(0.05, rel=0.05)
+    assert kf.value == pytest.approx(100.0 + 0.05 * 199, abs=0.05)
+    assert kf.forecast(10.0) == pytest.approx(kf.value + 10.0 * kf.trend)
+    # A plain level filter lags the ramp
+    level = mm.FairValueKalman(process_noise=1e-4, observation_noise=1e-2)
+    for i in range(200):
+        level.update(100.0 + 0.05 * i)
+    assert level.value < kf.value
+
+
+def test_fair_value_blender_weights_and_staleness():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0)], [(100.2, 3.0)])
+    other = mm.L2Book()
+    other.apply_snapshot([(100.3, 1.0)], [(100.5, 1.0)])
+
+    fv = mm.FairValueBlender(halflife_ms=1_000.0)
+    fv.add_source("mid", 1.0, book=book)
+    fv.add_source("micro", 1.0, book=book, reference="microprice")
+    fv.add_source("binance", 1.0, book=other)
+    fv.add_source("index", 2.0, halflife_ms=math.inf, max_age_ms=5_000)
+    assert fv.sources() == ["mid", "micro", "binance", "index"] and len(fv) == 4
+    assert fv.fair_value(0) is None
+
+    fv.update("index", 100.4, ts=0)
+    value = fv.tick(0)
+    mid, micro = 100.1, (100.0 * 3.0 + 100.2 * 1.0) / 4.0
+    assert value == pytest.approx((mid + micro + 100.4 + 2 * 100.4) / 5.0)
+    assert fv.price("micro") == pytest.approx(micro)
+
+    # One halflife later the book prices count half; the index does not decay
+    weights = fv.weights(1_000)
+    assert weights["index"] == pytest.approx(2.0 / 3.5)
+    assert weights["mid"] == pytest.approx(0.5 / 3.5)
+    assert sum(weights.values()) == pytest.approx(1.0)
+
+    # Past max_age the index drops out
+    assert fv.tick(6_000) == pytest.approx((mid + micro + 100.4) / 3.0)
+    assert "index" not in fv.weights(6_000)
+
+    fv.set_weight("binance", 0.0)
+    assert fv.fair_value(6_000) == pytest.approx((mid + micro) / 2.0)
+    assert fv.remove_source("binance") and not fv.remove_source("binance")
+
+    with pytest.raises(KeyError):
+        fv.update("missing", 100.0, 0)
+    with pytest.raises(ValueError):
+        fv.add_source("bad", -1.0)
+    with pytest.raises(ValueError):
+        fv.add_source("bad", 1.0, book=book, reference="vwap")
+
+    fv.reset()
+    assert fv.fair_value(6_000) is None
+
+
+def test_iceberg_detector_flags_refilling_levels():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0), (99.5, 4.0)], [(100.5, 2.0), (101.0, 3.0)])
+    det = mm.IcebergDetector(depth=5, min_refills=2, expiry_ms=1_000)
+    det.observe(book, 0)
+
+    # The bid at 100 trades 3.0 through a 1.0 display and shows 1.0 again
+    det.on_trade(100.0, 1.5, "sell")
+    det.on_trade(100.0, 1.5)                     # side inferred from the touch
+    book.apply_delta(bids=[(100.0, 1.0)], asks=[])
+    det.observe(book, 100)
+    assert det.icebergs() == [] and det.hidden_size("bid", 100.0) is None
+
+    det.on_trade(100.0, 2.0, "sell")
+    book.apply_delta(bids=[(100.0, 1.2)], asks=[])
+    det.observe(book, 200)
+    (ice,) = det.icebergs("bid")
+    assert (ice.side, ice.price, ice.refills, ice.last_ts) == ("buy", 100.0, 2, 200)
+    # Revealed: (3 - 1 + 1) + (2 - 1 + 1.2)
+    assert ice.hidden_size == pytest.approx(5.2) and ice.display_size == pytest.approx(1.1)
+    assert det.icebergs("ask") == []
+
+    # A print that does not exhaust the display is no refill; the ask side
+    # trades through a normal level that then disappears
+    det.on_trade(100.0, 0.5, "sell")
+    det.on_trade(100.5, 2.0, "buy")
+    book.apply_delta(bids=[(100.0, 0.7)], asks=[(100.5, 0.0)])
+    det.observe(book, 300)
+    assert det.icebergs()[0].refills == 2 and det.hidden_size("ask", 100.5) is None
+
+    # Forgotten after expiry_ms without a refill
+    det.observe(book, 1_300)
+    assert det.icebergs() == []
+    with pytest.raises(ValueError):
+        mm.IcebergDetector(min_refills=0)
+
+
+def test_sweep_detector_flags_bursts_and_fires_callbacks():
+    det = mm.SweepDetector(window_ms=100, min_levels=3, min_notional=500.0,
+                           large_notional=1_000.0, cooldown_ms=500)
+    events = []
+
+    class Listener:
+        def on_sweep(self, sweep):
+            events.append(("sweep", sweep.side, det.count))   # detector is readable
+
+        def on_large_trade(self, ts, price, qty, side):
+            events.append(("large", ts, side))
+
+    det.set_callbacks(Listener())
+    # Two levels is not a sweep, and sells form their own burst
+    assert det.on_trade(0, 100.0, 2.0, "buy") is None
+    assert det.on_trade(10, 100.5, 1.0, "buy") is None
+    assert det.on_trade(15, 99.0, 1.0, "sell") is None
+    sweep = det.on_trade(20, 101.0, 2.0, "buy")
+    assert sweep.side == "buy" and sweep.levels == 3 and sweep.qty == 5.0
+    assert sweep.notional == pytest.approx(502.5)
+    assert sweep.move_bps == pytest.approx(100.0)
+    assert events == [("sweep", "buy", 1)]
+    # Flagged once per burst; later prints only extend it
+    assert det.on_trade(30, 101.5, 10.0, "buy") is None
+    assert events[-1] == ("large", 30, "buy")
+    assert det.last_sweep.end_ts == 30 and det.last_sweep.levels == 4
+    assert det.active(500) and not det.active(531)
+    # Outside the window a new burst starts
+    assert det.on_trade(150, 102.0, 1.0, "buy") is None
+    assert det.count == 1
+
+    det.reset()
+    assert det.last_sweep is None and not det.active(150)
+    with pytest.raises(ValueError):
+        mm.SweepDetector(window_ms=0)
+
+
+def test_hawkes_intensity_spikes_and_decays():
+    import math
+
+    hk = mm.HawkesIntensity(mu=0.5, alpha=0.8, beta=2.0, alpha_cross=0.2)
+    assert hk.branching_ratio == pytest.approx(0.5)
+    assert hk.excitation() == 1.0 and hk.imbalance() == 0.0
+    for ts in (0, 100, 200):
+        hk.on_trade("buy", ts)
+    # Each buy adds 0.8 to buys and 0.2 to sells, decaying at 2/s
+    jumps = sum(math.exp(-2.0 * (200 - t) / 1000) for t in (0, 100, 200))
+    buy, sell = hk.intensities(200)
+    assert buy == pytest.approx(0.5 + 0.8 * jumps)
+    assert sell == pytest.approx(0.5 + 0.2 * jumps)
+    assert hk.intensity("buy", 200) == pytest.approx(buy)
+    assert hk.excitation(200) > 2.0 and hk.imbalance(200) > 0.3
+    # One second later the excitation is down by e^-2
+    assert hk.intensity("buy", 1_200) - 0.5 == pytest.approx((buy - 0.5) * math.exp(-2.0))
+    hk.on_trade("sell", 1_200, weight=2.0)
+    assert hk.intensity("sell", 1_200) == pytest.approx(0.5 + 0.2 * jumps * math.exp(-2.0) + 1.6)
+    assert hk.count == 4
+
+    hk.reset()
+    assert hk.intensity() == 1.0
+    with pytest.raises(ValueError):
+        mm.HawkesIntensity(alpha=1.0, beta=1.0)             # explosive
+    with pytest.raises(ValueError):
+        mm.HawkesIntensity().on_trade("buy")                # no clock, no ts
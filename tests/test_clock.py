@@ -0,0 +1,110 @@
+"""
+Unit tests for the mm_orderbook replay clock.
+"""
+
+import time
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def test_replay_clock_basics():
+    clock = mm.ReplayClock(1_000)
+    assert clock.is_replay and clock.now_ms() == 1_000
+    assert clock.advance(250) == 1_250
+    clock.set(500)                                  # reruns may move back
+    assert clock.now_ms() == 500
+    with pytest.raises(ValueError):
+        clock.advance(-1)
+
+    wall = mm.ReplayClock.wall()
+    assert not wall.is_replay
+    assert abs(wall.now_ms() - time.time() * 1000) < 5_000
+    with pytest.raises(ValueError):
+        wall.set(0)
+
+
+def test_analytics_default_to_clock_time():
+    clock = mm.ReplayClock(0)
+    stats = mm.RollingStats(window=None, window_ms=100, clock=clock)
+    tape = mm.TradeTape(window_ms=100, clock=clock)
+    ewma = mm.Ewma(halflife_ms=100.0, clock=clock)
+    stats.push(1.0)
+    tape.add_trade(0, 100.0, 1.0, "buy")
+    assert ewma.update(1.0) == 1.0
+    clock.set(100)
+    stats.push(3.0)
+    assert len(stats) == 1 and stats.mean() == 3.0
+    # One half-life later the first observation weighs 0.5
+    assert ewma.update(4.0) == pytest.approx((1.0 * 0.5 + 4.0) / 1.5)
+    tape.advance()
+    assert tape.volume() == 0.0
+    # An explicit ts still wins over the clock
+    stats.push(5.0, ts=150)
+    assert len(stats) == 2
+
+    with pytest.raises(ValueError):
+        mm.TradeTape().advance()                    # no clock, no ts
+    with pytest.raises(ValueError):
+        mm.Ewma(halflife_ms=10.0).update(1.0)
+
+
+def test_backtester_and_journal_replay_drive_the_clock(tmp_path):
+    clock = mm.ReplayClock()
+    bt = mm.Backtester(clock=clock)
+    bt.add_snapshot("BTC", 10, [(100.0, 1.0)], [(101.0, 1.0)])
+    bt.add_trade("BTC", 40, 100.0, 0.5)
+    seen = []
+
+    class Probe:
+        def on_book(self, bt, symbol, ts):
+            seen.append((ts, clock.now_ms()))
+
+        def on_trade(self, bt, symbol, ts, price, qty):
+            seen.append((ts, clock.now_ms()))
+
+    bt.run(Probe())
+    assert seen == [(10, 10), (40, 40)]
+    assert bt.clock.now_ms() == 40
+    with pytest.raises(ValueError):
+        mm.Backtester(clock=mm.ReplayClock.wall())
+
+    path = tmp_path / "md.zst"
+    w = mm.JournalWriter(str(path))
+    w.write_trade("BTC", 1_000, 100.0, 1.0, "buy")
+    w.write_trade("BTC", 1_500, 100.0, 2.0, "sell")
+    w.close()
+    tape = mm.TradeTape(window_ms=60_000, clock=clock)
+    times = []
+    mm.JournalReader(str(path)).replay(
+        tapes={"BTC": tape}, clock=clock, callback=lambda rec: times.append(clock.now_ms()))
+    assert times == [1_000, 1_500] and tape.volume() == 3.0
+
+
+def test_monotonic_ns_is_epoch_aligned_and_tags_book_updates():
+    a = mm.monotonic_ns()
+    b = mm.monotonic_ns()
+    assert a <= b and abs(a - time.time_ns()) < 5_000_000_000
+
+    book = mm.L2Book()
+    assert book.recv_ns is None
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)])
+    assert a <= book.recv_ns <= mm.monotonic_ns()
+
+
+def test_clock_skew_tracks_the_fastest_delay():
+    skew = mm.ClockSkew(window=3)
+    assert skew.offset_ms is None and skew.to_local_ns(0) is None
+    # Local clock 50ms ahead; network delays 2, 5, 3 and 9ms
+    for ex_ms, delay in [(1_000, 2), (1_100, 5), (1_200, 3)]:
+        assert skew.observe(ex_ms, (ex_ms + 50 + delay) * 1_000_000) == 50 + delay
+    assert skew.offset_ms == 52.0 and skew.excess_ms == 1.0
+    assert skew.to_local_ns(2_000) == 2_052_000_000
+    skew.observe(1_300, (1_300 + 59) * 1_000_000)   # the 2ms sample leaves the window
+    assert skew.offset_ms == 53.0 and skew.excess_ms == 6.0 and skew.count == 4
+    skew.reset()
+    assert skew.count == 0 and skew.offset_ms is None
+    assert abs(skew.observe(int(time.time() * 1000))) < 5_000     # recv_ns defaults to now
+    with pytest.raises(ValueError):
+        mm.ClockSkew(window=0)
@@ -0,0 +1,90 @@
+"""
+Unit tests for mm_orderbook.EventQueue, the SPSC ring between a producer
+thread and the strategy loop.
+"""
+
+import threading
+import time
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def trade(i):
+    return mm.FeedEvent("trade", symbol="BTCUSDT", ts=i, price=100.0 + i, size=1.0, side="buy")
+
+
+def test_push_pop_and_drain():
+    q = mm.EventQueue(capacity=3)
+    assert q.capacity == 4  # rounded up to a power of two
+    assert len(q) == 0 and q.pop_nowait() is None and q.pop(timeout_ms=0) is None
+
+    assert all(q.push(trade(i)) for i in range(4))
+    assert not q.push(trade(4)) and q.dropped == 1  # full: the new event is rejected
+    assert len(q) == 4
+    first = q.pop()
+    assert (first.kind, first.ts, first.price, first.side) == ("trade", 0, 100.0, "buy")
+    assert [e.ts for e in q.drain(max_events=2)] == [1, 2]
+    assert q.push(trade(5))
+    assert [e.ts for e in q.drain()] == [3, 5]
+    assert q.drain(timeout_ms=1) == [] and repr(q) == "EventQueue(len=0, capacity=4, dropped=1)"
+
+    with pytest.raises(ValueError):
+        mm.EventQueue(capacity=0)
+    with pytest.raises(ValueError):
+        mm.FeedEvent("quote")
+    with pytest.raises(ValueError):
+        mm.FeedEvent("trade", side="up")
+    with pytest.raises(ValueError):
+        q.pop(timeout_ms=-1)
+
+
+def test_blocking_pop_is_woken_by_producer_thread():
+    q = mm.EventQueue(capacity=1024)
+    n = 5000
+
+    def produce():
+        for i in range(n):
+            while not q.push(trade(i)):
+                time.sleep(0.0001)
+
+    t0 = time.monotonic()
+    assert q.pop(timeout_ms=20) is None
+    assert time.monotonic() - t0 >= 0.015
+
+    producer = threading.Thread(target=produce)
+    producer.start()
+    seen = []
+    while len(seen) < n:
+        e = q.pop(timeout_ms=5000)
+        assert e is not None
+        seen.append(e.ts)
+        seen += [e.ts for e in q.drain(max_events=100)]
+    producer.join()
+    assert seen == list(range(n))
+
+
+def test_await_next_event():
+    import asyncio
+
+    q = mm.EventQueue(capacity=64)
+    q.push(trade(0))
+
+    async def main():
+        first = await q.next_event()  # already queued
+        loop = asyncio.get_running_loop()
+        # Pushed from another thread once the task is waiting
+        loop.call_later(0.02, lambda: threading.Thread(target=lambda: [q.push(trade(i)) for i in (1, 2)]).start())
+        second = await asyncio.wait_for(q.next_event(), 5)
+        third = await q.next_event()
+        with pytest.raises(asyncio.TimeoutError):
+            await asyncio.wait_for(q.next_event(), 0.02)
+        # The cancelled wait does not swallow the next event
+        q.push(trade(3))
+        fourth = await q.next_event()
+        return [e.ts for e in (first, second, third, fourth)]
+
+    assert asyncio.run(main()) == [0, 1, 2, 3]
+    with pytest.raises(RuntimeError):  # no running loop
+        next(q.next_event().__await__())
@@ -0,0 +1,462 @@
+"""
+Unit tests for the embedded mm_orderbook.FeedClient against a local WebSocket server.
+"""
+
+import base64
+import hashlib
+import json
+import os
+import socket
+import ssl
+import struct
+import threading
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+GUID = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11"
+# Self-signed for localhost and 127.0.0.1
+TLS_CERT = os.path.join(os.path.dirname(__file__), "fixtures", "tls", "localhost.pem")
+TLS_KEY = os.path.join(os.path.dirname(__file__), "fixtures", "tls", "localhost.key")
+
+
+def send_frame(conn, payload, opcode=0x1):
+    data = payload.encode() if isinstance(payload, str) else payload
+    n = len(data)
+    if n < 126:
+        header = struct.pack("!BB", 0x80 | opcode, n)
+    elif n < 65536:
+        header = struct.pack("!BBH", 0x80 | opcode, 126, n)
+    else:
+        header = struct.pack("!BBQ", 0x80 | opcode, 127, n)
+    conn.sendall(header + data)
+
+
+def recv_exact(conn, n):
+    buf = b""
+    while len(buf) < n:
+        chunk = conn.recv(n - len(buf))
+        if not chunk:
+            raise ConnectionError("closed")
+        buf += chunk
+    return buf
+
+
+def recv_frame(conn):
+    b0, b1 = recv_exact(conn, 2)
+    n = b1 & 0x7F
+    if n == 126:
+        n = struct.unpack("!H", recv_exact(conn, 2))[0]
+    elif n == 127:
+        n = struct.unpack("!Q", recv_exact(conn, 8))[0]
+    mask = recv_exact(conn, 4) if b1 & 0x80 else b"\0\0\0\0"
+    data = bytes(x ^ mask[i % 4] for i, x in enumerate(recv_exact(conn, n)))
+    return b0 & 0x0F, data
+
+
+class WsServer:
+    """One-connection-at-a-time server running `script(server, conn)` per connection."""
+
+    def __init__(self, script, tls=False):
+        self.sock = socket.socket()
+        self.sock.bind(("127.0.0.1", 0))
+        self.sock.listen()
+        self.tls = None
+        if tls:
+            self.tls = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+            self.tls.load_cert_chain(TLS_CERT, TLS_KEY)
+        self.url = "%s://localhost:%d/v5/public/linear" % ("wss" if tls else "ws", self.sock.getsockname()[1])
+        self.script = script
+        self.received = []
+        self.connections = 0
+        self.thread = threading.Thread(target=self.serve, daemon=True)
+        self.thread.start()
+
+    def serve(self):
+        while True:
+            try:
+                conn, _ = self.sock.accept()
+            except OSError:
+                return
+            if self.tls:
+                try:
+                    conn = self.tls.wrap_socket(conn, server_side=True)
+                except OSError:  # the client refused the handshake
+                    conn.close()
+                    continue
+            with conn:
+                request = b""
+                while b"\r\n\r\n" not in request:
+                    request += conn.recv(4096)
+                key = [l.split(":", 1)[1].strip() for l in request.decode().split("\r\n")
+                       if l.lower().startswith("sec-websocket-key")][0]
+                accept = base64.b64encode(hashlib.sha1((key + GUID).encode()).digest()).decode()
+                conn.sendall((
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n"
+                    "Connection: Upgrade\r\nSec-WebSocket-Accept: %s\r\n\r\n" % accept
+                ).encode())
+                self.connections += 1
+                try:
+                    self.script(self, conn)
+                except (ConnectionError, OSError):
+                    pass
+
+    def close(self):
+        self.sock.close()
+
+
+def book_msg(kind, u, bids, asks, symbol="BTCUSDT"):
+    return json.dumps({
+        "topic": "orderbook.50." + symbol, "type": kind, "ts": 1000 + u,
+        "data": {"s": symbol, "b": bids, "a": asks, "u": u, "seq": u},
+    })
+
+
+def poll_until(client, kind, timeout_ms=5000, count=1):
+    events = []
+    while sum(e.kind == kind for e in events) < count:
+        batch = client.poll(timeout_ms=timeout_ms)
+        assert batch, "timed out waiting for %r, got %r" % (kind, events)
+        events += batch
+    return events
+
+
+def test_feed_client_applies_bybit_books_and_trades():
+    def script(server, conn):
+        opcode, sub = recv_frame(conn)
+        server.received.append(json.loads(sub))
+        send_frame(conn, json.dumps({"success": True, "op": "subscribe"}))
+        send_frame(conn, book_msg("snapshot", 10, [["100.0", "1.0"], ["99.5", "2.0"]], [["100.5", "3.0"]]))
+        send_frame(conn, book_msg("delta", 11, [["100.0", "0"], ["100.2", "4.0"]], []))
+        send_frame(conn, b"\x01\x02", opcode=0x9)  # ping, answered with a pong
+        assert recv_frame(conn) == (0xA, b"\x01\x02")
+        trades = {"topic": "publicTrade.BTCUSDT", "type": "snapshot", "data": [
+            {"T": 2000, "s": "BTCUSDT", "S": "Buy", "v": "0.5", "p": "100.5", "i": "a"},
+            {"T": 2001, "s": "BTCUSDT", "S": "Sell", "v": "1.5", "p": "100.2", "i": "b"},
+        ]}
+        # Fragmented text message
+        raw = json.dumps(trades).encode()
+        conn.sendall(struct.pack("!BB", 0x01, 10) + raw[:10])
+        send_frame(conn, raw[10:], opcode=0x0)
+        send_frame(conn, "not json")
+        send_frame(conn, struct.pack("!H", 1000), opcode=0x8)
+        recv_frame(conn)  # close echo
+
+    server = WsServer(script)
+    client = mm.FeedClient(server.url, ["orderbook.50.BTCUSDT", "publicTrade.BTCUSDT"], reconnect=False)
+    assert client.symbols() == ["BTCUSDT"] and not client.is_running
+    book = client.book("BTCUSDT")
+    assert book.needs_resync  # until the first snapshot
+    client.start()
+    events = poll_until(client, "disconnected")
+    client.stop()
+    server.close()
+
+    assert server.received == [{"op": "subscribe", "args": ["orderbook.50.BTCUSDT", "publicTrade.BTCUSDT"]}]
+    kinds = [e.kind for e in events]
+    assert kinds == ["connected", "book", "synced", "book", "trade", "trade", "error", "disconnected", "desynced"]
+    assert [(e.update_id, e.applied, e.ts) for e in (events[1], events[3])] == [(10, True, 1010), (11, True, 1011)]
+    assert (events[4].price, events[4].size, events[4].side) == (100.5, 0.5, "buy")
+    assert "invalid JSON" in events[6].message
+    assert {e.exchange for e in events} == {"bybit"}
+    snap, trade = events[1].normalize(), events[4].normalize()
+    assert isinstance(snap, mm.BookUpdate) and snap.is_snapshot and not events[3].normalize().is_snapshot
+    assert (snap.exchange, snap.symbol, snap.update_id, snap.ts, snap.applied) == ("bybit", "BTCUSDT", 10, 1010, True)
+    assert isinstance(trade, mm.Trade)
+    assert (trade.exchange, trade.symbol, trade.price, trade.size, trade.side, trade.ts) == (
+        "bybit", "BTCUSDT", 100.5, 0.5, "buy", 2000)
+    assert events[0].normalize() is None and events[6].normalize() is None
+
+    # The shared handle sees what the feed thread applied
+    assert book.best_bid == (100.2, 4.0)
+    assert book.depth(5) == ([(100.2, 4.0), (99.5, 2.0)], [(100.5, 3.0)])
+    assert book.last_update_id == 11 and book.needs_resync
+    tape = client.trades("BTCUSDT")
+    assert tape.trade_count() == 2 and tape.buy_volume() == 0.5
+    assert client.book("ETHUSDT") is None and not client.is_connected
+
+
+def test_feed_client_validation_and_connect_failure():
+    with pytest.raises(ValueError):
+        mm.FeedClient("https://stream.bybit.com/v5/public/linear", ["orderbook.50.BTCUSDT"])
+    with pytest.raises(ValueError):
+        mm.FeedClient("ws://localhost:1/", ["kline.1.BTCUSDT"])
+    with pytest.raises(ValueError):
+        mm.FeedClient("ws://localhost:1/", ["orderbook.50.BTCUSDT"], exchange="kraken")
+    with pytest.raises(ValueError):
+        mm.FeedClient("ws://localhost:1/", ["orderbook.50.BTCUSDT"], reconnect_delay_ms=100, max_reconnect_delay_ms=10)
+    client = mm.FeedClient("ws://localhost:1/", ["orderbook.50.BTCUSDT"])
+    with pytest.raises(ValueError):
+        client.set_callbacks(on_synced=42)
+
+    # Nothing listens on this port: error + disconnected, thread ends by itself
+    sock = socket.socket()
+    sock.bind(("127.0.0.1", 0))
+    port = sock.getsockname()[1]
+    sock.close()
+    client = mm.FeedClient("ws://127.0.0.1:%d/" % port, ["orderbook.1.BTCUSDT"], max_events=1, reconnect=False)
+    client.start()
+    client.stop()  # joins the thread once the connect attempt failed
+    events = client.poll()
+    # max_events=1 keeps only the newest event
+    assert [e.kind for e in events] == ["disconnected"] and client.dropped == 1
+
+
+@pytest.mark.skipif(not mm.HAS_TLS, reason="extension built without TLS")
+def test_feed_client_over_tls(monkeypatch):
+    def script(server, conn):
+        server.received.append(json.loads(recv_frame(conn)[1]))
+        send_frame(conn, book_msg("snapshot", 10, [["100.0", "1.0"]], [["100.5", "3.0"]]))
+        send_frame(conn, struct.pack("!H", 1000), opcode=0x8)
+        recv_frame(conn)
+
+    server = WsServer(script, tls=True)
+    # The default trust store does not know the test certificate
+    monkeypatch.delenv("SSL_CERT_FILE", raising=False)
+    client = mm.FeedClient(server.url, ["orderbook.50.BTCUSDT"], reconnect=False)
+    client.start()
+    client.stop()
+    events = client.poll()
+    assert [e.kind for e in events] == ["error", "disconnected"]
+    assert "certificate verify failed" in events[0].message
+
+    monkeypatch.setenv("SSL_CERT_FILE", TLS_CERT)
+    for url in (server.url, server.url.replace("localhost", "127.0.0.1")):
+        client = mm.FeedClient(url, ["orderbook.50.BTCUSDT"], reconnect=False)
+        client.start()
+        events = poll_until(client, "disconnected")
+        client.stop()
+        assert [e.kind for e in events][:3] == ["connected", "book", "synced"]
+        assert client.book("BTCUSDT").best_bid == (100.0, 1.0)
+    server.close()
+    assert server.received == [{"op": "subscribe", "args": ["orderbook.50.BTCUSDT"]}] * 2
+
+
+def test_feed_client_resyncs_on_gap_and_reconnects():
+    def script(server, conn):
+        server.received.append(json.loads(recv_frame(conn)[1]))
+        if server.connections == 1:
+            send_frame(conn, book_msg("snapshot", 10, [["100.0", "1.0"]], [["101.0", "1.0"]]))
+            send_frame(conn, book_msg("delta", 11, [["100.0", "2.0"]], []))
+            send_frame(conn, book_msg("delta", 13, [["100.0", "3.0"]], []))  # 12 is missing
+            # The gap triggers a resubscribe, answered with a fresh snapshot
+            server.received.append(json.loads(recv_frame(conn)[1]))
+            server.received.append(json.loads(recv_frame(conn)[1]))
+            send_frame(conn, book_msg("delta", 14, [["99.0", "5.0"]], []))  # buffered
+            send_frame(conn, book_msg("snapshot", 13, [["100.0", "3.0"]], [["101.0", "1.0"]]))
+            conn.close()  # drop the connection without a close frame
+        else:
+            send_frame(conn, book_msg("snapshot", 20, [["100.5", "1.0"]], [["101.0", "1.0"]]))
+            while True:
+                recv_frame(conn)
+
+    server = WsServer(script)
+    client = mm.FeedClient(server.url, ["orderbook.50.BTCUSDT"], reconnect_delay_ms=10)
+    calls = []
+
+    class Listener:
+        def on_connected(self):
+            calls.append("connected")
+
+        def on_disconnected(self):
+            calls.append("disconnected")
+
+        def on_desynced(self, symbol):
+            calls.append(("desynced", symbol, client.book(symbol).needs_resync))
+
+    def on_synced(symbol):
+        book = client.book(symbol)
+        calls.append(("synced", book.last_update_id, book.depth(5)[0]))
+
+    client.set_callbacks(Listener(), on_synced=on_synced)
+    book = client.book("BTCUSDT")
+    client.start()
+    events = poll_until(client, "synced", count=3)
+    client.stop()
+    server.close()
+
+    topic = ["orderbook.50.BTCUSDT"]
+    assert server.received == [
+        {"op": "subscribe", "args": topic},
+        {"op": "unsubscribe", "args": topic},
+        {"op": "subscribe", "args": topic},
+        {"op": "subscribe", "args": topic},  # replayed after the reconnect
+    ]
+    states = [(e.kind, e.update_id, e.applied) for e in events]
+    assert states == [
+        ("connected", None, False),
+        ("book", 10, True), ("synced", None, False),
+        ("book", 11, True),
+        ("book", 13, False), ("desynced", None, False),
+        ("book", 14, False),
+        ("book", 13, True), ("synced", None, False),
+        ("error", None, False), ("disconnected", None, False), ("desynced", None, False),
+        ("connected", None, False),
+        ("book", 20, True), ("synced", None, False),
+    ]
+    assert calls == [
+        "connected", ("synced", 10, [(100.0, 1.0)]), ("desynced", "BTCUSDT", True),
+        # The delta buffered while desynced is replayed on top of the snapshot
+        ("synced", 14, [(100.0, 3.0), (99.0, 5.0)]),
+        "disconnected", ("desynced", "BTCUSDT", True), "connected", ("synced", 20, [(100.5, 1.0)]),
+        "disconnected", ("desynced", "BTCUSDT", True),  # stop()
+    ]
+    # Stopping leaves the last book in place but marks it desynced
+    assert book.best_bid == (100.5, 1.0) and book.needs_resync
+    assert client.reconnects >= 1 and book.gap_count == 1
+
+
+def test_feed_client_into_event_queue():
+    def script(server, conn):
+        recv_frame(conn)
+        send_frame(conn, book_msg("snapshot", 10, [["100.0", "1.0"]], [["101.0", "1.0"]]))
+        send_frame(conn, book_msg("delta", 11, [["100.0", "2.0"]], []))
+        send_frame(conn, struct.pack("!H", 1000), opcode=0x8)
+        recv_frame(conn)
+
+    server = WsServer(script)
+    queue = mm.EventQueue(capacity=64)
+    client = mm.FeedClient(server.url, ["orderbook.50.BTCUSDT"], reconnect=False, queue=queue)
+    with pytest.raises(ValueError):
+        mm.FeedClient(server.url, ["orderbook.50.BTCUSDT"], queue=queue)  # one producer
+    with pytest.raises(ValueError):
+        queue.push(mm.FeedEvent("error", message="x"))
+    client.start()
+    events = []
+    while not events or events[-1].kind != "desynced":
+        e = queue.pop(timeout_ms=5000)
+        assert e is not None, events
+        events.append(e)
+    with pytest.raises(ValueError):
+        client.poll()
+    client.stop()
+    server.close()
+
+    assert [(e.kind, e.update_id) for e in events] == [
+        ("connected", None), ("book", 10), ("synced", None), ("book", 11),
+        ("disconnected", None), ("desynced", None),
+    ]
+    assert queue.dropped == 0 and client.book("BTCUSDT").best_bid == (100.0, 2.0)
+    # The producer role is released with the client
+    del client
+    assert queue.push(mm.FeedEvent("error", message="x"))
+
+
+def test_feed_client_next_event_with_asyncio():
+    import asyncio
+
+    def script(server, conn):
+        recv_frame(conn)
+        send_frame(conn, book_msg("snapshot", 10, [["100.0", "1.0"]], [["101.0", "1.0"]]))
+        while True:
+            recv_frame(conn)
+
+    server = WsServer(script)
+    client = mm.FeedClient(server.url, ["orderbook.50.BTCUSDT"], reconnect=False)
+
+    async def main():
+        client.start()
+        kinds = []
+        while "synced" not in kinds:
+            kinds.append((await asyncio.wait_for(client.next_event(), 5)).kind)
+        return kinds
+
+    assert asyncio.run(main()) == ["connected", "book", "synced"]
+    client.stop()
+    server.close()
+    with pytest.raises(ValueError):
+        mm.FeedClient(server.url, ["orderbook.50.BTCUSDT"], queue=mm.EventQueue()).next_event()
+
+
+def test_private_stream_routes_fills_into_orders_and_position():
+    import hashlib
+    import hmac
+
+    def private_msg(topic, *data):
+        return json.dumps({"topic": topic, "creationTime": 5000, "data": list(data)})
+
+    def script(server, conn):
+        auth = json.loads(recv_frame(conn)[1])
+        server.received.append(auth)
+        key, expires, signature = auth["args"]
+        good = hmac.new(b"s3cret", b"GET/realtime%d" % expires, hashlib.sha256).hexdigest() == signature
+        send_frame(conn, json.dumps({"success": good, "ret_msg": "" if good else "bad sig", "op": "auth"}))
+        if not good:
+            return
+        server.received.append(json.loads(recv_frame(conn)[1]))
+        order = {"symbol": "BTCUSDT", "orderId": "ex-7", "orderLinkId": "q1", "side": "Buy",
+                 "price": "100.5", "qty": "2", "updatedTime": "5001"}
+        send_frame(conn, private_msg("order", dict(order, orderStatus="New"),
+                                     dict(order, orderLinkId="other", orderStatus="New")))
+        fill = {"symbol": "BTCUSDT", "orderId": "ex-7", "orderLinkId": "q1", "side": "Buy",
+                "execType": "Trade", "execPrice": "100.5", "execFee": "-0.01", "isMaker": True}
+        send_frame(conn, private_msg("execution", dict(fill, execQty="0.5", execTime="5002"),
+                                     dict(fill, execType="Funding", execQty="9", execTime="5003")))
+        send_frame(conn, private_msg("execution", dict(fill, execQty="1.5", execTime="5004")))
+        send_frame(conn, private_msg("position", {"symbol": "BTCUSDT", "side": "Buy", "size": "2",
+                                                  "avgPrice": "100.5", "updatedTime": "5005"}))
+        send_frame(conn, private_msg("order", dict(order, orderStatus="Cancelled")))  # too late
+        send_frame(conn, struct.pack("!H", 1000), opcode=0x8)
+        recv_frame(conn)
+
+    server = WsServer(script)
+    topics = ["order", "execution", "position"]
+    with pytest.raises(ValueError):
+        mm.FeedClient(server.url, topics)  # no credentials
+    with pytest.raises(ValueError):
+        mm.FeedClient(server.url, topics + ["orderbook.50.BTCUSDT"], api_key="k", api_secret="s3cret")
+    # Credentials only go over TLS or to this machine
+    with pytest.raises(ValueError, match="wss"):
+        mm.FeedClient("ws://stream.bybit.com/v5/private", topics, api_key="k", api_secret="s3cret")
+    if mm.HAS_TLS:
+        mm.FeedClient("wss://stream.bybit.com/v5/private", topics, api_key="k", api_secret="s3cret")
+    orders, position = mm.OrderManager(), mm.Position()
+    orders.submit("q1", "BTCUSDT", "buy", 100.5, 2.0, 4000)
+    client = mm.FeedClient(server.url, topics, api_key="key1", api_secret="s3cret", reconnect=False)
+    client.set_routing(orders=orders, positions={"BTCUSDT": position})
+    client.start()
+    events = poll_until(client, "disconnected")
+    client.stop()
+    server.close()
+
+    assert server.received[0]["op"] == "auth" and server.received[0]["args"][0] == "key1"
+    assert server.received[1] == {"op": "subscribe", "args": topics}
+    kinds = [e.kind for e in events]
+    assert kinds == ["connected", "order", "order", "fill", "fill", "position", "order", "error", "disconnected"]
+    ack, fill = events[1], events[3]
+    assert (ack.client_id, ack.exchange_id, ack.status, ack.side, ack.price, ack.size) == ("q1", "ex-7", "New", "buy", 100.5, 2.0)
+    assert (fill.client_id, fill.ts, fill.size, fill.fee, fill.liquidity) == ("q1", 5002, 0.5, -0.01, "maker")
+    assert (events[5].side, events[5].size, events[5].price) == ("buy", 2.0, 100.5)
+    assert "routing failed" in events[7].message  # cancel after the order filled
+    update, normalized_fill = ack.normalize(), fill.normalize()
+    assert isinstance(update, mm.OrderUpdate) and isinstance(normalized_fill, mm.Fill)
+    assert (update.status, update.venue_status, update.client_id, update.qty) == ("new", "New", "q1", 2.0)
+    assert events[6].normalize().status == "canceled" and events[6].normalize().is_final
+    assert (normalized_fill.exchange, normalized_fill.side, normalized_fill.qty, normalized_fill.fee,
+            normalized_fill.liquidity) == ("bybit", "buy", 0.5, -0.01, "maker")
+    assert events[5].normalize() is None
+    with pytest.raises(ValueError):
+        mm.FeedEvent("trade", symbol="BTCUSDT", price=1.0, size=1.0).normalize()  # no exchange
+
+    info = orders.order("q1")
+    assert (info.state, info.exchange_id, info.filled) == ("FILLED", "ex-7", 2.0)
+    assert "other" not in orders  # unknown orders are not routed
+    assert position.net_qty == 2.0 and position.fees == pytest.approx(-0.02)
+
+
+def test_private_stream_auth_failure_ends_session():
+    def script(server, conn):
+        recv_frame(conn)
+        send_frame(conn, json.dumps({"success": False, "ret_msg": "invalid key", "op": "auth"}))
+        while True:
+            recv_frame(conn)
+
+    server = WsServer(script)
+    client = mm.FeedClient(server.url, ["execution"], api_key="k", api_secret="x", reconnect=False)
+    client.start()
+    events = poll_until(client, "disconnected")
+    client.stop()
+    server.close()
+    assert [e.kind for e in events] == ["connected", "error", "disconnected"]
+    assert events[1].message == "auth failed: invalid key"
@@ -0,0 +1,163 @@
+"""
+Unit tests for the mm_orderbook FIX 4.4 codecs.
+"""
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+SOH = b"\x01"
+
+
+def fix_checksum(data):
+    return b"10=%03d\x01" % (sum(data) % 256)
+
+
+def raw_message(*fields):
+    body = b"".join(b"%d=%s\x01" % (t, v.encode() if isinstance(v, str) else v) for t, v in fields)
+    head = b"8=FIX.4.4\x019=%d\x01" % len(body)
+    return head + body + fix_checksum(head + body)
+
+
+def test_new_order_single_and_cancel_encoding():
+    codec = mm.FixCodec("MM", "VENUE")
+    wire = codec.new_order_single(7, "c1", "BTC-USD", "buy", 0.5, price=100.25, post_only=True,
+                                  account="acct", sending_time_ms=1700000000123)
+    head, _, rest = wire.partition(b"\x0135=")
+    body_len = int(head.split(SOH)[1][2:])
+    assert head.startswith(b"8=FIX.4.4\x019=") and len(b"35=" + rest) - 7 == body_len
+    assert wire.endswith(fix_checksum(wire[:-7]))
+
+    msg = codec.decode(wire)
+    assert (msg.msg_type, msg.seq_num, msg.sender_comp_id, msg.target_comp_id) == ("D", 7, "MM", "VENUE")
+    assert msg.sending_time == "20231114-22:13:20.123" and not msg.poss_dup
+    assert [msg.get(t) for t in (1, 11, 21, 55, 54, 38, 40, 44, 59, 18)] == [
+        "acct", "c1", "1", "BTC-USD", "1", "0.5", "2", "100.25", "1", "6"]
+    assert msg.execution_report() is None
+
+    cancel = codec.decode(codec.order_cancel_request(8, "c2", "c1", "BTC-USD", "sell",
+                                                     order_id="o1", sending_time_ms=0))
+    assert cancel.msg_type == "F" and cancel.sending_time == "19700101-00:00:00.000"
+    assert [cancel.get(t) for t in (41, 37, 11, 54, 38)] == ["c1", "o1", "c2", "2", None]
+
+    with pytest.raises(ValueError):
+        codec.new_order_single(1, "c3", "BTC-USD", "buy", 1.0)   # limit without price
+    with pytest.raises(ValueError):
+        codec.new_order_single(1, "c\x01", "BTC-USD", "buy", 1.0, ord_type="market")
+    with pytest.raises(ValueError):
+        codec.new_order_single(1, "c3", "BTC-USD", "buy", 1.0, 5.0, time_in_force="gtd")
+
+
+def test_execution_report_round_trip():
+    codec = mm.FixCodec("VENUE", "MM")
+    wire = codec.execution_report(3, "o1", "e1", "trade", "partially_filled", "BTC-USD", "buy",
+                                  leaves_qty=0.3, cum_qty=0.2, avg_px=100.0, cl_ord_id="c1",
+                                  price=100.5, order_qty=0.5, last_px=100.0, last_qty=0.2)
+    er = codec.decode(wire).execution_report()
+    assert (er.order_id, er.cl_ord_id, er.exec_id, er.symbol, er.side) == ("o1", "c1", "e1", "BTC-USD", "buy")
+    assert (er.exec_type, er.ord_status) == ("trade", "partially_filled")
+    assert (er.price, er.order_qty, er.last_px, er.last_qty) == (100.5, 0.5, 100.0, 0.2)
+    assert (er.leaves_qty, er.cum_qty, er.avg_px, er.text) == (0.3, 0.2, 100.0, None)
+
+    # Venue-built message with codes the encoder does not know
+    msg = codec.decode(raw_message((35, "8"), (49, "V"), (56, "MM"), (34, "9"), (43, "Y"),
+                                   (37, "o2"), (17, "e2"), (150, "Z"), (39, "8"), (54, "2"),
+                                   (58, "post only would cross")))
+    er = msg.execution_report()
+    assert msg.poss_dup and (er.exec_type, er.ord_status, er.side) == ("Z", "rejected", "sell")
+    assert er.text == "post only would cross"
+    with pytest.raises(ValueError):
+        codec.execution_report(1, "o", "e", "bogus", "new", "X", "buy", 0.0, 0.0)
+
+
+def test_market_data_snapshot_and_incremental_apply_to_book():
+    codec = mm.FixCodec("VENUE", "MM")
+    book = mm.L2Book()
+    snap = codec.decode(codec.market_data_snapshot(1, "BTC-USD", [(100.0, 1.0), (99.5, 2.0)],
+                                                   [(100.5, 3.0)], md_req_id="r1"))
+    assert snap.msg_type == "W" and snap.symbol == "BTC-USD" and snap.get(262) == "r1"
+    assert [(e.entry_type, e.price, e.size, e.action) for e in snap.entries()] == [
+        ("bid", 100.0, 1.0, None), ("bid", 99.5, 2.0, None), ("offer", 100.5, 3.0, None)]
+    assert codec.apply(book, snap)
+    assert book.depth(5) == ([(100.0, 1.0), (99.5, 2.0)], [(100.5, 3.0)])
+
+    inc = codec.decode(codec.market_data_incremental(2, "BTC-USD", [
+        ("delete", "bid", 100.0, 0.0), ("new", "offer", 100.25, 4.0), ("change", "bid", 99.5, 1.5),
+        ("new", "trade", 100.5, 0.1)]))
+    entries = inc.entries()
+    assert [(e.action, e.entry_type, e.symbol) for e in entries] == [
+        ("delete", "bid", "BTC-USD"), ("new", "offer", "BTC-USD"), ("change", "bid", "BTC-USD"),
+        ("new", "trade", "BTC-USD")]
+    assert entries[0].size is None
+    assert not codec.apply(book, inc, symbol="ETH-USD")   # nothing for that symbol
+    assert codec.apply(book, inc, symbol="BTC-USD")
+    assert book.depth(5) == ([(99.5, 1.5)], [(100.25, 4.0), (100.5, 3.0)])
+
+    with pytest.raises(ValueError):
+        codec.apply(book, codec.decode(codec.order_cancel_request(3, "a", "b", "BTC-USD", "buy")))
+
+
+def test_normalize_market_data_and_executions():
+    codec = mm.FixCodec("VENUE", "MM")
+    snap = codec.decode(codec.market_data_snapshot(1, "BTC-USD", [(100.0, 1.0)], [(100.5, 3.0)],
+                                                   sending_time_ms=1700000000123))
+    [update] = snap.normalize()
+    assert isinstance(update, mm.BookUpdate) and update.is_snapshot and not update.applied
+    assert (update.exchange, update.symbol, update.ts) == ("VENUE", "BTC-USD", 1700000000123)
+    assert (update.bids, update.asks) == ([(100.0, 1.0)], [(100.5, 3.0)])
+
+    inc = codec.decode(codec.market_data_incremental(2, "BTC-USD", [
+        ("delete", "bid", 100.0, 0.0), ("new", "offer", 100.25, 4.0), ("new", "trade", 100.5, 0.1)]))
+    update, trade = inc.normalize(exchange="cme")
+    assert (update.exchange, update.kind, update.bids, update.asks) == ("cme", "delta", [(100.0, 0.0)], [(100.25, 4.0)])
+    assert isinstance(trade, mm.Trade)
+    assert (trade.symbol, trade.price, trade.size, trade.side) == ("BTC-USD", 100.5, 0.1, None)
+
+    ack = codec.decode(codec.execution_report(3, "o1", "e1", "new", "new", "BTC-USD", "sell",
+                                              leaves_qty=0.5, cum_qty=0.0, cl_ord_id="c1", price=101.0,
+                                              order_qty=0.5, sending_time_ms=1700000000000))
+    [order] = ack.normalize()
+    assert isinstance(order, mm.OrderUpdate) and not order.is_final
+    assert (order.status, order.venue_status, order.side, order.price, order.ts) == (
+        "new", "0", "sell", 101.0, 1700000000000)
+    fill = codec.decode(codec.execution_report(4, "o1", "e2", "trade", "filled", "BTC-USD", "sell",
+                                               leaves_qty=0.0, cum_qty=0.5, cl_ord_id="c1",
+                                               last_px=101.0, last_qty=0.5))
+    order, execution = fill.normalize()
+    assert order.status == "filled" and order.is_final and order.filled_qty == 0.5
+    assert isinstance(execution, mm.Fill)
+    assert (execution.client_id, execution.exchange_id, execution.trade_id) == ("c1", "o1", "e2")
+    assert (execution.side, execution.price, execution.qty, execution.fee) == ("sell", 101.0, 0.5, None)
+    assert codec.decode(codec.order_cancel_request(5, "c2", "c1", "BTC-USD", "sell")).normalize() == []
+
+    raw = raw_message((35, "8"), (49, "VENUE"), (56, "MM"), (34, "6"), (37, "o2"), (11, "c2"), (17, "e9"),
+                      (150, "F"), (39, "1"), (55, "ETH-USD"), (54, "1"), (31, "10.5"), (32, "2"),
+                      (12, "-0.02"), (851, "1"), (60, "20231114-22:13:20.5"))
+    order, execution = codec.decode(raw).normalize()
+    assert (order.status, order.ts) == ("partially_filled", 1700000000500)
+    assert (execution.side, execution.fee, execution.liquidity) == ("buy", -0.02, "maker")
+
+
+def test_stream_feed_frames_partial_and_corrupt_messages():
+    codec = mm.FixCodec("VENUE", "MM")
+    a = codec.market_data_snapshot(1, "BTC-USD", [(1.0, 1.0)], [])
+    b = codec.market_data_snapshot(2, "BTC-USD", [(2.0, 1.0)], [])
+    corrupt = bytearray(codec.market_data_snapshot(3, "BTC-USD", [(3.0, 1.0)], []))
+    corrupt[-5] = ord("9") if corrupt[-5] != ord("9") else ord("8")   # break the checksum
+    c = codec.market_data_snapshot(4, "BTC-USD", [(4.0, 1.0)], [])
+
+    stream = a + b + bytes(corrupt) + c
+    out = codec.feed(stream[:10])
+    assert out == [] and codec.buffered == 10
+    out = codec.feed(stream[10:len(a) + 5])
+    assert [m.seq_num for m in out] == [1]
+    out = codec.feed(stream[len(a) + 5:])
+    assert [m.seq_num for m in out] == [2, 4]
+    assert codec.errors == 1 and "CheckSum" in codec.last_error and codec.buffered == 0
+
+    with pytest.raises(ValueError):
+        codec.decode(a[:-1])
+    with pytest.raises(ValueError):
+        codec.decode(bytes(corrupt))
+    codec.reset()
+    assert codec.errors == 0 and codec.last_error is None
@@ -0,0 +1,236 @@
+"""
+Unit tests for the zstd-compressed binary market-data journal in mm_orderbook.
+"""
+
+import shutil
+import subprocess
+import time
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def write_sample(path, **kwargs):
+    w = mm.JournalWriter(str(path), **kwargs)
+    w.write_snapshot("BTC", 0, [(100.0, 1.0), (99.0, 2.0)], [(101.0, 1.5)], update_id=7)
+    w.write_delta("BTC", 10, [(100.0, 0.0)], [(101.5, 3.0)], update_id=8)
+    w.write_trade("BTC", 20, 100.5, 0.3, "buy")
+    w.write_trade("ETH", 25, 10.0, 1.0, "ask")
+    w.write_delta("ETH", 30, [(9.9, 4.0)], [])
+    w.close()
+    return w
+
+
+def test_journal_roundtrip(tmp_path):
+    w = write_sample(tmp_path / "md.zst")
+    assert w.records == 5
+
+    recs = list(mm.JournalReader(str(tmp_path / "md.zst")))
+    assert [(r.kind, r.symbol, r.ts) for r in recs] == [
+        ("snapshot", "BTC", 0),
+        ("delta", "BTC", 10),
+        ("trade", "BTC", 20),
+        ("trade", "ETH", 25),
+        ("delta", "ETH", 30),
+    ]
+    assert recs[0].bids == [(100.0, 1.0), (99.0, 2.0)]
+    assert recs[0].asks == [(101.0, 1.5)]
+    assert recs[0].update_id == 7
+    assert recs[4].update_id is None
+    assert (recs[2].price, recs[2].qty, recs[2].side) == (100.5, 0.3, "buy")
+    assert recs[3].side == "sell"
+    assert recs[0].price is None
+
+
+def test_journal_chunks_append_and_compress(tmp_path):
+    path = tmp_path / "md.zst"
+    # Tiny chunks: every record becomes its own frame
+    write_sample(path, chunk_bytes=1)
+    write_sample(path)
+    assert len(list(mm.JournalReader(str(path)))) == 10
+
+    big = tmp_path / "big.zst"
+    w = mm.JournalWriter(str(big))
+    levels = [(100.0 - i * 0.5, 1.0 + i) for i in range(20)]
+    for ts in range(2000):
+        w.write_delta("BTC", ts, levels, levels, update_id=ts)
+    w.close()
+    raw = 2000 * (32 + 40 * 16)
+    assert big.stat().st_size < raw / 10
+    assert sum(1 for _ in mm.JournalReader(str(big))) == 2000
+
+
+def test_journal_is_a_standard_zstd_stream(tmp_path):
+    zstd = shutil.which("zstd")
+    if zstd is None:
+        pytest.skip("zstd CLI not installed")
+    write_sample(tmp_path / "md.zst")
+    out = subprocess.run([zstd, "-dc", str(tmp_path / "md.zst")], capture_output=True, check=True)
+    # 5 record headers, 6 book levels, 2 trade prints, plus the "BTC" and
+    # "ETH" definitions padded to 8 bytes
+    assert len(out.stdout) == 5 * 32 + 6 * 16 + 2 * 16 + 2 * (32 + 8)
+
+
+def test_journal_replay_into_books_and_tapes(tmp_path):
+    write_sample(tmp_path / "md.zst")
+    reader = mm.JournalReader(str(tmp_path / "md.zst"))
+    books = {"BTC": mm.L2Book()}
+    tapes = {"BTC": mm.TradeTape(), "ETH": mm.TradeTape()}
+    seen = []
+    n = reader.replay(books=books, tapes=tapes, callback=lambda r: seen.append(r.kind))
+    assert n == 5
+    assert seen == ["snapshot", "delta", "trade", "trade", "delta"]
+    assert books["BTC"].bids(5) == [(99.0, 2.0)]
+    assert books["BTC"].asks(5) == [(101.0, 1.5), (101.5, 3.0)]
+    assert books["BTC"].last_update_id == 8
+    assert tapes["BTC"].buy_volume() == pytest.approx(0.3)
+    assert len(tapes["ETH"]) == 1
+
+    # Exhausted until rewound
+    assert reader.replay() == 0
+    reader.rewind()
+    assert reader.replay() == 5
+
+
+def test_journal_replay_checks_sequence_and_crosses(tmp_path):
+    w = mm.JournalWriter(str(tmp_path / "md.zst"))
+    w.write_snapshot("BTC", 0, [(100.0, 2.0)], [(100.5, 1.5), (101.0, 0.8)], update_id=10)
+    w.write_delta("BTC", 1, [(100.6, 1.0)], [], update_id=11)   # crosses the stale ask
+    w.write_delta("BTC", 2, [(99.0, 1.0)], [], update_id=11)    # stale
+    w.write_delta("BTC", 3, [(98.0, 1.0)], [], update_id=13)    # 12 is missing
+    w.write_delta("BTC", 4, [(97.0, 1.0)], [], update_id=14)
+    w.close()
+
+    book = mm.L2Book(cross_policy="drop_older_side")
+    assert mm.JournalReader(str(tmp_path / "md.zst")).replay(books={"BTC": book}) == 5
+    assert book.bids(5) == [(100.6, 1.0), (100.0, 2.0)]
+    assert book.asks(5) == [(101.0, 0.8)]
+    assert book.last_update_id == 11
+    assert book.needs_resync and book.gap_count == 1
+
+    with pytest.raises(mm.SequenceGapError):
+        mm.JournalReader(str(tmp_path / "md.zst")).replay(books={"BTC": mm.L2Book(raise_on_gap=True)})
+    with pytest.raises(mm.CrossedBookError):
+        mm.JournalReader(str(tmp_path / "md.zst")).replay(books={"BTC": mm.L2Book(cross_policy="raise")})
+
+
+def test_journal_replay_time_scaled(tmp_path):
+    w = mm.JournalWriter(str(tmp_path / "md.zst"))
+    w.write_trade("BTC", 0, 1.0, 1.0, "buy")
+    w.write_trade("BTC", 200, 1.0, 1.0, "buy")
+    w.close()
+    reader = mm.JournalReader(str(tmp_path / "md.zst"))
+    start = time.monotonic()
+    assert reader.replay(speed=2.0) == 2
+    assert time.monotonic() - start >= 0.09
+
+    with pytest.raises(ValueError):
+        reader.replay(speed=0.0)
+
+
+def test_journal_rejects_corrupt_files(tmp_path):
+    path = tmp_path / "bad.zst"
+    path.write_bytes(b"not a journal")
+    with pytest.raises(ValueError):
+        list(mm.JournalReader(str(path)))
+
+
+def test_journal_to_arrow_selects_columns_and_filters_in_rust(tmp_path):
+    write_sample(tmp_path / "md.zst")
+    reader = mm.JournalReader(str(tmp_path / "md.zst"))
+    batch = reader.to_arrow(columns=["ts", "price", "symbol"], start_ts=10, end_ts=30)
+    assert batch.column_names == ["ts", "price", "symbol"]
+    assert batch.to_pydict() == {
+        "ts": [10, 10, 20, 25],
+        "price": [100.0, 101.5, 100.5, 10.0],
+        "symbol": ["BTC", "BTC", "BTC", "ETH"],
+    }
+    eth = reader.to_arrow(symbols=["ETH"], columns=["seq", "kind", "side", "update_id"])
+    assert eth.to_pydict() == {"seq": [3, 4], "kind": ["trade", "delta"], "side": ["sell", "bid"],
+                               "update_id": [None, None]}
+    assert reader.to_arrow(start_ts=100).num_rows == 0
+
+    with pytest.raises(ValueError):
+        reader.to_arrow(columns=["ts", "bogus"])
+    with pytest.raises(ValueError):
+        reader.to_arrow(columns=["ts", "ts"])
+
+
+def test_read_journal_to_polars(tmp_path):
+    pl = pytest.importorskip("polars")
+    write_sample(tmp_path / "md.zst")
+    df = mm.read_journal_to_polars(str(tmp_path / "md.zst"), columns=["ts", "symbol", "size"],
+                                   symbols=["BTC"], end_ts=20)
+    assert isinstance(df, pl.DataFrame) and df.columns == ["ts", "symbol", "size"]
+    assert df["size"].to_list() == [1.0, 2.0, 1.5, 0.0, 3.0]
+
+
+def write_hours(path, hours=6, per_hour=50, **kwargs):
+    w = mm.JournalWriter(str(path), chunk_bytes=4096, **kwargs)
+    for i in range(hours * per_hour):
+        ts = i * 3_600_000 // per_hour
+        w.write_delta("BTC", ts, [(100.0, float(i))], [], update_id=i + 1)
+    w.close()
+
+
+def test_journal_index_seek_skips_to_time(tmp_path):
+    path = tmp_path / "day.zst"
+    write_hours(path)
+    assert (tmp_path / "day.zst.idx").exists()
+    reader = mm.JournalReader(str(path))
+    cps = reader.checkpoints
+    assert len(cps) > 3 and cps[0][0] == 0 and cps[-1][1] == 299 * 72_000
+    assert all(a[1] <= b[0] for a, b in zip(cps, cps[1:]))
+
+    assert reader.seek(2 * 3_600_000)
+    rec = next(reader)
+    assert (rec.ts, rec.update_id) == (7_200_000, 101)
+    assert next(reader).ts == 7_272_000
+    assert reader.seek(2 * 3_600_000 + 1) and next(reader).update_id == 102
+    assert not reader.seek(10 ** 12)
+    assert next(reader, None) is None
+    reader.rewind()
+    assert next(reader).update_id == 1
+
+    window = reader.to_arrow(start_ts=7_200_000, end_ts=14_400_000, columns=["ts", "seq", "update_id"])
+    assert window.num_rows == 100
+    assert window.column("seq")[0] == 100 and window.column("update_id")[-1] == 200
+
+
+def test_journal_without_index_or_with_stale_index(tmp_path):
+    path = tmp_path / "day.zst"
+    write_hours(path, hours=2)
+    (tmp_path / "day.zst.idx").unlink()
+    write_hours(path, hours=1)              # appended; its index no longer starts at byte 0
+    reader = mm.JournalReader(str(path))
+    assert reader.checkpoints == []
+    assert reader.seek(3_600_000) and next(reader).update_id == 51
+    assert reader.to_arrow(start_ts=3_600_000, columns=["seq"]).column("seq")[0] == 50
+    assert reader.to_arrow().num_rows == 150
+
+    n = reader.build_index()
+    assert n > 0 and len(mm.JournalReader(str(path)).checkpoints) == n
+    assert reader.seek(3_600_000) and next(reader).update_id == 51
+    assert reader.to_arrow(start_ts=3_600_000, columns=["seq"]).column("seq")[0] == 50
+
+    # A truncated index still serves its valid prefix
+    idx = tmp_path / "day.zst.idx"
+    idx.write_bytes(idx.read_bytes()[:8 + 40 * 2 + 7])
+    partial = mm.JournalReader(str(path))
+    assert len(partial.checkpoints) == 2
+    assert partial.to_arrow().num_rows == 150
+    assert partial.seek(3_600_000) and next(partial).update_id == 51
+
+
+def test_journal_reader_streams_frames_from_the_open_file(tmp_path):
+    path = tmp_path / "day.zst"
+    write_hours(path, hours=1)
+    reader = mm.JournalReader(str(path))
+    write_hours(path, hours=1)              # frames appended after opening are read too
+    assert sum(1 for _ in reader) == 100
+
+    # A frame cut short mid-write is reported, not silently dropped
+    path.write_bytes(path.read_bytes()[:-3])
+    with pytest.raises(ValueError):
+        list(mm.JournalReader(str(path)))
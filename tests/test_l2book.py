@@ -79,3 +79,966 @@ def test_mid_microprice_imbalance_values():
     # imbalance = 0.7 / 5.3 ≈ 0.1320754717
     imb = book.imbalance(5)
     assert approx_equal(imb, (3.0 - 2.3) / (3.0 + 2.3))
+
+
+def test_depth_top_n_levels():
+    book = make_book_with_snapshot()
+
+    assert book.bids(1) == [(100.0, 2.0)]
+    assert book.asks(5) == [(100.5, 1.5), (101.0, 0.8)]
+
+    bids, asks = book.depth(2)
+    assert bids == [(100.0, 2.0), (99.5, 1.0)]
+    assert asks == [(100.5, 1.5), (101.0, 0.8)]
+
+    assert book.depth(0) == ([], [])
+
+
+def test_sequence_gap_flags_resync():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 2.0)], [(100.5, 1.5)], update_id=10)
+
+    assert book.apply_delta([(100.1, 1.0)], [], update_id=11) is True
+    # Stale delta is skipped without flagging a gap
+    assert book.apply_delta([(100.2, 1.0)], [], update_id=11) is False
+    assert not book.needs_resync
+
+    # Missing update 12
+    assert book.apply_delta([(100.3, 1.0)], [], update_id=13) is False
+    assert book.needs_resync
+    assert book.gap_count == 1
+    assert book.last_update_id == 11
+
+    # Snapshot clears the resync flag
+    book.apply_snapshot([(100.0, 2.0)], [(100.5, 1.5)], update_id=20)
+    assert not book.needs_resync
+    assert book.apply_delta([], [(100.4, 1.0)], update_id=25, prev_update_id=20) is True
+
+
+def test_sequence_gap_raises_when_configured():
+    book = mm.L2Book(raise_on_gap=True)
+    book.apply_snapshot([(100.0, 2.0)], [(100.5, 1.5)], update_id=1)
+
+    with pytest.raises(mm.SequenceGapError):
+        book.apply_delta([], [], update_id=5, prev_update_id=3)
+
+
+def test_bybit_parser_applies_snapshot_and_delta():
+    book = mm.L2Book()
+    parser = mm.BybitBookParser()
+
+    snapshot = (
+        b'{"topic":"orderbook.50.BTCUSDT","type":"snapshot","ts":1672304484978,'
+        b'"data":{"s":"BTCUSDT","b":[["16493.50","0.006"],["16493.00","0.100"]],'
+        b'"a":[["16611.00","0.029"]],"u":18521288,"seq":7961638724}}'
+    )
+    msg = parser.apply(book, snapshot)
+    assert msg.topic == "orderbook.50.BTCUSDT"
+    assert msg.kind == "snapshot"
+    assert msg.symbol == "BTCUSDT"
+    assert msg.update_id == 18521288
+    assert msg.seq == 7961638724
+    assert book.bids(1) == [(16493.5, 0.006)]
+
+    delta = (
+        '{"topic":"orderbook.50.BTCUSDT","type":"delta","ts":1672304484979,'
+        '"data":{"s":"BTCUSDT","b":[["16493.50","0"]],"a":[],"u":18521289,"seq":7961638725}}'
+    )
+    msg = parser.apply(book, delta)
+    assert msg.applied
+    assert book.bids(1) == [(16493.0, 0.1)]
+    update = msg.normalize()
+    assert (update.exchange, update.symbol, update.kind, update.update_id) == ("bybit", "BTCUSDT", "delta", 18521289)
+    assert update.ts == 1672304484979 and update.applied and update.bids == []
+
+    with pytest.raises(ValueError):
+        parser.apply(book, "{not json")
+
+
+def test_depth_parser_handles_bybit_binance_and_okx_layouts():
+    bybit = mm.DepthParser("bybit")
+    msg = bybit.parse(b'{"topic":"orderbook.1.ETHUSDT","type":"delta","ts":5,'
+                      b'"data":{"s":"ETHUSDT","b":[["10.5","2"]],"a":[],"u":1,"seq":3}}')
+    assert (msg.exchange, msg.kind, msg.symbol, msg.update_id) == ("bybit", "snapshot", "ETHUSDT", 1)
+    assert msg.bids == [(10.5, 2.0)] and not msg.applied
+    update = msg.normalize()
+    assert isinstance(update, mm.BookUpdate) and update.is_snapshot
+    assert (update.exchange, update.symbol, update.update_id, update.ts, update.bids) == (
+        "bybit", "ETHUSDT", 1, 5, [(10.5, 2.0)])
+
+    # Binance: partial depth snapshot from a combined stream, then diffs
+    book = mm.L2Book()
+    binance = mm.DepthParser("binance")
+    msg = binance.apply(book, '{"stream":"btcusdt@depth5","data":{"lastUpdateId":100,'
+                              '"bids":[["99.0","1.0"]],"asks":[["101.0","1.0"]]}}')
+    assert msg.is_snapshot and msg.symbol == "BTCUSDT" and book.last_update_id == 100
+
+    def diff(first, last, bids):
+        return ('{"e":"depthUpdate","E":7,"s":"BTCUSDT","U":%d,"u":%d,"b":%s,"a":[]}'
+                % (first, last, bids))
+
+    assert not binance.apply(book, diff(90, 100, '[["99.0","9"]]')).applied   # stale
+    msg = binance.apply(book, diff(95, 103, '[["99.5","2.0"]]'))   # straddles 101
+    assert msg.applied and (msg.first_update_id, msg.update_id, msg.ts) == (95, 103, 7)
+    assert binance.apply(book, diff(104, 104, '[["99.0","0"]]')).applied
+    assert book.bids(5) == [(99.5, 2.0)]
+    assert not binance.apply(book, diff(110, 111, "[]")).applied and book.needs_resync
+
+    # OKX: snapshot then update chained by prevSeqId
+    book = mm.L2Book()
+    okx = mm.DepthParser("okx")
+    msg = okx.apply(book, '{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"snapshot",'
+                          '"data":[{"asks":[["8476.98","415","0","13"]],"bids":[["8476.97","256","0","12"]],'
+                          '"ts":"1597026383085","checksum":-855196043,"prevSeqId":-1,"seqId":123456}]}')
+    assert (msg.symbol, msg.update_id, msg.prev_update_id) == ("BTC-USDT", 123456, None)
+    assert msg.ts == 1597026383085 and msg.checksum == -855196043
+    update = msg.normalize()
+    assert (update.exchange, update.symbol, update.kind, update.asks) == ("okx", "BTC-USDT", "snapshot", [(8476.98, 415.0)])
+    update = ('{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"update",'
+              '"data":[{"asks":[["8476.98","100","0","3"]],"bids":[],"ts":"1597026383086",'
+              '"checksum":1,"prevSeqId":%d,"seqId":%d}]}')
+    assert okx.apply(book, update % (123456, 123460)).applied
+    assert book.best_ask == (8476.98, 100.0)
+    assert not okx.apply(book, update % (123470, 123471)).applied and book.gap_count == 1
+
+    with pytest.raises(ValueError):
+        okx.parse('{"event":"subscribe","arg":{"channel":"books","instId":"BTC-USDT"}}')
+    with pytest.raises(ValueError):
+        binance.parse('{"e":"trade"}')
+    with pytest.raises(ValueError):
+        mm.DepthParser("kraken")
+
+
+def test_parse_depth_message_function():
+    raw = (b'{"topic":"orderbook.50.BTCUSDT","type":"snapshot","ts":5,'
+           b'"data":{"s":"BTCUSDT","b":[["100.0","1"]],"a":[["100.5","2"]],"u":7,"seq":3}}')
+    msg = mm.parse_depth_message(raw, "bybit")
+    assert (msg.exchange, msg.kind, msg.symbol, msg.update_id) == ("bybit", "snapshot", "BTCUSDT", 7)
+    assert msg.bids == [(100.0, 1.0)] and msg.asks == [(100.5, 2.0)] and not msg.applied
+
+    book = mm.L2Book()
+    assert mm.parse_depth_message(raw, book=book).applied
+    assert book.best_bid == (100.0, 1.0) and book.last_update_id == 7
+    msg = mm.parse_depth_message('{"e":"depthUpdate","E":7,"s":"BTCUSDT","U":1,"u":2,"b":[],"a":[]}', "binance")
+    assert (msg.exchange, msg.first_update_id, msg.update_id) == ("binance", 1, 2)
+    with pytest.raises(ValueError):
+        mm.parse_depth_message(raw, "kraken")
+    with pytest.raises(ValueError):
+        mm.parse_depth_message(b'{"op":"subscribe","success":true}', "bybit")
+
+
+def test_deeply_nested_frames_raise_instead_of_overflowing_the_stack():
+    parser = mm.DepthParser("bybit")
+    for frame in ("[" * 1_000_000, '{"a":' * 200_000, "[" * 129 + "]" * 129):
+        with pytest.raises(ValueError, match="nesting too deep"):
+            parser.parse(frame)
+    with pytest.raises(ValueError, match="nesting too deep"):
+        mm.parse_depth_message(b"[" * 1_000_000, "binance")
+    # Depth within the cap is still only a message-shape error
+    with pytest.raises(ValueError) as err:
+        parser.parse("[" * 128 + "]" * 128)
+    assert "nesting" not in str(err.value)
+
+
+def test_binance_sync_stitches_snapshot_and_buffered_diffs():
+    sync = mm.BinanceBookSync()
+    sync.buffer_diff(95, 99, [(98.0, 1.0)], [])
+    sync.buffer_diff(100, 105, [(99.0, 1.0)], [])
+    sync.buffer_diff(106, 110, [], [(101.5, 2.0)])
+    assert not sync.is_synced
+    assert sync.buffered == 3
+
+    # Diff 95..99 predates the snapshot and is dropped
+    assert sync.apply_snapshot(102, [(100.0, 1.0)], [(101.0, 1.0)]) is True
+    assert sync.is_synced
+    assert sync.book.last_update_id == 110
+    assert sync.book.bids(5) == [(100.0, 1.0), (99.0, 1.0)]
+    assert sync.book.asks(5) == [(101.0, 1.0), (101.5, 2.0)]
+
+    # Broken chain drops the sync
+    assert sync.buffer_diff(120, 121, [], []) is False
+    assert not sync.is_synced
+    assert sync.resync_count == 1
+
+
+def test_binance_sync_diffs_follow_the_book_cross_policy():
+    # Replayed and live diffs go through the book's delta path
+    book = mm.L2Book(cross_policy="drop_older_side")
+    sync = mm.BinanceBookSync(book)
+    sync.buffer_diff(100, 101, [(101.0, 2.0)], [])   # crosses the snapshot ask
+    assert sync.apply_snapshot(100, [(100.0, 1.0)], [(101.0, 1.0), (102.0, 1.0)])
+    assert book.best_bid == (101.0, 2.0) and book.asks(5) == [(102.0, 1.0)]
+    assert sync.buffer_diff(102, 102, [], [(100.5, 1.0)])
+    assert book.bids(5) == [(100.0, 1.0)] and book.best_ask == (100.5, 1.0)
+    assert book.last_update_id == 102 and not book.needs_resync
+
+    strict = mm.BinanceBookSync(mm.L2Book(cross_policy="raise"))
+    assert strict.apply_snapshot(1, [(100.0, 1.0)], [(101.0, 1.0)])
+    with pytest.raises(mm.CrossedBookError):
+        strict.buffer_diff(2, 2, [(101.5, 1.0)], [])
+
+
+def test_binance_sync_first_live_diff_may_straddle_the_snapshot():
+    # Nothing buffered past the snapshot: the first live diff only has to
+    # cover lastUpdateId + 1, later ones continue the chain strictly
+    sync = mm.BinanceBookSync()
+    assert sync.apply_snapshot(105, [(100.0, 1.0)], [(101.0, 1.0)])
+    assert sync.buffer_diff(103, 110, [(100.0, 2.0)], []) is True
+    assert sync.resync_count == 0 and sync.book.last_update_id == 110
+    assert sync.buffer_diff(105, 112, [], []) is False
+    assert sync.resync_count == 1
+
+    futures = mm.BinanceBookSync()
+    assert futures.apply_snapshot(105, [(100.0, 1.0)], [(101.0, 1.0)])
+    assert futures.buffer_diff(103, 110, [], [(101.0, 3.0)], prev_final_update_id=102)
+    assert futures.buffer_diff(111, 113, [], [], prev_final_update_id=110)
+    assert futures.resync_count == 0 and futures.book.last_update_id == 113
+
+    gap = mm.BinanceBookSync()
+    assert gap.apply_snapshot(105, [(100.0, 1.0)], [(101.0, 1.0)])
+    assert gap.buffer_diff(107, 110, [], []) is False
+
+
+def test_okx_and_kraken_checksums():
+    import zlib
+
+    book = mm.L2Book()
+    book.apply_snapshot([(3366.1, 7.0), (3366.0, 6.0)], [(3366.8, 9.0), (3368.0, 8.0), (3372.0, 8.0)])
+    crc = zlib.crc32(b"3366.1:7:3366.8:9:3366:6:3368:8:3372:8")
+    signed = crc - 2**32 if crc >= 2**31 else crc
+    assert book.checksum() == signed
+    assert book.verify_checksum(signed)
+    assert not book.verify_checksum(signed + 1)
+
+    book.apply_snapshot([(0.05005, 0.000005), (0.05004, 0.00000499)], [(0.05006, 0.00000101)])
+    expected = zlib.crc32(b"5006101" + b"5005500" + b"5004499")
+    assert book.checksum("kraken", price_decimals=5, size_decimals=8) == expected
+
+
+def test_vwap_for_qty_walks_opposite_side():
+    book = make_book_with_snapshot()
+
+    avg, worst, filled = book.vwap_for_qty("buy", 2.0)
+    assert approx_equal(avg, (100.5 * 1.5 + 101.0 * 0.5) / 2.0)
+    assert worst == 101.0
+    assert approx_equal(filled, 2.0)
+
+    # Larger than the whole bid side: partial fill
+    avg, worst, filled = book.vwap_for_qty("sell", 5.0)
+    assert approx_equal(filled, 3.0)
+    assert worst == 99.5
+
+    assert book.vwap_for_qty("buy", 0.0) is None
+    with pytest.raises(ValueError):
+        book.vwap_for_qty("up", 1.0)
+
+
+def test_spread_and_spread_bps_references():
+    book = make_book_with_snapshot()
+
+    assert approx_equal(book.spread(), 0.5)
+    assert approx_equal(book.spread_bps(), 0.5 / 100.25 * 1e4)
+    assert approx_equal(book.spread_bps("best_bid"), 50.0)
+    assert approx_equal(book.half_spread_bps("best_bid"), 25.0)
+    assert approx_equal(book.spread_bps("microprice"), 0.5 / book.microprice() * 1e4)
+
+    assert mm.L2Book().spread() is None
+    assert mm.L2Book().spread_bps() is None
+
+
+def test_book_manager_routes_by_symbol():
+    manager = mm.BookManager()
+    manager.apply_snapshot("BTCUSDT", [(100.0, 1.0)], [(101.0, 1.0)])
+    manager.apply_snapshot("ETHUSDT", [(10.0, 1.0)], [(11.0, 1.0)])
+
+    assert manager.apply_delta("BTCUSDT", [(100.5, 1.0)], []) is True
+    assert manager.mids() == {"BTCUSDT": 100.75, "ETHUSDT": 10.5}
+    assert manager.snapshot_all(1)["ETHUSDT"] == ([(10.0, 1.0)], [(11.0, 1.0)])
+    assert manager.symbols() == ["BTCUSDT", "ETHUSDT"]
+    assert "BTCUSDT" in manager and len(manager) == 2
+
+    # get() hands out the managed book itself, not a copy
+    manager.get("BTCUSDT").apply_delta([], [(100.8, 1.0)])
+    assert approx_equal(manager.mids()["BTCUSDT"], 100.65)
+
+    with pytest.raises(KeyError):
+        manager.apply_delta("XRPUSDT", [], [])
+
+
+def test_crossed_book_policies():
+    book = mm.L2Book(cross_policy="drop_older_side")
+    book.apply_snapshot([(100.0, 2.0)], [(100.5, 1.5), (101.0, 0.8)])
+
+    # New bid through the stale best ask removes the crossed ask levels
+    book.apply_delta([(100.6, 1.0)], [])
+    assert not book.is_crossed()
+    assert book.asks(5) == [(101.0, 0.8)]
+
+    book = mm.L2Book(cross_policy="raise")
+    book.apply_snapshot([(100.0, 2.0)], [(100.5, 1.5)])
+    with pytest.raises(mm.CrossedBookError):
+        book.apply_delta([], [(100.0, 1.0)])
+
+    book.cross_policy = "ignore"
+    book.apply_delta([], [(99.0, 1.0)])
+    assert book.is_crossed()
+
+    with pytest.raises(ValueError):
+        mm.L2Book(cross_policy="sometimes")
+
+
+def test_apply_deltas_batch():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 2.0)], [(101.0, 1.0)], update_id=1)
+
+    applied = book.apply_deltas_batch([
+        ([(100.5, 1.0)], [], 2),
+        ([], [(100.8, 1.0)], 3, 2),
+        ([(99.0, 1.0)], [], 3),  # stale, skipped
+        ([], [(101.0, 0.0)]),    # no ids, always applied
+    ])
+    assert applied == 3
+    assert book.last_update_id == 3
+    assert book.depth(5) == ([(100.5, 1.0), (100.0, 2.0)], [(100.8, 1.0)])
+
+
+
+def test_large_book_operations_from_threads():
+    import threading
+
+    # Books this deep are updated and walked with the GIL released
+    bids = [(1000.0 - i, 1.0) for i in range(500)]
+    asks = [(1001.0 + i, 1.0) for i in range(500)]
+    errors = []
+
+    def worker(k):
+        try:
+            book = mm.L2Book()
+            seen = []
+            book.set_callbacks(on_best_change=lambda u: seen.append(book.best_bid))
+            for i in range(20):
+                book.apply_snapshot(bids, asks, update_id=2 * i)
+                update = book.apply_delta(
+                    [(1000.0 - j, 0.0) for j in range(k + 1)] + [(1000.5, 3.0)],
+                    [(1001.0 + j, 2.0) for j in range(100)],
+                    update_id=2 * i + 1,
+                    return_update=True,
+                )
+                assert update.applied and update.best_bid == (1000.5, 3.0)
+                assert len(update.bids_removed) == k + 1
+                top_bids, top_asks = book.depth(1000)
+                assert len(top_bids) == 500 - k and len(top_asks) == 500
+                # 200 across the 2-lot asks, then 100 one-lots up to 1200
+                avg, worst, filled = book.vwap_for_qty("buy", 300.0)
+                assert (avg, worst, filled) == pytest.approx((325150.0 / 300, 1200.0, 300.0))
+                assert book.imbalance(1000) == pytest.approx((502 - k - 600) / (502 - k + 600))
+            # The callback ran with the book released and saw the new top
+            assert seen == [(1000.5, 3.0)] * 20
+        except Exception as exc:  # surfaced in the main thread
+            errors.append(exc)
+
+    threads = [threading.Thread(target=worker, args=(k,)) for k in range(4)]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+    assert errors == []
+
+
+def test_shared_book_consistent_reads_under_concurrent_writes():
+    import threading
+
+    shared = mm.SharedL2Book()
+    shared.apply_snapshot([(100.0, 1.0)], [(101.0, 2.0)], update_id=1)
+    assert shared.best_bid == (100.0, 1.0)
+    assert shared.apply_delta([(100.5, 3.0)], [], update_id=2)
+    assert not shared.apply_delta([(99.0, 1.0)], [], update_id=2)  # stale
+    assert shared.last_update_id == 2
+    assert shared.depth(5) == ([(100.5, 3.0), (100.0, 1.0)], [(101.0, 2.0)])
+    assert shared.mid() == pytest.approx(100.75)
+    assert shared.vwap_for_qty("buy", 1.0) == (101.0, 101.0, 1.0)
+
+    # Every snapshot has all sizes equal to its update id; readers must never
+    # see a mix of two snapshots
+    def levels(i):
+        return [(1000.0 - j, float(i)) for j in range(200)], [(1001.0 + j, float(i)) for j in range(200)]
+
+    done = threading.Event()
+    errors = []
+
+    def writer():
+        for i in range(3, 300):
+            shared.apply_snapshot(*levels(i), update_id=i)
+        done.set()
+
+    def reader():
+        try:
+            while not done.is_set():
+                copy = shared.snapshot()
+                bids, asks = copy.depth(1000)
+                sizes = {s for _, s in bids + asks}
+                assert sizes == {float(copy.last_update_id)} and len(bids) == 200
+                bids, asks = shared.depth(1000)
+                assert len({s for _, s in bids + asks}) == 1
+        except Exception as exc:
+            errors.append(exc)
+
+    threads = [threading.Thread(target=writer)] + [threading.Thread(target=reader) for _ in range(2)]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+    assert errors == []
+    assert shared.last_update_id == 299
+
+    # The copy is detached from the shared book
+    copy = shared.snapshot()
+    shared.clear()
+    assert shared.best_bid is None and copy.best_bid == (1000.0, 299.0)
+
+def test_numpy_snapshot_roundtrip():
+    np = pytest.importorskip("numpy")
+
+    book = mm.L2Book()
+    book.apply_snapshot(
+        np.array([[100.0, 2.0], [99.5, 1.0]]),
+        np.array([[100.5, 1.5], [101.0, 0.8]]),
+    )
+    assert book.depth(5) == ([(100.0, 2.0), (99.5, 1.0)], [(100.5, 1.5), (101.0, 0.8)])
+
+    bids, asks = book.to_numpy(1)
+    assert bids.dtype == np.float64 and bids.shape == (1, 2)
+    assert bids.flags["C_CONTIGUOUS"]
+    assert bids.tolist() == [[100.0, 2.0]]
+    assert asks.tolist() == [[100.5, 1.5]]
+
+    with pytest.raises(ValueError):
+        book.apply_snapshot(np.zeros((2, 3)), [])
+
+
+def test_integer_tick_mode_merges_float_artifacts():
+    book = mm.L2Book(tick_size=0.1)
+    book.apply_snapshot([(0.1 + 0.2, 1.0), (0.2, 1.0)], [(0.5, 1.0)])
+    book.apply_delta([(0.3, 2.0)], [(0.7000000001, 1.0)])
+
+    # 0.1 + 0.2 and 0.3 are the same tick; prices come back tick-exact
+    assert book.bids(5) == [(0.3, 2.0), (0.2, 1.0)]
+    assert book.asks(5) == [(0.5, 1.0), (0.7, 1.0)]
+    assert book.tick_size == 0.1
+
+    # Float mode keeps them apart
+    book = mm.L2Book()
+    book.apply_snapshot([(0.1 + 0.2, 1.0), (0.3, 2.0)], [])
+    assert len(book.bids(5)) == 2
+
+
+def test_apply_delta_returns_book_update():
+    book = mm.L2Book(cross_policy="drop_older_side")
+    assert book.apply_snapshot([(100.0, 1.0), (99.0, 2.0)], [(101.0, 1.0), (102.0, 2.0)]) is None
+    assert book.apply_delta([(98.0, 1.0)], []) is True
+
+    up = book.apply_delta([(99.0, 0.0), (100.5, 3.0)], [(103.0, 1.0)], return_update=True)
+    assert isinstance(up, mm.BookUpdate)
+    assert up.applied
+    assert (up.old_best_bid, up.best_bid) == ((100.0, 1.0), (100.5, 3.0))
+    assert up.best_bid_changed and not up.best_ask_changed and up.top_changed
+    assert up.bids_added == [(100.5, 3.0)]
+    assert up.bids_removed == [(99.0, 2.0)]
+    assert (up.asks_added, up.asks_removed) == ([(103.0, 1.0)], [])
+
+    # Size-only change at the top counts as a best change, not an add
+    up = book.apply_delta([(100.5, 4.0)], [], return_update=True)
+    assert up.best_bid_changed and up.bids_added == [] and up.bids_removed == []
+
+    # Levels dropped by the cross policy are reported as removed
+    up = book.apply_delta([(101.5, 1.0)], [], return_update=True)
+    assert up.asks_removed == [(101.0, 1.0)]
+    assert up.best_ask == (102.0, 2.0) and up.best_ask_changed
+
+    # Stale deltas leave the book unchanged
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=10)
+    up = book.apply_delta([(100.0, 5.0)], [], update_id=9, return_update=True)
+    assert not up.applied and not up.top_changed
+    assert (up.kind, up.update_id, up.exchange, up.symbol) == ("delta", 9, None, None)
+
+
+def test_apply_snapshot_returns_book_update():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0), (99.0, 2.0)], [(101.0, 1.0)])
+    up = book.apply_snapshot([(100.0, 1.0), (98.0, 2.0)], [(101.5, 1.0)], return_update=True)
+    assert up.applied and not up.best_bid_changed and up.best_ask_changed
+    assert (up.bids_added, up.bids_removed) == ([(98.0, 2.0)], [(99.0, 2.0)])
+    assert (up.asks_added, up.asks_removed) == ([(101.5, 1.0)], [(101.0, 1.0)])
+    assert "best_ask=(101, 1) -> (101.5, 1)" in repr(up)
+    assert up.is_snapshot and up.update_id is None
+
+
+def test_book_callbacks_fire_from_apply_delta():
+    events = []
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=10)
+    book.set_callbacks(
+        on_best_change=lambda up: events.append(("best", up.best_bid, book.best_bid)),
+        on_cross=lambda bid, ask: events.append(("cross", bid, ask)),
+        on_gap=lambda last, expected: events.append(("gap", last, expected)),
+    )
+
+    assert book.apply_delta([(99.0, 1.0)], [], update_id=11) is True
+    assert events == []
+    book.apply_delta([(100.5, 2.0)], [], update_id=12)
+    # Callbacks run after the update and may read the book
+    assert events == [("best", (100.5, 2.0), (100.5, 2.0))]
+    events.clear()
+
+    book.apply_delta([(101.5, 1.0)], [], update_id=13)
+    assert events[0] == ("cross", 101.5, 101.0)
+    assert events[1][0] == "best"
+    events.clear()
+
+    assert book.apply_delta([], [(103.0, 1.0)], update_id=20, prev_update_id=15) is False
+    assert events == [("gap", 13, 15)]
+
+    book.set_callbacks()
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=30)
+    book.apply_delta([(100.5, 1.0)], [], update_id=31)
+    assert len(events) == 1
+
+
+def test_book_callbacks_listener_object():
+    class Listener:
+        def __init__(self):
+            self.updates = []
+            self.gaps = []
+
+        def on_best_change(self, update):
+            self.updates.append(update)
+
+        def on_gap(self, last, expected):
+            self.gaps.append((last, expected))
+
+    listener = Listener()
+    book = mm.L2Book(raise_on_gap=True)
+    book.set_callbacks(listener)
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=1)
+    up = book.apply_delta([], [(100.5, 1.0)], update_id=2, return_update=True)
+    assert listener.updates[0].best_ask == up.best_ask == (100.5, 1.0)
+    # on_gap fires before SequenceGapError propagates
+    with pytest.raises(mm.SequenceGapError):
+        book.apply_delta([], [(102.0, 1.0)], update_id=5, prev_update_id=4)
+    assert listener.gaps == [(2, 4)]
+
+    books = mm.BookManager()
+    books.apply_snapshot("BTC", [(100.0, 1.0)], [(101.0, 1.0)])
+    books.get("BTC").set_callbacks(listener)
+    books.apply_delta("BTC", [(100.2, 1.0)], [])
+    assert listener.updates[-1].best_bid == (100.2, 1.0)
+
+    with pytest.raises(ValueError):
+        book.set_callbacks(on_cross=42)
+
+
+def test_consolidated_book_merges_venues():
+    bybit = mm.L2Book()
+    binance = mm.L2Book()
+    bybit.apply_snapshot([(100.0, 1.0), (99.0, 2.0)], [(101.0, 1.0)])
+    binance.apply_snapshot([(100.5, 0.5)], [(100.8, 2.0), (101.0, 3.0)])
+
+    cb = mm.ConsolidatedBook(latency_bps_per_ms=0.5)
+    cb.add_venue("bybit", bybit, fee_bps=5.5)
+    cb.add_venue("binance", binance, fee_bps=4.0, latency_ms=2.0)
+    assert cb.venues() == ["bybit", "binance"] and len(cb) == 2 and "bybit" in cb
+
+    assert cb.bids() == [(100.5, 0.5, "binance"), (100.0, 1.0, "bybit"), (99.0, 2.0, "bybit")]
+    # Equal prices keep registration order
+    assert cb.asks(2) == [(100.8, 2.0, "binance"), (101.0, 1.0, "bybit")]
+    assert cb.best_bid() == (100.5, 0.5, "binance")
+    assert cb.mid() == pytest.approx(100.65)
+
+    # Books are read live, not copied
+    bybit.apply_delta([(100.7, 1.0)], [])
+    assert cb.best_bid() == (100.7, 1.0, "bybit")
+    assert cb.is_crossed(adjusted=False) is False
+
+    assert cb.cost_bps("binance") == pytest.approx(5.0)
+    price, size, venue = cb.best_ask(adjusted=True)
+    assert (venue, size) == ("binance", 2.0)
+    assert price == pytest.approx(100.8 * (1 + 5.0 / 10_000))
+
+    avg, filled, alloc = cb.sweep("buy", 2.5, adjusted=False)
+    assert filled == 2.5 and alloc == {"binance": 2.0, "bybit": 0.5}
+    assert avg == pytest.approx((100.8 * 2.0 + 101.0 * 0.5) / 2.5)
+
+
+def test_consolidated_book_adjusted_cross():
+    a = mm.L2Book()
+    b = mm.L2Book()
+    a.apply_snapshot([(100.0, 1.0)], [(100.2, 1.0)])
+    b.apply_snapshot([(100.3, 1.0)], [(100.5, 1.0)])
+    cb = mm.ConsolidatedBook()
+    cb.add_venue("a", a, fee_bps=5.0)
+    cb.add_venue("b", b, fee_bps=5.0)
+    # The ~10bp raw edge does not cover 2 x 5bp fees
+    assert cb.is_crossed(adjusted=False)
+    assert not cb.is_crossed()
+    cb.set_costs("a", fee_bps=1.0)
+    cb.set_costs("b", fee_bps=1.0)
+    assert cb.is_crossed()
+
+    assert cb.remove_venue("b") and not cb.remove_venue("b")
+    assert not cb.is_crossed(adjusted=False)
+    with pytest.raises(KeyError):
+        cb.set_costs("b", fee_bps=1.0)
+    with pytest.raises(ValueError):
+        cb.add_venue("c", a, latency_ms=-1.0)
+    assert cb.sweep("sell", 1.0) is not None
+    assert mm.ConsolidatedBook().sweep("sell", 1.0) is None
+
+
+def test_staleness_guard_withholds_prices():
+    clock = mm.ReplayClock(1_000)
+    book = mm.L2Book(max_age_ms=500, clock=clock)
+    assert book.is_stale and book.age_ms() is None and book.mid() is None
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)])
+    assert not book.is_stale and book.mid() == 100.5
+
+    clock.set(1_300)
+    book.apply_delta(bids=[(100.0, 2.0)], asks=[])
+    clock.set(1_700)
+    assert book.age_ms() == 400.0
+    assert (book.age_ms("bid"), book.age_ms("ask")) == (400.0, 700.0)
+    assert book.microprice() is not None
+
+    clock.set(1_900)
+    assert book.is_stale and book.mid() is None and book.microprice() is None
+    assert book.spread() == 1.0                      # raw book reads are unaffected
+    book.max_age_ms = None
+    assert book.mid() == 100.5
+
+    strict = mm.L2Book(max_age_ms=10, raise_on_stale=True, clock=clock)
+    strict.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)])
+    clock.advance(11)
+    with pytest.raises(mm.StaleBookError):
+        strict.mid()
+    with pytest.raises(mm.StaleBookError):
+        mm.QuoteEngine(0.1, 1.5).quote_book(strict, 0.0, 0.01)
+    with pytest.raises(ValueError):
+        mm.L2Book(max_age_ms=-1)
+
+    # Without a clock, ages are measured on monotonic_ns()
+    live = mm.L2Book(max_age_ms=60_000)
+    live.apply_snapshot([(1.0, 1.0)], [(2.0, 1.0)])
+    assert 0.0 <= live.age_ms() < 1_000 and live.mid() == 1.5
+
+
+def test_max_levels_evicts_levels_furthest_from_touch():
+    book = mm.L2Book(max_levels=2)
+    assert book.max_levels == 2 and mm.L2Book().max_levels is None
+    book.apply_snapshot([(100.0, 1.0), (99.0, 1.0), (98.0, 1.0)], [(101.0, 1.0), (102.0, 1.0), (103.0, 1.0)])
+    assert book.depth(10) == ([(100.0, 1.0), (99.0, 1.0)], [(101.0, 1.0), (102.0, 1.0)])
+
+    # A better level pushes out the worst; a worse one is dropped at once
+    book.apply_delta(bids=[(100.5, 2.0), (97.0, 5.0)], asks=[(100.8, 3.0)])
+    assert book.depth(10) == ([(100.5, 2.0), (100.0, 1.0)], [(100.8, 3.0), (101.0, 1.0)])
+    # Evicted levels are gone for good: removing the touch leaves one level
+    book.apply_delta(bids=[(100.5, 0.0)], asks=[])
+    assert book.bids(10) == [(100.0, 1.0)]
+    with pytest.raises(ValueError):
+        mm.L2Book(max_levels=0)
+
+
+def test_size_at_and_level_index():
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0), (99.5, 2.0), (99.0, 3.0)], [(100.5, 4.0), (101.0, 5.0)])
+    assert book.size_at("bid", 99.5) == 2.0 and book.size_at("ask", 101.0) == 5.0
+    assert book.size_at("bid", 99.75) == 0.0 and book.size_at("ask", 100.0) == 0.0
+    assert [book.level_index("bid", p) for p in (100.0, 99.5, 99.0)] == [0, 1, 2]
+    assert [book.level_index("sell", p) for p in (100.5, 101.0)] == [0, 1]
+    assert book.level_index("bid", 100.5) is None
+    with pytest.raises(ValueError):
+        book.size_at("mid", 100.0)
+
+    ticks = mm.L2Book(tick_size=0.5)
+    ticks.apply_snapshot([(100.0, 1.0)], [])
+    assert ticks.size_at("bid", 100.0000001) == 1.0 and ticks.level_index("bid", 100.1) == 0
+
+
+def test_liquidity_within_bps():
+    book = mm.L2Book()
+    assert book.liquidity_within_bps("bid", 10.0) is None
+    book.apply_snapshot([(99.9, 1.0), (99.8, 2.0), (99.0, 5.0)], [(100.1, 3.0), (100.3, 4.0)])
+    # mid 100: 10bp reaches 99.9 / 100.1, 20bp 99.8 / 100.2, 100bp everything
+    assert book.liquidity_within_bps("bid", 10.0) == pytest.approx((1.0, 99.9))
+    assert book.liquidity_within_bps("bid", 20.0) == pytest.approx((3.0, 99.9 + 2 * 99.8))
+    assert book.liquidity_within_bps("ask", 20.0) == pytest.approx((3.0, 300.3))
+    assert book.liquidity_within_bps("sell", 100.0) == pytest.approx((7.0, 300.3 + 401.2))
+    assert book.liquidity_within_bps("bid", 0.0) == (0.0, 0.0)
+    with pytest.raises(ValueError):
+        book.liquidity_within_bps("bid", -1.0)
+
+
+def test_weighted_mid_over_levels():
+    book = mm.L2Book()
+    assert book.weighted_mid() is None
+    book.apply_snapshot([(100.0, 1.0), (99.0, 3.0)], [(101.0, 2.0), (104.0, 2.0)])
+    assert book.weighted_mid(1) == book.mid() == 100.5
+    # bid vwap 99.25, ask vwap 102.5
+    assert book.weighted_mid(2) == pytest.approx((99.25 + 102.5) / 2)
+    # Decay pulls the averages back towards the touch
+    decayed = book.weighted_mid(2, decay=1.0)
+    w = math.exp(-1.0)
+    bid = (100.0 + 3 * w * 99.0) / (1 + 3 * w)
+    ask = (2 * 101.0 + 2 * w * 104.0) / (2 + 2 * w)
+    assert decayed == pytest.approx((bid + ask) / 2)
+    assert book.weighted_mid(2, decay=50.0) == pytest.approx(100.5)
+    with pytest.raises(ValueError):
+        book.weighted_mid(2, decay=-1.0)
+
+
+def test_shape_profiles_and_descriptors():
+    book = mm.L2Book()
+    assert book.shape() is None
+    # mid 100; bids 10/20/30bp out with linear depth, asks accelerating
+    book.apply_snapshot([(99.9, 1.0), (99.8, 1.0), (99.7, 1.0)], [(100.1, 1.0), (100.2, 2.0), (100.3, 4.0)])
+    s = book.shape(4)
+    assert s.bid_cum == [1.0, 2.0, 3.0, 3.0]
+    assert s.ask_cum == [1.0, 3.0, 7.0, 7.0]
+    assert s.ratios == pytest.approx([0.5, 0.4, 0.3, 0.3])
+    assert s.bid_slope == pytest.approx(0.1) and s.bid_convexity == pytest.approx(0.0, abs=1e-9)
+    # ask cum 1, 3, 7 at 10, 20, 30bp lie on 0.01 x^2 - 0.1 x + 1
+    assert s.ask_slope == pytest.approx(0.3) and s.ask_convexity == pytest.approx(0.01)
+    f = s.features()
+    assert len(f) == 3 * 4 + 4 and f[-4:] == pytest.approx([0.1, 0.3, 0.0, 0.01], abs=1e-9)
+
+    thin = mm.L2Book()
+    thin.apply_snapshot([(99.9, 1.0)], [(100.1, 1.0), (100.2, 1.0)])
+    t = thin.shape(2)
+    assert t.bid_slope is None and t.ask_slope == pytest.approx(0.1) and t.ask_convexity is None
+    assert math.isnan(t.features()[-4]) and math.isnan(t.features()[-1])
+
+
+def test_impact_of_sweeping_a_notional():
+    book = mm.L2Book()
+    assert book.impact("buy", 100.0) is None
+    book.apply_snapshot([(100.0, 1.0), (99.0, 2.0)], [(101.0, 1.0), (102.0, 1.0), (103.0, 5.0)])
+    # Inside the first level: the touch stays put
+    bps, best, avg, qty = book.impact("buy", 50.5)
+    assert (bps, best, avg) == (0.0, 101.0, 101.0) and qty == pytest.approx(0.5)
+    # Exactly clears two levels: the touch moves to 103
+    bps, best, avg, qty = book.impact("buy", 203.0)
+    assert best == 103.0 and bps == pytest.approx(2 / 101 * 1e4) and (avg, qty) == (101.5, 2.0)
+    # Selling moves the bid down; impact is still positive
+    bps, best, avg, qty = book.impact("sell", 199.0)
+    assert best == 99.0 and bps == pytest.approx(100.0) and qty == pytest.approx(1.0 + 99.0 / 99.0)
+    # Emptying a side leaves no best price; impact runs to the last level taken
+    bps, best, avg, qty = book.impact("sell", 1e9)
+    assert best is None and bps == pytest.approx(100.0) and qty == 3.0
+    with pytest.raises(ValueError):
+        book.impact("buy", 0.0)
+
+
+def test_validate_reports_invariant_violations():
+    book = make_book_with_snapshot()
+    report = book.validate()
+    assert report and report.issues == [] and (report.bid_levels, report.ask_levels) == (2, 2)
+    assert report.best_bid == (100.0, 2.0) and not report.crossed
+
+    # Non-finite input is rejected before it reaches the ladder
+    with pytest.raises(ValueError):
+        book.apply_delta([(99.0, math.inf)], [(math.nan, 1.0)])
+    assert book.validate().ok and len(book) == 4
+
+    crossed = mm.L2Book()
+    crossed.apply_snapshot([(101.0, 1.0)], [(100.0, 1.0)])
+    assert crossed.validate().kinds() == ["crossed"] and crossed.validate().crossed
+    assert crossed.validate(allow_crossed=True).ok
+
+
+def test_debug_checks_raise_on_violation():
+    book = mm.L2Book(debug_checks=True)
+    assert book.debug_checks and not mm.L2Book().debug_checks
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=1)
+    book.apply_delta([(100.5, 2.0)], [], update_id=2)
+    with pytest.raises(mm.BookInvariantError, match="crossed"):
+        book.apply_delta([(101.0, 1.0)], [], update_id=3)
+    # Bad levels never get that far: they are rejected on the way in
+    with pytest.raises(ValueError, match="invalid bid size inf"):
+        book.apply_snapshot([(100.0, math.inf)], [(101.0, 1.0)])
+    # A cross policy that resolves the cross keeps the book valid
+    dropping = mm.L2Book(cross_policy="drop_older_side", debug_checks=True)
+    dropping.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)])
+    dropping.apply_delta([(101.0, 1.0)], [])
+    assert dropping.validate().ok
+
+
+def test_decimal_and_string_levels_round_trip_exactly():
+    from decimal import Decimal
+
+    book = mm.L2Book(tick_size=Decimal("0.00000001"))
+    book.apply_snapshot([(Decimal("0.30000001"), "1.5")], [("0.30000003", Decimal("2"))])
+    # A float that drifted off the grid still lands on the same level
+    book.apply_delta([(0.1 + 0.20000001, 4.0)], [])
+    assert len(book.bids(10)) == 1
+    bids, asks = book.to_decimal()
+    assert bids == [(Decimal("0.30000001"), Decimal("4.0"))]
+    assert asks == [(Decimal("0.30000003"), Decimal("2.0"))]
+    assert str(book.to_decimal(1)[1][0][0]) == "0.30000003"
+
+    plain = mm.L2Book()
+    plain.apply_snapshot([("100.1", "1")], [(Decimal("100.2"), 1.0)])
+    assert plain.best_bid == (100.1, 1.0)
+    assert plain.to_decimal()[0] == [(Decimal("100.1"), Decimal("1.0"))]
+    plain.apply_delta([("100.1", "0")], [])
+    assert plain.best_bid is None
+    with pytest.raises(ValueError):
+        plain.apply_delta([("abc", "1")], [])
+
+
+def test_decimal_prices_find_their_tick_in_fixed_point():
+    from decimal import Decimal
+
+    book = mm.L2Book(tick_size=Decimal("0.00000001"))
+    # Exactly half a tick rounds away from zero; through binary floats
+    # 1.5e-8 / 1e-8 falls just short of 1.5 and lands on the tick below
+    book.apply_snapshot([("0.000000015", "1"), (Decimal("7.5E-8"), "2")], [])
+    assert book.to_decimal()[0] == [(Decimal("0.00000008"), Decimal("2.0")),
+                                    (Decimal("0.00000002"), Decimal("1.0"))]
+    # Any spelling of the same decimal is the same level
+    book.apply_delta([(Decimal("2E-8"), "5"), ("+0.0000000200", Decimal("0"))], [])
+    book.apply_delta([(Decimal("0.000000020"), "6")], [])
+    assert book.bids(5) == [(0.00000008, 2.0), (0.00000002, 6.0)]
+    for bad in ("1e", ".", "+", "1.2.3", "0x10"):
+        with pytest.raises(ValueError):
+            book.apply_delta([(bad, "1")], [])
+
+
+def test_pickle_and_copy_round_trip():
+    import copy
+    import pickle
+    from decimal import Decimal
+
+    book = mm.L2Book(tick_size=Decimal("0.01"), max_levels=5, cross_policy="drop_older_side")
+    book.apply_snapshot([("100.01", 2.0), (99.5, 1.0)], [(100.5, 1.5)], update_id=10)
+    book.apply_delta([(100.02, 1.0)], [], update_id=11)
+    assert book.apply_delta([], [], update_id=14) is False
+    seen = []
+    book.set_callbacks(listener=seen.append)
+
+    restored = pickle.loads(pickle.dumps(book))
+    assert restored.bids(10) == book.bids(10) and restored.asks(10) == book.asks(10)
+    assert restored.to_decimal() == book.to_decimal()
+    assert (restored.last_update_id, restored.gap_count) == (11, 1)
+    assert (restored.tick_size, restored.max_levels) == (0.01, 5)
+    # Callbacks stay behind; the restored book updates silently
+    restored.apply_snapshot([(100.0, 1.0)], [(100.4, 1.0)], update_id=20)
+    assert seen == [] and book.best_ask == (100.5, 1.5)
+
+    # A copy keeps the gap too, so it drops deltas until resynced
+    clone = copy.deepcopy(book)
+    assert clone.apply_delta([(100.02, 0.0)], []) is False
+    clone.apply_snapshot([(99.0, 1.0)], [(100.0, 1.0)], update_id=30)
+    assert clone.best_bid == (99.0, 1.0) and book.best_bid == (100.02, 1.0)
+
+    manager = mm.BookManager(raise_on_gap=True)
+    manager.apply_snapshot("BTCUSDT", [(100.0, 1.0)], [(101.0, 1.0)], update_id=1)
+    loaded = pickle.loads(pickle.dumps(manager))
+    assert loaded.snapshot_all(5) == manager.snapshot_all(5)
+    with pytest.raises(mm.SequenceGapError):
+        loaded.apply_delta("BTCUSDT", [], [], update_id=5)
+    # copy.copy shares the books, deepcopy does not
+    copy.copy(manager).get("BTCUSDT").apply_delta([(100.5, 1.0)], [])
+    deep = copy.deepcopy(manager)
+    deep.get("BTCUSDT").apply_delta([(100.5, 0.0)], [])
+    assert manager.mids()["BTCUSDT"] == 100.75 and deep.mids()["BTCUSDT"] == 100.5
+
+    with pytest.raises(ValueError):
+        mm._restore_state(b"garbage")
+    with pytest.raises(ValueError):
+        mm._restore_state(book.__reduce__()[1][0][:-3])
+
+
+def test_pickle_stateful_analytics():
+    import pickle
+
+    rs = mm.RollingStats(window=4)
+    rs.extend([5.0, 1.0, 3.0, 2.0, 4.0])
+    e = mm.Ewma(halflife_ms=1000)
+    e.update(0.0, 0)
+    pos = mm.Position("fifo")
+    pos.on_fill(100.0, 2.0, "buy")
+    pos.on_fill(101.0, 1.0, "sell")
+
+    rs2, e2, pos2 = pickle.loads(pickle.dumps((rs, e, pos)))
+    assert (rs2.mean(), rs2.median(), len(rs2)) == (2.5, 2.5, 4)
+    rs2.push(0.5)
+    rs.push(0.5)
+    assert rs2.max() == rs.max()
+    assert e2.update(3.0, 1000) == pytest.approx(2.0)
+    assert (pos2.net_qty, pos2.realized_pnl, pos2.avg_price) == (1.0, 1.0, 100.0)
+
+
+def test_dunder_methods():
+    book = mm.L2Book()
+    assert not book and len(book) == 0
+    assert repr(book) == "L2Book(bids=[], asks=[], levels=(0, 0), last_update_id=None)"
+    book.apply_snapshot([(100.0, 1.0), (99.5, 2.0)], [(101.0, 1.0)], update_id=7)
+    # Levels on both sides make the book truthy; one side alone does not
+    assert book and len(book) == 3
+    one_sided = mm.L2Book()
+    one_sided.apply_snapshot([(100.0, 1.0)], [])
+    assert not one_sided and len(one_sided) == 1
+
+    book.apply_delta([(99.0, 1.0), (98.5, 1.0)], [])
+    assert repr(book) == (
+        "L2Book(bids=[(100.0, 1.0), (99.5, 2.0), (99.0, 1.0), ...], "
+        "asks=[(101.0, 1.0)], levels=(4, 1), last_update_id=7)"
+    )
+
+    # Equality compares levels only, not settings or update ids
+    other = mm.L2Book(tick_size=0.5, max_levels=10)
+    other.apply_snapshot(book.bids(10), book.asks(10))
+    assert book == other and not (book != other)
+    other.apply_delta([], [(101.0, 2.0)])
+    assert book != other
+    assert book != "L2Book" and book is not None
+    with pytest.raises(TypeError):
+        hash(book)
+
+
+def test_invalid_prices_raise_without_touching_the_book():
+    book = mm.L2Book(tick_size=0.5)
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=1)
+    for bad in (math.nan, math.inf, -math.inf, -1.0):
+        with pytest.raises(mm.InvalidPriceError, match="invalid bid price"):
+            book.apply_delta([(99.0, 1.0), (bad, 1.0)], [], update_id=2)
+        with pytest.raises(mm.InvalidPriceError, match="invalid ask price"):
+            book.apply_snapshot([(99.0, 1.0)], [(bad, 1.0)], update_id=5)
+    with pytest.raises(ValueError, match="invalid ask size NaN"):
+        book.apply_delta([], [(101.5, math.nan)], update_id=2)
+    with pytest.raises(mm.InvalidPriceError):
+        book.apply_deltas_batch([([(99.0, 1.0)], [], 2), ([(math.nan, 1.0)], [], 3)])
+    # InvalidPriceError is a ValueError; rejected updates neither apply nor
+    # count against the sequence, except batch deltas before the bad one
+    assert issubclass(mm.InvalidPriceError, ValueError)
+    assert book.bids(5) == [(100.0, 1.0), (99.0, 1.0)] and book.last_update_id == 2
+    assert book.gap_count == 0 and book.apply_delta([], [(101.5, 1.0)], update_id=3)
+
+    manager = mm.BookManager()
+    with pytest.raises(mm.InvalidPriceError):
+        manager.apply_snapshot("BTCUSDT", [(math.nan, 1.0)], [])
+    shared = mm.SharedL2Book()
+    with pytest.raises(mm.InvalidPriceError):
+        shared.apply_snapshot([(-5.0, 1.0)], [])
+    bt = mm.Backtester()
+    with pytest.raises(mm.InvalidPriceError):
+        bt.add_delta("BTCUSDT", 0, [(math.inf, 1.0)], [])
+    sim = mm.SimExchange()
+    with pytest.raises(mm.InvalidPriceError):
+        sim.apply_snapshot([(math.nan, 1.0)], [], 0)
+
+    # Gap and cross errors keep their own types
+    strict = mm.L2Book(raise_on_gap=True, cross_policy="raise")
+    strict.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=1)
+    with pytest.raises(mm.CrossedBookError, match="best bid 102"):
+        strict.apply_delta([(102.0, 1.0)], [], update_id=2)
+    with pytest.raises(mm.SequenceGapError, match="last applied 2, delta expects 4"):
+        strict.apply_delta([], [], update_id=5)
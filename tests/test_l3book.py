@@ -0,0 +1,124 @@
+"""
+Unit tests for Rust-backed mm_orderbook.L3Book (market-by-order).
+"""
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def make_book():
+    book = mm.L3Book()
+    book.add(1, "buy", 100.0, 1.0)
+    book.add(2, "buy", 100.0, 2.0)
+    book.add(3, "buy", 100.0, 0.5)
+    book.add(4, "sell", 101.0, 1.0)
+    return book
+
+
+def test_queue_position_and_priority_rules():
+    book = make_book()
+    assert book.queue_position(3) == (2, 3.0)
+
+    # Size decrease keeps priority
+    book.modify(2, 1.5)
+    assert book.orders_at("buy", 100.0) == [1, 2, 3]
+    assert book.queue_position(3) == (2, 2.5)
+
+    # Size increase loses priority
+    book.modify(1, 3.0)
+    assert book.orders_at("buy", 100.0) == [2, 3, 1]
+
+
+def test_execute_delete_and_l2_projection():
+    book = make_book()
+
+    assert book.execute(1, 1.0) == 0.0
+    assert 1 not in book
+    assert book.execute(2, 0.5) == 1.5
+    assert book.best_bid == (100.0, 2.0)
+
+    assert book.delete(4) is True
+    assert book.delete(4) is False
+    assert book.best_ask is None
+
+    l2 = book.to_l2()
+    assert l2.depth(5) == ([(100.0, 2.0)], [])
+
+    with pytest.raises(ValueError):
+        book.add(2, "buy", 99.0, 1.0)
+    with pytest.raises(KeyError):
+        book.execute(42, 1.0)
+
+
+def test_queue_tracker_from_l2_stream():
+    tracker = mm.QueueTracker()
+    tracker.add_order(7, "buy", 100.0, 1.0, level_size=4.0)
+    assert tracker.position(7) == (4.0, 0.0)
+
+    # Size joining the level queues behind us
+    tracker.apply_delta([(100.0, 6.0)], [])
+    assert tracker.position(7) == (4.0, 1.0)
+
+    # Cancels are split proportionally between ahead and behind
+    tracker.apply_delta([(100.0, 3.0)], [])
+    ahead, behind = tracker.position(7)
+    assert ahead == pytest.approx(1.6)
+    assert behind == pytest.approx(0.4)
+
+    # Trades eat the queue from the front, then fill us
+    assert tracker.on_trade(100.0, 1.0) == []
+    assert tracker.on_trade(100.0, 5.0) == [(7, 1.0)]
+
+
+def test_fill_probability_from_queue_and_trade_flow():
+    import math
+
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 4.0), (99.0, 10.0)], [(101.0, 5.0), (102.0, 8.0)])
+    model = mm.FillProbability(depth=3, halflife_ms=None)
+    assert model.probability("buy", 0, 1000) is None
+
+    model.observe(book, 0)
+    model.on_trade(100.0, 1.0, "sell")
+    model.on_trade(100.0, 1.0)  # side inferred from the touch
+    model.on_trade(100.5, 9.0)  # inside the spread: ignored
+    # 4 - 2 traded leaves 2, so 1 was cancelled
+    book.apply_delta([(100.0, 1.0)], [])
+    model.observe(book, 1000)
+
+    rates = model.rates("buy", 0)
+    assert rates["trade_rate"] == pytest.approx(2.0)
+    assert rates["mean_trade_size"] == pytest.approx(1.0)
+    assert rates["through_rate"] == 0.0
+    assert rates["cancel_rate"] == pytest.approx(0.25)
+
+    # Front of the queue: one print within 1s fills us
+    assert model.probability("buy", 0, 1000, queue_ahead=0.0) == pytest.approx(1 - math.exp(-2))
+    # 2 ahead shrinks to 2 * e^-0.25 ~ 1.56 after cancels: two prints needed
+    assert model.probability("buy", 0, 1000, queue_ahead=2.0) == pytest.approx(1 - 3 * math.exp(-2))
+    # Default is the back of the visible queue (1.0 -> 0.78 -> one print)
+    assert model.level_probabilities("buy", 1000)[0] == pytest.approx(1 - math.exp(-2))
+    assert model.probability("buy", 1, 1000) == 0.0
+
+    # A print at 99 sweeps through 100
+    model.on_trade(99.0, 3.0, "sell")
+    book.apply_delta([(100.0, 0.0), (99.0, 7.0)], [])
+    model.observe(book, 2000)
+    rates = model.rates("buy", 0)
+    assert rates["through_rate"] == pytest.approx(0.5)
+    assert rates["cancel_rate"] == pytest.approx(1.0 / 5.0)
+    assert model.rates("buy", 1)["trade_rate"] == pytest.approx(0.5)
+
+    tracker = mm.QueueTracker()
+    tracker.add_order(1, "buy", 99.0, 1.0, level_size=7.0)
+    assert model.order_probability(tracker, 1, 1000) == pytest.approx(
+        model.probability("buy", 0, 1000, queue_ahead=7.0)
+    )
+    assert model.order_probability(tracker, 2, 1000) is None
+    assert 0.0 < model.order_probability(tracker, 1, 1000) < 1.0
+
+    with pytest.raises(ValueError):
+        model.probability("buy", 3, 1000)
+    model.reset()
+    assert model.rates("sell", 0) is None
@@ -0,0 +1,139 @@
+"""
+Unit tests for the native metrics registry and Prometheus exposition in mm_orderbook.
+"""
+
+import time
+import urllib.request
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def delta_of(metrics, name, labels=None):
+    start = metrics.value(name, labels) or 0.0
+    return lambda: (metrics.value(name, labels) or 0.0) - start
+
+
+def test_metrics_counters_gauges_histograms():
+    m = mm.Metrics()
+    m.inc("test_events_total", labels={"symbol": "BTC"}, help="Test events")
+    m.inc("test_events_total", 2.5, labels={"symbol": "BTC"})
+    m.set("test_inventory", -3.0, labels={"symbol": "BTC"})
+    for v in (0.5, 3.0, 3.0, 100.0):
+        m.observe("test_latency_us", v)
+
+    assert m.value("test_events_total", {"symbol": "BTC"}) == 3.5
+    assert m.value("test_inventory", {"symbol": "BTC"}) == -3.0
+    assert m.value("test_latency_us") == 4
+    assert m.value("test_missing") is None
+    # Log-linear buckets: 16 per power of two
+    assert m.quantile("test_latency_us", 0.5) == pytest.approx(3.0, rel=0.07)
+    assert m.quantile("test_latency_us", 1.0) == pytest.approx(100.0, rel=0.07)
+
+    text = m.render_prometheus()
+    assert "# HELP test_events_total Test events\n# TYPE test_events_total counter\n" in text
+    assert 'test_events_total{symbol="BTC"} 3.5\n' in text
+    assert 'test_inventory{symbol="BTC"} -3\n' in text
+    assert 'test_latency_us_bucket{le="1"} 1\n' in text
+    assert 'test_latency_us_bucket{le="4"} 3\n' in text
+    assert 'test_latency_us_bucket{le="128"} 4\n' in text
+    assert 'test_latency_us_bucket{le="+Inf"} 4\n' in text
+    assert "test_latency_us_sum 106.5\n" in text
+
+    with pytest.raises(ValueError):
+        m.set("test_events_total", 1.0, labels={"symbol": "BTC"})
+    with pytest.raises(ValueError):
+        m.inc("test_events_total", -1.0)
+    with pytest.raises(ValueError):
+        m.inc("bad name")
+
+
+def test_metrics_native_instrumentation():
+    m = mm.Metrics()
+    deltas = delta_of(m, "mm_book_updates_total", {"kind": "delta"})
+    skipped = delta_of(m, "mm_book_updates_skipped_total")
+    gaps = delta_of(m, "mm_book_sequence_gaps_total")
+    t2q = delta_of(m, "mm_tick_to_quote_us")
+    submits = delta_of(m, "mm_orders_total", {"event": "submit"})
+    acks = delta_of(m, "mm_orders_total", {"event": "ack"})
+    ack_latency = delta_of(m, "mm_order_ack_latency_ms")
+    throttled = delta_of(m, "mm_ratelimit_requests_total", {"result": "throttled"})
+
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=10)
+    book.apply_delta([(100.0, 2.0)], [], update_id=11)
+    book.apply_delta([(100.0, 3.0)], [], update_id=11)
+    book.apply_delta([(100.0, 3.0)], [], update_id=20, prev_update_id=15)
+    assert (deltas(), skipped(), gaps()) == (1, 2, 1)
+
+    engine = mm.QuoteEngine(gamma=0.1, kappa=1.5, horizon=1.0, min_spread=0.0)
+    engine.quote_book(book, 0.0, 0.01)
+    assert t2q() == 1
+
+    om = mm.OrderManager()
+    om.submit("m1", "BTC", "buy", 100.0, 1.0, 1_000)
+    om.on_ack("m1", 1_030)
+    assert (submits(), acks(), ack_latency()) == (1, 1, 1)
+
+    rl = mm.RateLimiter([(1, 60.0)])
+    rl.try_acquire()
+    rl.try_acquire()
+    assert throttled() == 1
+
+
+def test_metrics_http_endpoint():
+    m = mm.Metrics()
+    m.inc("test_http_total")
+    port = m.serve(0)
+    body = urllib.request.urlopen(f"http://127.0.0.1:{port}/metrics", timeout=5).read().decode()
+    assert "# TYPE test_http_total counter" in body
+    assert "mm_book_updates_total" in body
+    with pytest.raises(urllib.error.HTTPError):
+        urllib.request.urlopen(f"http://127.0.0.1:{port}/other", timeout=5)
+
+
+def test_latency_tracker_percentiles_and_snapshot():
+    t = mm.LatencyTracker()
+    assert t.percentile(99.0) is None
+    assert t.snapshot()["p50"] is None
+    for ns in range(1, 10_001):
+        t.record_ns(ns * 1_000)
+    assert t.count == len(t) == 10_000
+    assert (t.min, t.max) == (1_000, 10_000_000)
+    assert t.mean == pytest.approx(5_000_500.0)
+    assert t.percentile(50.0) == pytest.approx(5_000_000, rel=0.01)
+    assert t.percentile(99.9) == pytest.approx(9_990_000, rel=0.01)
+    assert t.percentile(100.0) == 10_000_000
+    assert t.percentile(0.0) == 1_000
+
+    snap = t.snapshot()
+    assert snap["count"] == 10_000
+    assert snap["p99"] == pytest.approx(9_900_000, rel=0.01)
+    assert snap["p99.99"] <= snap["max"]
+    assert sum(c for _, _, c in t.buckets()) == 10_000
+
+    with pytest.raises(ValueError):
+        t.percentile(101.0)
+    with pytest.raises(ValueError):
+        mm.LatencyTracker(precision_bits=0)
+
+    t.reset()
+    assert (t.count, t.min, t.max, t.mean) == (0, None, None, None)
+
+
+def test_latency_tracker_record_and_export():
+    t = mm.LatencyTracker("test_decision_ns", labels={"symbol": "BTC"}, help="Tick to decision")
+    assert t.record(1_000, 1_500) == 500
+    assert t.record(2_000, 1_000) == 0
+    assert t.record(time.perf_counter_ns()) >= 0
+    assert t.count == 3
+
+    m = mm.Metrics()
+    assert m.value("test_decision_ns", {"symbol": "BTC"}) == 3
+    text = m.render_prometheus()
+    assert "# TYPE test_decision_ns histogram\n" in text
+    assert 'test_decision_ns_count{symbol="BTC"} 3\n' in text
+    # Same name and labels share one histogram
+    shared = mm.LatencyTracker("test_decision_ns", labels={"symbol": "BTC"})
+    assert shared.count == 3
@@ -0,0 +1,73 @@
+"""
+Unit tests for the Rust-backed Parquet market-data Recorder in mm_orderbook.
+"""
+
+import os
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def record_sample(rec):
+    rec.record_snapshot("BTC", 0, [(100.0, 1.0), (99.0, 2.0)], [(101.0, 1.5)], update_id=7)
+    rec.record_delta("BTC", 10, [(100.0, 0.0)], [])
+    rec.record_trade("BTC", 20, 100.5, 0.3, "buy")
+    rec.record_trade("ETH", 25, 10.0, 1.0)
+
+
+def test_recorder_partitions_and_closes_files(tmp_path):
+    rec = mm.Recorder(str(tmp_path), row_group_rows=2)
+    record_sample(rec)
+    assert rec.files == []
+    files = rec.close()
+    rel = sorted(os.path.relpath(f, tmp_path) for f in files)
+    assert rel == [
+        os.path.join("book", "symbol=BTC", "0-0.parquet"),
+        os.path.join("trades", "symbol=BTC", "20-0.parquet"),
+        os.path.join("trades", "symbol=ETH", "25-0.parquet"),
+    ]
+    for f in files:
+        with open(f, "rb") as fh:
+            data = fh.read()
+        assert data[:4] == b"PAR1" and data[-4:] == b"PAR1"
+
+    # A second recorder never overwrites existing files
+    rec = mm.Recorder(str(tmp_path))
+    rec.record_trade("ETH", 25, 10.0, 1.0)
+    assert rec.close()[0].endswith("25-1.parquet")
+
+
+def test_recorder_rotates_by_time_and_size(tmp_path):
+    rec = mm.Recorder(str(tmp_path / "t"), rotate_ms=1000)
+    for ts in (0, 500, 999, 1000, 2500):
+        rec.record_trade("BTC", ts, 1.0, 1.0)
+    assert [os.path.basename(f) for f in rec.close()] == [
+        "0-0.parquet",
+        "1000-0.parquet",
+        "2500-0.parquet",
+    ]
+
+    rec = mm.Recorder(str(tmp_path / "s"), rotate_bytes=200, row_group_rows=1)
+    for ts in range(10):
+        rec.record_trade("BTC", ts, 1.0, 1.0)
+    assert len(rec.close()) > 1
+
+    with pytest.raises(ValueError):
+        mm.Recorder(str(tmp_path), rotate_ms=0)
+
+
+def test_recorder_files_read_back_with_pyarrow(tmp_path):
+    pq = pytest.importorskip("pyarrow.parquet")
+    rec = mm.Recorder(str(tmp_path), row_group_rows=2)
+    record_sample(rec)
+    rec.close()
+
+    book = pq.read_table(tmp_path / "book" / "symbol=BTC" / "0-0.parquet").to_pydict()
+    assert book["kind"] == ["snapshot", "snapshot", "snapshot", "delta"]
+    assert book["side"] == ["bid", "bid", "ask", "bid"]
+    assert book["price"] == [100.0, 99.0, 101.0, 100.0]
+    assert book["update_id"] == [7, 7, 7, None]
+
+    trades = pq.read_table(tmp_path / "trades" / "symbol=ETH" / "25-0.parquet").to_pydict()
+    assert trades == {"ts": [25], "price": [10.0], "qty": [1.0], "side": [None]}
@@ -0,0 +1,282 @@
+"""
+Unit tests for mm_orderbook.OrderGateway against a local HTTP server that
+checks request signatures like the exchanges do.
+"""
+
+import asyncio
+import hashlib
+import hmac
+import json
+import os
+import ssl
+import threading
+import time
+from http.server import BaseHTTPRequestHandler, HTTPServer
+from urllib.parse import parse_qsl, urlsplit
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+KEY, SECRET = "testkey", "testsecret"
+# Self-signed for localhost and 127.0.0.1
+TLS_CERT = os.path.join(os.path.dirname(__file__), "fixtures", "tls", "localhost.pem")
+TLS_KEY = os.path.join(os.path.dirname(__file__), "fixtures", "tls", "localhost.key")
+
+
+def sign(payload):
+    return hmac.new(SECRET.encode(), payload.encode(), hashlib.sha256).hexdigest()
+
+
+class ExchangeServer:
+    """Records (method, path, params, signature_ok) for every request and
+    answers with the next (status, body) in `responses`."""
+
+    def __init__(self, responses, tls=False):
+        self.responses = list(responses)
+        self.requests = []
+        outer = self
+
+        class Handler(BaseHTTPRequestHandler):
+            protocol_version = "HTTP/1.1"
+
+            def handle_any(self):
+                parts = urlsplit(self.path)
+                body = self.rfile.read(int(self.headers.get("Content-Length") or 0)).decode()
+                if parts.path.endswith("/time"):
+                    params, ok = {}, True
+                elif "X-BAPI-SIGN" in self.headers:
+                    h = self.headers
+                    payload = h["X-BAPI-TIMESTAMP"] + h["X-BAPI-API-KEY"] + h["X-BAPI-RECV-WINDOW"] + body
+                    params, ok = json.loads(body), h["X-BAPI-SIGN"] == sign(payload)
+                    params["timestamp"] = int(h["X-BAPI-TIMESTAMP"])
+                else:
+                    query, _, signature = parts.query.rpartition("&signature=")
+                    params = dict(parse_qsl(query))
+                    ok = self.headers["X-MBX-APIKEY"] == KEY and signature == sign(query)
+                    params["timestamp"] = int(params["timestamp"])
+                outer.requests.append((self.command, parts.path, params, ok))
+                status, reply = outer.responses.pop(0)
+                data = json.dumps(reply).encode()
+                self.send_response(status)
+                self.send_header("Content-Length", str(len(data)))
+                self.end_headers()
+                self.wfile.write(data)
+
+            do_GET = do_POST = do_PUT = do_DELETE = handle_any
+
+            def log_message(self, *args):
+                pass
+
+        self.httpd = HTTPServer(("127.0.0.1", 0), Handler)
+        self.url = "http://127.0.0.1:%d" % self.httpd.server_address[1]
+        if tls:
+            context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+            context.load_cert_chain(TLS_CERT, TLS_KEY)
+            self.httpd.socket = context.wrap_socket(self.httpd.socket, server_side=True)
+            self.url = "https://localhost:%d" % self.httpd.server_address[1]
+        threading.Thread(target=self.httpd.serve_forever, daemon=True).start()
+
+    def close(self):
+        self.httpd.shutdown()
+        self.httpd.server_close()
+
+
+def bybit_ok(order_id="", link_id=""):
+    return 200, {"retCode": 0, "retMsg": "OK", "result": {"orderId": order_id, "orderLinkId": link_id}}
+
+
+def test_bybit_place_amend_cancel_updates_order_manager():
+    now = int(time.time() * 1000)
+    server = ExchangeServer([
+        (200, {"retCode": 0, "retMsg": "OK", "result": {}, "time": now + 2000}),
+        (503, {"error": "overloaded"}),
+        bybit_ok("ex-1", "q1"),
+        bybit_ok("ex-1", "q1"),
+        (200, {"retCode": 10002, "retMsg": "invalid timestamp", "result": {}}),
+        (200, {"retCode": 0, "retMsg": "OK", "result": {}, "time": now + 2000}),
+        bybit_ok("ex-1", "q1"),
+        (200, {"retCode": 110007, "retMsg": "insufficient balance", "result": {}}),
+    ])
+    orders = mm.OrderManager()
+    gw = mm.OrderGateway(server.url, KEY, SECRET, orders=orders, retry_delay_ms=1)
+    assert gw.orders is orders
+    offset = gw.sync_time()
+    assert 1990 <= offset <= 2010 and gw.time_offset_ms == offset
+
+    r = gw.place("q1", "BTCUSDT", "buy", 100.5, 0.01, time_in_force="post_only")
+    assert (r.ok, r.exchange_id, r.attempts, r.code) == (True, "ex-1", 2, 0)
+    assert orders.state("q1") == "ACKED" and orders.order("q1").exchange_id == "ex-1"
+
+    r = gw.amend("q1", "BTCUSDT", price=100.25)
+    assert r.ok and orders.order("q1").price == 100.25
+    r = gw.cancel("q1", "BTCUSDT")
+    assert r.ok and r.attempts == 2 and orders.state("q1") == "CANCELED"
+
+    r = gw.place("q2", "BTCUSDT", "sell", 101.0, 5.0)
+    assert (r.ok, r.code, r.message) == (False, 110007, "insufficient balance")
+    assert orders.state("q2") == "REJECTED"
+    assert gw.requests == 6 and gw.retries == 2
+    server.close()
+
+    methods = [(m, p) for m, p, _, _ in server.requests]
+    assert methods == [("GET", "/v5/market/time")] + [("POST", "/v5/order/create")] * 2 + [
+        ("POST", "/v5/order/amend"), ("POST", "/v5/order/cancel"), ("GET", "/v5/market/time"),
+        ("POST", "/v5/order/cancel"), ("POST", "/v5/order/create")]
+    assert all(ok for _, _, _, ok in server.requests)
+    place = server.requests[1][2]
+    assert abs(place.pop("timestamp") - (now + 2000)) < 1000  # stamped with exchange time
+    assert place == {"category": "linear", "symbol": "BTCUSDT", "orderLinkId": "q1", "side": "Buy",
+                     "orderType": "Limit", "timeInForce": "PostOnly", "qty": "0.01", "price": "100.5"}
+    amend = server.requests[3][2]
+    del amend["timestamp"]
+    assert amend == {"category": "linear", "symbol": "BTCUSDT", "orderLinkId": "q1", "price": "100.25"}
+
+
+def test_binance_futures_signing_and_errors():
+    server = ExchangeServer([
+        (200, {"orderId": 123456789012, "clientOrderId": "f1", "status": "NEW"}),
+        (200, {"orderId": 123456789012, "status": "NEW"}),
+        (400, {"code": -2011, "msg": "Unknown order sent."}),
+    ])
+    orders = mm.OrderManager()
+    gw = mm.OrderGateway(server.url, KEY, SECRET, exchange="binance_futures", orders=orders)
+    r = gw.place("f1", "ETHUSDT", "sell", 2000.5, 1.5, time_in_force="post_only")
+    assert r.ok and r.exchange_id == "123456789012" and orders.state("f1") == "ACKED"
+    assert gw.amend("f1", "ETHUSDT", qty=2.0).ok and orders.order("f1").qty == 2.0
+    r = gw.cancel("f1", "ETHUSDT")
+    assert (r.ok, r.status, r.code) == (False, 400, -2011)
+    # The cancel was refused; the order stays live with no cancel pending
+    assert orders.state("f1") == "ACKED" and not orders.order("f1").cancel_pending
+    server.close()
+
+    assert all(ok for _, _, _, ok in server.requests)
+    (m1, p1, place, _), (m2, _, amend, _), (m3, _, cancel, _) = server.requests
+    assert (m1, m2, m3, p1) == ("POST", "PUT", "DELETE", "/fapi/v1/order")
+    assert {k: place[k] for k in ("side", "type", "timeInForce", "quantity", "price", "newClientOrderId")} == {
+        "side": "SELL", "type": "LIMIT", "timeInForce": "GTX", "quantity": "1.5", "price": "2000.5",
+        "newClientOrderId": "f1"}
+    # Futures amends carry side, price and qty; the missing price comes from the OrderManager
+    assert (amend["side"], amend["quantity"], amend["price"], amend["origClientOrderId"]) == ("SELL", "2", "2000.5", "f1")
+    assert cancel["origClientOrderId"] == "f1" and cancel["recvWindow"] == "5000"
+
+
+def test_prices_and_quantities_go_out_as_exact_decimals():
+    from decimal import Decimal
+
+    server = ExchangeServer([bybit_ok("ex-%d" % i) for i in range(4)])
+    gw = mm.OrderGateway(server.url, KEY, SECRET)
+    assert gw.place("d1", "BTCUSDT", "buy", 100.1 + 0.2, 0.1 + 0.2).ok   # float noise dropped
+    assert gw.place("d2", "BTCUSDT", "buy", Decimal("64000.50"), "0.001").ok
+
+    filtered = mm.OrderGateway(server.url, KEY, SECRET,
+                               filters={"BTCUSDT": mm.SymbolFilters(tick_size=0.5, lot_size=0.001)})
+    assert filtered.place("d3", "BTCUSDT", "sell", 100, 0.01).ok
+    assert filtered.amend("d3", "BTCUSDT", price=Decimal("100.5")).ok
+    with pytest.raises(ValueError, match="not a multiple of 0.5"):
+        filtered.place("d4", "BTCUSDT", "sell", 100.3, 0.01)
+    with pytest.raises(ValueError, match="not a multiple of 0.001"):
+        filtered.place("d4", "BTCUSDT", "sell", 100.0, 0.0105)
+    with pytest.raises(ValueError):
+        gw.place("d4", "BTCUSDT", "sell", "nan", 1.0)
+    server.close()
+
+    sent = [(p.get("price"), p.get("qty")) for _, _, p, _ in server.requests]
+    assert sent == [("100.3", "0.3"), ("64000.5", "0.001"), ("100.0", "0.010"), ("100.5", None)]
+
+
+@pytest.mark.skipif(not mm.HAS_TLS, reason="extension built without TLS")
+def test_signed_requests_over_tls(monkeypatch):
+    monkeypatch.setenv("SSL_CERT_FILE", TLS_CERT)
+    server = ExchangeServer([bybit_ok("ex-1", "t1")], tls=True)
+    gw = mm.OrderGateway(server.url, KEY, SECRET)
+    r = gw.place("t1", "BTCUSDT", "buy", 100.0, 1.0)
+    server.close()
+    assert r.ok and r.exchange_id == "ex-1"
+    (method, path, params, ok), = server.requests
+    assert (method, path, params["orderLinkId"], ok) == ("POST", "/v5/order/create", "t1", True)
+
+
+def test_send_is_awaitable_and_leaves_the_loop_running():
+    server = ExchangeServer([(503, {"error": "overloaded"}), bybit_ok("ex-1", "a1"), bybit_ok(), bybit_ok()])
+    orders = mm.OrderManager()
+    gw = mm.OrderGateway(server.url, KEY, SECRET, orders=orders, retry_delay_ms=300)
+    place = mm.OrderRequest.place("a1", "BTCUSDT", "buy", 100.5, 0.01, time_in_force="post_only")
+    assert (place.action, place.side, place.price, place.qty) == ("place", "buy", 100.5, 0.01)
+
+    async def main():
+        ticks = 0
+
+        async def ticker():
+            nonlocal ticks
+            while True:
+                await asyncio.sleep(0.01)
+                ticks += 1
+
+        task = asyncio.create_task(ticker())
+        pending = gw.send(place)
+        assert orders.state("a1") == "NEW"   # recorded before the round trip
+        r = await pending                    # 503, 300 ms back-off, then the ack
+        assert ticks >= 10                   # the loop kept running meanwhile
+        assert r.ok and r.attempts == 2 and r.exchange_id == "ex-1"
+        assert orders.state("a1") == "ACKED"
+        r = await gw.send(mm.OrderRequest.amend("a1", "BTCUSDT", qty=0.02))
+        assert r.ok and orders.order("a1").qty == 0.02
+        results = await asyncio.gather(gw.send(mm.OrderRequest.cancel("a1", "BTCUSDT")))
+        assert results[0].ok and orders.state("a1") == "CANCELED"
+        with pytest.raises(ValueError):
+            gw.send(mm.OrderRequest.place("x y", "BTCUSDT", "buy", 1.0, 1.0))
+        task.cancel()
+
+    asyncio.run(main())
+    server.close()
+    assert [p for _, p, _, _ in server.requests] == ["/v5/order/create"] * 2 + ["/v5/order/amend", "/v5/order/cancel"]
+    with pytest.raises(ValueError):
+        mm.OrderRequest.amend("a1", "BTCUSDT")
+    with pytest.raises(RuntimeError):
+        gw.send(place)  # no running loop
+
+    orders = mm.OrderManager()
+    gw = mm.OrderGateway("http://127.0.0.1:1", KEY, SECRET, orders=orders, timeout_ms=200)
+
+    async def unreachable():
+        with pytest.raises(OSError):
+            await gw.send(mm.OrderRequest.place("b1", "BTCUSDT", "sell", 1.0, 1.0))
+
+    asyncio.run(unreachable())
+    assert orders.state("b1") == "NEW" and orders.in_flight == 1
+
+
+def test_validation_and_network_failure():
+    with pytest.raises(ValueError):
+        mm.OrderGateway("http://127.0.0.1:1", "bad key", SECRET)
+    with pytest.raises(ValueError):
+        mm.OrderGateway("ftp://api.bybit.com", KEY, SECRET)
+    # Keys and signatures never go out in plaintext
+    with pytest.raises(ValueError, match="https"):
+        mm.OrderGateway("http://api.bybit.com", KEY, SECRET)
+    with pytest.raises(ValueError, match="https"):
+        mm.OrderGateway("http://10.0.0.5:8080", KEY, SECRET)
+    for url in ("http://localhost:1", "http://127.0.0.2:1", "http://[::1]:1"):
+        mm.OrderGateway(url, KEY, SECRET)
+    if mm.HAS_TLS:
+        mm.OrderGateway("https://api.bybit.com", KEY, SECRET)
+    else:
+        with pytest.raises(ValueError, match="needs TLS"):
+            mm.OrderGateway("https://api.bybit.com", KEY, SECRET)
+    gw = mm.OrderGateway("http://127.0.0.1:1", KEY, SECRET, exchange="binance", timeout_ms=200)
+    with pytest.raises(ValueError):
+        gw.place("x y", "BTCUSDT", "buy", 1.0, 1.0)
+    with pytest.raises(ValueError):
+        gw.place("a1", "BTCUSDT", "buy", 1.0, 1.0, time_in_force="GTD")
+    with pytest.raises(ValueError):
+        gw.place("a1", "BTCUSDT", "buy", float("nan"), 1.0)
+    with pytest.raises(ValueError):
+        gw.amend("a1", "BTCUSDT", price=1.0)  # binance spot has no amend
+
+    orders = mm.OrderManager()
+    gw = mm.OrderGateway("http://127.0.0.1:1", KEY, SECRET, orders=orders, timeout_ms=200)
+    with pytest.raises(OSError):
+        gw.place("a1", "BTCUSDT", "buy", 1.0, 1.0)
+    # No answer: the submit stays in flight
+    assert orders.state("a1") == "NEW" and orders.in_flight == 1
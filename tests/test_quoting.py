@@ -5,6 +5,17 @@ from src.common.config import Config
 from src.common.models import OrderBook, PriceLevel
 from src.strategy.quoting import MarketMakingStrategy
 
+import math
+
+import pytest
+
+try:
+	import mm_orderbook as mm
+except ImportError:
+	mm = None
+
+needs_mm = pytest.mark.skipif(mm is None, reason="mm_orderbook is not built")
+
 
 def make_ob(mid=Decimal('100'), spread=Decimal('1')) -> OrderBook:
 	bid = mid - spread / 2
@@ -49,3 +60,261 @@ def test_inventory_skew_bounds():
 	strat.update_orderbook('BTCUSDT', ob)
 	assert quotes
 
+
+# ---- Rust-backed quoting components (mm_orderbook) ----
+
+
+@needs_mm
+def test_avellaneda_stoikov_quote():
+    engine = mm.QuoteEngine(gamma=0.1, kappa=1.5)
+
+    q = engine.quote(100.0, inventory=2.0, volatility=2.0)
+    assert q.reservation_price == pytest.approx(100.0 - 2.0 * 0.1 * 4.0)
+    assert q.spread == pytest.approx(0.1 * 4.0 + (2.0 / 0.1) * math.log(1.0 + 0.1 / 1.5))
+    assert q.bid_price == pytest.approx(q.reservation_price - q.spread / 2.0)
+    assert q.ask_price == pytest.approx(q.reservation_price + q.spread / 2.0)
+
+    # Flat inventory quotes symmetrically around the reference price
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 2.0)], [(100.5, 1.5)])
+    q = engine.quote_book(book, 0.0, 1.0)
+    assert q.reservation_price == pytest.approx(100.25)
+
+    assert engine.quote_book(mm.L2Book(), 0.0, 1.0) is None
+
+    # Attribute writes are checked like the constructor
+    for name in ("gamma", "kappa"):
+        for bad in (0.0, -1.0, float("nan")):
+            with pytest.raises(ValueError, match=name):
+                setattr(engine, name, bad)
+        with pytest.raises(ValueError, match=name):
+            mm.QuoteEngine(**{"gamma": 0.1, "kappa": 1.5, name: bad})
+    engine.gamma, engine.kappa = 0.2, 2.0
+    assert engine.quote(100.0, 0.0, 1.0).spread == pytest.approx(0.2 + 10.0 * math.log(1.1))
+
+
+@needs_mm
+def test_inventory_skew_curves_and_composition():
+    skew = mm.InventorySkew(10.0, max_price_skew_bps=20.0)
+
+    adj = skew.adjustment(5.0)
+    assert adj.skew == pytest.approx(0.5)
+    assert adj.price_shift_bps == pytest.approx(-10.0)
+    assert skew.sizes(5.0, 2.0) == pytest.approx((1.0, 3.0))
+    # At max position the inventory-increasing side is switched off
+    assert skew.sizes(10.0, 2.0) == pytest.approx((0.0, 4.0))
+    assert skew.sizes(-25.0, 2.0) == pytest.approx((4.0, 0.0))
+
+    quote = mm.QuoteEngine(0.1, 1.5).quote(100.0, 0.0, 1.0)
+    shifted = skew.apply(quote, 5.0)
+    assert shifted.reservation_price == pytest.approx(100.0 * (1 - 10.0 / 1e4))
+    assert shifted.spread == pytest.approx(quote.spread)
+
+    for curve in ("sigmoid", "exponential"):
+        skew.curve = curve
+        assert skew.adjustment(10.0).skew == pytest.approx(1.0)
+        assert skew.adjustment(-5.0).skew == pytest.approx(-skew.adjustment(5.0).skew)
+
+
+@needs_mm
+def test_ladder_generator_spacing_sizes_and_rounding():
+    ladder = mm.LadderGenerator(
+        3, 1.0, first_bps=10, step_bps=10, size_profile="pyramid", tick_size=0.5, lot_size=0.1
+    )
+    bids, asks = ladder.generate(100.0, 101.0)
+    # Levels that round onto the same tick are pushed one tick further out
+    assert bids == [(99.5, 1.0), (99.0, 1.5), (98.5, 2.0)]
+    assert asks == [(101.5, 1.0), (102.0, 1.5), (102.5, 2.0)]
+
+    ladder = mm.LadderGenerator(
+        2, 1.0, spacing="custom", offsets_bps=[0.0, 50.0],
+        size_profile="custom", size_multipliers=[1.0, 3.0], min_notional=150.0,
+    )
+    bids, asks = ladder.generate(100.0, 100.5)
+    # The first level fails the min-notional filter
+    assert bids == [(pytest.approx(99.5), 3.0)]
+    assert len(asks) == 1
+
+    with pytest.raises(ValueError):
+        mm.LadderGenerator(3, 1.0, spacing="custom", offsets_bps=[1.0])
+
+
+@needs_mm
+def test_symbol_filters_rounding_and_validation():
+    filters = mm.SymbolFilters(0.1, 0.001, min_notional=5.0, min_qty=0.002)
+
+    assert filters.round_price(100.06, "buy") == 100.0
+    assert filters.round_price(100.06, "sell") == 100.1
+    assert filters.round_price(100.06) == 100.1
+    assert filters.round_qty(0.0129) == 0.012
+
+    assert filters.validate(100.1, 0.05)
+    assert not filters.validate(100.05, 0.05)
+    assert "min_notional" in filters.violation(100.0, 0.01)
+    assert "min_qty" in filters.violation(100.0, 0.001)
+
+    # Attached filters snap derived prices to the tick
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 2.0)], [(100.5, 1.5)])
+    book.filters = filters
+    assert book.mid() == 100.3
+    assert book.microprice() == 100.3
+    book.filters = None
+    assert book.mid() == 100.25
+
+
+@needs_mm
+def test_basis_tracker_basis_and_funding():
+    bt = mm.BasisTracker(funding_interval_hours=8.0, skew_factor=0.5, max_skew_bps=10.0)
+    assert bt.update(100.0, 100.2) is None  # no funding rate yet
+    assert bt.basis == pytest.approx(0.2)
+    assert bt.basis_bps == pytest.approx(20.0)
+    assert bt.annualized_basis == pytest.approx(0.002 * 3 * 365)
+    assert bt.fair_value is None
+
+    bt.set_funding(0.0001)
+    assert bt.annualized_funding == pytest.approx(0.0001 * 3 * 365)
+    assert bt.fair_value == pytest.approx(100.01)
+    skew = bt.update(100.0, 100.03)
+    premium = (100.03 - 100.01) / 100.01 * 10_000
+    assert bt.premium_bps == pytest.approx(premium)
+    assert skew == bt.skew_bps == pytest.approx(-0.5 * premium)
+
+    # Rich perp: skew is capped
+    assert bt.update(100.0, 101.0) == -10.0
+    # Cheap perp leans long
+    assert bt.update(100.0, 99.5) == 10.0
+
+
+@needs_mm
+def test_basis_tracker_time_to_funding_and_smoothing():
+    bt = mm.BasisTracker(funding_interval_hours=8.0, halflife_ms=1_000.0)
+    interval = 8 * 3_600_000
+    bt.set_funding(0.0002, next_funding_ts=interval)
+    bt.update(100.0, 100.0, ts=interval // 2)
+    # Half the interval left: half the funding is priced in
+    assert bt.fair_value == pytest.approx(100.01)
+    first = bt.premium_bps
+    bt.update(100.0, 100.1, ts=interval // 2 + 1_000)
+    raw = (100.1 - 100.01) / 100.01 * 10_000
+    assert first < bt.premium_bps < raw
+
+    with pytest.raises(ValueError):
+        bt.update(100.0, 100.0)  # ts required with halflife_ms
+    with pytest.raises(ValueError):
+        mm.BasisTracker(funding_interval_hours=0.0)
+    with pytest.raises(ValueError):
+        bt.set_funding(math.nan)
+
+    bt.reset()
+    assert (bt.basis, bt.funding_rate, bt.skew_bps) == (None, None, None)
+
+
+@needs_mm
+def test_requote_gate_interval_improvement_and_rate():
+    gate = mm.RequoteGate(min_interval_ms=100, min_improvement_bps=2.0, max_amends=3, per_ms=1_000)
+    assert gate.should_send("BTC", 100.0, 101.0, ts=0)          # first quote
+    assert gate.check("BTC", 100.5, 101.0, ts=50) == "interval"
+    assert gate.check("BTC", 100.01, 101.0, ts=200) == "improvement"   # 1 bp
+    assert not gate.should_send("BTC", 100.01, 101.0, ts=200)
+    assert gate.should_send("BTC", 100.05, 101.0, ts=200)      # 5 bps
+    assert gate.last_quote("BTC") == (100.05, 101.0)
+    # Pulling a side always counts as a change; symbols are independent
+    assert gate.should_send("BTC", 100.05, None, ts=300)
+    assert gate.should_send("ETH", 10.0, 10.1, ts=300)
+    # Fourth send within 1s hits the rate limit until the first ages out
+    assert gate.check("BTC", 99.0, 100.0, ts=400) == "rate"
+    assert gate.should_send("BTC", 99.0, 100.0, ts=1_000)
+    assert gate.suppressed() == {"improvement": 1}
+
+    gate.record("BTC", 98.0, 99.0, ts=1_010)                     # forced requote
+    assert gate.check("BTC", 97.0, 98.0, ts=1_050) == "interval"
+    gate.reset("BTC")
+    assert gate.last_quote("BTC") is None
+    assert gate.should_send("BTC", 97.0, 98.0, ts=1_050)
+
+    clock = mm.ReplayClock(0)
+    gate = mm.RequoteGate(clock=clock)
+    assert gate.should_send("BTC", 100.0, 101.0)
+    clock.set(50)
+    assert not gate.should_send("BTC", 99.0, 100.0)
+    with pytest.raises(ValueError):
+        mm.RequoteGate().should_send("BTC", 100.0, 101.0)
+    with pytest.raises(ValueError):
+        mm.RequoteGate(max_amends=0)
+
+
+@needs_mm
+def test_glft_quoter_closed_form():
+    gamma, a, k, delta, sigma = 0.05, 2.0, 1.5, 1.0, 0.8
+    glft = mm.GlftQuoter(gamma=gamma, a=a, k=k, delta=delta)
+    c1 = math.log(1 + gamma * delta / k) / (gamma * delta)
+    c2 = math.sqrt(gamma / (2 * a * delta * k) * (1 + gamma * delta / k) ** (k / (gamma * delta) + 1))
+    half, skew = glft.half_spread_and_skew(sigma)
+    assert half == pytest.approx(c1 + delta / 2 * sigma * c2)
+    assert skew == pytest.approx(sigma * c2)
+
+    q = glft.quote(100.0, inventory=3.0, volatility=sigma)
+    assert q.bid_price == pytest.approx(100.0 - (half + 3 * skew))
+    assert q.ask_price == pytest.approx(100.0 + (half - 3 * skew))
+    assert q.spread == pytest.approx(2 * half) and glft.xi == gamma
+    # Long inventory shifts both quotes down; flat quotes are symmetric
+    flat = glft.quote(100.0, 0.0, sigma)
+    assert flat.ask_price - 100.0 == pytest.approx(100.0 - flat.bid_price)
+
+    book = mm.L2Book()
+    book.apply_snapshot([(99.0, 1.0)], [(101.0, 1.0)])
+    assert glft.quote_book(book, 0.0, sigma).bid_price == pytest.approx(flat.bid_price)
+    with pytest.raises(ValueError):
+        mm.GlftQuoter(gamma=0.1, a=0.0, k=1.0)
+
+    # Attribute writes are checked like the constructor and leave the quoter intact
+    for name in ("gamma", "a", "k", "delta"):
+        for bad in (0.0, -2.0, float("nan")):
+            with pytest.raises(ValueError, match=name):
+                setattr(glft, name, bad)
+    assert glft.quote(100.0, 0.0, sigma).bid_price == pytest.approx(flat.bid_price)
+    with pytest.raises(ValueError, match="xi"):
+        mm.GlftQuoter(gamma=0.1, a=1.0, k=1.0, xi=-1.0)
+    glft.delta = 2.0
+    assert glft.delta == 2.0 and glft.quote(100.0, 0.0, sigma).spread != pytest.approx(q.spread)
+
+
+@needs_mm
+def test_glft_fit_intensity_recovers_exponential():
+    # 10_000 fills over 100 time units with exponentially distributed depth
+    n, duration, k = 10_000, 100.0, 2.0
+    distances = [-math.log(1 - (i + 0.5) / n) / k for i in range(n)]
+    fit_a, fit_k = mm.GlftQuoter.fit_intensity(distances, duration)
+    assert fit_a == pytest.approx(n / duration, rel=0.05)
+    assert fit_k == pytest.approx(k, rel=0.05)
+
+    glft = mm.GlftQuoter(gamma=0.1, a=1.0, k=1.0)
+    assert glft.fit(distances, duration) is not None and glft.k == pytest.approx(k, rel=0.05)
+    assert mm.GlftQuoter.fit_intensity([0.5], duration) is None
+
+
+@needs_mm
+def test_intensity_estimator_rolling_fit():
+    est = mm.IntensityEstimator(window_ms=100_000, min_samples=100)
+    assert est.fit() is None
+    # 4 trades per second on each side, decaying with k=2 for buys, k=4 for sells
+    n = 400
+    for i in range(n):
+        u = (i + 0.5) / n
+        ts = i * 250
+        est.on_trade(100.0 - math.log(1 - u) / 2.0, 100.0, "buy", ts=ts)
+        est.add_distance(-math.log(1 - u) / 4.0, "sell", ts=ts + 125)
+    a, k = est.fit("buy")
+    assert a == pytest.approx(4.0, rel=0.1) and k == pytest.approx(2.0, rel=0.1)
+    assert est.fit("sell")[1] == pytest.approx(4.0, rel=0.1)
+    assert est.fit() is not None                    # both sides pooled
+
+    glft = mm.GlftQuoter(gamma=0.1, a=1.0, k=1.0)
+    est.apply(glft, "buy")
+    assert glft.k == pytest.approx(k)
+    # Trades older than the window drop out
+    est.advance(ts=400_000)
+    assert len(est) == 0 and est.fit() is None
+    with pytest.raises(ValueError):
+        mm.IntensityEstimator(levels=1)
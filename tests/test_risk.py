@@ -3,6 +3,15 @@ from src.common.config import Config
 from src.risk.risk_manager import RiskManager
 from src.common.models import Side
 
+import pytest
+
+try:
+	import mm_orderbook as mm
+except ImportError:
+	mm = None
+
+needs_mm = pytest.mark.skipif(mm is None, reason="mm_orderbook is not built")
+
 
 def make_config() -> Config:
 	from src.common.config import get_config
@@ -36,3 +45,570 @@ def test_position_exposure_cap():
 	ok, reason = rm.can_place_order(symbol, Side.BUY, size, price)
 	assert not ok
 
+
+# ---- Rust-backed risk and position components (mm_orderbook) ----
+
+
+@needs_mm
+def test_risk_guard_trips_and_latches():
+    guard = mm.RiskGuard(max_loss=100.0, max_drawdown=50.0, max_feed_age_ms=1000)
+    guard.on_feed(0)
+
+    guard.update_pnl(80.0)
+    guard.update_pnl(40.0, unrealized=-10.0)
+    assert guard.drawdown == pytest.approx(50.0)
+    assert guard.evaluate(500) is True
+    assert "drawdown" in guard.trip_reason
+
+    # Latched until reset, even if the condition clears
+    guard.update_pnl(80.0)
+    assert guard.evaluate(500) is True
+
+    guard.reset()
+    assert not guard.halted
+    assert guard.evaluate(2000) is True
+    assert "stale" in guard.trip_reason
+
+
+@needs_mm
+def test_risk_guard_reject_rate_needs_min_orders():
+    guard = mm.RiskGuard(max_reject_rate=0.5, min_orders=4)
+    for rejected in (True, True, False):
+        guard.record_order(rejected)
+    assert guard.evaluate() is False
+
+    guard.record_order(True)
+    assert guard.reject_rate == pytest.approx(0.75)
+    assert guard.evaluate() is True
+
+
+@pytest.mark.parametrize("mode,avg,realized", [("average", 105.0, 15.0), ("fifo", 110.0, 20.0)])
+@needs_mm
+def test_position_cost_modes(mode, avg, realized):
+    pos = mm.Position(mode)
+    pos.on_fill(100.0, 1.0, "buy", fee=0.1)
+    pos.on_fill(110.0, 1.0, "buy")
+    pos.on_fill(120.0, 1.0, "sell")
+
+    assert pos.net_qty == 1.0
+    assert pos.avg_price == pytest.approx(avg)
+    assert pos.realized_pnl == pytest.approx(realized)
+    assert pos.total_pnl(120.0) == pytest.approx(30.0 - 0.1)
+
+    # Flip through zero: the remainder opens short at the fill price
+    pos.on_fill(90.0, 3.0, "sell")
+    assert pos.net_qty == -2.0
+    assert pos.avg_price == pytest.approx(90.0)
+    assert pos.unrealized_pnl(85.0) == pytest.approx(10.0)
+
+
+@needs_mm
+def test_fee_model_tiers_and_position_integration():
+    fees = mm.FeeModel(1.0, 5.0, tiers=[(1e6, -0.5, 4.0), (1e7, -1.0, 3.0)])
+    assert fees.fee_for(100.0, 10.0, "maker") == pytest.approx(0.1)
+    assert fees.fee_for(100.0, 10.0, "taker") == pytest.approx(0.5)
+    assert fees.tier_index == 0
+
+    # Second tier pays a maker rebate
+    fees.volume_30d = 2e6
+    assert fees.tier_index == 1
+    assert fees.fee_for(100.0, 10.0, "maker") == pytest.approx(-0.05)
+
+    pos = mm.Position(fee_model=fees)
+    assert pos.on_fill(100.0, 1.0, "buy", liquidity="maker") == pytest.approx(-0.005)
+    assert pos.on_fill(101.0, 1.0, "sell", fee=0.2) == 0.2
+    assert pos.fees == pytest.approx(0.195)
+
+    with pytest.raises(ValueError):
+        mm.Position().on_fill(100.0, 1.0, "buy", liquidity="taker")
+
+
+@needs_mm
+def test_rate_limiter_multi_bucket():
+    import threading
+    import time
+
+    rl = mm.RateLimiter([(2, 0.1), (100, 60.0)])
+    assert rl.try_acquire() and rl.try_acquire()
+    assert rl.try_acquire() is False
+    assert 0.0 < rl.time_until_available() <= 0.05 + 1e-6
+    time.sleep(0.06)
+    assert rl.try_acquire()
+    # Weight larger than a bucket's capacity can never be served
+    assert rl.time_until_available(5) == float("inf")
+
+    # A failed acquire charges no bucket
+    rl.set_used(1, 99.5)
+    assert rl.try_acquire(weights=[0, 2]) is False
+    assert rl.available()[1] < 1.0
+
+    shared = mm.RateLimiter([(1000, 100.0)])
+    granted = []
+
+    def worker():
+        granted.append(sum(shared.try_acquire() for _ in range(500)))
+
+    threads = [threading.Thread(target=worker) for _ in range(4)]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+    assert sum(granted) == 1000
+
+
+@needs_mm
+def test_order_manager_lifecycle_and_races():
+    om = mm.OrderManager(ack_timeout_ms=1000)
+    om.submit("a1", "BTCUSDT", "buy", 100.0, 2.0, 0)
+    om.submit("a2", "BTCUSDT", "sell", 101.0, 1.0, 0)
+    om.submit("e1", "ETHUSDT", "buy", 10.0, 1.0, 0)
+    assert om.in_flight == 3
+
+    assert om.on_ack("a1", 5, exchange_id="X1") is True
+    assert om.on_fill("a1", 0.5, 100.0, 10) == "PARTIALLY_FILLED"
+    assert om.on_fill("a1", 1.5, 99.0, 11) == "FILLED"
+    assert om.order("a1").avg_fill_price == pytest.approx(99.25)
+    # Fill can beat the ack; the late ack is then ignored
+    assert om.on_fill("a2", 0.4, 101.0, 12) == "PARTIALLY_FILLED"
+    assert om.on_ack("a2", 13) is False
+    assert om.state("a2") == "PARTIALLY_FILLED"
+
+    with pytest.raises(mm.InvalidTransitionError):
+        om.on_fill("a1", 0.1, 100.0, 20)
+    with pytest.raises(ValueError):
+        om.on_fill("a2", 5.0, 101.0, 20)
+    with pytest.raises(KeyError):
+        om.on_ack("nope", 20)
+
+    open_btc = om.open_orders(symbol="BTCUSDT")
+    assert [o.client_id for o in open_btc] == ["a2"]
+    assert open_btc[0].remaining == pytest.approx(0.6)
+    assert [o.client_id for o in om.open_orders(side="buy")] == ["e1"]
+    assert om.open_orders(price=10.0)[0].symbol == "ETHUSDT"
+
+    # e1 was never acked: its submit times out
+    assert om.timed_out(999) == []
+    assert om.timed_out(1000) == [("e1", "submit", 0)]
+    om.on_reject("e1", 1001)
+
+    assert om.request_cancel("a2", 1100) is True
+    assert om.request_cancel("a2", 1101) is False
+    assert om.timed_out(2100) == [("a2", "cancel", 1100)]
+    assert om.on_canceled("a2", 2200) is True
+    assert om.on_canceled("a2", 2201) is False
+
+    assert om.open_orders() == []
+    assert om.purge_terminal() == 3
+    assert len(om) == 0
+
+
+@needs_mm
+def test_stp_checker_against_open_orders():
+    om = mm.OrderManager()
+    om.submit("s1", "BTCUSDT", "sell", 101.0, 1.0, 0)
+    om.submit("s2", "BTCUSDT", "sell", 100.5, 1.0, 0)
+    om.submit("b1", "BTCUSDT", "buy", 99.0, 1.0, 0)
+    om.submit("x1", "ETHUSDT", "sell", 50.0, 1.0, 0)
+
+    stp = mm.StpChecker("reprice", tick_size=0.1)
+    d = stp.check(om, "BTCUSDT", "buy", 101.0)
+    assert d.action == "reprice"
+    assert d.conflicts == ["s2", "s1"]
+    assert d.price == pytest.approx(100.4)
+
+    # Below our lowest ask, and other symbols are ignored
+    assert stp.check(om, "BTCUSDT", "buy", 100.4).action == "none"
+
+    d = mm.StpChecker("reject").check(om, "BTCUSDT", "sell", 98.0)
+    assert (d.action, d.conflicts, d.price) == ("reject", ["b1"], None)
+
+    om.on_canceled("b1", 1)
+    assert mm.StpChecker().check(om, "BTCUSDT", "sell", 98.0).action == "none"
+
+    with pytest.raises(ValueError):
+        mm.StpChecker("reprice")
+
+
+@needs_mm
+def test_order_diff_minimal_actions():
+    resting = [
+        ("b1", "buy", 99.0, 1.0),       # stays
+        ("b2", "buy", 98.0, 1.0),       # size down
+        ("b3", "buy", 97.0, 1.0),       # moved to 96.5
+        ("a1", "sell", 101.0, 1.0),     # no longer wanted
+    ]
+    bids = [(99.0, 1.0), (98.0, 0.5), (96.5, 1.0)]
+    diff = mm.OrderDiff()
+    actions = [(a.action, a.side, a.client_id, a.price, a.qty)
+               for a in diff.diff(resting, bids, [])]
+    assert actions == [
+        ("cancel", "sell", "a1", 101.0, 1.0),
+        ("amend_size", "buy", "b2", 98.0, 0.5),
+        ("amend_price", "buy", "b3", 96.5, 1.0),
+    ]
+
+    # Size increases lose priority here, and price amends are unsupported
+    strict = mm.OrderDiff(amend_price=False, amend_size_up=False)
+    actions = [(a.action, a.client_id, a.price, a.qty)
+               for a in strict.diff(resting, [(99.0, 2.0), (98.0, 0.5), (95.0, 1.0)], [])]
+    assert actions == [
+        ("cancel", "b1", 99.0, 1.0),
+        ("cancel", "b3", 97.0, 1.0),
+        ("cancel", "a1", 101.0, 1.0),
+        ("amend_size", "b2", 98.0, 0.5),
+        ("place", None, 99.0, 2.0),
+        ("place", None, 95.0, 1.0),
+    ]
+    # Tolerances absorb small differences
+    assert mm.OrderDiff(price_tolerance=0.05, size_tolerance=0.01).diff(
+        [("b1", "buy", 99.0, 1.0)], [(99.02, 1.005)], []) == []
+
+    om = mm.OrderManager()
+    om.submit("b1", "BTCUSDT", "buy", 99.0, 1.0, 0)
+    om.submit("b2", "BTCUSDT", "buy", 98.0, 1.0, 0)
+    om.on_fill("b1", 0.4, 99.0, 1)
+    om.request_cancel("b2", 1)
+    actions = diff.diff_orders(om, "BTCUSDT", [(99.0, 1.0)], [(101.0, 1.0)])
+    assert [(a.action, a.client_id, a.qty) for a in actions] == [
+        ("amend_size", "b1", 1.0), ("place", None, 1.0)]
+
+
+@needs_mm
+def test_markout_analyzer_horizons_and_groups():
+    mk = mm.MarkoutAnalyzer(horizons_ms=[100, 1_000], tod_bucket_minutes=60)
+    assert mk.horizons_ms == [100, 1_000]
+    base = 13 * 3_600_000  # 13:00 UTC
+    mk.on_mid(base, 100.0)
+    mk.add_fill(base, "buy", 99.99, 1.0, level=0)
+    mk.add_fill(base + 10, "sell", 100.01, 2.0, level=1)
+    assert mk.pending == 2
+
+    assert mk.on_mid(base + 50, 100.02) == 0
+    # The 100ms horizon of the buy resolves as-of base + 100 (mid 100.02)
+    assert mk.on_mid(base + 105, 99.98) == 0
+    assert mk.on_mid(base + 2_000, 100.5) == 2
+    assert mk.pending == 0
+
+    rows = mk.take_markouts()
+    assert [r[:5] for r in rows] == [(base, "buy", 99.99, 1.0, 0), (base + 10, "sell", 100.01, 2.0, 1)]
+    buy_100, buy_1s = rows[0][5]
+    sell_100, sell_1s = rows[1][5]
+    assert buy_100 == pytest.approx((100.02 - 99.99) / 99.99 * 10_000)
+    assert buy_1s == pytest.approx((99.98 - 99.99) / 99.99 * 10_000)
+    assert sell_100 == pytest.approx((100.01 - 99.98) / 100.01 * 10_000)
+    assert sell_1s == pytest.approx((100.01 - 99.98) / 100.01 * 10_000)
+    assert mk.take_markouts() == []
+
+    by_side = mk.summary()
+    assert by_side["buy"][0] == (pytest.approx(buy_100), 1)
+    by_level = mk.summary("level")
+    assert set(by_level) == {0, 1}
+    by_tod = mk.summary("tod")
+    assert list(by_tod) == [13 * 60]
+    assert by_tod[13 * 60][1][1] == 2
+    with pytest.raises(ValueError):
+        mk.summary("venue")
+
+
+@needs_mm
+def test_markout_analyzer_late_fills_use_history():
+    mk = mm.MarkoutAnalyzer(horizons_ms=[100], history_ms=5_000)
+    book = mm.L2Book()
+    book.apply_snapshot([(99.0, 1.0)], [(101.0, 1.0)])
+    mk.update(book, 0)
+    book.apply_snapshot([(101.0, 1.0)], [(103.0, 1.0)])
+    mk.update(book, 200)
+    mk.on_mid(1_000, 110.0)
+
+    # Reported late: resolves immediately against the mid as of ts 100
+    mk.add_fill(0, "buy", 100.0, 1.0)
+    (row,) = mk.take_markouts()
+    assert row[5] == [pytest.approx(0.0)]
+
+    # Older than the history: the horizon cannot be measured
+    mk.on_mid(20_000, 110.0)
+    mk.add_fill(0, "sell", 100.0, 1.0)
+    assert mk.take_markouts()[0][5] == [None]
+
+    mk.reset()
+    assert mk.summary() == {} and mk.pending == 0
+    with pytest.raises(ValueError):
+        mm.MarkoutAnalyzer(horizons_ms=[])
+    with pytest.raises(ValueError):
+        mk.add_fill(0, "buy", 0.0, 1.0)
+
+
+@needs_mm
+def test_order_id_gen_increasing_parseable_and_restart_safe():
+    import threading
+
+    clock = mm.ReplayClock(1_704_067_200_000 + 5_000)
+    gen = mm.OrderIdGen("mm1-", clock=clock)
+    a, b = gen.next(), gen.next()
+    assert a.startswith("mm1-") and len(a) == gen.id_len <= 36 and a < b
+    assert gen.parse(a) == ("mm1-", 1_704_067_205_000, 0)
+    assert gen.parse(b) == ("mm1-", 1_704_067_205_000, 1)
+    clock.advance(1)
+    assert gen.parse(gen.next())[1:] == (1_704_067_205_001, 0)
+
+    # A restarted generator resumes above the newest known ID even if the
+    # clock went back
+    clock.set(1_704_067_200_000)
+    fresh = mm.OrderIdGen("mm1-", clock=clock)
+    fresh.resume_after(b)
+    assert fresh.next() > b
+    with pytest.raises(ValueError):
+        fresh.parse("other-" + b[4:])
+
+    # Strictly increasing and unique across threads on the wall clock
+    live = mm.OrderIdGen("t")
+    ids = []
+    lock = threading.Lock()
+
+    def worker():
+        mine = [live.next() for _ in range(2_000)]
+        assert mine == sorted(mine)
+        with lock:
+            ids.extend(mine)
+
+    threads = [threading.Thread(target=worker) for _ in range(4)]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+    assert len(set(ids)) == 8_000
+    assert len(live.next_batch(3)) == 3
+
+    with pytest.raises(ValueError):
+        mm.OrderIdGen("bad prefix!")
+    with pytest.raises(ValueError):
+        mm.OrderIdGen("x" * 30)
+
+
+@needs_mm
+def test_pnl_attribution_splits_pnl_per_symbol_and_hour():
+    hour = 3_600_000
+    pnl = mm.PnlAttribution(fee_model=mm.FeeModel(maker_bps=-1.0, taker_bps=5.0))
+    pnl.on_mark("BTC", 0, 100.0)
+    assert pnl.on_fill("BTC", 1_000, 99.9, 1.0, "buy", liquidity="maker") == pytest.approx(0.1)
+    pnl.on_marks("BTC", [(2_000, 100.5), (hour - 1, 101.0)])
+    pnl.on_fill("BTC", hour + 5, 101.1, 1.0, "sell", mid=101.0, fee=0.05)
+    pnl.on_fill("ETH", 10, 10.0, 2.0, "sell")       # no mark yet: mid = price
+
+    first, second = pnl.report("BTC")
+    assert (first.start_ms, second.start_ms) == (0, hour)
+    assert first.spread == pytest.approx(0.1) and first.inventory == pytest.approx(1.0)
+    assert first.rebates == pytest.approx(99.9 * 1e-4) and first.fees == 0.0
+    assert first.realized == 0.0 and first.fills == 1
+    assert second.spread == pytest.approx(0.1) and second.inventory == 0.0
+    assert second.fees == pytest.approx(0.05) and second.realized == pytest.approx(1.2)
+
+    # Flat again: the components add up to realized PnL net of fees
+    total = pnl.totals("BTC")
+    assert total.total == pytest.approx(total.realized - total.fees + total.rebates)
+    assert total.fills == 2 and total.volume == pytest.approx(201.0)
+    assert pnl.positions() == {"BTC": 0.0, "ETH": -2.0}
+    assert pnl.totals().fills == 3
+
+    cols = pnl.columns()
+    assert cols["symbol"] == ["BTC", "BTC", "ETH"]
+    assert cols["total"] == pytest.approx([b.total for b in pnl.report()])
+    pnl.reset()
+    assert pnl.report() == []
+    with pytest.raises(ValueError):
+        mm.PnlAttribution().on_fill("BTC", 0, 100.0, 1.0, "buy", liquidity="maker")
+
+
+@needs_mm
+def test_equity_tracker_drawdown_and_time_under_water():
+    eq = mm.EquityTracker(window_ms=10_000)
+    for ts, e in [(0, 100.0), (1_000, 110.0), (2_000, 99.0), (3_000, 104.5), (4_000, 112.0), (5_000, 111.0)]:
+        eq.record(e, ts)
+    assert eq.peak == 112.0 and eq.equity == 111.0 and len(eq) == 6
+    assert eq.drawdown == 1.0 and eq.drawdown_pct == pytest.approx(1.0 / 112.0)
+    assert eq.max_drawdown == 11.0 and eq.max_drawdown_pct == pytest.approx(0.1)
+    assert eq.time_under_water_ms == 1_000 and eq.max_time_under_water_ms == 2_000
+    with pytest.raises(ValueError):
+        eq.record(100.0, 4_999)
+
+
+@needs_mm
+def test_equity_tracker_sharpe_sortino_over_window():
+    import math
+    import statistics
+
+    pnl = mm.EquityTracker(window_ms=3_000, capital=1_000.0)
+    assert pnl.sharpe() is None
+    curve = [0.0, 10.0, 5.0, 20.0, 15.0, 30.0]
+    for i, e in enumerate(curve):
+        pnl.record(e, i * 1_000)
+    # Last 3s: samples 2..5, returns +15, -5, +15 over capital, 1s apart
+    r = [0.015, -0.005, 0.015]
+    scale = math.sqrt(365 * 86_400)
+    assert pnl.sharpe() == pytest.approx(statistics.mean(r) / statistics.stdev(r) * scale)
+    assert pnl.sortino() == pytest.approx(statistics.mean(r) / math.sqrt(0.005 ** 2 / 3) * scale)
+    assert pnl.sharpe(window_ms=100_000) is not None
+    with pytest.raises(ValueError):
+        mm.EquityTracker().record(0.0)              # no clock, no ts
+
+    np = pytest.importorskip("numpy")
+    arr = pnl.to_array()
+    assert arr.shape == (6, 3)
+    assert np.allclose(arr[:, 1], curve) and arr[2, 2] == 5.0
+
+
+@needs_mm
+def test_limit_book_symbol_and_portfolio_limits():
+    book = mm.LimitBook(default=mm.SymbolLimits(max_order_qty=5.0),
+                        max_gross_notional=1_000.0, max_net_notional=600.0)
+    book.set_limits("BTC", mm.SymbolLimits(max_position=3.0, max_notional=500.0,
+                                           max_order_qty=2.0, max_open_orders=2))
+    assert book.limits("ETH").max_order_qty == 5.0
+    assert book.check("BTC", "buy", 100.0, 1.0)
+    d = book.check("BTC", "buy", 100.0, 2.5)
+    assert not d.allowed and "max_order_qty" in d.reason
+
+    # Open orders count towards the per-side cap and the worst-case position
+    om = mm.OrderManager()
+    om.submit("b1", "BTC", "buy", 99.0, 1.0, 0)
+    book.set_position("BTC", 1.5, 100.0)
+    assert "max_position" in book.check("BTC", "buy", 100.0, 1.0, om).reason
+    assert book.check("BTC", "buy", 100.0, 0.5, om)
+    assert book.check("BTC", "sell", 100.0, 2.0, om)        # reduces the position
+    om.submit("b2", "BTC", "buy", 98.0, 0.1, 0)
+    assert "max_open_orders" in book.check("BTC", "buy", 100.0, 0.1, om).reason
+    assert "max_notional" in book.check("BTC", "buy", 250.0, 1.0).reason   # 2.5 * 250
+
+    # Portfolio: ETH adds 400 long, BTC is 150 long
+    book.set_position("ETH", 2.0, 200.0)
+    assert book.gross_notional == pytest.approx(550.0)
+    assert book.exposures() == {"BTC": 150.0, "ETH": 400.0}
+    d = book.check("ETH", "buy", 200.0, 0.5)                 # net 150 + 500
+    assert "max_net_notional" in d.reason
+    book.set_position("BTC", -1.5, 100.0)
+    assert book.net_notional == pytest.approx(250.0)
+    d = book.check("ETH", "buy", 200.0, 2.5)                 # gross 150 + 900
+    assert "max_gross_notional" in d.reason
+    with pytest.raises(ValueError):
+        book.check("BTC", "buy", 100.0, 0.0)
+
+
+@needs_mm
+def test_price_band_guard_moves_reference_and_cooldown():
+    guard = mm.PriceBandGuard(max_move_bps=100.0, window_ms=1_000, max_deviation_bps=200.0,
+                              reference_max_age_ms=5_000, cooldown_ms=10_000)
+    assert not guard.on_mid("BTC", 100.0, ts=0)
+    assert not guard.on_mid("BTC", 100.9, ts=500)
+    # 1.5% above the window low of 100
+    assert guard.on_mid("BTC", 101.5, ts=900)
+    assert "max_move_bps" in guard.reason("BTC")
+    assert guard.halted_symbols(ts=1_000) == ["BTC"]
+    assert not guard.on_mid("ETH", 10.0, ts=1_000)
+    # Once 100 and 100.9 age out the move is within the band, so the halt
+    # ends cooldown_ms after the breach
+    assert guard.on_mid("BTC", 101.6, ts=2_000)
+    assert not guard.is_halted("BTC", ts=10_900)
+
+    guard.set_reference("ETH", 10.0, ts=11_000)
+    assert guard.on_mid("ETH", 10.3, ts=11_000)
+    assert "reference" in guard.reason("ETH")
+    guard.reset("ETH")
+    # A stale reference is ignored
+    assert not guard.on_mid("ETH", 10.3, ts=17_000)
+
+    guard.halt("BTC", "bad index print", ts=20_000)
+    assert guard.is_halted("BTC", ts=25_000) and guard.reason("BTC") == "bad index print"
+    guard.reset()
+    assert guard.halted_symbols(ts=25_000) == []
+    with pytest.raises(ValueError):
+        mm.PriceBandGuard().on_mid("BTC", 100.0)
+
+
+@needs_mm
+def test_reject_monitor_trips_risk_guard():
+    guard = mm.RiskGuard()
+    mon = mm.RejectMonitor(window_ms=1_000, max_timeouts=2, max_reject_rate=0.5,
+                           min_orders=4, guard=guard)
+    for ts in range(3):
+        assert not mon.on_order(ts)
+    assert not mon.on_reject(3)
+    assert not mon.on_reject(4)          # 2/3 rejected, but below min_orders
+    assert not mon.on_order(5)           # 2/4 is fine...
+    assert mon.counts() == (4, 2, 0, 0)
+    assert not guard.halted
+    assert mon.on_reject(6)              # ...3/4 is not
+    assert guard.halted and "reject rate" in guard.trip_reason
+    assert mon.last_breach == guard.trip_reason
+
+    # The window ages out; the guard stays latched until reset
+    assert not mon.evaluate(2_000) and mon.counts() == (0, 0, 0, 0)
+    assert guard.halted
+    guard.reset()
+    for ts in (2_100, 2_200):
+        assert not mon.on_timeout(ts)
+    assert mon.on_timeout(2_300) and "3 ack timeouts" in guard.trip_reason
+
+    clock = mm.ReplayClock(0)
+    solo = mm.RejectMonitor(max_cancel_rejects=0, clock=clock)
+    assert solo.guard is None and solo.on_cancel_reject()
+    with pytest.raises(ValueError):
+        mm.RejectMonitor().on_order()
+
+
+@needs_mm
+def test_hedger_sizes_clip_to_cost_budget_and_throttles():
+    book = mm.L2Book()
+    book.apply_snapshot([(99.95, 1.0), (99.9, 2.0), (99.8, 5.0)], [(100.05, 1.0), (100.1, 2.0)])
+    hedger = mm.Hedger(threshold=1.0, max_cost_bps=10.0, min_interval_ms=1_000)
+    assert hedger.compute(0.5, book, ts=0) is None              # within threshold
+    # Long 5: selling 3 reaches 99.9 (8.3bp); 0.5 more at 99.8 brings the
+    # average to 99.9, exactly 10bp
+    order = hedger.compute(5.0, book, ts=0)
+    assert order.side == "sell" and order.wanted == 5.0
+    assert order.qty == pytest.approx(3.5) and order.limit_price == 99.8
+    assert order.avg_price == pytest.approx(99.9) and order.cost_bps == pytest.approx(10.0)
+    assert hedger.compute(5.0, book, ts=500) is None            # throttled
+    assert not hedger.ready(500) and hedger.ready(1_000) and hedger.count == 1
+
+    # Short, hedged by buying; a clip cap and fees count
+    fees = mm.FeeModel(maker_bps=0.0, taker_bps=2.0)
+    hedger = mm.Hedger(threshold=0.0, target=0.0, max_clip=0.5, max_cost_bps=10.0, fee_model=fees)
+    order = hedger.compute(-3.0, book, ts=0)
+    assert order.side == "buy" and order.qty == 0.5 and order.limit_price == 100.05
+    assert order.cost_bps == pytest.approx(7.0)
+    assert order.fee == pytest.approx(100.05 * 0.5 * 2e-4)
+    # A hedge ratio maps inventory to hedge units; min_clip blocks dust
+    hedger = mm.Hedger(threshold=1.0, target=0.5, hedge_ratio=0.5, max_cost_bps=1.0, min_clip=0.1)
+    assert hedger.compute(4.0, book, ts=0) is None               # 5bp on the first unit
+    with pytest.raises(ValueError):
+        mm.Hedger(threshold=1.0, target=2.0)
+
+
+@needs_mm
+def test_portfolio_risk_exposures_and_parametric_var():
+    import math
+
+    cov = [[0.0004, 0.0003], [0.0003, 0.0009]]      # 2% and 3% vol, corr 0.5
+    risk = mm.PortfolioRisk(["BTC", "ETH"], covariance=cov, betas={"ETH": 1.5},
+                            confidence=0.99, var_budget=2_100.0)
+    assert risk.var == 0.0 and not risk.breached
+    risk.set_position("BTC", 1.0, 50_000.0)
+    risk.set_position("ETH", -10.0, 2_000.0)
+    assert risk.exposures() == [50_000.0, -20_000.0]
+    assert risk.gross_notional == 70_000.0 and risk.net_notional == 30_000.0
+    assert risk.beta_exposure == pytest.approx(50_000.0 - 1.5 * 20_000.0)
+
+    z = 2.3263478740
+    assert risk.var == pytest.approx(z * math.sqrt(760_000.0))
+    assert sum(risk.contributions()) == pytest.approx(risk.var)
+    # Buying 0.1 BTC more lifts VaR past the budget; selling reduces it
+    assert risk.var_with("BTC", 0.1) == pytest.approx(z * math.sqrt(910_000.0))
+    assert not risk.allows("BTC", 0.1) and risk.allows("BTC", -0.1)
+    risk.set_mark("BTC", 60_000.0)
+    assert risk.breached
+
+    assert mm.PortfolioRisk(["A"]).var is None
+    with pytest.raises(ValueError):
+        risk.set_covariance([[1.0, 0.5], [0.4, 1.0]])         # not symmetric
+    with pytest.raises(ValueError):
+        risk.set_position("SOL", 1.0, 100.0)
@@ -0,0 +1,208 @@
+"""
+Unit tests for the mm_orderbook SBE decoder.
+"""
+
+import struct
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+BINANCE_SCHEMA = """<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe" package="spot_stream"
+                   id="1" version="0" byteOrder="littleEndian">
+  <types>
+    <composite name="messageHeader">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="templateId" primitiveType="uint16"/>
+      <type name="schemaId" primitiveType="uint16"/>
+      <type name="version" primitiveType="uint16"/>
+    </composite>
+    <composite name="groupSize16Encoding">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="numInGroup" primitiveType="uint16"/>
+    </composite>
+    <composite name="varString8">
+      <type name="length" primitiveType="uint8"/>
+      <type name="varData" primitiveType="uint8" length="0" characterEncoding="UTF-8"/>
+    </composite>
+  </types>
+  <!-- diff depth: prices and quantities are mantissas scaled by the exponents -->
+  <sbe:message name="DepthDiffStreamEvent" id="10003">
+    <field name="eventTime" id="1" type="int64"/>
+    <field name="firstBookUpdateId" id="2" type="int64"/>
+    <field name="lastBookUpdateId" id="3" type="int64"/>
+    <field name="priceExponent" id="4" type="int8"/>
+    <field name="qtyExponent" id="5" type="int8"/>
+    <group name="bids" id="100" dimensionType="groupSize16Encoding">
+      <field name="price" id="1" type="int64"/>
+      <field name="qty" id="2" type="int64"/>
+    </group>
+    <group name="asks" id="101" dimensionType="groupSize16Encoding">
+      <field name="price" id="1" type="int64"/>
+      <field name="qty" id="2" type="int64"/>
+    </group>
+    <data name="symbol" id="200" type="varString8"/>
+  </sbe:message>
+</sbe:messageSchema>
+"""
+
+CME_SCHEMA = """<?xml version="1.0" encoding="UTF-8"?>
+<messageSchema package="mdp" id="2" version="9" byteOrder="bigEndian">
+  <types>
+    <composite name="messageHeader">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="templateId" primitiveType="uint16"/>
+      <type name="schemaId" primitiveType="uint16"/>
+      <type name="version" primitiveType="uint16"/>
+    </composite>
+    <composite name="groupSize">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="numInGroup" primitiveType="uint8"/>
+    </composite>
+    <composite name="PRICE9">
+      <type name="mantissa" primitiveType="int64"/>
+      <type name="exponent" primitiveType="int8" presence="constant">-9</type>
+    </composite>
+    <type name="Int32NULL" primitiveType="int32" presence="optional"/>
+    <type name="Symbol" primitiveType="char" length="6"/>
+    <enum name="MDUpdateAction" encodingType="uint8">
+      <validValue name="New">0</validValue>
+      <validValue name="Change">1</validValue>
+      <validValue name="Delete">2</validValue>
+    </enum>
+    <enum name="MDEntryTypeBook" encodingType="char">
+      <validValue name="Bid">0</validValue>
+      <validValue name="Offer">1</validValue>
+    </enum>
+    <set name="MatchEventIndicator" encodingType="uint8">
+      <choice name="LastTradeMsg">0</choice>
+      <choice name="LastQuoteMsg">2</choice>
+      <choice name="EndOfEvent">7</choice>
+    </set>
+  </types>
+  <message name="MDIncrementalRefreshBook" id="46">
+    <field name="transactTime" id="60" type="uint64"/>
+    <field name="matchEventIndicator" id="5799" type="MatchEventIndicator"/>
+    <field name="venue" id="207" type="Symbol"/>
+    <group name="noMDEntries" id="268" dimensionType="groupSize">
+      <field name="mdEntryPx" id="270" type="PRICE9"/>
+      <field name="mdEntrySize" id="271" type="Int32NULL"/>
+      <field name="rptSeq" id="83" type="uint32"/>
+      <field name="mdUpdateAction" id="279" type="MDUpdateAction"/>
+      <field name="mdEntryType" id="269" type="MDEntryTypeBook"/>
+      <field name="tradeableSize" id="5762" type="Int32NULL" sinceVersion="10"/>
+    </group>
+  </message>
+</messageSchema>
+"""
+
+INT32_NULL = -(2 ** 31)
+
+
+def binance_diff(first, last, bids, asks, symbol=b"BTCUSDT", px_exp=-2, qty_exp=-3):
+    root = struct.pack("<qqqbb", 1700000000000, first, last, px_exp, qty_exp)
+    out = struct.pack("<HHHH", len(root), 10003, 1, 0) + root
+    for levels in (bids, asks):
+        out += struct.pack("<HH", 16, len(levels))
+        out += b"".join(struct.pack("<qq", p, q) for p, q in levels)
+    return out + struct.pack("<B", len(symbol)) + symbol
+
+
+def cme_book(entries, indicator=0b10000100, entry_len=18, version=9):
+    # entry_len 18 omits tradeableSize (added in version 10); 22 includes it
+    root = struct.pack(">QB6s", 1700000000000000000, indicator, b"CME")
+    out = struct.pack(">HHHH", len(root), 46, 2, version) + root
+    out += struct.pack(">HB", entry_len, len(entries))
+    for px, size, seq, action, side, tradeable in entries:
+        entry = struct.pack(">qiIBc", px, size, seq, action, side)
+        if entry_len >= 22:
+            entry += struct.pack(">i", tradeable)
+        out += entry + b"\x00" * (entry_len - len(entry))
+    return out
+
+
+def test_decode_fields_groups_and_var_data():
+    dec = mm.SbeDecoder(BINANCE_SCHEMA)
+    assert dec.messages() == [(10003, "DepthDiffStreamEvent")]
+    wire = binance_diff(11, 13, [(10000, 1500)], [(10050, 250), (10100, 0)])
+    msg = dec.decode(wire + b"trailing")
+    assert (msg.name, msg.template_id, msg.schema_id, msg.version) == ("DepthDiffStreamEvent", 10003, 1, 0)
+    assert msg.length == len(wire)
+    f = msg.fields
+    assert (f["firstBookUpdateId"], f["lastBookUpdateId"], f["priceExponent"]) == (11, 13, -2)
+    assert f["bids"] == [{"price": 10000, "qty": 1500}]
+    assert f["asks"] == [{"price": 10050, "qty": 250}, {"price": 10100, "qty": 0}]
+    assert f["symbol"] == "BTCUSDT"
+
+    both = dec.decode_all(wire + binance_diff(14, 14, [], [], symbol=b"ETHUSDT"))
+    assert [m.fields["symbol"] for m in both] == ["BTCUSDT", "ETHUSDT"]
+
+    with pytest.raises(ValueError):
+        dec.decode(wire[:-3])                       # truncated var data
+    with pytest.raises(ValueError):
+        dec.decode(struct.pack("<HHHH", 0, 999, 1, 0))   # unknown template
+    with pytest.raises(ValueError):
+        mm.SbeDecoder("<messageSchema><message name='x' id='1'><field name='a' type='nope'/></message></messageSchema>")
+
+
+def test_enums_sets_composites_and_schema_evolution():
+    dec = mm.SbeDecoder(CME_SCHEMA)
+    msg = dec.decode(cme_book([(100_250_000_000, 5, 7, 0, b"0", 0),
+                               (100_500_000_000, INT32_NULL, 8, 2, b"1", 0)]))
+    f = msg.fields
+    assert f["matchEventIndicator"] == ["LastQuoteMsg", "EndOfEvent"] and f["venue"] == "CME"
+    e0, e1 = f["noMDEntries"]
+    assert e0["mdEntryPx"] == pytest.approx(100.25) and e0["mdEntrySize"] == 5
+    assert (e0["mdUpdateAction"], e0["mdEntryType"]) == ("New", "Bid")
+    assert e1["mdEntrySize"] is None and (e1["mdUpdateAction"], e1["mdEntryType"]) == ("Delete", "Offer")
+    # Older encoding: the field added in version 10 is past the entry block
+    assert e0["tradeableSize"] is None
+
+    # Newer encoding with a longer, padded entry block still decodes
+    newer = dec.decode(cme_book([(1_000_000_000, 3, 9, 1, b"0", 2)], entry_len=24, version=10))
+    (entry,) = newer.fields["noMDEntries"]
+    assert (entry["mdEntryPx"], entry["tradeableSize"], entry["rptSeq"]) == (1.0, 2, 9)
+
+
+def test_apply_binance_diffs_with_update_ids():
+    dec = mm.SbeDecoder(BINANCE_SCHEMA)
+    dec.map_book("DepthDiffStreamEvent", "price", "qty", bid_group="bids", ask_group="asks",
+                 price_exponent="priceExponent", size_exponent="qtyExponent",
+                 update_id="lastBookUpdateId", first_update_id="firstBookUpdateId")
+    book = mm.L2Book()
+    book.apply_snapshot([(100.0, 1.0)], [(100.5, 1.0), (101.0, 2.0)], update_id=10)
+
+    assert not dec.apply(book, binance_diff(5, 9, [(9000, 1000)], []))          # stale
+    assert dec.apply(book, binance_diff(9, 12, [(10000, 1500)], [(10100, 0)]))  # straddles 10
+    assert book.depth(5) == ([(100.0, 1.5)], [(100.5, 1.0)])
+    assert book.last_update_id == 12
+    assert dec.apply(book, binance_diff(13, 15, [(9950, 500)], [], px_exp=-2, qty_exp=-3))
+    assert book.depth(5)[0] == [(100.0, 1.5), (99.5, 0.5)]
+    update = dec.normalize(binance_diff(16, 16, [(9950, 0)], [(10100, 250)]), "binance")
+    assert (update.exchange, update.symbol, update.kind, update.update_id) == ("binance", "BTCUSDT", "delta", 16)
+    assert (update.bids, update.asks, update.applied) == ([(99.5, 0.0)], [(101.0, 0.25)], False)
+    assert book.last_update_id == 15   # normalize leaves books alone
+
+    assert not dec.apply(book, binance_diff(17, 18, [(9900, 1000)], []))        # gap
+    assert book.needs_resync and book.gap_count == 1
+
+    with pytest.raises(KeyError):
+        dec.map_book("Nope", "price", "qty", bid_group="bids", ask_group="asks")
+    with pytest.raises(ValueError):
+        dec.map_book("DepthDiffStreamEvent", "price", "qty", bid_group="bids")
+
+
+def test_apply_single_group_with_side_and_action():
+    dec = mm.SbeDecoder(CME_SCHEMA)
+    book = mm.L2Book()
+    assert not dec.apply(book, cme_book([(1_000_000_000, 3, 1, 0, b"0", 0)]))   # not mapped yet
+    dec.map_book("MDIncrementalRefreshBook", "mdEntryPx", "mdEntrySize", group="noMDEntries",
+                 side="mdEntryType", action="mdUpdateAction")
+    assert dec.apply(book, cme_book([(100_000_000_000, 3, 1, 0, b"0", 0),
+                                     (99_750_000_000, 4, 2, 0, b"0", 0),
+                                     (100_250_000_000, 2, 3, 0, b"1", 0)]))
+    assert book.depth(5) == ([(100.0, 3.0), (99.75, 4.0)], [(100.25, 2.0)])
+    assert dec.apply(book, cme_book([(100_000_000_000, INT32_NULL, 4, 2, b"0", 0),
+                                     (100_250_000_000, 6, 5, 1, b"1", 0)]))
+    assert book.depth(5) == ([(99.75, 4.0)], [(100.25, 6.0)])
@@ -0,0 +1,105 @@
+"""
+Tests for mm_orderbook.ShmBook: a book published through a memory-mapped
+file from a writer to read-only readers (same or another process).
+"""
+
+import multiprocessing as mp
+import sys
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+pytestmark = pytest.mark.skipif(sys.platform == "win32", reason="ShmBook needs a Unix platform")
+
+
+def test_writer_publishes_top_levels_to_readers(tmp_path):
+    path = str(tmp_path / "book")
+    writer = mm.ShmBook.create(path, levels=2, tick_size=0.5)
+    reader = mm.ShmBook.open(path)
+    assert writer.is_writer and not reader.is_writer and reader.levels == 2
+    assert reader.seq == 0 and reader.best_bid is None and reader.age_ms is None
+
+    writer.apply_snapshot([(100.0, 1.0), (99.5, 2.0), (99.0, 3.0)], [(101.0, 1.0)], update_id=1)
+    assert reader.seq == 1 and reader.last_update_id == 1
+    # Only `levels` per side are published; the writer keeps the rest
+    assert reader.bids(10) == [(100.0, 1.0), (99.5, 2.0)]
+    assert reader.depth(1) == ([(100.0, 1.0)], [(101.0, 1.0)])
+    assert (reader.mid(), reader.spread()) == (100.5, 1.0)
+    assert reader.age_ms is not None and reader.age_ms >= 0.0
+
+    writer.apply_delta([(100.0, 0.0)], [], update_id=2)
+    assert reader.bids(10) == [(99.5, 2.0), (99.0, 3.0)]
+    snap = reader.snapshot()
+    assert isinstance(snap, mm.L2Book) and snap.tick_size == 0.5
+    assert snap.last_update_id == 2 and snap.best_ask == (101.0, 1.0)
+
+    # Gap state is published with the levels
+    assert writer.apply_delta([], [(101.5, 1.0)], update_id=5) is False
+    assert reader.needs_resync and reader.gap_count == 1 and reader.seq == 3
+
+    with pytest.raises(RuntimeError, match="read-only"):
+        reader.apply_snapshot([], [])
+    with pytest.raises(mm.InvalidPriceError):
+        writer.apply_snapshot([(float("nan"), 1.0)], [])
+    writer.clear()
+    assert reader.best_bid is None and reader.last_update_id is None
+
+
+def test_create_replaces_a_live_file_without_truncating_it(tmp_path):
+    path = str(tmp_path / "book")
+    old = mm.ShmBook.create(path, levels=2)
+    old.apply_snapshot([(100.0, 1.0)], [(101.0, 1.0)], update_id=1)
+    reader = mm.ShmBook.open(path)
+    assert reader.best_bid == (100.0, 1.0)
+
+    new = mm.ShmBook.create(path, levels=4)
+    new.apply_snapshot([(200.0, 1.0)], [(201.0, 1.0)], update_id=7)
+    # The reader's mapping still points at the old, intact file, which is
+    # marked retired so nobody trades on it
+    with pytest.raises(RuntimeError, match="re-created"):
+        reader.best_bid
+    reopened = mm.ShmBook.open(path)
+    assert reopened.levels == 4 and reopened.best_bid == (200.0, 1.0)
+    assert sorted(p.name for p in tmp_path.iterdir()) == ["book"]
+
+
+def test_open_rejects_other_files(tmp_path):
+    other = tmp_path / "other"
+    other.write_bytes(b"\0" * 4096)
+    with pytest.raises(ValueError, match="not a ShmBook"):
+        mm.ShmBook.open(str(other))
+    with pytest.raises(FileNotFoundError):
+        mm.ShmBook.open(str(tmp_path / "missing"))
+    with pytest.raises(ValueError):
+        mm.ShmBook.create(str(tmp_path / "book"), levels=0)
+
+
+def _read_consistently(path, rounds, out):
+    reader = mm.ShmBook.open(path)
+    torn = 0
+    for _ in range(rounds):
+        bids, asks = reader.depth(3)
+        # The writer always publishes equal sizes on both sides
+        if bids and {s for _, s in bids} != {s for _, s in asks}:
+            torn += 1
+    out.put(torn)
+
+
+def test_readers_in_other_processes_never_see_torn_updates(tmp_path):
+    path = str(tmp_path / "book")
+    writer = mm.ShmBook.create(path, levels=3)
+    writer.apply_snapshot([(100.0, 0.0)], [], update_id=0)
+    ctx = mp.get_context("fork")
+    out = ctx.Queue()
+    procs = [ctx.Process(target=_read_consistently, args=(path, 20_000, out)) for _ in range(2)]
+    for p in procs:
+        p.start()
+    i = 0
+    while any(p.is_alive() for p in procs):
+        i += 1
+        levels = [(100.0 - k, float(i)) for k in range(3)]
+        writer.apply_snapshot(levels, [(101.0 + k, float(i)) for k in range(3)], update_id=i)
+    for p in procs:
+        p.join()
+    assert [out.get(), out.get()] == [0, 0]
@@ -0,0 +1,345 @@
+"""
+Unit tests for the Rust-backed simulation layer in mm_orderbook.
+"""
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+
+def make_sim(**kwargs):
+    sim = mm.SimExchange(**kwargs)
+    sim.apply_snapshot(
+        [(100.0, 2.0), (99.0, 3.0)], [(101.0, 1.0), (102.0, 4.0)], ts=0
+    )
+    return sim
+
+
+def test_sim_taker_fills_walk_the_book():
+    sim = make_sim(fee_model=mm.FeeModel(1.0, 5.0))
+    oid = sim.submit_market("buy", 3.0)
+    fills = sim.take_fills()
+    assert [(f.price, f.qty, f.liquidity) for f in fills] == [
+        (101.0, 1.0, "taker"),
+        (102.0, 2.0, "taker"),
+    ]
+    assert all(f.order_id == oid for f in fills)
+    assert fills[1].fee == pytest.approx(102.0 * 2.0 * 5e-4)
+
+    # Crossing limit: taker up to the limit price, remainder rests
+    oid = sim.submit_limit("sell", 99.5, 3.0)
+    assert [(f.price, f.qty) for f in sim.take_fills()] == [(100.0, 2.0)]
+    assert sim.open_orders() == [(oid, "sell", 99.5, 1.0)]
+
+    assert sim.submit_limit("buy", 101.0, 1.0, post_only=True) is None
+    assert sim.take_fills() == []
+
+
+def test_sim_maker_fills_respect_queue():
+    sim = make_sim()
+    oid = sim.submit_limit("buy", 100.0, 1.0)
+    assert sim.queue_position(oid) == (2.0, 0.0)
+
+    # 1.5 trades at our price: still 0.5 ahead of us
+    sim.on_trade(100.0, 1.5, ts=10)
+    assert sim.take_fills() == []
+    sim.on_trade(100.0, 1.0, ts=11)
+    fills = sim.take_fills()
+    assert [(f.order_id, f.qty, f.liquidity, f.ts) for f in fills] == [
+        (oid, 0.5, "maker", 11)
+    ]
+
+    # A print through our price fills the rest
+    sim.on_trade(99.0, 0.1, ts=12)
+    assert sum(f.qty for f in sim.take_fills()) == pytest.approx(0.5)
+    assert sim.open_orders() == []
+
+
+def test_sim_book_moving_through_resting_order():
+    sim = make_sim()
+    oid = sim.submit_limit("sell", 103.0, 1.0)
+    sim.apply_delta([(103.5, 2.0)], [], ts=20)
+    fills = sim.take_fills()
+    assert [(f.order_id, f.price, f.qty) for f in fills] == [(oid, 103.0, 1.0)]
+    assert sim.book.best_bid == (103.5, 2.0)
+
+    oid = sim.submit_limit("buy", 90.0, 1.0)
+    assert sim.cancel(oid) is True
+    assert sim.cancel(oid) is False
+
+
+class QuoteOnceStrategy:
+    """Joins the best bid once per symbol, then flattens on fill."""
+
+    def __init__(self):
+        self.quoted = set()
+        self.fills = []
+        self.trades = 0
+
+    def on_book(self, bt, symbol, ts):
+        if symbol in self.quoted:
+            return
+        bid = bt.book(symbol).best_bid
+        bt.exchange(symbol).submit_limit("buy", bid[0], 1.0)
+        self.quoted.add(symbol)
+
+    def on_trade(self, bt, symbol, ts, price, qty):
+        self.trades += 1
+
+    def on_fill(self, bt, symbol, fill):
+        self.fills.append((symbol, fill.side, fill.liquidity, bt.now))
+        if fill.side == "buy":
+            assert bt.position(symbol).net_qty == fill.qty
+            bt.exchange(symbol).submit_market("sell", fill.qty)
+
+
+def test_backtester_replays_symbols_in_time_order():
+    bt = mm.Backtester(fee_model=mm.FeeModel(0.0, 10.0))
+    # Loaded out of order on purpose
+    bt.add_trade("BTC", 30, 100.0, 5.0)
+    bt.add_snapshot("BTC", 10, [(100.0, 1.0)], [(101.0, 1.0)])
+    bt.add_snapshot("ETH", 20, [(10.0, 1.0)], [(11.0, 1.0)])
+    bt.add_delta("BTC", 40, [(100.0, 0.0), (100.5, 2.0)], [])
+    assert len(bt) == 4
+    assert bt.symbols() == ["BTC", "ETH"]
+
+    strat = QuoteOnceStrategy()
+    report = bt.run(strat)
+    assert report.events == 4
+    assert strat.trades == 1
+    # BTC bid fills when the trade at t=30 clears the queue ahead, then the
+    # strategy sells back at the bid with a taker fee
+    assert strat.fills == [
+        ("BTC", "buy", "maker", 30),
+        ("BTC", "sell", "taker", 30),
+    ]
+    btc = report.positions["BTC"]
+    assert btc.net_qty == 0.0
+    assert report.realized_pnl == pytest.approx(0.0)
+    assert report.fees == pytest.approx(100.0 * 10e-4)
+    assert report.total_pnl == pytest.approx(-0.1)
+    assert [s for s, _ in report.fills] == ["BTC", "BTC"]
+
+    # Unfilled ETH bid is still resting; running again starts clean
+    assert len(bt.exchange("ETH").open_orders()) == 1
+    again = bt.run(QuoteOnceStrategy())
+    assert [(f.price, f.qty) for _, f in again.fills] == [
+        (f.price, f.qty) for _, f in report.fills
+    ]
+
+
+def test_latency_model_distributions_are_seeded():
+    assert mm.LatencyModel("fixed", ms=3.0).samples(3) == [3.0, 3.0, 3.0]
+
+    normal = mm.LatencyModel("normal", ms=10.0, std_ms=2.0, seed=7)
+    draws = normal.samples(5000)
+    assert sum(draws) / len(draws) == pytest.approx(10.0, rel=0.02)
+    normal.reset()
+    assert normal.samples(5) == draws[:5]
+
+    lognormal = mm.LatencyModel("lognormal", ms=5.0, sigma=0.8, seed=1)
+    draws = sorted(lognormal.samples(5001))
+    assert draws[2500] == pytest.approx(5.0, rel=0.1)
+    assert min(draws) > 0.0
+
+    hist = mm.LatencyModel("empirical", samples=[1.0, 50.0], weights=[9.0, 1.0])
+    draws = hist.samples(2000)
+    assert set(draws) == {1.0, 50.0}
+    assert draws.count(50.0) / len(draws) == pytest.approx(0.1, abs=0.03)
+
+    with pytest.raises(ValueError):
+        mm.LatencyModel("empirical")
+    with pytest.raises(ValueError):
+        mm.LatencyModel("uniform")
+
+
+class LatencyProbe:
+    """Sends one market buy on the first book event and records what it saw."""
+
+    def __init__(self):
+        self.seen = []
+        self.fills = []
+
+    def on_book(self, bt, symbol, ts):
+        self.seen.append((ts, bt.now, bt.book(symbol).best_ask[0]))
+        if len(self.seen) == 1:
+            bt.submit_market(symbol, "buy", 1.0)
+
+    def on_fill(self, bt, symbol, fill):
+        self.fills.append((fill.price, fill.ts))
+
+
+def latency_backtest(**kwargs):
+    bt = mm.Backtester(**kwargs)
+    bt.add_snapshot("BTC", 0, [(100.0, 1.0)], [(101.0, 1.0)])
+    bt.add_delta("BTC", 5, [], [(101.0, 0.0), (102.0, 1.0)])
+    probe = LatencyProbe()
+    bt.run(probe)
+    return bt, probe
+
+
+def test_backtester_order_and_feed_latency():
+    _, probe = latency_backtest()
+    assert probe.fills == [(101.0, 0)]
+
+    # The market order lands after the ask moved away
+    _, probe = latency_backtest(order_latency=mm.LatencyModel("fixed", ms=10.0))
+    assert probe.fills == [(102.0, 10)]
+
+    # The strategy sees the snapshot late, through its own delayed book
+    bt, probe = latency_backtest(feed_latency=mm.LatencyModel("fixed", ms=20.0))
+    assert probe.seen == [(0, 20, 101.0), (5, 25, 102.0)]
+    assert probe.fills == [(102.0, 20)]
+    assert bt.pending == 0
+
+
+def test_sweep_runner_grid_over_native_template():
+    bt = mm.Backtester(fee_model=mm.FeeModel(-1.0, 5.0))
+    bt.add_snapshot("BTC", 0, [(100.0, 5.0)], [(100.2, 5.0)])
+    bt.add_trade("BTC", 1, 100.04, 1.0)
+    bt.add_trade("BTC", 2, 100.16, 1.0)
+    grid = {"size": [1.0, 2.0], "half_spread_bps": [5.0, 15.0], "tick_size": [0.01]}
+    res = mm.SweepRunner(threads=2).run(bt, "symmetric", grid)
+    # Sorted keys, last varying fastest
+    assert res["half_spread_bps"] == [5.0, 5.0, 15.0, 15.0]
+    assert res["size"] == [1.0, 2.0, 1.0, 2.0]
+    # 5bp around 100.1 quotes 100.04 / 100.16: both prints fill us, 1 unit
+    # each; 15bp quotes are never reached
+    assert res["fills"] == [2, 2, 0, 0]
+    assert res["realized_pnl"][0] == pytest.approx(0.12)
+    assert res["fees"][0] == pytest.approx(-200.2 * 1e-4)
+    assert res["total_pnl"][1] == pytest.approx(0.12 + 200.2 * 1e-4)
+    assert res["max_inventory"][:2] == [1.0, 1.0] and res["volume"][2] == 0.0
+    assert mm.SweepRunner().run(bt, "symmetric", grid) == res
+
+    flat = mm.SweepRunner().run(bt, "symmetric", {"max_position": [0.0]})
+    assert flat["fills"] == [0]
+    with pytest.raises(ValueError):
+        mm.SweepRunner().run(bt, "symmetric", {"spread": [1.0]})
+    with pytest.raises(ValueError):
+        mm.SweepRunner().run(bt, "nope", {})
+    with pytest.raises(ValueError):
+        mm.SweepRunner(threads=0)
+
+
+def test_backtester_runs_builtin_templates_without_callbacks():
+    bt = mm.Backtester()
+    bt.add_snapshot("BTC", 0, [(100.0, 5.0)], [(100.2, 5.0)])
+    bt.add_trade("BTC", 1_000, 100.08, 1.0)
+    bt.add_trade("BTC", 2_000, 100.11, 1.0)
+    # AS around 100.1: spread 0.001 + 20 ln(1.001), quoted 100.08 / 100.12.
+    # Long 1 after the first print, the reservation price drops by 0.001 and
+    # the ask skews down to 100.11, where the second print takes it.
+    params = {"gamma": 0.1, "kappa": 100.0, "volatility": 0.1, "tick_size": 0.01}
+    report = bt.run_template("avellaneda", params)
+    assert [(s, f.side, f.price, f.qty) for s, f in report.fills] == [
+        ("BTC", "buy", 100.08, 1.0),
+        ("BTC", "sell", 100.11, 1.0),
+    ]
+    assert report.realized_pnl == pytest.approx(0.03) and report.events == 3
+    assert report.positions["BTC"].net_qty == pytest.approx(0.0)
+    assert len(bt) == 3 and bt.pending == 0         # backtester state untouched
+
+    # Same engine as the sweep runner
+    report = bt.run_template("symmetric", {"half_spread_bps": 5.0, "tick_size": 0.01})
+    res = mm.SweepRunner().run(bt, "symmetric", {"half_spread_bps": [5.0], "tick_size": [0.01]})
+    assert res["total_pnl"] == [pytest.approx(report.total_pnl)]
+    with pytest.raises(ValueError):
+        bt.run_template("avellaneda", {"gamma": 0.0})
+
+
+def test_walk_forward_fits_on_train_and_scores_out_of_sample(tmp_path):
+    path = tmp_path / "md.zst"
+    w = mm.JournalWriter(str(path))
+    w.write_snapshot("BTC", 0, [(100.0, 5.0)], [(100.2, 5.0)])
+    for k in range(4):
+        w.write_trade("BTC", k * 1_000 + 200, 100.04, 1.0, "sell")
+        w.write_trade("BTC", k * 1_000 + 400, 100.16, 1.0, "buy")
+    w.close()
+    bt = mm.Backtester()
+    reader = mm.JournalReader(str(path))
+    assert bt.add_journal(reader, end_ts=1_000) == 3
+    assert bt.add_journal(reader) == 6                # picks up where it stopped
+    assert len(bt) == 9
+
+    wf = mm.WalkForward(train_ms=1_000, test_ms=1_000)
+    assert wf.windows(bt) == [(0, 1_000, 1_000, 2_000), (1_000, 2_000, 2_000, 3_000),
+                              (2_000, 3_000, 3_000, 3_401)]
+    grid = {"half_spread_bps": [15.0, 5.0], "tick_size": [0.01]}
+    report = wf.run(bt, "symmetric", grid)
+    assert len(report.folds) == 3
+    # 5bp round-trips 0.12 every second; 15bp never trades. Test windows
+    # start after the snapshot, which still reaches the book as warm-up.
+    for fold in report.folds:
+        assert fold.params == {"half_spread_bps": 5.0, "tick_size": 0.01}
+        assert fold.train_pnl == pytest.approx(0.12) and fold.test_pnl == pytest.approx(0.12)
+        assert fold.test_fills == 2
+    assert report.oos_pnl == pytest.approx(0.36) and report.positive_folds == 1.0
+    # Per ms: the last test window is only 401ms long
+    assert report.efficiency == pytest.approx((0.36 / 2_401) / (0.36 / 3_000))
+
+    anchored = mm.WalkForward(train_ms=2_000, test_ms=1_000, anchored=True)
+    assert [f[:2] for f in anchored.windows(bt)] == [(0, 2_000), (0, 3_000)]
+    with pytest.raises(ValueError):
+        mm.WalkForward(train_ms=1_000, test_ms=1_000, metric="sharpe")
+
+
+def test_synthetic_feed_builds_a_valid_book_deterministically(tmp_path):
+    feed = mm.SyntheticFeed(seed=7, levels=5, spread_ticks=2, trade_prob=0.3)
+    records = feed.take(2_000)
+    assert records[0].kind == "snapshot"
+    assert len(records[0].bids) == len(records[0].asks) == 5
+    assert [r.kind for r in records] == [r.kind for r in mm.SyntheticFeed(
+        seed=7, levels=5, spread_ticks=2, trade_prob=0.3).take(2_000)]
+    assert [r.ts for r in records] == sorted(r.ts for r in records)
+
+    book = mm.L2Book()
+    trades = 0
+    for r in records:
+        if r.kind == "snapshot":
+            book.apply_snapshot(r.bids, r.asks, r.update_id)
+        elif r.kind == "delta":
+            book.apply_delta(r.bids, r.asks, r.update_id)
+            assert not book.is_crossed()
+        else:
+            trades += 1
+            assert r.side in ("buy", "sell") and r.qty > 0
+    assert 0.2 < trades / 2_000 < 0.4
+    if records[-1].kind == "trade":
+        r = next(feed)                                # its delta is already generated
+        book.apply_delta(r.bids, r.asks, r.update_id)
+    # The replayed book matches the generator's own view
+    snap = feed.snapshot()
+    assert book.best_bid == snap.bids[0] and book.best_ask == snap.asks[0]
+    for price, _ in snap.bids + snap.asks:
+        assert round(price / 0.01) == pytest.approx(price / 0.01)
+
+    feed.reset()
+    assert feed.snapshot().update_id == 0 and feed.snapshot().bids == records[0].bids
+    assert [r.update_id for r in feed.take(50)] == [r.update_id for r in records[:50]]
+
+    # Long-run event rate holds with bursts switched on
+    bursty = mm.SyntheticFeed(event_rate=100.0, burstiness=0.8, trade_prob=0.0, seed=1)
+    n = len(bursty.until(60_000)) - 1                 # one delta per event
+    assert 4_500 < n < 7_500
+
+    bt = mm.Backtester()
+    assert mm.SyntheticFeed(seed=3).load_into(bt, 1_000) == len(bt) > 1
+    w = mm.JournalWriter(str(tmp_path / "syn.zst"))
+    written = mm.SyntheticFeed(seed=3).write_to(w, 1_000)
+    w.close()
+    assert written == len(bt)
+    assert sum(1 for _ in mm.JournalReader(str(tmp_path / "syn.zst"))) == written
+
+
+def test_synthetic_feed_regimes_switch_volatility():
+    feed = mm.SyntheticFeed(regimes=[(1.0, 500.0), (50.0, 500.0)], seed=2)
+    seen = set()
+    for _ in range(2_000):
+        next(feed)
+        seen.add(feed.volatility_bps)
+    assert seen == {1.0, 50.0}
+    with pytest.raises(ValueError):
+        mm.SyntheticFeed(burstiness=1.0)
+    with pytest.raises(ValueError):
+        mm.SyntheticFeed(regimes=[])
@@ -0,0 +1,160 @@
+"""
+Unit tests for mm_orderbook.SnapshotFetcher against a local HTTP server.
+"""
+
+import json
+import os
+import ssl
+import threading
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import pytest
+
+mm = pytest.importorskip("mm_orderbook")
+
+# Self-signed for localhost and 127.0.0.1
+TLS_CERT = os.path.join(os.path.dirname(__file__), "fixtures", "tls", "localhost.pem")
+TLS_KEY = os.path.join(os.path.dirname(__file__), "fixtures", "tls", "localhost.key")
+
+
+class RestServer:
+    """Answers every GET with the next (status, headers, body) in `responses`."""
+
+    def __init__(self, responses, chunked=False, tls=False):
+        self.responses = list(responses)
+        self.paths = []
+        outer = self
+
+        class Handler(BaseHTTPRequestHandler):
+            protocol_version = "HTTP/1.1"
+
+            def do_GET(self):
+                outer.paths.append(self.path)
+                status, headers, body = outer.responses.pop(0)
+                data = json.dumps(body).encode()
+                self.send_response(status)
+                for k, v in headers.items():
+                    self.send_header(k, v)
+                if chunked:
+                    self.send_header("Transfer-Encoding", "chunked")
+                    self.end_headers()
+                    for i in range(0, len(data), 7):
+                        part = data[i:i + 7]
+                        self.wfile.write(b"%x\r\n%s\r\n" % (len(part), part))
+                    self.wfile.write(b"0\r\n\r\n")
+                else:
+                    self.send_header("Content-Length", str(len(data)))
+                    self.end_headers()
+                    self.wfile.write(data)
+
+            def log_message(self, *args):
+                pass
+
+        self.httpd = HTTPServer(("127.0.0.1", 0), Handler)
+        self.url = "http://127.0.0.1:%d" % self.httpd.server_address[1]
+        if tls:
+            context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+            context.load_cert_chain(TLS_CERT, TLS_KEY)
+            self.httpd.socket = context.wrap_socket(self.httpd.socket, server_side=True)
+            self.url = "https://localhost:%d" % self.httpd.server_address[1]
+        threading.Thread(target=self.httpd.serve_forever, daemon=True).start()
+
+    def close(self):
+        self.httpd.shutdown()
+        self.httpd.server_close()
+
+
+def test_bybit_snapshot_into_book_and_shared_book():
+    body = {"retCode": 0, "retMsg": "OK", "result": {
+        "s": "BTCUSDT", "b": [["100.0", "1.5"], ["99.5", "2"]], "a": [["100.5", "3"]],
+        "ts": 1, "u": 42, "seq": 7}}
+    limits = {"X-Bapi-Limit": "600", "X-Bapi-Limit-Status": "590"}
+    server = RestServer([(200, limits, body), (200, {}, body),
+                         (200, {}, {"retCode": 10001, "retMsg": "params error"})])
+    limiter = mm.RateLimiter([(600.0, 5.0)])
+    fetcher = mm.SnapshotFetcher(server.url + "/", limiter=limiter, weight_bucket=0)
+    assert fetcher.base_url == server.url and fetcher.weight(200) == 1.0
+
+    book = mm.L2Book()
+    assert fetcher.apply(book, "BTCUSDT", limit=200)
+    assert book.depth(5) == ([(100.0, 1.5), (99.5, 2.0)], [(100.5, 3.0)])
+    assert book.last_update_id == 42
+    # Exchange-reported usage overwrote the bucket: 600 - 590 = 10 used
+    assert limiter.available()[0] == pytest.approx(590.0, abs=0.5)
+
+    shared = mm.SharedL2Book()
+    assert fetcher.apply(shared, "BTCUSDT")
+    assert shared.best_ask == (100.5, 3.0) and shared.last_update_id == 42
+
+    with pytest.raises(OSError, match="params error"):
+        fetcher.fetch("BTCUSDT")
+    server.close()
+    assert server.paths[0] == "/v5/market/orderbook?category=linear&symbol=BTCUSDT&limit=200"
+    assert fetcher.requests == 3
+
+
+def test_binance_snapshot_resyncs_binance_book_sync():
+    body = {"lastUpdateId": 100, "bids": [["10.0", "1.0"]], "asks": [["11.0", "2.0"]]}
+    server = RestServer([(200, {"X-MBX-USED-WEIGHT-1M": "30"}, body)], chunked=True)
+    limiter = mm.RateLimiter([(10.0, 3600.0), (1200.0, 3600.0)])
+    fetcher = mm.SnapshotFetcher(server.url, exchange="binance", limiter=limiter, weight_bucket=1)
+    assert [fetcher.weight(n) for n in (100, 500, 1000, 5000)] == [5.0, 25.0, 50.0, 250.0]
+
+    sync = mm.BinanceBookSync()
+    sync.buffer_diff(99, 101, [(10.0, 3.0)], [])
+    sync.buffer_diff(102, 102, [], [(11.0, 0.0), (11.5, 1.0)])
+    assert fetcher.apply(sync, "BTCUSDT", limit=100)
+    server.close()
+    assert sync.is_synced and sync.book.last_update_id == 102
+    assert sync.book.best_bid == (10.0, 3.0) and sync.book.best_ask == (11.5, 1.0)
+    assert server.paths == ["/api/v3/depth?symbol=BTCUSDT&limit=100"]
+    # The weight bucket follows the exchange; the request bucket paid 5
+    available = limiter.available()
+    assert available[0] == pytest.approx(5.0, abs=0.5)
+    assert available[1] == pytest.approx(1170.0, abs=0.5)
+
+
+def test_rate_limit_budget_and_http_429():
+    body = {"lastUpdateId": 1, "bids": [], "asks": []}
+    server = RestServer([(200, {}, body), (429, {}, {"code": -1003, "msg": "Too many requests"})])
+    limiter = mm.RateLimiter([(10.0, 60.0)])
+    fetcher = mm.SnapshotFetcher(server.url, exchange="binance_futures", limiter=limiter,
+                                 weight_bucket=0, max_wait_ms=0)
+    assert fetcher.fetch("BTCUSDT", limit=1000) == ([], [], 1)   # weight 10
+    with pytest.raises(TimeoutError):
+        fetcher.fetch("BTCUSDT", limit=5)   # no budget left, max_wait_ms=0
+    assert fetcher.throttled == 1 and fetcher.requests == 1
+
+    limiter.reset()
+    with pytest.raises(OSError, match="HTTP 429"):
+        fetcher.fetch("BTCUSDT", limit=5)
+    assert limiter.available()[0] < 1.0   # a 429 empties the weight bucket
+    server.close()
+
+
+@pytest.mark.skipif(not mm.HAS_TLS, reason="extension built without TLS")
+def test_snapshot_over_tls(monkeypatch):
+    body = {"retCode": 0, "retMsg": "OK", "result": {
+        "s": "BTCUSDT", "b": [["100.0", "1.5"]], "a": [["100.5", "3"]], "ts": 1, "u": 42, "seq": 7}}
+    server = RestServer([(200, {}, body)] * 2, chunked=True, tls=True)
+    fetcher = mm.SnapshotFetcher(server.url)
+    monkeypatch.delenv("SSL_CERT_FILE", raising=False)
+    with pytest.raises(OSError, match="certificate verify failed"):
+        fetcher.fetch("BTCUSDT")
+    monkeypatch.setenv("SSL_CERT_FILE", TLS_CERT)
+    assert fetcher.fetch("BTCUSDT", limit=1) == ([(100.0, 1.5)], [(100.5, 3.0)], 42)
+    server.close()
+
+
+def test_validation():
+    with pytest.raises(ValueError):
+        mm.SnapshotFetcher("ftp://api.bybit.com")
+    with pytest.raises(ValueError):
+        mm.SnapshotFetcher("http://127.0.0.1:1", exchange="kraken")
+    with pytest.raises(ValueError):
+        mm.SnapshotFetcher("http://127.0.0.1:1", weight_bucket=0)
+    fetcher = mm.SnapshotFetcher("http://127.0.0.1:1")
+    with pytest.raises(ValueError):
+        fetcher.fetch("BTC&limit=1")
+    with pytest.raises(TypeError):
+        fetcher.apply(object(), "BTCUSDT")